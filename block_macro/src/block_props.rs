@@ -223,8 +223,12 @@ fn create_block_input_fields_init(
             format_ident!("{}", props.get("kind").cloned().unwrap_or("Null".into()))
         });
 
+        let lists = block_input_props
+            .iter()
+            .map(|(_, props)| accepts_lists(props));
+
         quote! {
-            #(#input_field: #krate::blocks::InputImpl::new(#input_name, #krate::HaystackKind::#kind, uuid.clone())),*
+            #(#input_field: #krate::blocks::InputImpl::new(#input_name, #krate::HaystackKind::#kind, uuid.clone()).accepting_lists(#lists)),*
         }
     }
 }
@@ -288,12 +292,19 @@ fn create_block_defined_input_init(
 
         let names = (0..count).map(|i| format!("{name}{i}"));
 
+        let lists = accepts_lists(block_defined_input_props);
+
         quote! {
-            _inputs: vec![ #(#krate::blocks::InputImpl::new(#names, #krate::HaystackKind::#kind, uuid.clone())),* ],
+            _inputs: vec![ #(#krate::blocks::InputImpl::new(#names, #krate::HaystackKind::#kind, uuid.clone()).accepting_lists(#lists)),* ],
         }
     }
 }
 
+/// Whether an input attribute sets `lists = true`.
+fn accepts_lists(props: &BTreeMap<String, String>) -> bool {
+    props.get("lists").is_some_and(|lists| lists == "true")
+}
+
 /// Creates the output field initializer.
 fn create_block_outputs_field_init(
     block_output_props: &[(String, BTreeMap<String, String>)],
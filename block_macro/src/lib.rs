@@ -32,6 +32,10 @@ use proc_macro::TokenStream;
 /// Input and output fields are declared with the `InputImpl` and `OutputImpl`
 /// types, either imported (`use logic_mesh::blocks::{InputImpl, OutputImpl};`)
 /// or as fully qualified paths (`logic_mesh::blocks::InputImpl`).
+///
+/// An `#[input(kind = "Number", lists = true)]` input also takes lists of
+/// its kind, for blocks that compute element-wise; other inputs fault on
+/// a list.
 #[allow(clippy::let_and_return)]
 #[proc_macro_attribute]
 pub fn block(_args: TokenStream, input: TokenStream) -> TokenStream {
@@ -106,6 +106,7 @@ pub(super) fn get_crate_path(ast: &syn::DeriveInput) -> syn::Path {
 ///
 /// - `kind`: a string property for the Haystack Kind for the input.
 /// - `count`: the number of inputs to be created.
+/// - `lists`: `true` if the inputs also take lists of their kind.
 pub(super) fn get_block_input_attribute(ast: &syn::DeriveInput) -> BTreeMap<String, String> {
     let mut attrs: BTreeMap<String, String> = BTreeMap::new();
 
@@ -136,6 +137,9 @@ fn get_attribute_props(input_attr: &syn::Attribute, attrs: &mut BTreeMap<String,
             Lit::Int(lit) => {
                 attrs.insert(id.to_string(), lit.to_string());
             }
+            Lit::Bool(lit) => {
+                attrs.insert(id.to_string(), lit.value.to_string());
+            }
             _ => {}
         }
         Ok(())
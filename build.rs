@@ -7,6 +7,9 @@ use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+/// Block categories built only with a feature, on native targets.
+const GATED_CATEGORIES: &[(&str, &str)] = &[("modbus", "modbus"), ("mqtt", "mqtt")];
+
 fn main() {
     let blocks_dir = Path::new("src/blocks");
     let out_dir = std::env::var("OUT_DIR").unwrap();
@@ -33,6 +36,14 @@ fn main() {
             continue;
         }
 
+        // Skip categories whose feature is off, or that need a native target
+        if let Some((_, feature)) = GATED_CATEGORIES.iter().find(|(dir, _)| *dir == category)
+            && (std::env::var_os(format!("CARGO_FEATURE_{}", feature.to_uppercase())).is_none()
+                || std::env::var("CARGO_CFG_TARGET_ARCH").as_deref() == Ok("wasm32"))
+        {
+            continue;
+        }
+
         let mut category_blocks = Vec::new();
 
         let files = fs::read_dir(&path).expect("Failed to read block category dir");
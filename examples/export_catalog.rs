@@ -0,0 +1,51 @@
+//! Regenerates the block catalog committed to the repository.
+//!
+//! Writes the JSON Schema of the core blocks to
+//! `schema/blocks.schema.json` and their TypeScript declarations, along
+//! with the wasm and program file types, to
+//! `web/packages/logic-mesh/src/catalog.ts`.
+//!
+//! Run with:
+//!
+//! ```bash
+//! cargo run --example export_catalog
+//! ```
+//!
+//! Run it without the optional block features (`modbus`, `mqtt`), so only
+//! the blocks every build ships end up in the catalog.
+
+use std::path::Path;
+
+use logic_mesh::blocks::catalog::{json_schema, typescript};
+use logic_mesh::blocks::registry::{CORE_LIB, list_registered_blocks};
+use serde::Serialize;
+use serde_json::ser::{PrettyFormatter, Serializer};
+
+fn main() -> std::io::Result<()> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let blocks: Vec<_> = list_registered_blocks()
+        .into_iter()
+        .filter(|desc| desc.library == CORE_LIB)
+        .collect();
+
+    // Tab indented, like the other schemas under `schema/`.
+    let mut schema = Vec::new();
+    json_schema(&blocks).serialize(&mut Serializer::with_formatter(
+        &mut schema,
+        PrettyFormatter::with_indent(b"\t"),
+    ))?;
+    schema.push(b'\n');
+
+    let schema_path = root.join("schema/blocks.schema.json");
+    std::fs::write(&schema_path, schema)?;
+    let ts_path = root.join("web/packages/logic-mesh/src/catalog.ts");
+    std::fs::write(&ts_path, typescript(&blocks))?;
+
+    println!(
+        "Wrote {} blocks to {} and {}",
+        blocks.len(),
+        schema_path.display(),
+        ts_path.display()
+    );
+    Ok(())
+}
@@ -351,6 +351,7 @@ fn sample_program() -> Program {
         description: Some("Two sine sources feeding an adder.".to_string()),
         blocks,
         links,
+        ..Default::default()
     }
 }
 
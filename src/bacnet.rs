@@ -0,0 +1,492 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! BACnet/IP device presenting block pins as BACnet objects.
+//!
+//! Enabled by the `bacnet` feature. [`BacnetServer`] answers on a UDP
+//! socket as one BACnet device whose objects are selected pins of a
+//! running engine:
+//!
+//! - Analog Value objects for Number pins, with engineering units taken
+//!   from the pin's unit;
+//! - Binary Value objects for Bool pins, or Number pins read as
+//!   non-zero;
+//! - Multi-state Value objects for Number pins holding a state from 1.
+//!
+//! Commandable objects keep a 16 level priority array, the same levels
+//! the `PriorityArray` block models. A write at a priority sets that
+//! level, writing NULL releases it, and the pin takes the value of the
+//! highest level set, or the relinquish default when none is.
+//!
+//! Change of value subscriptions are fed by the engine's watch channel:
+//! subscribers are notified as soon as a mapped pin or the block's fault
+//! state changes.
+//!
+//! The device executes Who-Is, ReadProperty, ReadPropertyMultiple,
+//! WriteProperty and SubscribeCOV. It does not segment: a response that
+//! does not fit the client's maximum APDU is aborted.
+
+mod apdu;
+mod encoding;
+mod object;
+mod property;
+mod server;
+mod services;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+pub use object::{DeviceConfig, ObjectMapping};
+pub use server::BacnetServer;
+
+/// The standard UDP port of BACnet/IP, `0xBAC0`.
+pub const DEFAULT_PORT: u16 = 47808;
+
+/// Object types the device has.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ObjectType {
+    /// Analog Value, a Number pin.
+    AnalogValue,
+    /// Binary Value, a Bool pin.
+    BinaryValue,
+    /// The device itself.
+    Device,
+    /// Multi-state Value, a Number pin holding a state from 1.
+    MultiStateValue,
+}
+
+impl ObjectType {
+    /// The type's BACnet enumeration value.
+    pub fn code(self) -> u16 {
+        match self {
+            ObjectType::AnalogValue => 2,
+            ObjectType::BinaryValue => 5,
+            ObjectType::Device => 8,
+            ObjectType::MultiStateValue => 19,
+        }
+    }
+
+    /// The type with BACnet enumeration value `code`, if the device has
+    /// such objects.
+    pub fn from_code(code: u16) -> Option<Self> {
+        match code {
+            2 => Some(ObjectType::AnalogValue),
+            5 => Some(ObjectType::BinaryValue),
+            8 => Some(ObjectType::Device),
+            19 => Some(ObjectType::MultiStateValue),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ObjectType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ObjectType::AnalogValue => "analog-value",
+            ObjectType::BinaryValue => "binary-value",
+            ObjectType::Device => "device",
+            ObjectType::MultiStateValue => "multi-state-value",
+        })
+    }
+}
+
+/// Errors reported by the BACnet server.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum BacnetError {
+    /// Reading or writing the socket failed.
+    #[error("BACnet I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    /// The device settings are not valid.
+    #[error("Invalid BACnet device: {0}")]
+    InvalidDevice(String),
+    /// An [`ObjectMapping`] is not valid.
+    #[error("Invalid BACnet object mapping: {0}")]
+    InvalidMapping(String),
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::VecDeque;
+    use std::net::SocketAddr;
+    use std::time::Duration;
+
+    use libhaystack::units::get_unit;
+    use libhaystack::val::Value;
+    use tokio::net::UdpSocket;
+    use uuid::Uuid;
+
+    use super::apdu::{
+        Apdu, Datagram, ErrorCode, MAX_APDU, confirmed, encode_datagram, unconfirmed,
+    };
+    use super::encoding::{AppValue, ObjectId};
+    use super::property as prop;
+    use super::services::{
+        CovNotification, IAm, PropertyRef, ReadAccessSpec, ReadProperty, ReadPropertyAck,
+        ReadPropertyMultiple, ReadPropertyMultipleAck, ReadResult, SubscribeCov, WhoIs,
+        WriteProperty,
+    };
+    use super::{BacnetServer, ObjectMapping, ObjectType};
+    use crate::base::block::BlockProps;
+    use crate::base::engine::{Engine, messages::EngineMessage};
+    use crate::blocks::logic::Not;
+    use crate::blocks::math::Abs;
+    use crate::single_threaded::SingleThreadedEngine;
+
+    const AV: u16 = 2;
+    const BV: u16 = 5;
+    const DEVICE: u16 = 8;
+    const MSV: u16 = 19;
+
+    /// A BACnet client over loopback UDP.
+    struct Client {
+        socket: UdpSocket,
+        server: SocketAddr,
+        invoke_id: u8,
+        notifications: VecDeque<CovNotification>,
+    }
+
+    impl Client {
+        async fn send(&self, apdu: Apdu) {
+            let expecting_reply = matches!(apdu, Apdu::ConfirmedRequest { .. });
+            let datagram = encode_datagram(&apdu.encode(), false, None, expecting_reply);
+            self.socket.send_to(&datagram, self.server).await.unwrap();
+        }
+
+        async fn receive(&mut self) -> Apdu {
+            let mut buf = [0u8; 2048];
+            let (len, peer) =
+                tokio::time::timeout(Duration::from_secs(2), self.socket.recv_from(&mut buf))
+                    .await
+                    .expect("no answer from the device")
+                    .unwrap();
+            let datagram = Datagram::decode(peer, &buf[..len]).unwrap();
+            Apdu::decode(&datagram.apdu).unwrap()
+        }
+
+        /// Sends a confirmed request and waits for its answer, keeping
+        /// the notifications that arrive meanwhile.
+        async fn request(&mut self, service: u8, data: Vec<u8>) -> Apdu {
+            self.invoke_id += 1;
+            self.send(Apdu::ConfirmedRequest {
+                segmented: false,
+                max_apdu: MAX_APDU,
+                invoke_id: self.invoke_id,
+                service,
+                data,
+            })
+            .await;
+            loop {
+                let apdu = self.receive().await;
+                let invoke_id = match &apdu {
+                    Apdu::UnconfirmedRequest {
+                        service: unconfirmed::UNCONFIRMED_COV_NOTIFICATION,
+                        data,
+                    } => {
+                        let notification = CovNotification::decode(data).unwrap();
+                        self.notifications.push_back(notification);
+                        continue;
+                    }
+                    Apdu::SimpleAck { invoke_id, .. }
+                    | Apdu::ComplexAck { invoke_id, .. }
+                    | Apdu::Error { invoke_id, .. }
+                    | Apdu::Reject { invoke_id, .. }
+                    | Apdu::Abort { invoke_id, .. } => *invoke_id,
+                    other => panic!("unexpected {other:?}"),
+                };
+                assert_eq!(invoke_id, self.invoke_id);
+                return apdu;
+            }
+        }
+
+        async fn read(
+            &mut self,
+            object: ObjectId,
+            property: u32,
+            index: Option<u32>,
+        ) -> ReadResult {
+            let request = ReadProperty {
+                object,
+                property: PropertyRef { property, index },
+            };
+            match self
+                .request(confirmed::READ_PROPERTY, request.encode())
+                .await
+            {
+                Apdu::ComplexAck { data, .. } => Ok(ReadPropertyAck::decode(&data).unwrap().values),
+                Apdu::Error { error, .. } => Err(error),
+                other => panic!("unexpected {other:?}"),
+            }
+        }
+
+        async fn write(
+            &mut self,
+            object: ObjectId,
+            value: AppValue,
+            priority: Option<u32>,
+        ) -> Result<(), ErrorCode> {
+            let request = WriteProperty {
+                object,
+                property: PropertyRef::new(prop::PRESENT_VALUE),
+                values: vec![value],
+                priority,
+            };
+            match self
+                .request(confirmed::WRITE_PROPERTY, request.encode())
+                .await
+            {
+                Apdu::SimpleAck { .. } => Ok(()),
+                Apdu::Error { error, .. } => Err(error),
+                other => panic!("unexpected {other:?}"),
+            }
+        }
+
+        async fn notification(&mut self) -> CovNotification {
+            if let Some(notification) = self.notifications.pop_front() {
+                return notification;
+            }
+            match self.receive().await {
+                Apdu::UnconfirmedRequest {
+                    service: unconfirmed::UNCONFIRMED_COV_NOTIFICATION,
+                    data,
+                } => CovNotification::decode(&data).unwrap(),
+                other => panic!("unexpected {other:?}"),
+            }
+        }
+    }
+
+    fn mapping(object_type: ObjectType, block: Uuid, name: &str) -> ObjectMapping {
+        serde_json::from_value(serde_json::json!({
+            "objectType": object_type,
+            "instance": 1,
+            "name": name,
+            "block": block.to_string(),
+            "pin": "in",
+        }))
+        .unwrap()
+    }
+
+    fn present_value(notification: &CovNotification) -> &AppValue {
+        let (_, values) = notification
+            .values
+            .iter()
+            .find(|(property, _)| *property == prop::PRESENT_VALUE)
+            .expect("the notification has a present value");
+        &values[0]
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn clients_read_command_and_subscribe_to_pins() {
+        let mut engine = SingleThreadedEngine::new();
+
+        let mut setpoint = Abs::new();
+        setpoint.input.val = Some(Value::make_number_unit(21.5, get_unit("°C").unwrap()));
+        let setpoint_id = *setpoint.id();
+        let mut occupied = Not::new();
+        occupied.input.val = Some(false.into());
+        let occupied_id = *occupied.id();
+        let mut mode = Abs::new();
+        mode.input.val = Some(Value::from(2));
+        let mode_id = *mode.id();
+        engine.schedule(setpoint).unwrap();
+        engine.schedule(occupied).unwrap();
+        engine.schedule(mode).unwrap();
+
+        let mut av = mapping(ObjectType::AnalogValue, setpoint_id, "ZN-T-SP");
+        av.commandable = true;
+        av.cov_increment = 0.5;
+        let mut bv = mapping(ObjectType::BinaryValue, occupied_id, "OCC");
+        bv.commandable = true;
+        let mut msv = mapping(ObjectType::MultiStateValue, mode_id, "MODE");
+        msv.states = vec!["Off".into(), "Heat".into(), "Cool".into()];
+
+        let device = serde_json::from_value(serde_json::json!({
+            "instance": 2001,
+            "name": "AHU-1",
+        }))
+        .unwrap();
+        let server = BacnetServer::new(&mut engine, device, vec![av, bv, msv]).unwrap();
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let address = socket.local_addr().unwrap();
+        tokio::spawn(server.serve(socket));
+
+        let (reply_sender, _replies) = tokio::sync::mpsc::channel(8);
+        let sender = engine.create_message_channel(Uuid::new_v4(), reply_sender);
+
+        let av = ObjectId::new(AV, 1);
+        let bv = ObjectId::new(BV, 1);
+        let msv = ObjectId::new(MSV, 1);
+        let device = ObjectId::new(DEVICE, 2001);
+
+        let client = async {
+            let mut client = Client {
+                socket: UdpSocket::bind("127.0.0.1:0").await.unwrap(),
+                server: address,
+                invoke_id: 0,
+                notifications: VecDeque::new(),
+            };
+
+            client
+                .send(Apdu::UnconfirmedRequest {
+                    service: unconfirmed::WHO_IS,
+                    data: WhoIs {
+                        range: Some((2000, 2010)),
+                    }
+                    .encode(),
+                })
+                .await;
+            match client.receive().await {
+                Apdu::UnconfirmedRequest {
+                    service: unconfirmed::I_AM,
+                    data,
+                } => assert_eq!(IAm::decode(&data).unwrap().device, device),
+                other => panic!("unexpected {other:?}"),
+            }
+
+            assert_eq!(
+                client.read(av, prop::PRESENT_VALUE, None).await,
+                Ok(vec![AppValue::Real(21.5)])
+            );
+            assert_eq!(
+                client.read(av, prop::UNITS, None).await,
+                Ok(vec![AppValue::Enumerated(62)])
+            );
+            assert_eq!(
+                client.read(device, prop::OBJECT_LIST, Some(0)).await,
+                Ok(vec![AppValue::Unsigned(4)])
+            );
+            assert_eq!(
+                client
+                    .read(ObjectId::new(AV, 99), prop::PRESENT_VALUE, None)
+                    .await,
+                Err(ErrorCode::UNKNOWN_OBJECT)
+            );
+
+            // Subscribing notifies the current value at once.
+            let subscribe = SubscribeCov {
+                process: 7,
+                object: av,
+                confirmed: Some(false),
+                lifetime: Some(300),
+            };
+            assert!(matches!(
+                client
+                    .request(confirmed::SUBSCRIBE_COV, subscribe.encode())
+                    .await,
+                Apdu::SimpleAck { .. }
+            ));
+            let notification = client.notification().await;
+            assert_eq!(notification.process, 7);
+            assert_eq!(notification.object, av);
+            assert_eq!(present_value(&notification), &AppValue::Real(21.5));
+
+            // A command at priority 8 drives the pin and is notified.
+            client
+                .write(av, AppValue::Real(18.0), Some(8))
+                .await
+                .unwrap();
+            assert_eq!(
+                present_value(&client.notification().await),
+                &AppValue::Real(18.0)
+            );
+            assert_eq!(
+                client.read(av, prop::PRIORITY_ARRAY, Some(8)).await,
+                Ok(vec![AppValue::Real(18.0)])
+            );
+            assert_eq!(
+                client.read(av, prop::PRIORITY_ARRAY, Some(0)).await,
+                Ok(vec![AppValue::Unsigned(16)])
+            );
+            assert_eq!(
+                client.read(av, prop::RELINQUISH_DEFAULT, None).await,
+                Ok(vec![AppValue::Real(21.5)])
+            );
+
+            // A lower priority waits for the higher one to be released,
+            // then moves the pin by less than the COV increment.
+            client
+                .write(av, AppValue::Real(18.25), Some(16))
+                .await
+                .unwrap();
+            assert_eq!(
+                client.read(av, prop::PRESENT_VALUE, None).await,
+                Ok(vec![AppValue::Real(18.0)])
+            );
+            client.write(av, AppValue::Null, Some(8)).await.unwrap();
+            assert_eq!(
+                client.read(av, prop::PRESENT_VALUE, None).await,
+                Ok(vec![AppValue::Real(18.25)])
+            );
+            client.write(av, AppValue::Null, Some(16)).await.unwrap();
+            assert_eq!(
+                present_value(&client.notification().await),
+                &AppValue::Real(21.5)
+            );
+
+            client
+                .write(bv, AppValue::Enumerated(1), None)
+                .await
+                .unwrap();
+            assert_eq!(
+                client.write(msv, AppValue::Unsigned(3), None).await,
+                Err(ErrorCode::WRITE_ACCESS_DENIED)
+            );
+
+            let request = ReadPropertyMultiple {
+                specs: vec![
+                    ReadAccessSpec {
+                        object: bv,
+                        properties: vec![
+                            PropertyRef::new(prop::PRESENT_VALUE),
+                            PropertyRef::new(prop::OBJECT_NAME),
+                        ],
+                    },
+                    ReadAccessSpec {
+                        object: msv,
+                        properties: vec![
+                            PropertyRef {
+                                property: prop::STATE_TEXT,
+                                index: Some(2),
+                            },
+                            PropertyRef::new(prop::UNITS),
+                        ],
+                    },
+                    ReadAccessSpec {
+                        object: msv,
+                        properties: vec![PropertyRef::new(prop::ALL)],
+                    },
+                ],
+            };
+            let results = match client
+                .request(confirmed::READ_PROPERTY_MULTIPLE, request.encode())
+                .await
+            {
+                Apdu::ComplexAck { data, .. } => {
+                    ReadPropertyMultipleAck::decode(&data).unwrap().results
+                }
+                other => panic!("unexpected {other:?}"),
+            };
+            assert_eq!(results.len(), 3);
+            assert_eq!(results[0].0, bv);
+            assert_eq!(results[0].1[0].1, Ok(vec![AppValue::Enumerated(1)]));
+            assert_eq!(
+                results[0].1[1].1,
+                Ok(vec![AppValue::CharacterString("OCC".into())])
+            );
+            assert_eq!(
+                results[1].1[0].1,
+                Ok(vec![AppValue::CharacterString("Heat".into())])
+            );
+            assert_eq!(results[1].1[1].1, Err(ErrorCode::UNKNOWN_PROPERTY));
+            let all = &results[2].1;
+            assert!(all.iter().any(|(property, value)| {
+                property.property == prop::PRESENT_VALUE
+                    && *value == Ok(vec![AppValue::Unsigned(2)])
+            }));
+
+            sender.send(EngineMessage::Shutdown).await.unwrap();
+        };
+
+        tokio::join!(engine.run(), client);
+    }
+}
@@ -0,0 +1,379 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! BACnet/IP datagrams: the BVLC header, the network layer header and
+//! the application PDU.
+
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+
+use super::encoding::Malformed;
+
+const BVLC_TYPE: u8 = 0x81;
+const BVLC_FORWARDED_NPDU: u8 = 0x04;
+const BVLC_ORIGINAL_UNICAST: u8 = 0x0A;
+const BVLC_ORIGINAL_BROADCAST: u8 = 0x0B;
+
+const NPDU_VERSION: u8 = 0x01;
+const NPDU_NETWORK_MESSAGE: u8 = 0x80;
+const NPDU_DESTINATION: u8 = 0x20;
+const NPDU_SOURCE: u8 = 0x08;
+const NPDU_EXPECTING_REPLY: u8 = 0x04;
+
+/// The global broadcast network number.
+const GLOBAL_NETWORK: u16 = 0xFFFF;
+
+/// Confirmed services.
+pub(super) mod confirmed {
+    pub(in super::super) const CONFIRMED_COV_NOTIFICATION: u8 = 1;
+    pub(in super::super) const SUBSCRIBE_COV: u8 = 5;
+    pub(in super::super) const READ_PROPERTY: u8 = 12;
+    pub(in super::super) const READ_PROPERTY_MULTIPLE: u8 = 14;
+    pub(in super::super) const WRITE_PROPERTY: u8 = 15;
+}
+
+/// Unconfirmed services.
+pub(super) mod unconfirmed {
+    pub(in super::super) const I_AM: u8 = 0;
+    pub(in super::super) const UNCONFIRMED_COV_NOTIFICATION: u8 = 2;
+    pub(in super::super) const WHO_IS: u8 = 8;
+}
+
+/// Reject reasons.
+pub(super) mod reject {
+    pub(in super::super) const INVALID_TAG: u8 = 4;
+    pub(in super::super) const UNRECOGNIZED_SERVICE: u8 = 9;
+}
+
+/// Abort reasons.
+pub(super) mod abort {
+    pub(in super::super) const SEGMENTATION_NOT_SUPPORTED: u8 = 4;
+}
+
+/// A device behind a router: its network number and MAC address.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(super) struct Route {
+    pub(super) network: u16,
+    pub(super) mac: Vec<u8>,
+}
+
+/// A received datagram carrying an APDU.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Datagram {
+    /// Where replies go: the sender, or the original sender of a
+    /// datagram forwarded by a broadcast management device.
+    pub(super) reply_to: SocketAddr,
+    /// The remote device that sent it, when it came through a router.
+    pub(super) source: Option<Route>,
+    pub(super) apdu: Vec<u8>,
+}
+
+impl Datagram {
+    /// Decodes a datagram from `peer`. [`None`] for what carries no
+    /// APDU for this device: other BVLC functions, network layer
+    /// messages and traffic routed to another network.
+    pub(super) fn decode(peer: SocketAddr, data: &[u8]) -> Option<Self> {
+        if data.len() < 4 || data[0] != BVLC_TYPE {
+            return None;
+        }
+        if usize::from(u16::from_be_bytes([data[2], data[3]])) != data.len() {
+            return None;
+        }
+        let (reply_to, npdu) = match data[1] {
+            BVLC_ORIGINAL_UNICAST | BVLC_ORIGINAL_BROADCAST => (peer, &data[4..]),
+            BVLC_FORWARDED_NPDU => {
+                let origin = data.get(4..10)?;
+                let ip = Ipv4Addr::new(origin[0], origin[1], origin[2], origin[3]);
+                let port = u16::from_be_bytes([origin[4], origin[5]]);
+                (SocketAddrV4::new(ip, port).into(), &data[10..])
+            }
+            _ => return None,
+        };
+
+        let (&version, rest) = npdu.split_first()?;
+        let (&control, mut rest) = rest.split_first()?;
+        if version != NPDU_VERSION || control & NPDU_NETWORK_MESSAGE != 0 {
+            return None;
+        }
+        let mut destination = None;
+        if control & NPDU_DESTINATION != 0 {
+            let (route, after) = read_route(rest)?;
+            destination = Some(route);
+            rest = after;
+        }
+        let mut source = None;
+        if control & NPDU_SOURCE != 0 {
+            let (route, after) = read_route(rest)?;
+            source = Some(route);
+            rest = after;
+        }
+        if let Some(destination) = destination {
+            // Hop count.
+            rest = rest.get(1..)?;
+            if destination.network != GLOBAL_NETWORK {
+                return None;
+            }
+        }
+
+        Some(Self {
+            reply_to,
+            source,
+            apdu: rest.to_vec(),
+        })
+    }
+}
+
+fn read_route(data: &[u8]) -> Option<(Route, &[u8])> {
+    let network = u16::from_be_bytes([*data.first()?, *data.get(1)?]);
+    let len = usize::from(*data.get(2)?);
+    let mac = data.get(3..3 + len)?.to_vec();
+    Some((Route { network, mac }, &data[3 + len..]))
+}
+
+/// Wraps an APDU in the network and BVLC headers, addressed to
+/// `destination` when it sits behind a router.
+pub(super) fn encode_datagram(
+    apdu: &[u8],
+    broadcast: bool,
+    destination: Option<&Route>,
+    expecting_reply: bool,
+) -> Vec<u8> {
+    let mut npdu = vec![NPDU_VERSION, 0];
+    if expecting_reply {
+        npdu[1] |= NPDU_EXPECTING_REPLY;
+    }
+    if let Some(route) = destination {
+        npdu[1] |= NPDU_DESTINATION;
+        npdu.extend(route.network.to_be_bytes());
+        npdu.push(route.mac.len() as u8);
+        npdu.extend(&route.mac);
+        // Hop count.
+        npdu.push(255);
+    }
+    npdu.extend(apdu);
+
+    let function = if broadcast {
+        BVLC_ORIGINAL_BROADCAST
+    } else {
+        BVLC_ORIGINAL_UNICAST
+    };
+    let mut datagram = vec![BVLC_TYPE, function];
+    datagram.extend(((npdu.len() + 4) as u16).to_be_bytes());
+    datagram.extend(npdu);
+    datagram
+}
+
+/// An application PDU.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Apdu {
+    ConfirmedRequest {
+        /// Whether the request is a segment of a larger one.
+        segmented: bool,
+        /// The largest response the requester accepts, in octets.
+        max_apdu: usize,
+        invoke_id: u8,
+        service: u8,
+        data: Vec<u8>,
+    },
+    UnconfirmedRequest {
+        service: u8,
+        data: Vec<u8>,
+    },
+    SimpleAck {
+        invoke_id: u8,
+        service: u8,
+    },
+    ComplexAck {
+        invoke_id: u8,
+        service: u8,
+        data: Vec<u8>,
+    },
+    Error {
+        invoke_id: u8,
+        service: u8,
+        error: ErrorCode,
+    },
+    Reject {
+        invoke_id: u8,
+        reason: u8,
+    },
+    Abort {
+        invoke_id: u8,
+        reason: u8,
+    },
+}
+
+/// The maximum APDU lengths, by their 4 bit code.
+const MAX_APDU_LENGTHS: [usize; 6] = [50, 128, 206, 480, 1024, 1476];
+
+/// The largest APDU the device accepts.
+pub(super) const MAX_APDU: usize = 1476;
+
+/// An error class and code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct ErrorCode {
+    pub(super) class: u32,
+    pub(super) code: u32,
+}
+
+impl ErrorCode {
+    pub(super) const OPERATIONAL_PROBLEM: Self = Self::new(0, 25);
+    pub(super) const UNKNOWN_OBJECT: Self = Self::new(1, 31);
+    pub(super) const INVALID_DATA_TYPE: Self = Self::new(2, 9);
+    pub(super) const UNKNOWN_PROPERTY: Self = Self::new(2, 32);
+    pub(super) const VALUE_OUT_OF_RANGE: Self = Self::new(2, 37);
+    pub(super) const WRITE_ACCESS_DENIED: Self = Self::new(2, 40);
+    pub(super) const INVALID_ARRAY_INDEX: Self = Self::new(2, 42);
+    pub(super) const PROPERTY_IS_NOT_AN_ARRAY: Self = Self::new(2, 50);
+    pub(super) const OPTIONAL_FUNCTIONALITY_NOT_SUPPORTED: Self = Self::new(5, 45);
+
+    const fn new(class: u32, code: u32) -> Self {
+        Self { class, code }
+    }
+}
+
+impl Apdu {
+    pub(super) fn encode(&self) -> Vec<u8> {
+        use super::encoding::AppValue;
+
+        match self {
+            Apdu::ConfirmedRequest {
+                invoke_id,
+                service,
+                data,
+                max_apdu,
+                ..
+            } => {
+                let code = MAX_APDU_LENGTHS
+                    .iter()
+                    .rposition(|len| len <= max_apdu)
+                    .unwrap_or(0);
+                let mut pdu = vec![0x00, code as u8, *invoke_id, *service];
+                pdu.extend(data);
+                pdu
+            }
+            Apdu::UnconfirmedRequest { service, data } => {
+                let mut pdu = vec![0x10, *service];
+                pdu.extend(data);
+                pdu
+            }
+            Apdu::SimpleAck { invoke_id, service } => vec![0x20, *invoke_id, *service],
+            Apdu::ComplexAck {
+                invoke_id,
+                service,
+                data,
+            } => {
+                let mut pdu = vec![0x30, *invoke_id, *service];
+                pdu.extend(data);
+                pdu
+            }
+            Apdu::Error {
+                invoke_id,
+                service,
+                error,
+            } => {
+                let mut pdu = vec![0x50, *invoke_id, *service];
+                AppValue::Enumerated(error.class).encode(&mut pdu);
+                AppValue::Enumerated(error.code).encode(&mut pdu);
+                pdu
+            }
+            Apdu::Reject { invoke_id, reason } => vec![0x60, *invoke_id, *reason],
+            Apdu::Abort { invoke_id, reason } => vec![0x71, *invoke_id, *reason],
+        }
+    }
+
+    pub(super) fn decode(pdu: &[u8]) -> Result<Self, Malformed> {
+        use super::encoding::{AppValue, Reader};
+
+        let byte = |index: usize| pdu.get(index).copied().ok_or(Malformed("truncated APDU"));
+        let first = byte(0)?;
+        Ok(match first >> 4 {
+            0 => {
+                let segmented = first & 0x08 != 0;
+                let header = if segmented { 5 } else { 3 };
+                Apdu::ConfirmedRequest {
+                    segmented,
+                    max_apdu: MAX_APDU_LENGTHS
+                        .get(usize::from(byte(1)? & 0x0F))
+                        .copied()
+                        .unwrap_or(MAX_APDU),
+                    invoke_id: byte(2)?,
+                    service: byte(header)?,
+                    data: pdu[header + 1..].to_vec(),
+                }
+            }
+            1 => Apdu::UnconfirmedRequest {
+                service: byte(1)?,
+                data: pdu[2..].to_vec(),
+            },
+            2 => Apdu::SimpleAck {
+                invoke_id: byte(1)?,
+                service: byte(2)?,
+            },
+            3 if first & 0x08 == 0 => Apdu::ComplexAck {
+                invoke_id: byte(1)?,
+                service: byte(2)?,
+                data: pdu[3..].to_vec(),
+            },
+            5 => {
+                let mut reader = Reader::new(&pdu[3.min(pdu.len())..]);
+                let mut enumerated = || match reader.app()? {
+                    AppValue::Enumerated(value) => Ok(value),
+                    _ => Err(Malformed("bad error")),
+                };
+                Apdu::Error {
+                    invoke_id: byte(1)?,
+                    service: byte(2)?,
+                    error: ErrorCode {
+                        class: enumerated()?,
+                        code: enumerated()?,
+                    },
+                }
+            }
+            6 => Apdu::Reject {
+                invoke_id: byte(1)?,
+                reason: byte(2)?,
+            },
+            7 => Apdu::Abort {
+                invoke_id: byte(1)?,
+                reason: byte(2)?,
+            },
+            _ => return Err(Malformed("unsupported APDU type")),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::SocketAddr;
+
+    use super::{Apdu, Datagram, Route, encode_datagram};
+
+    #[test]
+    fn datagrams_carry_routes_and_apdus() {
+        let peer: SocketAddr = "192.168.1.10:47808".parse().unwrap();
+        let apdu = Apdu::SimpleAck {
+            invoke_id: 7,
+            service: 15,
+        }
+        .encode();
+
+        let route = Route {
+            network: 0xFFFF,
+            mac: vec![],
+        };
+        let datagram = encode_datagram(&apdu, true, Some(&route), false);
+        let decoded = Datagram::decode(peer, &datagram).unwrap();
+        assert_eq!(decoded.reply_to, peer);
+        assert_eq!(
+            Apdu::decode(&decoded.apdu).unwrap(),
+            Apdu::decode(&apdu).unwrap()
+        );
+
+        let remote = Route {
+            network: 5,
+            mac: vec![1],
+        };
+        assert!(
+            Datagram::decode(peer, &encode_datagram(&apdu, false, Some(&remote), false)).is_none()
+        );
+    }
+}
@@ -0,0 +1,443 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! BACnet tagged value encoding.
+//!
+//! Every value starts with a tag octet: the tag number in the high
+//! nibble, the class bit (application or context) and a three bit
+//! length, extended into the following octets when it does not fit.
+//! Constructed values sit between opening and closing context tags.
+
+/// A malformed PDU, with what was wrong.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Malformed(pub(super) &'static str);
+
+pub(super) type Result<T> = std::result::Result<T, Malformed>;
+
+/// An object, by type code and instance number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(super) struct ObjectId {
+    pub(super) object_type: u16,
+    pub(super) instance: u32,
+}
+
+impl ObjectId {
+    /// Largest instance number; 4194303 addresses "this device".
+    pub(super) const MAX_INSTANCE: u32 = 0x3F_FFFF;
+
+    pub(super) fn new(object_type: u16, instance: u32) -> Self {
+        Self {
+            object_type,
+            instance,
+        }
+    }
+
+    fn to_bits(self) -> u32 {
+        (u32::from(self.object_type) << 22) | (self.instance & Self::MAX_INSTANCE)
+    }
+
+    fn from_bits(bits: u32) -> Self {
+        Self::new((bits >> 22) as u16, bits & Self::MAX_INSTANCE)
+    }
+}
+
+/// An application tagged value.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum AppValue {
+    Null,
+    Boolean(bool),
+    Unsigned(u32),
+    Signed(i32),
+    Real(f32),
+    Double(f64),
+    CharacterString(String),
+    BitString(Vec<bool>),
+    Enumerated(u32),
+    ObjectId(ObjectId),
+}
+
+/// A decoded tag header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Tag {
+    /// Application tag number and content length. A boolean's value
+    /// is its length.
+    Application(u8, usize),
+    /// Context tag number and content length.
+    Context(u8, usize),
+    Opening(u8),
+    Closing(u8),
+}
+
+const APP_NULL: u8 = 0;
+const APP_BOOLEAN: u8 = 1;
+const APP_UNSIGNED: u8 = 2;
+const APP_SIGNED: u8 = 3;
+const APP_REAL: u8 = 4;
+const APP_DOUBLE: u8 = 5;
+const APP_CHARACTER_STRING: u8 = 7;
+const APP_BIT_STRING: u8 = 8;
+const APP_ENUMERATED: u8 = 9;
+const APP_OBJECT_ID: u8 = 12;
+
+/// UTF-8, the only character set written.
+const CHARSET_UTF8: u8 = 0;
+
+fn write_tag(buf: &mut Vec<u8>, number: u8, context: bool, len: usize) {
+    let class = if context { 0x08 } else { 0 };
+    let (first, extended) = if number < 15 {
+        (number << 4, None)
+    } else {
+        (0xF0, Some(number))
+    };
+    let lvt = if len < 5 { len as u8 } else { 5 };
+    buf.push(first | class | lvt);
+    buf.extend(extended);
+    if len >= 5 {
+        if len < 254 {
+            buf.push(len as u8);
+        } else if len <= u16::MAX as usize {
+            buf.push(254);
+            buf.extend((len as u16).to_be_bytes());
+        } else {
+            buf.push(255);
+            buf.extend((len as u32).to_be_bytes());
+        }
+    }
+}
+
+fn unsigned_bytes(value: u32) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let skip = bytes.iter().take(3).take_while(|byte| **byte == 0).count();
+    bytes[skip..].to_vec()
+}
+
+fn signed_bytes(value: i32) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let mut skip = 0;
+    while skip < 3 {
+        let (byte, next) = (bytes[skip], bytes[skip + 1]);
+        let redundant = (byte == 0 && next & 0x80 == 0) || (byte == 0xFF && next & 0x80 != 0);
+        if !redundant {
+            break;
+        }
+        skip += 1;
+    }
+    bytes[skip..].to_vec()
+}
+
+fn bit_string_bytes(bits: &[bool]) -> Vec<u8> {
+    let unused = (8 - bits.len() % 8) % 8;
+    let mut bytes = vec![unused as u8];
+    bytes.extend(bits.chunks(8).map(|chunk| {
+        chunk.iter().enumerate().fold(0u8, |byte, (index, bit)| {
+            byte | (u8::from(*bit) << (7 - index))
+        })
+    }));
+    bytes
+}
+
+impl AppValue {
+    /// Appends the application tagged value.
+    pub(super) fn encode(&self, buf: &mut Vec<u8>) {
+        let (tag, content) = match self {
+            AppValue::Null => (APP_NULL, vec![]),
+            AppValue::Boolean(value) => {
+                write_tag(buf, APP_BOOLEAN, false, usize::from(*value));
+                return;
+            }
+            AppValue::Unsigned(value) => (APP_UNSIGNED, unsigned_bytes(*value)),
+            AppValue::Signed(value) => (APP_SIGNED, signed_bytes(*value)),
+            AppValue::Real(value) => (APP_REAL, value.to_be_bytes().to_vec()),
+            AppValue::Double(value) => (APP_DOUBLE, value.to_be_bytes().to_vec()),
+            AppValue::CharacterString(value) => {
+                let mut content = vec![CHARSET_UTF8];
+                content.extend(value.as_bytes());
+                (APP_CHARACTER_STRING, content)
+            }
+            AppValue::BitString(bits) => (APP_BIT_STRING, bit_string_bytes(bits)),
+            AppValue::Enumerated(value) => (APP_ENUMERATED, unsigned_bytes(*value)),
+            AppValue::ObjectId(id) => (APP_OBJECT_ID, id.to_bits().to_be_bytes().to_vec()),
+        };
+        write_tag(buf, tag, false, content.len());
+        buf.extend(content);
+    }
+}
+
+/// Appends a context tagged unsigned, or enumerated, value.
+pub(super) fn context_unsigned(buf: &mut Vec<u8>, tag: u8, value: u32) {
+    let content = unsigned_bytes(value);
+    write_tag(buf, tag, true, content.len());
+    buf.extend(content);
+}
+
+/// Appends a context tagged boolean.
+#[cfg(test)]
+pub(super) fn context_bool(buf: &mut Vec<u8>, tag: u8, value: bool) {
+    write_tag(buf, tag, true, 1);
+    buf.push(u8::from(value));
+}
+
+/// Appends a context tagged object identifier.
+pub(super) fn context_object_id(buf: &mut Vec<u8>, tag: u8, id: ObjectId) {
+    write_tag(buf, tag, true, 4);
+    buf.extend(id.to_bits().to_be_bytes());
+}
+
+pub(super) fn opening(buf: &mut Vec<u8>, tag: u8) {
+    buf.push((tag << 4) | 0x0E);
+}
+
+pub(super) fn closing(buf: &mut Vec<u8>, tag: u8) {
+    buf.push((tag << 4) | 0x0F);
+}
+
+/// Reads tagged values from the start of a buffer.
+pub(super) struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub(super) fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// The next tag and the length of its header, without consuming
+    /// either.
+    fn header(&self) -> Result<Option<(Tag, usize)>> {
+        let Some(&first) = self.data.first() else {
+            return Ok(None);
+        };
+        let byte = |index: usize| {
+            self.data
+                .get(index)
+                .copied()
+                .ok_or(Malformed("truncated tag"))
+        };
+
+        let mut used = 1;
+        let mut number = first >> 4;
+        if number == 15 {
+            number = byte(1)?;
+            used += 1;
+        }
+        let context = first & 0x08 != 0;
+        let lvt = first & 0x07;
+        if context && lvt == 6 {
+            return Ok(Some((Tag::Opening(number), used)));
+        }
+        if context && lvt == 7 {
+            return Ok(Some((Tag::Closing(number), used)));
+        }
+
+        let len = if lvt < 5 {
+            usize::from(lvt)
+        } else {
+            let len = byte(used)?;
+            used += 1;
+            match len {
+                254 => {
+                    used += 2;
+                    usize::from(u16::from_be_bytes([byte(used - 2)?, byte(used - 1)?]))
+                }
+                255 => {
+                    used += 4;
+                    u32::from_be_bytes([
+                        byte(used - 4)?,
+                        byte(used - 3)?,
+                        byte(used - 2)?,
+                        byte(used - 1)?,
+                    ]) as usize
+                }
+                len => usize::from(len),
+            }
+        };
+        let tag = if context {
+            Tag::Context(number, len)
+        } else {
+            Tag::Application(number, len)
+        };
+        Ok(Some((tag, used)))
+    }
+
+    /// The next tag, without consuming it.
+    pub(super) fn peek(&self) -> Result<Option<Tag>> {
+        Ok(self.header()?.map(|(tag, _)| tag))
+    }
+
+    /// Consumes the next tag and its content.
+    fn take(&mut self) -> Result<(Tag, &'a [u8])> {
+        let (tag, used) = self.header()?.ok_or(Malformed("missing value"))?;
+        let len = match tag {
+            Tag::Context(_, len) => len,
+            Tag::Application(APP_BOOLEAN, _) | Tag::Opening(_) | Tag::Closing(_) => 0,
+            Tag::Application(_, len) => len,
+        };
+        let content = self
+            .data
+            .get(used..used + len)
+            .ok_or(Malformed("truncated value"))?;
+        self.data = &self.data[used + len..];
+        Ok((tag, content))
+    }
+
+    /// Whether the next tag is the context tag `number`, primitive or
+    /// opening.
+    pub(super) fn at_context(&self, number: u8) -> bool {
+        matches!(
+            self.peek(),
+            Ok(Some(Tag::Context(n, _) | Tag::Opening(n))) if n == number
+        )
+    }
+
+    pub(super) fn at_closing(&self, number: u8) -> bool {
+        matches!(self.peek(), Ok(Some(Tag::Closing(n))) if n == number)
+    }
+
+    /// Reads an application tagged value.
+    pub(super) fn app(&mut self) -> Result<AppValue> {
+        let (tag, content) = self.take()?;
+        let Tag::Application(number, len) = tag else {
+            return Err(Malformed("expected an application tag"));
+        };
+        Ok(match number {
+            APP_NULL => AppValue::Null,
+            APP_BOOLEAN => AppValue::Boolean(len != 0),
+            APP_UNSIGNED => AppValue::Unsigned(unsigned(content)?),
+            APP_SIGNED => AppValue::Signed(signed(content)?),
+            APP_REAL => AppValue::Real(f32::from_be_bytes(
+                content.try_into().map_err(|_| Malformed("bad real"))?,
+            )),
+            APP_DOUBLE => AppValue::Double(f64::from_be_bytes(
+                content.try_into().map_err(|_| Malformed("bad double"))?,
+            )),
+            APP_CHARACTER_STRING => match content.split_first() {
+                Some((&CHARSET_UTF8, text)) => AppValue::CharacterString(
+                    String::from_utf8(text.to_vec()).map_err(|_| Malformed("bad string"))?,
+                ),
+                _ => return Err(Malformed("unsupported character set")),
+            },
+            APP_BIT_STRING => {
+                let (&unused, bytes) = content.split_first().ok_or(Malformed("bad bit string"))?;
+                let mut bits: Vec<bool> = bytes
+                    .iter()
+                    .flat_map(|byte| (0..8).map(move |bit| byte & (0x80 >> bit) != 0))
+                    .collect();
+                bits.truncate(bits.len().saturating_sub(usize::from(unused)));
+                AppValue::BitString(bits)
+            }
+            APP_ENUMERATED => AppValue::Enumerated(unsigned(content)?),
+            APP_OBJECT_ID => AppValue::ObjectId(ObjectId::from_bits(unsigned(content)?)),
+            _ => return Err(Malformed("unsupported application tag")),
+        })
+    }
+
+    /// Reads the primitive context tag `number`.
+    fn context(&mut self, number: u8) -> Result<&'a [u8]> {
+        match self.take()? {
+            (Tag::Context(n, _), content) if n == number => Ok(content),
+            _ => Err(Malformed("unexpected tag")),
+        }
+    }
+
+    pub(super) fn context_unsigned(&mut self, number: u8) -> Result<u32> {
+        unsigned(self.context(number)?)
+    }
+
+    pub(super) fn context_bool(&mut self, number: u8) -> Result<bool> {
+        Ok(unsigned(self.context(number)?)? != 0)
+    }
+
+    pub(super) fn context_object_id(&mut self, number: u8) -> Result<ObjectId> {
+        let content = self.context(number)?;
+        if content.len() != 4 {
+            return Err(Malformed("bad object identifier"));
+        }
+        Ok(ObjectId::from_bits(unsigned(content)?))
+    }
+
+    /// Reads the context tag `number` if it comes next.
+    pub(super) fn optional_unsigned(&mut self, number: u8) -> Result<Option<u32>> {
+        match self.at_context(number) {
+            true => self.context_unsigned(number).map(Some),
+            false => Ok(None),
+        }
+    }
+
+    pub(super) fn opening(&mut self, number: u8) -> Result<()> {
+        match self.take()? {
+            (Tag::Opening(n), _) if n == number => Ok(()),
+            _ => Err(Malformed("expected an opening tag")),
+        }
+    }
+
+    pub(super) fn closing(&mut self, number: u8) -> Result<()> {
+        match self.take()? {
+            (Tag::Closing(n), _) if n == number => Ok(()),
+            _ => Err(Malformed("expected a closing tag")),
+        }
+    }
+
+    /// Reads application tagged values up to the closing tag `number`,
+    /// consuming it.
+    pub(super) fn values_until_closing(&mut self, number: u8) -> Result<Vec<AppValue>> {
+        let mut values = Vec::new();
+        while !self.at_closing(number) {
+            values.push(self.app()?);
+        }
+        self.closing(number)?;
+        Ok(values)
+    }
+}
+
+fn unsigned(content: &[u8]) -> Result<u32> {
+    if content.is_empty() || content.len() > 4 {
+        return Err(Malformed("bad unsigned"));
+    }
+    Ok(content
+        .iter()
+        .fold(0u32, |value, byte| (value << 8) | u32::from(*byte)))
+}
+
+fn signed(content: &[u8]) -> Result<i32> {
+    let value = unsigned(content)?;
+    let shift = 32 - 8 * content.len() as u32;
+    Ok(((value << shift) as i32) >> shift)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{AppValue, ObjectId, Reader};
+
+    #[test]
+    fn application_values_round_trip() {
+        let values = [
+            AppValue::Null,
+            AppValue::Boolean(true),
+            AppValue::Unsigned(0),
+            AppValue::Unsigned(70_000),
+            AppValue::Signed(-129),
+            AppValue::Signed(127),
+            AppValue::Real(21.5),
+            AppValue::Double(-0.25),
+            AppValue::CharacterString("Zone temperature setpoint".into()),
+            AppValue::BitString(vec![false, true, false, false, true]),
+            AppValue::Enumerated(95),
+            AppValue::ObjectId(ObjectId::new(19, 4_000_000)),
+        ];
+
+        let mut buf = Vec::new();
+        values.iter().for_each(|value| value.encode(&mut buf));
+        let mut reader = Reader::new(&buf);
+        for value in values {
+            assert_eq!(reader.app().unwrap(), value);
+        }
+        assert!(reader.is_empty());
+
+        let mut buf = Vec::new();
+        AppValue::Real(1.0).encode(&mut buf);
+        assert_eq!(buf, [0x44, 0x3F, 0x80, 0, 0]);
+    }
+}
@@ -0,0 +1,226 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! The device and its objects: settings, property values and command
+//! priority arrays.
+
+use libhaystack::val::{Number, Value};
+use serde::{Deserialize, Serialize};
+
+use crate::base::priority::Priorities;
+
+use super::ObjectType;
+use super::apdu::ErrorCode;
+use super::encoding::AppValue;
+
+/// The device's identity.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceConfig {
+    /// Device instance number, unique on the internetwork.
+    pub instance: u32,
+    /// Device object name, unique on the internetwork.
+    pub name: String,
+    /// Device description.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Vendor identifier assigned by ASHRAE.
+    #[serde(default)]
+    pub vendor_id: u16,
+    /// Vendor name.
+    #[serde(default = "default_vendor_name")]
+    pub vendor_name: String,
+    /// Model name.
+    #[serde(default = "default_model_name")]
+    pub model_name: String,
+}
+
+fn default_vendor_name() -> String {
+    "logic-mesh".into()
+}
+
+fn default_model_name() -> String {
+    "logic-mesh".into()
+}
+
+/// One pin exposed as a BACnet object.
+///
+/// # Examples
+///
+/// ```
+/// use logic_mesh::bacnet::ObjectMapping;
+///
+/// let mapping: ObjectMapping = serde_json::from_str(
+///     r#"{"objectType": "analogValue", "instance": 1, "name": "ZN-T-SP",
+///         "block": "8c49ad3e-f9f2-4a42-9a5e-a3c5eb1c2bd0", "pin": "in",
+///         "commandable": true, "covIncrement": 0.5}"#,
+/// )
+/// .unwrap();
+/// assert!(mapping.commandable);
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ObjectMapping {
+    /// Analog, binary or multi-state value.
+    pub object_type: ObjectType,
+    /// Instance number, unique per type.
+    pub instance: u32,
+    /// Object name, unique in the device.
+    pub name: String,
+    /// Object description.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// UUID of the block.
+    pub block: String,
+    /// Pin name: an output, or else an input, of the block.
+    pub pin: String,
+    /// Whether the present value takes prioritized writes. Objects that
+    /// are not commandable cannot be written.
+    #[serde(default)]
+    pub commandable: bool,
+    /// The value a commandable object takes when no priority is set.
+    /// Unless given, the pin's value before the first command.
+    #[serde(default)]
+    pub relinquish_default: Option<f64>,
+    /// Smallest change of an analog value notified to subscribers; `0`
+    /// notifies every change.
+    #[serde(default)]
+    pub cov_increment: f64,
+    /// Engineering unit symbol of an analog value, by default the pin's
+    /// unit.
+    #[serde(default)]
+    pub units: Option<String>,
+    /// Names of a multi-state value's states, from state 1.
+    #[serde(default)]
+    pub states: Vec<String>,
+}
+
+/// Number of command priorities; 1 is the highest.
+pub(super) const PRIORITIES: usize = 16;
+
+/// The commands of one object, as engineering values: reals, `0` or `1`
+/// for binary values, the state for multi-state values.
+pub(super) type PriorityArray = Priorities<f64, PRIORITIES>;
+
+impl ObjectMapping {
+    /// `value` as the object's present value.
+    pub(super) fn present_value(&self, value: &Value) -> AppValue {
+        self.to_app(engineering(value))
+    }
+
+    /// An engineering value as a property value of the object.
+    pub(super) fn to_app(&self, value: f64) -> AppValue {
+        match self.object_type {
+            ObjectType::AnalogValue => AppValue::Real(value as f32),
+            ObjectType::BinaryValue => AppValue::Enumerated(u32::from(value != 0.0)),
+            _ => AppValue::Unsigned(value.round().max(1.0) as u32),
+        }
+    }
+
+    /// A written property value as an engineering value.
+    pub(super) fn engineering_value(&self, value: &AppValue) -> Result<f64, ErrorCode> {
+        let value = match (self.object_type, value) {
+            (ObjectType::AnalogValue, AppValue::Real(value)) => f64::from(*value),
+            (ObjectType::AnalogValue, AppValue::Double(value)) => *value,
+            (ObjectType::AnalogValue, AppValue::Signed(value)) => f64::from(*value),
+            (ObjectType::AnalogValue | ObjectType::MultiStateValue, AppValue::Unsigned(value)) => {
+                f64::from(*value)
+            }
+            (ObjectType::BinaryValue, AppValue::Enumerated(value)) => f64::from(*value),
+            _ => return Err(ErrorCode::INVALID_DATA_TYPE),
+        };
+        let in_range = match self.object_type {
+            ObjectType::BinaryValue => value == 0.0 || value == 1.0,
+            ObjectType::MultiStateValue => value >= 1.0 && value <= self.states.len() as f64,
+            _ => value.is_finite(),
+        };
+        if in_range {
+            Ok(value)
+        } else {
+            Err(ErrorCode::VALUE_OUT_OF_RANGE)
+        }
+    }
+
+    /// An engineering value to write to the pin, which holds `current`.
+    pub(super) fn to_pin(&self, value: f64, current: &Value) -> Value {
+        match (self.object_type, current) {
+            (ObjectType::BinaryValue, Value::Bool(_) | Value::Null) => {
+                Value::make_bool(value != 0.0)
+            }
+            (
+                _,
+                Value::Number(Number {
+                    unit: Some(unit), ..
+                }),
+            ) => Number::make_with_unit(value, unit).into(),
+            _ => Number::make(value).into(),
+        }
+    }
+
+    /// The BACnet engineering units of an analog value on a pin holding
+    /// `value`.
+    pub(super) fn engineering_units(&self, value: &Value) -> u32 {
+        let symbol = match (&self.units, value) {
+            (Some(symbol), _) => Some(
+                libhaystack::units::get_unit(symbol).map_or(symbol.as_str(), |unit| unit.symbol()),
+            ),
+            (
+                None,
+                Value::Number(Number {
+                    unit: Some(unit), ..
+                }),
+            ) => Some(unit.symbol()),
+            _ => None,
+        };
+        symbol.map_or(NO_UNITS, units_code)
+    }
+}
+
+/// A pin value as an engineering value.
+pub(super) fn engineering(value: &Value) -> f64 {
+    match value {
+        Value::Number(number) => number.value,
+        Value::Bool(value) => f64::from(u8::from(value.value)),
+        _ => 0.0,
+    }
+}
+
+const NO_UNITS: u32 = 95;
+
+/// The BACnet engineering units enumeration of a Haystack unit symbol.
+fn units_code(symbol: &str) -> u32 {
+    match symbol {
+        "°C" => 62,
+        "°F" => 64,
+        "K" => 63,
+        "Δ°C" => 121,
+        "Δ°F" => 120,
+        "%" => 98,
+        "%RH" => 29,
+        "Pa" => 53,
+        "kPa" => 54,
+        "bar" => 55,
+        "psi" => 56,
+        "inH₂O" => 58,
+        "W" => 47,
+        "kW" => 48,
+        "MW" => 49,
+        "Wh" => 18,
+        "kWh" => 19,
+        "V" => 5,
+        "A" => 3,
+        "Hz" => 27,
+        "s" => 73,
+        "min" => 72,
+        "h" => 71,
+        "ms" => 159,
+        "m" => 31,
+        "m/s" => 74,
+        "L/s" => 87,
+        "m³/h" => 135,
+        "cfm" => 84,
+        "ppm" => 96,
+        "lx" => 37,
+        "rpm" => 104,
+        _ => NO_UNITS,
+    }
+}
@@ -0,0 +1,39 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Property identifiers the device knows.
+
+pub(super) const ALL: u32 = 8;
+pub(super) const APDU_TIMEOUT: u32 = 11;
+pub(super) const APPLICATION_SOFTWARE_VERSION: u32 = 12;
+pub(super) const COV_INCREMENT: u32 = 22;
+pub(super) const DESCRIPTION: u32 = 28;
+pub(super) const DEVICE_ADDRESS_BINDING: u32 = 30;
+pub(super) const EVENT_STATE: u32 = 36;
+pub(super) const FIRMWARE_REVISION: u32 = 44;
+pub(super) const MAX_APDU_LENGTH_ACCEPTED: u32 = 62;
+pub(super) const MODEL_NAME: u32 = 70;
+pub(super) const NUMBER_OF_APDU_RETRIES: u32 = 73;
+pub(super) const NUMBER_OF_STATES: u32 = 74;
+pub(super) const OBJECT_IDENTIFIER: u32 = 75;
+pub(super) const OBJECT_LIST: u32 = 76;
+pub(super) const OBJECT_NAME: u32 = 77;
+pub(super) const OBJECT_TYPE: u32 = 79;
+pub(super) const OPTIONAL: u32 = 80;
+pub(super) const OUT_OF_SERVICE: u32 = 81;
+pub(super) const PRESENT_VALUE: u32 = 85;
+pub(super) const PRIORITY_ARRAY: u32 = 87;
+pub(super) const PROTOCOL_OBJECT_TYPES_SUPPORTED: u32 = 96;
+pub(super) const PROTOCOL_SERVICES_SUPPORTED: u32 = 97;
+pub(super) const PROTOCOL_VERSION: u32 = 98;
+pub(super) const RELINQUISH_DEFAULT: u32 = 104;
+pub(super) const REQUIRED: u32 = 105;
+pub(super) const SEGMENTATION_SUPPORTED: u32 = 107;
+pub(super) const STATE_TEXT: u32 = 110;
+pub(super) const STATUS_FLAGS: u32 = 111;
+pub(super) const SYSTEM_STATUS: u32 = 112;
+pub(super) const UNITS: u32 = 117;
+pub(super) const VENDOR_IDENTIFIER: u32 = 120;
+pub(super) const VENDOR_NAME: u32 = 121;
+pub(super) const PROTOCOL_REVISION: u32 = 139;
+pub(super) const DATABASE_REVISION: u32 = 155;
+pub(super) const PROPERTY_LIST: u32 = 371;
@@ -0,0 +1,949 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! BACnet/IP device serving block pins as value objects.
+
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, BTreeSet};
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+use libhaystack::val::Value;
+use tokio::net::UdpSocket;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use super::apdu::{
+    Apdu, Datagram, ErrorCode, MAX_APDU, Route, abort, confirmed, encode_datagram, reject,
+    unconfirmed,
+};
+use super::encoding::{AppValue, ObjectId};
+use super::object::{PRIORITIES, PriorityArray, engineering};
+use super::property as prop;
+use super::services::{
+    CovNotification, IAm, PropertyRef, ReadProperty, ReadPropertyAck, ReadPropertyMultiple,
+    ReadPropertyMultipleAck, ReadResult, SubscribeCov, WhoIs, WriteProperty,
+};
+use super::{BacnetError, DeviceConfig, ObjectMapping, ObjectType};
+use crate::base::engine::{
+    Engine,
+    messages::{BlockDefinition, ChangeSource, EngineMessage, WatchMessage},
+};
+use crate::tokio_impl::engine::single_threaded::Messages;
+
+/// Protocol revision the device claims.
+const PROTOCOL_REVISION: u32 = 14;
+
+/// Segmentation supported: none.
+const NO_SEGMENTATION: u32 = 3;
+
+/// Services the device executes, as bits of the services supported
+/// bit string.
+const SERVICES_SUPPORTED: [usize; 5] = [5, 12, 14, 15, 34];
+const SERVICES_SUPPORTED_BITS: usize = 41;
+const OBJECT_TYPES_SUPPORTED_BITS: usize = 55;
+
+/// Serves selected pins of an engine as the objects of a BACnet/IP
+/// device.
+///
+/// # Examples
+///
+/// ```no_run
+/// use logic_mesh::bacnet::{BacnetServer, DEFAULT_PORT, DeviceConfig, ObjectMapping};
+/// use logic_mesh::base::engine::Engine;
+/// use logic_mesh::single_threaded::SingleThreadedEngine;
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut engine = SingleThreadedEngine::new();
+/// let device: DeviceConfig =
+///     serde_json::from_str(r#"{"instance": 2001, "name": "AHU-1 controller"}"#)?;
+/// let objects: Vec<ObjectMapping> = serde_json::from_str(
+///     r#"[{"objectType": "analogValue", "instance": 1, "name": "SA-T",
+///          "block": "...", "pin": "out"}]"#,
+/// )?;
+/// let server = BacnetServer::new(&mut engine, device, objects)?;
+///
+/// let socket = tokio::net::UdpSocket::bind(("0.0.0.0", DEFAULT_PORT)).await?;
+/// tokio::spawn(server.serve(socket));
+/// engine.run().await;
+/// # Ok(())
+/// # }
+/// ```
+pub struct BacnetServer {
+    device: DeviceConfig,
+    objects: Vec<Object>,
+    engine: EngineLink,
+    invoke_id: u8,
+}
+
+/// The server's channel to the engine.
+struct EngineLink {
+    id: Uuid,
+    sender: mpsc::Sender<Messages>,
+    replies: mpsc::Receiver<Messages>,
+}
+
+/// A mapped object and its runtime state.
+struct Object {
+    id: ObjectId,
+    block: Uuid,
+    mapping: ObjectMapping,
+    /// Set by the first command.
+    priority: Option<PriorityArray>,
+    /// The pin's last known value and the block's fault state.
+    pin: Option<Pin>,
+    /// Present value and fault state last notified to subscribers.
+    notified: Option<(AppValue, bool)>,
+    subscriptions: Vec<Subscription>,
+}
+
+#[derive(Debug, Clone)]
+struct Pin {
+    value: Value,
+    fault: bool,
+}
+
+struct Subscription {
+    reply_to: SocketAddr,
+    route: Option<Route>,
+    process: u32,
+    confirmed: bool,
+    /// [`None`] for an indefinite subscription.
+    expires: Option<Instant>,
+}
+
+/// The object a request addresses.
+#[derive(Clone, Copy)]
+enum Target {
+    Device,
+    Object(usize),
+}
+
+/// How a confirmed request is answered.
+enum Outcome {
+    SimpleAck,
+    ComplexAck(Vec<u8>),
+    Error(ErrorCode),
+    Reject(u8),
+    /// A subscription was added to the object; the acknowledgement is
+    /// followed by an initial notification.
+    Subscribed(usize),
+}
+
+/// Block definitions inspected while answering one request.
+type Inspected = BTreeMap<Uuid, BlockDefinition>;
+
+impl BacnetServer {
+    /// Attaches a device to `engine`. Call before the engine runs: the
+    /// server's reply channel is registered with it here.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BacnetError::InvalidDevice`] for an instance number out
+    /// of range, and [`BacnetError::InvalidMapping`] if a block id is not
+    /// a UUID, two objects share a type and instance or a name, or a
+    /// multi-state value has no states.
+    pub fn new<E: Engine<Channel = mpsc::Sender<Messages>>>(
+        engine: &mut E,
+        device: DeviceConfig,
+        mappings: Vec<ObjectMapping>,
+    ) -> Result<Self, BacnetError> {
+        if device.instance >= ObjectId::MAX_INSTANCE {
+            return Err(BacnetError::InvalidDevice(format!(
+                "instance {} is out of range",
+                device.instance
+            )));
+        }
+
+        let mut names = BTreeSet::from([device.name.clone()]);
+        let mut objects: Vec<Object> = Vec::with_capacity(mappings.len());
+        for mapping in mappings {
+            let invalid = |reason: String| {
+                BacnetError::InvalidMapping(format!(
+                    "{} {}: {reason}",
+                    mapping.object_type, mapping.instance
+                ))
+            };
+            if mapping.object_type == ObjectType::Device {
+                return Err(invalid("a pin cannot be a device".into()));
+            }
+            if mapping.instance >= ObjectId::MAX_INSTANCE {
+                return Err(invalid("instance is out of range".into()));
+            }
+            if mapping.object_type == ObjectType::MultiStateValue && mapping.states.is_empty() {
+                return Err(invalid("a multi-state value needs states".into()));
+            }
+            let block = Uuid::parse_str(&mapping.block)
+                .map_err(|err| invalid(format!("invalid block '{}': {err}", mapping.block)))?;
+            let id = ObjectId::new(mapping.object_type.code(), mapping.instance);
+            if objects.iter().any(|object| object.id == id) {
+                return Err(invalid("the object is mapped twice".into()));
+            }
+            if !names.insert(mapping.name.clone()) {
+                return Err(invalid(format!("the name '{}' is taken", mapping.name)));
+            }
+            objects.push(Object {
+                id,
+                block,
+                mapping,
+                priority: None,
+                pin: None,
+                notified: None,
+                subscriptions: Vec::new(),
+            });
+        }
+
+        let id = Uuid::new_v4();
+        let (reply_sender, replies) = mpsc::channel(32);
+        let sender = engine.create_message_channel(id, reply_sender);
+
+        Ok(Self {
+            device,
+            objects,
+            engine: EngineLink {
+                id,
+                sender,
+                replies,
+            },
+            invoke_id: 0,
+        })
+    }
+
+    /// Serves requests arriving on `socket` until the engine stops.
+    pub async fn serve(mut self, socket: UdpSocket) -> std::io::Result<()> {
+        let (watch_sender, mut watch) = mpsc::unbounded_channel();
+        let subscribe = EngineMessage::WatchBlockSubReq(self.engine.id, watch_sender);
+        if !matches!(
+            self.engine.exchange(subscribe).await,
+            Some(EngineMessage::WatchBlockSubRes(Ok(_)))
+        ) {
+            return Ok(());
+        }
+
+        let mut buf = [0u8; 2048];
+        loop {
+            tokio::select! {
+                received = socket.recv_from(&mut buf) => {
+                    let (len, peer) = received?;
+                    if let Some(datagram) = Datagram::decode(peer, &buf[..len]) {
+                        self.handle(&socket, datagram).await;
+                    }
+                }
+                change = watch.recv() => match change {
+                    Some(change) => self.on_change(&socket, change).await,
+                    None => return Ok(()),
+                },
+            }
+        }
+    }
+
+    async fn handle(&mut self, socket: &UdpSocket, datagram: Datagram) {
+        let Ok(apdu) = Apdu::decode(&datagram.apdu) else {
+            return;
+        };
+        match apdu {
+            Apdu::UnconfirmedRequest {
+                service: unconfirmed::WHO_IS,
+                data,
+            } => {
+                let Ok(who_is) = WhoIs::decode(&data) else {
+                    return;
+                };
+                if who_is
+                    .range
+                    .is_none_or(|(low, high)| (low..=high).contains(&self.device.instance))
+                {
+                    let i_am = Apdu::UnconfirmedRequest {
+                        service: unconfirmed::I_AM,
+                        data: self.i_am().encode(),
+                    };
+                    send(socket, datagram.reply_to, datagram.source.as_ref(), &i_am).await;
+                }
+            }
+            Apdu::ConfirmedRequest {
+                segmented,
+                max_apdu,
+                invoke_id,
+                service,
+                data,
+            } => {
+                let outcome = if segmented {
+                    None
+                } else {
+                    Some(self.confirmed(service, &data, &datagram).await)
+                };
+                let mut subscribed = None;
+                let response = match outcome {
+                    None => Apdu::Abort {
+                        invoke_id,
+                        reason: abort::SEGMENTATION_NOT_SUPPORTED,
+                    },
+                    Some(Outcome::SimpleAck) => Apdu::SimpleAck { invoke_id, service },
+                    Some(Outcome::Subscribed(index)) => {
+                        subscribed = Some(index);
+                        Apdu::SimpleAck { invoke_id, service }
+                    }
+                    Some(Outcome::ComplexAck(data)) => Apdu::ComplexAck {
+                        invoke_id,
+                        service,
+                        data,
+                    },
+                    Some(Outcome::Error(error)) => Apdu::Error {
+                        invoke_id,
+                        service,
+                        error,
+                    },
+                    Some(Outcome::Reject(reason)) => Apdu::Reject { invoke_id, reason },
+                };
+                // Without segmentation, what does not fit is aborted.
+                let response = match response.encode().len() > max_apdu.min(MAX_APDU) {
+                    true => Apdu::Abort {
+                        invoke_id,
+                        reason: abort::SEGMENTATION_NOT_SUPPORTED,
+                    },
+                    false => response,
+                };
+                send(
+                    socket,
+                    datagram.reply_to,
+                    datagram.source.as_ref(),
+                    &response,
+                )
+                .await;
+
+                if let Some(index) = subscribed {
+                    let subscriber = self.objects[index].subscriptions.len() - 1;
+                    if let Some(values) = self.snapshot(index).await {
+                        self.objects[index].notified = Some(values.clone());
+                        self.notify(socket, index, Some(subscriber), values).await;
+                    }
+                }
+            }
+            // Acknowledgements of confirmed notifications.
+            _ => {}
+        }
+    }
+
+    async fn confirmed(&mut self, service: u8, data: &[u8], datagram: &Datagram) -> Outcome {
+        let outcome = match service {
+            confirmed::READ_PROPERTY => match ReadProperty::decode(data) {
+                Ok(request) => Ok(self.read_property(request).await),
+                Err(err) => Err(err),
+            },
+            confirmed::READ_PROPERTY_MULTIPLE => match ReadPropertyMultiple::decode(data) {
+                Ok(request) => Ok(self.read_multiple(request).await),
+                Err(err) => Err(err),
+            },
+            confirmed::WRITE_PROPERTY => match WriteProperty::decode(data) {
+                Ok(request) => Ok(self.write(request).await),
+                Err(err) => Err(err),
+            },
+            confirmed::SUBSCRIBE_COV => {
+                SubscribeCov::decode(data).map(|request| self.subscribe(request, datagram))
+            }
+            _ => return Outcome::Reject(reject::UNRECOGNIZED_SERVICE),
+        };
+        outcome.unwrap_or(Outcome::Reject(reject::INVALID_TAG))
+    }
+
+    fn i_am(&self) -> IAm {
+        IAm {
+            device: self.device_id(),
+            max_apdu: MAX_APDU as u32,
+            segmentation: NO_SEGMENTATION,
+            vendor: u32::from(self.device.vendor_id),
+        }
+    }
+
+    fn device_id(&self) -> ObjectId {
+        ObjectId::new(ObjectType::Device.code(), self.device.instance)
+    }
+
+    fn target(&self, id: ObjectId) -> Option<Target> {
+        if id.object_type == ObjectType::Device.code()
+            && (id.instance == self.device.instance || id.instance == ObjectId::MAX_INSTANCE)
+        {
+            return Some(Target::Device);
+        }
+        self.objects
+            .iter()
+            .position(|object| object.id == id)
+            .map(Target::Object)
+    }
+
+    async fn read_property(&mut self, request: ReadProperty) -> Outcome {
+        match self
+            .read(&mut Inspected::new(), request.object, request.property)
+            .await
+        {
+            Ok(values) => Outcome::ComplexAck(
+                ReadPropertyAck {
+                    object: request.object,
+                    property: request.property,
+                    values,
+                }
+                .encode(),
+            ),
+            Err(error) => Outcome::Error(error),
+        }
+    }
+
+    async fn read_multiple(&mut self, request: ReadPropertyMultiple) -> Outcome {
+        let mut inspected = Inspected::new();
+        let mut results = Vec::with_capacity(request.specs.len());
+        for spec in request.specs {
+            let mut properties: Vec<(PropertyRef, ReadResult)> = Vec::new();
+            for property in spec.properties {
+                let expanded = match (property.property, self.target(spec.object)) {
+                    (prop::ALL | prop::REQUIRED, Some(target)) => self.properties(target),
+                    (prop::OPTIONAL, Some(_)) => vec![],
+                    _ => vec![property.property],
+                };
+                for id in expanded {
+                    let property = PropertyRef {
+                        property: id,
+                        ..property
+                    };
+                    let result = self.read(&mut inspected, spec.object, property).await;
+                    properties.push((property, result));
+                }
+            }
+            results.push((spec.object, properties));
+        }
+        Outcome::ComplexAck(ReadPropertyMultipleAck { results }.encode())
+    }
+
+    async fn read(
+        &mut self,
+        inspected: &mut Inspected,
+        object: ObjectId,
+        property: PropertyRef,
+    ) -> ReadResult {
+        let target = self.target(object).ok_or(ErrorCode::UNKNOWN_OBJECT)?;
+        let values = match target {
+            Target::Device => self.device_property(property.property),
+            Target::Object(index) => {
+                self.object_property(inspected, index, property.property)
+                    .await?
+            }
+        }
+        .ok_or(ErrorCode::UNKNOWN_PROPERTY)?;
+
+        let is_array = matches!(
+            property.property,
+            prop::OBJECT_LIST | prop::PRIORITY_ARRAY | prop::STATE_TEXT | prop::PROPERTY_LIST
+        );
+        match (property.index, is_array) {
+            (None, _) => Ok(values),
+            (Some(_), false) => Err(ErrorCode::PROPERTY_IS_NOT_AN_ARRAY),
+            (Some(0), true) => Ok(vec![AppValue::Unsigned(values.len() as u32)]),
+            (Some(index), true) => values
+                .get(index as usize - 1)
+                .cloned()
+                .map(|value| vec![value])
+                .ok_or(ErrorCode::INVALID_ARRAY_INDEX),
+        }
+    }
+
+    /// The properties of `target`, as listed by ReadPropertyMultiple's
+    /// `all`.
+    fn properties(&self, target: Target) -> Vec<u32> {
+        let mut properties = vec![
+            prop::OBJECT_IDENTIFIER,
+            prop::OBJECT_NAME,
+            prop::OBJECT_TYPE,
+        ];
+        let mapping = match target {
+            Target::Device => {
+                properties.extend([
+                    prop::SYSTEM_STATUS,
+                    prop::VENDOR_NAME,
+                    prop::VENDOR_IDENTIFIER,
+                    prop::MODEL_NAME,
+                    prop::FIRMWARE_REVISION,
+                    prop::APPLICATION_SOFTWARE_VERSION,
+                    prop::PROTOCOL_VERSION,
+                    prop::PROTOCOL_REVISION,
+                    prop::PROTOCOL_SERVICES_SUPPORTED,
+                    prop::PROTOCOL_OBJECT_TYPES_SUPPORTED,
+                    prop::OBJECT_LIST,
+                    prop::MAX_APDU_LENGTH_ACCEPTED,
+                    prop::SEGMENTATION_SUPPORTED,
+                    prop::APDU_TIMEOUT,
+                    prop::NUMBER_OF_APDU_RETRIES,
+                    prop::DEVICE_ADDRESS_BINDING,
+                    prop::DATABASE_REVISION,
+                ]);
+                if self.device.description.is_some() {
+                    properties.push(prop::DESCRIPTION);
+                }
+                properties.push(prop::PROPERTY_LIST);
+                return properties;
+            }
+            Target::Object(index) => &self.objects[index].mapping,
+        };
+
+        properties.extend([
+            prop::PRESENT_VALUE,
+            prop::STATUS_FLAGS,
+            prop::EVENT_STATE,
+            prop::OUT_OF_SERVICE,
+        ]);
+        match mapping.object_type {
+            ObjectType::AnalogValue => properties.extend([prop::UNITS, prop::COV_INCREMENT]),
+            ObjectType::MultiStateValue => {
+                properties.extend([prop::NUMBER_OF_STATES, prop::STATE_TEXT])
+            }
+            _ => {}
+        }
+        if mapping.commandable {
+            properties.extend([prop::PRIORITY_ARRAY, prop::RELINQUISH_DEFAULT]);
+        }
+        if mapping.description.is_some() {
+            properties.push(prop::DESCRIPTION);
+        }
+        properties.push(prop::PROPERTY_LIST);
+        properties
+    }
+
+    fn device_property(&self, property: u32) -> Option<Vec<AppValue>> {
+        let value = match property {
+            prop::OBJECT_IDENTIFIER => AppValue::ObjectId(self.device_id()),
+            prop::OBJECT_NAME => AppValue::CharacterString(self.device.name.clone()),
+            prop::OBJECT_TYPE => AppValue::Enumerated(ObjectType::Device.code().into()),
+            prop::DESCRIPTION => AppValue::CharacterString(self.device.description.clone()?),
+            // Operational.
+            prop::SYSTEM_STATUS => AppValue::Enumerated(0),
+            prop::VENDOR_NAME => AppValue::CharacterString(self.device.vendor_name.clone()),
+            prop::VENDOR_IDENTIFIER => AppValue::Unsigned(self.device.vendor_id.into()),
+            prop::MODEL_NAME => AppValue::CharacterString(self.device.model_name.clone()),
+            prop::FIRMWARE_REVISION | prop::APPLICATION_SOFTWARE_VERSION => {
+                AppValue::CharacterString(env!("CARGO_PKG_VERSION").into())
+            }
+            prop::PROTOCOL_VERSION => AppValue::Unsigned(1),
+            prop::PROTOCOL_REVISION => AppValue::Unsigned(PROTOCOL_REVISION),
+            prop::PROTOCOL_SERVICES_SUPPORTED => {
+                bit_string(SERVICES_SUPPORTED_BITS, &SERVICES_SUPPORTED)
+            }
+            prop::PROTOCOL_OBJECT_TYPES_SUPPORTED => {
+                let types = [
+                    ObjectType::AnalogValue,
+                    ObjectType::BinaryValue,
+                    ObjectType::Device,
+                    ObjectType::MultiStateValue,
+                ]
+                .map(|object_type| usize::from(object_type.code()));
+                bit_string(OBJECT_TYPES_SUPPORTED_BITS, &types)
+            }
+            prop::OBJECT_LIST => {
+                return Some(
+                    std::iter::once(self.device_id())
+                        .chain(self.objects.iter().map(|object| object.id))
+                        .map(AppValue::ObjectId)
+                        .collect(),
+                );
+            }
+            prop::MAX_APDU_LENGTH_ACCEPTED => AppValue::Unsigned(MAX_APDU as u32),
+            prop::SEGMENTATION_SUPPORTED => AppValue::Enumerated(NO_SEGMENTATION),
+            prop::APDU_TIMEOUT => AppValue::Unsigned(3000),
+            prop::NUMBER_OF_APDU_RETRIES => AppValue::Unsigned(3),
+            prop::DEVICE_ADDRESS_BINDING => return Some(vec![]),
+            prop::DATABASE_REVISION => AppValue::Unsigned(1),
+            prop::PROPERTY_LIST => return Some(self.property_list(Target::Device)),
+            _ => return None,
+        };
+        Some(vec![value])
+    }
+
+    /// The property list property: every property but the identifier,
+    /// name, type and the list itself.
+    fn property_list(&self, target: Target) -> Vec<AppValue> {
+        self.properties(target)
+            .into_iter()
+            .filter(|property| {
+                !matches!(
+                    *property,
+                    prop::OBJECT_IDENTIFIER
+                        | prop::OBJECT_NAME
+                        | prop::OBJECT_TYPE
+                        | prop::PROPERTY_LIST
+                )
+            })
+            .map(AppValue::Enumerated)
+            .collect()
+    }
+
+    async fn object_property(
+        &mut self,
+        inspected: &mut Inspected,
+        index: usize,
+        property: u32,
+    ) -> Result<Option<Vec<AppValue>>, ErrorCode> {
+        if !self.properties(Target::Object(index)).contains(&property) {
+            return Ok(None);
+        }
+
+        let object = &self.objects[index];
+        let mapping = &object.mapping;
+        let value = match property {
+            prop::OBJECT_IDENTIFIER => AppValue::ObjectId(object.id),
+            prop::OBJECT_NAME => AppValue::CharacterString(mapping.name.clone()),
+            prop::OBJECT_TYPE => AppValue::Enumerated(object.id.object_type.into()),
+            prop::DESCRIPTION => {
+                AppValue::CharacterString(mapping.description.clone().unwrap_or_default())
+            }
+            // Normal.
+            prop::EVENT_STATE => AppValue::Enumerated(0),
+            prop::OUT_OF_SERVICE => AppValue::Boolean(false),
+            prop::COV_INCREMENT => AppValue::Real(mapping.cov_increment as f32),
+            prop::NUMBER_OF_STATES => AppValue::Unsigned(mapping.states.len() as u32),
+            prop::STATE_TEXT => {
+                return Ok(Some(
+                    mapping
+                        .states
+                        .iter()
+                        .cloned()
+                        .map(AppValue::CharacterString)
+                        .collect(),
+                ));
+            }
+            prop::PRIORITY_ARRAY => {
+                return Ok(Some(
+                    (1..=PRIORITIES)
+                        .map(|priority| {
+                            object
+                                .priority
+                                .as_ref()
+                                .and_then(|array| array.level(priority).copied())
+                                .map_or(AppValue::Null, |value| mapping.to_app(value))
+                        })
+                        .collect(),
+                ));
+            }
+            prop::RELINQUISH_DEFAULT
+                if object.priority.is_some() || mapping.relinquish_default.is_some() =>
+            {
+                let default = object
+                    .priority
+                    .as_ref()
+                    .map(|array| array.relinquish_default)
+                    .or(mapping.relinquish_default)
+                    .unwrap_or_default();
+                mapping.to_app(default)
+            }
+            prop::PROPERTY_LIST => return Ok(Some(self.property_list(Target::Object(index)))),
+            _ => {
+                let pin = self.engine.pin(inspected, object).await?;
+                let mapping = &self.objects[index].mapping;
+                match property {
+                    prop::PRESENT_VALUE => mapping.present_value(&pin.value),
+                    prop::STATUS_FLAGS => status_flags(pin.fault),
+                    prop::UNITS => AppValue::Enumerated(mapping.engineering_units(&pin.value)),
+                    // The relinquish default before the first command.
+                    _ => mapping.present_value(&pin.value),
+                }
+            }
+        };
+        Ok(Some(vec![value]))
+    }
+
+    async fn write(&mut self, request: WriteProperty) -> Outcome {
+        let index = match self.target(request.object) {
+            Some(Target::Object(index)) => index,
+            Some(Target::Device) => return Outcome::Error(ErrorCode::WRITE_ACCESS_DENIED),
+            None => return Outcome::Error(ErrorCode::UNKNOWN_OBJECT),
+        };
+        let property = request.property.property;
+        let commandable = self.objects[index].mapping.commandable;
+        if !matches!(property, prop::PRESENT_VALUE | prop::RELINQUISH_DEFAULT) || !commandable {
+            return Outcome::Error(
+                match self.properties(Target::Object(index)).contains(&property) {
+                    true => ErrorCode::WRITE_ACCESS_DENIED,
+                    false => ErrorCode::UNKNOWN_PROPERTY,
+                },
+            );
+        }
+        if request.property.index.is_some() {
+            return Outcome::Error(ErrorCode::PROPERTY_IS_NOT_AN_ARRAY);
+        }
+        let [value] = request.values.as_slice() else {
+            return Outcome::Error(ErrorCode::INVALID_DATA_TYPE);
+        };
+        let priority = request.priority.unwrap_or(PRIORITIES as u32) as usize;
+        if !(1..=PRIORITIES).contains(&priority) {
+            return Outcome::Error(ErrorCode::VALUE_OUT_OF_RANGE);
+        }
+
+        let object = &self.objects[index];
+        let value = match (property, value) {
+            (prop::PRESENT_VALUE, AppValue::Null) => None,
+            _ => match object.mapping.engineering_value(value) {
+                Ok(value) => Some(value),
+                Err(error) => return Outcome::Error(error),
+            },
+        };
+        let pin = match self.engine.pin(&mut Inspected::new(), object).await {
+            Ok(pin) => pin,
+            Err(error) => return Outcome::Error(error),
+        };
+
+        let object = &mut self.objects[index];
+        let array = object.priority.get_or_insert_with(|| {
+            PriorityArray::new(
+                object
+                    .mapping
+                    .relinquish_default
+                    .unwrap_or_else(|| engineering(&pin.value)),
+            )
+        });
+        match (property, value) {
+            (prop::PRESENT_VALUE, value) => {
+                array.write(priority, value);
+            }
+            (_, Some(value)) => array.relinquish_default = value,
+            (_, None) => return Outcome::Error(ErrorCode::INVALID_DATA_TYPE),
+        }
+
+        let value = object.mapping.to_pin(*array.effective(), &pin.value);
+        match self
+            .engine
+            .write_pin(object.block, &object.mapping.pin, value)
+            .await
+        {
+            Ok(()) => Outcome::SimpleAck,
+            Err(error) => Outcome::Error(error),
+        }
+    }
+
+    fn subscribe(&mut self, request: SubscribeCov, datagram: &Datagram) -> Outcome {
+        let index = match self.target(request.object) {
+            Some(Target::Object(index)) => index,
+            Some(Target::Device) => {
+                return Outcome::Error(ErrorCode::OPTIONAL_FUNCTIONALITY_NOT_SUPPORTED);
+            }
+            None => return Outcome::Error(ErrorCode::UNKNOWN_OBJECT),
+        };
+
+        let subscriptions = &mut self.objects[index].subscriptions;
+        subscriptions.retain(|subscription| {
+            subscription.reply_to != datagram.reply_to
+                || subscription.route != datagram.source
+                || subscription.process != request.process
+        });
+        if request.is_cancellation() {
+            return Outcome::SimpleAck;
+        }
+
+        subscriptions.push(Subscription {
+            reply_to: datagram.reply_to,
+            route: datagram.source.clone(),
+            process: request.process,
+            confirmed: request.confirmed.unwrap_or(false),
+            expires: request
+                .lifetime
+                .filter(|lifetime| *lifetime > 0)
+                .map(|lifetime| Instant::now() + Duration::from_secs(lifetime.into())),
+        });
+        Outcome::Subscribed(index)
+    }
+
+    /// Notifies the subscribers of the objects on the changed block.
+    async fn on_change(&mut self, socket: &UdpSocket, change: WatchMessage) {
+        let fault = change.state.is_fault();
+        let now = Instant::now();
+        for index in 0..self.objects.len() {
+            let object = &mut self.objects[index];
+            if object.block != change.block_id {
+                continue;
+            }
+            let value = match change.changes.get(&object.mapping.pin) {
+                Some(ChangeSource::Input(_, value) | ChangeSource::Output(_, value)) => {
+                    value.clone()
+                }
+                None => match &object.pin {
+                    Some(pin) => pin.value.clone(),
+                    None => continue,
+                },
+            };
+            object.pin = Some(Pin {
+                value: value.clone(),
+                fault,
+            });
+
+            object
+                .subscriptions
+                .retain(|subscription| subscription.expires.is_none_or(|expires| expires > now));
+            if object.subscriptions.is_empty() {
+                continue;
+            }
+
+            let present_value = object.mapping.present_value(&value);
+            let changed = match &object.notified {
+                None => true,
+                Some((notified, was_fault)) => {
+                    *was_fault != fault
+                        || significant(notified, &present_value, object.mapping.cov_increment)
+                }
+            };
+            if changed {
+                object.notified = Some((present_value.clone(), fault));
+                self.notify(socket, index, None, (present_value, fault))
+                    .await;
+            }
+        }
+    }
+
+    /// The object's present value and fault state, read from the engine
+    /// unless a change was seen already.
+    async fn snapshot(&mut self, index: usize) -> Option<(AppValue, bool)> {
+        let object = &self.objects[index];
+        let pin = match &object.pin {
+            Some(pin) => pin.clone(),
+            None => {
+                let pin = self.engine.pin(&mut Inspected::new(), object).await.ok()?;
+                self.objects[index].pin = Some(pin.clone());
+                pin
+            }
+        };
+        let object = &self.objects[index];
+        Some((object.mapping.present_value(&pin.value), pin.fault))
+    }
+
+    /// Notifies one subscriber of the object, or all of them.
+    async fn notify(
+        &mut self,
+        socket: &UdpSocket,
+        index: usize,
+        subscriber: Option<usize>,
+        (present_value, fault): (AppValue, bool),
+    ) {
+        let now = Instant::now();
+        let device = self.device_id();
+        let object = &self.objects[index];
+        let values = vec![
+            (prop::PRESENT_VALUE, vec![present_value]),
+            (prop::STATUS_FLAGS, vec![status_flags(fault)]),
+        ];
+
+        let mut messages = Vec::new();
+        for (position, subscription) in object.subscriptions.iter().enumerate() {
+            if subscriber.is_some_and(|subscriber| subscriber != position) {
+                continue;
+            }
+            let data = CovNotification {
+                process: subscription.process,
+                device,
+                object: object.id,
+                time_remaining: subscription.expires.map_or(0, |expires| {
+                    expires.saturating_duration_since(now).as_secs() as u32
+                }),
+                values: values.clone(),
+            }
+            .encode();
+            messages.push((
+                subscription.reply_to,
+                subscription.route.clone(),
+                subscription.confirmed,
+                data,
+            ));
+        }
+
+        for (reply_to, route, is_confirmed, data) in messages {
+            // Confirmed notifications are not retried; their
+            // acknowledgements are ignored.
+            let apdu = match is_confirmed {
+                true => {
+                    self.invoke_id = self.invoke_id.wrapping_add(1);
+                    Apdu::ConfirmedRequest {
+                        segmented: false,
+                        max_apdu: MAX_APDU,
+                        invoke_id: self.invoke_id,
+                        service: confirmed::CONFIRMED_COV_NOTIFICATION,
+                        data,
+                    }
+                }
+                false => Apdu::UnconfirmedRequest {
+                    service: unconfirmed::UNCONFIRMED_COV_NOTIFICATION,
+                    data,
+                },
+            };
+            send(socket, reply_to, route.as_ref(), &apdu).await;
+        }
+    }
+}
+
+impl EngineLink {
+    /// The object's pin, from the block inspected once per request.
+    async fn pin(&mut self, inspected: &mut Inspected, object: &Object) -> Result<Pin, ErrorCode> {
+        if let Entry::Vacant(entry) = inspected.entry(object.block) {
+            match self
+                .exchange(EngineMessage::InspectBlockReq(self.id, object.block))
+                .await
+            {
+                Some(EngineMessage::InspectBlockRes(Ok(def))) => entry.insert(def),
+                _ => return Err(ErrorCode::OPERATIONAL_PROBLEM),
+            };
+        }
+
+        let def = &inspected[&object.block];
+        let pin = &object.mapping.pin;
+        let value = def
+            .outputs
+            .get(pin)
+            .map(|output| &output.val)
+            .or_else(|| def.inputs.get(pin).map(|input| &input.val))
+            .ok_or(ErrorCode::OPERATIONAL_PROBLEM)?;
+        Ok(Pin {
+            value: value.clone(),
+            fault: def.state == "fault",
+        })
+    }
+
+    /// Writes an input of the block, or an output if it has no such
+    /// input.
+    async fn write_pin(&mut self, block: Uuid, pin: &str, value: Value) -> Result<(), ErrorCode> {
+        let input = EngineMessage::WriteBlockInputReq(self.id, block, pin.into(), value.clone());
+        if let Some(EngineMessage::WriteBlockInputRes(Ok(_))) = self.exchange(input).await {
+            return Ok(());
+        }
+        let output = EngineMessage::WriteBlockOutputReq(self.id, block, pin.into(), value);
+        match self.exchange(output).await {
+            Some(EngineMessage::WriteBlockOutputRes(Ok(_))) => Ok(()),
+            _ => Err(ErrorCode::OPERATIONAL_PROBLEM),
+        }
+    }
+
+    /// Sends a request and waits for its reply, or [`None`] once the
+    /// engine has stopped.
+    async fn exchange(&mut self, msg: Messages) -> Option<Messages> {
+        self.sender.send(msg).await.ok()?;
+        self.replies.recv().await
+    }
+}
+
+/// Whether a present value moved enough to be notified.
+fn significant(notified: &AppValue, current: &AppValue, increment: f64) -> bool {
+    match (notified, current) {
+        (AppValue::Real(notified), AppValue::Real(current)) if increment > 0.0 => {
+            f64::from((current - notified).abs()) >= increment
+        }
+        _ => notified != current,
+    }
+}
+
+/// Status flags: in alarm, fault, overridden and out of service.
+fn status_flags(fault: bool) -> AppValue {
+    AppValue::BitString(vec![false, fault, false, false])
+}
+
+fn bit_string(len: usize, set: &[usize]) -> AppValue {
+    AppValue::BitString((0..len).map(|bit| set.contains(&bit)).collect())
+}
+
+async fn send(socket: &UdpSocket, to: SocketAddr, route: Option<&Route>, apdu: &Apdu) {
+    let expecting_reply = matches!(apdu, Apdu::ConfirmedRequest { .. });
+    let datagram = encode_datagram(&apdu.encode(), false, route, expecting_reply);
+    if let Err(err) = socket.send_to(&datagram, to).await {
+        log::debug!("BACnet send to {to} failed: {err}");
+    }
+}
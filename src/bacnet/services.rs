@@ -0,0 +1,426 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Service requests and acknowledgements. The device decodes requests
+//! and encodes answers; the reverse halves serve the tests' client.
+
+use super::apdu::ErrorCode;
+#[cfg(test)]
+use super::encoding::context_bool;
+use super::encoding::{
+    self, AppValue, ObjectId, Reader, Result, context_object_id, context_unsigned,
+};
+
+/// A property of an object, optionally one element of an array.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct PropertyRef {
+    pub(super) property: u32,
+    pub(super) index: Option<u32>,
+}
+
+impl PropertyRef {
+    #[cfg(test)]
+    pub(super) fn new(property: u32) -> Self {
+        Self {
+            property,
+            index: None,
+        }
+    }
+}
+
+/// Who-Is, optionally limited to a range of device instances.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct WhoIs {
+    pub(super) range: Option<(u32, u32)>,
+}
+
+impl WhoIs {
+    #[cfg(test)]
+    pub(super) fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        if let Some((low, high)) = self.range {
+            context_unsigned(&mut buf, 0, low);
+            context_unsigned(&mut buf, 1, high);
+        }
+        buf
+    }
+
+    pub(super) fn decode(data: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(data);
+        if reader.is_empty() {
+            return Ok(Self { range: None });
+        }
+        Ok(Self {
+            range: Some((reader.context_unsigned(0)?, reader.context_unsigned(1)?)),
+        })
+    }
+}
+
+/// I-Am.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct IAm {
+    pub(super) device: ObjectId,
+    pub(super) max_apdu: u32,
+    pub(super) segmentation: u32,
+    pub(super) vendor: u32,
+}
+
+impl IAm {
+    pub(super) fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        AppValue::ObjectId(self.device).encode(&mut buf);
+        AppValue::Unsigned(self.max_apdu).encode(&mut buf);
+        AppValue::Enumerated(self.segmentation).encode(&mut buf);
+        AppValue::Unsigned(self.vendor).encode(&mut buf);
+        buf
+    }
+
+    #[cfg(test)]
+    pub(super) fn decode(data: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(data);
+        match (reader.app()?, reader.app()?, reader.app()?, reader.app()?) {
+            (
+                AppValue::ObjectId(device),
+                AppValue::Unsigned(max_apdu),
+                AppValue::Enumerated(segmentation),
+                AppValue::Unsigned(vendor),
+            ) => Ok(Self {
+                device,
+                max_apdu,
+                segmentation,
+                vendor,
+            }),
+            _ => Err(encoding::Malformed("bad I-Am")),
+        }
+    }
+}
+
+/// ReadProperty request.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct ReadProperty {
+    pub(super) object: ObjectId,
+    pub(super) property: PropertyRef,
+}
+
+impl ReadProperty {
+    #[cfg(test)]
+    pub(super) fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        context_object_id(&mut buf, 0, self.object);
+        encode_property(&mut buf, 1, self.property);
+        buf
+    }
+
+    pub(super) fn decode(data: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(data);
+        Ok(Self {
+            object: reader.context_object_id(0)?,
+            property: decode_property(&mut reader, 1)?,
+        })
+    }
+}
+
+/// ReadProperty acknowledgement.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct ReadPropertyAck {
+    pub(super) object: ObjectId,
+    pub(super) property: PropertyRef,
+    pub(super) values: Vec<AppValue>,
+}
+
+impl ReadPropertyAck {
+    pub(super) fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        context_object_id(&mut buf, 0, self.object);
+        encode_property(&mut buf, 1, self.property);
+        encode_values(&mut buf, 3, &self.values);
+        buf
+    }
+
+    #[cfg(test)]
+    pub(super) fn decode(data: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(data);
+        let object = reader.context_object_id(0)?;
+        let property = decode_property(&mut reader, 1)?;
+        reader.opening(3)?;
+        Ok(Self {
+            object,
+            property,
+            values: reader.values_until_closing(3)?,
+        })
+    }
+}
+
+/// The properties to read of one object.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct ReadAccessSpec {
+    pub(super) object: ObjectId,
+    pub(super) properties: Vec<PropertyRef>,
+}
+
+/// ReadPropertyMultiple request.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct ReadPropertyMultiple {
+    pub(super) specs: Vec<ReadAccessSpec>,
+}
+
+impl ReadPropertyMultiple {
+    #[cfg(test)]
+    pub(super) fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        for spec in &self.specs {
+            context_object_id(&mut buf, 0, spec.object);
+            encoding::opening(&mut buf, 1);
+            for property in &spec.properties {
+                encode_property(&mut buf, 0, *property);
+            }
+            encoding::closing(&mut buf, 1);
+        }
+        buf
+    }
+
+    pub(super) fn decode(data: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(data);
+        let mut specs = Vec::new();
+        while !reader.is_empty() {
+            let object = reader.context_object_id(0)?;
+            reader.opening(1)?;
+            let mut properties = Vec::new();
+            while !reader.at_closing(1) {
+                properties.push(decode_property(&mut reader, 0)?);
+            }
+            reader.closing(1)?;
+            specs.push(ReadAccessSpec { object, properties });
+        }
+        if specs.is_empty() {
+            return Err(encoding::Malformed("nothing to read"));
+        }
+        Ok(Self { specs })
+    }
+}
+
+/// The outcome of reading one property.
+pub(super) type ReadResult = std::result::Result<Vec<AppValue>, ErrorCode>;
+
+/// ReadPropertyMultiple acknowledgement.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct ReadPropertyMultipleAck {
+    pub(super) results: Vec<(ObjectId, Vec<(PropertyRef, ReadResult)>)>,
+}
+
+impl ReadPropertyMultipleAck {
+    pub(super) fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        for (object, results) in &self.results {
+            context_object_id(&mut buf, 0, *object);
+            encoding::opening(&mut buf, 1);
+            for (property, result) in results {
+                encode_property(&mut buf, 2, *property);
+                match result {
+                    Ok(values) => encode_values(&mut buf, 4, values),
+                    Err(error) => {
+                        encoding::opening(&mut buf, 5);
+                        AppValue::Enumerated(error.class).encode(&mut buf);
+                        AppValue::Enumerated(error.code).encode(&mut buf);
+                        encoding::closing(&mut buf, 5);
+                    }
+                }
+            }
+            encoding::closing(&mut buf, 1);
+        }
+        buf
+    }
+
+    #[cfg(test)]
+    pub(super) fn decode(data: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(data);
+        let mut results = Vec::new();
+        while !reader.is_empty() {
+            let object = reader.context_object_id(0)?;
+            reader.opening(1)?;
+            let mut properties = Vec::new();
+            while !reader.at_closing(1) {
+                let property = decode_property(&mut reader, 2)?;
+                let result = if reader.at_context(4) {
+                    reader.opening(4)?;
+                    Ok(reader.values_until_closing(4)?)
+                } else {
+                    reader.opening(5)?;
+                    let values = reader.values_until_closing(5)?;
+                    match values.as_slice() {
+                        [AppValue::Enumerated(class), AppValue::Enumerated(code)] => {
+                            Err(ErrorCode {
+                                class: *class,
+                                code: *code,
+                            })
+                        }
+                        _ => return Err(encoding::Malformed("bad property access error")),
+                    }
+                };
+                properties.push((property, result));
+            }
+            reader.closing(1)?;
+            results.push((object, properties));
+        }
+        Ok(Self { results })
+    }
+}
+
+/// WriteProperty request.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct WriteProperty {
+    pub(super) object: ObjectId,
+    pub(super) property: PropertyRef,
+    pub(super) values: Vec<AppValue>,
+    pub(super) priority: Option<u32>,
+}
+
+impl WriteProperty {
+    #[cfg(test)]
+    pub(super) fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        context_object_id(&mut buf, 0, self.object);
+        encode_property(&mut buf, 1, self.property);
+        encode_values(&mut buf, 3, &self.values);
+        if let Some(priority) = self.priority {
+            context_unsigned(&mut buf, 4, priority);
+        }
+        buf
+    }
+
+    pub(super) fn decode(data: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(data);
+        let object = reader.context_object_id(0)?;
+        let property = decode_property(&mut reader, 1)?;
+        reader.opening(3)?;
+        let values = reader.values_until_closing(3)?;
+        Ok(Self {
+            object,
+            property,
+            values,
+            priority: reader.optional_unsigned(4)?,
+        })
+    }
+}
+
+/// SubscribeCOV request. Without `confirmed` and `lifetime` it cancels
+/// the subscription.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct SubscribeCov {
+    pub(super) process: u32,
+    pub(super) object: ObjectId,
+    pub(super) confirmed: Option<bool>,
+    /// Seconds; `0` for an indefinite subscription.
+    pub(super) lifetime: Option<u32>,
+}
+
+impl SubscribeCov {
+    #[cfg(test)]
+    pub(super) fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        context_unsigned(&mut buf, 0, self.process);
+        context_object_id(&mut buf, 1, self.object);
+        if let Some(confirmed) = self.confirmed {
+            context_bool(&mut buf, 2, confirmed);
+        }
+        if let Some(lifetime) = self.lifetime {
+            context_unsigned(&mut buf, 3, lifetime);
+        }
+        buf
+    }
+
+    pub(super) fn decode(data: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(data);
+        let process = reader.context_unsigned(0)?;
+        let object = reader.context_object_id(1)?;
+        let confirmed = match reader.at_context(2) {
+            true => Some(reader.context_bool(2)?),
+            false => None,
+        };
+        Ok(Self {
+            process,
+            object,
+            confirmed,
+            lifetime: reader.optional_unsigned(3)?,
+        })
+    }
+
+    pub(super) fn is_cancellation(&self) -> bool {
+        self.confirmed.is_none() && self.lifetime.is_none()
+    }
+}
+
+/// A COV notification, confirmed or not.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct CovNotification {
+    pub(super) process: u32,
+    pub(super) device: ObjectId,
+    pub(super) object: ObjectId,
+    /// Seconds left of the subscription; `0` if indefinite.
+    pub(super) time_remaining: u32,
+    pub(super) values: Vec<(u32, Vec<AppValue>)>,
+}
+
+impl CovNotification {
+    pub(super) fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        context_unsigned(&mut buf, 0, self.process);
+        context_object_id(&mut buf, 1, self.device);
+        context_object_id(&mut buf, 2, self.object);
+        context_unsigned(&mut buf, 3, self.time_remaining);
+        encoding::opening(&mut buf, 4);
+        for (property, values) in &self.values {
+            context_unsigned(&mut buf, 0, *property);
+            encode_values(&mut buf, 2, values);
+        }
+        encoding::closing(&mut buf, 4);
+        buf
+    }
+
+    #[cfg(test)]
+    pub(super) fn decode(data: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(data);
+        let process = reader.context_unsigned(0)?;
+        let device = reader.context_object_id(1)?;
+        let object = reader.context_object_id(2)?;
+        let time_remaining = reader.context_unsigned(3)?;
+        reader.opening(4)?;
+        let mut values = Vec::new();
+        while !reader.at_closing(4) {
+            let property = reader.context_unsigned(0)?;
+            reader.optional_unsigned(1)?;
+            reader.opening(2)?;
+            values.push((property, reader.values_until_closing(2)?));
+            reader.optional_unsigned(3)?;
+        }
+        reader.closing(4)?;
+        Ok(Self {
+            process,
+            device,
+            object,
+            time_remaining,
+            values,
+        })
+    }
+}
+
+/// Writes a property identifier as context tag `tag`, followed by its
+/// array index as tag `tag + 1`.
+fn encode_property(buf: &mut Vec<u8>, tag: u8, property: PropertyRef) {
+    context_unsigned(buf, tag, property.property);
+    if let Some(index) = property.index {
+        context_unsigned(buf, tag + 1, index);
+    }
+}
+
+fn decode_property(reader: &mut Reader, tag: u8) -> Result<PropertyRef> {
+    Ok(PropertyRef {
+        property: reader.context_unsigned(tag)?,
+        index: reader.optional_unsigned(tag + 1)?,
+    })
+}
+
+fn encode_values(buf: &mut Vec<u8>, tag: u8, values: &[AppValue]) {
+    encoding::opening(buf, tag);
+    values.iter().for_each(|value| value.encode(buf));
+    encoding::closing(buf, tag);
+}
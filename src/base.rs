@@ -2,14 +2,20 @@
 
 //! Basic building blocks of the engine.
 
+pub mod alarm;
 pub mod block;
 pub mod engine;
 pub mod error;
+pub mod history;
 pub mod input;
 pub mod link;
 pub mod output;
+pub mod priority;
 pub mod program;
 pub mod status;
 
-pub use error::{EngineError, Error, ExternalError, LinkEnd, RegistryError, Result, ValueError};
+pub use error::{
+    AlarmError, EngineError, Error, ExternalError, HistoryError, LinkEnd, ProgramError,
+    RegistryError, Result, ValueError,
+};
 pub use status::Status;
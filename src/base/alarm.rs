@@ -0,0 +1,734 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Operator-facing alarms.
+//!
+//! The blocks of the `alarm` category decide when a condition is in
+//! alarm, applying their limits, deadband and delays, and report it on
+//! their `out` pin. An [`AlarmTable`] turns those reports into alarms an
+//! operator works with: an alarm is raised [`Active`](AlarmState::Active),
+//! is [`Acknowledged`](AlarmState::Acknowledged) by the operator and
+//! [`Cleared`](AlarmState::Cleared) when its condition returns to normal,
+//! in either order, and leaves the table once it is both. A shelved
+//! source keeps being tracked but does not announce its alarms until the
+//! shelving expires.
+//!
+//! An engine given a table through its `set_alarms` method registers its
+//! alarm blocks as sources, seeds their priority and message from the
+//! blocks' inputs, feeds the table from the blocks' change-of-value
+//! notifications, and answers
+//! [`AlarmReq`](crate::base::engine::messages::EngineMessage::AlarmReq)
+//! requests from it. Every transition is sent as an [`AlarmEvent`] to
+//! the subscribers of the alarm stream.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::collections::HashMap;
+//!
+//! use logic_mesh::base::alarm::{AlarmState, AlarmTable};
+//! use logic_mesh::base::block::BlockState;
+//! use logic_mesh::base::engine::messages::{ChangeSource, WatchMessage};
+//! use logic_mesh::Uuid;
+//!
+//! let table = AlarmTable::new();
+//! let source = Uuid::new_v4();
+//! table.watch(source, "HighLimitAlarm");
+//!
+//! let report = |active: bool| WatchMessage {
+//!     block_id: source,
+//!     changes: HashMap::from([(
+//!         "out".to_string(),
+//!         ChangeSource::Output("out".to_string(), active.into()),
+//!     )]),
+//!     state: BlockState::Running,
+//! };
+//!
+//! table.record(&report(true), 1_000);
+//! assert_eq!(table.alarms()[0].state, AlarmState::Active);
+//!
+//! table.acknowledge(&source, 2_000).unwrap();
+//! table.record(&report(false), 3_000);
+//! assert!(table.alarms().is_empty());
+//! ```
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use libhaystack::val::Value;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::base::engine::messages::{ChangeSource, WatchMessage};
+use crate::base::error::AlarmError;
+
+/// Category of the blocks an engine registers as alarm sources.
+pub const ALARM_CATEGORY: &str = "alarm";
+
+/// Priority of the alarms of a source whose `priority` input is not set.
+pub const DEFAULT_PRIORITY: u8 = 100;
+
+/// The inputs of an alarm block that set the priority and message of its
+/// alarms.
+pub const ALARM_INPUTS: [&str; 2] = ["priority", "message"];
+
+/// Where an alarm is in its lifecycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AlarmState {
+    /// In alarm and not yet acknowledged.
+    Active,
+    /// In alarm and acknowledged by an operator.
+    Acknowledged,
+    /// Returned to normal but not yet acknowledged.
+    Cleared,
+}
+
+/// An alarm raised by a source block.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Alarm {
+    /// The UUID of the alarm block.
+    pub source: Uuid,
+    /// The alarm block's type name, such as `HighLimitAlarm`.
+    pub kind: String,
+    /// The text of the block's `message` input.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub message: String,
+    /// The alarm's priority, 0 being the most urgent.
+    pub priority: u8,
+    /// Where the alarm is in its lifecycle.
+    pub state: AlarmState,
+    /// When the alarm was raised, in milliseconds since the Unix epoch.
+    pub raised_at: u64,
+    /// When the alarm was acknowledged.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub acked_at: Option<u64>,
+    /// When the alarm's condition returned to normal.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cleared_at: Option<u64>,
+    /// Until when the source is shelved.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shelved_until: Option<u64>,
+}
+
+/// What happened to an alarm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AlarmTransition {
+    /// The source went into alarm.
+    Raised,
+    /// An operator acknowledged the alarm.
+    Acknowledged,
+    /// The source returned to normal.
+    Cleared,
+    /// An operator shelved the source.
+    Shelved,
+    /// The source's shelving was lifted or expired.
+    Unshelved,
+    /// The source block was removed from the engine.
+    Removed,
+}
+
+/// A notification of the alarm stream.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlarmEvent {
+    /// When the transition happened, in milliseconds since the Unix epoch.
+    pub ts: u64,
+    /// The UUID of the alarm block.
+    pub source: Uuid,
+    /// What happened.
+    pub transition: AlarmTransition,
+    /// The alarm after the transition, also when it left the table with
+    /// it, or [`None`] if the source has no alarm.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alarm: Option<Alarm>,
+}
+
+/// An operator request on an alarm source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AlarmAction {
+    /// Acknowledges the source's alarm.
+    Acknowledge,
+    /// Shelves the source for the given number of milliseconds.
+    Shelve(u64),
+    /// Lifts the source's shelving.
+    Unshelve,
+}
+
+/// The alarms of an engine's alarm blocks, and the subscribers to their
+/// transitions.
+///
+/// Cloning a table gives another handle to the same alarms.
+#[derive(Clone, Default)]
+pub struct AlarmTable {
+    inner: Arc<Mutex<Inner>>,
+}
+
+type Listener = Arc<Mutex<dyn FnMut(&AlarmEvent) -> bool + Send>>;
+
+#[derive(Default)]
+struct Inner {
+    sources: BTreeMap<Uuid, Source>,
+    alarms: BTreeMap<Uuid, Alarm>,
+    listeners: BTreeMap<Uuid, Listener>,
+    /// Transitions waiting to be sent to the listeners.
+    pending: VecDeque<AlarmEvent>,
+    /// Whether a caller is sending the pending transitions.
+    delivering: bool,
+}
+
+struct Source {
+    kind: String,
+    priority: u8,
+    message: String,
+    /// The last reported state of the block's `out` pin.
+    active: bool,
+    shelved_until: Option<u64>,
+}
+
+impl Source {
+    fn configure(&mut self, pin: &str, value: &Value) {
+        match (pin, value) {
+            ("priority", Value::Number(number)) => {
+                self.priority = number.value.clamp(0.0, u8::MAX as f64) as u8;
+            }
+            ("message", Value::Str(text)) => self.message = text.value.clone(),
+            _ => {}
+        }
+    }
+}
+
+impl AlarmTable {
+    /// Creates a table with no sources.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the alarm block `source`, of the type `kind`.
+    pub fn watch(&self, source: Uuid, kind: impl Into<String>) {
+        self.lock().sources.entry(source).or_insert_with(|| Source {
+            kind: kind.into(),
+            priority: DEFAULT_PRIORITY,
+            message: String::new(),
+            active: false,
+            shelved_until: None,
+        });
+    }
+
+    /// Removes the source and its alarm. Returns `false` if it was not
+    /// registered.
+    pub fn forget(&self, source: &Uuid, now: u64) -> bool {
+        self.update(|inner| {
+            if inner.sources.remove(source).is_none() {
+                return false;
+            }
+            let alarm = inner.alarms.remove(source);
+            inner.notify(now, *source, AlarmTransition::Removed, alarm);
+            true
+        })
+    }
+
+    /// The alarms in the table, most urgent first and then oldest first.
+    pub fn alarms(&self) -> Vec<Alarm> {
+        let mut alarms: Vec<Alarm> = self.lock().alarms.values().cloned().collect();
+        alarms.sort_by_key(|alarm| (alarm.priority, alarm.raised_at));
+        alarms
+    }
+
+    /// The alarm of `source`, if it has one.
+    pub fn alarm(&self, source: &Uuid) -> Option<Alarm> {
+        self.lock().alarms.get(source).cloned()
+    }
+
+    /// Sends every transition to `listener` until it returns `false` or
+    /// is unsubscribed. Replaces an earlier listener with the same id.
+    ///
+    /// Listeners run outside the table's lock, so they may call back into
+    /// the table. Transitions are sent in the order they happen, each
+    /// by whichever caller is already sending, which may be on another
+    /// thread than the one that caused it.
+    pub fn subscribe(&self, id: Uuid, listener: impl FnMut(&AlarmEvent) -> bool + Send + 'static) {
+        self.lock()
+            .listeners
+            .insert(id, Arc::new(Mutex::new(listener)));
+    }
+
+    /// Stops sending transitions to the listener `id`. Returns `false` if
+    /// it was not subscribed.
+    pub fn unsubscribe(&self, id: &Uuid) -> bool {
+        self.lock().listeners.remove(id).is_some()
+    }
+
+    /// Sets the priority or message of `source` from the value of its
+    /// block's input `pin`, such as a constant the engine wrote before
+    /// the block reported it. Other pins and unregistered sources are
+    /// ignored.
+    pub fn configure(&self, source: &Uuid, pin: &str, value: &Value) {
+        if let Some(source) = self.lock().sources.get_mut(source) {
+            source.configure(pin, value);
+        }
+    }
+
+    /// Updates the sources from a change notification, raising or
+    /// clearing their alarms as their `out` pin changes.
+    pub fn record(&self, msg: &WatchMessage, now: u64) {
+        self.update(|inner| {
+            let Some(source) = inner.sources.get_mut(&msg.block_id) else {
+                return;
+            };
+
+            let mut active = None;
+            for change in msg.changes.values() {
+                match change {
+                    ChangeSource::Input(pin, value) => source.configure(pin, value),
+                    ChangeSource::Output(pin, value) if pin == "out" => {
+                        active = Some(matches!(value, Value::Bool(active) if active.value));
+                    }
+                    _ => {}
+                }
+            }
+
+            match active {
+                Some(active) if active != source.active => {
+                    source.active = active;
+                    if active {
+                        inner.raise(msg.block_id, now);
+                    } else {
+                        inner.clear(msg.block_id, now);
+                    }
+                }
+                _ => {}
+            }
+        })
+    }
+
+    /// Acknowledges the alarm of `source`. Acknowledging an acknowledged
+    /// alarm does nothing.
+    pub fn acknowledge(&self, source: &Uuid, now: u64) -> Result<(), AlarmError> {
+        self.update(|inner| {
+            let alarm = inner
+                .alarms
+                .get_mut(source)
+                .ok_or(AlarmError::NotFound(*source))?;
+
+            match alarm.state {
+                AlarmState::Acknowledged => return Ok(()),
+                AlarmState::Active => alarm.state = AlarmState::Acknowledged,
+                AlarmState::Cleared => {}
+            }
+            alarm.acked_at = Some(now);
+
+            let alarm = if alarm.state == AlarmState::Cleared {
+                inner.alarms.remove(source)
+            } else {
+                Some(alarm.clone())
+            };
+            inner.notify(now, *source, AlarmTransition::Acknowledged, alarm);
+            Ok(())
+        })
+    }
+
+    /// Shelves `source` until `until`, or lifts its shelving with
+    /// [`None`].
+    pub fn shelve(&self, source: &Uuid, until: Option<u64>, now: u64) -> Result<(), AlarmError> {
+        self.update(|inner| {
+            let shelved = &mut inner
+                .sources
+                .get_mut(source)
+                .ok_or(AlarmError::UnknownSource(*source))?
+                .shelved_until;
+            let was_shelved = shelved.is_some();
+            *shelved = until;
+
+            let transition = match until {
+                Some(_) => AlarmTransition::Shelved,
+                None if was_shelved => AlarmTransition::Unshelved,
+                None => return Ok(()),
+            };
+            let alarm = inner.alarms.get_mut(source).map(|alarm| {
+                alarm.shelved_until = until;
+                alarm.clone()
+            });
+            inner.notify(now, *source, transition, alarm);
+            Ok(())
+        })
+    }
+
+    /// Applies an operator request to `source`.
+    pub fn apply(&self, source: &Uuid, action: AlarmAction, now: u64) -> Result<(), AlarmError> {
+        match action {
+            AlarmAction::Acknowledge => self.acknowledge(source, now),
+            AlarmAction::Shelve(duration) => {
+                self.shelve(source, Some(now.saturating_add(duration)), now)
+            }
+            AlarmAction::Unshelve => self.shelve(source, None, now),
+        }
+    }
+
+    /// Lifts the shelving that expired, and returns when the next one
+    /// does, if any.
+    pub fn unshelve_due(&self, now: u64) -> Option<u64> {
+        let expired: Vec<Uuid> = self
+            .lock()
+            .sources
+            .iter()
+            .filter(|(_, source)| source.shelved_until.is_some_and(|until| until <= now))
+            .map(|(id, _)| *id)
+            .collect();
+        for source in expired {
+            let _ = self.shelve(&source, None, now);
+        }
+
+        self.lock()
+            .sources
+            .values()
+            .filter_map(|source| source.shelved_until)
+            .min()
+    }
+
+    /// Changes the table with `change`, then sends the transitions it
+    /// caused.
+    fn update<R>(&self, change: impl FnOnce(&mut Inner) -> R) -> R {
+        let result = change(&mut self.lock());
+        self.deliver();
+        result
+    }
+
+    /// Sends the pending transitions in order, without holding the lock.
+    /// Only one caller sends at a time: transitions caused meanwhile, by
+    /// a listener or on another thread, are left to it.
+    fn deliver(&self) {
+        {
+            let mut inner = self.lock();
+            if inner.delivering {
+                return;
+            }
+            inner.delivering = true;
+        }
+        let _delivering = Delivering(self);
+
+        loop {
+            let (event, listeners) = {
+                let mut inner = self.lock();
+                let Some(event) = inner.pending.pop_front() else {
+                    inner.delivering = false;
+                    break;
+                };
+                let listeners: Vec<_> = inner
+                    .listeners
+                    .iter()
+                    .map(|(id, listener)| (*id, listener.clone()))
+                    .collect();
+                (event, listeners)
+            };
+
+            for (id, listener) in listeners {
+                let keep = {
+                    let mut listener = listener
+                        .lock()
+                        .unwrap_or_else(|poisoned| poisoned.into_inner());
+                    listener(&event)
+                };
+                if !keep {
+                    let mut inner = self.lock();
+                    if inner
+                        .listeners
+                        .get(&id)
+                        .is_some_and(|current| Arc::ptr_eq(current, &listener))
+                    {
+                        inner.listeners.remove(&id);
+                    }
+                }
+            }
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        // A panicking listener leaves at worst a missed notification.
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Inner {
+    /// Raises a new alarm for `id`, replacing a cleared one waiting for
+    /// acknowledgement.
+    fn raise(&mut self, id: Uuid, now: u64) {
+        let Some(source) = self.sources.get(&id) else {
+            return;
+        };
+        let alarm = Alarm {
+            source: id,
+            kind: source.kind.clone(),
+            message: source.message.clone(),
+            priority: source.priority,
+            state: AlarmState::Active,
+            raised_at: now,
+            acked_at: None,
+            cleared_at: None,
+            shelved_until: source.shelved_until,
+        };
+        let shelved = source.shelved_until.is_some();
+        self.alarms.insert(id, alarm.clone());
+        if !shelved {
+            self.notify(now, id, AlarmTransition::Raised, Some(alarm));
+        }
+    }
+
+    /// Clears the alarm of `id`, removing it if it was acknowledged.
+    fn clear(&mut self, id: Uuid, now: u64) {
+        let Some(alarm) = self.alarms.get_mut(&id) else {
+            return;
+        };
+        alarm.cleared_at = Some(now);
+        let acked = alarm.state == AlarmState::Acknowledged;
+        alarm.state = AlarmState::Cleared;
+        let shelved = alarm.shelved_until.is_some();
+
+        let alarm = if acked {
+            self.alarms.remove(&id)
+        } else {
+            Some(alarm.clone())
+        };
+        if !shelved {
+            self.notify(now, id, AlarmTransition::Cleared, alarm);
+        }
+    }
+
+    fn notify(&mut self, ts: u64, source: Uuid, transition: AlarmTransition, alarm: Option<Alarm>) {
+        let event = AlarmEvent {
+            ts,
+            source,
+            transition,
+            alarm,
+        };
+        self.pending.push_back(event);
+    }
+}
+
+/// Lets another caller send transitions after a listener panicked.
+struct Delivering<'a>(&'a AlarmTable);
+
+impl Drop for Delivering<'_> {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            self.0.lock().delivering = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    use libhaystack::val::Value;
+    use uuid::Uuid;
+
+    use super::{AlarmAction, AlarmState, AlarmTable, AlarmTransition, DEFAULT_PRIORITY};
+    use crate::base::block::BlockState;
+    use crate::base::engine::messages::{ChangeSource, WatchMessage};
+
+    fn report(source: Uuid, changes: &[(&str, Value)]) -> WatchMessage {
+        WatchMessage {
+            block_id: source,
+            changes: changes
+                .iter()
+                .map(|(pin, value)| {
+                    let change = if *pin == "out" {
+                        ChangeSource::Output(pin.to_string(), value.clone())
+                    } else {
+                        ChangeSource::Input(pin.to_string(), value.clone())
+                    };
+                    (pin.to_string(), change)
+                })
+                .collect::<HashMap<_, _>>(),
+            state: BlockState::Running,
+        }
+    }
+
+    fn listen(table: &AlarmTable) -> Arc<Mutex<Vec<AlarmTransition>>> {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        table.subscribe(Uuid::new_v4(), move |event| {
+            sink.lock().unwrap().push(event.transition);
+            true
+        });
+        events
+    }
+
+    #[test]
+    fn alarms_follow_their_lifecycle() {
+        let table = AlarmTable::new();
+        let events = listen(&table);
+        let source = Uuid::new_v4();
+        table.watch(source, "BoolAlarm");
+
+        // Unregistered blocks are ignored.
+        table.record(&report(Uuid::new_v4(), &[("out", true.into())]), 0);
+        assert!(table.alarms().is_empty());
+
+        table.record(
+            &report(
+                source,
+                &[
+                    ("out", true.into()),
+                    ("priority", 7.into()),
+                    ("message", "Pump tripped".into()),
+                ],
+            ),
+            100,
+        );
+        let alarm = table.alarm(&source).unwrap();
+        assert_eq!(alarm.state, AlarmState::Active);
+        assert_eq!(
+            (alarm.priority, alarm.message.as_str()),
+            (7, "Pump tripped")
+        );
+        assert_eq!(alarm.raised_at, 100);
+
+        // Clearing before the acknowledgement keeps the alarm.
+        table.record(&report(source, &[("out", false.into())]), 200);
+        let alarm = table.alarm(&source).unwrap();
+        assert_eq!(
+            (alarm.state, alarm.cleared_at),
+            (AlarmState::Cleared, Some(200))
+        );
+
+        // Raised again before anyone acknowledged it.
+        table.record(&report(source, &[("out", true.into())]), 300);
+        table.acknowledge(&source, 400).unwrap();
+        table.acknowledge(&source, 450).unwrap();
+        let alarm = table.alarm(&source).unwrap();
+        assert_eq!(
+            (alarm.state, alarm.acked_at),
+            (AlarmState::Acknowledged, Some(400))
+        );
+
+        table.record(&report(source, &[("out", false.into())]), 500);
+        assert!(table.alarm(&source).is_none());
+        assert!(table.acknowledge(&source, 600).is_err());
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            [
+                AlarmTransition::Raised,
+                AlarmTransition::Cleared,
+                AlarmTransition::Raised,
+                AlarmTransition::Acknowledged,
+                AlarmTransition::Cleared,
+            ]
+        );
+
+        assert!(table.forget(&source, 700));
+        assert!(!table.forget(&source, 700));
+    }
+
+    #[test]
+    fn shelved_sources_are_tracked_silently() {
+        let table = AlarmTable::new();
+        let events = listen(&table);
+        let urgent = Uuid::new_v4();
+        let source = Uuid::new_v4();
+        table.watch(urgent, "HighLimitAlarm");
+        table.watch(source, "LowLimitAlarm");
+
+        assert!(
+            table
+                .apply(&Uuid::new_v4(), AlarmAction::Unshelve, 0)
+                .is_err()
+        );
+        table.apply(&source, AlarmAction::Shelve(1000), 0).unwrap();
+        table.record(&report(source, &[("out", true.into())]), 100);
+        table.record(
+            &report(urgent, &[("out", true.into()), ("priority", 1.into())]),
+            200,
+        );
+
+        let alarms = table.alarms();
+        assert_eq!(
+            alarms
+                .iter()
+                .map(|alarm| (alarm.source, alarm.priority, alarm.shelved_until))
+                .collect::<Vec<_>>(),
+            [(urgent, 1, None), (source, DEFAULT_PRIORITY, Some(1000))]
+        );
+
+        assert_eq!(table.unshelve_due(500), Some(1000));
+        assert_eq!(table.unshelve_due(1000), None);
+        assert_eq!(table.alarm(&source).unwrap().shelved_until, None);
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            [
+                AlarmTransition::Shelved,
+                AlarmTransition::Raised,
+                AlarmTransition::Unshelved,
+            ]
+        );
+    }
+
+    #[test]
+    fn shelving_for_ever_does_not_overflow() {
+        let table = AlarmTable::new();
+        let source = Uuid::new_v4();
+        table.watch(source, "BoolAlarm");
+
+        table
+            .apply(&source, AlarmAction::Shelve(u64::MAX), 1_000)
+            .unwrap();
+        assert_eq!(table.unshelve_due(2_000), Some(u64::MAX));
+        table.record(&report(source, &[("out", true.into())]), 3_000);
+        assert_eq!(table.alarm(&source).unwrap().shelved_until, Some(u64::MAX));
+    }
+
+    /// Listeners run outside the table's lock, and the transitions they
+    /// cause are sent after the one they are handling.
+    #[test]
+    fn listeners_may_call_back_into_the_table() {
+        let table = AlarmTable::new();
+        let events = listen(&table);
+        let source = Uuid::new_v4();
+        table.watch(source, "BoolAlarm");
+
+        let operator = table.clone();
+        table.subscribe(Uuid::new_v4(), move |event| {
+            if event.transition == AlarmTransition::Raised {
+                assert_eq!(operator.alarms().len(), 1);
+                operator.acknowledge(&event.source, event.ts + 1).unwrap();
+            }
+            true
+        });
+
+        table.record(&report(source, &[("out", true.into())]), 100);
+        assert_eq!(
+            table.alarm(&source).unwrap().state,
+            AlarmState::Acknowledged
+        );
+        assert_eq!(
+            *events.lock().unwrap(),
+            [AlarmTransition::Raised, AlarmTransition::Acknowledged]
+        );
+    }
+
+    #[test]
+    fn configured_inputs_reach_the_raised_alarm() {
+        let table = AlarmTable::new();
+        let source = Uuid::new_v4();
+        table.watch(source, "BoolAlarm");
+
+        table.configure(&source, "priority", &7.into());
+        table.configure(&source, "message", &"Supply fan tripped".into());
+        table.configure(&source, "in", &false.into());
+        table.configure(&Uuid::new_v4(), "priority", &1.into());
+
+        table.record(&report(source, &[("out", true.into())]), 1_000);
+        let alarms = table.alarms();
+        assert_eq!(alarms.len(), 1);
+        assert_eq!(alarms[0].priority, 7);
+        assert_eq!(alarms[0].message, "Supply fan tripped");
+    }
+}
@@ -271,6 +271,30 @@ pub fn convert_value_kind(
     }
 }
 
+/// Converts the elements of the list `val` to the `expected` kind, for
+/// the inputs that [accept lists](crate::base::input::InputProps::accepts_lists).
+///
+/// # Errors
+///
+/// Returns an error if `val` is not a list or an element cannot be
+/// converted.
+pub fn convert_list_kind(val: Value, expected: HaystackKind) -> Result<Value, ValueError> {
+    let Value::List(list) = val else {
+        return Err(ValueError::KindConversion {
+            expected: HaystackKind::List,
+            actual: HaystackKind::from(&val),
+        });
+    };
+
+    list.into_iter()
+        .map(|el| {
+            let actual = HaystackKind::from(&el);
+            convert_value_kind(el, expected, actual)
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(Value::make_list)
+}
+
 #[cfg(test)]
 pub(crate) mod test_utils;
 #[cfg(test)]
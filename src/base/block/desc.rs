@@ -131,8 +131,8 @@ impl TryFrom<&str> for BlockRunCondition {
 impl Display for BlockRunCondition {
     fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let kind = match self {
-            BlockRunCondition::Change => "native",
-            BlockRunCondition::Always => "external",
+            BlockRunCondition::Change => "change",
+            BlockRunCondition::Always => "always",
         };
         write!(fmt, "{kind}")
     }
@@ -8,7 +8,9 @@ use libhaystack::val::Value;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::base::alarm::{Alarm, AlarmAction};
 use crate::base::block::BlockState;
+use crate::base::history::{HistoryQuery, Point, Recording, Sample};
 use crate::base::program::{Program, data::LinkData};
 
 /// Block input properties.
@@ -57,6 +59,52 @@ pub struct BlockDefinition {
     pub fault_reason: Option<String>,
 }
 
+/// Id of the program that blocks join unless loaded into a named one:
+/// blocks added one by one and programs loaded through
+/// [`LoadProgramReq`](EngineMessage::LoadProgramReq).
+pub const DEFAULT_PROGRAM: &str = "default";
+
+/// Lifecycle operation on one program loaded in an engine.
+///
+/// An engine hosts any number of programs side by side, each identified
+/// by an id. Blocks belong to exactly one program; links may cross
+/// program boundaries, in which case they are saved with the program of
+/// their source block and are removed when either end is unloaded.
+#[derive(Debug, Clone)]
+pub enum ProgramAction {
+    /// Loads a [`Program`] under a new id. Fails if the id is taken.
+    Load(Program),
+    /// Stops executing the program's blocks. They keep their values and
+    /// still answer requests, and report the `disabled` state.
+    Pause,
+    /// Resumes a paused program.
+    Resume,
+    /// Removes every block of the program, keeping the (now empty)
+    /// program loaded.
+    Reset,
+    /// Removes every block of the program and the program itself. The
+    /// [`DEFAULT_PROGRAM`] cannot be unloaded, only reset.
+    Unload,
+}
+
+/// Summary of a program loaded in an engine.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgramSummary {
+    /// The program id.
+    pub id: String,
+    /// The program name, as loaded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// The program description, as loaded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Number of blocks in the program.
+    pub blocks: usize,
+    /// Whether the program is paused.
+    pub paused: bool,
+}
+
 /// The source of a change.
 #[derive(Debug, Clone)]
 pub enum ChangeSource {
@@ -116,6 +164,11 @@ pub enum EngineMessage<WatchEventSender: Clone> {
     /// Response to [`GetCurrentProgramReq`](Self::GetCurrentProgramReq).
     GetCurrentProgramRes(Result<Program, String>),
 
+    /// Request the program with the given id in save format.
+    GetProgramReq(Uuid, String),
+    /// Response to [`GetProgramReq`](Self::GetProgramReq).
+    GetProgramRes(Result<Program, String>),
+
     /// Atomically load a full [`Program`] (blocks, links, pin values, UI
     /// metadata) into the engine. Replaces the multi-call JS chain of
     /// `addBlock` + `createLink` + `writeBlockInput` per block.
@@ -123,6 +176,16 @@ pub enum EngineMessage<WatchEventSender: Clone> {
     /// Response to [`LoadProgramReq`](Self::LoadProgramReq).
     LoadProgramRes(Result<(), String>),
 
+    /// Applies a lifecycle action to the program with the given id.
+    ProgramReq(Uuid, String, ProgramAction),
+    /// Response to [`ProgramReq`](Self::ProgramReq).
+    ProgramRes(Result<(), String>),
+
+    /// Lists the programs loaded in the engine.
+    ListProgramsReq(Uuid),
+    /// Response to [`ListProgramsReq`](Self::ListProgramsReq).
+    ListProgramsRes(Vec<ProgramSummary>),
+
     /// Request to inspect a block's current state.
     InspectBlockReq(Uuid, Uuid),
     /// Response to [`InspectBlockReq`](Self::InspectBlockReq).
@@ -143,12 +206,33 @@ pub enum EngineMessage<WatchEventSender: Clone> {
     /// Response to [`RemoveLinkReq`](Self::RemoveLinkReq).
     RemoveLinkRes(Result<bool, String>),
 
+    /// Starts recording a pin into the engine's history, changes how it
+    /// is recorded, or with [`None`] stops recording it.
+    TrendReq(Uuid, Point, Option<Recording>),
+    /// Response to [`TrendReq`](Self::TrendReq).
+    TrendRes(Result<(), String>),
+
+    /// Reads the engine's history.
+    HistoryReq(Uuid, HistoryQuery),
+    /// Response to [`HistoryReq`](Self::HistoryReq).
+    HistoryRes(Result<Vec<Sample>, String>),
+
+    /// Lists the alarms in the engine's alarm table.
+    ListAlarmsReq(Uuid),
+    /// Response to [`ListAlarmsReq`](Self::ListAlarmsReq).
+    ListAlarmsRes(Result<Vec<Alarm>, String>),
+
+    /// Acknowledges, shelves or unshelves the alarm of a source block.
+    AlarmReq(Uuid, Uuid, AlarmAction),
+    /// Response to [`AlarmReq`](Self::AlarmReq).
+    AlarmRes(Result<(), String>),
+
     /// Shut down the engine.
     Shutdown,
     /// Pause block execution.
     Pause,
     /// Resume block execution.
     Resume,
-    /// Reset the engine, removing all blocks and links.
+    /// Reset the engine, removing all blocks, links and programs.
     Reset,
 }
@@ -4,7 +4,8 @@
 //! Defines the errors reported by this crate.
 //!
 //! Each subsystem owns its own error enum — [`RegistryError`],
-//! [`EngineError`], [`ValueError`] and [`ExternalError`] — so a failure
+//! [`EngineError`], [`ValueError`], [`ExternalError`],
+//! [`ProgramError`], [`HistoryError`] and [`AlarmError`] — so a failure
 //! carries the data of the thing that failed and can be matched on
 //! without inspecting a formatted message. [`enum@Error`] is the aggregate
 //! that the crate's entry points return; it forwards `Display` to the
@@ -23,13 +24,19 @@
 //! ```
 //!
 
+pub mod alarm;
 pub mod engine;
 pub mod external;
+pub mod history;
+pub mod program;
 pub mod registry;
 pub mod value;
 
+pub use alarm::AlarmError;
 pub use engine::{EngineError, LinkEnd};
 pub use external::ExternalError;
+pub use history::HistoryError;
+pub use program::ProgramError;
 pub use registry::RegistryError;
 pub use value::ValueError;
 
@@ -70,6 +77,18 @@ pub enum Error {
     /// Resolving or running an external (host-provided) block failed.
     #[error(transparent)]
     External(#[from] ExternalError),
+
+    /// Reading or upgrading a stored program failed.
+    #[error(transparent)]
+    Program(#[from] ProgramError),
+
+    /// Recording or querying the trend history failed.
+    #[error(transparent)]
+    History(#[from] HistoryError),
+
+    /// Acknowledging or shelving an alarm failed.
+    #[error(transparent)]
+    Alarm(#[from] AlarmError),
 }
 
 #[cfg(test)]
@@ -0,0 +1,26 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//!
+//! Errors raised by requests to the
+//! [`AlarmTable`](crate::base::alarm::AlarmTable).
+//!
+
+use thiserror::Error;
+use uuid::Uuid;
+
+/// Failures of the alarm requests.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum AlarmError {
+    /// The block is not an alarm block of the engine.
+    #[error("Block '{0}' is not an alarm source")]
+    UnknownSource(Uuid),
+
+    /// The source has no alarm to acknowledge.
+    #[error("Block '{0}' has no alarm")]
+    NotFound(Uuid),
+
+    /// The engine was not given an alarm table.
+    #[error("Alarms are not enabled on this engine")]
+    NotEnabled,
+}
@@ -98,6 +98,24 @@ pub enum EngineError {
          the `*_send` registry entry points instead"
     )]
     ScheduleRequiresSend,
+
+    /// No program with this id is loaded on the engine.
+    #[error("Program '{id}' not found")]
+    ProgramNotFound {
+        /// The id that was not found.
+        id: String,
+    },
+
+    /// A program with this id is already loaded on the engine.
+    #[error("Program '{id}' is already loaded")]
+    ProgramExists {
+        /// The id that is taken.
+        id: String,
+    },
+
+    /// The default program can be reset but not unloaded.
+    #[error("The default program cannot be unloaded")]
+    DefaultProgramUnload,
 }
 
 /// Parses a block id, tagging a failure with the string that was rejected.
@@ -2,17 +2,19 @@
 
 //!
 //! Errors raised for external (host-provided) blocks — blocks whose
-//! executor is supplied by the JavaScript host rather than compiled in.
+//! executor is supplied by the JavaScript host, a script or a WebAssembly
+//! plugin, rather than compiled in.
 //!
 
 use thiserror::Error;
 
 /// Failures of external block resolution and execution.
 ///
-/// External blocks only exist on `wasm32`, where the host registers the
-/// JavaScript function that executes them; the `Unsupported*` variants
-/// are what every other target and the multi-threaded engine report
-/// instead.
+/// On `wasm32` the host registers the JavaScript function that executes
+/// an external block; native builds with the `scripting` or `plugins`
+/// features run blocks registered with a script or loaded from a plugin
+/// instead. The `Unsupported*` variants
+/// are what the other builds and engines report.
 #[derive(Debug, Error)]
 #[non_exhaustive]
 pub enum ExternalError {
@@ -57,4 +59,38 @@ pub enum ExternalError {
         /// Description of the unexpected return value.
         detail: String,
     },
+
+    /// Nothing is registered to execute an external block on this native
+    /// build.
+    #[cfg(all(
+        any(feature = "scripting", feature = "plugins"),
+        not(target_arch = "wasm32")
+    ))]
+    #[error("Nothing registered to execute the external block '{library}::{name}'")]
+    NotRegistered {
+        /// The library of the block.
+        library: String,
+        /// The block without an executor.
+        name: String,
+    },
+
+    /// The script of a block does not compile.
+    #[cfg(all(feature = "scripting", not(target_arch = "wasm32")))]
+    #[error("Script of block '{name}' does not compile: {detail}")]
+    ScriptCompile {
+        /// The block whose script failed.
+        name: String,
+        /// The compiler's message.
+        detail: String,
+    },
+
+    /// A WebAssembly plugin could not be loaded.
+    #[cfg(all(feature = "plugins", not(target_arch = "wasm32")))]
+    #[error("Plugin '{library}' could not be loaded: {detail}")]
+    PluginLoad {
+        /// The library the plugin was loaded into.
+        library: String,
+        /// Why loading failed.
+        detail: String,
+    },
 }
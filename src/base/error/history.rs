@@ -0,0 +1,44 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//!
+//! Errors raised while recording or querying the trend
+//! [`History`](crate::base::history::History).
+//!
+
+use thiserror::Error;
+use uuid::Uuid;
+
+/// Failures of the history stores and queries.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum HistoryError {
+    /// Reading or writing the store's backing file failed.
+    #[error("History store I/O failed: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// A stored record could not be read back.
+    #[error("History record {line} is corrupt: {reason}")]
+    CorruptRecord {
+        /// One-based line number of the record.
+        line: usize,
+        /// What was wrong with it.
+        reason: String,
+    },
+
+    /// A query asked for something the history cannot answer.
+    #[error("Invalid history query: {0}")]
+    InvalidQuery(String),
+
+    /// A trended pin does not exist on its block.
+    #[error("Pin '{pin}' not found on block '{block}'")]
+    PinNotFound {
+        /// The block the pin was looked up on.
+        block: Uuid,
+        /// The pin name that was not found.
+        pin: String,
+    },
+
+    /// The engine was not given a history to record into.
+    #[error("History is not enabled on this engine")]
+    NotEnabled,
+}
@@ -0,0 +1,85 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//!
+//! Errors raised while reading a stored [`Program`](crate::base::program::Program)
+//! and bringing it up to the current file format.
+//!
+
+use libhaystack::val::kind::HaystackKind;
+use thiserror::Error;
+
+/// Failures of the program file format and its migration chain.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum ProgramError {
+    /// The document is not a JSON object, so it has no program fields to
+    /// read or upgrade.
+    #[error("Program document must be an object")]
+    NotAnObject,
+
+    /// The `version` field is present but is not a non-negative integer.
+    #[error("Invalid program format version: {found}")]
+    InvalidVersion {
+        /// The rejected `version` field, as JSON text.
+        found: String,
+    },
+
+    /// The document was written by a newer release than this one, which
+    /// has no way of knowing what changed since.
+    #[error("Program format version {found} is newer than the supported version {supported}")]
+    UnsupportedVersion {
+        /// The version the document declares.
+        found: u32,
+        /// The newest version this build understands.
+        supported: u32,
+    },
+
+    /// An upgrade step rejected the document.
+    #[error("Upgrading program from format version {from} failed: {reason}")]
+    UpgradeFailed {
+        /// The version the failing step upgrades from.
+        from: u32,
+        /// What the step found wrong with the document.
+        reason: String,
+    },
+
+    /// A Haystack grid row is neither a `block` nor a `link` record.
+    #[error("Grid row {row} is neither a block nor a link record")]
+    UnknownGridRow {
+        /// Index of the offending row.
+        row: usize,
+    },
+
+    /// A Haystack grid row lacks a tag its record requires, or carries
+    /// it with the wrong kind.
+    #[error("Grid row {row} requires tag '{tag}' of kind {expected:?}")]
+    GridTag {
+        /// Index of the offending row.
+        row: usize,
+        /// The tag that is missing or mistyped.
+        tag: &'static str,
+        /// The kind the tag must have.
+        expected: HaystackKind,
+    },
+
+    /// A template parameter without a default was not given a value.
+    #[error("Template parameter '{name}' has no value and no default")]
+    MissingParam {
+        /// The parameter name.
+        name: String,
+    },
+
+    /// A value was given for a parameter the template does not declare.
+    #[error("'{name}' is not a parameter of this template")]
+    UnknownParam {
+        /// The parameter name.
+        name: String,
+    },
+
+    /// The program refers to a parameter it does not declare.
+    #[error("Program refers to undeclared parameter '{name}'")]
+    UndeclaredParam {
+        /// The parameter name.
+        name: String,
+    },
+}
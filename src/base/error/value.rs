@@ -46,6 +46,30 @@ pub enum ValueError {
     #[error("Unit conversion failed: {0}")]
     UnitConversion(String),
 
+    /// The units of two operands have different dimensions, such as
+    /// `°F` and `kPa`, so they can't be added, subtracted or compared.
+    #[error("Incompatible units {lhs} and {rhs}")]
+    IncompatibleUnits {
+        /// The unit of the left operand.
+        lhs: String,
+        /// The unit of the right operand.
+        rhs: String,
+    },
+
+    /// The list operands of an element-wise operation have different
+    /// lengths.
+    #[error("List lengths differ: {expected} and {actual}")]
+    LengthMismatch {
+        /// The length of the first list.
+        expected: usize,
+        /// The length of the list that differs from it.
+        actual: usize,
+    },
+
+    /// A list to reduce, such as the numbers to average, is empty.
+    #[error("Expected a non-empty list")]
+    EmptyList,
+
     /// Decoding a Zinc-encoded string failed. `libhaystack` surfaces
     /// Zinc decoding failures as [`std::io::Error`].
     #[error("Zinc decoding failed: {0}")]
@@ -0,0 +1,631 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Trend history of pin values.
+//!
+//! A [`History`] records the values of selected pins, with their
+//! [`Status`], into a [`HistoryStore`]: on every change, or sampled on a
+//! fixed interval. An engine given a history through its `set_history`
+//! method feeds it from the blocks' change-of-value notifications and
+//! answers [`HistoryReq`](crate::base::engine::messages::EngineMessage::HistoryReq)
+//! queries from it, so a UI can chart values recorded before it
+//! connected.
+//!
+//! Two stores are provided: [`MemoryStore`], a ring buffer per pin, and
+//! [`FileStore`], an append-only file that survives restarts.
+//!
+//! # Examples
+//!
+//! ```
+//! use logic_mesh::base::history::{
+//!     History, HistoryQuery, MemoryStore, Point, Recording, Rollup, Sample,
+//! };
+//! use logic_mesh::base::Status;
+//! use logic_mesh::{Uuid, Value};
+//!
+//! let history = History::new(MemoryStore::new(1000));
+//! let point = Point::new(Uuid::new_v4(), "out");
+//! history.trend(point.clone(), Recording::OnChange);
+//!
+//! for (ts, value) in [(0, 20.0), (30_000, 22.0), (60_000, 21.0)] {
+//!     history
+//!         .append(&point, Sample::new(ts, Value::from(value), Status::Ok))
+//!         .unwrap();
+//! }
+//!
+//! let hourly = history
+//!     .query(&HistoryQuery {
+//!         rollup: Some(Rollup::Max),
+//!         interval: Some(3_600_000),
+//!         ..HistoryQuery::new(point)
+//!     })
+//!     .unwrap();
+//! assert_eq!(hourly, [Sample::new(0, Value::from(22.0), Status::Ok)]);
+//! ```
+
+mod file;
+mod memory;
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use libhaystack::val::{Number, Value};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::base::Status;
+use crate::base::block::BlockState;
+use crate::base::engine::messages::{ChangeSource, WatchMessage};
+use crate::base::error::HistoryError;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use file::FileStore;
+pub use memory::MemoryStore;
+
+/// A trended pin: a block input or output, by name.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Point {
+    /// The block UUID.
+    pub block: Uuid,
+    /// The pin name.
+    pub pin: String,
+}
+
+impl Point {
+    /// Creates a point for the `pin` of `block`.
+    pub fn new(block: Uuid, pin: impl Into<String>) -> Self {
+        Self {
+            block,
+            pin: pin.into(),
+        }
+    }
+}
+
+/// A recorded value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Sample {
+    /// Time of the sample, in milliseconds since the Unix epoch.
+    pub ts: u64,
+    /// The pin value.
+    pub value: Value,
+    /// The value's quality when it was recorded.
+    pub status: Status,
+}
+
+impl Sample {
+    /// Creates a sample.
+    pub fn new(ts: u64, value: Value, status: Status) -> Self {
+        Self { ts, value, status }
+    }
+}
+
+/// When a trended pin is recorded.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Recording {
+    /// On every change of the value or of its status.
+    #[default]
+    OnChange,
+    /// Every given number of milliseconds, with the last known value.
+    Interval(u64),
+}
+
+/// How a query rolls up the samples of each interval into one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Rollup {
+    /// The smallest number.
+    Min,
+    /// The largest number.
+    Max,
+    /// The mean of the numbers.
+    Avg,
+    /// The last value, of any kind.
+    Last,
+}
+
+/// A read of one point's history.
+///
+/// Without a [`rollup`](Self::rollup) the query returns the recorded
+/// samples. With one, it returns a sample per
+/// [`interval`](Self::interval) that has any, stamped with the start of
+/// the interval, or a single sample for the whole range when no interval
+/// is given. Intervals are counted from `start`, or from the first
+/// sample. [`Min`](Rollup::Min), [`Max`](Rollup::Max) and
+/// [`Avg`](Rollup::Avg) skip values that are not numbers and report the
+/// worst status of the interval.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryQuery {
+    /// The point to read.
+    #[serde(flatten)]
+    pub point: Point,
+    /// Start of the range, inclusive, in milliseconds since the Unix
+    /// epoch. Defaults to the oldest sample.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<u64>,
+    /// End of the range, exclusive. Defaults to the newest sample.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<u64>,
+    /// Rolls the samples up.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rollup: Option<Rollup>,
+    /// Length of the rollup intervals, in milliseconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interval: Option<u64>,
+}
+
+impl HistoryQuery {
+    /// A query for every sample of `point`.
+    pub fn new(point: Point) -> Self {
+        Self {
+            point,
+            ..Default::default()
+        }
+    }
+}
+
+/// Where a [`History`] keeps its samples.
+pub trait HistoryStore: Send {
+    /// Appends a sample of `point`. Samples of a point mostly arrive
+    /// oldest first, but an import may append older ones later.
+    fn append(&mut self, point: &Point, sample: Sample) -> Result<(), HistoryError>;
+
+    /// The samples of `point` with `start <= ts < end`, oldest first.
+    fn read(&self, point: &Point, start: u64, end: u64) -> Result<Vec<Sample>, HistoryError>;
+}
+
+/// The trended pins and the store their samples go to.
+///
+/// Cloning a history gives another handle to the same trends and store.
+/// The trends and the store are locked apart, so store I/O never holds
+/// up a change to the trends; an engine also runs the store I/O off its
+/// own task.
+#[derive(Clone)]
+pub struct History {
+    trends: Arc<Mutex<BTreeMap<Point, Trend>>>,
+    store: Arc<Mutex<Box<dyn HistoryStore>>>,
+}
+
+struct Trend {
+    recording: Recording,
+    /// The last known value of the pin.
+    last: Option<(Value, Status)>,
+    /// When an interval trend samples next.
+    due: Option<u64>,
+}
+
+impl History {
+    /// Creates a history recording into `store`, with no pins trended.
+    pub fn new(store: impl HistoryStore + 'static) -> Self {
+        Self {
+            trends: Arc::default(),
+            store: Arc::new(Mutex::new(Box::new(store))),
+        }
+    }
+
+    /// Starts recording `point`, or changes how it is recorded.
+    pub fn trend(&self, point: Point, recording: Recording) {
+        let mut trends = lock(&self.trends);
+        let last = trends.remove(&point).and_then(|trend| trend.last);
+        trends.insert(
+            point,
+            Trend {
+                recording,
+                last,
+                due: None,
+            },
+        );
+    }
+
+    /// Stops recording `point`, keeping its samples. Returns `false` if
+    /// it was not trended.
+    pub fn untrend(&self, point: &Point) -> bool {
+        lock(&self.trends).remove(point).is_some()
+    }
+
+    /// The trended points and how each is recorded.
+    pub fn trends(&self) -> Vec<(Point, Recording)> {
+        lock(&self.trends)
+            .iter()
+            .map(|(point, trend)| (point.clone(), trend.recording))
+            .collect()
+    }
+
+    /// Appends a sample of `point` to the store, whether or not it is
+    /// trended. Used to import history recorded elsewhere.
+    pub fn append(&self, point: &Point, sample: Sample) -> Result<(), HistoryError> {
+        lock(&self.store).append(point, sample)
+    }
+
+    /// Records the trended pins a change notification carries.
+    ///
+    /// A faulted block's values are recorded with [`Status::Fault`], and a
+    /// block entering or leaving a fault records its on-change pins again
+    /// with the new status.
+    pub fn record(&self, msg: &WatchMessage, now: u64) {
+        let status = match msg.state {
+            BlockState::Fault { .. } => Status::Fault,
+            _ => Status::Ok,
+        };
+
+        let mut samples = Vec::new();
+        let mut trends = lock(&self.trends);
+        let block = Point::new(msg.block_id, String::new());
+        for (point, trend) in trends.range_mut(block..) {
+            if point.block != msg.block_id {
+                break;
+            }
+
+            let value = match msg.changes.get(&point.pin) {
+                Some(ChangeSource::Input(_, value) | ChangeSource::Output(_, value)) => {
+                    value.clone()
+                }
+                None => match &trend.last {
+                    Some((value, last)) if *last != status => value.clone(),
+                    _ => continue,
+                },
+            };
+            samples.extend(observe(point, trend, value, status, now));
+        }
+        drop(trends);
+        self.store_all(samples);
+    }
+
+    /// Records the current value of `point`, if trended, as a change
+    /// notification carrying it would. Used to seed a new trend.
+    pub fn observe(&self, point: &Point, value: Value, status: Status, now: u64) {
+        let sample = lock(&self.trends)
+            .get_mut(point)
+            .and_then(|trend| observe(point, trend, value, status, now));
+        self.store_all(sample);
+    }
+
+    /// Samples the interval trends that are due, and returns when the
+    /// next one is, if any.
+    pub fn sample_due(&self, now: u64) -> Option<u64> {
+        let mut samples = Vec::new();
+        let mut next: Option<u64> = None;
+        for (point, trend) in lock(&self.trends).iter_mut() {
+            let Recording::Interval(interval) = trend.recording else {
+                continue;
+            };
+            let interval = interval.max(1);
+            let due = *trend.due.get_or_insert(now + interval);
+            if due <= now {
+                if let Some((value, status)) = &trend.last {
+                    samples.push((point.clone(), Sample::new(now, value.clone(), *status)));
+                }
+                // Skip the intervals missed while the engine was busy.
+                trend.due = Some(due + (now - due) / interval * interval + interval);
+            }
+            let due = trend.due.unwrap_or(due);
+            next = Some(next.map_or(due, |next| next.min(due)));
+        }
+        self.store_all(samples);
+        next
+    }
+
+    /// Reads the samples `query` asks for.
+    pub fn query(&self, query: &HistoryQuery) -> Result<Vec<Sample>, HistoryError> {
+        let start = query.start.unwrap_or(0);
+        let end = query.end.unwrap_or(u64::MAX);
+        if start > end {
+            return Err(HistoryError::InvalidQuery(format!(
+                "range starts at {start}, after its end at {end}"
+            )));
+        }
+        if query.interval == Some(0) {
+            return Err(HistoryError::InvalidQuery(
+                "rollup interval must not be zero".into(),
+            ));
+        }
+
+        let mut samples = lock(&self.store).read(&query.point, start, end)?;
+        Ok(match query.rollup {
+            Some(rollup) => {
+                // Buckets are filled in order, so the samples must be.
+                samples.sort_by_key(|sample| sample.ts);
+                let origin = query
+                    .start
+                    .or_else(|| samples.first().map(|sample| sample.ts))
+                    .unwrap_or_default();
+                roll_up(samples, rollup, origin, query.interval)
+            }
+            None => samples,
+        })
+    }
+
+    /// Appends the recorded samples, logging the ones the store refuses.
+    fn store_all(&self, samples: impl IntoIterator<Item = (Point, Sample)>) {
+        let mut samples = samples.into_iter().peekable();
+        if samples.peek().is_none() {
+            return;
+        }
+        let mut store = lock(&self.store);
+        for (point, sample) in samples {
+            if let Err(err) = store.append(&point, sample) {
+                log::warn!("Recording {}/{} failed: {err}", point.block, point.pin);
+            }
+        }
+    }
+}
+
+fn lock<T: ?Sized>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    // A panic mid-append leaves at worst a missing sample.
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Keeps a trended pin's new value, and returns the sample to record if
+/// it is recorded on change.
+fn observe(
+    point: &Point,
+    trend: &mut Trend,
+    value: Value,
+    status: Status,
+    now: u64,
+) -> Option<(Point, Sample)> {
+    let sample = (trend.recording == Recording::OnChange)
+        .then(|| (point.clone(), Sample::new(now, value.clone(), status)));
+    trend.last = Some((value, status));
+    sample
+}
+
+/// One sample per interval, see [`HistoryQuery`].
+fn roll_up(
+    samples: Vec<Sample>,
+    rollup: Rollup,
+    origin: u64,
+    interval: Option<u64>,
+) -> Vec<Sample> {
+    let mut buckets: Vec<(u64, Vec<Sample>)> = Vec::new();
+    for sample in samples {
+        let ts = match interval {
+            Some(interval) => origin + sample.ts.saturating_sub(origin) / interval * interval,
+            None => origin,
+        };
+        match buckets.last_mut() {
+            Some((bucket, samples)) if *bucket == ts => samples.push(sample),
+            _ => buckets.push((ts, vec![sample])),
+        }
+    }
+
+    buckets
+        .into_iter()
+        .filter_map(|(ts, samples)| aggregate(ts, &samples, rollup))
+        .collect()
+}
+
+fn aggregate(ts: u64, samples: &[Sample], rollup: Rollup) -> Option<Sample> {
+    if rollup == Rollup::Last {
+        let last = samples.last()?;
+        return Some(Sample::new(ts, last.value.clone(), last.status));
+    }
+
+    let numbers: Vec<&Number> = samples
+        .iter()
+        .filter_map(|sample| match &sample.value {
+            Value::Number(number) => Some(number),
+            _ => None,
+        })
+        .collect();
+    let unit = numbers.first()?.unit;
+    let values = numbers.iter().map(|number| number.value);
+    let value = match rollup {
+        Rollup::Min => values.fold(f64::INFINITY, f64::min),
+        Rollup::Max => values.fold(f64::NEG_INFINITY, f64::max),
+        _ => values.sum::<f64>() / numbers.len() as f64,
+    };
+    let status = samples
+        .iter()
+        .map(|sample| sample.status)
+        .max_by_key(|status| match status {
+            Status::Ok => 0,
+            Status::Stale => 1,
+            Status::Fault => 2,
+        })
+        .unwrap_or_default();
+
+    Some(Sample::new(ts, Number { value, unit }.into(), status))
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use libhaystack::val::Value;
+    use uuid::Uuid;
+
+    use super::{
+        History, HistoryQuery, HistoryStore, MemoryStore, Point, Recording, Rollup, Sample,
+    };
+    use crate::base::Status;
+    use crate::base::block::BlockState;
+    use crate::base::engine::messages::{ChangeSource, WatchMessage};
+    use crate::base::error::HistoryError;
+
+    fn change(block: Uuid, pin: &str, value: f64, state: BlockState) -> WatchMessage {
+        WatchMessage {
+            block_id: block,
+            changes: HashMap::from([(
+                pin.to_string(),
+                ChangeSource::Output(pin.to_string(), value.into()),
+            )]),
+            state,
+        }
+    }
+
+    fn values(samples: &[Sample]) -> Vec<(u64, Value, Status)> {
+        samples
+            .iter()
+            .map(|sample| (sample.ts, sample.value.clone(), sample.status))
+            .collect()
+    }
+
+    #[test]
+    fn records_trended_pins_on_change_and_on_interval() {
+        let history = History::new(MemoryStore::new(100));
+        let block = Uuid::new_v4();
+        let out = Point::new(block, "out");
+        let input = Point::new(block, "in");
+        history.trend(out.clone(), Recording::OnChange);
+        history.trend(input.clone(), Recording::Interval(1000));
+
+        history.record(&change(block, "out", 1.0, BlockState::Running), 100);
+        history.record(&change(block, "in", 5.0, BlockState::Running), 200);
+        // Untrended blocks and pins are ignored.
+        history.record(
+            &change(Uuid::new_v4(), "out", 9.0, BlockState::Running),
+            300,
+        );
+        history.record(&change(block, "other", 9.0, BlockState::Running), 300);
+        // A fault is recorded on the unchanged pins too.
+        let fault = WatchMessage {
+            block_id: block,
+            changes: HashMap::new(),
+            state: BlockState::fault("boom"),
+        };
+        history.record(&fault, 400);
+
+        assert_eq!(
+            values(&history.query(&HistoryQuery::new(out.clone())).unwrap()),
+            [
+                (100, 1.0.into(), Status::Ok),
+                (400, 1.0.into(), Status::Fault)
+            ]
+        );
+
+        // The first call schedules the interval, later ones sample it.
+        assert_eq!(history.sample_due(0), Some(1000));
+        assert_eq!(history.sample_due(1000), Some(2000));
+        assert_eq!(history.sample_due(3500), Some(4000));
+        assert_eq!(
+            values(&history.query(&HistoryQuery::new(input.clone())).unwrap()),
+            [
+                (1000, 5.0.into(), Status::Fault),
+                (3500, 5.0.into(), Status::Fault)
+            ]
+        );
+
+        assert!(history.untrend(&input));
+        assert_eq!(history.sample_due(4000), None);
+        assert_eq!(history.trends(), [(out, Recording::OnChange)]);
+    }
+
+    #[test]
+    fn queries_roll_up_ranges() {
+        let history = History::new(MemoryStore::new(100));
+        let point = Point::new(Uuid::new_v4(), "out");
+        let samples = [
+            (0, Value::from(1.0), Status::Ok),
+            (500, Value::from(3.0), Status::Ok),
+            (1000, Value::from(10.0), Status::Fault),
+            (1200, Value::from("text"), Status::Ok),
+            (2500, Value::from(4.0), Status::Ok),
+        ];
+        for (ts, value, status) in samples.clone() {
+            history
+                .append(&point, Sample::new(ts, value, status))
+                .unwrap();
+        }
+
+        let query = |start, end, rollup, interval| {
+            values(
+                &history
+                    .query(&HistoryQuery {
+                        start,
+                        end,
+                        rollup,
+                        interval,
+                        ..HistoryQuery::new(point.clone())
+                    })
+                    .unwrap(),
+            )
+        };
+
+        assert_eq!(query(None, None, None, None), samples);
+        assert_eq!(query(Some(500), Some(1200), None, None), samples[1..3]);
+        assert_eq!(
+            query(None, None, Some(Rollup::Avg), Some(1000)),
+            [
+                (0, 2.0.into(), Status::Ok),
+                (1000, 10.0.into(), Status::Fault),
+                (2000, 4.0.into(), Status::Ok)
+            ]
+        );
+        assert_eq!(
+            query(Some(200), None, Some(Rollup::Min), Some(1000)),
+            [
+                (200, 3.0.into(), Status::Fault),
+                (2200, 4.0.into(), Status::Ok)
+            ]
+        );
+        assert_eq!(
+            query(None, Some(2000), Some(Rollup::Max), None),
+            [(0, 10.0.into(), Status::Fault)]
+        );
+        assert_eq!(
+            query(None, None, Some(Rollup::Last), Some(1000)),
+            [
+                (0, 3.0.into(), Status::Ok),
+                (1000, "text".into(), Status::Ok),
+                (2000, 4.0.into(), Status::Ok)
+            ]
+        );
+
+        assert!(
+            history
+                .query(&HistoryQuery {
+                    interval: Some(0),
+                    rollup: Some(Rollup::Avg),
+                    ..HistoryQuery::new(point.clone())
+                })
+                .is_err()
+        );
+    }
+
+    /// A store handing its samples back in the order they came.
+    struct Unsorted(Vec<Sample>);
+
+    impl HistoryStore for Unsorted {
+        fn append(&mut self, _: &Point, sample: Sample) -> Result<(), HistoryError> {
+            self.0.push(sample);
+            Ok(())
+        }
+
+        fn read(&self, _: &Point, start: u64, end: u64) -> Result<Vec<Sample>, HistoryError> {
+            Ok(self
+                .0
+                .iter()
+                .filter(|sample| (start..end).contains(&sample.ts))
+                .cloned()
+                .collect())
+        }
+    }
+
+    #[test]
+    fn rolls_up_samples_appended_out_of_order() {
+        let history = History::new(Unsorted(Vec::new()));
+        let point = Point::new(Uuid::new_v4(), "out");
+        for (ts, value) in [(1500, 5.0), (200, 1.0), (1100, 3.0)] {
+            history
+                .append(&point, Sample::new(ts, value.into(), Status::Ok))
+                .unwrap();
+        }
+
+        let avg = history
+            .query(&HistoryQuery {
+                rollup: Some(Rollup::Avg),
+                interval: Some(1000),
+                ..HistoryQuery::new(point)
+            })
+            .unwrap();
+        assert_eq!(
+            values(&avg),
+            [
+                (200, 2.0.into(), Status::Ok),
+                (1200, 5.0.into(), Status::Ok)
+            ]
+        );
+    }
+}
@@ -0,0 +1,180 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Append-only file store.
+//!
+//! Each sample is a line of JSON with its point, written in one call:
+//!
+//! ```text
+//! {"block":"7c4f...","pin":"out","ts":1767225600000,"value":21.5,"status":"Ok"}
+//! ```
+//!
+//! Values are in Hayson, the JSON form of Haystack values. Reads scan the
+//! whole file, which is fine for the months of trends a controller keeps;
+//! rotate the file to bound it. Engines run the reads and writes on the
+//! blocking thread pool, off their own tasks.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use libhaystack::val::Value;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{HistoryStore, Point, Sample};
+use crate::base::Status;
+use crate::base::error::HistoryError;
+
+/// Keeps every sample in an append-only file of JSON lines.
+#[derive(Debug)]
+pub struct FileStore {
+    path: PathBuf,
+    file: File,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Record {
+    block: Uuid,
+    pin: String,
+    ts: u64,
+    value: Value,
+    status: Status,
+}
+
+impl FileStore {
+    /// Opens the store at `path`, creating the file if needed.
+    ///
+    /// A record cut short by a crash while it was written is dropped.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, HistoryError> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(&path)?;
+
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents)?;
+        if contents.last().is_some_and(|byte| *byte != b'\n') {
+            let complete = contents
+                .iter()
+                .rposition(|byte| *byte == b'\n')
+                .map_or(0, |pos| pos + 1);
+            file.set_len(complete as u64)?;
+            file.seek(SeekFrom::End(0))?;
+        }
+
+        Ok(Self { path, file })
+    }
+
+    /// The path of the store's file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl HistoryStore for FileStore {
+    fn append(&mut self, point: &Point, sample: Sample) -> Result<(), HistoryError> {
+        let record = Record {
+            block: point.block,
+            pin: point.pin.clone(),
+            ts: sample.ts,
+            value: sample.value,
+            status: sample.status,
+        };
+        let mut line = serde_json::to_vec(&record).map_err(std::io::Error::from)?;
+        line.push(b'\n');
+        self.file.write_all(&line)?;
+        Ok(())
+    }
+
+    fn read(&self, point: &Point, start: u64, end: u64) -> Result<Vec<Sample>, HistoryError> {
+        let mut samples = Vec::new();
+        for (index, line) in BufReader::new(File::open(&self.path)?).lines().enumerate() {
+            let line = line?;
+            let record: Record =
+                serde_json::from_str(&line).map_err(|err| HistoryError::CorruptRecord {
+                    line: index + 1,
+                    reason: err.to_string(),
+                })?;
+            if record.block == point.block
+                && record.pin == point.pin
+                && (start..end).contains(&record.ts)
+            {
+                samples.push(Sample::new(record.ts, record.value, record.status));
+            }
+        }
+        // Imported samples may sit after newer ones in the file.
+        samples.sort_by_key(|sample| sample.ts);
+        Ok(samples)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use libhaystack::units::get_unit;
+    use libhaystack::val::{Number, Value};
+    use uuid::Uuid;
+
+    use super::FileStore;
+    use crate::base::Status;
+    use crate::base::error::HistoryError;
+    use crate::base::history::{HistoryStore, Point, Sample};
+
+    #[test]
+    fn samples_survive_reopening_the_file() {
+        let path = std::env::temp_dir().join(format!("logic-mesh-history-{}", Uuid::new_v4()));
+        let temp = Point::new(Uuid::new_v4(), "out");
+        let alarm = Point::new(Uuid::new_v4(), "in");
+        let celsius = Value::from(Number::make_with_unit(21.5, get_unit("°C").unwrap()));
+
+        let mut store = FileStore::open(&path).unwrap();
+        store
+            .append(&temp, Sample::new(10, celsius.clone(), Status::Ok))
+            .unwrap();
+        store
+            .append(&alarm, Sample::new(20, true.into(), Status::Fault))
+            .unwrap();
+        store
+            .append(&temp, Sample::new(30, 22.0.into(), Status::Ok))
+            .unwrap();
+        drop(store);
+
+        // A torn record from a crash mid-append is dropped on open.
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        file.write_all(br#"{"block":"#).unwrap();
+        drop(file);
+
+        let mut store = FileStore::open(&path).unwrap();
+        store
+            .append(&temp, Sample::new(40, 23.0.into(), Status::Stale))
+            .unwrap();
+        assert_eq!(
+            store.read(&temp, 0, u64::MAX).unwrap(),
+            [
+                Sample::new(10, celsius, Status::Ok),
+                Sample::new(30, 22.0.into(), Status::Ok),
+                Sample::new(40, 23.0.into(), Status::Stale),
+            ]
+        );
+        assert_eq!(
+            store.read(&alarm, 20, 21).unwrap(),
+            [Sample::new(20, true.into(), Status::Fault)]
+        );
+        assert!(store.read(&temp, 11, 30).unwrap().is_empty());
+
+        std::fs::write(&path, "{}\n").unwrap();
+        assert!(matches!(
+            store.read(&temp, 0, u64::MAX),
+            Err(HistoryError::CorruptRecord { line: 1, .. })
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,111 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! In-memory ring buffer store.
+
+use std::collections::{BTreeMap, VecDeque};
+
+use super::{HistoryStore, Point, Sample};
+use crate::base::error::HistoryError;
+
+/// Keeps the newest samples of each point in memory, dropping the oldest
+/// once a point holds `capacity` of them. The history is lost with the
+/// process.
+#[derive(Debug, Default, Clone)]
+pub struct MemoryStore {
+    capacity: usize,
+    points: BTreeMap<Point, VecDeque<Sample>>,
+}
+
+impl MemoryStore {
+    /// Creates a store keeping up to `capacity` samples per point.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            points: BTreeMap::new(),
+        }
+    }
+}
+
+impl HistoryStore for MemoryStore {
+    fn append(&mut self, point: &Point, sample: Sample) -> Result<(), HistoryError> {
+        let samples = self.points.entry(point.clone()).or_default();
+        // Keep the samples sorted for `read`, also when an import
+        // appends them out of order.
+        let index = samples.partition_point(|kept| kept.ts <= sample.ts);
+        samples.insert(index, sample);
+        if samples.len() > self.capacity {
+            samples.pop_front();
+        }
+        Ok(())
+    }
+
+    fn read(&self, point: &Point, start: u64, end: u64) -> Result<Vec<Sample>, HistoryError> {
+        let Some(samples) = self.points.get(point) else {
+            return Ok(Vec::new());
+        };
+        let first = samples.partition_point(|sample| sample.ts < start);
+        let last = samples.partition_point(|sample| sample.ts < end);
+        Ok(samples.range(first..last.max(first)).cloned().collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use libhaystack::val::Value;
+    use uuid::Uuid;
+
+    use super::MemoryStore;
+    use crate::base::Status;
+    use crate::base::history::{HistoryStore, Point, Sample};
+
+    #[test]
+    fn keeps_the_newest_samples_of_each_point() {
+        let mut store = MemoryStore::new(3);
+        let point = Point::new(Uuid::new_v4(), "out");
+        for ts in 0..5 {
+            store
+                .append(&point, Sample::new(ts, Value::from(ts as f64), Status::Ok))
+                .unwrap();
+        }
+
+        let stamps = |start, end| -> Vec<u64> {
+            store
+                .read(&point, start, end)
+                .unwrap()
+                .iter()
+                .map(|sample| sample.ts)
+                .collect()
+        };
+        assert_eq!(stamps(0, u64::MAX), [2, 3, 4]);
+        assert_eq!(stamps(3, 4), [3]);
+        assert!(stamps(5, 9).is_empty());
+        assert!(
+            store
+                .read(&Point::new(Uuid::new_v4(), "out"), 0, u64::MAX)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn keeps_samples_appended_out_of_order_sorted() {
+        let mut store = MemoryStore::new(3);
+        let point = Point::new(Uuid::new_v4(), "out");
+        for ts in [30, 10, 40, 20, 5] {
+            store
+                .append(&point, Sample::new(ts, Value::from(ts as f64), Status::Ok))
+                .unwrap();
+        }
+
+        let stamps = |start, end| -> Vec<u64> {
+            store
+                .read(&point, start, end)
+                .unwrap()
+                .iter()
+                .map(|sample| sample.ts)
+                .collect()
+        };
+        assert_eq!(stamps(0, u64::MAX), [20, 30, 40]);
+        assert_eq!(stamps(25, 45), [30, 40]);
+    }
+}
@@ -17,6 +17,8 @@ pub struct BaseInput<Reader, Writer> {
     pub name: String,
     /// The kind of data this input can receive.
     pub kind: HaystackKind,
+    /// Whether this input also takes lists of its kind.
+    pub accepts_lists: bool,
     /// The block id of the block this input belongs to.
     pub block_id: Uuid,
     /// The number of connections this input has.
@@ -50,6 +52,10 @@ impl<Reader, Writer: Clone + Send> InputProps for BaseInput<Reader, Writer> {
         &self.kind
     }
 
+    fn accepts_lists(&self) -> bool {
+        self.accepts_lists
+    }
+
     fn block_id(&self) -> &Uuid {
         &self.block_id
     }
@@ -20,6 +20,12 @@ pub trait InputProps {
     /// Returns the kind of data this input can receive.
     fn kind(&self) -> &HaystackKind;
 
+    /// Returns `true` if this input also takes lists of its kind, for
+    /// blocks that compute element-wise. Other inputs fault on a list.
+    fn accepts_lists(&self) -> bool {
+        false
+    }
+
     /// Returns the block id of the block this input belongs to.
     fn block_id(&self) -> &Uuid;
 
@@ -0,0 +1,86 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Command priority arrays.
+//!
+//! A commanded value takes the value of the highest priority level set,
+//! or its relinquish default once every level is released. The
+//! [`PriorityArray`](crate::blocks::control::PriorityArray) block, the
+//! Haystack `pointWrite` op and commandable BACnet objects all resolve
+//! their commands through [`Priorities`].
+
+/// Commands at `N` priority levels, level 1 being the highest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Priorities<T, const N: usize> {
+    levels: [Option<T>; N],
+    /// The value while no level is set.
+    pub relinquish_default: T,
+}
+
+impl<T, const N: usize> Priorities<T, N> {
+    /// Priorities without any level set, falling back to
+    /// `relinquish_default`.
+    pub fn new(relinquish_default: T) -> Self {
+        Self {
+            levels: std::array::from_fn(|_| None),
+            relinquish_default,
+        }
+    }
+
+    /// Sets `level` (1 to `N`) to `value`, or releases it when [`None`].
+    /// Returns what the level held before.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `level` is not between 1 and `N`.
+    pub fn write(&mut self, level: usize, value: Option<T>) -> Option<T> {
+        std::mem::replace(&mut self.levels[level - 1], value)
+    }
+
+    /// The value at `level`, if it is set.
+    pub fn level(&self, level: usize) -> Option<&T> {
+        self.levels.get(level.checked_sub(1)?)?.as_ref()
+    }
+
+    /// Every level from the highest, [`None`] where it is not set.
+    pub fn levels(&self) -> impl Iterator<Item = Option<&T>> {
+        self.levels.iter().map(Option::as_ref)
+    }
+
+    /// Returns `true` if any level is set.
+    pub fn is_commanded(&self) -> bool {
+        self.levels.iter().any(Option::is_some)
+    }
+
+    /// The value of the highest level set, or the relinquish default.
+    pub fn effective(&self) -> &T {
+        self.levels
+            .iter()
+            .flatten()
+            .next()
+            .unwrap_or(&self.relinquish_default)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Priorities;
+
+    #[test]
+    fn highest_level_wins_until_released() {
+        let mut array = Priorities::<i32, 16>::new(20);
+        assert!(!array.is_commanded());
+        array.write(16, Some(16));
+        assert_eq!(array.write(8, Some(8)), None);
+        assert_eq!(array.effective(), &8);
+
+        assert_eq!(array.write(8, None), Some(8));
+        assert_eq!(array.effective(), &16);
+        assert_eq!(array.level(16), Some(&16));
+        assert_eq!(array.level(0), None);
+        assert_eq!(array.level(17), None);
+
+        array.write(16, None);
+        assert_eq!(array.effective(), &20);
+        assert!(!array.is_commanded());
+    }
+}
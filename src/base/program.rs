@@ -11,8 +11,16 @@
 //! managed separately and load it via [`crate::base::engine::Engine`].
 
 pub mod data;
+pub mod diagram;
+pub mod diff;
+pub mod grid;
+pub mod template;
+pub mod version;
 
-pub use data::{BlockData, LinkData, PinValue, Position, Program, ProgramBlock, ProgramMeta};
+pub use data::{
+    BlockData, LinkData, PinValue, Position, Program, ProgramBlock, ProgramMeta, ProgramParam,
+};
+pub use version::{PROGRAM_FORMAT_VERSION, PROGRAM_JSON_SCHEMA};
 
 #[cfg(test)]
 mod test {
@@ -137,6 +145,46 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_program_serializes_format_version() {
+        let json = serde_json::to_value(Program::default()).expect("serialize");
+
+        assert_eq!(json["version"], super::PROGRAM_FORMAT_VERSION);
+    }
+
+    /// Files saved before the format was versioned carry no `version`
+    /// field and must keep loading.
+    #[test]
+    fn test_program_loads_unversioned_file() {
+        let json = r#"{
+            "name": "legacy",
+            "blocks": {
+                "00000000-0000-0000-0000-000000000000": {
+                    "name": "Add",
+                    "lib": "core",
+                    "inputs": { "in0": { "value": 42 } }
+                }
+            },
+            "links": {}
+        }"#;
+
+        let program: Program = serde_json::from_str(json).expect("legacy file loads");
+        assert_eq!(program.name.as_deref(), Some("legacy"));
+        let block = &program.blocks["00000000-0000-0000-0000-000000000000"];
+        assert_eq!(block.inputs["in0"].value, 42.into());
+    }
+
+    #[test]
+    fn test_program_rejects_newer_format() {
+        let json = format!(
+            r#"{{ "version": {}, "blocks": {{}}, "links": {{}} }}"#,
+            super::PROGRAM_FORMAT_VERSION + 1
+        );
+
+        let err = serde_json::from_str::<Program>(&json).expect_err("newer format is rejected");
+        assert!(err.to_string().contains("newer than the supported version"));
+    }
+
     #[test]
     fn test_program_block_stores_label_and_position() {
         // The engine should round-trip the per-block UI metadata
@@ -13,7 +13,9 @@
 use std::collections::BTreeMap;
 
 use libhaystack::val::Value;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error as _};
+
+use super::version::{PROGRAM_FORMAT_VERSION, upgrade};
 
 /// Backwards-compatible metadata wrapper. Predates [`Program`] and only
 /// carries top-level descriptors; modern code uses [`Program::name`] /
@@ -39,7 +41,7 @@ pub struct ProgramMeta {
 ///
 /// Fields use camelCase serialization to match the JS [`LinkData`]
 /// interface — programs round-trip through the wasm bridge unchanged.
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct LinkData {
     /// Optional link UUID (omitted for auto-generated IDs).
@@ -88,7 +90,7 @@ pub struct Position {
 /// whether the pin is currently wired up. Both fields are independently
 /// useful: connected inputs may still carry a last-known value, and
 /// disconnected inputs (constants) have only a value.
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PinValue {
     /// The pin's current value.
@@ -100,7 +102,7 @@ pub struct PinValue {
 
 /// One block entry in the program format. Mirrors the per-block object
 /// in the JS [`Program`] interface.
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ProgramBlock {
     /// Block type name (looked up in the block registry at load time).
     pub name: String,
@@ -124,11 +126,33 @@ pub struct ProgramBlock {
     pub outputs: BTreeMap<String, PinValue>,
 }
 
+/// A named parameter declared by a template [`Program`].
+///
+/// Input values, labels and the program's name and description refer to
+/// a parameter as `${name}`; [`Program::instantiate`] substitutes the
+/// values. See [`template`](super::template).
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProgramParam {
+    /// What the parameter controls, for whoever fills it in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Value used when instantiating without one. Parameters without a
+    /// default must be supplied.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<Value>,
+}
+
 /// Full savable program: identity, all blocks keyed by uuid, all links
 /// keyed by uuid. This is the format the engine's `save_program` emits
 /// and `load_program` consumes. Round-trips through the wasm bridge as
 /// JSON without reassembly.
 ///
+/// The serialized form carries a top-level `version` field. Documents
+/// written by older releases are brought up to the current shape on
+/// deserialize — see [`version`](super::version) — so an in-memory
+/// [`Program`] is always at
+/// [`PROGRAM_FORMAT_VERSION`](super::version::PROGRAM_FORMAT_VERSION).
+///
 /// # Examples
 ///
 /// ```
@@ -149,18 +173,81 @@ pub struct ProgramBlock {
 /// let loaded: Program = serde_json::from_str(&json).unwrap();
 /// assert_eq!(loaded.blocks.len(), 1);
 /// ```
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq)]
 pub struct Program {
     /// Program name.
-    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
     /// Human-readable description.
-    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     /// Blocks keyed by their UUID string.
-    #[serde(default)]
     pub blocks: BTreeMap<String, ProgramBlock>,
     /// Links keyed by their UUID string.
-    #[serde(default)]
     pub links: BTreeMap<String, LinkData>,
+    /// Parameters declared by a template program, keyed by name. Empty
+    /// for concrete programs.
+    pub params: BTreeMap<String, ProgramParam>,
+}
+
+/// Serialized shape of a [`Program`] at
+/// [`PROGRAM_FORMAT_VERSION`]. Borrows the program so saving does not
+/// copy it.
+#[derive(Serialize)]
+struct ProgramDocRef<'a> {
+    version: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: &'a Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: &'a Option<String>,
+    blocks: &'a BTreeMap<String, ProgramBlock>,
+    links: &'a BTreeMap<String, LinkData>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    params: &'a BTreeMap<String, ProgramParam>,
+}
+
+/// Deserialized shape of a program document once [`upgrade`] has
+/// brought it to [`PROGRAM_FORMAT_VERSION`].
+#[derive(Deserialize)]
+struct ProgramDoc {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    blocks: BTreeMap<String, ProgramBlock>,
+    #[serde(default)]
+    links: BTreeMap<String, LinkData>,
+    #[serde(default)]
+    params: BTreeMap<String, ProgramParam>,
+}
+
+impl Serialize for Program {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ProgramDocRef {
+            version: PROGRAM_FORMAT_VERSION,
+            name: &self.name,
+            description: &self.description,
+            blocks: &self.blocks,
+            links: &self.links,
+            params: &self.params,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Program {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // Upgrading works on the untyped document: older versions may not
+        // fit the current types at all.
+        let doc =
+            upgrade(serde_json::Value::deserialize(deserializer)?).map_err(D::Error::custom)?;
+        let doc = ProgramDoc::deserialize(doc).map_err(D::Error::custom)?;
+
+        Ok(Program {
+            name: doc.name,
+            description: doc.description,
+            blocks: doc.blocks,
+            links: doc.links,
+            params: doc.params,
+        })
+    }
 }
@@ -0,0 +1,572 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Graphviz DOT and Mermaid renderings of a [`Program`].
+//!
+//! Meant for design reviews, as-built documentation and pull request
+//! diffs, where the web editor is not at hand. To draw a running engine,
+//! render the [`Program`] its `save_program` (or
+//! [`GetCurrentProgramReq`](crate::base::engine::messages::EngineMessage::GetCurrentProgramReq))
+//! returns, and pass the block states from `inspect_block` or the watch
+//! stream through [`DiagramOptions`].
+//!
+//! Output is deterministic — blocks, pins and links are emitted in key
+//! order — so a rendered diagram diffs cleanly. Links to blocks missing
+//! from the program are left out of both renderings.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use super::data::{LinkData, PinValue, Program, ProgramBlock};
+use crate::base::block::BlockState;
+use crate::blocks::registry::get_block;
+
+/// Controls what a diagram shows beyond blocks, pins and links.
+///
+/// # Examples
+///
+/// ```
+/// use logic_mesh::base::block::BlockState;
+/// use logic_mesh::base::program::diagram::DiagramOptions;
+///
+/// let options = DiagramOptions::default()
+///     .with_values()
+///     .with_state("00000000-0000-0000-0000-000000000000", BlockState::fault("unit mismatch"));
+/// assert!(options.values);
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct DiagramOptions {
+    /// Annotate pins with the values stored in the program.
+    pub values: bool,
+    /// Show every pin the block declares, not just the linked ones and
+    /// those carrying a value.
+    pub all_pins: bool,
+    /// Block states keyed by block UUID. Faulted blocks are highlighted
+    /// and show their fault reason; other non-running states are named.
+    pub states: BTreeMap<String, BlockState>,
+}
+
+impl DiagramOptions {
+    /// Annotates pins with their values.
+    pub fn with_values(mut self) -> Self {
+        self.values = true;
+        self
+    }
+
+    /// Shows every declared pin.
+    pub fn with_all_pins(mut self) -> Self {
+        self.all_pins = true;
+        self
+    }
+
+    /// Records the state of one block.
+    pub fn with_state(mut self, block_id: impl Into<String>, state: BlockState) -> Self {
+        self.states.insert(block_id.into(), state);
+        self
+    }
+}
+
+impl Program {
+    /// Renders this program as a Graphviz DOT digraph. Blocks are nodes
+    /// whose inputs and outputs are ports; links are edges between them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use logic_mesh::base::program::{LinkData, Program, ProgramBlock};
+    /// use logic_mesh::base::program::diagram::DiagramOptions;
+    ///
+    /// let mut program = Program::default();
+    /// for id in ["a", "b"] {
+    ///     program.blocks.insert(id.to_string(), ProgramBlock {
+    ///         name: "Add".to_string(),
+    ///         lib: "core".to_string(),
+    ///         ..Default::default()
+    ///     });
+    /// }
+    /// program.links.insert("l".to_string(), LinkData {
+    ///     id: None,
+    ///     source_block_uuid: "a".to_string(),
+    ///     target_block_uuid: "b".to_string(),
+    ///     source_block_pin_name: "out".to_string(),
+    ///     target_block_pin_name: "in0".to_string(),
+    /// });
+    ///
+    /// let dot = program.to_dot(&DiagramOptions::default());
+    /// assert!(dot.contains(r#""a":"o_out":e -> "b":"i_in0":w"#));
+    /// ```
+    pub fn to_dot(&self, options: &DiagramOptions) -> String {
+        let pins = self.diagram_pins(options);
+        let mut dot = String::new();
+
+        let _ = writeln!(
+            dot,
+            "digraph {} {{",
+            dot_id(self.name.as_deref().unwrap_or("program"))
+        );
+        let _ = writeln!(dot, "  rankdir=LR;");
+        let _ = writeln!(dot, "  node [shape=plaintext, fontname=\"Helvetica\"];");
+        let _ = writeln!(dot, "  edge [fontname=\"Helvetica\"];");
+
+        for (id, block) in &self.blocks {
+            let (inputs, outputs) = &pins[id];
+            let state = options.states.get(id);
+            let fill = match state {
+                Some(BlockState::Fault { .. }) => "#f8d0d0",
+                Some(BlockState::Disabled) | Some(BlockState::Terminated) => "#e0e0e0",
+                _ => "#ffffff",
+            };
+
+            let mut label = String::new();
+            let _ = write!(
+                label,
+                "<table border=\"1\" cellborder=\"0\" cellspacing=\"0\" bgcolor=\"{fill}\">"
+            );
+            let _ = write!(
+                label,
+                "<tr><td colspan=\"2\"><b>{}</b></td></tr>",
+                html_escape(&block_title(block))
+            );
+            if let Some(state) = state.filter(|state| **state != BlockState::Running) {
+                let _ = write!(
+                    label,
+                    "<tr><td colspan=\"2\"><i>{}</i></td></tr>",
+                    html_escape(&state_text(state))
+                );
+            }
+
+            let rows = inputs.len().max(outputs.len());
+            for row in 0..rows {
+                label.push_str("<tr>");
+                match inputs.get(row) {
+                    Some(pin) => {
+                        let _ = write!(
+                            label,
+                            "<td port=\"i_{}\" align=\"left\">{}</td>",
+                            html_escape(pin),
+                            html_escape(&pin_text(pin, block.inputs.get(pin), options))
+                        );
+                    }
+                    None => label.push_str("<td></td>"),
+                }
+                match outputs.get(row) {
+                    Some(pin) => {
+                        let _ = write!(
+                            label,
+                            "<td port=\"o_{}\" align=\"right\">{}</td>",
+                            html_escape(pin),
+                            html_escape(&pin_text(pin, block.outputs.get(pin), options))
+                        );
+                    }
+                    None => label.push_str("<td></td>"),
+                }
+                label.push_str("</tr>");
+            }
+            label.push_str("</table>");
+
+            let _ = writeln!(dot, "  {} [label=<{label}>];", dot_id(id));
+        }
+
+        for link in self.drawn_links() {
+            // A link sourced from an input (input fan-out) leaves from the
+            // input's port.
+            let source_port = match pins.get(&link.source_block_uuid) {
+                Some((inputs, outputs))
+                    if !outputs.contains(&link.source_block_pin_name)
+                        && inputs.contains(&link.source_block_pin_name) =>
+                {
+                    format!("i_{}", link.source_block_pin_name)
+                }
+                _ => format!("o_{}", link.source_block_pin_name),
+            };
+            let _ = writeln!(
+                dot,
+                "  {}:{}:e -> {}:{}:w;",
+                dot_id(&link.source_block_uuid),
+                dot_id(&source_port),
+                dot_id(&link.target_block_uuid),
+                dot_id(&format!("i_{}", link.target_block_pin_name)),
+            );
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Renders this program as a Mermaid flowchart. Mermaid nodes have
+    /// no ports, so pins are listed inside the node and each link's edge
+    /// is labelled with the pins it joins.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use logic_mesh::base::program::{Program, ProgramBlock};
+    /// use logic_mesh::base::program::diagram::DiagramOptions;
+    ///
+    /// let mut program = Program::default();
+    /// program.blocks.insert("a".to_string(), ProgramBlock {
+    ///     name: "Add".to_string(),
+    ///     lib: "core".to_string(),
+    ///     label: Some("Demand sum".to_string()),
+    ///     ..Default::default()
+    /// });
+    ///
+    /// let mermaid = program.to_mermaid(&DiagramOptions::default());
+    /// assert!(mermaid.starts_with("flowchart LR"));
+    /// assert!(mermaid.contains("Demand sum"));
+    /// ```
+    pub fn to_mermaid(&self, options: &DiagramOptions) -> String {
+        let pins = self.diagram_pins(options);
+        // Mermaid node ids must be plain identifiers; number the blocks.
+        let node_ids: BTreeMap<&str, String> = self
+            .blocks
+            .keys()
+            .enumerate()
+            .map(|(index, id)| (id.as_str(), format!("b{index}")))
+            .collect();
+
+        let mut mermaid = String::from("flowchart LR\n");
+        let mut faulted = Vec::new();
+        let mut inactive = Vec::new();
+
+        for (id, block) in &self.blocks {
+            let node = &node_ids[id.as_str()];
+            let (inputs, outputs) = &pins[id];
+
+            let mut lines = vec![format!("<b>{}</b>", mermaid_escape(&block_title(block)))];
+            match options.states.get(id) {
+                Some(state @ BlockState::Fault { .. }) => {
+                    lines.push(format!("<i>{}</i>", mermaid_escape(&state_text(state))));
+                    faulted.push(node.as_str());
+                }
+                Some(state @ (BlockState::Disabled | BlockState::Terminated)) => {
+                    lines.push(format!("<i>{}</i>", mermaid_escape(&state_text(state))));
+                    inactive.push(node.as_str());
+                }
+                _ => {}
+            }
+            for pin in inputs {
+                lines.push(format!(
+                    "▸ {}",
+                    mermaid_escape(&pin_text(pin, block.inputs.get(pin), options))
+                ));
+            }
+            for pin in outputs {
+                lines.push(format!(
+                    "{} ▸",
+                    mermaid_escape(&pin_text(pin, block.outputs.get(pin), options))
+                ));
+            }
+
+            let _ = writeln!(mermaid, "  {node}[\"{}\"]", lines.join("<br/>"));
+        }
+
+        for link in self.drawn_links() {
+            let source = &node_ids[link.source_block_uuid.as_str()];
+            let target = &node_ids[link.target_block_uuid.as_str()];
+            let _ = writeln!(
+                mermaid,
+                "  {source} -- \"{} → {}\" --> {target}",
+                mermaid_escape(&link.source_block_pin_name),
+                mermaid_escape(&link.target_block_pin_name),
+            );
+        }
+
+        if !faulted.is_empty() {
+            mermaid.push_str("  classDef fault fill:#f8d0d0,stroke:#c00000;\n");
+            let _ = writeln!(mermaid, "  class {} fault;", faulted.join(","));
+        }
+        if !inactive.is_empty() {
+            mermaid.push_str("  classDef inactive fill:#e0e0e0,stroke:#808080;\n");
+            let _ = writeln!(mermaid, "  class {} inactive;", inactive.join(","));
+        }
+
+        mermaid
+    }
+
+    /// The links between blocks of the program, skipping any whose source
+    /// or target block is missing.
+    fn drawn_links(&self) -> impl Iterator<Item = &LinkData> {
+        self.links.values().filter(|link| {
+            self.blocks.contains_key(&link.source_block_uuid)
+                && self.blocks.contains_key(&link.target_block_uuid)
+        })
+    }
+
+    /// The input and output pins to draw for every block, keyed by block
+    /// id. Declared pins come first in declaration order, followed by any
+    /// the registry does not know about (unregistered or external blocks).
+    fn diagram_pins(
+        &self,
+        options: &DiagramOptions,
+    ) -> BTreeMap<&String, (Vec<String>, Vec<String>)> {
+        let mut linked_inputs: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+        let mut linked_outputs: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+        for link in self.links.values() {
+            linked_outputs
+                .entry(&link.source_block_uuid)
+                .or_default()
+                .insert(&link.source_block_pin_name);
+            linked_inputs
+                .entry(&link.target_block_uuid)
+                .or_default()
+                .insert(&link.target_block_pin_name);
+        }
+
+        self.blocks
+            .iter()
+            .map(|(id, block)| {
+                let desc = get_block(&block.name, Some(&block.lib)).map(|entry| entry.desc);
+                let declared_inputs: Vec<&str> = desc
+                    .as_ref()
+                    .map(|desc| desc.inputs.iter().map(|pin| pin.name.as_str()).collect())
+                    .unwrap_or_default();
+                let declared_outputs: Vec<&str> = desc
+                    .as_ref()
+                    .map(|desc| desc.outputs.iter().map(|pin| pin.name.as_str()).collect())
+                    .unwrap_or_default();
+
+                // Links may leave from an input (fan-out); such a source
+                // pin is drawn on the input side.
+                let is_input = |pin: &str| {
+                    !declared_outputs.contains(&pin)
+                        && (declared_inputs.contains(&pin) || block.inputs.contains_key(pin))
+                };
+                let linked_in = linked_inputs.get(id.as_str()).into_iter().flatten();
+                let linked_out = linked_outputs.get(id.as_str()).into_iter().flatten();
+
+                let inputs = select_pins(
+                    &declared_inputs,
+                    &block.inputs,
+                    linked_in.chain(linked_out.clone().filter(|pin| is_input(pin))),
+                    options.all_pins,
+                );
+                let outputs = select_pins(
+                    &declared_outputs,
+                    &block.outputs,
+                    linked_out.filter(|pin| !is_input(pin)),
+                    options.all_pins,
+                );
+
+                (id, (inputs, outputs))
+            })
+            .collect()
+    }
+}
+
+/// Picks the pins to draw on one side of a block.
+fn select_pins<'a>(
+    declared: &[&'a str],
+    stored: &'a BTreeMap<String, PinValue>,
+    linked: impl Iterator<Item = &'a &'a str>,
+    all_pins: bool,
+) -> Vec<String> {
+    let linked: BTreeSet<&str> = linked.copied().collect();
+    let shown = |pin: &str| {
+        all_pins
+            || linked.contains(pin)
+            || stored
+                .get(pin)
+                .is_some_and(|pin| !matches!(pin.value, libhaystack::val::Value::Null))
+    };
+
+    let mut pins: Vec<String> = declared
+        .iter()
+        .filter(|pin| shown(pin))
+        .map(|pin| pin.to_string())
+        .collect();
+
+    let extra: BTreeSet<&str> = stored
+        .keys()
+        .map(String::as_str)
+        .chain(linked.iter().copied())
+        .filter(|pin| !declared.contains(pin) && shown(pin))
+        .collect();
+    pins.extend(extra.into_iter().map(str::to_string));
+
+    pins
+}
+
+/// `label (lib::Name)`, or just the qualified name for unlabelled blocks.
+fn block_title(block: &ProgramBlock) -> String {
+    match &block.label {
+        Some(label) => format!("{label} ({}::{})", block.lib, block.name),
+        None => format!("{}::{}", block.lib, block.name),
+    }
+}
+
+fn state_text(state: &BlockState) -> String {
+    match state.fault_reason() {
+        Some(reason) => format!("fault: {reason}"),
+        None => state.label().to_string(),
+    }
+}
+
+fn pin_text(pin: &str, value: Option<&PinValue>, options: &DiagramOptions) -> String {
+    match value {
+        Some(value) if options.values && !matches!(value.value, libhaystack::val::Value::Null) => {
+            format!("{pin} = {}", value.value)
+        }
+        _ => pin.to_string(),
+    }
+}
+
+/// Quotes a DOT identifier.
+fn dot_id(id: &str) -> String {
+    format!("\"{}\"", id.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn mermaid_escape(text: &str) -> String {
+    text.replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+}
+
+#[cfg(test)]
+mod test {
+    use crate::base::block::BlockState;
+    use crate::base::program::data::{LinkData, PinValue, Program, ProgramBlock};
+
+    use super::DiagramOptions;
+
+    const SINE: &str = "00000000-0000-0000-0000-000000000000";
+    const ADD: &str = "00000000-0000-0000-0000-000000000001";
+
+    fn sample_program() -> Program {
+        let mut program = Program {
+            name: Some("demo".to_string()),
+            ..Default::default()
+        };
+
+        let mut sine = ProgramBlock {
+            name: "SineWave".to_string(),
+            lib: "core".to_string(),
+            label: Some("Wave".to_string()),
+            ..Default::default()
+        };
+        sine.inputs.insert(
+            "amplitude".to_string(),
+            PinValue {
+                value: 3.into(),
+                is_connected: false,
+            },
+        );
+        program.blocks.insert(SINE.to_string(), sine);
+        program.blocks.insert(
+            ADD.to_string(),
+            ProgramBlock {
+                name: "Add".to_string(),
+                lib: "core".to_string(),
+                ..Default::default()
+            },
+        );
+        program.links.insert(
+            "00000000-0000-0000-0000-0000000000aa".to_string(),
+            LinkData {
+                id: None,
+                source_block_uuid: SINE.to_string(),
+                target_block_uuid: ADD.to_string(),
+                source_block_pin_name: "out".to_string(),
+                target_block_pin_name: "in0".to_string(),
+            },
+        );
+
+        program
+    }
+
+    #[test]
+    fn dot_draws_pins_as_ports_and_links_as_edges() {
+        let dot = sample_program().to_dot(&DiagramOptions::default());
+
+        assert!(dot.starts_with("digraph \"demo\" {"));
+        assert!(dot.contains(&format!("\"{SINE}\":\"o_out\":e -> \"{ADD}\":\"i_in0\":w;")));
+        assert!(dot.contains("port=\"i_amplitude\""));
+        assert!(dot.contains("Wave (core::SineWave)"));
+        // Unlinked, valueless pins are left out by default.
+        assert!(!dot.contains("port=\"i_in1\""));
+        // Values are only shown on request.
+        assert!(!dot.contains("amplitude = 3"));
+    }
+
+    #[test]
+    fn dot_annotates_values_and_faults() {
+        let options = DiagramOptions::default()
+            .with_values()
+            .with_state(ADD, BlockState::fault("unit <mismatch>"));
+        let dot = sample_program().to_dot(&options);
+
+        assert!(dot.contains("amplitude = 3"));
+        assert!(dot.contains("fault: unit &lt;mismatch&gt;"));
+        assert!(dot.contains("bgcolor=\"#f8d0d0\""));
+    }
+
+    #[test]
+    fn dot_can_show_every_declared_pin() {
+        let dot = sample_program().to_dot(&DiagramOptions::default().with_all_pins());
+
+        assert!(dot.contains("port=\"i_in15\""));
+    }
+
+    #[test]
+    fn mermaid_labels_edges_with_pins() {
+        let options = DiagramOptions::default().with_state(ADD, BlockState::fault("boom"));
+        let mermaid = sample_program().to_mermaid(&options);
+
+        assert!(mermaid.starts_with("flowchart LR\n"));
+        assert!(mermaid.contains("b0 -- \"out → in0\" --> b1"));
+        assert!(mermaid.contains("class b1 fault;"));
+        assert!(mermaid.contains("<i>fault: boom</i>"));
+    }
+
+    #[test]
+    fn links_to_missing_blocks_are_left_out() {
+        let mut program = sample_program();
+        program.links.insert(
+            "00000000-0000-0000-0000-0000000000bb".to_string(),
+            LinkData {
+                id: None,
+                source_block_uuid: SINE.to_string(),
+                target_block_uuid: "gone".to_string(),
+                source_block_pin_name: "out".to_string(),
+                target_block_pin_name: "in0".to_string(),
+            },
+        );
+
+        let dot = program.to_dot(&DiagramOptions::default());
+        assert_eq!(dot.matches(" -> ").count(), 1);
+        assert!(!dot.contains("gone"));
+
+        let mermaid = program.to_mermaid(&DiagramOptions::default());
+        assert_eq!(mermaid.matches(" --> ").count(), 1);
+    }
+
+    #[test]
+    fn rendering_is_deterministic() {
+        let program = sample_program();
+
+        // The same program, its blocks and links inserted in reverse.
+        let mut reversed = Program {
+            name: program.name.clone(),
+            ..Default::default()
+        };
+        for (id, block) in program.blocks.iter().rev() {
+            reversed.blocks.insert(id.clone(), block.clone());
+        }
+        for (id, link) in program.links.iter().rev() {
+            reversed.links.insert(id.clone(), link.clone());
+        }
+
+        let options = DiagramOptions::default()
+            .with_values()
+            .with_state(SINE, BlockState::Disabled)
+            .with_state(ADD, BlockState::fault("boom"));
+        assert_eq!(program.to_dot(&options), reversed.to_dot(&options));
+        assert_eq!(program.to_mermaid(&options), reversed.to_mermaid(&options));
+    }
+}
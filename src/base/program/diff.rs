@@ -0,0 +1,1131 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Structural comparison and three-way merge of [`Program`]s.
+//!
+//! Programs are stored keyed by UUID, which makes text diffs of the JSON
+//! hard to read. [`Program::diff`] instead reports what changed in terms
+//! of the program: blocks added, removed or retyped, input constants
+//! changed, links rewired, and label/position-only edits, which
+//! [`ProgramDiff`]'s `Display` renders as a review-friendly summary.
+//!
+//! [`Program::merge`] combines two programs edited from a common base
+//! and reports the edits that collide as [`MergeConflict`]s.
+//!
+//! Only input constants — inputs that are not wired — take part in the
+//! comparison. Wired inputs and outputs carry last-known runtime values
+//! that change on every save, so they are not edits.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Display};
+
+use libhaystack::val::Value;
+
+use super::data::{LinkData, PinValue, Position, Program, ProgramBlock, ProgramParam};
+
+/// A value before and after a change.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change<T> {
+    /// The value in the older program.
+    pub before: T,
+    /// The value in the newer program.
+    pub after: T,
+}
+
+impl<T: PartialEq> Change<T> {
+    /// Returns the change, or [`None`] if both sides are equal.
+    fn between(before: T, after: T) -> Option<Self> {
+        (before != after).then_some(Change { before, after })
+    }
+}
+
+/// How one block differs between two programs.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockChange {
+    /// The block only exists in the newer program.
+    Added(ProgramBlock),
+    /// The block only exists in the older program.
+    Removed(ProgramBlock),
+    /// The block exists in both, with differences.
+    Modified(BlockDiff),
+}
+
+/// Field-level differences of a block present in both programs.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct BlockDiff {
+    /// The block's type, as `(lib, name)`, changed.
+    pub block_type: Option<Change<(String, String)>>,
+    /// Input constants that changed, keyed by pin name. [`None`] means
+    /// the pin carried no constant on that side.
+    pub constants: BTreeMap<String, Change<Option<Value>>>,
+    /// The display label changed.
+    pub label: Option<Change<Option<String>>>,
+    /// The UI position changed.
+    pub position: Option<Change<Option<Position>>>,
+}
+
+impl BlockDiff {
+    /// Returns `true` if only the label or position changed, which does
+    /// not affect what the program computes.
+    pub fn is_layout_only(&self) -> bool {
+        self.block_type.is_none() && self.constants.is_empty()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.is_layout_only() && self.label.is_none() && self.position.is_none()
+    }
+}
+
+/// How one link differs between two programs.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinkChange {
+    /// The link only exists in the newer program.
+    Added(LinkData),
+    /// The link only exists in the older program.
+    Removed(LinkData),
+    /// The link connects different pins in the newer program.
+    Rewired(Change<LinkData>),
+}
+
+/// Structural differences between two programs, as reported by
+/// [`Program::diff`].
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ProgramDiff {
+    /// The program name changed.
+    pub name: Option<Change<Option<String>>>,
+    /// The program description changed.
+    pub description: Option<Change<Option<String>>>,
+    /// Changed blocks keyed by block UUID.
+    pub blocks: BTreeMap<String, BlockChange>,
+    /// Changed links keyed by link UUID.
+    pub links: BTreeMap<String, LinkChange>,
+    /// Changed template parameters keyed by name. [`None`] means the
+    /// parameter is not declared on that side.
+    pub params: BTreeMap<String, Change<Option<ProgramParam>>>,
+}
+
+impl ProgramDiff {
+    /// Returns `true` if the programs are structurally the same.
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none()
+            && self.description.is_none()
+            && self.blocks.is_empty()
+            && self.links.is_empty()
+            && self.params.is_empty()
+    }
+
+    /// Returns `true` if the only changes are block labels and positions
+    /// or the program's name and description — edits that do not change
+    /// what the program computes.
+    pub fn is_layout_only(&self) -> bool {
+        self.links.is_empty()
+            && self.params.is_empty()
+            && self.blocks.values().all(|change| match change {
+                BlockChange::Modified(diff) => diff.is_layout_only(),
+                _ => false,
+            })
+    }
+}
+
+impl Program {
+    /// Compares this program (the older one) with `other` (the newer
+    /// one).
+    ///
+    /// Links are matched by UUID, and then by their endpoints: a link
+    /// that was deleted and recreated between the same pins under a new
+    /// UUID is not reported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use logic_mesh::base::program::{PinValue, Program, ProgramBlock};
+    /// use logic_mesh::base::program::diff::BlockChange;
+    ///
+    /// let mut before = Program::default();
+    /// before.blocks.insert("a".to_string(), ProgramBlock {
+    ///     name: "Add".to_string(),
+    ///     lib: "core".to_string(),
+    ///     ..Default::default()
+    /// });
+    ///
+    /// let mut after = before.clone();
+    /// after.blocks.get_mut("a").unwrap().inputs.insert(
+    ///     "in0".to_string(),
+    ///     PinValue { value: 42.into(), is_connected: false },
+    /// );
+    ///
+    /// let diff = before.diff(&after);
+    /// assert!(matches!(&diff.blocks["a"], BlockChange::Modified(block) if block.constants.contains_key("in0")));
+    /// assert!(!diff.is_layout_only());
+    /// ```
+    pub fn diff(&self, other: &Program) -> ProgramDiff {
+        let mut diff = ProgramDiff {
+            name: Change::between(self.name.clone(), other.name.clone()),
+            description: Change::between(self.description.clone(), other.description.clone()),
+            params: keys(&self.params, &other.params)
+                .into_iter()
+                .filter_map(|name| {
+                    Change::between(
+                        self.params.get(name).cloned(),
+                        other.params.get(name).cloned(),
+                    )
+                    .map(|change| (name.clone(), change))
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        for id in keys(&self.blocks, &other.blocks) {
+            let change = match (self.blocks.get(id), other.blocks.get(id)) {
+                (Some(before), Some(after)) => {
+                    let block = diff_block(before, after);
+                    if block.is_empty() {
+                        continue;
+                    }
+                    BlockChange::Modified(block)
+                }
+                (Some(before), None) => BlockChange::Removed(before.clone()),
+                (None, Some(after)) => BlockChange::Added(after.clone()),
+                (None, None) => continue,
+            };
+            diff.blocks.insert(id.clone(), change);
+        }
+
+        let mut removed = Vec::new();
+        let mut added = Vec::new();
+        for id in keys(&self.links, &other.links) {
+            match (self.links.get(id), other.links.get(id)) {
+                (Some(before), Some(after)) => {
+                    if !same_endpoints(before, after) {
+                        diff.links.insert(
+                            id.clone(),
+                            LinkChange::Rewired(Change {
+                                before: before.clone(),
+                                after: after.clone(),
+                            }),
+                        );
+                    }
+                }
+                (Some(before), None) => removed.push((id, before)),
+                (None, Some(after)) => added.push((id, after)),
+                (None, None) => {}
+            }
+        }
+
+        // A link recreated between the same pins under a new id is the
+        // same wire.
+        added.retain(|(_, after)| {
+            match removed
+                .iter()
+                .position(|(_, before)| same_endpoints(before, after))
+            {
+                Some(index) => {
+                    removed.remove(index);
+                    false
+                }
+                None => true,
+            }
+        });
+        for (id, link) in removed {
+            diff.links
+                .insert(id.clone(), LinkChange::Removed(link.clone()));
+        }
+        for (id, link) in added {
+            diff.links
+                .insert(id.clone(), LinkChange::Added(link.clone()));
+        }
+
+        diff
+    }
+}
+
+fn diff_block(before: &ProgramBlock, after: &ProgramBlock) -> BlockDiff {
+    let before_constants = constants(before);
+    let after_constants = constants(after);
+
+    BlockDiff {
+        block_type: Change::between(
+            (before.lib.clone(), before.name.clone()),
+            (after.lib.clone(), after.name.clone()),
+        ),
+        constants: keys(&before_constants, &after_constants)
+            .into_iter()
+            .filter_map(|pin| {
+                Change::between(
+                    before_constants.get(pin).cloned(),
+                    after_constants.get(pin).cloned(),
+                )
+                .map(|change| (pin.clone(), change))
+            })
+            .collect(),
+        label: Change::between(before.label.clone(), after.label.clone()),
+        position: Change::between(before.positions, after.positions),
+    }
+}
+
+/// The block's input constants: unwired inputs carrying a value.
+fn constants(block: &ProgramBlock) -> BTreeMap<String, Value> {
+    block
+        .inputs
+        .iter()
+        .filter_map(|(name, pin)| constant(pin).map(|value| (name.clone(), value.clone())))
+        .collect()
+}
+
+fn constant(pin: &PinValue) -> Option<&Value> {
+    (!pin.is_connected && !matches!(pin.value, Value::Null)).then_some(&pin.value)
+}
+
+fn same_endpoints(a: &LinkData, b: &LinkData) -> bool {
+    a.source_block_uuid == b.source_block_uuid
+        && a.source_block_pin_name == b.source_block_pin_name
+        && a.target_block_uuid == b.target_block_uuid
+        && a.target_block_pin_name == b.target_block_pin_name
+}
+
+/// Sorted union of the keys of two maps.
+fn keys<'a, V, W>(a: &'a BTreeMap<String, V>, b: &'a BTreeMap<String, W>) -> BTreeSet<&'a String> {
+    a.keys().chain(b.keys()).collect()
+}
+
+impl Display for ProgramDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(change) = &self.name {
+            writeln!(
+                f,
+                "~ name: {} -> {}",
+                opt(&change.before),
+                opt(&change.after)
+            )?;
+        }
+        if let Some(change) = &self.description {
+            writeln!(
+                f,
+                "~ description: {} -> {}",
+                opt(&change.before),
+                opt(&change.after)
+            )?;
+        }
+
+        for (id, change) in &self.blocks {
+            match change {
+                BlockChange::Added(block) => writeln!(f, "+ block {id} {}", block_title(block))?,
+                BlockChange::Removed(block) => writeln!(f, "- block {id} {}", block_title(block))?,
+                BlockChange::Modified(diff) => {
+                    if let Some(Change { before, after }) = &diff.block_type {
+                        writeln!(
+                            f,
+                            "~ block {id} type: {}::{} -> {}::{}",
+                            before.0, before.1, after.0, after.1
+                        )?;
+                    }
+                    for (pin, change) in &diff.constants {
+                        writeln!(
+                            f,
+                            "~ block {id} {pin}: {} -> {}",
+                            opt(&change.before),
+                            opt(&change.after)
+                        )?;
+                    }
+                    if let Some(change) = &diff.label {
+                        writeln!(
+                            f,
+                            "~ block {id} label: {} -> {} (layout)",
+                            opt(&change.before),
+                            opt(&change.after)
+                        )?;
+                    }
+                    if let Some(change) = &diff.position {
+                        let pos = |pos: &Option<Position>| match pos {
+                            Some(Position { x, y }) => format!("({x}, {y})"),
+                            None => "none".to_string(),
+                        };
+                        writeln!(
+                            f,
+                            "~ block {id} position: {} -> {} (layout)",
+                            pos(&change.before),
+                            pos(&change.after)
+                        )?;
+                    }
+                }
+            }
+        }
+
+        for (name, change) in &self.params {
+            match (&change.before, &change.after) {
+                (None, Some(_)) => writeln!(f, "+ param {name}")?,
+                (Some(_), None) => writeln!(f, "- param {name}")?,
+                _ => writeln!(f, "~ param {name}")?,
+            }
+        }
+
+        for (id, change) in &self.links {
+            match change {
+                LinkChange::Added(link) => writeln!(f, "+ link {id} {}", link_title(link))?,
+                LinkChange::Removed(link) => writeln!(f, "- link {id} {}", link_title(link))?,
+                LinkChange::Rewired(Change { before, after }) => writeln!(
+                    f,
+                    "~ link {id} {} => {}",
+                    link_title(before),
+                    link_title(after)
+                )?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn opt<T: Display>(value: &Option<T>) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => "none".to_string(),
+    }
+}
+
+fn block_title(block: &ProgramBlock) -> String {
+    match &block.label {
+        Some(label) => format!("{}::{} \"{label}\"", block.lib, block.name),
+        None => format!("{}::{}", block.lib, block.name),
+    }
+}
+
+fn link_title(link: &LinkData) -> String {
+    format!(
+        "{}.{} -> {}.{}",
+        link.source_block_uuid,
+        link.source_block_pin_name,
+        link.target_block_uuid,
+        link.target_block_pin_name
+    )
+}
+
+/// Which side of a three-way merge made an edit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    /// The first edited program passed to [`Program::merge`].
+    Ours,
+    /// The second edited program passed to [`Program::merge`].
+    Theirs,
+}
+
+/// A block field that both sides of a merge changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockField {
+    /// The block's library and type name.
+    Type,
+    /// The constant on the named input.
+    Constant(String),
+    /// The display label.
+    Label,
+    /// The UI position.
+    Position,
+}
+
+/// An edit that could not be merged automatically.
+///
+/// Field conflicts resolve to [`Side::Ours`]. A block or link removed on
+/// one side and changed on the other is kept in its changed form, so no
+/// edit is silently lost. Links left without a block at either end are
+/// dropped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergeConflict {
+    /// Both sides changed the program name or description differently.
+    ProgramField {
+        /// `"name"` or `"description"`.
+        field: &'static str,
+    },
+    /// Both sides changed the same block field differently.
+    BlockField {
+        /// The block UUID.
+        block: String,
+        /// The conflicting field.
+        field: BlockField,
+    },
+    /// Both sides added a block under the same UUID, differently.
+    BlockAddedTwice {
+        /// The block UUID.
+        block: String,
+    },
+    /// One side removed a block the other side changed.
+    BlockRemovedAndChanged {
+        /// The block UUID.
+        block: String,
+        /// The side that removed the block.
+        removed_by: Side,
+    },
+    /// Both sides rewired, or added under the same UUID, a link
+    /// differently.
+    LinkChangedTwice {
+        /// The link UUID.
+        link: String,
+    },
+    /// One side removed a link the other side rewired.
+    LinkRemovedAndChanged {
+        /// The link UUID.
+        link: String,
+        /// The side that removed the link.
+        removed_by: Side,
+    },
+    /// Both sides changed, added or removed the same template parameter
+    /// differently.
+    Param {
+        /// The parameter name.
+        param: String,
+    },
+    /// The merged link refers to a block the merge removed.
+    DanglingLink {
+        /// The link UUID.
+        link: String,
+        /// The missing block UUID.
+        block: String,
+    },
+}
+
+impl Display for MergeConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MergeConflict::ProgramField { field } => {
+                write!(f, "Both sides changed the program {field}")
+            }
+            MergeConflict::BlockField { block, field } => {
+                let field = match field {
+                    BlockField::Type => "type".to_string(),
+                    BlockField::Constant(pin) => format!("input '{pin}'"),
+                    BlockField::Label => "label".to_string(),
+                    BlockField::Position => "position".to_string(),
+                };
+                write!(f, "Both sides changed the {field} of block '{block}'")
+            }
+            MergeConflict::BlockAddedTwice { block } => {
+                write!(f, "Both sides added block '{block}' differently")
+            }
+            MergeConflict::BlockRemovedAndChanged { block, removed_by } => write!(
+                f,
+                "Block '{block}' was removed by {} and changed by {}",
+                side(*removed_by),
+                side(other(*removed_by))
+            ),
+            MergeConflict::LinkChangedTwice { link } => {
+                write!(f, "Both sides changed link '{link}' differently")
+            }
+            MergeConflict::LinkRemovedAndChanged { link, removed_by } => write!(
+                f,
+                "Link '{link}' was removed by {} and changed by {}",
+                side(*removed_by),
+                side(other(*removed_by))
+            ),
+            MergeConflict::Param { param } => {
+                write!(f, "Both sides changed parameter '{param}' differently")
+            }
+            MergeConflict::DanglingLink { link, block } => {
+                write!(f, "Link '{link}' refers to removed block '{block}'")
+            }
+        }
+    }
+}
+
+fn side(side: Side) -> &'static str {
+    match side {
+        Side::Ours => "ours",
+        Side::Theirs => "theirs",
+    }
+}
+
+fn other(side: Side) -> Side {
+    match side {
+        Side::Ours => Side::Theirs,
+        Side::Theirs => Side::Ours,
+    }
+}
+
+/// Outcome of [`Program::merge`].
+#[derive(Debug, Clone)]
+pub struct MergeResult {
+    /// The merged program, with conflicts resolved as described on
+    /// [`MergeConflict`].
+    pub program: Program,
+    /// The edits that could not be merged automatically.
+    pub conflicts: Vec<MergeConflict>,
+}
+
+impl MergeResult {
+    /// Returns `true` if the merge needed no conflict resolution.
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+/// Outcome of merging one value three ways.
+enum Merged<T> {
+    Clean(T),
+    Conflict(T),
+}
+
+impl<T> Merged<T> {
+    /// Unwraps the merged value, telling `report` whether it conflicted.
+    fn resolve(self, report: impl FnOnce(bool)) -> T {
+        match self {
+            Merged::Clean(value) => {
+                report(false);
+                value
+            }
+            Merged::Conflict(value) => {
+                report(true);
+                value
+            }
+        }
+    }
+}
+
+/// Merges one value: a side that left the base untouched yields to the
+/// other, and diverging edits conflict, resolved to ours.
+fn merge_value<T: PartialEq + Clone>(base: &T, ours: &T, theirs: &T) -> Merged<T> {
+    if ours == theirs || theirs == base {
+        Merged::Clean(ours.clone())
+    } else if ours == base {
+        Merged::Clean(theirs.clone())
+    } else {
+        Merged::Conflict(ours.clone())
+    }
+}
+
+impl Program {
+    /// Three-way merges `ours` and `theirs`, two programs edited from
+    /// `base`.
+    ///
+    /// Blocks are merged field by field, so one side moving a block and
+    /// the other changing its constants merges cleanly.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use logic_mesh::base::program::{Position, Program, ProgramBlock};
+    ///
+    /// let mut base = Program::default();
+    /// base.blocks.insert("a".to_string(), ProgramBlock {
+    ///     name: "Add".to_string(),
+    ///     lib: "core".to_string(),
+    ///     ..Default::default()
+    /// });
+    ///
+    /// let mut ours = base.clone();
+    /// ours.blocks.get_mut("a").unwrap().label = Some("Sum".to_string());
+    /// let mut theirs = base.clone();
+    /// theirs.blocks.get_mut("a").unwrap().positions = Some(Position { x: 1.0, y: 2.0 });
+    ///
+    /// let merged = Program::merge(&base, &ours, &theirs);
+    /// assert!(merged.is_clean());
+    /// let block = &merged.program.blocks["a"];
+    /// assert_eq!(block.label.as_deref(), Some("Sum"));
+    /// assert!(block.positions.is_some());
+    /// ```
+    pub fn merge(base: &Program, ours: &Program, theirs: &Program) -> MergeResult {
+        let mut conflicts = Vec::new();
+        let mut program = Program::default();
+
+        for (field, base, ours, theirs, target) in [
+            (
+                "name",
+                &base.name,
+                &ours.name,
+                &theirs.name,
+                &mut program.name,
+            ),
+            (
+                "description",
+                &base.description,
+                &ours.description,
+                &theirs.description,
+                &mut program.description,
+            ),
+        ] {
+            *target = match merge_value(base, ours, theirs) {
+                Merged::Clean(value) => value,
+                Merged::Conflict(value) => {
+                    conflicts.push(MergeConflict::ProgramField { field });
+                    value
+                }
+            };
+        }
+
+        let names: BTreeSet<&String> = base
+            .params
+            .keys()
+            .chain(ours.params.keys())
+            .chain(theirs.params.keys())
+            .collect();
+        for name in names {
+            let merged = merge_value(
+                &base.params.get(name),
+                &ours.params.get(name),
+                &theirs.params.get(name),
+            )
+            .resolve(|conflict| {
+                if conflict {
+                    conflicts.push(MergeConflict::Param {
+                        param: name.clone(),
+                    });
+                }
+            });
+            if let Some(param) = merged {
+                program.params.insert(name.clone(), param.clone());
+            }
+        }
+
+        let ids: BTreeSet<&String> = base
+            .blocks
+            .keys()
+            .chain(ours.blocks.keys())
+            .chain(theirs.blocks.keys())
+            .collect();
+        for id in ids {
+            let merged = match (
+                base.blocks.get(id),
+                ours.blocks.get(id),
+                theirs.blocks.get(id),
+            ) {
+                (Some(base), Some(ours), Some(theirs)) => {
+                    Some(merge_block(id, base, ours, theirs, &mut conflicts))
+                }
+                (Some(base), Some(kept), None) | (Some(base), None, Some(kept)) => {
+                    if diff_block(base, kept).is_empty() {
+                        None
+                    } else {
+                        let removed_by = if ours.blocks.contains_key(id) {
+                            Side::Theirs
+                        } else {
+                            Side::Ours
+                        };
+                        conflicts.push(MergeConflict::BlockRemovedAndChanged {
+                            block: id.clone(),
+                            removed_by,
+                        });
+                        Some(kept.clone())
+                    }
+                }
+                (None, Some(ours), Some(theirs)) => {
+                    if ours != theirs {
+                        conflicts.push(MergeConflict::BlockAddedTwice { block: id.clone() });
+                    }
+                    Some(ours.clone())
+                }
+                (None, Some(added), None) | (None, None, Some(added)) => Some(added.clone()),
+                (Some(_), None, None) | (None, None, None) => None,
+            };
+            if let Some(block) = merged {
+                program.blocks.insert(id.clone(), block);
+            }
+        }
+
+        let ids: BTreeSet<&String> = base
+            .links
+            .keys()
+            .chain(ours.links.keys())
+            .chain(theirs.links.keys())
+            .collect();
+        for id in ids {
+            let merged = match (base.links.get(id), ours.links.get(id), theirs.links.get(id)) {
+                (Some(base), Some(ours), Some(theirs)) => match merge_value(base, ours, theirs) {
+                    Merged::Clean(link) => Some(link),
+                    Merged::Conflict(link) => {
+                        conflicts.push(MergeConflict::LinkChangedTwice { link: id.clone() });
+                        Some(link)
+                    }
+                },
+                (Some(base), Some(kept), None) | (Some(base), None, Some(kept)) => {
+                    if same_endpoints(base, kept) {
+                        None
+                    } else {
+                        let removed_by = if ours.links.contains_key(id) {
+                            Side::Theirs
+                        } else {
+                            Side::Ours
+                        };
+                        conflicts.push(MergeConflict::LinkRemovedAndChanged {
+                            link: id.clone(),
+                            removed_by,
+                        });
+                        Some(kept.clone())
+                    }
+                }
+                (None, Some(ours), Some(theirs)) => {
+                    if ours != theirs {
+                        conflicts.push(MergeConflict::LinkChangedTwice { link: id.clone() });
+                    }
+                    Some(ours.clone())
+                }
+                (None, Some(added), None) | (None, None, Some(added)) => Some(added.clone()),
+                (Some(_), None, None) | (None, None, None) => None,
+            };
+            let Some(link) = merged else {
+                continue;
+            };
+
+            let missing = [&link.source_block_uuid, &link.target_block_uuid]
+                .into_iter()
+                .find(|block| !program.blocks.contains_key(*block));
+            match missing {
+                Some(block) => conflicts.push(MergeConflict::DanglingLink {
+                    link: id.clone(),
+                    block: block.clone(),
+                }),
+                None => {
+                    program.links.insert(id.clone(), link);
+                }
+            }
+        }
+
+        MergeResult { program, conflicts }
+    }
+}
+
+fn merge_block(
+    id: &str,
+    base: &ProgramBlock,
+    ours: &ProgramBlock,
+    theirs: &ProgramBlock,
+    conflicts: &mut Vec<MergeConflict>,
+) -> ProgramBlock {
+    let mut resolve = |field: BlockField, conflict: bool| {
+        if conflict {
+            conflicts.push(MergeConflict::BlockField {
+                block: id.to_string(),
+                field,
+            });
+        }
+    };
+
+    let (lib, name) = merge_value(
+        &(&base.lib, &base.name),
+        &(&ours.lib, &ours.name),
+        &(&theirs.lib, &theirs.name),
+    )
+    .resolve(|conflict| resolve(BlockField::Type, conflict));
+    let label = merge_value(&base.label, &ours.label, &theirs.label)
+        .resolve(|conflict| resolve(BlockField::Label, conflict));
+    let positions = merge_value(&base.positions, &ours.positions, &theirs.positions)
+        .resolve(|conflict| resolve(BlockField::Position, conflict));
+
+    let mut block = ProgramBlock {
+        lib: lib.clone(),
+        name: name.clone(),
+        label,
+        positions,
+        inputs: BTreeMap::new(),
+        // Outputs are last-known runtime values, not edits.
+        outputs: ours.outputs.clone(),
+    };
+
+    let pins: BTreeSet<&String> = base
+        .inputs
+        .keys()
+        .chain(ours.inputs.keys())
+        .chain(theirs.inputs.keys())
+        .collect();
+    for pin in pins {
+        let base_pin = base.inputs.get(pin);
+        let ours_pin = ours.inputs.get(pin);
+        let theirs_pin = theirs.inputs.get(pin);
+
+        // Decide on the constant; carry the whole pin from the side
+        // whose constant wins.
+        let constant = |pin: Option<&PinValue>| pin.and_then(constant).cloned();
+        let merged = match merge_value(
+            &constant(base_pin),
+            &constant(ours_pin),
+            &constant(theirs_pin),
+        ) {
+            Merged::Clean(value) if value == constant(ours_pin) => ours_pin.or(theirs_pin),
+            Merged::Clean(_) => theirs_pin,
+            Merged::Conflict(_) => {
+                conflicts.push(MergeConflict::BlockField {
+                    block: id.to_string(),
+                    field: BlockField::Constant(pin.clone()),
+                });
+                ours_pin
+            }
+        };
+        if let Some(merged) = merged {
+            block.inputs.insert(pin.clone(), merged.clone());
+        }
+    }
+
+    block
+}
+
+#[cfg(test)]
+mod test {
+    use crate::base::program::data::{LinkData, PinValue, Position, Program, ProgramBlock};
+
+    use super::{BlockChange, BlockField, LinkChange, MergeConflict, Side};
+
+    fn block(name: &str) -> ProgramBlock {
+        ProgramBlock {
+            name: name.to_string(),
+            lib: "core".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn constant(value: i32) -> PinValue {
+        PinValue {
+            value: value.into(),
+            is_connected: false,
+        }
+    }
+
+    fn link(source: &str, target: &str, pin: &str) -> LinkData {
+        LinkData {
+            id: None,
+            source_block_uuid: source.to_string(),
+            target_block_uuid: target.to_string(),
+            source_block_pin_name: "out".to_string(),
+            target_block_pin_name: pin.to_string(),
+        }
+    }
+
+    fn base() -> Program {
+        let mut program = Program::default();
+        let mut a = block("Add");
+        a.inputs.insert("in0".to_string(), constant(1));
+        program.blocks.insert("a".to_string(), a);
+        program.blocks.insert("b".to_string(), block("Add"));
+        program
+            .links
+            .insert("l1".to_string(), link("a", "b", "in0"));
+        program
+    }
+
+    #[test]
+    fn identical_programs_have_no_diff() {
+        assert!(base().diff(&base()).is_empty());
+    }
+
+    #[test]
+    fn diff_reports_structural_changes() {
+        let before = base();
+        let mut after = base();
+        after.blocks.get_mut("a").unwrap().name = "Sub".to_string();
+        after
+            .blocks
+            .get_mut("a")
+            .unwrap()
+            .inputs
+            .insert("in0".to_string(), constant(2));
+        after.blocks.remove("b");
+        after.blocks.insert("c".to_string(), block("Max"));
+        after.links.insert("l1".to_string(), link("a", "c", "a"));
+
+        let diff = before.diff(&after);
+
+        assert!(matches!(diff.blocks["b"], BlockChange::Removed(_)));
+        assert!(matches!(diff.blocks["c"], BlockChange::Added(_)));
+        let BlockChange::Modified(a) = &diff.blocks["a"] else {
+            panic!("block 'a' should be modified");
+        };
+        assert_eq!(
+            a.block_type.as_ref().map(|change| change.after.1.as_str()),
+            Some("Sub")
+        );
+        assert_eq!(a.constants["in0"].before, Some(1.into()));
+        assert_eq!(a.constants["in0"].after, Some(2.into()));
+        assert!(matches!(diff.links["l1"], LinkChange::Rewired(_)));
+        assert!(!diff.is_layout_only());
+
+        let text = diff.to_string();
+        assert!(text.contains("~ block a type: core::Add -> core::Sub"));
+        assert!(text.contains("~ block a in0: 1 -> 2"));
+        assert!(text.contains("- block b core::Add"));
+        assert!(text.contains("+ block c core::Max"));
+    }
+
+    #[test]
+    fn layout_only_changes_are_flagged() {
+        let before = base();
+        let mut after = base();
+        let a = after.blocks.get_mut("a").unwrap();
+        a.label = Some("Sum".to_string());
+        a.positions = Some(Position { x: 1.0, y: 2.0 });
+
+        let diff = before.diff(&after);
+        assert!(!diff.is_empty());
+        assert!(diff.is_layout_only());
+        assert!(diff.to_string().contains("(layout)"));
+    }
+
+    /// Wired inputs and outputs carry runtime values, which change on
+    /// every save without anyone editing the program.
+    #[test]
+    fn runtime_values_are_not_changes() {
+        let before = base();
+        let mut after = base();
+        let b = after.blocks.get_mut("b").unwrap();
+        b.inputs.insert(
+            "in0".to_string(),
+            PinValue {
+                value: 99.into(),
+                is_connected: true,
+            },
+        );
+        b.outputs.insert("out".to_string(), constant(99));
+
+        assert!(before.diff(&after).is_empty());
+    }
+
+    #[test]
+    fn recreated_links_are_not_changes() {
+        let before = base();
+        let mut after = base();
+        let link = after.links.remove("l1").unwrap();
+        after.links.insert("l2".to_string(), link);
+
+        assert!(before.diff(&after).is_empty());
+    }
+
+    #[test]
+    fn diff_reports_param_changes() {
+        let before = base();
+        let mut after = base();
+        after
+            .params
+            .insert("zoneName".to_string(), Default::default());
+
+        let diff = before.diff(&after);
+        assert!(diff.params["zoneName"].before.is_none());
+        assert!(!diff.is_layout_only());
+        assert!(diff.to_string().contains("+ param zoneName"));
+    }
+
+    #[test]
+    fn merge_combines_independent_edits() {
+        let base = base();
+
+        let mut ours = base.clone();
+        ours.blocks
+            .get_mut("a")
+            .unwrap()
+            .inputs
+            .insert("in0".to_string(), constant(5));
+        ours.blocks.insert("c".to_string(), block("Max"));
+
+        let mut theirs = base.clone();
+        theirs.blocks.get_mut("a").unwrap().label = Some("Sum".to_string());
+        theirs.links.insert("l2".to_string(), link("a", "b", "in1"));
+
+        let merged = Program::merge(&base, &ours, &theirs);
+
+        assert!(merged.is_clean(), "{:?}", merged.conflicts);
+        let a = &merged.program.blocks["a"];
+        assert_eq!(a.inputs["in0"].value, 5.into());
+        assert_eq!(a.label.as_deref(), Some("Sum"));
+        assert!(merged.program.blocks.contains_key("c"));
+        assert!(merged.program.links.contains_key("l2"));
+    }
+
+    #[test]
+    fn merge_reports_conflicting_edits() {
+        let base = base();
+
+        let mut ours = base.clone();
+        ours.blocks
+            .get_mut("a")
+            .unwrap()
+            .inputs
+            .insert("in0".to_string(), constant(5));
+
+        let mut theirs = base.clone();
+        theirs
+            .blocks
+            .get_mut("a")
+            .unwrap()
+            .inputs
+            .insert("in0".to_string(), constant(7));
+
+        let merged = Program::merge(&base, &ours, &theirs);
+
+        assert_eq!(
+            merged.conflicts,
+            vec![MergeConflict::BlockField {
+                block: "a".to_string(),
+                field: BlockField::Constant("in0".to_string()),
+            }]
+        );
+        // Resolved to ours.
+        assert_eq!(merged.program.blocks["a"].inputs["in0"].value, 5.into());
+    }
+
+    #[test]
+    fn merge_keeps_a_block_removed_on_one_side_and_changed_on_the_other() {
+        let base = base();
+
+        let mut ours = base.clone();
+        ours.blocks.remove("b");
+        ours.links.remove("l1");
+
+        let mut theirs = base.clone();
+        theirs.blocks.get_mut("b").unwrap().label = Some("Kept".to_string());
+
+        let merged = Program::merge(&base, &ours, &theirs);
+
+        assert_eq!(
+            merged.conflicts,
+            vec![MergeConflict::BlockRemovedAndChanged {
+                block: "b".to_string(),
+                removed_by: Side::Ours,
+            }]
+        );
+        assert_eq!(merged.program.blocks["b"].label.as_deref(), Some("Kept"));
+    }
+
+    /// A block only re-saved on one side, with new runtime values, is not
+    /// changed, so removing it on the other side is clean.
+    #[test]
+    fn merge_removes_a_block_whose_runtime_values_changed() {
+        let base = base();
+
+        let mut ours = base.clone();
+        ours.blocks.remove("b");
+        ours.links.remove("l1");
+
+        let mut theirs = base.clone();
+        let b = theirs.blocks.get_mut("b").unwrap();
+        b.inputs.insert(
+            "in0".to_string(),
+            PinValue {
+                value: 99.into(),
+                is_connected: true,
+            },
+        );
+        b.outputs.insert("out".to_string(), constant(99));
+        theirs.links.get_mut("l1").unwrap().id = Some("l1".to_string());
+
+        let merged = Program::merge(&base, &ours, &theirs);
+
+        assert!(merged.is_clean(), "{:?}", merged.conflicts);
+        assert!(!merged.program.blocks.contains_key("b"));
+        assert!(merged.program.links.is_empty());
+    }
+
+    #[test]
+    fn merge_drops_links_to_removed_blocks() {
+        let base = base();
+
+        let mut ours = base.clone();
+        ours.blocks.remove("b");
+        ours.links.remove("l1");
+
+        let mut theirs = base.clone();
+        theirs.links.insert("l2".to_string(), link("a", "b", "in1"));
+
+        let merged = Program::merge(&base, &ours, &theirs);
+
+        assert_eq!(
+            merged.conflicts,
+            vec![MergeConflict::DanglingLink {
+                link: "l2".to_string(),
+                block: "b".to_string(),
+            }]
+        );
+        assert!(merged.program.links.is_empty());
+    }
+}
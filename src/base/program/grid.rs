@@ -0,0 +1,683 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Haystack grid form of a [`Program`].
+//!
+//! A program maps to a grid with one row per block and one row per
+//! link, so Haystack servers can store and query it as ordinary records
+//! next to the points it controls.
+//!
+//! Grid meta carries the `logicProgram` marker, the `formatVersion`
+//! number and the optional program `name` and `description`. Template
+//! programs add a `params` Dict of parameter name to `{default, dis}`,
+//! both optional.
+//!
+//! Block rows:
+//!
+//! | Tag       | Kind   | Content                                     |
+//! | --------- | ------ | ------------------------------------------- |
+//! | `id`      | Ref    | Block UUID                                  |
+//! | `block`   | Marker |                                             |
+//! | `name`    | Str    | Block type name                             |
+//! | `lib`     | Str    | Block library                               |
+//! | `dis`     | Str    | Block label, if any                         |
+//! | `x`, `y`  | Number | UI position, if any                         |
+//! | `inputs`  | Dict   | Pin name to `{val, connected}`, if any      |
+//! | `outputs` | Dict   | Pin name to `{val, connected}`, if any      |
+//!
+//! Link rows:
+//!
+//! | Tag         | Kind   | Content                     |
+//! | ----------- | ------ | --------------------------- |
+//! | `id`        | Ref    | Link UUID                   |
+//! | `link`      | Marker |                             |
+//! | `sourceRef` | Ref    | Source block UUID           |
+//! | `sourcePin` | Str    | Source pin name             |
+//! | `targetRef` | Ref    | Target block UUID           |
+//! | `targetPin` | Str    | Target input name           |
+
+use std::collections::BTreeMap;
+
+use libhaystack::{
+    encoding::zinc,
+    val::{Dict, Grid, Value, kind::HaystackKind},
+};
+
+use super::data::{LinkData, PinValue, Position, Program, ProgramBlock, ProgramParam};
+use super::version::{PROGRAM_FORMAT_VERSION, upgrade};
+use crate::base::error::{ProgramError, Result, ValueError};
+
+impl Program {
+    /// Converts this program to a Haystack grid with one row per block
+    /// and per link. See the [module docs](self) for the row layout.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use logic_mesh::base::program::{Program, ProgramBlock};
+    ///
+    /// let mut program = Program::default();
+    /// program.blocks.insert(
+    ///     "00000000-0000-0000-0000-000000000000".to_string(),
+    ///     ProgramBlock {
+    ///         name: "Add".to_string(),
+    ///         lib: "core".to_string(),
+    ///         ..Default::default()
+    ///     },
+    /// );
+    ///
+    /// let grid = program.to_grid();
+    /// assert_eq!(grid.len(), 1);
+    /// assert_eq!(Program::from_grid(&grid)?.blocks.len(), 1);
+    /// # Ok::<(), logic_mesh::ProgramError>(())
+    /// ```
+    pub fn to_grid(&self) -> Grid {
+        let mut rows = Vec::with_capacity(self.blocks.len() + self.links.len());
+
+        for (id, block) in &self.blocks {
+            let mut row = Dict::new();
+            row.insert("id".into(), Value::make_ref(id));
+            row.insert("block".into(), Value::make_marker());
+            row.insert("name".into(), block.name.as_str().into());
+            row.insert("lib".into(), block.lib.as_str().into());
+            if let Some(label) = &block.label {
+                row.insert("dis".into(), label.as_str().into());
+            }
+            if let Some(pos) = block.positions {
+                row.insert("x".into(), pos.x.into());
+                row.insert("y".into(), pos.y.into());
+            }
+            if !block.inputs.is_empty() {
+                row.insert("inputs".into(), pins_to_dict(&block.inputs));
+            }
+            if !block.outputs.is_empty() {
+                row.insert("outputs".into(), pins_to_dict(&block.outputs));
+            }
+            rows.push(row);
+        }
+
+        for (id, link) in &self.links {
+            let mut row = Dict::new();
+            row.insert("id".into(), Value::make_ref(id));
+            row.insert("link".into(), Value::make_marker());
+            row.insert("sourceRef".into(), Value::make_ref(&link.source_block_uuid));
+            row.insert(
+                "sourcePin".into(),
+                link.source_block_pin_name.as_str().into(),
+            );
+            row.insert("targetRef".into(), Value::make_ref(&link.target_block_uuid));
+            row.insert(
+                "targetPin".into(),
+                link.target_block_pin_name.as_str().into(),
+            );
+            rows.push(row);
+        }
+
+        let mut meta = Dict::new();
+        meta.insert("logicProgram".into(), Value::make_marker());
+        meta.insert(
+            "formatVersion".into(),
+            Value::make_int(PROGRAM_FORMAT_VERSION.into()),
+        );
+        if let Some(name) = &self.name {
+            meta.insert("name".into(), name.as_str().into());
+        }
+        if let Some(description) = &self.description {
+            meta.insert("description".into(), description.as_str().into());
+        }
+        if !self.params.is_empty() {
+            let params: Dict = self
+                .params
+                .iter()
+                .map(|(name, param)| {
+                    let mut dict = Dict::new();
+                    if let Some(default) = &param.default {
+                        dict.insert("default".into(), default.clone());
+                    }
+                    if let Some(description) = &param.description {
+                        dict.insert("dis".into(), description.as_str().into());
+                    }
+                    (name.clone(), dict.into())
+                })
+                .collect();
+            meta.insert("params".into(), params.into());
+        }
+
+        Grid::make_from_dicts_with_meta(rows, meta)
+    }
+
+    /// Reads a program back from its Haystack grid form.
+    ///
+    /// Each link's [`id`](LinkData::id) is set to its row `id`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the grid declares an invalid or newer format
+    /// version, a row is neither a block nor a link, a row lacks a
+    /// required tag, or upgrading an older grid fails.
+    pub fn from_grid(grid: &Grid) -> Result<Program, ProgramError> {
+        let meta = grid.meta.as_ref();
+
+        let found = grid_version(meta.and_then(|meta| meta.get("formatVersion")))?;
+        if found > PROGRAM_FORMAT_VERSION {
+            return Err(ProgramError::UnsupportedVersion {
+                found,
+                supported: PROGRAM_FORMAT_VERSION,
+            });
+        }
+
+        let meta_str = |tag: &str| match meta.and_then(|meta| meta.get(tag)) {
+            Some(Value::Str(str)) => Some(str.value.clone()),
+            _ => None,
+        };
+
+        let mut program = Program {
+            name: meta_str("name"),
+            description: meta_str("description"),
+            ..Default::default()
+        };
+
+        if let Some(Value::Dict(params)) = meta.and_then(|meta| meta.get("params")) {
+            for (name, param) in params.iter() {
+                let param = match param {
+                    Value::Dict(param) => ProgramParam {
+                        description: match param.get("dis") {
+                            Some(Value::Str(str)) => Some(str.value.clone()),
+                            _ => None,
+                        },
+                        default: param.get("default").cloned(),
+                    },
+                    _ => ProgramParam::default(),
+                };
+                program.params.insert(name.clone(), param);
+            }
+        }
+
+        for (index, row) in grid.rows.iter().enumerate() {
+            let tags = RowTags { row, index };
+            let id = tags.reference("id")?;
+
+            if row.contains_key("block") {
+                let positions = match (row.get("x"), row.get("y")) {
+                    (Some(Value::Number(x)), Some(Value::Number(y))) => Some(Position {
+                        x: x.value,
+                        y: y.value,
+                    }),
+                    _ => None,
+                };
+
+                program.blocks.insert(
+                    id,
+                    ProgramBlock {
+                        name: tags.str("name")?,
+                        lib: tags.str("lib")?,
+                        label: tags.optional_str("dis"),
+                        positions,
+                        inputs: tags.pins("inputs")?,
+                        outputs: tags.pins("outputs")?,
+                    },
+                );
+            } else if row.contains_key("link") {
+                program.links.insert(
+                    id.clone(),
+                    LinkData {
+                        id: Some(id),
+                        source_block_uuid: tags.reference("sourceRef")?,
+                        target_block_uuid: tags.reference("targetRef")?,
+                        source_block_pin_name: tags.str("sourcePin")?,
+                        target_block_pin_name: tags.str("targetPin")?,
+                    },
+                );
+            } else {
+                return Err(ProgramError::UnknownGridRow { row: index });
+            }
+        }
+
+        upgrade_from(program, found)
+    }
+
+    /// Encodes this program's [grid form](Self::to_grid) as Zinc.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if Zinc encoding fails.
+    pub fn to_zinc(&self) -> Result<String> {
+        grid_to_zinc(self.to_grid())
+    }
+
+    /// Decodes a program from a Zinc-encoded grid.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the text is not a Zinc grid, or the grid is
+    /// not a valid program (see [`from_grid`](Self::from_grid)).
+    pub fn from_zinc(zinc: &str) -> Result<Program> {
+        match zinc::decode::from_str(zinc).map_err(ValueError::from)? {
+            Value::Grid(grid) => Ok(Program::from_grid(&grid)?),
+            other => Err(ValueError::UnexpectedValue {
+                expected: HaystackKind::Grid,
+                actual: Box::new(other),
+            }
+            .into()),
+        }
+    }
+}
+
+/// Returns the format version a grid's `formatVersion` meta declares. A
+/// grid without one predates versioning and is version `0`, as for
+/// [`document_version`](super::version::document_version).
+fn grid_version(version: Option<&Value>) -> Result<u32, ProgramError> {
+    match version {
+        None => Ok(0),
+        Some(Value::Number(number))
+            if number.value.fract() == 0.0 && (0.0..=u32::MAX as f64).contains(&number.value) =>
+        {
+            Ok(number.value as u32)
+        }
+        Some(version) => Err(ProgramError::InvalidVersion {
+            found: version.to_string(),
+        }),
+    }
+}
+
+/// Runs a program read from a grid of version `found` through the
+/// [upgrade chain](upgrade), as loading a JSON document of that version
+/// would.
+fn upgrade_from(program: Program, found: u32) -> Result<Program, ProgramError> {
+    if found == PROGRAM_FORMAT_VERSION {
+        return Ok(program);
+    }
+
+    let failed = |err: serde_json::Error| ProgramError::UpgradeFailed {
+        from: found,
+        reason: err.to_string(),
+    };
+    let mut doc = serde_json::to_value(&program).map_err(failed)?;
+    doc["version"] = found.into();
+    serde_json::from_value(upgrade(doc)?).map_err(failed)
+}
+
+/// Encodes pins as a dict of `{val, connected}` dicts keyed by pin name.
+fn pins_to_dict(pins: &BTreeMap<String, PinValue>) -> Value {
+    let mut dict = Dict::new();
+    for (name, pin) in pins {
+        let mut entry = Dict::new();
+        entry.insert("val".into(), pin.value.clone());
+        if pin.is_connected {
+            entry.insert("connected".into(), Value::make_marker());
+        }
+        dict.insert(name.clone(), entry.into());
+    }
+    dict.into()
+}
+
+/// Typed tag access on one grid row, tagging failures with the row index.
+struct RowTags<'a> {
+    row: &'a Dict,
+    index: usize,
+}
+
+impl RowTags<'_> {
+    fn error(&self, tag: &'static str, expected: HaystackKind) -> ProgramError {
+        ProgramError::GridTag {
+            row: self.index,
+            tag,
+            expected,
+        }
+    }
+
+    fn reference(&self, tag: &'static str) -> Result<String, ProgramError> {
+        match self.row.get(tag) {
+            Some(Value::Ref(reference)) => Ok(reference.value.clone()),
+            _ => Err(self.error(tag, HaystackKind::Ref)),
+        }
+    }
+
+    fn str(&self, tag: &'static str) -> Result<String, ProgramError> {
+        self.optional_str(tag)
+            .ok_or_else(|| self.error(tag, HaystackKind::Str))
+    }
+
+    fn optional_str(&self, tag: &'static str) -> Option<String> {
+        match self.row.get(tag) {
+            Some(Value::Str(str)) => Some(str.value.clone()),
+            _ => None,
+        }
+    }
+
+    fn pins(&self, tag: &'static str) -> Result<BTreeMap<String, PinValue>, ProgramError> {
+        let pins = match self.row.get(tag) {
+            None => return Ok(BTreeMap::new()),
+            Some(Value::Dict(pins)) => pins,
+            Some(_) => return Err(self.error(tag, HaystackKind::Dict)),
+        };
+
+        pins.iter()
+            .map(|(name, pin)| match pin {
+                Value::Dict(pin) => Ok((
+                    name.clone(),
+                    PinValue {
+                        value: pin.get("val").cloned().unwrap_or_default(),
+                        is_connected: pin.contains_key("connected"),
+                    },
+                )),
+                _ => Err(self.error(tag, HaystackKind::Dict)),
+            })
+            .collect()
+    }
+}
+
+/// The line libhaystack's Zinc encoder starts every grid with.
+const ZINC_VERSION_LINE: &str = "ver:\"3.0\"\n";
+
+/// Encodes a grid as Zinc, meta included.
+///
+/// Works around libhaystack 3.1 writing grid meta on a line of its own,
+/// after the version line, where its own decoder reads it as the column
+/// row: the grid is encoded without meta, and the meta tags are put on
+/// the version line. Only the version line is rewritten, and only if it
+/// is exactly [`ZINC_VERSION_LINE`]; otherwise the encoder's output is
+/// used as is. `encoder_drops_grid_meta` in the tests fails once the
+/// encoder is fixed, and this can go.
+pub(crate) fn grid_to_zinc(mut grid: Grid) -> Result<String> {
+    let Some(meta) = grid.meta.take().filter(|meta| !meta.is_empty()) else {
+        return Ok(zinc::encode::to_zinc_string(&Value::from(grid)).map_err(ValueError::from)?);
+    };
+
+    let body =
+        zinc::encode::to_zinc_string(&Value::from(grid.clone())).map_err(ValueError::from)?;
+    let Some(rest) = body.strip_prefix(ZINC_VERSION_LINE) else {
+        grid.meta = Some(meta);
+        return Ok(zinc::encode::to_zinc_string(&Value::from(grid)).map_err(ValueError::from)?);
+    };
+
+    let mut tags = Vec::with_capacity(meta.len());
+    for (name, value) in meta.iter() {
+        tags.push(match value {
+            Value::Marker => name.clone(),
+            value => format!(
+                "{name}:{}",
+                zinc::encode::to_zinc_string(value).map_err(ValueError::from)?
+            ),
+        });
+    }
+    Ok(format!(
+        "{} {}\n{rest}",
+        ZINC_VERSION_LINE.trim_end(),
+        tags.join(" ")
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use libhaystack::{
+        dict,
+        val::{Dict, Grid, Number, Value},
+    };
+
+    use crate::base::error::{Error, ProgramError, ValueError};
+    use crate::base::program::data::{
+        LinkData, PinValue, Position, Program, ProgramBlock, ProgramParam,
+    };
+    use crate::base::program::version::PROGRAM_FORMAT_VERSION;
+
+    const ADD0: &str = "00000000-0000-0000-0000-000000000000";
+    const ADD1: &str = "00000000-0000-0000-0000-000000000001";
+    const LINK: &str = "00000000-0000-0000-0000-0000000000aa";
+
+    fn sample_program() -> Program {
+        let mut program = Program {
+            name: Some("ahu-1".to_string()),
+            description: Some("Supply air reset".to_string()),
+            ..Default::default()
+        };
+
+        let mut add0 = ProgramBlock {
+            name: "Add".to_string(),
+            lib: "core".to_string(),
+            label: Some("Demand sum".to_string()),
+            positions: Some(Position { x: 10.0, y: 20.5 }),
+            ..Default::default()
+        };
+        add0.inputs.insert(
+            "in0".to_string(),
+            PinValue {
+                value: Number::make_with_unit(
+                    72.0,
+                    libhaystack::units::get_unit("°F").expect("unit"),
+                )
+                .into(),
+                is_connected: false,
+            },
+        );
+        program.blocks.insert(ADD0.to_string(), add0);
+
+        let mut add1 = ProgramBlock {
+            name: "Add".to_string(),
+            lib: "core".to_string(),
+            ..Default::default()
+        };
+        add1.inputs.insert(
+            "in0".to_string(),
+            PinValue {
+                value: Value::Null,
+                is_connected: true,
+            },
+        );
+        program.blocks.insert(ADD1.to_string(), add1);
+
+        program.links.insert(
+            LINK.to_string(),
+            LinkData {
+                id: Some(LINK.to_string()),
+                source_block_uuid: ADD0.to_string(),
+                target_block_uuid: ADD1.to_string(),
+                source_block_pin_name: "out".to_string(),
+                target_block_pin_name: "in0".to_string(),
+            },
+        );
+
+        program
+    }
+
+    #[test]
+    fn grid_has_one_row_per_block_and_link() {
+        let grid = sample_program().to_grid();
+
+        assert_eq!(grid.len(), 3);
+        assert_eq!(
+            grid.rows.iter().filter(|r| r.contains_key("block")).count(),
+            2
+        );
+        assert_eq!(
+            grid.rows.iter().filter(|r| r.contains_key("link")).count(),
+            1
+        );
+
+        let link = grid
+            .rows
+            .iter()
+            .find(|r| r.contains_key("link"))
+            .expect("link row");
+        assert_eq!(link.get("sourceRef"), Some(&Value::make_ref(ADD0)));
+        assert_eq!(link.get("targetPin"), Some(&Value::make_str("in0")));
+    }
+
+    #[test]
+    fn zinc_round_trip() {
+        let program = sample_program();
+
+        let zinc = program.to_zinc().expect("encode");
+        let loaded = Program::from_zinc(&zinc).expect("decode");
+
+        assert_eq!(loaded.name.as_deref(), Some("ahu-1"));
+        assert_eq!(loaded.description.as_deref(), Some("Supply air reset"));
+
+        let add0 = &loaded.blocks[ADD0];
+        assert_eq!(add0.name, "Add");
+        assert_eq!(add0.lib, "core");
+        assert_eq!(add0.label.as_deref(), Some("Demand sum"));
+        assert_eq!(add0.positions, Some(Position { x: 10.0, y: 20.5 }));
+        assert_eq!(
+            add0.inputs["in0"].value,
+            program.blocks[ADD0].inputs["in0"].value
+        );
+        assert!(!add0.inputs["in0"].is_connected);
+
+        let add1 = &loaded.blocks[ADD1];
+        assert!(add1.inputs["in0"].is_connected);
+        assert_eq!(add1.label, None);
+        assert_eq!(add1.positions, None);
+
+        let link = &loaded.links[LINK];
+        assert_eq!(link.id.as_deref(), Some(LINK));
+        assert_eq!(link.source_block_uuid, ADD0);
+        assert_eq!(link.target_block_uuid, ADD1);
+        assert_eq!(link.source_block_pin_name, "out");
+        assert_eq!(link.target_block_pin_name, "in0");
+    }
+
+    #[test]
+    fn template_params_round_trip() {
+        let mut program = sample_program();
+        program.params.insert(
+            "occupiedSp".to_string(),
+            ProgramParam {
+                description: Some("Occupied setpoint".to_string()),
+                default: Some(Value::make_number(72.0)),
+            },
+        );
+        program
+            .params
+            .insert("zoneName".to_string(), ProgramParam::default());
+
+        let zinc = program.to_zinc().expect("encode");
+        let loaded = Program::from_zinc(&zinc).expect("decode");
+
+        assert_eq!(loaded.params, program.params);
+    }
+
+    #[test]
+    fn rows_missing_required_tags_are_rejected() {
+        let grid = Grid::make_from_dicts(vec![dict! {
+            "id" => Value::make_ref(ADD0),
+            "block" => Value::make_marker(),
+            "lib" => Value::make_str("core")
+        }]);
+
+        assert_matches!(
+            Program::from_grid(&grid),
+            Err(ProgramError::GridTag {
+                row: 0,
+                tag: "name",
+                ..
+            })
+        );
+    }
+
+    #[test]
+    fn unknown_rows_are_rejected() {
+        let grid = Grid::make_from_dicts(vec![dict! {
+            "id" => Value::make_ref(ADD0),
+            "point" => Value::make_marker()
+        }]);
+
+        assert_matches!(
+            Program::from_grid(&grid),
+            Err(ProgramError::UnknownGridRow { row: 0 })
+        );
+    }
+
+    fn versioned_grid(version: Option<Value>) -> Grid {
+        let mut grid = sample_program().to_grid();
+        let meta = grid.meta.as_mut().expect("program grids have meta");
+        match version {
+            Some(version) => meta.insert("formatVersion".into(), version),
+            None => meta.remove("formatVersion"),
+        };
+        grid
+    }
+
+    #[test]
+    fn older_grids_are_upgraded() {
+        for version in [None, Some(Value::make_number(0.0))] {
+            let loaded = Program::from_grid(&versioned_grid(version)).expect("upgrade");
+
+            assert_eq!(loaded.name.as_deref(), Some("ahu-1"));
+            assert_eq!(loaded.blocks.len(), 2);
+            assert_eq!(loaded.links[LINK].target_block_pin_name, "in0");
+        }
+    }
+
+    #[test]
+    fn invalid_grid_versions_are_rejected() {
+        for version in [
+            Value::make_number(1.5),
+            Value::make_number(-1.0),
+            Value::make_number(1e12),
+            Value::make_number(f64::NAN),
+            Value::make_str("1"),
+        ] {
+            assert_matches!(
+                Program::from_grid(&versioned_grid(Some(version))),
+                Err(ProgramError::InvalidVersion { .. })
+            );
+        }
+
+        let newer = f64::from(PROGRAM_FORMAT_VERSION + 1);
+        assert_matches!(
+            Program::from_grid(&versioned_grid(Some(Value::make_number(newer)))),
+            Err(ProgramError::UnsupportedVersion { found, supported })
+                if found == PROGRAM_FORMAT_VERSION + 1 && supported == PROGRAM_FORMAT_VERSION
+        );
+    }
+
+    fn meta_grid() -> Grid {
+        Grid::make_from_dicts_with_meta(
+            vec![dict! { "id" => Value::make_ref(ADD0), "block" => Value::make_marker() }],
+            dict! {
+                "logicProgram" => Value::make_marker(),
+                "formatVersion" => Value::make_number(1.0),
+                "name" => Value::make_str("ahu-1")
+            },
+        )
+    }
+
+    #[test]
+    fn grid_meta_goes_on_the_version_line() {
+        let zinc = super::grid_to_zinc(meta_grid()).expect("encode");
+
+        assert_eq!(
+            zinc,
+            format!(
+                "ver:\"3.0\" formatVersion:1 logicProgram name:\"ahu-1\"\n\
+                 block,id\nM,@{ADD0}\n\n"
+            )
+        );
+        let Value::Grid(decoded) =
+            libhaystack::encoding::zinc::decode::from_str(&zinc).expect("decode")
+        else {
+            panic!("expected a grid");
+        };
+        assert_eq!(decoded.meta, meta_grid().meta);
+    }
+
+    /// Pins the libhaystack bug [`grid_to_zinc`](super::grid_to_zinc)
+    /// works around; once this fails the workaround can be removed.
+    #[test]
+    fn encoder_drops_grid_meta() {
+        let zinc = libhaystack::encoding::zinc::encode::to_zinc_string(&Value::from(meta_grid()))
+            .expect("encode");
+
+        let decoded = libhaystack::encoding::zinc::decode::from_str(&zinc);
+        assert!(!matches!(decoded, Ok(Value::Grid(grid)) if grid.meta == meta_grid().meta));
+    }
+
+    #[test]
+    fn zinc_that_is_not_a_grid_is_rejected() {
+        assert_matches!(
+            Program::from_zinc("42"),
+            Err(Error::Value(ValueError::UnexpectedValue { .. }))
+        );
+    }
+}
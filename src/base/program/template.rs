@@ -0,0 +1,366 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Parameterized program templates.
+//!
+//! A template is a [`Program`] that declares [`params`](Program::params)
+//! and refers to them as `${name}` in its input values, block labels,
+//! name and description. [`Program::instantiate`] turns a template into
+//! a concrete program, so the same logic can be deployed to many
+//! equipment instances with different constants.
+//!
+//! Substitution rules:
+//!
+//! - An input whose value is a string consisting of a single reference,
+//!   such as `"${occupiedSp}"`, takes the parameter value as is, keeping
+//!   its kind and unit.
+//! - Any other string input, label, name or description has each
+//!   reference replaced by the parameter value as text: strings verbatim,
+//!   other kinds in their Zinc form.
+//! - A `${` without a closing `}` is left as written.
+
+use std::collections::BTreeMap;
+
+use libhaystack::val::{Str, Value};
+use uuid::Uuid;
+
+use crate::base::error::ProgramError;
+
+use super::data::{LinkData, PinValue, Program, ProgramBlock};
+
+impl Program {
+    /// Creates a concrete program from this template.
+    ///
+    /// Each declared parameter takes its value from `values`, or its
+    /// default. Blocks and links get fresh UUIDs, so several instances
+    /// of one template can be loaded side by side. The instance declares
+    /// no parameters.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a parameter without a default is not given a
+    /// value, `values` names a parameter the template does not declare,
+    /// or the template refers to an undeclared parameter.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    ///
+    /// use logic_mesh::base::program::{PinValue, Program, ProgramBlock, ProgramParam};
+    ///
+    /// let mut template = Program::default();
+    /// template.params.insert("occupiedSp".to_string(), ProgramParam::default());
+    /// template.blocks.insert("sp".to_string(), ProgramBlock {
+    ///     name: "Add".to_string(),
+    ///     lib: "core".to_string(),
+    ///     label: Some("${zoneName} setpoint".to_string()),
+    ///     inputs: BTreeMap::from([(
+    ///         "in0".to_string(),
+    ///         PinValue { value: "${occupiedSp}".into(), is_connected: false },
+    ///     )]),
+    ///     ..Default::default()
+    /// });
+    /// template.params.insert("zoneName".to_string(), ProgramParam::default());
+    ///
+    /// let zone = template
+    ///     .instantiate(&BTreeMap::from([
+    ///         ("zoneName".to_string(), "Zone 1".into()),
+    ///         ("occupiedSp".to_string(), 21.5.into()),
+    ///     ]))
+    ///     .unwrap();
+    ///
+    /// let block = zone.blocks.values().next().unwrap();
+    /// assert_eq!(block.label.as_deref(), Some("Zone 1 setpoint"));
+    /// assert_eq!(block.inputs["in0"].value, 21.5.into());
+    /// ```
+    pub fn instantiate(&self, values: &BTreeMap<String, Value>) -> Result<Program, ProgramError> {
+        if let Some(name) = values.keys().find(|name| !self.params.contains_key(*name)) {
+            return Err(ProgramError::UnknownParam { name: name.clone() });
+        }
+
+        let params = self
+            .params
+            .iter()
+            .map(|(name, param)| {
+                values
+                    .get(name)
+                    .or(param.default.as_ref())
+                    .map(|value| (name.as_str(), value))
+                    .ok_or_else(|| ProgramError::MissingParam { name: name.clone() })
+            })
+            .collect::<Result<BTreeMap<_, _>, _>>()?;
+        let params = Params(params);
+
+        let ids: BTreeMap<&String, String> = self
+            .blocks
+            .keys()
+            .map(|id| (id, Uuid::new_v4().to_string()))
+            .collect();
+        let block_id = |id: &String| ids.get(id).cloned().unwrap_or_else(|| id.clone());
+
+        let blocks = self
+            .blocks
+            .iter()
+            .map(|(id, block)| Ok((block_id(id), params.block(block)?)))
+            .collect::<Result<_, ProgramError>>()?;
+
+        let links = self
+            .links
+            .values()
+            .map(|link| {
+                let id = Uuid::new_v4().to_string();
+                let link = LinkData {
+                    id: link.id.as_ref().map(|_| id.clone()),
+                    source_block_uuid: block_id(&link.source_block_uuid),
+                    target_block_uuid: block_id(&link.target_block_uuid),
+                    source_block_pin_name: link.source_block_pin_name.clone(),
+                    target_block_pin_name: link.target_block_pin_name.clone(),
+                };
+                (id, link)
+            })
+            .collect();
+
+        Ok(Program {
+            name: params.optional_text(&self.name)?,
+            description: params.optional_text(&self.description)?,
+            blocks,
+            links,
+            params: BTreeMap::new(),
+        })
+    }
+}
+
+/// Resolved parameter values of one instantiation.
+struct Params<'a>(BTreeMap<&'a str, &'a Value>);
+
+impl Params<'_> {
+    fn block(&self, block: &ProgramBlock) -> Result<ProgramBlock, ProgramError> {
+        Ok(ProgramBlock {
+            label: self.optional_text(&block.label)?,
+            inputs: block
+                .inputs
+                .iter()
+                .map(|(name, pin)| {
+                    let pin = PinValue {
+                        value: self.value(&pin.value)?,
+                        is_connected: pin.is_connected,
+                    };
+                    Ok((name.clone(), pin))
+                })
+                .collect::<Result<_, ProgramError>>()?,
+            ..block.clone()
+        })
+    }
+
+    fn value(&self, value: &Value) -> Result<Value, ProgramError> {
+        let Value::Str(Str { value: text }) = value else {
+            return Ok(value.clone());
+        };
+
+        match text
+            .strip_prefix("${")
+            .and_then(|rest| rest.strip_suffix('}'))
+        {
+            Some(name) if !name.contains(['$', '{', '}']) => self.get(name).cloned(),
+            _ => self.text(text).map(Value::from),
+        }
+    }
+
+    fn optional_text(&self, text: &Option<String>) -> Result<Option<String>, ProgramError> {
+        text.as_deref().map(|text| self.text(text)).transpose()
+    }
+
+    fn text(&self, text: &str) -> Result<String, ProgramError> {
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+
+        while let Some(start) = rest.find("${") {
+            let Some(len) = rest[start + 2..].find('}') else {
+                break;
+            };
+            out.push_str(&rest[..start]);
+            match self.get(&rest[start + 2..start + 2 + len])? {
+                Value::Str(Str { value }) => out.push_str(value),
+                value => out.push_str(&value.to_string()),
+            }
+            rest = &rest[start + 3 + len..];
+        }
+        out.push_str(rest);
+
+        Ok(out)
+    }
+
+    fn get(&self, name: &str) -> Result<&Value, ProgramError> {
+        self.0
+            .get(name)
+            .copied()
+            .ok_or_else(|| ProgramError::UndeclaredParam {
+                name: name.to_string(),
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use libhaystack::units::get_unit;
+    use libhaystack::val::{Number, Value};
+
+    use crate::base::error::ProgramError;
+    use crate::base::program::data::{LinkData, PinValue, Program, ProgramBlock, ProgramParam};
+
+    fn constant(value: impl Into<Value>) -> PinValue {
+        PinValue {
+            value: value.into(),
+            is_connected: false,
+        }
+    }
+
+    fn param(default: Option<Value>) -> ProgramParam {
+        ProgramParam {
+            description: None,
+            default,
+        }
+    }
+
+    fn zone_template() -> Program {
+        let mut program = Program {
+            name: Some("${zoneName} control".to_string()),
+            ..Default::default()
+        };
+        program.params.insert("zoneName".to_string(), param(None));
+        program.params.insert("occupiedSp".to_string(), param(None));
+        program.params.insert(
+            "minFlow".to_string(),
+            param(Some(
+                Number::make_with_unit(100.0, get_unit("cfm").unwrap()).into(),
+            )),
+        );
+
+        program.blocks.insert(
+            "a".to_string(),
+            ProgramBlock {
+                name: "Add".to_string(),
+                lib: "core".to_string(),
+                label: Some("${zoneName} setpoint".to_string()),
+                inputs: BTreeMap::from([
+                    ("in0".to_string(), constant("${occupiedSp}")),
+                    ("in1".to_string(), constant("${minFlow}")),
+                ]),
+                ..Default::default()
+            },
+        );
+        program.blocks.insert(
+            "b".to_string(),
+            ProgramBlock {
+                name: "Max".to_string(),
+                lib: "core".to_string(),
+                inputs: BTreeMap::from([(
+                    "in0".to_string(),
+                    constant("zone ${zoneName} at ${occupiedSp}"),
+                )]),
+                ..Default::default()
+            },
+        );
+        program.links.insert(
+            "l".to_string(),
+            LinkData {
+                id: Some("l".to_string()),
+                source_block_uuid: "a".to_string(),
+                target_block_uuid: "b".to_string(),
+                source_block_pin_name: "out".to_string(),
+                target_block_pin_name: "in1".to_string(),
+            },
+        );
+        program
+    }
+
+    fn values(entries: &[(&str, Value)]) -> BTreeMap<String, Value> {
+        entries
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn instantiate_substitutes_params() {
+        let template = zone_template();
+        let zone = template
+            .instantiate(&values(&[
+                ("zoneName", "Zone 1".into()),
+                ("occupiedSp", 21.5.into()),
+            ]))
+            .unwrap();
+
+        assert_eq!(zone.name.as_deref(), Some("Zone 1 control"));
+        assert!(zone.params.is_empty());
+
+        let add = zone.blocks.values().find(|b| b.name == "Add").unwrap();
+        assert_eq!(add.label.as_deref(), Some("Zone 1 setpoint"));
+        assert_eq!(add.inputs["in0"].value, 21.5.into());
+        // Whole-value references keep the unit of the default.
+        assert_eq!(
+            add.inputs["in1"].value,
+            Number::make_with_unit(100.0, get_unit("cfm").unwrap()).into()
+        );
+
+        let max = zone.blocks.values().find(|b| b.name == "Max").unwrap();
+        assert_eq!(max.inputs["in0"].value, "zone Zone 1 at 21.5".into());
+    }
+
+    #[test]
+    fn instantiate_assigns_fresh_ids() {
+        let template = zone_template();
+        let values = values(&[("zoneName", "Z".into()), ("occupiedSp", 20.into())]);
+        let first = template.instantiate(&values).unwrap();
+        let second = template.instantiate(&values).unwrap();
+
+        let ids = |program: &Program| program.blocks.keys().cloned().collect::<BTreeSet<_>>();
+        assert!(ids(&first).is_disjoint(&ids(&template)));
+        assert!(ids(&first).is_disjoint(&ids(&second)));
+
+        let (id, link) = first.links.iter().next().unwrap();
+        assert_ne!(id, "l");
+        assert_eq!(link.id.as_ref(), Some(id));
+        assert_eq!(first.blocks[&link.source_block_uuid].name, "Add");
+        assert_eq!(first.blocks[&link.target_block_uuid].name, "Max");
+    }
+
+    #[test]
+    fn instantiate_rejects_bad_params() {
+        let template = zone_template();
+
+        assert!(matches!(
+            template.instantiate(&values(&[("zoneName", "Z".into())])),
+            Err(ProgramError::MissingParam { name }) if name == "occupiedSp"
+        ));
+        assert!(matches!(
+            template.instantiate(&values(&[
+                ("zoneName", "Z".into()),
+                ("occupiedSp", 20.into()),
+                ("maxFlow", 20.into()),
+            ])),
+            Err(ProgramError::UnknownParam { name }) if name == "maxFlow"
+        ));
+
+        let mut template = template;
+        template.description = Some("${site}".to_string());
+        assert!(matches!(
+            template.instantiate(&values(&[("zoneName", "Z".into()), ("occupiedSp", 20.into())])),
+            Err(ProgramError::UndeclaredParam { name }) if name == "site"
+        ));
+    }
+
+    #[test]
+    fn params_round_trip_through_json() {
+        let template = zone_template();
+        let json = serde_json::to_string(&template).unwrap();
+        let loaded: Program = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, template);
+
+        // Concrete programs carry no params key.
+        let json = serde_json::to_value(Program::default()).unwrap();
+        assert!(json.get("params").is_none());
+    }
+}
@@ -0,0 +1,172 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Program file format versioning.
+//!
+//! Every serialized [`Program`](super::Program) carries a top-level
+//! `version` field. Documents written before the field existed are
+//! version `0`. On deserialize, [`upgrade`] runs the document through
+//! one step per version until it reaches [`PROGRAM_FORMAT_VERSION`], so
+//! the typed [`Program`](super::Program) only ever has to understand the
+//! current shape.
+//!
+//! Changing the shape of [`LinkData`](super::LinkData),
+//! [`PinValue`](super::PinValue) or any other stored type means appending
+//! a step to `UPGRADES` that rewrites the previous shape into the new
+//! one, and updating [`PROGRAM_JSON_SCHEMA`] to match. Steps are never
+//! edited or removed once released: stored files depend on them.
+
+use serde_json::{Map, Value};
+
+use crate::base::error::ProgramError;
+
+/// One upgrade step: rewrites a document of version `N` (its index in
+/// `UPGRADES`) into version `N + 1`, in place.
+type UpgradeStep = fn(&mut Map<String, Value>) -> Result<(), String>;
+
+/// The upgrade chain. `UPGRADES[n]` upgrades version `n` to `n + 1`.
+const UPGRADES: &[UpgradeStep] = &[v0_to_v1];
+
+/// The format version [`Program`](super::Program) serializes as.
+pub const PROGRAM_FORMAT_VERSION: u32 = UPGRADES.len() as u32;
+
+/// JSON Schema (draft 2020-12) describing a program document at
+/// [`PROGRAM_FORMAT_VERSION`]. Published so other tools can validate
+/// program files without linking this crate.
+pub const PROGRAM_JSON_SCHEMA: &str = include_str!("../../../schema/program.schema.json");
+
+/// Returns the format version a program document declares. A document
+/// without a `version` field predates versioning and is version `0`.
+///
+/// # Errors
+///
+/// Returns an error if the document is not an object or its `version`
+/// is not a non-negative integer that fits in a `u32`.
+pub fn document_version(doc: &Value) -> Result<u32, ProgramError> {
+    let Value::Object(fields) = doc else {
+        return Err(ProgramError::NotAnObject);
+    };
+
+    match fields.get("version") {
+        None => Ok(0),
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| ProgramError::InvalidVersion {
+                found: version.to_string(),
+            }),
+    }
+}
+
+/// Upgrades a program document to [`PROGRAM_FORMAT_VERSION`], applying
+/// every step between its declared version and the current one. The
+/// returned document has its `version` field set to the current version.
+///
+/// # Errors
+///
+/// Returns an error if the document is malformed, was written by a newer
+/// release, or an upgrade step rejects it.
+///
+/// # Examples
+///
+/// ```
+/// use logic_mesh::base::program::version::{PROGRAM_FORMAT_VERSION, upgrade};
+///
+/// let legacy = serde_json::json!({ "blocks": {}, "links": {} });
+/// let current = upgrade(legacy)?;
+/// assert_eq!(current["version"], PROGRAM_FORMAT_VERSION);
+/// # Ok::<(), logic_mesh::ProgramError>(())
+/// ```
+pub fn upgrade(mut doc: Value) -> Result<Value, ProgramError> {
+    let found = document_version(&doc)?;
+    if found > PROGRAM_FORMAT_VERSION {
+        return Err(ProgramError::UnsupportedVersion {
+            found,
+            supported: PROGRAM_FORMAT_VERSION,
+        });
+    }
+
+    let Value::Object(fields) = &mut doc else {
+        return Err(ProgramError::NotAnObject);
+    };
+
+    for (from, step) in UPGRADES.iter().enumerate().skip(found as usize) {
+        let from = from as u32;
+        step(fields).map_err(|reason| ProgramError::UpgradeFailed { from, reason })?;
+        fields.insert("version".to_string(), Value::from(from + 1));
+    }
+
+    Ok(doc)
+}
+
+/// Version `0` is the unversioned format shipped with 1.0. Version `1`
+/// has the same shape and only adds the `version` field, which the
+/// chain stamps after every step.
+fn v0_to_v1(_fields: &mut Map<String, Value>) -> Result<(), String> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use serde_json::{Value, json};
+
+    use super::{PROGRAM_FORMAT_VERSION, PROGRAM_JSON_SCHEMA, document_version, upgrade};
+    use crate::base::error::ProgramError;
+
+    #[test]
+    fn unversioned_documents_are_version_zero() {
+        assert_eq!(document_version(&json!({ "blocks": {} })).unwrap(), 0);
+        assert_eq!(document_version(&json!({ "version": 1 })).unwrap(), 1);
+    }
+
+    #[test]
+    fn upgrade_stamps_the_current_version() {
+        let doc = upgrade(json!({ "name": "legacy", "blocks": {}, "links": {} })).unwrap();
+
+        assert_eq!(doc["version"], PROGRAM_FORMAT_VERSION);
+        assert_eq!(doc["name"], "legacy");
+    }
+
+    #[test]
+    fn newer_documents_are_rejected() {
+        let err = upgrade(json!({ "version": PROGRAM_FORMAT_VERSION + 1 }))
+            .expect_err("a newer format cannot be read");
+
+        assert_matches!(
+            err,
+            ProgramError::UnsupportedVersion { found, supported }
+                if found == PROGRAM_FORMAT_VERSION + 1 && supported == PROGRAM_FORMAT_VERSION
+        );
+    }
+
+    #[test]
+    fn malformed_versions_are_rejected() {
+        assert_matches!(
+            upgrade(json!({ "version": "1" })),
+            Err(ProgramError::InvalidVersion { found }) if found == "\"1\""
+        );
+        assert_matches!(
+            upgrade(json!({ "version": -1 })),
+            Err(ProgramError::InvalidVersion { .. })
+        );
+        assert_matches!(upgrade(json!([])), Err(ProgramError::NotAnObject));
+    }
+
+    /// The published schema has to describe the version the crate
+    /// writes; a new upgrade step without a schema update fails here.
+    #[test]
+    fn schema_matches_the_current_version() {
+        let schema: Value = serde_json::from_str(PROGRAM_JSON_SCHEMA).expect("schema is JSON");
+
+        assert_eq!(
+            schema["properties"]["version"]["const"],
+            PROGRAM_FORMAT_VERSION
+        );
+        for field in ["version", "name", "description", "blocks", "links"] {
+            assert!(
+                schema["properties"].get(field).is_some(),
+                "schema is missing '{field}'"
+            );
+        }
+    }
+}
@@ -0,0 +1,182 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! The `validate`, `list-blocks` and `eval` commands.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+use std::process::ExitCode;
+
+use libhaystack::encoding::zinc;
+use logic_mesh::Value;
+use logic_mesh::base::block::{BlockDesc, BlockPin};
+use logic_mesh::blocks::registry::{eval_static_block, get_block, list_registered_blocks};
+use serde_json::json;
+use uuid::Uuid;
+
+/// Checks that every block of a program is registered, that its
+/// constants and links name existing pins, and that links join blocks
+/// of the program. Prints each problem found.
+pub fn validate(path: &Path) -> Result<ExitCode, String> {
+    let program = super::read_program(path)?;
+    let mut problems = Vec::new();
+
+    let mut descs = BTreeMap::new();
+    for (id, block) in &program.blocks {
+        if Uuid::parse_str(id).is_err() {
+            problems.push(format!("block '{id}': id is not a UUID"));
+        }
+        let Some(entry) = get_block(&block.name, Some(&block.lib)) else {
+            problems.push(format!(
+                "block '{id}': '{}::{}' is not registered",
+                block.lib, block.name
+            ));
+            continue;
+        };
+        for pin in block.inputs.keys() {
+            if !has_pin(&entry.desc.inputs, pin) {
+                problems.push(format!("block '{id}': no input '{pin}'"));
+            }
+        }
+        for pin in block.outputs.keys() {
+            if !has_pin(&entry.desc.outputs, pin) {
+                problems.push(format!("block '{id}': no output '{pin}'"));
+            }
+        }
+        descs.insert(id.as_str(), entry.desc);
+    }
+
+    for (id, link) in &program.links {
+        let ends = [
+            (
+                "source",
+                &link.source_block_uuid,
+                &link.source_block_pin_name,
+            ),
+            (
+                "target",
+                &link.target_block_uuid,
+                &link.target_block_pin_name,
+            ),
+        ];
+        for (end, block, pin) in ends {
+            if !program.blocks.contains_key(block) {
+                problems.push(format!(
+                    "link '{id}': {end} block '{block}' is not in the program"
+                ));
+                continue;
+            }
+            let Some(desc) = descs.get(block.as_str()) else {
+                continue;
+            };
+            // Links may start at an input, passing its value through.
+            let found = match end {
+                "source" => has_pin(&desc.outputs, pin) || has_pin(&desc.inputs, pin),
+                _ => has_pin(&desc.inputs, pin),
+            };
+            if !found {
+                problems.push(format!(
+                    "link '{id}': {end} pin '{pin}' not found on block '{block}'"
+                ));
+            }
+        }
+    }
+
+    if problems.is_empty() {
+        println!(
+            "{}: ok ({} blocks, {} links)",
+            path.display(),
+            program.blocks.len(),
+            program.links.len()
+        );
+        Ok(ExitCode::SUCCESS)
+    } else {
+        for problem in &problems {
+            eprintln!("{}: {problem}", path.display());
+        }
+        Ok(ExitCode::FAILURE)
+    }
+}
+
+/// Prints the registered blocks, optionally only those of one library,
+/// as a table or as a JSON array.
+pub fn list(lib: Option<&str>, as_json: bool) -> ExitCode {
+    let mut blocks: Vec<BlockDesc> = list_registered_blocks()
+        .into_iter()
+        .filter(|desc| lib.is_none_or(|lib| desc.library == lib))
+        .collect();
+    blocks.sort_by_key(BlockDesc::qname);
+    let mut out = std::io::stdout().lock();
+
+    if as_json {
+        let pins = |pins: &[BlockPin]| {
+            pins.iter()
+                .map(|pin| json!({ "name": pin.name, "kind": pin.kind.to_string() }))
+                .collect::<Vec<_>>()
+        };
+        let blocks: Vec<_> = blocks
+            .iter()
+            .map(|desc| {
+                json!({
+                    "name": desc.name,
+                    "lib": desc.library,
+                    "dis": desc.dis,
+                    "category": desc.category,
+                    "ver": desc.ver,
+                    "doc": desc.doc,
+                    "inputs": pins(&desc.inputs),
+                    "outputs": pins(&desc.outputs),
+                })
+            })
+            .collect();
+        let _ = writeln!(out, "{}", serde_json::Value::Array(blocks));
+    } else {
+        for desc in &blocks {
+            let line = format!("{:<28} {:<12} {}", desc.qname(), desc.category, desc.dis);
+            // Stop quietly when piped into `head`.
+            if writeln!(out, "{}", line.trim_end()).is_err() {
+                break;
+            }
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+/// Evaluates a block once on the given Zinc inputs and prints each
+/// output as `name = value`.
+pub fn eval(name: &str, lib: Option<&str>, inputs: Vec<String>) -> ExitCode {
+    let inputs = inputs.iter().map(|input| parse_value(input)).collect();
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("Tokio runtime");
+    let outputs = match runtime.block_on(eval_static_block(name, lib, inputs)) {
+        Ok(outputs) => outputs,
+        Err(err) => {
+            eprintln!("logic-mesh: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let names = get_block(name, lib)
+        .map(|entry| entry.desc.outputs)
+        .unwrap_or_default();
+    for (index, value) in outputs.iter().enumerate() {
+        let zinc = zinc::encode::to_zinc_string(value).unwrap_or_else(|_| value.to_string());
+        match names.get(index) {
+            Some(pin) => println!("{} = {zinc}", pin.name),
+            None => println!("{zinc}"),
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+/// Parses a Zinc literal, taking anything that is not one as a string.
+fn parse_value(text: &str) -> Value {
+    zinc::decode::from_str(text).unwrap_or_else(|_| Value::from(text))
+}
+
+fn has_pin(pins: &[BlockPin], name: &str) -> bool {
+    pins.iter().any(|pin| pin.name == name)
+}
@@ -0,0 +1,186 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Headless Logic Mesh runner.
+//!
+//! Runs a saved [`Program`](logic_mesh::base::program::Program) outside the
+//! browser and inspects the block registry from a shell:
+//!
+//! ```text
+//! logic-mesh run <program.json> [--engine single|multi] [--autosave <secs>] [--save-to <path>]
+//!                [--listen <addr>]
+//! logic-mesh validate <program.json>
+//! logic-mesh list-blocks [--lib <lib>] [--json]
+//! logic-mesh eval <block> [--lib <lib>] [<input>...]
+//! ```
+//!
+//! `run` streams every watch notification to stdout as one JSON object
+//! per line and reads pin writes from stdin in the same form. See
+//! [`run`] for the line formats.
+//!
+//! Build with `cargo build --features cli`.
+
+mod blocks;
+mod run;
+
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use logic_mesh::base::program::Program;
+
+const USAGE: &str = "\
+Usage:
+  logic-mesh run <program.json> [--engine single|multi] [--autosave <secs>] [--save-to <path>]
+                 [--listen <addr>]
+  logic-mesh validate <program.json>
+  logic-mesh list-blocks [--lib <lib>] [--json]
+  logic-mesh eval <block> [--lib <lib>] [<input>...]
+
+Commands:
+  run           Load a program and run it until interrupted
+  validate      Check that a program's blocks, pins and links exist
+  list-blocks   List the registered blocks
+  eval          Run a block once on the given inputs and print its outputs
+
+`eval` inputs are Zinc literals, e.g. 42, 21.5°C, T, \"text\". `run` reads pin
+writes from stdin and writes block changes to stdout as JSON lines.";
+
+fn main() -> ExitCode {
+    let args = Args(std::env::args().skip(1).collect());
+
+    command(args).unwrap_or_else(|err| {
+        eprintln!("logic-mesh: {err}\n\n{USAGE}");
+        ExitCode::from(2)
+    })
+}
+
+/// Dispatches to a command. Usage errors are returned; failures of the
+/// command itself are reported by the command and show in the exit code.
+fn command(mut args: Args) -> Result<ExitCode, String> {
+    match args.next().as_deref() {
+        Some("run") => run::run(run::Options::parse(&mut args)?),
+        Some("validate") => {
+            let path = args.positional("program file")?;
+            args.finish()?;
+            blocks::validate(&PathBuf::from(path))
+        }
+        Some("list-blocks") => {
+            let lib = args.option("--lib")?;
+            let json = args.flag("--json");
+            args.finish()?;
+            Ok(blocks::list(lib.as_deref(), json))
+        }
+        Some("eval") => {
+            let lib = args.option("--lib")?;
+            let name = args.positional("block name")?;
+            Ok(blocks::eval(&name, lib.as_deref(), args.rest()))
+        }
+        Some("-h" | "--help" | "help") => {
+            println!("{USAGE}");
+            Ok(ExitCode::SUCCESS)
+        }
+        Some(cmd) => Err(format!("unknown command '{cmd}'")),
+        None => Err("missing command".to_string()),
+    }
+}
+
+/// Reads and parses a program file, upgrading older format versions.
+fn read_program(path: &Path) -> Result<Program, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|err| format!("cannot read '{}': {err}", path.display()))?;
+    serde_json::from_str(&text)
+        .map_err(|err| format!("invalid program '{}': {err}", path.display()))
+}
+
+/// The command line arguments left after the command name.
+///
+/// Options are taken out by name first; whatever remains is positional.
+struct Args(Vec<String>);
+
+impl Args {
+    fn next(&mut self) -> Option<String> {
+        (!self.0.is_empty()).then(|| self.0.remove(0))
+    }
+
+    fn flag(&mut self, name: &str) -> bool {
+        let found = self.0.iter().position(|arg| arg == name);
+        found.map(|pos| self.0.remove(pos)).is_some()
+    }
+
+    fn option(&mut self, name: &str) -> Result<Option<String>, String> {
+        let Some(pos) = self.0.iter().position(|arg| arg == name) else {
+            return Ok(None);
+        };
+        self.0.remove(pos);
+        if pos < self.0.len() {
+            Ok(Some(self.0.remove(pos)))
+        } else {
+            Err(format!("{name} needs a value"))
+        }
+    }
+
+    fn positional(&mut self, what: &str) -> Result<String, String> {
+        match self.next() {
+            Some(arg) if !arg.starts_with("--") => Ok(arg),
+            Some(arg) => Err(format!("unknown option '{arg}'")),
+            None => Err(format!("missing {what}")),
+        }
+    }
+
+    fn rest(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.0)
+    }
+
+    fn finish(&mut self) -> Result<(), String> {
+        match self.next() {
+            Some(arg) => Err(format!("unexpected argument '{arg}'")),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn command_err(args: &[&str]) -> String {
+        command(Args(args.iter().map(|arg| arg.to_string()).collect())).expect_err("usage error")
+    }
+
+    #[test]
+    fn test_validate_arguments() {
+        assert_eq!(command_err(&["validate"]), "missing program file");
+        assert_eq!(
+            command_err(&["validate", "--strict"]),
+            "unknown option '--strict'"
+        );
+        assert_eq!(
+            command_err(&["validate", "a.json", "b.json"]),
+            "unexpected argument 'b.json'"
+        );
+    }
+
+    #[test]
+    fn test_eval_arguments() {
+        assert_eq!(command_err(&["eval"]), "missing block name");
+        assert_eq!(
+            command_err(&["eval", "Add", "--lib"]),
+            "--lib needs a value"
+        );
+
+        let mut args = Args(
+            ["--lib", "core", "Add", "1", "2°C"]
+                .map(String::from)
+                .to_vec(),
+        );
+        assert_eq!(args.option("--lib").unwrap().as_deref(), Some("core"));
+        assert_eq!(args.positional("block name").unwrap(), "Add");
+        assert_eq!(args.rest(), ["1", "2°C"]);
+    }
+
+    #[test]
+    fn test_unknown_commands() {
+        assert_eq!(command_err(&[]), "missing command");
+        assert_eq!(command_err(&["start"]), "unknown command 'start'");
+        assert_eq!(command_err(&["run", "--engine"]), "--engine needs a value");
+    }
+}
@@ -0,0 +1,496 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! The `run` command.
+//!
+//! Loads a program into an engine and drives it through the engine's
+//! message channel, the same way the browser editor does:
+//!
+//! - Every [`WatchMessage`] is written to stdout as one JSON line:
+//!
+//!   ```json
+//!   {"block":"<uuid>","state":"running","changes":{"out":{"output":42}}}
+//!   ```
+//!
+//!   Faulted blocks add a `"reason"`. Values are Haystack JSON (Hayson).
+//!
+//! - Each stdin line writes one pin, naming either an input or an output:
+//!
+//!   ```json
+//!   {"block":"<uuid>","input":"in0","value":21.5}
+//!   ```
+//!
+//! - With `--autosave`, the running program is saved every so many
+//!   seconds and once more on exit, to `--save-to` or back over the
+//!   program file. The file is replaced atomically.
+//!
+//! - With `--listen <addr>`, the engine is also served over WebSocket and
+//!   REST (see [`logic_mesh::server`]) so the web editor can attach.
+//!
+//! Ctrl-C or SIGTERM shuts the engine down and exits.
+
+use std::collections::BTreeMap;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
+
+use logic_mesh::Value;
+use logic_mesh::base::engine::Engine;
+use logic_mesh::base::engine::messages::{ChangeSource, EngineMessage, WatchMessage};
+use logic_mesh::base::program::Program;
+use logic_mesh::multi_threaded::MultiThreadedEngine;
+use logic_mesh::server::EngineServer;
+use logic_mesh::single_threaded::{Messages, SingleThreadedEngine};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::net::TcpListener;
+use tokio::runtime::{Builder, Runtime};
+use tokio::sync::mpsc::{self, Receiver, Sender};
+use uuid::Uuid;
+
+use super::Args;
+
+/// Which engine runs the program.
+#[derive(Debug, Clone, Copy)]
+enum EngineKind {
+    Single,
+    Multi,
+}
+
+/// Options of the `run` command.
+#[derive(Debug)]
+pub struct Options {
+    program: PathBuf,
+    engine: EngineKind,
+    autosave: Option<Duration>,
+    save_to: Option<PathBuf>,
+    listen: Option<String>,
+}
+
+impl Options {
+    pub fn parse(args: &mut Args) -> Result<Self, String> {
+        let engine = match args.option("--engine")?.as_deref() {
+            None | Some("single") => EngineKind::Single,
+            Some("multi") => EngineKind::Multi,
+            Some(other) => return Err(format!("unknown engine '{other}'")),
+        };
+        let autosave = args
+            .option("--autosave")?
+            .map(|secs| match secs.parse::<f64>() {
+                Ok(secs) if secs > 0.0 => Ok(Duration::from_secs_f64(secs)),
+                _ => Err(format!("invalid autosave interval '{secs}'")),
+            })
+            .transpose()?;
+        let save_to = args.option("--save-to")?.map(PathBuf::from);
+        let listen = args.option("--listen")?;
+        let program = PathBuf::from(args.positional("program file")?);
+        args.finish()?;
+
+        Ok(Self {
+            program,
+            engine,
+            autosave,
+            save_to,
+            listen,
+        })
+    }
+}
+
+/// Runs the program until a signal arrives.
+pub fn run(options: Options) -> Result<ExitCode, String> {
+    let program = super::read_program(&options.program)?;
+
+    Ok(match options.engine {
+        EngineKind::Single => {
+            let runtime = Builder::new_current_thread().enable_all().build();
+            drive_engine(
+                runtime.map_err(|err| err.to_string())?,
+                SingleThreadedEngine::new(),
+                program,
+                options,
+            )
+        }
+        EngineKind::Multi => {
+            let runtime = Builder::new_multi_thread().enable_all().build();
+            drive_engine(
+                runtime.map_err(|err| err.to_string())?,
+                MultiThreadedEngine::new(),
+                program,
+                options,
+            )
+        }
+    })
+}
+
+/// Runs `engine` on the runtime's main task while a [`Session`] talks
+/// to it from a spawned one.
+fn drive_engine<E: Engine<Channel = Sender<Messages>>>(
+    runtime: Runtime,
+    mut engine: E,
+    program: Program,
+    options: Options,
+) -> ExitCode {
+    let id = Uuid::new_v4();
+    let (reply_sender, replies) = mpsc::channel(32);
+    let sender = engine.create_message_channel(id, reply_sender);
+
+    if let Some(addr) = &options.listen {
+        let server = EngineServer::new(&mut engine);
+        match runtime.block_on(TcpListener::bind(addr)) {
+            Ok(listener) => {
+                eprintln!("logic-mesh: serving the engine on {addr}");
+                runtime.spawn(server.serve(listener));
+            }
+            Err(err) => {
+                eprintln!("logic-mesh: cannot listen on {addr}: {err}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let session = Session {
+        id,
+        sender,
+        replies,
+        save_to: options.save_to.unwrap_or(options.program),
+        name: program.name.clone(),
+        description: program.description.clone(),
+    };
+    let session = runtime.spawn(session.run(program, options.autosave));
+
+    runtime.block_on(engine.run());
+    runtime.block_on(session).unwrap_or(ExitCode::FAILURE)
+}
+
+/// One pin write read from stdin.
+#[derive(Debug, Deserialize)]
+struct PinWrite {
+    block: String,
+    #[serde(flatten)]
+    pin: Pin,
+    value: Value,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Pin {
+    Input(String),
+    Output(String),
+}
+
+/// The command's connection to the running engine.
+struct Session {
+    id: Uuid,
+    sender: Sender<Messages>,
+    replies: Receiver<Messages>,
+    save_to: PathBuf,
+    /// The loaded program's name and description, which the engine's
+    /// snapshot of the running blocks does not carry.
+    name: Option<String>,
+    description: Option<String>,
+}
+
+impl Session {
+    async fn run(mut self, program: Program, autosave: Option<Duration>) -> ExitCode {
+        let code = self.serve(program, autosave).await.unwrap_or_else(|err| {
+            eprintln!("logic-mesh: {err}");
+            ExitCode::FAILURE
+        });
+        let _ = self.sender.send(EngineMessage::Shutdown).await;
+        code
+    }
+
+    async fn serve(
+        &mut self,
+        program: Program,
+        autosave: Option<Duration>,
+    ) -> Result<ExitCode, String> {
+        // Subscribe first so the first values the program computes are seen.
+        let (watch_sender, mut watches) = mpsc::unbounded_channel();
+        match self
+            .request(EngineMessage::WatchBlockSubReq(self.id, watch_sender))
+            .await?
+        {
+            EngineMessage::WatchBlockSubRes(result) => result.map(drop)?,
+            other => return Err(unexpected(&other)),
+        }
+
+        match self
+            .request(EngineMessage::LoadProgramReq(self.id, program))
+            .await?
+        {
+            EngineMessage::LoadProgramRes(result) => result?,
+            other => return Err(unexpected(&other)),
+        }
+
+        let mut lines = stdin_lines();
+        let mut ticks = autosave.map(|period| {
+            let mut ticks = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+            ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            ticks
+        });
+
+        let shutdown = shutdown_signal();
+        tokio::pin!(shutdown);
+
+        loop {
+            tokio::select! {
+                _ = &mut shutdown => break,
+                Some(msg) = watches.recv() => {
+                    if !print_watch(&msg) {
+                        // Stdout is gone; nobody is listening anymore.
+                        break;
+                    }
+                }
+                Some(line) = lines.recv() => {
+                    if let Err(err) = self.write_pin(&line).await {
+                        eprintln!("logic-mesh: {err}");
+                    }
+                }
+                Some(_) = tick(&mut ticks) => {
+                    if let Err(err) = self.save().await {
+                        eprintln!("logic-mesh: autosave failed: {err}");
+                    }
+                }
+            }
+        }
+
+        if ticks.is_some() {
+            self.save().await?;
+        }
+        Ok(ExitCode::SUCCESS)
+    }
+
+    async fn request(&mut self, msg: Messages) -> Result<Messages, String> {
+        self.sender
+            .send(msg)
+            .await
+            .map_err(|_| "engine stopped".to_string())?;
+        self.replies
+            .recv()
+            .await
+            .ok_or_else(|| "engine stopped".to_string())
+    }
+
+    async fn write_pin(&mut self, line: &str) -> Result<(), String> {
+        let write: PinWrite =
+            serde_json::from_str(line).map_err(|err| format!("invalid write '{line}': {err}"))?;
+
+        let block = Uuid::parse_str(&write.block)
+            .map_err(|err| format!("invalid block id '{}': {err}", write.block))?;
+        let (id, value) = (self.id, write.value);
+        match write.pin {
+            Pin::Input(pin) => {
+                match self
+                    .request(EngineMessage::WriteBlockInputReq(id, block, pin, value))
+                    .await?
+                {
+                    EngineMessage::WriteBlockInputRes(result) => result.map(drop),
+                    other => Err(unexpected(&other)),
+                }
+            }
+            Pin::Output(pin) => {
+                match self
+                    .request(EngineMessage::WriteBlockOutputReq(id, block, pin, value))
+                    .await?
+                {
+                    EngineMessage::WriteBlockOutputRes(result) => result.map(drop),
+                    other => Err(unexpected(&other)),
+                }
+            }
+        }
+    }
+
+    async fn save(&mut self) -> Result<(), String> {
+        let program = match self
+            .request(EngineMessage::GetCurrentProgramReq(self.id))
+            .await?
+        {
+            EngineMessage::GetCurrentProgramRes(result) => result?,
+            other => return Err(unexpected(&other)),
+        };
+        let program = Program {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            ..program
+        };
+        let json = serde_json::to_string_pretty(&program).map_err(|err| err.to_string())?;
+        write_atomically(&self.save_to, json.as_bytes())
+            .map_err(|err| format!("cannot write '{}': {err}", self.save_to.display()))
+    }
+}
+
+/// Writes one watch notification to stdout. Returns `false` once stdout
+/// is closed.
+fn print_watch(msg: &WatchMessage) -> bool {
+    let changes: BTreeMap<_, _> = msg
+        .changes
+        .iter()
+        .map(|(pin, change)| {
+            let change = match change {
+                ChangeSource::Input(_, value) => json!({ "input": value }),
+                ChangeSource::Output(_, value) => json!({ "output": value }),
+            };
+            (pin, change)
+        })
+        .collect();
+
+    let mut line = json!({
+        "block": msg.block_id.to_string(),
+        "state": msg.state.label(),
+        "changes": changes,
+    });
+    if let Some(reason) = msg.state.fault_reason() {
+        line["reason"] = reason.into();
+    }
+
+    let mut stdout = std::io::stdout().lock();
+    writeln!(stdout, "{line}")
+        .and_then(|_| stdout.flush())
+        .is_ok()
+}
+
+/// Forwards stdin lines from a blocking reader thread. The channel closes
+/// at end of input.
+fn stdin_lines() -> mpsc::UnboundedReceiver<String> {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            let line = line.trim();
+            if !line.is_empty() && sender.send(line.to_string()).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+async fn tick(ticks: &mut Option<tokio::time::Interval>) -> Option<()> {
+    match ticks {
+        Some(ticks) => {
+            ticks.tick().await;
+            Some(())
+        }
+        None => None,
+    }
+}
+
+/// Resolves on Ctrl-C, or SIGTERM on Unix.
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        if let Ok(mut terminate) = signal(SignalKind::terminate()) {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = terminate.recv() => {}
+            }
+            return;
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// Writes next to `path` and renames over it, so a crash mid-write never
+/// leaves a truncated program behind.
+fn write_atomically(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    std::fs::write(&tmp, contents)?;
+    std::fs::rename(&tmp, path)
+}
+
+fn unexpected(msg: &Messages) -> String {
+    format!("unexpected engine reply: {msg:?}")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        Options::parse(&mut Args(args.iter().map(|arg| arg.to_string()).collect()))
+    }
+
+    #[test]
+    fn test_parse_run_options() {
+        let options = parse(&[
+            "--engine",
+            "multi",
+            "--autosave",
+            "2.5",
+            "prog.json",
+            "--save-to",
+            "out.json",
+        ])
+        .unwrap();
+        assert!(matches!(options.engine, EngineKind::Multi));
+        assert_eq!(options.autosave, Some(Duration::from_millis(2500)));
+        assert_eq!(options.program, PathBuf::from("prog.json"));
+        assert_eq!(options.save_to, Some(PathBuf::from("out.json")));
+        assert_eq!(options.listen, None);
+
+        let options = parse(&["prog.json"]).unwrap();
+        assert!(matches!(options.engine, EngineKind::Single));
+        assert_eq!(options.autosave, None);
+
+        assert_eq!(
+            parse(&["--engine", "dual", "prog.json"]).unwrap_err(),
+            "unknown engine 'dual'"
+        );
+        assert_eq!(
+            parse(&["--autosave", "0", "prog.json"]).unwrap_err(),
+            "invalid autosave interval '0'"
+        );
+        assert_eq!(parse(&["--listen"]).unwrap_err(), "--listen needs a value");
+        assert_eq!(parse(&[]).unwrap_err(), "missing program file");
+        assert_eq!(
+            parse(&["prog.json", "extra"]).unwrap_err(),
+            "unexpected argument 'extra'"
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_save_keeps_the_program_metadata() {
+        let program = super::super::read_program(Path::new("scenarios/dat-reset.program.json"))
+            .expect("Bundled program");
+        let save_to = std::env::temp_dir().join(format!("logic-mesh-save-{}.json", Uuid::new_v4()));
+
+        let mut engine = SingleThreadedEngine::new();
+        let id = Uuid::new_v4();
+        let (reply_sender, replies) = mpsc::channel(32);
+        let mut session = Session {
+            id,
+            sender: engine.create_message_channel(id, reply_sender),
+            replies,
+            save_to: save_to.clone(),
+            name: program.name.clone(),
+            description: program.description.clone(),
+        };
+
+        let blocks = program.blocks.len();
+        let client = tokio::spawn(async move {
+            let loaded = session
+                .request(EngineMessage::LoadProgramReq(session.id, program))
+                .await;
+            assert!(matches!(loaded, Ok(EngineMessage::LoadProgramRes(Ok(())))));
+            let saved = session.save().await;
+            let _ = session.sender.send(EngineMessage::Shutdown).await;
+            saved
+        });
+        engine.run().await;
+        client.await.unwrap().expect("Saved");
+
+        let saved = super::super::read_program(&save_to).expect("Saved program");
+        let _ = std::fs::remove_file(&save_to);
+        assert_eq!(saved.name.as_deref(), Some("DAT Temperature Reset"));
+        assert!(
+            saved
+                .description
+                .is_some_and(|description| description.starts_with("Discharge-air"))
+        );
+        assert_eq!(saved.blocks.len(), blocks);
+    }
+}
@@ -2,17 +2,28 @@
 
 //! Block implementations.
 
+pub mod alarm;
 pub mod bitwise;
+pub mod catalog;
 pub mod collections;
 pub mod control;
+pub mod dyn_block;
+pub mod fn_block;
+pub(crate) mod lists;
 pub mod logic;
 pub mod math;
 pub mod misc;
+#[cfg(all(feature = "modbus", not(target_arch = "wasm32")))]
+pub mod modbus;
+#[cfg(all(feature = "mqtt", not(target_arch = "wasm32")))]
+pub mod mqtt;
 pub mod psych;
 pub mod registry;
+pub mod sim;
 pub mod string;
 pub mod time;
 pub mod timers;
+pub(crate) mod units;
 pub(crate) mod utils;
 
 // Re-export implementations working with inputs and outputs
@@ -0,0 +1,23 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Alarm blocks.
+//!
+//! Each block turns a condition on its `in` pin into an alarm state on
+//! its `out` pin, held until the condition has lasted `delayOn`
+//! milliseconds and released once it has been gone for `delayOff`. The
+//! `priority` and `message` inputs are read by the engine's
+//! [`AlarmTable`](crate::base::alarm::AlarmTable), which raises and
+//! clears the operator-facing alarms as `out` changes.
+
+pub mod bool_alarm;
+mod delay;
+pub mod deviation_alarm;
+pub mod high_limit_alarm;
+pub mod low_limit_alarm;
+pub mod status_alarm;
+
+pub use bool_alarm::BoolAlarm;
+pub use deviation_alarm::DeviationAlarm;
+pub use high_limit_alarm::HighLimitAlarm;
+pub use low_limit_alarm::LowLimitAlarm;
+pub use status_alarm::StatusAlarm;
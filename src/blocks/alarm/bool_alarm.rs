@@ -0,0 +1,77 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Boolean alarm block.
+
+use std::time::Duration;
+
+use crate::base::{
+    block::Block,
+    input::{InputProps, input_reader::InputReader},
+};
+use crate::blocks::utils::get_sleep_dur;
+
+use libhaystack::val::Value;
+
+use super::delay::AlarmDelay;
+use crate::{blocks::InputImpl, blocks::OutputImpl};
+
+/// Goes into alarm while `in` is true, such as a pump's trip contact.
+/// Feed it through a `Not` block to alarm on false. `delayOn` and
+/// `delayOff` are in milliseconds.
+#[block]
+#[derive(BlockProps, Debug)]
+#[category = "alarm"]
+pub struct BoolAlarm {
+    #[input(name = "in", kind = "Bool")]
+    pub input: InputImpl,
+    #[input(name = "delayOn", kind = "Number")]
+    pub delay_on: InputImpl,
+    #[input(name = "delayOff", kind = "Number")]
+    pub delay_off: InputImpl,
+    #[input(kind = "Number")]
+    pub priority: InputImpl,
+    #[input(kind = "Str")]
+    pub message: InputImpl,
+    #[output(kind = "Bool")]
+    pub out: OutputImpl,
+    delay: AlarmDelay,
+}
+
+impl Block for BoolAlarm {
+    async fn execute(&mut self) {
+        self.wait_on_inputs(Duration::from_millis(get_sleep_dur()))
+            .await;
+
+        let Some(Value::Bool(input)) = self.input.get_value() else {
+            return;
+        };
+        self.delay
+            .update(input.value, &self.delay_on, &self.delay_off, &mut self.out);
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use libhaystack::val::Value;
+
+    use crate::{
+        base::block::Block, base::block::test_utils::write_block_inputs, blocks::alarm::BoolAlarm,
+    };
+
+    #[tokio::test]
+    async fn test_bool_alarm_holds_for_delay_off() {
+        let mut block = BoolAlarm::new();
+        write_block_inputs([
+            (&mut block.input, Value::from(true)),
+            (&mut block.delay_off, Value::from(3_600_000)),
+        ])
+        .await;
+        block.execute().await;
+        assert_eq!(block.out.value, true.into());
+
+        write_block_inputs([(&mut block.input, false)]).await;
+        block.execute().await;
+        assert_eq!(block.out.value, true.into());
+    }
+}
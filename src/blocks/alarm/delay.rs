@@ -0,0 +1,56 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! On and off delays shared by the alarm blocks.
+
+use libhaystack::{units::units_generated::MILLISECOND, val::Value};
+
+use crate::base::output::Output;
+use crate::blocks::utils::input_as_number_in;
+use crate::tokio_impl::sleep::current_time_millis;
+use crate::{blocks::InputImpl, blocks::OutputImpl};
+
+/// Debounces an alarm condition into the block's `out` pin.
+#[derive(Debug, Default)]
+pub(super) struct AlarmDelay {
+    /// When the condition started to differ from `out`, or zero.
+    pending_since_ms: u64,
+}
+
+impl AlarmDelay {
+    /// Sets `out` to `condition` once it has held for `delay_on`
+    /// milliseconds when going into alarm, or `delay_off` when returning
+    /// to normal.
+    pub(super) fn update(
+        &mut self,
+        condition: bool,
+        delay_on: &InputImpl,
+        delay_off: &InputImpl,
+        out: &mut OutputImpl,
+    ) {
+        // A new block starts out of alarm.
+        if !matches!(out.value, Value::Bool(_)) {
+            out.set(false.into());
+        }
+        if condition == is_active(out) {
+            self.pending_since_ms = 0;
+            return;
+        }
+
+        let now = current_time_millis();
+        if self.pending_since_ms == 0 {
+            self.pending_since_ms = now;
+        }
+
+        let delay = if condition { delay_on } else { delay_off };
+        let delay_ms = input_as_number_in(delay, &MILLISECOND).map_or(0, |ms| ms.max(0.0) as u64);
+        if now.saturating_sub(self.pending_since_ms) >= delay_ms {
+            self.pending_since_ms = 0;
+            out.set(condition.into());
+        }
+    }
+}
+
+/// Whether the block's `out` pin is in alarm.
+pub(super) fn is_active(out: &OutputImpl) -> bool {
+    matches!(&out.value, Value::Bool(active) if active.value)
+}
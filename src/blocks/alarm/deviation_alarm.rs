@@ -0,0 +1,103 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Deviation from setpoint alarm block.
+
+use std::time::Duration;
+
+use crate::base::{block::Block, input::input_reader::InputReader};
+use crate::blocks::utils::{
+    get_sleep_dur, input_as_float_or_default, input_as_number, input_as_number_matching,
+};
+
+use super::delay::{AlarmDelay, is_active};
+use crate::{blocks::InputImpl, blocks::OutputImpl};
+
+/// Goes into alarm when `in` strays from `setpoint` by more than `limit`,
+/// either way, and returns to normal once it is back within
+/// `limit - deadband`. The `limit` and `deadband` are in the units of
+/// `in`; `delayOn` and `delayOff` are in milliseconds.
+#[block]
+#[derive(BlockProps, Debug)]
+#[category = "alarm"]
+pub struct DeviationAlarm {
+    #[input(name = "in", kind = "Number")]
+    pub input: InputImpl,
+    #[input(kind = "Number")]
+    pub setpoint: InputImpl,
+    #[input(kind = "Number")]
+    pub limit: InputImpl,
+    #[input(kind = "Number")]
+    pub deadband: InputImpl,
+    #[input(name = "delayOn", kind = "Number")]
+    pub delay_on: InputImpl,
+    #[input(name = "delayOff", kind = "Number")]
+    pub delay_off: InputImpl,
+    #[input(kind = "Number")]
+    pub priority: InputImpl,
+    #[input(kind = "Str")]
+    pub message: InputImpl,
+    #[output(kind = "Bool")]
+    pub out: OutputImpl,
+    delay: AlarmDelay,
+}
+
+impl Block for DeviationAlarm {
+    async fn execute(&mut self) {
+        self.wait_on_inputs(Duration::from_millis(get_sleep_dur()))
+            .await;
+
+        let Some(input) = input_as_number(&self.input) else {
+            return;
+        };
+        let Some(setpoint) = input_as_number_matching(&self.setpoint, input.unit) else {
+            return;
+        };
+        let Some(limit) = input_as_number(&self.limit).map(|limit| limit.value) else {
+            return;
+        };
+
+        let threshold = if is_active(&self.out) {
+            limit - input_as_float_or_default(&self.deadband)
+        } else {
+            limit
+        };
+        self.delay.update(
+            (input.value - setpoint).abs() > threshold,
+            &self.delay_on,
+            &self.delay_off,
+            &mut self.out,
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::{
+        base::block::Block, base::block::test_utils::write_block_inputs,
+        blocks::alarm::DeviationAlarm,
+    };
+
+    #[tokio::test]
+    async fn test_deviation_alarm_on_either_side() {
+        let mut block = DeviationAlarm::new();
+        write_block_inputs([
+            (&mut block.setpoint, 21.0),
+            (&mut block.limit, 3.0),
+            (&mut block.deadband, 1.0),
+        ])
+        .await;
+
+        for (input, alarm) in [
+            (22.0, false),
+            (25.0, true),
+            (23.5, true),
+            (22.5, false),
+            (17.5, true),
+        ] {
+            write_block_inputs([(&mut block.input, input)]).await;
+            block.execute().await;
+            assert_eq!(block.out.value, alarm.into(), "in = {input}");
+        }
+    }
+}
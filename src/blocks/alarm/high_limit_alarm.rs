@@ -0,0 +1,112 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! High limit alarm block.
+
+use std::time::Duration;
+
+use crate::base::{block::Block, input::input_reader::InputReader};
+use crate::blocks::utils::{
+    get_sleep_dur, input_as_float_or_default, input_as_number, input_as_number_matching,
+};
+
+use super::delay::{AlarmDelay, is_active};
+use crate::{blocks::InputImpl, blocks::OutputImpl};
+
+/// Goes into alarm when `in` rises above `limit`, and returns to normal
+/// once it falls below `limit - deadband`. The `deadband` is in the units
+/// of `in`; `delayOn` and `delayOff` are in milliseconds.
+#[block]
+#[derive(BlockProps, Debug)]
+#[category = "alarm"]
+pub struct HighLimitAlarm {
+    #[input(name = "in", kind = "Number")]
+    pub input: InputImpl,
+    #[input(kind = "Number")]
+    pub limit: InputImpl,
+    #[input(kind = "Number")]
+    pub deadband: InputImpl,
+    #[input(name = "delayOn", kind = "Number")]
+    pub delay_on: InputImpl,
+    #[input(name = "delayOff", kind = "Number")]
+    pub delay_off: InputImpl,
+    #[input(kind = "Number")]
+    pub priority: InputImpl,
+    #[input(kind = "Str")]
+    pub message: InputImpl,
+    #[output(kind = "Bool")]
+    pub out: OutputImpl,
+    delay: AlarmDelay,
+}
+
+impl Block for HighLimitAlarm {
+    async fn execute(&mut self) {
+        self.wait_on_inputs(Duration::from_millis(get_sleep_dur()))
+            .await;
+
+        let Some(input) = input_as_number(&self.input) else {
+            return;
+        };
+        let Some(limit) = input_as_number_matching(&self.limit, input.unit) else {
+            return;
+        };
+
+        let threshold = if is_active(&self.out) {
+            limit - input_as_float_or_default(&self.deadband)
+        } else {
+            limit
+        };
+        self.delay.update(
+            input.value > threshold,
+            &self.delay_on,
+            &self.delay_off,
+            &mut self.out,
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use std::time::Duration;
+
+    use crate::{
+        base::block::Block, base::block::test_utils::write_block_inputs,
+        blocks::alarm::HighLimitAlarm, testing::TestBench,
+    };
+
+    #[tokio::test]
+    async fn test_high_limit_alarm_holds_within_deadband() {
+        let mut block = HighLimitAlarm::new();
+        write_block_inputs([(&mut block.limit, 80.0), (&mut block.deadband, 5.0)]).await;
+
+        for (input, alarm) in [(70.0, false), (81.0, true), (77.0, true), (74.0, false)] {
+            write_block_inputs([(&mut block.input, input)]).await;
+            block.execute().await;
+            assert_eq!(block.out.value, alarm.into(), "in = {input}");
+        }
+    }
+
+    #[test]
+    fn test_high_limit_alarm_delays_on_and_off() {
+        let mut bench = TestBench::new(HighLimitAlarm::new());
+        bench
+            .set("limit", 80)
+            .set("delayOn", 60_000)
+            .set("delayOff", 30_000)
+            .set("in", 90)
+            .settle();
+        bench.assert_output("out", false);
+
+        bench.advance(Duration::from_secs(59)).settle();
+        bench.assert_output("out", false);
+        bench.advance(Duration::from_secs(1)).settle();
+        bench.assert_output("out", true);
+
+        bench.set("in", 70).settle();
+        bench.assert_output("out", true);
+        bench.advance(Duration::from_secs(29)).settle();
+        bench.assert_output("out", true);
+        bench.advance(Duration::from_secs(1)).settle();
+        bench.assert_output("out", false);
+    }
+}
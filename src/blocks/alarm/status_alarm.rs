@@ -0,0 +1,91 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Status fault alarm block.
+
+use std::time::Duration;
+
+use crate::base::{
+    Status,
+    block::{Block, BlockProps, BlockState},
+    input::{InputProps, input_reader::InputReader},
+};
+use crate::blocks::utils::get_sleep_dur;
+
+use super::delay::AlarmDelay;
+use crate::{blocks::InputImpl, blocks::OutputImpl};
+
+/// Goes into alarm while the value on `in` does not have the
+/// [`Ok`](Status::Ok) status, such as when the block feeding it is in
+/// fault. The block reports the fault instead of faulting itself.
+/// `delayOn` and `delayOff` are in milliseconds.
+#[block]
+#[derive(BlockProps, Debug)]
+#[category = "alarm"]
+pub struct StatusAlarm {
+    #[input(name = "in", kind = "Null")]
+    pub input: InputImpl,
+    #[input(name = "delayOn", kind = "Number")]
+    pub delay_on: InputImpl,
+    #[input(name = "delayOff", kind = "Number")]
+    pub delay_off: InputImpl,
+    #[input(kind = "Number")]
+    pub priority: InputImpl,
+    #[input(kind = "Str")]
+    pub message: InputImpl,
+    #[output(kind = "Bool")]
+    pub out: OutputImpl,
+    delay: AlarmDelay,
+}
+
+impl Block for StatusAlarm {
+    async fn execute(&mut self) {
+        self.wait_on_inputs(Duration::from_millis(get_sleep_dur()))
+            .await;
+
+        // An upstream fault is what this block watches for, not a fault
+        // of its own.
+        if self.state().is_fault() {
+            self.set_state(BlockState::Running);
+        }
+
+        if self.input.get_value().is_none() {
+            return;
+        }
+        self.delay.update(
+            self.input.status != Status::Ok,
+            &self.delay_on,
+            &self.delay_off,
+            &mut self.out,
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::{
+        base::{
+            Status,
+            block::{Block, BlockProps},
+            input::InputProps,
+        },
+        blocks::alarm::StatusAlarm,
+    };
+
+    #[tokio::test]
+    async fn test_status_alarm_reports_upstream_fault() {
+        let mut block = StatusAlarm::new();
+        block.input.increment_conn();
+
+        for (status, alarm) in [
+            (Status::Ok, false),
+            (Status::Fault, true),
+            (Status::Ok, false),
+        ] {
+            block.input.writer().send((1.into(), status)).unwrap();
+            block.execute().await;
+            assert_eq!(block.out.value, alarm.into(), "{status:?}");
+            assert!(!block.state().is_fault());
+        }
+    }
+}
@@ -0,0 +1,651 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Machine-readable catalog of the registered blocks.
+//!
+//! [`json_schema`] describes the inputs and outputs of a set of blocks as
+//! a JSON Schema, with the kind, default value, unit and documentation of
+//! each pin. [`typescript`] emits the matching TypeScript declarations,
+//! along with the types of the wasm `listBlocks` output, [`Program`] and
+//! [`LinkData`], so the npm package is generated from the registry rather
+//! than written by hand.
+//!
+//! Both are produced for the core library by
+//! `cargo run --example export_catalog`, as `schema/blocks.schema.json`
+//! and `web/packages/logic-mesh/src/catalog.ts`.
+//!
+//! [`Program`]: crate::base::program::data::Program
+//! [`LinkData`]: crate::base::program::data::LinkData
+
+use std::fmt::Write;
+
+use libhaystack::val::Value;
+use libhaystack::val::kind::HaystackKind;
+use serde_json::{Map, Value as Json, json};
+
+use crate::base::block::{BlockDesc, BlockPin};
+use crate::blocks::registry::get_block;
+
+/// The `$id` of the generated JSON Schema.
+pub const SCHEMA_ID: &str = "https://github.com/rracariu/logic-mesh/schema/blocks.schema.json";
+
+/// Every Haystack kind a pin may have, in declaration order.
+const KINDS: [HaystackKind; 18] = [
+    HaystackKind::Null,
+    HaystackKind::Remove,
+    HaystackKind::Marker,
+    HaystackKind::Na,
+    HaystackKind::Bool,
+    HaystackKind::Number,
+    HaystackKind::Str,
+    HaystackKind::Uri,
+    HaystackKind::Ref,
+    HaystackKind::Symbol,
+    HaystackKind::Date,
+    HaystackKind::Time,
+    HaystackKind::DateTime,
+    HaystackKind::Coord,
+    HaystackKind::XStr,
+    HaystackKind::List,
+    HaystackKind::Dict,
+    HaystackKind::Grid,
+];
+
+/// Returns the JSON Schema of the pin values of `blocks`.
+///
+/// The schema validates an object keyed by qualified block name, each
+/// holding the `inputs` and `outputs` of that block by pin name. Values
+/// use the Haystack JSON (Hayson) encoding; the per-kind schemas are
+/// under `$defs`, next to one definition per block.
+///
+/// # Examples
+///
+/// ```
+/// use logic_mesh::blocks::catalog::json_schema;
+/// use logic_mesh::blocks::registry::get_block;
+///
+/// let add = get_block("Add", Some("core")).expect("Add exists").desc;
+/// let schema = json_schema(&[add]);
+///
+/// let pins = &schema["$defs"]["core::Add"]["properties"]["inputs"];
+/// assert_eq!(pins["properties"]["in1"]["$ref"], "#/$defs/number");
+/// ```
+pub fn json_schema(blocks: &[BlockDesc]) -> Json {
+    let blocks = sorted(blocks);
+
+    let mut defs = Map::new();
+    for kind in KINDS {
+        defs.insert(kind.to_string(), kind_schema(kind));
+    }
+    let mut properties = Map::new();
+    for desc in &blocks {
+        let qname = desc.qname();
+        properties.insert(qname.clone(), json!({ "$ref": format!("#/$defs/{qname}") }));
+        defs.insert(qname, block_schema(desc));
+    }
+
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "$id": SCHEMA_ID,
+        "title": "Logic Mesh block catalog",
+        "description": "The input and output values of the registered blocks, keyed by qualified block name.",
+        "type": "object",
+        "properties": properties,
+        "additionalProperties": false,
+        "$defs": defs,
+    })
+}
+
+/// Returns TypeScript declarations for the pin values of `blocks`.
+///
+/// Besides a `BlockDefinitions` interface mapping each qualified block
+/// name to its typed `inputs` and `outputs`, the output declares the
+/// kinds and their JSON values, the block descriptions returned by the
+/// wasm `listBlocks`, and the program file types.
+pub fn typescript(blocks: &[BlockDesc]) -> String {
+    let mut out = String::from(TS_PRELUDE);
+
+    out.push_str(
+        "\n/**\n * The input and output values of the registered blocks, by qualified\n * block name.\n */\nexport interface BlockDefinitions {\n",
+    );
+    for (index, desc) in sorted(blocks).iter().enumerate() {
+        if index > 0 {
+            out.push('\n');
+        }
+        write_doc(&mut out, "  ", &desc.doc);
+        let _ = writeln!(out, "  '{}': {{", desc.qname());
+        let [input_values, output_values] = initial_values(desc);
+        write_pins(&mut out, "inputs", &desc.inputs, &input_values);
+        write_pins(&mut out, "outputs", &desc.outputs, &output_values);
+        out.push_str("  };\n");
+    }
+    out.push_str(
+        "}\n\n/**\n * The input and output values of the block named `Name`.\n */\nexport type BlockDefinition<\n  Name extends keyof BlockDefinitions = keyof BlockDefinitions,\n> = BlockDefinitions[Name];\n",
+    );
+
+    out
+}
+
+/// The blocks sorted by qualified name, so the output is stable.
+fn sorted(blocks: &[BlockDesc]) -> Vec<&BlockDesc> {
+    let mut blocks: Vec<_> = blocks.iter().collect();
+    blocks.sort_by_key(|desc| desc.qname());
+    blocks
+}
+
+/// The initial values of the inputs and of the outputs of a block, by
+/// pin, if the registry can make an instance of it.
+fn initial_values(desc: &BlockDesc) -> [Vec<Option<Value>>; 2] {
+    let Some(make) = get_block(&desc.name, Some(&desc.library)).and_then(|entry| entry.make) else {
+        return Default::default();
+    };
+    let block = make();
+
+    let value = |val: Option<&Value>| val.filter(|val| !val.is_null()).cloned();
+    [
+        block
+            .inputs()
+            .iter()
+            .map(|input| value(input.get_value()))
+            .collect(),
+        block
+            .outputs()
+            .iter()
+            .map(|output| value(Some(output.value())))
+            .collect(),
+    ]
+}
+
+fn block_schema(desc: &BlockDesc) -> Json {
+    let [input_values, output_values] = initial_values(desc);
+    let pins = |pins: &[BlockPin], values: &[Option<Value>]| {
+        let mut properties = Map::new();
+        for (index, pin) in pins.iter().enumerate() {
+            let mut schema = Map::new();
+            schema.insert("$ref".into(), format!("#/$defs/{}", pin.kind).into());
+            if let Some(Some(value)) = values.get(index) {
+                if let Value::Number(num) = value
+                    && let Some(unit) = num.unit
+                {
+                    schema.insert("x-unit".into(), unit.symbol().into());
+                }
+                schema.insert(
+                    "default".into(),
+                    serde_json::to_value(value).unwrap_or_default(),
+                );
+            }
+            properties.insert(pin.name.clone(), schema.into());
+        }
+        json!({
+            "type": "object",
+            "properties": properties,
+            "additionalProperties": false,
+        })
+    };
+
+    let mut schema = json!({
+        "title": desc.dis,
+        "description": desc.doc,
+        "x-library": desc.library,
+        "x-category": desc.category,
+        "x-version": desc.ver,
+        "x-implementation": desc.implementation.to_string(),
+        "type": "object",
+        "properties": {
+            "inputs": pins(&desc.inputs, &input_values),
+            "outputs": pins(&desc.outputs, &output_values),
+        },
+        "additionalProperties": false,
+    });
+    if let Some(cond) = &desc.run_condition {
+        schema["x-runCondition"] = cond.to_string().into();
+    }
+    schema
+}
+
+/// The schema of the Hayson encoding of a kind.
+fn kind_schema(kind: HaystackKind) -> Json {
+    let tagged = |fields: Json, required: &[&str]| {
+        let mut properties = json!({ "_kind": { "const": kind.to_string() } });
+        if let Json::Object(fields) = fields {
+            properties
+                .as_object_mut()
+                .expect("Properties are an object")
+                .extend(fields);
+        }
+        let mut required_fields = vec!["_kind"];
+        required_fields.extend(required);
+        json!({
+            "type": "object",
+            "properties": properties,
+            "required": required_fields,
+        })
+    };
+    let string = json!({ "type": "string" });
+    let number = json!({ "type": "number" });
+
+    match kind {
+        HaystackKind::Null => json!({ "description": "Any value." }),
+        HaystackKind::Remove | HaystackKind::Marker | HaystackKind::Na => tagged(json!({}), &[]),
+        HaystackKind::Bool => json!({ "type": "boolean" }),
+        HaystackKind::Number => json!({
+            "oneOf": [
+                number,
+                tagged(json!({ "val": number, "unit": string }), &["val"]),
+            ]
+        }),
+        HaystackKind::Str => string,
+        HaystackKind::Uri | HaystackKind::Symbol | HaystackKind::Date | HaystackKind::Time => {
+            tagged(json!({ "val": string }), &["val"])
+        }
+        HaystackKind::Ref => tagged(json!({ "val": string, "dis": string }), &["val"]),
+        HaystackKind::DateTime => tagged(json!({ "val": string, "tz": string }), &["val"]),
+        HaystackKind::Coord => tagged(json!({ "lat": number, "lng": number }), &["lat", "lng"]),
+        HaystackKind::XStr => tagged(json!({ "type": string, "val": string }), &["type", "val"]),
+        HaystackKind::List => json!({ "type": "array" }),
+        HaystackKind::Dict => json!({ "type": "object" }),
+        HaystackKind::Grid => tagged(
+            json!({
+                "meta": { "type": "object" },
+                "cols": { "type": "array" },
+                "rows": { "type": "array" },
+            }),
+            &["cols", "rows"],
+        ),
+    }
+}
+
+/// Writes the `inputs` or `outputs` member of a block definition.
+fn write_pins(out: &mut String, member: &str, pins: &[BlockPin], values: &[Option<Value>]) {
+    if pins.is_empty() {
+        let _ = writeln!(out, "    {member}: Record<string, never>;");
+        return;
+    }
+
+    let _ = writeln!(out, "    {member}: {{");
+    for (index, pin) in pins.iter().enumerate() {
+        if let Some(Some(value)) = values.get(index) {
+            write_doc(out, "      ", &format!("Default: `{value}`"));
+        }
+        let _ = writeln!(
+            out,
+            "      {}: KindValue<'{}'>;",
+            property_name(&pin.name),
+            pin.kind
+        );
+    }
+    let _ = writeln!(out, "    }};");
+}
+
+/// Writes `doc` as a JSDoc comment, indented by `indent`.
+fn write_doc(out: &mut String, indent: &str, doc: &str) {
+    let doc = doc.trim().replace("*/", "*\\/");
+    if doc.is_empty() {
+        return;
+    }
+    if !doc.contains('\n') {
+        let _ = writeln!(out, "{indent}/** {doc} */");
+        return;
+    }
+
+    let _ = writeln!(out, "{indent}/**");
+    for line in doc.lines() {
+        let line = line.trim_end();
+        if line.is_empty() {
+            let _ = writeln!(out, "{indent} *");
+        } else {
+            let _ = writeln!(out, "{indent} * {line}");
+        }
+    }
+    let _ = writeln!(out, "{indent} */");
+}
+
+/// A pin name as a TypeScript property name, quoted when it is not an
+/// identifier.
+fn property_name(name: &str) -> String {
+    let mut chars = name.chars();
+    let is_ident = chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_' || first == '$')
+        && chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '$');
+
+    if is_ident {
+        name.to_string()
+    } else {
+        format!("'{}'", name.replace('\\', "\\\\").replace('\'', "\\'"))
+    }
+}
+
+/// The declarations that do not depend on the registered blocks.
+const TS_PRELUDE: &str = r#"// Generated by `cargo run --example export_catalog`; do not edit.
+
+/**
+ * The Haystack JSON (Hayson) encoding of the values of each kind.
+ */
+export interface KindValues {
+  null: unknown;
+  remove: { _kind: 'remove' };
+  marker: { _kind: 'marker' };
+  na: { _kind: 'na' };
+  bool: boolean;
+  number: number | { _kind: 'number'; val: number; unit?: string };
+  str: string;
+  uri: { _kind: 'uri'; val: string };
+  ref: { _kind: 'ref'; val: string; dis?: string };
+  symbol: { _kind: 'symbol'; val: string };
+  date: { _kind: 'date'; val: string };
+  time: { _kind: 'time'; val: string };
+  dateTime: { _kind: 'dateTime'; val: string; tz?: string };
+  coord: { _kind: 'coord'; lat: number; lng: number };
+  xstr: { _kind: 'xstr'; type: string; val: string };
+  list: unknown[];
+  dict: { [name: string]: unknown };
+  grid: {
+    _kind: 'grid';
+    meta?: { [name: string]: unknown };
+    cols: { name: string; meta?: { [name: string]: unknown } }[];
+    rows: { [name: string]: unknown }[];
+  };
+}
+
+/**
+ * The kind of the block pin.
+ */
+export type Kind = keyof KindValues;
+
+/**
+ * The JSON value of a pin of kind `K`.
+ */
+export type KindValue<K extends Kind> = KindValues[K];
+
+/**
+ * A block pin.
+ *
+ * Pins are the inputs and outputs of a block.
+ */
+export interface BlockPin {
+  /**
+   * The pin name
+   */
+  name: string;
+
+  /**
+   * The pin kind
+   * Must be a valid haystack type kind.
+   * See https://project-haystack.org/doc/docHaystack/Kinds
+   */
+  kind: Kind;
+
+  /**
+   * The pin value
+   * Value is a Haystack value encoded as JSON.
+   */
+  value?: unknown;
+
+  /**
+   * True if the pin is connected to another pin.
+   */
+  isConnected?: boolean;
+}
+
+/**
+ * Describe a block that is available in block library.
+ */
+export interface BlockDesc {
+  /**
+   * The block name
+   */
+  name: string;
+
+  /**
+   * The block display name
+   */
+  dis: string;
+
+  /**
+   * The block library name
+   */
+  lib: string;
+
+  /**
+   * The block library version
+   */
+  ver: string;
+
+  /**
+   * The block category
+   */
+  category: string;
+
+  /**
+   * The block documentation
+   */
+  doc: string;
+
+  /**
+   * The block implementation
+   */
+  implementation: 'native' | 'external';
+
+  /**
+   * The block inputs
+   */
+  inputs: BlockPin[];
+
+  /**
+   * The block outputs
+   */
+  outputs: BlockPin[];
+
+  /**
+   * The block run condition.
+   *
+   * If not set, the block will be executed when any of its inputs change.
+   * Otherwise, the block will execute regularly according to the run condition.
+   *
+   * Default: 'change'
+   */
+  runCondition?: 'change' | 'always';
+}
+
+/**
+ * A link between an output pin and an input pin of a program.
+ */
+export interface LinkData {
+  /**
+   * The link id
+   */
+  id?: string;
+
+  /**
+   * The link source block pin name
+   */
+  sourceBlockPinName: string;
+
+  /**
+   * The link source block uuid
+   */
+  sourceBlockUuid: string;
+
+  /**
+   * The link target block pin name
+   */
+  targetBlockPinName: string;
+
+  /**
+   * The link target block uuid
+   */
+  targetBlockUuid: string;
+}
+
+/**
+ * The value of a pin saved in a program.
+ */
+export interface PinValue {
+  value?: unknown;
+  isConnected?: boolean;
+}
+
+/**
+ * Describes a program that would be loaded in the engine.
+ *
+ * The program is a set of blocks and links between them.
+ * The program and the blocks have meta data that would be used in the editor
+ * for example to position the blocks.
+ */
+export interface Program {
+  /**
+   * Program file format version. Written by the engine on save; files
+   * without it are treated as the unversioned 1.0 format and upgraded on
+   * load.
+   */
+  version?: number;
+
+  /**
+   * The program name
+   */
+  name?: string;
+
+  /**
+   * Optional program description
+   */
+  description?: string;
+
+  /**
+   * Blocks used in the program
+   */
+  blocks: {
+    [blockUuid: string]: {
+      name: string;
+      lib: string;
+
+      /** User-supplied display label shown alongside the block-type name. */
+      label?: string;
+
+      positions?: {
+        x: number;
+        y: number;
+      };
+
+      inputs?: {
+        [pinName: string]: PinValue;
+      };
+
+      outputs?: {
+        [pinName: string]: PinValue;
+      };
+    };
+  };
+
+  /**
+   * Links between blocks
+   */
+  links: {
+    [linkUuid: string]: LinkData;
+  };
+
+  /**
+   * Parameters declared by a template program, referenced as `${name}`
+   * from input values, labels, the name and the description.
+   */
+  params?: {
+    [paramName: string]: { description?: string; default?: unknown };
+  };
+}
+"#;
+
+#[cfg(test)]
+mod test {
+    use libhaystack::val::kind::HaystackKind;
+
+    use crate::base::block::desc::BlockImplementation;
+    use crate::base::block::{BlockDesc, BlockPin};
+    use crate::blocks::registry::get_core_block;
+
+    use super::{json_schema, typescript};
+
+    fn external_desc() -> BlockDesc {
+        BlockDesc {
+            name: "Scale".into(),
+            library: "catalog_test".into(),
+            dis: "Scale".into(),
+            category: "math".into(),
+            ver: "0.1.0".into(),
+            inputs: vec![BlockPin {
+                name: "in-value".into(),
+                kind: HaystackKind::Number,
+            }],
+            outputs: vec![],
+            doc: "Scales a value.\n\nEnds a comment with */ on purpose.".into(),
+            implementation: BlockImplementation::External,
+            run_condition: None,
+        }
+    }
+
+    #[test]
+    fn test_json_schema() {
+        let add = get_core_block("Add").expect("Add exists").desc;
+        let schema = json_schema(&[external_desc(), add]);
+
+        let props = schema["properties"].as_object().expect("Properties");
+        assert_eq!(
+            props.keys().collect::<Vec<_>>(),
+            ["catalog_test::Scale", "core::Add"]
+        );
+        assert_eq!(props["core::Add"]["$ref"], "#/$defs/core::Add");
+
+        let add = &schema["$defs"]["core::Add"];
+        assert_eq!(add["title"], "Add");
+        assert_eq!(add["x-category"], "math");
+        assert_eq!(
+            add["properties"]["outputs"]["properties"]["out"]["$ref"],
+            "#/$defs/number"
+        );
+        assert!(schema["$defs"]["number"]["oneOf"].is_array());
+
+        let scale = &schema["$defs"]["catalog_test::Scale"];
+        assert_eq!(scale["x-implementation"], "external");
+        assert_eq!(
+            scale["properties"]["inputs"]["properties"]["in-value"]["$ref"],
+            "#/$defs/number"
+        );
+    }
+
+    #[test]
+    fn test_typescript() {
+        let ts = typescript(&[external_desc()]);
+
+        assert!(ts.contains("export interface BlockDefinitions {"));
+        assert!(ts.contains(
+            "  /**\n   * Scales a value.\n   *\n   * Ends a comment with *\\/ on purpose.\n   */\n  'catalog_test::Scale': {\n"
+        ));
+        assert!(ts.contains("      'in-value': KindValue<'number'>;\n"));
+        assert!(ts.contains("    outputs: Record<string, never>;\n"));
+        assert!(ts.ends_with("> = BlockDefinitions[Name];\n"));
+    }
+
+    /// The committed catalog matches the core blocks; regenerate it with
+    /// `cargo run --example export_catalog` when this fails. Blocks of
+    /// the optional block categories are left out of the catalog.
+    #[cfg(not(any(feature = "modbus", feature = "mqtt")))]
+    #[test]
+    fn test_committed_catalog_is_current() {
+        use crate::blocks::registry::{CORE_LIB, list_registered_blocks};
+
+        let blocks: Vec<_> = list_registered_blocks()
+            .into_iter()
+            .filter(|desc| desc.library == CORE_LIB)
+            .collect();
+
+        let committed: serde_json::Value =
+            serde_json::from_str(include_str!("../../schema/blocks.schema.json"))
+                .expect("Valid schema JSON");
+        assert!(
+            committed == json_schema(&blocks),
+            "schema/blocks.schema.json is stale"
+        );
+        assert!(
+            include_str!("../../web/packages/logic-mesh/src/catalog.ts") == typescript(&blocks),
+            "web/packages/logic-mesh/src/catalog.ts is stale"
+        );
+    }
+}
@@ -7,6 +7,7 @@ pub mod deadband;
 pub mod economizer;
 pub mod lead_lag;
 pub mod pid;
+pub mod pid_autotune;
 pub mod priority_array;
 pub mod reset;
 pub mod sequencer;
@@ -17,6 +18,7 @@ pub use deadband::Deadband;
 pub use economizer::Economizer;
 pub use lead_lag::LeadLag;
 pub use pid::Pid;
+pub use pid_autotune::PidAutotune;
 pub use priority_array::PriorityArray;
 pub use reset::Reset;
 pub use sequencer::Sequencer;
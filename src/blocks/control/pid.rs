@@ -37,7 +37,11 @@ use crate::{blocks::InputImpl, blocks::OutputImpl};
 ///
 /// If `input` is not connected, the controller's previous output is used as
 /// the process variable — convenient for demos that should converge to SP
-/// without an explicit plant model.
+/// without an explicit plant model. For a plant with lag and dead time,
+/// close the loop through the `sim` blocks such as `Fopdt` or `ThermalZone`.
+///
+/// To tune the gains, put a `PidAutotune` between `out` and the actuator
+/// and link its `kp`, `ki` and `kd` to this block's.
 #[block]
 #[derive(BlockProps, Debug)]
 #[category = "control"]
@@ -0,0 +1,641 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! PID relay autotuning block.
+
+use std::f64::consts::PI;
+use std::time::Duration;
+
+use libhaystack::units::units_generated::SECOND;
+use libhaystack::val::{Number, Value};
+
+use crate::base::output::props::OutputProps;
+use crate::base::{
+    block::Block,
+    input::{InputProps, input_reader::InputReader},
+    output::Output,
+};
+use crate::blocks::utils::{
+    input_as_number, input_as_number_in, input_as_number_matching, input_to_millis_or_default,
+};
+use crate::tokio_impl::sleep::current_time_millis;
+
+use crate::{blocks::InputImpl, blocks::OutputImpl};
+
+/// Relay autotuner for a [`Pid`](super::Pid) loop.
+///
+/// Sits between the controller and its actuator: `pid` takes the
+/// controller's output and `out` drives the actuator, passing `pid`
+/// through until a rising edge of `start` begins an Åström–Hägglund
+/// relay experiment. The relay then swings `out` by `step` either side
+/// of the output at the start, switching each time `input` crosses `sp`
+/// by more than `hysteresis`, until the loop oscillates steadily.
+///
+/// From the amplitude `a` and period of the last `cycles` oscillations
+/// (default 3, after a first one that is discarded) it identifies the
+/// ultimate gain `ku = 4·step / (π·√(a² − ε²))` and period `pu`, `ε`
+/// being how far from `sp` the relay switched, and computes the gains by
+/// `rule`:
+///
+/// - `zieglerNichols` (default): PI `0.45·ku`, `Ti = pu/1.2`; PID
+///   `0.6·ku`, `Ti = pu/2`, `Td = pu/8`.
+/// - `tyreusLuyben`: PI `ku/3.2`, `Ti = 2.2·pu`; PID `ku/2.2`,
+///   `Ti = 2.2·pu`, `Td = pu/6.3`. Slower, with less overshoot.
+/// - `simc`: fits a first-order-plus-dead-time model to `ku`, `pu` and
+///   the delay from each relay switch to the following peak, and applies
+///   Skogestad's PI rule with the closed-loop time constant equal to the
+///   dead time. Always PI.
+///
+/// The PI rules are used unless `derivative` is true. Once done, the
+/// `kp`, `ki = kp/Ti` (per second) and `kd` outputs hold the tuned gains,
+/// to be linked to the controller's inputs, and `out` goes back to `pid`.
+/// The filtered derivative of a [`Pid`](super::Pid) running every `dt`
+/// seconds amounts to a derivative time of `kd·dt/2`, so for the
+/// controller to act with `Td`, `kd = 2·kp·Td/dt`, with `dt` this block's
+/// `interval`: run both blocks at the same interval.
+///
+/// `status` is `idle`, `tuning`, `done` or `aborted`. The experiment
+/// aborts, leaving the gains alone, when `start` goes false, when
+/// `input` or `sp` go missing, when `input` strays from `sp` by more than
+/// `maxDeviation` (in the units of `input`, unlimited by default), or
+/// when no steady oscillation is found within `timeout` (any time unit,
+/// plain numbers are seconds, default 1 h). `reason` says why.
+///
+/// The relay output stays within `min` and `max` (default 0 and 100);
+/// `step` defaults to 10. Set `hysteresis` above the noise on `input`.
+/// `interval` accepts any time unit (`ms`, `s`, `min`, `h`); defaults to
+/// 200 ms, and should be short next to the loop's dead time.
+#[block]
+#[derive(BlockProps, Debug)]
+#[category = "control"]
+pub struct PidAutotune {
+    #[input(kind = "Number")]
+    pub input: InputImpl,
+    #[input(kind = "Number")]
+    pub sp: InputImpl,
+    #[input(kind = "Number")]
+    pub pid: InputImpl,
+    #[input(kind = "Bool")]
+    pub start: InputImpl,
+    #[input(kind = "Str")]
+    pub rule: InputImpl,
+    #[input(kind = "Bool")]
+    pub derivative: InputImpl,
+    #[input(kind = "Number")]
+    pub step: InputImpl,
+    #[input(kind = "Number")]
+    pub hysteresis: InputImpl,
+    #[input(kind = "Number")]
+    pub min: InputImpl,
+    #[input(kind = "Number")]
+    pub max: InputImpl,
+    #[input(kind = "Number")]
+    pub cycles: InputImpl,
+    #[input(kind = "Number")]
+    pub timeout: InputImpl,
+    #[input(name = "maxDeviation", kind = "Number")]
+    pub max_deviation: InputImpl,
+    #[input(kind = "Number")]
+    pub interval: InputImpl,
+    #[output(kind = "Number")]
+    pub out: OutputImpl,
+    #[output(name = "kp", kind = "Number")]
+    pub kp_out: OutputImpl,
+    #[output(name = "ki", kind = "Number")]
+    pub ki_out: OutputImpl,
+    #[output(name = "kd", kind = "Number")]
+    pub kd_out: OutputImpl,
+    #[output(kind = "Number")]
+    pub ku: OutputImpl,
+    #[output(kind = "Number")]
+    pub pu: OutputImpl,
+    #[output(kind = "Str")]
+    pub status: OutputImpl,
+    #[output(kind = "Str")]
+    pub reason: OutputImpl,
+    experiment: Option<Experiment>,
+    prev_start: Option<bool>,
+}
+
+impl Block for PidAutotune {
+    async fn execute(&mut self) {
+        let millis = input_to_millis_or_default(&self.interval.val);
+        self.wait_on_inputs(Duration::from_millis(millis)).await;
+
+        if !self.out.is_connected() {
+            return;
+        }
+        if self.status.value() == &Value::Null {
+            self.status.set("idle".into());
+        }
+
+        let start = matches!(self.start.get_value(), Some(Value::Bool(b)) if b.value);
+        let rising_start = matches!(self.prev_start, Some(false)) && start;
+        self.prev_start = Some(start);
+
+        let now = current_time_millis();
+        if rising_start {
+            match self.begin(now) {
+                Ok(experiment) => {
+                    self.experiment = Some(experiment);
+                    self.status.set("tuning".into());
+                    self.reason.set("".into());
+                }
+                Err(reason) => self.abort(reason),
+            }
+        }
+
+        if let Some(mut experiment) = self.experiment.take() {
+            match self.run(&mut experiment, start, now) {
+                Ok(None) => {
+                    self.out.set(experiment.relay.output().into());
+                    self.experiment = Some(experiment);
+                    return;
+                }
+                Ok(Some(gains)) => {
+                    self.kp_out.set(gains.kp.into());
+                    self.ki_out.set(gains.ki.into());
+                    self.kd_out.set(gains.kd.into());
+                    self.status.set("done".into());
+                }
+                Err(reason) => self.abort(reason),
+            }
+        }
+
+        if let Some(pid) = input_as_number(&self.pid) {
+            self.out.set(pid.into());
+        }
+    }
+}
+
+impl PidAutotune {
+    /// Sets up an experiment around the current controller output.
+    fn begin(&self, now: u64) -> Result<Experiment, String> {
+        let rule = Rule::from_input(&self.rule)?;
+        let step = input_as_number(&self.step).map_or(10.0, |n| n.value);
+        let min = input_as_number(&self.min).map_or(0.0, |n| n.value);
+        let max = input_as_number(&self.max).map_or(100.0, |n| n.value);
+        if step <= 0.0 || max - min < 2.0 * step {
+            return Err(format!(
+                "a step of {step} does not fit between {min} and {max}"
+            ));
+        }
+
+        let bias = input_as_number(&self.pid)
+            .map_or((min + max) / 2.0, |n| n.value)
+            .clamp(min + step, max - step);
+        let hysteresis = input_as_number_matching(
+            &self.hysteresis,
+            input_as_number(&self.input).and_then(|n| n.unit),
+        )
+        .unwrap_or(0.0)
+        .max(0.0);
+        let cycles = input_as_number(&self.cycles).map_or(3, |n| n.value.max(1.0) as usize);
+        let timeout = input_as_number_in(&self.timeout, &SECOND)
+            .filter(|secs| secs.is_finite())
+            .unwrap_or(3600.0)
+            .max(0.0);
+
+        Ok(Experiment {
+            rule,
+            cycles,
+            deadline: now.saturating_add((timeout * 1000.0) as u64),
+            relay: Relay::new(bias, step, hysteresis),
+        })
+    }
+
+    /// Steps the experiment. Returns the tuned gains once it is done.
+    fn run(
+        &mut self,
+        experiment: &mut Experiment,
+        start: bool,
+        now: u64,
+    ) -> Result<Option<Gains>, String> {
+        if !start {
+            return Err("cancelled".into());
+        }
+        let Some(input) = input_as_number(&self.input) else {
+            return Err("no process value".into());
+        };
+        let Some(sp) = input_as_number_matching(&self.sp, input.unit) else {
+            return Err("no setpoint".into());
+        };
+        if let Some(limit) = input_as_number_matching(&self.max_deviation, input.unit)
+            && (input.value - sp).abs() > limit
+        {
+            return Err(format!("process value strayed more than {limit} from sp"));
+        }
+        if now >= experiment.deadline {
+            return Err("no steady oscillation before the timeout".into());
+        }
+
+        experiment.relay.update(now, input.value, sp);
+        let Some(ultimate) = experiment.relay.ultimate(experiment.cycles) else {
+            return Ok(None);
+        };
+
+        let derivative = matches!(self.derivative.get_value(), Some(Value::Bool(b)) if b.value);
+        let dt = input_to_millis_or_default(&self.interval.val) as f64 / 1000.0;
+        let gains = ultimate.gains(experiment.rule, derivative, dt)?;
+        self.ku.set(ultimate.ku.into());
+        self.pu
+            .set(Number::make_with_unit(ultimate.pu, &SECOND).into());
+        Ok(Some(gains))
+    }
+
+    fn abort(&mut self, reason: String) {
+        self.experiment = None;
+        self.status.set("aborted".into());
+        self.reason.set(reason.as_str().into());
+    }
+}
+
+/// How gains are computed from the relay experiment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Rule {
+    ZieglerNichols,
+    TyreusLuyben,
+    Simc,
+}
+
+impl Rule {
+    /// Reads the `rule` input, Ziegler–Nichols unless set.
+    fn from_input(input: &InputImpl) -> Result<Self, String> {
+        match &input.val {
+            Some(Value::Str(name)) => match name.value.as_str() {
+                "zieglerNichols" => Ok(Self::ZieglerNichols),
+                "tyreusLuyben" => Ok(Self::TyreusLuyben),
+                "simc" => Ok(Self::Simc),
+                other => Err(format!("unknown rule '{other}'")),
+            },
+            _ => Ok(Self::ZieglerNichols),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Experiment {
+    rule: Rule,
+    cycles: usize,
+    deadline: u64,
+    relay: Relay,
+}
+
+/// Gains of a [`Pid`](super::Pid): parallel form, `ki` per second, and
+/// `kd` scaled to its filtered derivative.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Gains {
+    kp: f64,
+    ki: f64,
+    kd: f64,
+}
+
+/// A relay feedback experiment, and the oscillation cycles it produced.
+#[derive(Debug)]
+struct Relay {
+    bias: f64,
+    step: f64,
+    hysteresis: f64,
+    high: Option<bool>,
+    /// The time of the last switch, and the extreme `input` reached
+    /// since, with when.
+    swing: Option<(u64, f64, u64)>,
+    /// The trough of the current cycle, its delay after the switch up in
+    /// seconds, and how far past `sp` the switch down came.
+    trough: Option<(f64, f64, f64)>,
+    /// The time of the last switch up, where cycles start.
+    cycle_start: Option<u64>,
+    cycles: Vec<Cycle>,
+}
+
+/// One oscillation, from a switch up to the next.
+#[derive(Debug, Clone, Copy)]
+struct Cycle {
+    period: f64,
+    amplitude: f64,
+    dead_time: f64,
+    /// How far past `sp` the relay switched: `hysteresis`, and the ground
+    /// `input` covered until the next sample.
+    crossing: f64,
+}
+
+impl Relay {
+    fn new(bias: f64, step: f64, hysteresis: f64) -> Self {
+        Self {
+            bias,
+            step,
+            hysteresis,
+            high: None,
+            swing: None,
+            trough: None,
+            cycle_start: None,
+            cycles: Vec::new(),
+        }
+    }
+
+    fn output(&self) -> f64 {
+        match self.high {
+            Some(true) => self.bias + self.step,
+            _ => self.bias - self.step,
+        }
+    }
+
+    /// Switches the relay on `input`, and records the cycles it completes.
+    fn update(&mut self, now: u64, input: f64, sp: f64) {
+        let Some(high) = self.high else {
+            self.high = Some(input < sp);
+            return;
+        };
+
+        // While high the process falls for a dead time, then rises: track
+        // the trough. While low, the peak.
+        if let Some((_, extreme, at)) = &mut self.swing
+            && ((high && input < *extreme) || (!high && input > *extreme))
+        {
+            *extreme = input;
+            *at = now;
+        }
+
+        let switch = if high {
+            input > sp + self.hysteresis
+        } else {
+            input < sp - self.hysteresis
+        };
+        if !switch {
+            return;
+        }
+
+        let swing = self
+            .swing
+            .replace((now, input, now))
+            .map(|(switched, extreme, at)| (extreme, at.saturating_sub(switched) as f64 / 1000.0));
+        let crossing = (input - sp).abs();
+        self.high = Some(!high);
+        if high {
+            self.trough = swing.map(|(trough, fall)| (trough, fall, crossing));
+            return;
+        }
+
+        if let (Some(start), Some((trough, fall, down)), Some((peak, rise))) =
+            (self.cycle_start, self.trough.take(), swing)
+        {
+            self.cycles.push(Cycle {
+                period: now.saturating_sub(start) as f64 / 1000.0,
+                amplitude: (peak - trough) / 2.0,
+                dead_time: (fall + rise) / 2.0,
+                crossing: (down + crossing) / 2.0,
+            });
+        }
+        self.cycle_start = Some(now);
+    }
+
+    /// The ultimate gain and period, once the last `count` cycles after
+    /// the first agree on period and amplitude within 20%.
+    fn ultimate(&self, count: usize) -> Option<Ultimate> {
+        if self.cycles.len() <= count {
+            return None;
+        }
+        let recent = &self.cycles[self.cycles.len() - count..];
+        let mean = |of: fn(&Cycle) -> f64| recent.iter().map(of).sum::<f64>() / count as f64;
+        let steady = |of: fn(&Cycle) -> f64, mean: f64| {
+            recent
+                .iter()
+                .all(|cycle| (of(cycle) - mean).abs() <= 0.2 * mean)
+        };
+
+        let period = mean(|cycle| cycle.period);
+        let amplitude = mean(|cycle| cycle.amplitude);
+        if amplitude <= 0.0
+            || !steady(|cycle| cycle.period, period)
+            || !steady(|cycle| cycle.amplitude, amplitude)
+        {
+            return None;
+        }
+
+        let crossing = mean(|cycle| cycle.crossing).min(amplitude);
+        let swing = (amplitude.powi(2) - crossing.powi(2)).sqrt();
+        Some(Ultimate {
+            ku: 4.0 * self.step / (PI * swing.max(f64::EPSILON)),
+            pu: period,
+            dead_time: mean(|cycle| cycle.dead_time),
+            amplitude,
+            step: self.step,
+            crossing,
+        })
+    }
+}
+
+/// What a relay experiment identified, and the oscillation it came from.
+#[derive(Debug, Clone, Copy)]
+struct Ultimate {
+    ku: f64,
+    /// Seconds.
+    pu: f64,
+    /// From a relay switch to the next peak, in seconds.
+    dead_time: f64,
+    amplitude: f64,
+    step: f64,
+    /// How far past `sp` the relay switched.
+    crossing: f64,
+}
+
+impl Ultimate {
+    /// The gains by `rule`, for a controller running every `dt` seconds.
+    fn gains(&self, rule: Rule, derivative: bool, dt: f64) -> Result<Gains, String> {
+        let (ku, pu) = (self.ku, self.pu);
+        let (kp, ti, td) = match (rule, derivative) {
+            (Rule::ZieglerNichols, false) => (0.45 * ku, pu / 1.2, 0.0),
+            (Rule::ZieglerNichols, true) => (0.6 * ku, pu / 2.0, pu / 8.0),
+            (Rule::TyreusLuyben, false) => (ku / 3.2, 2.2 * pu, 0.0),
+            (Rule::TyreusLuyben, true) => (ku / 2.2, 2.2 * pu, pu / 6.3),
+            (Rule::Simc, _) => {
+                let (gain, tau, dead_time) = self.model()?;
+                let kp = tau / (gain * 2.0 * dead_time);
+                (kp, tau.min(8.0 * dead_time), 0.0)
+            }
+        };
+        Ok(Gains {
+            kp,
+            ki: kp / ti,
+            kd: 2.0 * kp * td / dt,
+        })
+    }
+
+    /// Fits `K · e^(−θs) / (τs + 1)` to the oscillation. Under a relay of
+    /// `step` `d` switching `ε` past `sp`, the process peaks θ after each
+    /// switch, at `a = K·d − (K·d − ε)·e^(−θ/τ)`, then takes
+    /// `τ · ln((a + K·d) / (K·d − ε))` to get `ε` past `sp` the other way,
+    /// the rest of the half period. Returns `(K, τ, θ)`.
+    fn model(&self) -> Result<(f64, f64, f64), String> {
+        let (amplitude, crossing, theta) = (self.amplitude, self.crossing, self.dead_time);
+        let no_fit =
+            || "the oscillation does not fit a first-order-plus-dead-time model".to_string();
+        let decay = self.pu / 2.0 - theta;
+        if theta <= 0.0 || decay <= 0.0 {
+            return Err(no_fit());
+        }
+
+        // `K·d` given τ, from the amplitude, and the decay time it implies,
+        // which grows with τ.
+        let swing = |tau: f64| {
+            let fade = (-theta / tau).exp();
+            (amplitude - crossing * fade) / (1.0 - fade)
+        };
+        let fall = |tau: f64| {
+            let swing = swing(tau);
+            tau * ((amplitude + swing) / (swing - crossing)).ln()
+        };
+
+        let (mut lo, mut hi) = (theta / 100.0, theta * 1000.0);
+        if fall(lo).is_nan() || fall(lo) > decay {
+            return Err(no_fit());
+        }
+        for _ in 0..100 {
+            let mid = (lo * hi).sqrt();
+            if fall(mid) < decay {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        let tau = (lo * hi).sqrt();
+        Ok((swing(tau) / self.step, tau, theta))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::VecDeque;
+    use std::time::Duration;
+
+    use libhaystack::val::Value;
+
+    use crate::blocks::control::PidAutotune;
+    use crate::testing::TestBench;
+
+    fn number(bench: &TestBench<PidAutotune>, pin: &str) -> f64 {
+        match bench.output(pin) {
+            Value::Number(n) => n.value,
+            other => panic!("expected a number on {pin}, got {other}"),
+        }
+    }
+
+    /// Runs a relay experiment on `2·e^(−10s) / (60s + 1)`, sampled every
+    /// 250 ms, from rest at an output of 50.
+    fn tune(rule: &str) -> TestBench<PidAutotune> {
+        tune_with(rule, false)
+    }
+
+    /// [`tune`], computing PID gains if `derivative` is set.
+    fn tune_with(rule: &str, derivative: bool) -> TestBench<PidAutotune> {
+        let (gain, tau, dead_time): (f64, f64, usize) = (2.0, 60.0, 40);
+        let mut bench = TestBench::new(PidAutotune::new());
+        bench
+            .set("input", 100)
+            .set("sp", 100)
+            .set("pid", 50)
+            .set("rule", rule)
+            .set("derivative", derivative)
+            .set("hysteresis", 0.5)
+            .set("interval", 250)
+            .set("start", false)
+            .settle();
+        bench.assert_output("status", "idle");
+        bench.set("start", true).settle();
+        bench.assert_output("status", "tuning");
+
+        let mut pv = 100.0;
+        let mut history: VecDeque<f64> = std::iter::repeat_n(50.0, dead_time).collect();
+        for _ in 0..4 * 3600 {
+            if bench.output("status") != &Value::from("tuning") {
+                break;
+            }
+            history.push_back(number(&bench, "out"));
+            let target = gain * history.pop_front().unwrap();
+            pv = target + (pv - target) * (-0.25 / tau).exp();
+            bench
+                .set("input", pv)
+                .advance(Duration::from_millis(250))
+                .settle();
+        }
+        bench
+    }
+
+    #[test]
+    fn test_autotune_ziegler_nichols() {
+        let bench = tune("zieglerNichols");
+        bench
+            .assert_output("status", "done")
+            .assert_output("out", 50);
+
+        // The relay of ±10 and hysteresis 0.5 holds the plant in a cycle of
+        // amplitude 20 − 19.5·e^(−1/6) ≈ 3.50 and period
+        // 2·(10 + 60·ln(23.50 / 19.5)) ≈ 42.4 s.
+        let (ku, pu) = (number(&bench, "ku"), number(&bench, "pu"));
+        assert!((pu - 42.4).abs() < 1.0, "pu = {pu}");
+        assert!((ku - 3.68).abs() < 0.1, "ku = {ku}");
+        assert!((number(&bench, "kp") - 0.45 * ku).abs() < 1e-9);
+        assert!((number(&bench, "ki") - 0.45 * ku * 1.2 / pu).abs() < 1e-9);
+        bench.assert_output("kd", 0);
+    }
+
+    #[test]
+    fn test_autotune_derivative_matches_the_pid() {
+        let mut bench = tune_with("zieglerNichols", true);
+        bench.assert_output("status", "done");
+
+        // Td = pu/8, scaled for a Pid running every 250 ms.
+        let (ku, pu) = (number(&bench, "ku"), number(&bench, "pu"));
+        let kp = 0.6 * ku;
+        assert!((number(&bench, "kp") - kp).abs() < 1e-9);
+        assert!((number(&bench, "ki") - kp * 2.0 / pu).abs() < 1e-9);
+        assert!((number(&bench, "kd") - 2.0 * kp * (pu / 8.0) / 0.25).abs() < 1e-9);
+        bench.set("start", false).settle();
+    }
+
+    #[test]
+    fn test_autotune_simc_fits_the_plant() {
+        let bench = tune("simc");
+        bench.assert_output("status", "done");
+
+        // Kc = τ / (K·2θ) = 1.5, Ti = min(τ, 8θ) = 60 s.
+        let (kp, ki) = (number(&bench, "kp"), number(&bench, "ki"));
+        assert!((kp - 1.5).abs() < 0.05, "kp = {kp}");
+        assert!((ki - 0.025).abs() < 0.001, "ki = {ki}");
+    }
+
+    #[test]
+    fn test_autotune_aborts() {
+        let mut bench = TestBench::new(PidAutotune::new());
+        bench
+            .set("input", 20)
+            .set("sp", 20)
+            .set("pid", 40)
+            .set("maxDeviation", 2)
+            .set("start", false)
+            .settle();
+        bench.set("start", true).settle();
+        bench.assert_output("status", "tuning");
+
+        bench.set("input", 23).settle();
+        bench
+            .assert_output("status", "aborted")
+            .assert_output("reason", "process value strayed more than 2 from sp")
+            .assert_output("out", 40)
+            .assert_output("kp", Value::Null);
+
+        bench.set("start", false).set("step", 60).settle();
+        bench.set("start", true).settle();
+        bench.assert_output("reason", "a step of 60 does not fit between 0 and 100");
+
+        bench
+            .set("input", 20)
+            .set("step", 10)
+            .set("start", false)
+            .settle();
+        bench.set("start", true).settle();
+        bench.set("start", false).settle();
+        bench
+            .assert_output("status", "aborted")
+            .assert_output("reason", "cancelled");
+    }
+}
@@ -2,10 +2,13 @@
 
 //! Priority array block.
 
+use libhaystack::val::Value;
+
 use crate::base::{
     block::{Block, BlockProps},
     input::{InputProps, input_reader::InputReader},
     output::Output,
+    priority::Priorities,
 };
 
 use crate::{blocks::InputImpl, blocks::OutputImpl};
@@ -58,17 +61,12 @@ impl Block for PriorityArray {
     async fn execute(&mut self) {
         self.read_inputs_until_ready().await;
 
-        if let Some(Some(input)) = self
-            .inputs()
-            .iter()
-            .map(|input| input.get_value())
-            .find(|input| input.is_some())
-        {
-            self.out.set(input.clone());
-        } else {
-            self.out
-                .set(self.default.get_value().cloned().unwrap_or_default());
+        let mut priorities =
+            Priorities::<Value, 16>::new(self.default.get_value().cloned().unwrap_or_default());
+        for (level, input) in self.inputs().iter().take(16).enumerate() {
+            priorities.write(level + 1, input.get_value().cloned());
         }
+        self.out.set(priorities.effective().clone());
     }
 }
 
@@ -0,0 +1,313 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Blocks whose description is only known at runtime.
+//!
+//! A [`DynBlock`] takes its pins from the [`BlockDesc`] it is created
+//! with, and leaves running the block to a [`DynExecutor`]: the function
+//! of a [`FnBlockBuilder`](super::fn_block::FnBlockBuilder), a script or
+//! a plugin. The block waits for its inputs as its description's
+//! [`run_condition`](BlockDesc::run_condition) says, and faults with the
+//! executor's error until a later run succeeds.
+
+use std::fmt::{self, Debug};
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use crate::base::block::desc::BlockRunCondition;
+use crate::base::block::{
+    Block, BlockDesc, BlockInput, BlockOutput, BlockProps, BlockState, BlockStaticDesc,
+};
+use crate::base::input::{InputProps, input_reader::InputReader};
+use crate::base::link::Link;
+use crate::base::output::OutputProps;
+use crate::blocks::utils::get_sleep_dur;
+use crate::blocks::{InputImpl, OutputImpl};
+
+/// Runs a [`DynBlock`].
+#[cfg(not(target_arch = "wasm32"))]
+pub trait DynExecutor {
+    /// Runs the block described by `desc` once over its `inputs`, setting
+    /// its `outputs`. An error faults the block with its message.
+    fn execute(
+        &mut self,
+        desc: &BlockDesc,
+        inputs: &[InputImpl],
+        outputs: &mut [OutputImpl],
+    ) -> impl std::future::Future<Output = Result<(), String>> + Send;
+}
+
+/// WASM variant of [`DynExecutor`] without the `Send` bound.
+#[cfg(target_arch = "wasm32")]
+pub trait DynExecutor {
+    /// Runs the block described by `desc` once over its `inputs`, setting
+    /// its `outputs`. An error faults the block with its message.
+    #[allow(async_fn_in_trait)]
+    async fn execute(
+        &mut self,
+        desc: &BlockDesc,
+        inputs: &[InputImpl],
+        outputs: &mut [OutputImpl],
+    ) -> Result<(), String>;
+}
+
+/// A block described at runtime and run by an executor `E`.
+pub struct DynBlock<E> {
+    id: Uuid,
+    desc: Arc<BlockDesc>,
+    inputs: Vec<InputImpl>,
+    outputs: Vec<OutputImpl>,
+    state: BlockState,
+    executor: E,
+}
+
+impl<E> DynBlock<E> {
+    /// Creates an instance of the block described by `desc`, with the id
+    /// `block_id` or a new one.
+    pub fn with_executor(
+        desc: impl Into<Arc<BlockDesc>>,
+        block_id: Option<Uuid>,
+        executor: E,
+    ) -> Self {
+        let desc = desc.into();
+        let id = block_id.unwrap_or_else(Uuid::new_v4);
+
+        Self {
+            id,
+            inputs: desc
+                .inputs
+                .iter()
+                .map(|input| InputImpl::new(&input.name, input.kind, id))
+                .collect(),
+            outputs: desc
+                .outputs
+                .iter()
+                .map(|output| OutputImpl::new_named(&output.name, output.kind, id))
+                .collect(),
+            desc,
+            state: BlockState::Running,
+            executor,
+        }
+    }
+}
+
+impl<E> Debug for DynBlock<E> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("DynBlock")
+            .field("id", &self.id)
+            .field("desc", &self.desc)
+            .field("inputs", &self.inputs)
+            .field("outputs", &self.outputs)
+            .field("state", &self.state)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<E> BlockProps for DynBlock<E> {
+    type Reader = <InputImpl as InputProps>::Reader;
+    type Writer = <InputImpl as InputProps>::Writer;
+
+    fn id(&self) -> &Uuid {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.desc.name
+    }
+
+    fn desc(&self) -> &BlockDesc {
+        &self.desc
+    }
+
+    fn state(&self) -> BlockState {
+        self.state.clone()
+    }
+
+    fn set_state(&mut self, state: BlockState) -> BlockState {
+        self.state = state;
+        self.state.clone()
+    }
+
+    fn inputs(&self) -> Vec<&(dyn BlockInput<Self::Reader, Self::Writer> + Send)> {
+        self.inputs.iter().map(|input| input as _).collect()
+    }
+
+    fn inputs_mut(&mut self) -> Vec<&mut (dyn BlockInput<Self::Reader, Self::Writer> + Send)> {
+        self.inputs.iter_mut().map(|input| input as _).collect()
+    }
+
+    fn outputs(&self) -> Vec<&(dyn BlockOutput<Self::Writer> + Send)> {
+        self.outputs.iter().map(|output| output as _).collect()
+    }
+
+    fn outputs_mut(&mut self) -> Vec<&mut (dyn BlockOutput<Self::Writer> + Send)> {
+        self.outputs.iter_mut().map(|output| output as _).collect()
+    }
+
+    fn links(&self) -> Vec<(&str, Vec<&(dyn Link + Send)>)> {
+        let inputs = self
+            .inputs
+            .iter()
+            .map(|input| (input.name(), input.links()));
+        let outputs = self
+            .outputs
+            .iter()
+            .map(|output| (output.name(), output.links()));
+        inputs.chain(outputs).collect()
+    }
+
+    fn remove_link_by_id(&mut self, link_id: &Uuid) {
+        self.inputs
+            .iter_mut()
+            .for_each(|input| input.remove_link_by_id(link_id));
+        self.outputs
+            .iter_mut()
+            .for_each(|output| output.remove_link_by_id(link_id));
+    }
+
+    fn remove_all_links(&mut self) {
+        self.inputs
+            .iter_mut()
+            .for_each(|input| input.remove_all_links());
+        self.outputs
+            .iter_mut()
+            .for_each(|output| output.remove_all_links());
+    }
+}
+
+impl<E> BlockStaticDesc for DynBlock<E> {
+    /// An empty placeholder: the description lives in the instance, see
+    /// [`BlockProps::desc`].
+    fn desc() -> &'static BlockDesc {
+        static PLACEHOLDER: LazyLock<BlockDesc> = LazyLock::new(BlockDesc::default);
+        &PLACEHOLDER
+    }
+}
+
+impl<E: DynExecutor + Send> Block for DynBlock<E> {
+    async fn execute(&mut self) {
+        if let Some(BlockRunCondition::Always) = self.desc.run_condition {
+            self.wait_on_inputs(Duration::from_millis(get_sleep_dur()))
+                .await;
+        } else {
+            self.read_inputs_until_ready().await;
+        }
+
+        match self
+            .executor
+            .execute(&self.desc, &self.inputs, &mut self.outputs)
+            .await
+        {
+            Ok(()) => {
+                if !matches!(self.state, BlockState::Running) {
+                    self.set_state(BlockState::Running);
+                }
+            }
+            Err(err) => {
+                self.set_state(BlockState::fault(err));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use libhaystack::val::Value;
+
+    use crate::HaystackKind;
+    use crate::base::block::{BlockDesc, BlockPin};
+    use crate::blocks::fn_block::FnBlockBuilder;
+    use crate::testing::ProgramScenario;
+
+    /// A block of `library` with number `inputs` and `outputs`.
+    pub(crate) fn number_desc(
+        library: &str,
+        name: &str,
+        inputs: &[&str],
+        outputs: &[&str],
+    ) -> BlockDesc {
+        let pins = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| BlockPin {
+                    name: name.to_string(),
+                    kind: HaystackKind::Number,
+                })
+                .collect()
+        };
+        BlockDesc {
+            name: name.into(),
+            library: library.into(),
+            category: "test".into(),
+            inputs: pins(inputs),
+            outputs: pins(outputs),
+            ..Default::default()
+        }
+    }
+
+    /// Plays a program feeding 3 to the `Double` block of `library`, and
+    /// expects 6 of its `out` output.
+    async fn assert_program_doubles(library: &str) {
+        let json = format!(
+            r#"{{
+                "name": "{library}",
+                "program": {{
+                    "blocks": {{
+                        "00000000-0000-4000-8000-000000000001": {{
+                            "name": "SampleHold", "lib": "core", "label": "source"
+                        }},
+                        "00000000-0000-4000-8000-000000000002": {{
+                            "name": "Double", "lib": "{library}", "label": "double"
+                        }}
+                    }},
+                    "links": {{
+                        "00000000-0000-4000-8000-0000000000aa": {{
+                            "sourceBlockUuid": "00000000-0000-4000-8000-000000000001",
+                            "sourceBlockPinName": "out",
+                            "targetBlockUuid": "00000000-0000-4000-8000-000000000002",
+                            "targetBlockPinName": "in"
+                        }}
+                    }}
+                }},
+                "timeline": [
+                    {{ "at": 0, "write": {{ "source.out": 3 }} }},
+                    {{ "at": "1s", "expect": {{ "double.out": 6 }} }}
+                ]
+            }}"#
+        );
+        ProgramScenario::from_json(&json, "")
+            .unwrap()
+            .run_async()
+            .await
+            .unwrap()
+            .assert_ok();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_programs_run_dyn_blocks_by_name() {
+        FnBlockBuilder::new(number_desc("fn_program_test", "Double", &["in"], &["out"]))
+            .register(|_, inputs| {
+                Box::pin(async move { Ok(vec![Value::from(f64::try_from(&inputs[0])? * 2.0)]) })
+            })
+            .unwrap();
+        assert_program_doubles("fn_program_test").await;
+
+        #[cfg(feature = "scripting")]
+        {
+            use crate::scripting::{ScriptLimits, register_script_block};
+
+            let desc = number_desc("script_program_test", "Double", &["in"], &["out"]);
+            register_script_block(&desc, r#"inputs["in"] * 2.0"#, ScriptLimits::default()).unwrap();
+            assert_program_doubles("script_program_test").await;
+        }
+
+        #[cfg(feature = "plugins")]
+        {
+            use crate::plugins::{PluginLimits, block::test::component, register_plugin};
+
+            register_plugin("plugin_program_test", &component(), PluginLimits::default()).unwrap();
+            assert_program_doubles("plugin_program_test").await;
+        }
+    }
+}
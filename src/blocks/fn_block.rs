@@ -0,0 +1,240 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Blocks defined at runtime by a function, without a block type.
+//!
+//! The Rust counterpart of `defineBlock` on the JavaScript side: a
+//! [`FnBlockBuilder`] registers a [`BlockDesc`] along with the async
+//! function that executes it, so the block is scheduled by
+//! [`schedule_block`](super::registry::schedule_block), evaluated by
+//! [`eval_static_block`](super::registry::eval_static_block) and loaded
+//! from programs like any registered block.
+
+use std::error::Error as StdError;
+use std::sync::Arc;
+
+use futures::future::BoxFuture;
+use libhaystack::val::Value;
+
+use crate::base::block::BlockDesc;
+use crate::base::block::desc::BlockImplementation;
+use crate::base::error::RegistryError;
+use crate::base::input::InputProps;
+use crate::base::output::Output;
+use crate::blocks::dyn_block::{DynBlock, DynExecutor};
+use crate::blocks::registry::{RegisteredBlock, register_erased};
+use crate::blocks::{InputImpl, OutputImpl};
+
+/// What a block function returns: the output values, in output order, or
+/// the error that faults the block.
+pub type FnBlockResult = Result<Vec<Value>, Box<dyn StdError + Send + Sync>>;
+
+/// The function executing a block, over the instance's state and the
+/// input values.
+type ExecuteFn<S> =
+    dyn for<'a> Fn(&'a mut S, Vec<Value>) -> BoxFuture<'a, FnBlockResult> + Send + Sync;
+
+/// Registers a block defined by a description and an async function.
+///
+/// On every run, the function gets the block instance's state and the
+/// input values in input order, [`Value::Null`] for the inputs without
+/// one. It returns the output values in output order; a shorter list
+/// leaves the remaining outputs as they are, and an error faults the
+/// block until a later run succeeds.
+///
+/// # Examples
+///
+/// ```
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() -> Result<(), logic_mesh::Error> {
+/// use logic_mesh::{
+///     HaystackKind, Value,
+///     base::block::{BlockDesc, BlockPin},
+///     blocks::{fn_block::FnBlockBuilder, registry::eval_static_block},
+/// };
+///
+/// let pin = |name: &str| BlockPin { name: name.into(), kind: HaystackKind::Number };
+/// let desc = BlockDesc {
+///     name: "Totalize".into(),
+///     library: "glue".into(),
+///     category: "math".into(),
+///     inputs: vec![pin("in")],
+///     outputs: vec![pin("total")],
+///     ..Default::default()
+/// };
+///
+/// FnBlockBuilder::new(desc)
+///     .with_state(|| 0.0)
+///     .register(|total: &mut f64, inputs| {
+///         Box::pin(async move {
+///             *total += f64::try_from(&inputs[0])?;
+///             Ok(vec![Value::from(*total)])
+///         })
+///     })?;
+///
+/// let outputs = eval_static_block("Totalize", Some("glue"), vec![Value::from(2)]).await?;
+/// assert_eq!(outputs, vec![Value::from(2)]);
+/// # Ok(())
+/// # }
+/// ```
+pub struct FnBlockBuilder<S = ()> {
+    desc: BlockDesc,
+    init: Arc<dyn Fn() -> S + Send + Sync>,
+}
+
+impl FnBlockBuilder {
+    /// Starts a block described by `desc`, without state.
+    pub fn new(desc: BlockDesc) -> Self {
+        Self {
+            desc,
+            init: Arc::new(|| ()),
+        }
+    }
+}
+
+impl<S: Send + Sync + 'static> FnBlockBuilder<S> {
+    /// Gives every instance of the block its own state, created by `init`.
+    pub fn with_state<T>(self, init: impl Fn() -> T + Send + Sync + 'static) -> FnBlockBuilder<T> {
+        FnBlockBuilder {
+            desc: self.desc,
+            init: Arc::new(init),
+        }
+    }
+
+    /// Registers the block, executed by `execute`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a block with the same name is already registered in the
+    /// library of the description.
+    pub fn register<F>(self, execute: F) -> Result<(), RegistryError>
+    where
+        F: for<'a> Fn(&'a mut S, Vec<Value>) -> BoxFuture<'a, FnBlockResult>
+            + Send
+            + Sync
+            + 'static,
+    {
+        let desc = Arc::new(BlockDesc {
+            implementation: BlockImplementation::Native,
+            ..self.desc
+        });
+        let execute: Arc<ExecuteFn<S>> = Arc::new(execute);
+        let init = self.init;
+
+        register_erased(
+            desc.as_ref().clone(),
+            Arc::new(move |block_id| {
+                RegisteredBlock::new(DynBlock::with_executor(
+                    desc.clone(),
+                    block_id,
+                    FnExecutor {
+                        state: init(),
+                        execute: execute.clone(),
+                    },
+                ))
+            }),
+        )
+    }
+}
+
+/// Runs an instance of a block registered by a [`FnBlockBuilder`].
+struct FnExecutor<S> {
+    state: S,
+    execute: Arc<ExecuteFn<S>>,
+}
+
+impl<S: Send> DynExecutor for FnExecutor<S> {
+    async fn execute(
+        &mut self,
+        _desc: &BlockDesc,
+        inputs: &[InputImpl],
+        outputs: &mut [OutputImpl],
+    ) -> Result<(), String> {
+        let inputs = inputs
+            .iter()
+            .map(|input| input.get_value().cloned().unwrap_or_default())
+            .collect();
+
+        let values = (self.execute)(&mut self.state, inputs)
+            .await
+            .map_err(|err| err.to_string())?;
+        for (output, value) in outputs.iter_mut().zip(values) {
+            output.set(value);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use libhaystack::val::Value;
+
+    use crate::base::block::test_utils::write_block_inputs;
+    use crate::base::block::{Block, BlockDesc, BlockProps, desc::BlockRunCondition};
+    use crate::base::error::RegistryError;
+    use crate::blocks::dyn_block::test::number_desc;
+    use crate::blocks::registry::{eval_static_block, get_block};
+
+    use super::FnBlockBuilder;
+
+    fn fn_desc(name: &str, inputs: &[&str], outputs: &[&str]) -> BlockDesc {
+        number_desc("fn_test", name, inputs, outputs)
+    }
+
+    #[tokio::test]
+    async fn test_fn_block_evaluates_and_faults() {
+        FnBlockBuilder::new(fn_desc("Ratio", &["a", "b"], &["out"]))
+            .register(|_, inputs| {
+                Box::pin(async move {
+                    let a = f64::try_from(&inputs[0])?;
+                    let b = f64::try_from(&inputs[1])?;
+                    if b == 0.0 {
+                        return Err("division by zero".into());
+                    }
+                    Ok(vec![Value::from(a / b)])
+                })
+            })
+            .unwrap();
+
+        let outputs = eval_static_block("Ratio", Some("fn_test"), vec![6.into(), 3.into()]).await;
+        assert_eq!(outputs.unwrap(), vec![Value::from(2)]);
+
+        let err = FnBlockBuilder::new(fn_desc("Ratio", &[], &[]))
+            .register(|_, _| Box::pin(async { Ok(vec![]) }))
+            .expect_err("already registered");
+        assert!(matches!(err, RegistryError::BlockAlreadyRegistered { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_fn_block_keeps_state_per_instance() {
+        let desc = BlockDesc {
+            run_condition: Some(BlockRunCondition::Change),
+            ..fn_desc("Count", &["in"], &["count"])
+        };
+        FnBlockBuilder::new(desc)
+            .with_state(|| 0_i32)
+            .register(|count: &mut i32, _| {
+                Box::pin(async move {
+                    *count += 1;
+                    Ok(vec![Value::from(*count)])
+                })
+            })
+            .unwrap();
+
+        let make = || {
+            get_block("Count", Some("fn_test"))
+                .and_then(|entry| entry.make_erased)
+                .expect("Count is registered")(None)
+        };
+        let mut first = make();
+        for count in [1, 2] {
+            write_block_inputs([(first.get_input_mut("in").unwrap(), count)]).await;
+            first.execute().await;
+            assert_eq!(first.outputs()[0].value(), &Value::from(count));
+        }
+
+        let mut second = make();
+        write_block_inputs([(second.get_input_mut("in").unwrap(), 1)]).await;
+        second.execute().await;
+        assert_eq!(second.outputs()[0].value(), &Value::from(1));
+    }
+}
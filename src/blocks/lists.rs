@@ -0,0 +1,188 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Element-wise evaluation of blocks over list values.
+//!
+//! `Number` and `Bool` pins accept lists of such values, as built by the
+//! `List` block. A block that supports them evaluates its operands
+//! element-wise when any of them is a list, repeating a plain value for
+//! every element, so `Add` of a list of 40 zone temperatures and an
+//! offset yields 40 adjusted temperatures. The lists must all have the
+//! same length. A lone list operand is reduced instead: `Average` of
+//! the 40 temperatures is their mean.
+
+use libhaystack::val::kind::HaystackKind;
+use libhaystack::val::{Number, Value};
+
+use crate::base::error::ValueError;
+
+/// Splits `operands` into one row of operands per list element, with
+/// the plain values repeated on every row. [`None`] if no operand is a
+/// list.
+///
+/// # Errors
+///
+/// Fails if the lists have different lengths.
+pub(crate) fn rows<'a>(operands: &[&'a Value]) -> Result<Option<Vec<Vec<&'a Value>>>, ValueError> {
+    let mut len = None;
+    for operand in operands {
+        if let Value::List(list) = operand {
+            match len {
+                None => len = Some(list.len()),
+                Some(expected) if expected != list.len() => {
+                    return Err(ValueError::LengthMismatch {
+                        expected,
+                        actual: list.len(),
+                    });
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    Ok(len.map(|len| {
+        (0..len)
+            .map(|index| {
+                operands
+                    .iter()
+                    .map(|operand| match operand {
+                        Value::List(list) => &list[index],
+                        other => *other,
+                    })
+                    .collect()
+            })
+            .collect()
+    }))
+}
+
+/// Evaluates a numeric block over `operands`: a lone list is reduced by
+/// `reduce`, several operands are reduced element-wise when any of them
+/// is a list, giving a list, and plain numbers are reduced as they are.
+///
+/// # Errors
+///
+/// Fails if a lone list is empty, if the lists have different lengths,
+/// if an element is not a number, or if `reduce` fails.
+pub(crate) fn eval_numbers(
+    operands: &[&Value],
+    reduce: impl Fn(&[Number]) -> Result<Number, ValueError>,
+) -> Result<Value, ValueError> {
+    if let [Value::List(list)] = operands {
+        if list.is_empty() {
+            return Err(ValueError::EmptyList);
+        }
+        return reduce(&numbers(list.iter())?).map(Value::from);
+    }
+
+    match rows(operands)? {
+        None => reduce(&numbers(operands.iter().copied())?).map(Value::from),
+        Some(rows) => rows
+            .into_iter()
+            .map(|row| reduce(&numbers(row.into_iter())?).map(Value::from))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::make_list),
+    }
+}
+
+/// The numbers among `values`.
+///
+/// # Errors
+///
+/// Fails on the first value that is not a number.
+pub(crate) fn numbers<'a>(
+    values: impl Iterator<Item = &'a Value>,
+) -> Result<Vec<Number>, ValueError> {
+    values
+        .map(|value| match value {
+            Value::Number(num) => Ok(*num),
+            other => Err(ValueError::UnexpectedValue {
+                expected: HaystackKind::Number,
+                actual: Box::new(other.clone()),
+            }),
+        })
+        .collect()
+}
+
+/// The samples of a filter input: the number, or the numbers of a list,
+/// and whether it was a list. [`None`] if the input holds neither.
+///
+/// # Errors
+///
+/// Fails if a list element is not a number.
+pub(crate) fn samples(value: Option<&Value>) -> Result<Option<(Vec<Number>, bool)>, ValueError> {
+    match value {
+        Some(Value::Number(num)) => Ok(Some((vec![*num], false))),
+        Some(Value::List(list)) => Ok(Some((numbers(list.iter())?, true))),
+        _ => Ok(None),
+    }
+}
+
+/// The output of a filter for `values`, a list if its input was a list.
+pub(crate) fn from_samples(mut values: Vec<Value>, is_list: bool) -> Value {
+    if is_list || values.len() != 1 {
+        Value::make_list(values)
+    } else {
+        values.remove(0)
+    }
+}
+
+/// The operand values of the inputs that hold a number or a list.
+pub(crate) fn numeric_operands<'a>(values: impl Iterator<Item = Option<&'a Value>>) -> Vec<Value> {
+    values
+        .flatten()
+        .filter(|value| matches!(value, Value::Number(_) | Value::List(_)))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use libhaystack::val::{Number, Value};
+
+    use crate::base::error::ValueError;
+
+    use super::{eval_numbers, rows};
+
+    fn list(values: &[f64]) -> Value {
+        Value::make_list(values.iter().map(|&val| val.into()).collect())
+    }
+
+    fn sum(numbers: &[Number]) -> Result<Number, ValueError> {
+        Ok(Number::make(numbers.iter().map(|num| num.value).sum()))
+    }
+
+    #[test]
+    fn test_rows() {
+        let scalar = Value::from(1.0);
+        assert!(rows(&[&scalar, &scalar]).unwrap().is_none());
+
+        let values = list(&[2.0, 3.0]);
+        let rows = rows(&[&values, &scalar]).unwrap().unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1], [&Value::from(3.0), &scalar]);
+    }
+
+    #[test]
+    fn test_eval_numbers() {
+        let scalar = Value::from(1.0);
+        let values = list(&[2.0, 3.0]);
+
+        assert_eq!(eval_numbers(&[&values], sum).unwrap(), 5.0.into());
+        assert_eq!(
+            eval_numbers(&[&values, &scalar], sum).unwrap(),
+            list(&[3.0, 4.0])
+        );
+        assert_eq!(eval_numbers(&[&scalar, &scalar], sum).unwrap(), 2.0.into());
+
+        assert!(matches!(
+            eval_numbers(&[&values, &list(&[1.0])], sum),
+            Err(ValueError::LengthMismatch {
+                expected: 2,
+                actual: 1
+            })
+        ));
+        assert!(matches!(
+            eval_numbers(&[&list(&[])], sum),
+            Err(ValueError::EmptyList)
+        ));
+    }
+}
@@ -9,6 +9,8 @@ use crate::{blocks::InputImpl, blocks::OutputImpl};
 use super::util::execute_impl;
 
 /// Outputs true if value of the inputs are equal.
+/// If either input is a list, the inputs are compared element-wise and
+/// the output is a list of booleans.
 #[block]
 #[derive(BlockProps, Debug)]
 #[category = "logic"]
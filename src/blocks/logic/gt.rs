@@ -9,6 +9,8 @@ use crate::{blocks::InputImpl, blocks::OutputImpl};
 use super::util::execute_impl;
 
 /// Outputs true if value of the in1 is greater.
+/// If either input is a list, the inputs are compared element-wise and
+/// the output is a list of booleans.
 #[block]
 #[derive(BlockProps, Debug)]
 #[dis = "GreaterThan"]
@@ -31,8 +33,13 @@ impl Block for GreaterThan {
 #[cfg(test)]
 mod test {
 
+    use libhaystack::units::get_unit;
+    use libhaystack::val::{Number, Value};
+
     use crate::{
-        base::block::Block, base::block::test_utils::write_block_inputs, blocks::logic::GreaterThan,
+        base::block::test_utils::write_block_inputs,
+        base::block::{Block, BlockProps},
+        blocks::logic::GreaterThan,
     };
 
     #[tokio::test]
@@ -44,4 +51,35 @@ mod test {
         block.execute().await;
         assert_eq!(block.out.value, true.into());
     }
+
+    #[tokio::test]
+    async fn test_gt_block_units() {
+        let mut block = GreaterThan::new();
+        let num = |value, unit| Number::make_with_unit(value, get_unit(unit).unwrap());
+
+        write_block_inputs([
+            (&mut block.input1, num(77.0, "°F")),
+            (&mut block.input2, num(20.0, "°C")),
+        ])
+        .await;
+        block.execute().await;
+        assert_eq!(block.out.value, true.into());
+
+        write_block_inputs([(&mut block.input2, num(20.0, "kPa"))]).await;
+        block.execute().await;
+        assert!(block.state().is_fault());
+    }
+
+    #[tokio::test]
+    async fn test_gt_block_lists() {
+        let mut block = GreaterThan::new();
+        let temps = Value::make_list(vec![18.0.into(), 23.0.into(), 21.0.into()]);
+
+        write_block_inputs([(&mut block.input1, temps), (&mut block.input2, 20.0.into())]).await;
+        block.execute().await;
+        assert_eq!(
+            block.out.value,
+            Value::make_list(vec![false.into(), true.into(), true.into()])
+        );
+    }
 }
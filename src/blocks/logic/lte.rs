@@ -9,6 +9,8 @@ use crate::{blocks::InputImpl, blocks::OutputImpl};
 use super::util::execute_impl;
 
 /// Outputs true if value of the in1 is less or equal.
+/// If either input is a list, the inputs are compared element-wise and
+/// the output is a list of booleans.
 #[block]
 #[derive(BlockProps, Debug)]
 #[dis = "LessThanEqual"]
@@ -3,17 +3,57 @@
 use libhaystack::val::Bool;
 use libhaystack::val::Value;
 
-use crate::base::block::{Block, convert_value};
+use crate::base::block::{Block, BlockState, convert_value};
+use crate::base::error::ValueError;
 use crate::base::input::input_reader::InputReader;
+use crate::blocks::{lists, units};
 
-/// Converts the inputs to the same type.
-pub(super) fn convert_inputs<B: Block>(block: &B) -> (Option<Value>, Option<Value>) {
-    let in1 = block.inputs().first().and_then(|input| input.get_value());
-    let in2 = block.inputs().get(1).and_then(|input| input.get_value());
-
+/// Converts the inputs to the same type, and numbers to the same unit.
+fn convert_inputs(
+    in1: Option<&Value>,
+    in2: Option<&Value>,
+) -> Result<(Option<Value>, Option<Value>), ValueError> {
     let in2 = in2.and_then(|in2| in1.and_then(|in1| convert_value(in1, in2.clone()).ok()));
 
-    (in1.cloned(), in2)
+    match (in1, in2) {
+        (Some(Value::Number(num1)), Some(Value::Number(num2))) => {
+            let (num1, num2) = match (num1.unit, num2.unit) {
+                (Some(unit), _) => (*num1, units::convert(num2, unit)?),
+                (None, Some(unit)) => (units::convert(*num1, unit)?, num2),
+                (None, None) => (*num1, num2),
+            };
+            Ok((Some(num1.into()), Some(num2.into())))
+        }
+        (in1, in2) => Ok((in1.cloned(), in2)),
+    }
+}
+
+/// Compares the inputs, element-wise if either of them is a list.
+fn compare(
+    in1: Option<&Value>,
+    in2: Option<&Value>,
+    func: impl Fn(Option<Value>, Option<Value>) -> bool,
+) -> Result<Option<Value>, ValueError> {
+    let is_list = |value: Option<&Value>| matches!(value, Some(Value::List(_)));
+    let compare_one = |in1, in2| {
+        let (in1, in2) = convert_inputs(in1, in2)?;
+        Ok(Value::from(Bool {
+            value: func(in1, in2),
+        }))
+    };
+
+    match (in1, in2) {
+        (Some(in1), Some(in2)) if is_list(Some(in1)) || is_list(Some(in2)) => {
+            let rows = lists::rows(&[in1, in2])?.unwrap_or_default();
+            rows.into_iter()
+                .map(|row| compare_one(Some(row[0]), Some(row[1])))
+                .collect::<Result<Vec<_>, _>>()
+                .map(|bools| Some(Value::make_list(bools)))
+        }
+        // A list is compared once both inputs are there.
+        (in1, in2) if is_list(in1) || is_list(in2) => Ok(None),
+        (in1, in2) => compare_one(in1, in2).map(Some),
+    }
 }
 
 /// Executes the block.
@@ -22,12 +62,21 @@ pub(super) async fn execute_impl<B: Block>(
     func: impl Fn(Option<Value>, Option<Value>) -> bool,
 ) {
     block.read_inputs_until_ready().await;
-    let (input1, input2) = convert_inputs(block);
+    let in1 = block.inputs().first().and_then(|input| input.get_value());
+    let in2 = block.inputs().get(1).and_then(|input| input.get_value());
 
-    block.outputs_mut()[0].set(
-        Bool {
-            value: func(input1, input2),
+    let out = match compare(in1, in2, func) {
+        Ok(Some(out)) => out,
+        Ok(None) => return,
+        Err(err) => {
+            let fault = BlockState::fault(format!("{}: {err}", block.desc().name));
+            block.set_state(fault);
+            return;
         }
-        .into(),
-    );
+    };
+    if !matches!(block.state(), BlockState::Running) {
+        block.set_state(BlockState::Running);
+    }
+
+    block.outputs_mut()[0].set(out);
 }
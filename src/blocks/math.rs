@@ -12,6 +12,7 @@ pub mod cos;
 pub mod div;
 pub mod even;
 pub mod exp;
+pub mod expr;
 pub mod log10;
 pub mod logn;
 pub mod max;
@@ -37,6 +38,7 @@ pub use cos::Cos;
 pub use div::Div;
 pub use even::Even;
 pub use exp::Exp;
+pub use expr::Expr;
 pub use log10::Log10;
 pub use logn::Logn;
 pub use max::Max;
@@ -4,23 +4,33 @@
 
 use crate::base::{
     block::{Block, BlockProps, BlockState},
+    error::ValueError,
     input::input_reader::InputReader,
     output::Output,
 };
 
-use libhaystack::val::{Number, Value};
-
-use crate::blocks::OutputImpl;
+use crate::blocks::{
+    OutputImpl,
+    lists::{eval_numbers, numeric_operands},
+    units,
+};
 
 /// Performs an addition of multiple numbers from the 16 inputs
 /// this block has.
 /// The addition would take into account the units of those input's values,
-/// if the units are not convertible, the block would be in an error state.
+/// converting them to the unit of the first one; a temperature difference
+/// (`Δ°C`) can be added to a temperature (`°F`), but temperatures are
+/// only added in the same unit. If the units are not convertible, the
+/// block would be in an error state.
+///
+/// A list input, as built by the `List` block, is added element-wise to
+/// the other inputs, a single number being added to every element, and
+/// the output is a list. A list on its own is summed up.
 #[block]
 #[derive(BlockProps, Debug)]
 #[dis = "Add"]
 #[category = "math"]
-#[input(kind = "Number", count = 16)]
+#[input(kind = "Number", count = 16, lists = true)]
 pub struct Add {
     #[output(kind = "Number")]
     pub out: OutputImpl,
@@ -30,48 +40,39 @@ impl Block for Add {
     async fn execute(&mut self) {
         self.read_inputs_until_ready().await;
 
-        let mut has_err = false;
-
-        let val = self
-            .inputs()
-            .into_iter()
-            .filter_map(|input| match input.get_value().as_ref() {
-                Some(Value::Number(num)) => Some(*num),
-                _ => None,
-            })
-            .reduce(|mut acc, val| {
-                if acc.unit.is_none()
-                    && acc.value == 0.0
-                    && let Some(unit) = val.unit
-                {
-                    acc = Number::make_with_unit(0.0, unit);
-                };
-
-                match acc + val {
-                    Ok(res) => res,
-                    Err(_) => {
-                        has_err = true;
-                        Number::make(0.0)
-                    }
-                }
-            });
-
-        if has_err {
-            self.set_state(BlockState::fault("Add: unit mismatch between inputs"));
-        } else if !matches!(self.state(), BlockState::Running) {
-            self.set_state(BlockState::Running);
+        let operands = numeric_operands(self.inputs().into_iter().map(|input| input.get_value()));
+        if operands.is_empty() {
+            return;
         }
 
-        if let Some(res) = val {
-            self.out.set((res).into())
+        let res = eval_numbers(&operands.iter().collect::<Vec<_>>(), |numbers| {
+            let [first, rest @ ..] = numbers else {
+                return Err(ValueError::EmptyList);
+            };
+            rest.iter().copied().try_fold(*first, units::add)
+        });
+        match res {
+            Ok(res) => {
+                if !matches!(self.state(), BlockState::Running) {
+                    self.set_state(BlockState::Running);
+                }
+                self.out.set(res)
+            }
+            Err(err) => {
+                self.set_state(BlockState::fault(format!("Add: {err}")));
+            }
         }
     }
 }
 
 #[cfg(test)]
 mod test {
+    use libhaystack::units::get_unit;
+    use libhaystack::val::{Number, Value};
+
     use crate::{
-        base::block::{Block, BlockProps},
+        base::block::test_utils::write_block_inputs,
+        base::block::{Block, BlockProps, BlockState},
         base::input::input_reader::InputReader,
         blocks::math::Add,
     };
@@ -100,4 +101,44 @@ mod test {
         block.execute().await;
         assert_eq!(block.out.value, 6.into());
     }
+
+    #[tokio::test]
+    async fn test_add_block_units() {
+        let mut block = Add::new();
+        let num = |value, unit| Number::make_with_unit(value, get_unit(unit).unwrap());
+
+        write_block_inputs([(block.get_input_mut("in0").unwrap(), num(68.0, "°F"))]).await;
+        write_block_inputs([(block.get_input_mut("in1").unwrap(), num(10.0, "Δ°C"))]).await;
+        block.execute().await;
+        assert_eq!(block.out.value, num(86.0, "°F").into());
+
+        write_block_inputs([(block.get_input_mut("in1").unwrap(), num(1.0, "kPa"))]).await;
+        block.execute().await;
+        assert_eq!(
+            block.state(),
+            BlockState::fault("Add: Incompatible units °F and kPa")
+        );
+        assert_eq!(block.out.value, num(86.0, "°F").into());
+    }
+
+    #[tokio::test]
+    async fn test_add_block_lists() {
+        let mut block = Add::new();
+        let list = |values: &[f64]| Value::make_list(values.iter().map(|&v| v.into()).collect());
+
+        write_block_inputs([(block.get_input_mut("in0").unwrap(), list(&[1.0, 2.0]))]).await;
+        block.execute().await;
+        assert_eq!(block.out.value, 3.into());
+
+        write_block_inputs([(block.get_input_mut("in1").unwrap(), 10)]).await;
+        block.execute().await;
+        assert_eq!(block.out.value, list(&[11.0, 12.0]));
+
+        write_block_inputs([(block.get_input_mut("in1").unwrap(), list(&[1.0]))]).await;
+        block.execute().await;
+        assert_eq!(
+            block.state(),
+            BlockState::fault("Add: List lengths differ: 2 and 1")
+        );
+    }
 }
@@ -11,19 +11,25 @@ use crate::{
     blocks::utils::convert_units,
 };
 
-use libhaystack::val::{Number, Value};
+use libhaystack::val::Number;
 
-use crate::blocks::OutputImpl;
+use crate::blocks::{
+    OutputImpl,
+    lists::{eval_numbers, numeric_operands},
+};
 
 /// Calculates an average of multiple numbers from the 16 inputs
 /// this block has.
 /// The operation would take into account the units of those input's values,
 /// if the units are not convertible, the block would be in an error state.
+///
+/// A list input is evaluated element-wise with the other inputs, giving a
+/// list. A list on its own gives the average of its elements.
 #[block]
 #[derive(BlockProps, Debug)]
 #[dis = "Average"]
 #[category = "math"]
-#[input(kind = "Number", count = 16)]
+#[input(kind = "Number", count = 16, lists = true)]
 pub struct Average {
     #[output(kind = "Number")]
     pub out: OutputImpl,
@@ -33,35 +39,29 @@ impl Block for Average {
     async fn execute(&mut self) {
         self.read_inputs_until_ready().await;
 
-        let val = self
-            .inputs()
-            .into_iter()
-            .filter_map(|input| match input.get_value().as_ref() {
-                Some(Value::Number(num)) => Some(*num),
-                _ => None,
-            })
-            .collect::<Vec<Number>>();
+        let operands = numeric_operands(self.inputs().into_iter().map(|input| input.get_value()));
+        if operands.is_empty() {
+            return;
+        }
 
-        if let Ok(numbers) = convert_units(&val) {
-            if !matches!(self.state(), BlockState::Running) {
-                self.set_state(BlockState::Running);
+        let res = eval_numbers(&operands.iter().collect::<Vec<_>>(), |numbers| {
+            let numbers = convert_units(numbers)?;
+            let avg = numbers.iter().map(|n| n.value).sum::<f64>() / numbers.len() as f64;
+            Ok(Number {
+                value: avg,
+                unit: numbers[0].unit,
+            })
+        });
+        match res {
+            Ok(res) => {
+                if !matches!(self.state(), BlockState::Running) {
+                    self.set_state(BlockState::Running);
+                }
+                self.out.set(res)
+            }
+            Err(err) => {
+                self.set_state(BlockState::fault(format!("Average: {err}")));
             }
-
-            let avg = numbers.iter().fold(0.0, |acc, n| acc + n.value) / numbers.len() as f64;
-
-            let avg = if let Some(Number {
-                value: _,
-                unit: Some(unit),
-            }) = numbers.first()
-            {
-                Number::make_with_unit(avg, unit)
-            } else {
-                Number::make(avg)
-            };
-
-            self.out.set((avg).into())
-        } else {
-            self.set_state(BlockState::fault("Average: unit conversion failed"));
         }
     }
 }
@@ -69,9 +69,13 @@ impl Block for Average {
 #[cfg(test)]
 mod test {
 
+    use libhaystack::val::Value;
+
     use crate::base::block::test_utils::write_block_inputs;
     use crate::{
-        base::block::Block, base::input::input_reader::InputReader, blocks::math::Average,
+        base::block::{Block, BlockProps, BlockState},
+        base::input::input_reader::InputReader,
+        blocks::math::Average,
     };
 
     #[tokio::test]
@@ -85,4 +89,21 @@ mod test {
         block.execute().await;
         assert_eq!(block.out.value, 5.into());
     }
+
+    #[tokio::test]
+    async fn test_average_block_list() {
+        let mut block = Average::new();
+        let temps = Value::make_list(vec![18.0.into(), 23.0.into(), 22.0.into()]);
+
+        write_block_inputs([(block._inputs.get_mut(0).unwrap(), temps)]).await;
+        block.execute().await;
+        assert_eq!(block.out.value, 21.into());
+
+        write_block_inputs([(block._inputs.get_mut(0).unwrap(), Value::make_list(vec![]))]).await;
+        block.execute().await;
+        assert_eq!(
+            block.state(),
+            BlockState::fault("Average: Expected a non-empty list")
+        );
+    }
 }
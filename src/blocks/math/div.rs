@@ -3,18 +3,20 @@
 //! Division block.
 
 use crate::base::{
-    block::Block,
+    block::{Block, BlockProps, BlockState},
     input::{InputProps, input_reader::InputReader},
     output::Output,
 };
 
 use libhaystack::val::Value;
 
-use crate::{blocks::InputImpl, blocks::OutputImpl};
+use crate::blocks::{InputImpl, OutputImpl, units};
 
 /// Performs an division of 2 numbers.
-/// The operation would take into account the units of those input's values,
-/// if the units are not convertible, the block would be in an error state.
+/// The unit of the quotient is derived from the units of the inputs, such
+/// as `m/s` for `m` over `s`; inputs of the same dimension give a plain
+/// ratio. If no unit matches the quotient's dimensions, the block would be
+/// in an error state.
 #[block]
 #[derive(BlockProps, Debug)]
 #[dis = "Divide"]
@@ -34,9 +36,18 @@ impl Block for Div {
 
         if let (Some(Value::Number(a)), Some(Value::Number(b))) =
             (self.a.get_value(), self.b.get_value())
-            && let Ok(res) = *a / *b
         {
-            self.out.set(res.into());
+            match units::div(*a, *b) {
+                Ok(res) => {
+                    if !matches!(self.state(), BlockState::Running) {
+                        self.set_state(BlockState::Running);
+                    }
+                    self.out.set(res.into())
+                }
+                Err(err) => {
+                    self.set_state(BlockState::fault(format!("Div: {err}")));
+                }
+            }
         }
     }
 }
@@ -0,0 +1,132 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Expression block.
+
+mod formula;
+
+use crate::base::{
+    block::{Block, BlockProps, BlockState},
+    input::{InputProps, input_reader::InputReader},
+    output::Output,
+};
+
+use libhaystack::val::Value;
+
+use crate::{blocks::InputImpl, blocks::OutputImpl};
+
+use formula::{EvalError, Formula};
+
+/// Evaluates the `formula` over its 16 inputs, `in0` to `in15`, for
+/// example `(in0 - in1) * 0.5 + max(in2, 10)`.
+///
+/// Formulas combine arithmetic (`+ - * / % ^`), comparisons, boolean
+/// logic (`&&`/`and`, `||`/`or`, `!`/`not`), `if(cond, then, else)` and
+/// math functions, over unit-aware numbers: `in0 > 20°C` converts an
+/// input in `°F` before comparing. The output is a number or a boolean.
+/// A formula that does not parse, including one nesting parentheses,
+/// calls or unary operators more than 64 deep, or values it cannot
+/// combine, fault the block; it waits while an input the formula needs
+/// has no value.
+#[block]
+#[derive(BlockProps, Debug)]
+#[dis = "Expression"]
+#[category = "math"]
+#[input(kind = "Number", count = 16)]
+pub struct Expr {
+    #[input(kind = "Str")]
+    pub formula: InputImpl,
+    #[output(kind = "Null")]
+    pub out: OutputImpl,
+    compiled: Option<(String, Result<Formula, String>)>,
+}
+
+impl Block for Expr {
+    async fn execute(&mut self) {
+        self.read_inputs_until_ready().await;
+
+        let Some(Value::Str(text)) = self.formula.get_value() else {
+            return;
+        };
+        if self
+            .compiled
+            .as_ref()
+            .is_none_or(|(compiled, _)| *compiled != text.value)
+        {
+            let formula = Formula::parse(&text.value, self._inputs.len());
+            self.compiled = Some((text.value.clone(), formula));
+        }
+
+        let result = match &self.compiled {
+            Some((_, Ok(formula))) => {
+                let inputs: Vec<_> = self._inputs.iter().map(|input| input.get_value()).collect();
+                formula.eval(&inputs)
+            }
+            Some((_, Err(err))) => Err(EvalError::Invalid(format!("invalid formula, {err}"))),
+            None => return,
+        };
+
+        match result {
+            Ok(val) => {
+                if !matches!(self.state(), BlockState::Running) {
+                    self.set_state(BlockState::Running);
+                }
+                self.out.set(val.into());
+            }
+            Err(EvalError::Invalid(err)) => {
+                self.set_state(BlockState::fault(format!("Expr: {err}")));
+            }
+            Err(EvalError::Missing(_)) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use libhaystack::val::Value;
+
+    use crate::{
+        base::block::test_utils::write_block_inputs,
+        base::block::{Block, BlockProps, BlockState},
+        base::input::input_reader::InputReader,
+        blocks::math::Expr,
+    };
+
+    async fn write_numbers(block: &mut Expr, values: &[(&str, f64)]) {
+        for (name, value) in values {
+            write_block_inputs([(block.get_input_mut(name).unwrap(), *value)]).await;
+            block.read_inputs().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_expr_block() {
+        let mut block = Expr::new();
+
+        write_numbers(&mut block, &[("in0", 7.0), ("in1", 3.0), ("in2", 4.0)]).await;
+        write_block_inputs([(&mut block.formula, "(in0 - in1) * 0.5 + max(in2, 10)")]).await;
+        block.execute().await;
+        assert_eq!(block.out.value, Value::from(12));
+
+        write_block_inputs([(&mut block.formula, "in0 > in1 && in2 != 4")]).await;
+        block.execute().await;
+        assert_eq!(block.out.value, Value::from(false));
+    }
+
+    #[tokio::test]
+    async fn test_expr_block_faults_on_invalid_formula() {
+        let mut block = Expr::new();
+
+        write_numbers(&mut block, &[("in0", 1.0)]).await;
+        write_block_inputs([(&mut block.formula, "in0 +* 2")]).await;
+        block.execute().await;
+        assert_eq!(
+            block.state().fault_reason(),
+            Some("Expr: invalid formula, unexpected '*' at 5")
+        );
+
+        write_block_inputs([(&mut block.formula, "in0 * 2")]).await;
+        block.execute().await;
+        assert_eq!(block.state(), BlockState::Running);
+        assert_eq!(block.out.value, Value::from(2));
+    }
+}
@@ -0,0 +1,670 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! The formula language of the [`Expr`](super::Expr) block.
+//!
+//! Operators, from the lowest precedence to the highest:
+//!
+//! | Operators            | Operands                     |
+//! |----------------------|------------------------------|
+//! | `\|\|`, `or`         | booleans                     |
+//! | `&&`, `and`          | booleans                     |
+//! | `==`, `!=`           | numbers or booleans          |
+//! | `<`, `<=`, `>`, `>=` | numbers                      |
+//! | `+`, `-`             | numbers                      |
+//! | `*`, `/`, `%`        | numbers                      |
+//! | unary `-`, `!`, `not`| a number, a boolean          |
+//! | `^`                  | numbers, right associative   |
+//!
+//! Operands are number literals, optionally with a unit written right
+//! after them (`10kW`, `72°F`, `50%`), `true`, `false`, `pi`, the inputs
+//! `in0` to `in15`, parenthesized formulas and function calls:
+//! `abs`, `floor`, `ceil`, `round`, `sqrt`, `exp`, `ln`, `log10`, `sin`,
+//! `cos`, `tan`, `asin`, `acos`, `atan`, `pow(x, y)`, `min(..)`,
+//! `max(..)`, `avg(..)`, `clamp(x, lo, hi)` and `if(cond, then, else)`,
+//! which only evaluates the branch it takes.
+//!
+//! Numbers are converted to a common unit before they are added,
+//! subtracted or compared, so `in0 > 20°C` works for an input in `°F`,
+//! and products and quotients get the unit derived from their operands'
+//! units, such as `kWh` for `10kW * 2h`.
+//! `abs`, `floor`, `ceil`, `round`, `min`, `max`, `avg` and `clamp` keep
+//! the unit; the other functions and `^` return plain numbers.
+
+use std::fmt::{self, Display};
+
+use libhaystack::units::get_unit;
+use libhaystack::val::{Number, Value};
+
+use crate::blocks::units;
+use crate::blocks::utils::convert_units;
+
+/// Operators by precedence level, lowest first.
+const BINARY_OPS: [&[&str]; 6] = [
+    &["||"],
+    &["&&"],
+    &["==", "!="],
+    &["<", "<=", ">", ">="],
+    &["+", "-"],
+    &["*", "/", "%"],
+];
+
+/// How deep parentheses, calls and unary operators may nest, so deeply
+/// nested formulas fail to parse rather than overflow the stack.
+const MAX_NESTING: usize = 64;
+
+/// Known functions with their minimum and maximum argument count.
+const FUNCTIONS: &[(&str, usize, usize)] = &[
+    ("abs", 1, 1),
+    ("floor", 1, 1),
+    ("ceil", 1, 1),
+    ("round", 1, 1),
+    ("sqrt", 1, 1),
+    ("exp", 1, 1),
+    ("ln", 1, 1),
+    ("log10", 1, 1),
+    ("sin", 1, 1),
+    ("cos", 1, 1),
+    ("tan", 1, 1),
+    ("asin", 1, 1),
+    ("acos", 1, 1),
+    ("atan", 1, 1),
+    ("pow", 2, 2),
+    ("min", 1, usize::MAX),
+    ("max", 1, usize::MAX),
+    ("avg", 1, usize::MAX),
+    ("clamp", 3, 3),
+    ("if", 3, 3),
+];
+
+/// A parsed formula.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Formula(Node);
+
+/// The result of a formula.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum Val {
+    Num(Number),
+    Bool(bool),
+}
+
+/// Why a formula could not be evaluated.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum EvalError {
+    /// The input with this index has no number or boolean value yet.
+    Missing(usize),
+    /// The values don't fit the formula, e.g. incompatible units.
+    Invalid(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Val(Val),
+    Input(usize),
+    Neg(Box<Node>),
+    Not(Box<Node>),
+    Binary(&'static str, Box<Node>, Box<Node>),
+    Call(&'static str, Vec<Node>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(Number),
+    Ident(String),
+    Sym(&'static str),
+}
+
+impl Formula {
+    /// Parses `text`, which may refer to the inputs `in0` to
+    /// `in{inputs - 1}`.
+    pub(super) fn parse(text: &str, inputs: usize) -> Result<Self, String> {
+        let mut parser = Parser {
+            tokens: tokenize(text)?,
+            pos: 0,
+            inputs,
+            depth: 0,
+        };
+        let node = parser.binary(0)?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(Self(node)),
+            Some((at, token)) => Err(format!("unexpected {token} at {at}")),
+        }
+    }
+
+    /// Evaluates the formula over the values of the inputs.
+    pub(super) fn eval(&self, inputs: &[Option<&Value>]) -> Result<Val, EvalError> {
+        eval(&self.0, inputs)
+    }
+}
+
+impl From<Val> for Value {
+    fn from(val: Val) -> Self {
+        match val {
+            Val::Num(num) => num.into(),
+            Val::Bool(val) => val.into(),
+        }
+    }
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Num(num) => write!(f, "number {}", num.value),
+            Token::Ident(ident) => write!(f, "'{ident}'"),
+            Token::Sym(sym) => write!(f, "'{sym}'"),
+        }
+    }
+}
+
+/// Splits `text` into tokens along with their character offsets.
+fn tokenize(text: &str) -> Result<Vec<(usize, Token)>, String> {
+    const SYMBOLS: [&str; 18] = [
+        "||", "&&", "==", "!=", "<=", ">=", "<", ">", "+", "-", "*", "/", "%", "^", "!", "(", ")",
+        ",",
+    ];
+
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+
+    while pos < chars.len() {
+        let start = pos;
+        let c = chars[pos];
+
+        if c.is_whitespace() {
+            pos += 1;
+        } else if c.is_ascii_digit()
+            || (c == '.' && chars.get(pos + 1).is_some_and(char::is_ascii_digit))
+        {
+            let (num, end) = number_literal(&chars, pos)?;
+            tokens.push((start, Token::Num(num)));
+            pos = end;
+        } else if c.is_alphabetic() || c == '_' {
+            while pos < chars.len() && (chars[pos].is_alphanumeric() || chars[pos] == '_') {
+                pos += 1;
+            }
+            let ident: String = chars[start..pos].iter().collect();
+            let token = match ident.as_str() {
+                "and" => Token::Sym("&&"),
+                "or" => Token::Sym("||"),
+                "not" => Token::Sym("!"),
+                _ => Token::Ident(ident),
+            };
+            tokens.push((start, token));
+        } else if let Some(sym) = SYMBOLS.iter().find(|sym| {
+            sym.chars()
+                .enumerate()
+                .all(|(i, s)| chars.get(pos + i) == Some(&s))
+        }) {
+            tokens.push((start, Token::Sym(sym)));
+            pos += sym.len();
+        } else {
+            return Err(format!("unexpected '{c}' at {pos}"));
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Reads a number literal starting at `pos`, with its unit if one is
+/// written right after it. Returns the number and where it ends.
+fn number_literal(chars: &[char], mut pos: usize) -> Result<(Number, usize), String> {
+    let start = pos;
+    let digits = |pos: &mut usize| {
+        while chars.get(*pos).is_some_and(char::is_ascii_digit) {
+            *pos += 1;
+        }
+    };
+
+    digits(&mut pos);
+    if chars.get(pos) == Some(&'.') {
+        pos += 1;
+        digits(&mut pos);
+    }
+    if matches!(chars.get(pos), Some('e' | 'E')) {
+        let sign = usize::from(matches!(chars.get(pos + 1), Some('+' | '-')));
+        if chars.get(pos + 1 + sign).is_some_and(char::is_ascii_digit) {
+            pos += 1 + sign;
+            digits(&mut pos);
+        }
+    }
+
+    let literal: String = chars[start..pos].iter().collect();
+    let value: f64 = literal
+        .parse()
+        .map_err(|_| format!("invalid number '{literal}' at {start}"))?;
+
+    let unit_start = pos;
+    let is_unit_char = |c: char| c.is_alphanumeric() || matches!(c, '_' | '$' | '°' | '%');
+    match chars.get(pos) {
+        // A lone `%` is the percent unit, unless it is followed by an
+        // operand and so is the modulus operator.
+        Some('%')
+            if chars
+                .get(pos + 1)
+                .is_none_or(|c| !(c.is_alphanumeric() || matches!(c, '.' | '(' | '_'))) =>
+        {
+            pos += 1;
+        }
+        Some(&c) if c.is_alphabetic() || matches!(c, '_' | '$' | '°') => {
+            while chars.get(pos).is_some_and(|&c| is_unit_char(c)) {
+                pos += 1;
+            }
+            // Units such as `m/s` continue after a slash, though
+            // `10kW/in0` divides by an input.
+            if chars.get(pos) == Some(&'/') && chars.get(pos + 1).is_some_and(|c| c.is_alphabetic())
+            {
+                let mut end = pos + 1;
+                while chars.get(end).is_some_and(|&c| is_unit_char(c)) {
+                    end += 1;
+                }
+                let unit: String = chars[unit_start..end].iter().collect();
+                if get_unit(&unit).is_some() {
+                    pos = end;
+                }
+            }
+        }
+        _ => {}
+    }
+
+    if pos == unit_start {
+        return Ok((Number::make(value), pos));
+    }
+    let unit: String = chars[unit_start..pos].iter().collect();
+    match get_unit(&unit) {
+        Some(unit) => Ok((Number::make_with_unit(value, unit), pos)),
+        None => Err(format!("unknown unit '{unit}' at {unit_start}")),
+    }
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    inputs: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn binary(&mut self, level: usize) -> Result<Node, String> {
+        let Some(ops) = BINARY_OPS.get(level) else {
+            return self.unary();
+        };
+
+        let mut lhs = self.binary(level + 1)?;
+        while let Some(op) = ops.iter().find_map(|op| self.eat(op)) {
+            let rhs = self.binary(level + 1)?;
+            lhs = Node::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Node, String> {
+        if self.depth > MAX_NESTING {
+            let at = self.tokens.get(self.pos).map_or(0, |(at, _)| *at);
+            return Err(format!("formula nests deeper than {MAX_NESTING} at {at}"));
+        }
+
+        self.depth += 1;
+        let node = self.operand();
+        self.depth -= 1;
+        node
+    }
+
+    fn operand(&mut self) -> Result<Node, String> {
+        if self.eat("-").is_some() {
+            Ok(Node::Neg(Box::new(self.unary()?)))
+        } else if self.eat("!").is_some() {
+            Ok(Node::Not(Box::new(self.unary()?)))
+        } else {
+            let base = self.primary()?;
+            if let Some(op) = self.eat("^") {
+                Ok(Node::Binary(op, Box::new(base), Box::new(self.unary()?)))
+            } else {
+                Ok(base)
+            }
+        }
+    }
+
+    fn primary(&mut self) -> Result<Node, String> {
+        let Some((at, token)) = self.tokens.get(self.pos).cloned() else {
+            return Err("unexpected end of formula".into());
+        };
+        self.pos += 1;
+
+        match token {
+            Token::Num(num) => Ok(Node::Val(Val::Num(num))),
+            Token::Sym("(") => {
+                let node = self.binary(0)?;
+                self.expect(")")?;
+                Ok(node)
+            }
+            Token::Ident(ident) if self.eat("(").is_some() => self.call(&ident, at),
+            Token::Ident(ident) => match ident.as_str() {
+                "true" => Ok(Node::Val(Val::Bool(true))),
+                "false" => Ok(Node::Val(Val::Bool(false))),
+                "pi" => Ok(Node::Val(Val::Num(Number::make(std::f64::consts::PI)))),
+                _ => match ident
+                    .strip_prefix("in")
+                    .and_then(|i| i.parse::<usize>().ok())
+                {
+                    Some(index) if index < self.inputs => Ok(Node::Input(index)),
+                    _ => Err(format!("unknown name '{ident}' at {at}")),
+                },
+            },
+            token => Err(format!("unexpected {token} at {at}")),
+        }
+    }
+
+    /// Parses the arguments of a call to `name`, after its `(`.
+    fn call(&mut self, name: &str, at: usize) -> Result<Node, String> {
+        let Some(&(name, min, max)) = FUNCTIONS.iter().find(|(func, ..)| *func == name) else {
+            return Err(format!("unknown function '{name}' at {at}"));
+        };
+
+        let mut args = Vec::new();
+        if self.eat(")").is_none() {
+            loop {
+                args.push(self.binary(0)?);
+                if self.eat(",").is_none() {
+                    break;
+                }
+            }
+            self.expect(")")?;
+        }
+
+        if args.len() < min || args.len() > max {
+            Err(format!(
+                "'{name}' at {at} takes {} arguments, not {}",
+                if min == max {
+                    min.to_string()
+                } else {
+                    format!("at least {min}")
+                },
+                args.len()
+            ))
+        } else {
+            Ok(Node::Call(name, args))
+        }
+    }
+
+    fn eat(&mut self, sym: &'static str) -> Option<&'static str> {
+        match self.tokens.get(self.pos) {
+            Some((_, Token::Sym(next))) if *next == sym => {
+                self.pos += 1;
+                Some(sym)
+            }
+            _ => None,
+        }
+    }
+
+    fn expect(&mut self, sym: &'static str) -> Result<(), String> {
+        if self.eat(sym).is_some() {
+            Ok(())
+        } else {
+            match self.tokens.get(self.pos) {
+                Some((at, token)) => Err(format!("expected '{sym}' at {at}, found {token}")),
+                None => Err(format!("expected '{sym}' at the end of the formula")),
+            }
+        }
+    }
+}
+
+fn eval(node: &Node, inputs: &[Option<&Value>]) -> Result<Val, EvalError> {
+    match node {
+        Node::Val(val) => Ok(*val),
+        Node::Input(index) => match inputs.get(*index).copied().flatten() {
+            Some(Value::Number(num)) => Ok(Val::Num(*num)),
+            Some(Value::Bool(val)) => Ok(Val::Bool(val.value)),
+            _ => Err(EvalError::Missing(*index)),
+        },
+        Node::Neg(node) => {
+            let num = number(eval(node, inputs)?, "-")?;
+            Ok(Val::Num(Number {
+                value: -num.value,
+                unit: num.unit,
+            }))
+        }
+        Node::Not(node) => Ok(Val::Bool(!boolean(eval(node, inputs)?, "!")?)),
+        Node::Binary(op @ ("&&" | "||"), lhs, rhs) => {
+            let lhs = boolean(eval(lhs, inputs)?, op)?;
+            if lhs == (*op == "||") {
+                Ok(Val::Bool(lhs))
+            } else {
+                Ok(Val::Bool(boolean(eval(rhs, inputs)?, op)?))
+            }
+        }
+        Node::Binary(op, lhs, rhs) => binary(op, eval(lhs, inputs)?, eval(rhs, inputs)?),
+        Node::Call("if", args) => {
+            if boolean(eval(&args[0], inputs)?, "if")? {
+                eval(&args[1], inputs)
+            } else {
+                eval(&args[2], inputs)
+            }
+        }
+        Node::Call(name, args) => {
+            let args = args
+                .iter()
+                .map(|arg| eval(arg, inputs).and_then(|val| number(val, name)))
+                .collect::<Result<Vec<_>, _>>()?;
+            call(name, &args)
+        }
+    }
+}
+
+fn binary(op: &str, lhs: Val, rhs: Val) -> Result<Val, EvalError> {
+    if let (Val::Bool(lhs), Val::Bool(rhs)) = (lhs, rhs) {
+        return match op {
+            "==" => Ok(Val::Bool(lhs == rhs)),
+            "!=" => Ok(Val::Bool(lhs != rhs)),
+            _ => Err(invalid(format!("'{op}' expects numbers"))),
+        };
+    }
+
+    let (lhs, rhs) = (number(lhs, op)?, number(rhs, op)?);
+    let num = match op {
+        "+" => units::add(lhs, rhs).map_err(|err| invalid(err.to_string()))?,
+        "-" => units::sub(lhs, rhs).map_err(|err| invalid(err.to_string()))?,
+        "*" => units::mul(lhs, rhs).map_err(|err| invalid(err.to_string()))?,
+        "/" => units::div(lhs, rhs).map_err(|err| invalid(err.to_string()))?,
+        "^" => Number::make(lhs.value.powf(rhs.value)),
+        _ => {
+            let [lhs, rhs] = common_unit(&[lhs, rhs], op)?[..] else {
+                unreachable!("two numbers in, two out")
+            };
+            let value = match op {
+                "%" => lhs.value % rhs.value,
+                _ => {
+                    return Ok(Val::Bool(match op {
+                        "==" => lhs.value == rhs.value,
+                        "!=" => lhs.value != rhs.value,
+                        "<" => lhs.value < rhs.value,
+                        "<=" => lhs.value <= rhs.value,
+                        ">" => lhs.value > rhs.value,
+                        _ => lhs.value >= rhs.value,
+                    }));
+                }
+            };
+            Number {
+                value,
+                unit: lhs.unit,
+            }
+        }
+    };
+    Ok(Val::Num(num))
+}
+
+fn call(name: &str, args: &[Number]) -> Result<Val, EvalError> {
+    let with_unit = |num: &Number, value: f64| Number {
+        value,
+        unit: num.unit,
+    };
+    let plain = |f: fn(f64) -> f64| Number::make(f(args[0].value));
+
+    let num = match name {
+        "abs" => with_unit(&args[0], args[0].value.abs()),
+        "floor" => with_unit(&args[0], args[0].value.floor()),
+        "ceil" => with_unit(&args[0], args[0].value.ceil()),
+        "round" => with_unit(&args[0], args[0].value.round()),
+        "sqrt" => plain(f64::sqrt),
+        "exp" => plain(f64::exp),
+        "ln" => plain(f64::ln),
+        "log10" => plain(f64::log10),
+        "sin" => plain(f64::sin),
+        "cos" => plain(f64::cos),
+        "tan" => plain(f64::tan),
+        "asin" => plain(f64::asin),
+        "acos" => plain(f64::acos),
+        "atan" => plain(f64::atan),
+        "pow" => Number::make(args[0].value.powf(args[1].value)),
+        _ => {
+            let args = common_unit(args, name)?;
+            let values = args.iter().map(|num| num.value);
+            let value = match name {
+                "min" => values.fold(f64::INFINITY, f64::min),
+                "max" => values.fold(f64::NEG_INFINITY, f64::max),
+                "avg" => values.sum::<f64>() / args.len() as f64,
+                _ => args[0].value.max(args[1].value).min(args[2].value),
+            };
+            with_unit(&args[0], value)
+        }
+    };
+    Ok(Val::Num(num))
+}
+
+fn number(val: Val, op: &str) -> Result<Number, EvalError> {
+    match val {
+        Val::Num(num) => Ok(num),
+        Val::Bool(_) => Err(invalid(format!("'{op}' expects numbers"))),
+    }
+}
+
+fn boolean(val: Val, op: &str) -> Result<bool, EvalError> {
+    match val {
+        Val::Bool(val) => Ok(val),
+        Val::Num(_) => Err(invalid(format!("'{op}' expects booleans"))),
+    }
+}
+
+fn common_unit(numbers: &[Number], op: &str) -> Result<Vec<Number>, EvalError> {
+    convert_units(numbers).map_err(|_| invalid(format!("'{op}' got incompatible units")))
+}
+
+fn invalid(msg: impl Into<String>) -> EvalError {
+    EvalError::Invalid(msg.into())
+}
+
+#[cfg(test)]
+mod test {
+    use libhaystack::units::get_unit;
+    use libhaystack::val::{Number, Value};
+
+    use super::{EvalError, Formula, Val};
+
+    fn eval(text: &str, inputs: &[Value]) -> Result<Val, EvalError> {
+        let inputs: Vec<_> = inputs.iter().map(Some).collect();
+        Formula::parse(text, 16).unwrap().eval(&inputs)
+    }
+
+    fn num(value: f64) -> Val {
+        Val::Num(Number::make(value))
+    }
+
+    #[test]
+    fn test_precedence_and_functions() {
+        let inputs = [Value::from(7), Value::from(3), Value::from(4)];
+
+        assert_eq!(
+            eval("(in0 - in1) * 0.5 + max(in2, 10)", &inputs),
+            Ok(num(12.0))
+        );
+        assert_eq!(eval("-2^2 + 2 * 3 % 4", &inputs), Ok(num(-2.0)));
+        assert_eq!(eval("2 ^ 3 ^ 2", &inputs), Ok(num(512.0)));
+        assert_eq!(
+            eval("if(in0 > in1 and !false, avg(in1, in2, 2), 0)", &inputs),
+            Ok(num(3.0))
+        );
+        assert_eq!(
+            eval("in0 >= 7 == (in1 != 3)", &inputs),
+            Ok(Val::Bool(false))
+        );
+        assert_eq!(
+            eval("clamp(in0, 0, 5) + round(1.4e1)", &inputs),
+            Ok(num(19.0))
+        );
+        // The branch not taken may refer to inputs without values.
+        assert_eq!(
+            eval("if(true, 1, in9) + (true or in9)", &inputs),
+            Err(EvalError::Invalid("'+' expects numbers".into()))
+        );
+        assert_eq!(eval("false and in9", &inputs), Ok(Val::Bool(false)));
+        assert_eq!(eval("in9 + 1", &inputs), Err(EvalError::Missing(9)));
+    }
+
+    #[test]
+    fn test_units() {
+        let fahrenheit = get_unit("°F").unwrap();
+        let inputs = [Value::from(Number::make_with_unit(77.0, fahrenheit))];
+
+        assert_eq!(eval("in0 > 20°C", &inputs), Ok(Val::Bool(true)));
+        assert_eq!(eval("in0 < 30°C", &inputs), Ok(Val::Bool(true)));
+        assert_eq!(
+            eval("max(in0, 0) - 5", &inputs),
+            Ok(Val::Num(Number::make_with_unit(72.0, fahrenheit)))
+        );
+        assert_eq!(
+            eval("10kW / 2", &inputs),
+            Ok(Val::Num(Number::make_with_unit(
+                5.0,
+                get_unit("kW").unwrap()
+            )))
+        );
+        assert_eq!(
+            eval("50% + 10 % 4", &inputs),
+            Ok(Val::Num(Number::make_with_unit(
+                52.0,
+                get_unit("%").unwrap()
+            )))
+        );
+        assert_eq!(
+            eval("10kW * 2h", &inputs),
+            Ok(Val::Num(Number::make_with_unit(
+                20.0,
+                get_unit("kWh").unwrap()
+            )))
+        );
+        assert!(matches!(
+            eval("in0 + 1kW", &inputs),
+            Err(EvalError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |text| Formula::parse(text, 2).unwrap_err();
+
+        assert_eq!(error("in0 +"), "unexpected end of formula");
+        assert_eq!(error("(in0 + 1"), "expected ')' at the end of the formula");
+        assert_eq!(error("in2 * 2"), "unknown name 'in2' at 0");
+        assert_eq!(error("foo(1)"), "unknown function 'foo' at 0");
+        assert_eq!(
+            error("clamp(1, 2)"),
+            "'clamp' at 0 takes 3 arguments, not 2"
+        );
+        assert_eq!(error("1 2"), "unexpected number 2 at 2");
+        assert_eq!(error("1 # 2"), "unexpected '#' at 2");
+        assert_eq!(error("3parsecs"), "unknown unit 'parsecs' at 1");
+    }
+
+    #[test]
+    fn test_nesting_is_limited() {
+        let nested = |depth: usize| format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+
+        assert!(Formula::parse(&nested(64), 0).is_ok());
+        assert_eq!(
+            Formula::parse(&nested(65), 0).unwrap_err(),
+            "formula nests deeper than 64 at 65"
+        );
+        assert!(Formula::parse(&nested(100_000), 0).is_err());
+        assert!(Formula::parse(&"-".repeat(100_000), 0).is_err());
+        assert!(Formula::parse(&format!("{}1", "abs(".repeat(100_000)), 0).is_err());
+    }
+}
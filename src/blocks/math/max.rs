@@ -13,17 +13,23 @@ use crate::{
 
 use libhaystack::val::{Number, Value};
 
-use crate::{blocks::InputImpl, blocks::OutputImpl};
+use crate::blocks::{
+    InputImpl, OutputImpl,
+    lists::{eval_numbers, numeric_operands},
+};
 
 /// Outputs the Maximum value of the inputs.
+///
+/// If an input is a list, the maximum is taken element-wise, giving a
+/// list. A list on its own gives its maximum element.
 #[block]
 #[derive(BlockProps, Debug)]
 #[dis = "Maximum"]
 #[category = "math"]
 pub struct Max {
-    #[input(kind = "Number")]
+    #[input(kind = "Number", lists = true)]
     pub a: InputImpl,
-    #[input(kind = "Number")]
+    #[input(kind = "Number", lists = true)]
     pub b: InputImpl,
     #[output(kind = "Number")]
     pub out: OutputImpl,
@@ -33,22 +39,33 @@ impl Block for Max {
     async fn execute(&mut self) {
         self.read_inputs_until_ready().await;
 
-        if let (Some(Value::Number(a)), Some(Value::Number(b))) =
-            (self.a.get_value(), self.b.get_value())
-        {
-            convert_units(&[a.to_owned(), b.to_owned()])
-                .map(|parts| {
-                    if let [a, b] = &parts[..] {
-                        let val = Number {
-                            value: a.value.max(b.value),
-                            unit: a.unit,
-                        };
-                        self.out.set(val.into());
-                    }
-                })
-                .unwrap_or_else(|_| {
-                    self.set_state(BlockState::fault("Max: unit conversion failed"));
-                });
+        let operands = numeric_operands([self.a.get_value(), self.b.get_value()].into_iter());
+        // Both numbers are needed, unless `a` or `b` is a list.
+        if matches!(&operands[..], [] | [Value::Number(_)]) {
+            return;
+        }
+
+        let res = eval_numbers(&operands.iter().collect::<Vec<_>>(), |numbers| {
+            let numbers = convert_units(numbers)?;
+            let value = numbers
+                .iter()
+                .map(|n| n.value)
+                .fold(numbers[0].value, f64::max);
+            Ok(Number {
+                value,
+                unit: numbers[0].unit,
+            })
+        });
+        match res {
+            Ok(res) => {
+                if !matches!(self.state(), BlockState::Running) {
+                    self.set_state(BlockState::Running);
+                }
+                self.out.set(res)
+            }
+            Err(err) => {
+                self.set_state(BlockState::fault(format!("Max: {err}")));
+            }
         }
     }
 }
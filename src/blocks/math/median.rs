@@ -11,19 +11,25 @@ use crate::{
     blocks::utils::convert_units,
 };
 
-use libhaystack::val::{Number, Value};
+use libhaystack::val::Number;
 
-use crate::blocks::OutputImpl;
+use crate::blocks::{
+    OutputImpl,
+    lists::{eval_numbers, numeric_operands},
+};
 
 /// Calculates the median of multiple numbers from the 16 inputs
 /// this block has.
 /// The operation would take into account the units of those input's values,
 /// if the units are not convertible, the block would be in an error state.
+///
+/// A list input is evaluated element-wise with the other inputs, giving a
+/// list. A list on its own gives the median of its elements.
 #[block]
 #[derive(BlockProps, Debug)]
 #[dis = "Median"]
 #[category = "math"]
-#[input(kind = "Number", count = 16)]
+#[input(kind = "Number", count = 16, lists = true)]
 pub struct Median {
     #[output(kind = "Number")]
     pub out: OutputImpl,
@@ -33,43 +39,36 @@ impl Block for Median {
     async fn execute(&mut self) {
         self.read_inputs_until_ready().await;
 
-        let val = self
-            .inputs()
-            .into_iter()
-            .filter_map(|input| match input.get_value().as_ref() {
-                Some(Value::Number(num)) => Some(*num),
-                _ => None,
-            })
-            .collect::<Vec<Number>>();
-
-        if let Ok(mut numbers) = convert_units(&val) {
-            if !matches!(self.state(), BlockState::Running) {
-                self.set_state(BlockState::Running);
-            }
+        let operands = numeric_operands(self.inputs().into_iter().map(|input| input.get_value()));
+        if operands.is_empty() {
+            return;
+        }
 
+        let res = eval_numbers(&operands.iter().collect::<Vec<_>>(), |numbers| {
+            let mut numbers = convert_units(numbers)?;
             numbers.sort();
 
+            let mid = numbers.len() / 2;
             let median = if numbers.len() % 2 == 0 {
-                let mid = numbers.len() / 2;
                 (numbers[mid - 1].value + numbers[mid].value) / 2.0
             } else {
-                let mid = numbers.len() / 2;
                 numbers[mid].value
             };
-
-            let median = if let Some(Number {
-                value: _,
-                unit: Some(unit),
-            }) = numbers.first()
-            {
-                Number::make_with_unit(median, unit)
-            } else {
-                Number::make(median)
-            };
-
-            self.out.set(median.into());
-        } else {
-            self.set_state(BlockState::fault("Median: unit conversion failed"));
+            Ok(Number {
+                value: median,
+                unit: numbers[0].unit,
+            })
+        });
+        match res {
+            Ok(res) => {
+                if !matches!(self.state(), BlockState::Running) {
+                    self.set_state(BlockState::Running);
+                }
+                self.out.set(res)
+            }
+            Err(err) => {
+                self.set_state(BlockState::fault(format!("Median: {err}")));
+            }
         }
     }
 }
@@ -3,23 +3,32 @@
 //! Multiplication block.
 
 use crate::base::{
-    block::{Block, BlockProps},
+    block::{Block, BlockProps, BlockState},
+    error::ValueError,
     input::input_reader::InputReader,
     output::Output,
 };
 
-use libhaystack::val::{Number, Value};
+use libhaystack::val::Value;
 
-use crate::blocks::OutputImpl;
+use crate::blocks::{
+    OutputImpl,
+    lists::{eval_numbers, numeric_operands},
+    units,
+};
 
 /// Performs a multiplication of multiple numbers from the 16 inputs
 /// this block has.
-/// The operation would take into account the units of those input's values,
-/// if the units are not convertible, the block would be in an error state.
+/// The unit of the product is derived from the units of the inputs, such
+/// as `kWh` for `kW` times `h`. If no unit matches the product's
+/// dimensions, the block would be in an error state.
+///
+/// A list input is multiplied element-wise with the other inputs, and the
+/// output is a list. A list on its own is multiplied out.
 #[block]
 #[derive(BlockProps, Debug)]
 #[category = "math"]
-#[input(kind = "Number", count = 16)]
+#[input(kind = "Number", count = 16, lists = true)]
 pub struct Mul {
     #[output(kind = "Number")]
     pub out: OutputImpl,
@@ -29,51 +38,39 @@ impl Block for Mul {
     async fn execute(&mut self) {
         self.read_inputs_until_ready().await;
 
-        let mut val: Option<Number> = None;
-        let mut cnt = 0;
-        for el in self
-            .inputs()
-            .into_iter()
-            .filter_map(|input| match input.get_value().as_ref() {
-                Some(Value::Number(num)) => Some(*num),
-                _ => None,
-            })
-        {
-            cnt += 1;
-
-            if let Some(v) = val {
-                let res = v * el;
-
-                if res.is_err() {
-                    val = None;
-                    break;
-                }
+        let operands = numeric_operands(self.inputs().into_iter().map(|input| input.get_value()));
+        // A product takes two numbers, or a list.
+        if matches!(&operands[..], [] | [Value::Number(_)]) {
+            return;
+        }
 
-                match res {
-                    Ok(res) => {
-                        val.replace(res);
-                    }
-                    Err(_) => {
-                        val = None;
-                        break;
-                    }
+        let res = eval_numbers(&operands.iter().collect::<Vec<_>>(), |numbers| {
+            let [first, rest @ ..] = numbers else {
+                return Err(ValueError::EmptyList);
+            };
+            rest.iter().copied().try_fold(*first, units::mul)
+        });
+        match res {
+            Ok(res) => {
+                if !matches!(self.state(), BlockState::Running) {
+                    self.set_state(BlockState::Running);
                 }
-            } else {
-                val = Some(el);
+                self.out.set(res)
+            }
+            Err(err) => {
+                self.set_state(BlockState::fault(format!("Mul: {err}")));
             }
-        }
-
-        if cnt > 1
-            && let Some(res) = val
-        {
-            self.out.set(res.into())
         }
     }
 }
 
 #[cfg(test)]
 mod test {
+    use libhaystack::units::get_unit;
+    use libhaystack::val::Number;
+
     use crate::{
+        base::block::test_utils::write_block_inputs,
         base::block::{Block, BlockProps},
         base::input::input_reader::InputReader,
         blocks::math::Mul,
@@ -103,4 +100,19 @@ mod test {
         block.execute().await;
         assert_eq!(block.out.value, 9.into());
     }
+
+    #[tokio::test]
+    async fn test_mul_block_units() {
+        let mut block = Mul::new();
+        let num = |value, unit| Number::make_with_unit(value, get_unit(unit).unwrap());
+
+        write_block_inputs([(block.get_input_mut("in0").unwrap(), num(2.0, "kW"))]).await;
+        write_block_inputs([(block.get_input_mut("in1").unwrap(), num(3.0, "h"))]).await;
+        block.execute().await;
+        assert_eq!(block.out.value, num(6.0, "kWh").into());
+
+        write_block_inputs([(block.get_input_mut("in1").unwrap(), num(20.0, "°C"))]).await;
+        block.execute().await;
+        assert!(block.state().is_fault());
+    }
 }
@@ -3,16 +3,18 @@
 //! Power block.
 
 use crate::base::{
-    block::Block,
+    block::{Block, BlockProps, BlockState},
     input::{InputProps, input_reader::InputReader},
     output::Output,
 };
 
-use libhaystack::val::{Number, Value};
+use libhaystack::val::Value;
 
-use crate::{blocks::InputImpl, blocks::OutputImpl};
+use crate::blocks::{InputImpl, OutputImpl, units};
 
 /// Outputs the power root value of the input.
+/// The unit of the base is raised too, so `m` squared is `m²`; the exponent
+/// must not have a unit.
 #[block]
 #[derive(BlockProps, Debug)]
 #[dis = "Power"]
@@ -33,13 +35,17 @@ impl Block for Pow {
         if let (Some(Value::Number(base)), Some(Value::Number(exponent))) =
             (self.base.get_value(), self.exponent.get_value())
         {
-            self.out.set(
-                Number {
-                    value: base.value.powf(exponent.value),
-                    unit: base.unit,
+            match units::pow(*base, *exponent) {
+                Ok(res) => {
+                    if !matches!(self.state(), BlockState::Running) {
+                        self.set_state(BlockState::Running);
+                    }
+                    self.out.set(res.into())
                 }
-                .into(),
-            );
+                Err(err) => {
+                    self.set_state(BlockState::fault(format!("Pow: {err}")));
+                }
+            }
         }
     }
 }
@@ -10,11 +10,13 @@ use crate::base::{
 
 use libhaystack::val::Value;
 
-use crate::{blocks::InputImpl, blocks::OutputImpl};
+use crate::blocks::{InputImpl, OutputImpl, units};
 
 /// Performs an subtraction of 2 numbers.
 /// The operation would take into account the units of those input's values,
-/// if the units are not convertible, the block would be in an error state.
+/// converting `b` to the unit of `a`; the difference of two temperatures is
+/// a temperature difference (`Δ°C`). If the units are not convertible, the
+/// block would be in an error state.
 #[block]
 #[derive(BlockProps, Debug)]
 #[category = "math"]
@@ -32,13 +34,17 @@ impl Block for Sub {
         self.read_inputs_until_ready().await;
 
         if let (Some(Value::Number(a)), Some(Value::Number(b))) =
-            (&self.a.get_value(), &self.b.get_value())
+            (self.a.get_value(), self.b.get_value())
         {
-            match *a - *b {
-                Ok(res) => self.out.set(res.into()),
-                Err(e) => {
-                    log::error!("Error while subtracting: {}", e);
-                    self.set_state(BlockState::fault(format!("Sub: {}", e)));
+            match units::sub(*a, *b) {
+                Ok(res) => {
+                    if !matches!(self.state(), BlockState::Running) {
+                        self.set_state(BlockState::Running);
+                    }
+                    self.out.set(res.into())
+                }
+                Err(err) => {
+                    self.set_state(BlockState::fault(format!("Sub: {err}")));
                 }
             }
         }
@@ -48,8 +54,12 @@ impl Block for Sub {
 #[cfg(test)]
 mod test {
 
+    use libhaystack::val::Value;
+
     use crate::{
-        base::block::Block, base::block::test_utils::write_block_inputs, blocks::math::Sub,
+        base::block::test_utils::write_block_inputs,
+        base::block::{Block, BlockProps, BlockState},
+        blocks::math::Sub,
     };
 
     #[tokio::test]
@@ -61,4 +71,18 @@ mod test {
         block.execute().await;
         assert_eq!(block.out.value, 7.into());
     }
+
+    #[tokio::test]
+    async fn test_sub_block_faults_on_lists() {
+        let mut block = Sub::new();
+
+        write_block_inputs([
+            (&mut block.a, Value::make_list(vec![10.into()])),
+            (&mut block.b, 3.into()),
+        ])
+        .await;
+
+        block.execute().await;
+        assert!(matches!(block.state(), BlockState::Fault { .. }));
+    }
 }
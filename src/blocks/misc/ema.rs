@@ -5,10 +5,14 @@
 use std::time::Duration;
 
 use crate::base::output::props::OutputProps;
-use crate::base::{block::Block, input::input_reader::InputReader, output::Output};
+use crate::base::{
+    block::{Block, BlockProps, BlockState},
+    input::input_reader::InputReader,
+    output::Output,
+};
+use crate::blocks::lists::{from_samples, samples};
 use crate::blocks::utils::{input_as_number, input_to_millis_or_default};
 
-use libhaystack::units::Unit;
 use libhaystack::val::Number;
 
 use crate::{blocks::InputImpl, blocks::OutputImpl};
@@ -19,11 +23,14 @@ use crate::{blocks::InputImpl, blocks::OutputImpl};
 /// closely; a smaller alpha smooths more heavily. The block samples at
 /// `interval` milliseconds; on the first sample the output is seeded
 /// with the current input.
+///
+/// A list input is filtered element-wise and gives a list; the filter
+/// is seeded again whenever the list changes its length.
 #[block]
 #[derive(BlockProps, Debug)]
 #[category = "misc"]
 pub struct Ema {
-    #[input(name = "in", kind = "Number")]
+    #[input(name = "in", kind = "Number", lists = true)]
     pub input: InputImpl,
     #[input(kind = "Number")]
     pub alpha: InputImpl,
@@ -31,9 +38,7 @@ pub struct Ema {
     pub interval: InputImpl,
     #[output(kind = "Number")]
     pub out: OutputImpl,
-    last: f64,
-    initialized: bool,
-    unit: Option<&'static Unit>,
+    last: Vec<f64>,
 }
 
 impl Block for Ema {
@@ -45,29 +50,41 @@ impl Block for Ema {
             return;
         }
 
-        let in_n = match input_as_number(&self.input) {
-            Some(n) => n,
-            None => return,
+        let (samples, is_list) = match samples(self.input.val.as_ref()) {
+            Ok(Some(samples)) => samples,
+            Ok(None) => return,
+            Err(err) => {
+                self.set_state(BlockState::fault(format!("Ema: {err}")));
+                return;
+            }
         };
-        let input = in_n.value;
-        self.unit = in_n.unit;
+        if !matches!(self.state(), BlockState::Running) {
+            self.set_state(BlockState::Running);
+        }
         let alpha = input_as_number(&self.alpha)
             .map(|n| n.value)
             .unwrap_or(0.1)
             .clamp(0.0, 1.0);
 
-        let next = if !self.initialized {
-            self.initialized = true;
-            input
+        // Seed on the first sample, or when the list changes its length.
+        if self.last.len() != samples.len() {
+            self.last = samples.iter().map(|n| n.value).collect();
         } else {
-            alpha * input + (1.0 - alpha) * self.last
-        };
+            for (last, sample) in self.last.iter_mut().zip(&samples) {
+                *last = alpha * sample.value + (1.0 - alpha) * *last;
+            }
+        }
 
-        self.last = next;
-        self.out.set(match self.unit {
-            Some(u) => Number::make_with_unit(next, u).into(),
-            None => next.into(),
-        });
+        let values = self
+            .last
+            .iter()
+            .zip(&samples)
+            .map(|(&next, sample)| match sample.unit {
+                Some(u) => Number::make_with_unit(next, u).into(),
+                None => next.into(),
+            })
+            .collect();
+        self.out.set(from_samples(values, is_list));
     }
 }
 
@@ -160,4 +177,25 @@ mod test {
         block.execute().await;
         assert_eq!(block.out.value, 42.into());
     }
+
+    #[tokio::test]
+    async fn test_ema_filters_lists() {
+        let mut block = Ema::new();
+        block
+            .out
+            .links
+            .push(BaseLink::new(uuid::Uuid::new_v4(), "test".to_string()));
+        let list = |values: &[f64]| Value::make_list(values.iter().map(|&v| v.into()).collect());
+
+        for values in [[0.0, 10.0], [100.0, 10.0]] {
+            write_block_inputs([
+                (&mut block.input, list(&values)),
+                (&mut block.alpha, 0.5.into()),
+                (&mut block.interval, 0.0.into()),
+            ])
+            .await;
+            block.execute().await;
+        }
+        assert_eq!(block.out.value, list(&[50.0, 10.0]));
+    }
 }
@@ -6,7 +6,12 @@ use std::collections::VecDeque;
 use std::time::Duration;
 
 use crate::base::output::props::OutputProps;
-use crate::base::{block::Block, input::input_reader::InputReader, output::Output};
+use crate::base::{
+    block::{Block, BlockProps, BlockState},
+    input::input_reader::InputReader,
+    output::Output,
+};
+use crate::blocks::lists::{from_samples, samples};
 use crate::blocks::utils::{input_as_number, input_to_millis_or_default};
 
 use libhaystack::val::Number;
@@ -16,11 +21,14 @@ use crate::{blocks::InputImpl, blocks::OutputImpl};
 /// Simple moving average over the last N samples. The block samples at
 /// `interval` milliseconds and reports the unweighted mean of the most
 /// recent `window` samples (clamped to at least 1).
+///
+/// A list input is averaged element-wise and gives a list; the window
+/// starts over whenever the list changes its length.
 #[block]
 #[derive(BlockProps, Debug)]
 #[category = "misc"]
 pub struct MovingAverage {
-    #[input(name = "in", kind = "Number")]
+    #[input(name = "in", kind = "Number", lists = true)]
     pub input: InputImpl,
     #[input(kind = "Number")]
     pub window: InputImpl,
@@ -28,7 +36,7 @@ pub struct MovingAverage {
     pub interval: InputImpl,
     #[output(kind = "Number")]
     pub out: OutputImpl,
-    samples: VecDeque<f64>,
+    samples: VecDeque<Vec<f64>>,
 }
 
 impl Block for MovingAverage {
@@ -40,28 +48,50 @@ impl Block for MovingAverage {
             return;
         }
 
-        let in_n = match input_as_number(&self.input) {
-            Some(n) => n,
-            None => return,
+        let (samples, is_list) = match samples(self.input.val.as_ref()) {
+            Ok(Some(samples)) => samples,
+            Ok(None) => return,
+            Err(err) => {
+                self.set_state(BlockState::fault(format!("MovingAverage: {err}")));
+                return;
+            }
         };
-        let v = in_n.value;
+        if !matches!(self.state(), BlockState::Running) {
+            self.set_state(BlockState::Running);
+        }
 
         let window = input_as_number(&self.window)
             .map(|n| n.value as usize)
             .unwrap_or(10)
             .max(1);
 
-        self.samples.push_back(v);
+        // Start over when the list changes its length.
+        if self
+            .samples
+            .front()
+            .is_some_and(|front| front.len() != samples.len())
+        {
+            self.samples.clear();
+        }
+        self.samples
+            .push_back(samples.iter().map(|n| n.value).collect());
         while self.samples.len() > window {
             self.samples.pop_front();
         }
 
-        let sum: f64 = self.samples.iter().sum();
-        let mean = sum / self.samples.len() as f64;
-        self.out.set(match in_n.unit {
-            Some(u) => Number::make_with_unit(mean, u).into(),
-            None => mean.into(),
-        });
+        let values = samples
+            .iter()
+            .enumerate()
+            .map(|(index, sample)| {
+                let sum: f64 = self.samples.iter().map(|row| row[index]).sum();
+                let mean = sum / self.samples.len() as f64;
+                match sample.unit {
+                    Some(u) => Number::make_with_unit(mean, u).into(),
+                    None => mean.into(),
+                }
+            })
+            .collect();
+        self.out.set(from_samples(values, is_list));
     }
 }
 
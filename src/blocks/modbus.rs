@@ -0,0 +1,21 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Modbus TCP blocks, enabled by the `modbus` feature.
+//!
+//! See [`crate::modbus`] for the shared connections and register
+//! layouts.
+
+pub(crate) mod device;
+pub mod read_bit;
+pub mod read_register;
+pub mod write_coil;
+pub mod write_register;
+
+pub use read_bit::ModbusReadBit;
+pub use read_register::ModbusReadRegister;
+pub use write_coil::ModbusWriteCoil;
+pub use write_register::ModbusWriteRegister;
+
+/// Polling period of the read blocks, and retry period of the write
+/// blocks, unless set.
+const DEFAULT_INTERVAL_MILLIS: u64 = 1000;
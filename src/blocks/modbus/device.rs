@@ -0,0 +1,205 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Device settings and shared connections of the Modbus blocks.
+
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock};
+
+use libhaystack::val::Value;
+use tokio::sync::Mutex;
+
+use crate::blocks::InputImpl;
+use crate::blocks::utils::input_as_number;
+use crate::modbus::{DataFormat, ModbusClient, ModbusError, Table, WordOrder};
+
+/// Default Modbus TCP port.
+const DEFAULT_PORT: f64 = 502.0;
+
+type Connection = Arc<Mutex<Option<ModbusClient>>>;
+
+/// One connection per `host:port`, shared by every block talking to
+/// that device; devices often accept only a handful of connections.
+static CONNECTIONS: LazyLock<std::sync::Mutex<HashMap<String, Connection>>> =
+    LazyLock::new(Default::default);
+
+/// Where a block reads or writes.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Device {
+    pub(super) addr: String,
+    pub(super) unit: u8,
+    pub(super) address: u16,
+}
+
+impl Device {
+    /// Reads the device inputs. [`None`] while `host` is not set, an
+    /// error if another input is out of range.
+    pub(super) fn from_inputs(
+        host: &InputImpl,
+        port: &InputImpl,
+        unit: &InputImpl,
+        address: &InputImpl,
+    ) -> Result<Option<Self>, String> {
+        let Some(Value::Str(host)) = &host.val else {
+            return Ok(None);
+        };
+        if host.value.is_empty() {
+            return Ok(None);
+        }
+
+        let port: u16 = integer(port, DEFAULT_PORT, "port")?;
+        Ok(Some(Self {
+            addr: format!("{}:{port}", host.value),
+            unit: integer(unit, 1.0, "unitId")?,
+            address: integer(address, 0.0, "address")?,
+        }))
+    }
+
+    pub(super) async fn read_registers(
+        &self,
+        table: Table,
+        count: u16,
+    ) -> Result<Vec<u16>, ModbusError> {
+        self.call(async |client: &mut ModbusClient| {
+            client
+                .read_registers(self.unit, table, self.address, count)
+                .await
+        })
+        .await
+    }
+
+    pub(super) async fn read_bit(&self, table: Table) -> Result<bool, ModbusError> {
+        self.call(async |client: &mut ModbusClient| {
+            client.read_bits(self.unit, table, self.address, 1).await
+        })
+        .await
+        .map(|bits| bits[0])
+    }
+
+    pub(super) async fn write_registers(&self, values: &[u16]) -> Result<(), ModbusError> {
+        self.call(async |client: &mut ModbusClient| {
+            client
+                .write_registers(self.unit, self.address, values)
+                .await
+        })
+        .await
+    }
+
+    pub(super) async fn write_coil(&self, value: bool) -> Result<(), ModbusError> {
+        self.call(async |client: &mut ModbusClient| {
+            client.write_coil(self.unit, self.address, value).await
+        })
+        .await
+    }
+
+    /// Runs `request` on the device's connection, connecting first if
+    /// needed and dropping the connection if the request broke it.
+    ///
+    /// The client is taken out of the pool while in use: the engine
+    /// cancels `execute` when a command reaches the block, and a request
+    /// cut short mid-frame must not leave its half on the shared stream.
+    async fn call<T>(
+        &self,
+        request: impl AsyncFnOnce(&mut ModbusClient) -> Result<T, ModbusError>,
+    ) -> Result<T, ModbusError> {
+        let connection = CONNECTIONS
+            .lock()
+            .expect("Modbus connections are locked")
+            .entry(self.addr.clone())
+            .or_default()
+            .clone();
+
+        let mut pooled = connection.lock().await;
+        let mut client = match pooled.take() {
+            Some(client) => client,
+            None => ModbusClient::connect(&self.addr).await?,
+        };
+        let result = request(&mut client).await;
+        if !result.as_ref().is_err_and(ModbusError::is_connection_lost) {
+            *pooled = Some(client);
+        }
+        result
+    }
+}
+
+/// Reads a register table input, `holding` unless set.
+pub(super) fn register_table(input: &InputImpl) -> Result<Table, String> {
+    match table(input, Table::Holding)? {
+        table if table.is_bits() => Err(format!("'{table}' is not a register table")),
+        table => Ok(table),
+    }
+}
+
+/// Reads a bit table input, `coil` unless set.
+pub(super) fn bit_table(input: &InputImpl) -> Result<Table, String> {
+    match table(input, Table::Coil)? {
+        table if !table.is_bits() => Err(format!("'{table}' is not a bit table")),
+        table => Ok(table),
+    }
+}
+
+fn table(input: &InputImpl, default: Table) -> Result<Table, String> {
+    match &input.val {
+        Some(Value::Str(name)) => {
+            Table::parse(&name.value).ok_or_else(|| format!("unknown table '{}'", name.value))
+        }
+        _ => Ok(default),
+    }
+}
+
+/// The register layout inputs, with their scaling.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Layout {
+    pub(super) format: DataFormat,
+    pub(super) order: WordOrder,
+    pub(super) scale: f64,
+    pub(super) offset: f64,
+}
+
+impl Layout {
+    pub(super) fn from_inputs(
+        format: &InputImpl,
+        order: &InputImpl,
+        scale: &InputImpl,
+        offset: &InputImpl,
+    ) -> Result<Self, String> {
+        let format = match &format.val {
+            Some(Value::Str(name)) => DataFormat::parse(&name.value)
+                .ok_or_else(|| format!("unknown format '{}'", name.value))?,
+            _ => DataFormat::default(),
+        };
+        let order = match &order.val {
+            Some(Value::Str(name)) => WordOrder::parse(&name.value)
+                .ok_or_else(|| format!("unknown word order '{}'", name.value))?,
+            _ => WordOrder::default(),
+        };
+        let scale = input_as_number(scale).map_or(1.0, |scale| scale.value);
+        if scale == 0.0 {
+            return Err("scale must not be 0".into());
+        }
+        Ok(Self {
+            format,
+            order,
+            scale,
+            offset: input_as_number(offset).map_or(0.0, |offset| offset.value),
+        })
+    }
+
+    /// The engineering value held in `registers`.
+    pub(super) fn decode(&self, registers: &[u16]) -> f64 {
+        self.format.decode(registers, self.order) * self.scale + self.offset
+    }
+
+    /// The registers holding the engineering `value`.
+    pub(super) fn encode(&self, value: f64) -> Vec<u16> {
+        self.format
+            .encode((value - self.offset) / self.scale, self.order)
+    }
+}
+
+fn integer<T: TryFrom<i64>>(input: &InputImpl, default: f64, name: &str) -> Result<T, String> {
+    let value = input_as_number(input).map_or(default, |number| number.value);
+    if value.fract() != 0.0 {
+        return Err(format!("{name} {value} is not an integer"));
+    }
+    T::try_from(value as i64).map_err(|_| format!("{name} {value} is out of range"))
+}
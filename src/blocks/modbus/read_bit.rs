@@ -0,0 +1,78 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Modbus bit read block.
+
+use std::time::Duration;
+
+use libhaystack::val::Value;
+
+use crate::base::{
+    block::{Block, BlockProps, BlockState},
+    input::input_reader::InputReader,
+    output::Output,
+};
+use crate::blocks::modbus::DEFAULT_INTERVAL_MILLIS;
+use crate::blocks::modbus::device::{Device, bit_table};
+use crate::blocks::utils::input_to_millis_or_default;
+
+use crate::blocks::{InputImpl, OutputImpl};
+
+/// Polls a coil or discrete input from a Modbus TCP device.
+///
+/// Reads the bit at `address` of `table` (`coil` or `discrete`, default
+/// `coil`) every `interval` (default 1 s). `port` defaults to 502 and
+/// `unitId` to 1. The block faults while the device cannot be reached
+/// or answers with an exception.
+#[block]
+#[derive(BlockProps, Debug)]
+#[category = "modbus"]
+pub struct ModbusReadBit {
+    #[input(kind = "Str")]
+    pub host: InputImpl,
+    #[input(kind = "Number")]
+    pub port: InputImpl,
+    #[input(name = "unitId", kind = "Number")]
+    pub unit_id: InputImpl,
+    #[input(kind = "Str")]
+    pub table: InputImpl,
+    #[input(kind = "Number")]
+    pub address: InputImpl,
+    #[input(kind = "Number")]
+    pub interval: InputImpl,
+    #[output(kind = "Bool")]
+    pub out: OutputImpl,
+}
+
+impl Block for ModbusReadBit {
+    async fn execute(&mut self) {
+        let millis = match self.interval.val {
+            Some(_) => input_to_millis_or_default(&self.interval.val),
+            None => DEFAULT_INTERVAL_MILLIS,
+        };
+        self.wait_on_inputs(Duration::from_millis(millis)).await;
+
+        let config = Device::from_inputs(&self.host, &self.port, &self.unit_id, &self.address)
+            .and_then(|device| {
+                let table = bit_table(&self.table)?;
+                Ok(device.map(|device| (device, table)))
+            });
+        let (device, table) = match config {
+            Ok(Some(config)) => config,
+            Ok(None) => return,
+            Err(err) => {
+                self.set_state(BlockState::fault(format!("ModbusReadBit: {err}")));
+                return;
+            }
+        };
+
+        match device.read_bit(table).await {
+            Ok(bit) => {
+                self.out.set(Value::make_bool(bit));
+                self.set_state(BlockState::Running);
+            }
+            Err(err) => {
+                self.set_state(BlockState::fault(format!("ModbusReadBit: {err}")));
+            }
+        }
+    }
+}
@@ -0,0 +1,77 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Modbus coil write block.
+
+use std::time::Duration;
+
+use libhaystack::val::Value;
+
+use crate::base::{
+    block::{Block, BlockProps, BlockState},
+    input::input_reader::InputReader,
+    output::Output,
+};
+use crate::blocks::modbus::DEFAULT_INTERVAL_MILLIS;
+use crate::blocks::modbus::device::Device;
+
+use crate::blocks::{InputImpl, OutputImpl};
+
+/// Writes `in` to a coil of a Modbus TCP device.
+///
+/// Writes whenever `in` or the device settings change. `port` defaults
+/// to 502 and `unitId` to 1. The block faults while a write fails, and
+/// retries every second until it succeeds.
+/// `out` follows `in` once the device has accepted it.
+#[block]
+#[derive(BlockProps, Debug)]
+#[category = "modbus"]
+pub struct ModbusWriteCoil {
+    #[input(kind = "Str")]
+    pub host: InputImpl,
+    #[input(kind = "Number")]
+    pub port: InputImpl,
+    #[input(name = "unitId", kind = "Number")]
+    pub unit_id: InputImpl,
+    #[input(kind = "Number")]
+    pub address: InputImpl,
+    #[input(name = "in", kind = "Bool")]
+    pub input: InputImpl,
+    #[output(kind = "Bool")]
+    pub out: OutputImpl,
+    written: Option<(Device, bool)>,
+}
+
+impl Block for ModbusWriteCoil {
+    async fn execute(&mut self) {
+        self.wait_on_inputs(Duration::from_millis(DEFAULT_INTERVAL_MILLIS))
+            .await;
+
+        let Some(Value::Bool(value)) = self.input.val else {
+            return;
+        };
+        let device = match Device::from_inputs(&self.host, &self.port, &self.unit_id, &self.address)
+        {
+            Ok(Some(device)) => device,
+            Ok(None) => return,
+            Err(err) => {
+                self.set_state(BlockState::fault(format!("ModbusWriteCoil: {err}")));
+                return;
+            }
+        };
+        let write = (device, value.value);
+        if self.written.as_ref() == Some(&write) {
+            return;
+        }
+
+        match write.0.write_coil(write.1).await {
+            Ok(()) => {
+                self.out.set(self.input.val.clone().unwrap_or_default());
+                self.written = Some(write);
+                self.set_state(BlockState::Running);
+            }
+            Err(err) => {
+                self.set_state(BlockState::fault(format!("ModbusWriteCoil: {err}")));
+            }
+        }
+    }
+}
@@ -0,0 +1,16 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! MQTT blocks, enabled by the `mqtt` feature.
+//!
+//! See [`crate::mqtt`] for the shared connections and payload
+//! encodings.
+
+pub(crate) mod connection;
+pub mod publish;
+pub mod subscribe;
+
+pub use publish::MqttPublish;
+pub use subscribe::MqttSubscribe;
+
+/// Retry period of the blocks while the broker cannot be reached.
+const DEFAULT_INTERVAL_MILLIS: u64 = 1000;
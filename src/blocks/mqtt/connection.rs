@@ -0,0 +1,93 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Broker settings and shared connections of the MQTT blocks.
+
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock};
+
+use libhaystack::val::Value;
+use tokio::sync::Mutex;
+
+use crate::blocks::InputImpl;
+use crate::blocks::utils::input_as_number;
+use crate::mqtt::{DEFAULT_PORT, Encoding, MqttClient, MqttError, MqttOptions, QoS};
+
+type Connection = Arc<Mutex<Option<MqttClient>>>;
+
+/// One connection per `host:port`, shared by every block talking to
+/// that broker.
+static CONNECTIONS: LazyLock<std::sync::Mutex<HashMap<String, Connection>>> =
+    LazyLock::new(Default::default);
+
+/// The broker and topic a block publishes or subscribes to.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Endpoint {
+    pub(super) host: String,
+    pub(super) port: u16,
+    pub(super) topic: String,
+    pub(super) encoding: Encoding,
+    pub(super) qos: QoS,
+}
+
+impl Endpoint {
+    /// Reads the endpoint inputs. [`None`] while `host` or `topic` is not
+    /// set, an error if another input is out of range.
+    pub(super) fn from_inputs(
+        host: &InputImpl,
+        port: &InputImpl,
+        topic: &InputImpl,
+        encoding: &InputImpl,
+        qos: &InputImpl,
+    ) -> Result<Option<Self>, String> {
+        let (Some(Value::Str(host)), Some(Value::Str(topic))) = (&host.val, &topic.val) else {
+            return Ok(None);
+        };
+        if host.value.is_empty() || topic.value.is_empty() {
+            return Ok(None);
+        }
+
+        let port = input_as_number(port).map_or(f64::from(DEFAULT_PORT), |port| port.value);
+        if port.fract() != 0.0 || !(1.0..=65535.0).contains(&port) {
+            return Err(format!("port {port} is out of range"));
+        }
+        let encoding = match &encoding.val {
+            Some(Value::Str(name)) => Encoding::parse(&name.value)
+                .ok_or_else(|| format!("unknown encoding '{}'", name.value))?,
+            _ => Encoding::default(),
+        };
+        let qos = match input_as_number(qos) {
+            Some(qos) if qos.value == 0.0 => QoS::AtMostOnce,
+            Some(qos) if qos.value == 1.0 => QoS::AtLeastOnce,
+            Some(qos) => return Err(format!("QoS {} is not supported", qos.value)),
+            None => QoS::default(),
+        };
+        Ok(Some(Self {
+            host: host.value.clone(),
+            port: port as u16,
+            topic: topic.value.clone(),
+            encoding,
+            qos,
+        }))
+    }
+
+    /// The broker's connection, connecting first if there is none or
+    /// the last one was lost.
+    pub(super) async fn client(&self) -> Result<MqttClient, MqttError> {
+        let connection = CONNECTIONS
+            .lock()
+            .expect("MQTT connections are locked")
+            .entry(format!("{}:{}", self.host, self.port))
+            .or_default()
+            .clone();
+
+        let mut pooled = connection.lock().await;
+        if let Some(client) = pooled.as_ref().filter(|client| client.is_connected()) {
+            return Ok(client.clone());
+        }
+        let mut options = MqttOptions::new(self.host.clone());
+        options.port = self.port;
+        let client = MqttClient::connect(options).await?;
+        *pooled = Some(client.clone());
+        Ok(client)
+    }
+}
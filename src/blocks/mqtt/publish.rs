@@ -0,0 +1,110 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! MQTT publish block.
+
+use std::time::Duration;
+
+use libhaystack::val::Value;
+
+use crate::base::{
+    block::{Block, BlockProps, BlockState},
+    input::input_reader::InputReader,
+    output::Output,
+};
+use crate::blocks::mqtt::DEFAULT_INTERVAL_MILLIS;
+use crate::blocks::mqtt::connection::Endpoint;
+use crate::mqtt::Message;
+
+use crate::blocks::{InputImpl, OutputImpl};
+
+/// Publishes `in` to an MQTT topic.
+///
+/// Publishes whenever `in` or the settings change, encoded per
+/// `encoding` (`json`, the default, or `zinc`) at `qos` 0 or 1 (default
+/// 0), retained when `retain` is true.
+///
+/// `port` defaults to 1883. The block faults while the broker cannot be
+/// reached, and retries every second until it succeeds.
+/// `out` follows `in` once published.
+#[block]
+#[derive(BlockProps, Debug)]
+#[category = "mqtt"]
+pub struct MqttPublish {
+    #[input(kind = "Str")]
+    pub host: InputImpl,
+    #[input(kind = "Number")]
+    pub port: InputImpl,
+    #[input(kind = "Str")]
+    pub topic: InputImpl,
+    #[input(kind = "Str")]
+    pub encoding: InputImpl,
+    #[input(kind = "Number")]
+    pub qos: InputImpl,
+    #[input(kind = "Bool")]
+    pub retain: InputImpl,
+    #[input(name = "in", kind = "Null")]
+    pub input: InputImpl,
+    #[output(kind = "Null")]
+    pub out: OutputImpl,
+    published: Option<(Endpoint, Message)>,
+}
+
+impl Block for MqttPublish {
+    async fn execute(&mut self) {
+        self.wait_on_inputs(Duration::from_millis(DEFAULT_INTERVAL_MILLIS))
+            .await;
+
+        let Some(value) = self.input.val.clone() else {
+            return;
+        };
+        let config = Endpoint::from_inputs(
+            &self.host,
+            &self.port,
+            &self.topic,
+            &self.encoding,
+            &self.qos,
+        )
+        .and_then(|endpoint| {
+            let Some(endpoint) = endpoint else {
+                return Ok(None);
+            };
+            let payload = endpoint
+                .encoding
+                .encode(&value)
+                .map_err(|err| err.to_string())?;
+            let message = Message {
+                topic: endpoint.topic.clone(),
+                payload,
+                qos: endpoint.qos,
+                retain: matches!(self.retain.val, Some(Value::Bool(retain)) if retain.value),
+            };
+            Ok(Some((endpoint, message)))
+        });
+        let publish = match config {
+            Ok(Some(publish)) => publish,
+            Ok(None) => return,
+            Err(err) => {
+                self.set_state(BlockState::fault(format!("MqttPublish: {err}")));
+                return;
+            }
+        };
+        if self.published.as_ref() == Some(&publish) {
+            return;
+        }
+
+        let result = match publish.0.client().await {
+            Ok(client) => client.publish(publish.1.clone()).await,
+            Err(err) => Err(err),
+        };
+        match result {
+            Ok(()) => {
+                self.out.set(value);
+                self.published = Some(publish);
+                self.set_state(BlockState::Running);
+            }
+            Err(err) => {
+                self.set_state(BlockState::fault(format!("MqttPublish: {err}")));
+            }
+        }
+    }
+}
@@ -0,0 +1,141 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! MQTT subscribe block.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::{Mutex, mpsc};
+
+use crate::base::{
+    block::{Block, BlockProps, BlockState},
+    input::input_reader::InputReader,
+    output::Output,
+};
+use crate::blocks::mqtt::DEFAULT_INTERVAL_MILLIS;
+use crate::blocks::mqtt::connection::Endpoint;
+use crate::mqtt::Message;
+
+use crate::blocks::{InputImpl, OutputImpl};
+
+/// Outputs the values published on an MQTT topic.
+///
+/// Subscribes to `topic`, which may hold `+` and `#` wildcards, at `qos`
+/// 0 or 1 (default 0), and outputs each message's payload decoded per
+/// `encoding` (`json`, the default, or `zinc`). A retained message, if
+/// the broker has one, is output at once.
+///
+/// `port` defaults to 1883. The block faults while the broker cannot be
+/// reached or a payload cannot be decoded, and resubscribes every second
+/// until the broker is back.
+#[block]
+#[derive(BlockProps, Debug)]
+#[category = "mqtt"]
+pub struct MqttSubscribe {
+    #[input(kind = "Str")]
+    pub host: InputImpl,
+    #[input(kind = "Number")]
+    pub port: InputImpl,
+    #[input(kind = "Str")]
+    pub topic: InputImpl,
+    #[input(kind = "Str")]
+    pub encoding: InputImpl,
+    #[input(kind = "Number")]
+    pub qos: InputImpl,
+    #[output(kind = "Null")]
+    pub out: OutputImpl,
+    subscription: Option<Subscription>,
+}
+
+/// A subscription and the messages received on it. The receiver is
+/// shared so a cancelled `execute` does not take it along.
+#[derive(Debug)]
+struct Subscription {
+    endpoint: Endpoint,
+    messages: Arc<Mutex<mpsc::UnboundedReceiver<Message>>>,
+}
+
+impl Block for MqttSubscribe {
+    async fn execute(&mut self) {
+        let wait = Duration::from_millis(DEFAULT_INTERVAL_MILLIS);
+        let endpoint = match Endpoint::from_inputs(
+            &self.host,
+            &self.port,
+            &self.topic,
+            &self.encoding,
+            &self.qos,
+        ) {
+            Ok(Some(endpoint)) => endpoint,
+            Ok(None) => {
+                self.subscription = None;
+                self.wait_on_inputs(wait).await;
+                return;
+            }
+            Err(err) => {
+                self.set_state(BlockState::fault(format!("MqttSubscribe: {err}")));
+                self.wait_on_inputs(wait).await;
+                return;
+            }
+        };
+
+        if self
+            .subscription
+            .as_ref()
+            .is_none_or(|subscription| subscription.endpoint != endpoint)
+        {
+            let result = match endpoint.client().await {
+                Ok(client) => client.subscribe(&endpoint.topic, endpoint.qos).await,
+                Err(err) => Err(err),
+            };
+            match result {
+                Ok(messages) => {
+                    self.subscription = Some(Subscription {
+                        endpoint: endpoint.clone(),
+                        messages: Arc::new(Mutex::new(messages)),
+                    });
+                    self.set_state(BlockState::Running);
+                }
+                Err(err) => {
+                    self.subscription = None;
+                    self.set_state(BlockState::fault(format!("MqttSubscribe: {err}")));
+                    self.wait_on_inputs(wait).await;
+                    return;
+                }
+            }
+        }
+
+        let Some(messages) = self
+            .subscription
+            .as_ref()
+            .map(|subscription| subscription.messages.clone())
+        else {
+            return;
+        };
+        let message = tokio::select! {
+            // Settings are read again after an input or a while.
+            _ = self.wait_on_inputs(wait) => return,
+            message = async move { messages.lock().await.recv().await } => message,
+        };
+
+        match message {
+            Some(message) => match endpoint.encoding.decode(&message.payload) {
+                Ok(value) => {
+                    self.out.set(value);
+                    self.set_state(BlockState::Running);
+                }
+                Err(err) => {
+                    self.set_state(BlockState::fault(format!(
+                        "MqttSubscribe: '{}': {err}",
+                        message.topic
+                    )));
+                }
+            },
+            None => {
+                self.subscription = None;
+                self.set_state(BlockState::fault(
+                    "MqttSubscribe: MQTT connection lost".to_string(),
+                ));
+            }
+        }
+    }
+}
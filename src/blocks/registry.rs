@@ -15,6 +15,7 @@ use crate::base::error::{RegistryError, Result};
 use std::collections::HashMap;
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::sync::LazyLock;
 use std::sync::Mutex;
 use uuid::Uuid;
@@ -27,15 +28,28 @@ pub const CORE_LIB: &str = "core";
 pub(crate) type DynBlockProps = dyn BlockProps<Reader = ReaderImpl, Writer = WriterImpl>;
 type MapType = HashMap<String, HashMap<String, BlockEntry>>;
 type BlockRegistry = Mutex<MapType>;
+/// Creates an instance of a runtime-registered block, with the given id
+/// or a new one. Shared, as function blocks capture their function.
+pub(crate) type MakeErased = Arc<dyn Fn(Option<Uuid>) -> RegisteredBlock + Send + Sync>;
 
 /// A block registration entry in the registry.
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct BlockEntry {
     /// Block descriptor (name, library, pins, etc.).
     pub desc: BlockDesc,
     /// Factory function that creates a new instance of this block.
     pub make: Option<fn() -> Box<DynBlockProps>>,
-    pub(crate) make_erased: Option<fn(Option<Uuid>) -> RegisteredBlock>,
+    pub(crate) make_erased: Option<MakeErased>,
+}
+
+impl std::fmt::Debug for BlockEntry {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt.debug_struct("BlockEntry")
+            .field("desc", &self.desc)
+            .field("make", &self.make)
+            .field("make_erased", &self.make_erased.is_some())
+            .finish()
+    }
 }
 
 /// Object-safe view over [`Block`] so runtime-registered blocks can be
@@ -76,6 +90,24 @@ where
 /// evaluator treat downstream-crate blocks uniformly with built-ins.
 pub(crate) struct RegisteredBlock(Box<dyn ErasedBlock>);
 
+impl RegisteredBlock {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn new<B>(block: B) -> Self
+    where
+        B: Block<Reader = ReaderImpl, Writer = WriterImpl> + Send + Sync + 'static,
+    {
+        Self(Box::new(block))
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub(crate) fn new<B>(block: B) -> Self
+    where
+        B: Block<Reader = ReaderImpl, Writer = WriterImpl> + 'static,
+    {
+        Self(Box::new(block))
+    }
+}
+
 impl std::fmt::Debug for RegisteredBlock {
     fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         fmt.debug_tuple("RegisteredBlock")
@@ -415,6 +447,34 @@ pub fn register_block_desc(desc: &BlockDesc) -> Result<(), RegistryError> {
     Ok(())
 }
 
+/// Registers `desc` with the constructor of its instances. Used for the
+/// blocks defined at runtime rather than by a type.
+pub(crate) fn register_erased(
+    desc: BlockDesc,
+    make_erased: MakeErased,
+) -> Result<(), RegistryError> {
+    let mut reg = BLOCKS.lock().expect("Block registry is locked");
+
+    let reg = reg.entry(desc.library.clone()).or_default();
+    if reg.contains_key(&desc.name) {
+        return Err(RegistryError::BlockAlreadyRegistered {
+            library: desc.library,
+            name: desc.name,
+        });
+    }
+
+    reg.insert(
+        desc.name.clone(),
+        BlockEntry {
+            desc,
+            make: None,
+            make_erased: Some(make_erased),
+        },
+    );
+
+    Ok(())
+}
+
 /// Bounds a block type must meet to be registered at runtime. All
 /// macro-generated blocks satisfy this. [`Send`] + [`Sync`] (native only) lets
 /// registered blocks be scheduled on the multi-threaded engine.
@@ -512,7 +572,7 @@ fn make_registered(
         if let Some(lib) = lib {
             reg.get(lib)
                 .and_then(|blocks| blocks.get(name))
-                .and_then(|entry| entry.make_erased)
+                .and_then(|entry| entry.make_erased.clone())
                 .ok_or_else(|| RegistryError::BlockNotFound {
                     library: lib.to_string(),
                     name: name.to_string(),
@@ -523,7 +583,7 @@ fn make_registered(
                 .filter_map(|(lib, blocks)| {
                     blocks
                         .get(name)
-                        .and_then(|entry| entry.make_erased)
+                        .and_then(|entry| entry.make_erased.clone())
                         .map(|make| (lib.as_str(), make))
                 })
                 .collect();
@@ -534,7 +594,7 @@ fn make_registered(
                         name: name.to_string(),
                     });
                 }
-                [(_, make)] => *make,
+                [(_, make)] => make.clone(),
                 _ => {
                     let mut libraries: Vec<_> =
                         matches.iter().map(|(lib, _)| lib.to_string()).collect();
@@ -653,13 +713,13 @@ fn register_impl<B: RegisterableBlock>(reg: &mut MapType) -> Result<(), Registry
                 Some(uuid) => B::with_uuid(uuid),
                 None => B::default(),
             };
-            RegisteredBlock(Box::new(block))
+            RegisteredBlock::new(block)
         };
 
         BlockEntry {
             desc: desc.clone(),
             make: Some(make),
-            make_erased: Some(make_erased),
+            make_erased: Some(Arc::new(make_erased)),
         }
     });
 
@@ -0,0 +1,76 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Process simulation blocks: plant models that close the loop around a
+//! controller, for tuning and testing control sequences offline.
+//!
+//! The dynamic models integrate over the time elapsed between cycles,
+//! with exact discretization where there is one, so they stay stable
+//! whatever the `interval`. Time parameters accept any time unit; plain
+//! numbers are seconds.
+
+use std::collections::VecDeque;
+
+use libhaystack::units::units_generated::SECOND;
+
+use super::InputImpl;
+use super::utils::input_as_number_in;
+use crate::tokio_impl::sleep::current_time_millis;
+
+pub mod actuator;
+pub mod fan_pump;
+pub mod fopdt;
+pub mod integrating_process;
+pub mod thermal_zone;
+
+pub use actuator::Actuator;
+pub use fan_pump::FanPump;
+pub use fopdt::Fopdt;
+pub use integrating_process::IntegratingProcess;
+pub use thermal_zone::ThermalZone;
+
+/// Reads a time input in seconds, `default` if it is missing. Negative
+/// times are taken as zero.
+fn input_as_secs(input: &InputImpl, default: f64) -> f64 {
+    input_as_number_in(input, &SECOND)
+        .filter(|secs| secs.is_finite())
+        .unwrap_or(default)
+        .max(0.0)
+}
+
+/// The clock of a model: the time of its last cycle.
+#[derive(Debug, Default)]
+struct ModelClock {
+    last_ms: u64,
+}
+
+impl ModelClock {
+    /// Moves the clock to now. Returns the current time in milliseconds,
+    /// and the seconds elapsed since the last cycle, [`None`] on the
+    /// first one.
+    fn tick(&mut self) -> (u64, Option<f64>) {
+        let now = current_time_millis();
+        let elapsed = (self.last_ms != 0).then(|| now.saturating_sub(self.last_ms) as f64 / 1000.0);
+        self.last_ms = now;
+        (now, elapsed)
+    }
+}
+
+/// The recent history of an input, for a transport delay (dead time).
+#[derive(Debug, Default)]
+struct DeadTime {
+    samples: VecDeque<(u64, f64)>,
+}
+
+impl DeadTime {
+    /// Records `value` at `now_ms`, and returns the value the input held
+    /// `delay_ms` before: the last one recorded by then, or the oldest
+    /// one while the history is shorter than the delay.
+    fn delay(&mut self, now_ms: u64, value: f64, delay_ms: u64) -> f64 {
+        self.samples.push_back((now_ms, value));
+        let cutoff = now_ms.saturating_sub(delay_ms);
+        while self.samples.len() > 1 && self.samples[1].0 <= cutoff {
+            self.samples.pop_front();
+        }
+        self.samples[0].1
+    }
+}
@@ -0,0 +1,135 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Valve or damper actuator block.
+
+use std::time::Duration;
+
+use crate::base::output::props::OutputProps;
+use crate::base::{block::Block, input::input_reader::InputReader, output::Output};
+use crate::blocks::utils::{input_as_number, input_to_millis_or_default};
+
+use super::{ModelClock, input_as_secs};
+use crate::{blocks::InputImpl, blocks::OutputImpl};
+
+/// Valve or damper driven by an actuator, with its travel time and the
+/// play (backlash) of its linkage.
+///
+/// `in` is the commanded position and `out` the actual one, both in %
+/// open (0–100):
+///
+/// - The position moves toward the command at `100 / stroke` % per
+///   second. `stroke` is the full-stroke time, in any time unit, plain
+///   numbers are seconds (default 90 s, 0 for an instant move).
+/// - `hysteresis` is the play of the linkage, in % (default 0). On a
+///   reversal, the command has to move through it before the position
+///   follows, so the position lags the command by half of it.
+/// - `interval` is the simulation step, in any time unit (default
+///   200 ms).
+///
+/// The actuator starts at its first command.
+#[block]
+#[derive(BlockProps, Debug)]
+#[category = "sim"]
+pub struct Actuator {
+    #[input(name = "in", kind = "Number")]
+    pub input: InputImpl,
+    #[input(kind = "Number")]
+    pub stroke: InputImpl,
+    #[input(kind = "Number")]
+    pub hysteresis: InputImpl,
+    #[input(kind = "Number")]
+    pub interval: InputImpl,
+    #[output(kind = "Number")]
+    pub out: OutputImpl,
+    position: f64,
+    target: f64,
+    clock: ModelClock,
+}
+
+impl Block for Actuator {
+    async fn execute(&mut self) {
+        let millis = input_to_millis_or_default(&self.interval.val);
+        self.wait_on_inputs(Duration::from_millis(millis)).await;
+
+        if !self.out.is_connected() {
+            return;
+        }
+
+        let Some(command) = input_as_number(&self.input).map(|n| n.value.clamp(0.0, 100.0)) else {
+            return;
+        };
+        let stroke = input_as_secs(&self.stroke, 90.0);
+        let play =
+            input_as_number(&self.hysteresis).map_or(0.0, |n| n.value.clamp(0.0, 100.0)) / 2.0;
+
+        let (_, elapsed) = self.clock.tick();
+        let Some(dt) = elapsed else {
+            self.target = command;
+            self.position = command;
+            self.out.set(self.position.into());
+            return;
+        };
+
+        // The linkage only pushes once the command takes up the play.
+        if command - play > self.target {
+            self.target = command - play;
+        } else if command + play < self.target {
+            self.target = command + play;
+        }
+
+        let travel = self.target - self.position;
+        self.position = if stroke == 0.0 {
+            self.target
+        } else {
+            let max_step = 100.0 * dt / stroke;
+            self.position + travel.clamp(-max_step, max_step)
+        };
+        self.out.set(self.position.into());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::blocks::sim::Actuator;
+    use crate::testing::TestBench;
+
+    #[test]
+    fn test_actuator_stroke_time() {
+        let mut bench = TestBench::new(Actuator::new());
+        bench.set("in", 0).set("stroke", 60).settle();
+        bench.assert_output("out", 0);
+
+        // 100 % in 60 s: 50 % in 30 s.
+        bench.set("in", 100).settle();
+        bench.advance(Duration::from_secs(30)).settle();
+        bench.assert_output("out", 50);
+        bench.advance(Duration::from_secs(40)).settle();
+        bench.assert_output("out", 100);
+    }
+
+    #[test]
+    fn test_actuator_hysteresis() {
+        let mut bench = TestBench::new(Actuator::new());
+        bench
+            .set("in", 50)
+            .set("stroke", 0)
+            .set("hysteresis", 4)
+            .settle();
+        bench.assert_output("out", 50);
+
+        // Within the play, nothing moves.
+        bench.set("in", 52).advance(Duration::from_secs(1)).settle();
+        bench.assert_output("out", 50);
+
+        // Past it, the position trails the command by half the play,
+        // in both directions.
+        bench.set("in", 60).advance(Duration::from_secs(1)).settle();
+        bench.assert_output("out", 58);
+        bench.set("in", 57).advance(Duration::from_secs(1)).settle();
+        bench.assert_output("out", 58);
+        bench.set("in", 40).advance(Duration::from_secs(1)).settle();
+        bench.assert_output("out", 42);
+    }
+}
@@ -0,0 +1,153 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Integrating process block.
+
+use std::time::Duration;
+
+use crate::base::output::props::OutputProps;
+use crate::base::{block::Block, input::input_reader::InputReader, output::Output};
+use crate::blocks::utils::{input_as_number, input_to_millis_or_default};
+
+use super::{DeadTime, ModelClock, input_as_secs};
+use crate::{blocks::InputImpl, blocks::OutputImpl};
+
+/// Integrating process, a plant with no self-regulation: a tank level
+/// fed by a pump against a draw, a pressure vessel, a conveyor buffer.
+///
+/// The output ramps at `gain · (in − load)` per second, `in` arriving
+/// after a `deadTime`: `dy/dt = gain · (in(t − deadTime) − load)`. It
+/// only stops moving when `in` balances `load`.
+///
+/// - `gain` defaults to 1, `load` to 0.
+/// - `deadTime` accepts any time unit, plain numbers are seconds; it
+///   defaults to 0.
+/// - The output starts at 0, or restarts at `initial` whenever it is
+///   written. It is kept within `min` and `max` when they are set, like
+///   a tank that runs dry or overflows.
+/// - `interval` is the simulation step, in any time unit (default
+///   200 ms).
+#[block]
+#[derive(BlockProps, Debug)]
+#[category = "sim"]
+pub struct IntegratingProcess {
+    #[input(name = "in", kind = "Number")]
+    pub input: InputImpl,
+    #[input(kind = "Number")]
+    pub gain: InputImpl,
+    #[input(kind = "Number")]
+    pub load: InputImpl,
+    #[input(name = "deadTime", kind = "Number")]
+    pub dead_time: InputImpl,
+    #[input(kind = "Number")]
+    pub initial: InputImpl,
+    #[input(kind = "Number")]
+    pub min: InputImpl,
+    #[input(kind = "Number")]
+    pub max: InputImpl,
+    #[input(kind = "Number")]
+    pub interval: InputImpl,
+    #[output(kind = "Number")]
+    pub out: OutputImpl,
+    level: f64,
+    last_initial: Option<f64>,
+    rate: f64,
+    clock: ModelClock,
+    history: DeadTime,
+}
+
+impl Block for IntegratingProcess {
+    async fn execute(&mut self) {
+        let millis = input_to_millis_or_default(&self.interval.val);
+        self.wait_on_inputs(Duration::from_millis(millis)).await;
+
+        if !self.out.is_connected() {
+            return;
+        }
+
+        let input = input_as_number(&self.input).map_or(0.0, |n| n.value);
+        let gain = input_as_number(&self.gain).map_or(1.0, |n| n.value);
+        let load = input_as_number(&self.load).map_or(0.0, |n| n.value);
+        let dead_time = input_as_secs(&self.dead_time, 0.0);
+
+        let initial = input_as_number(&self.initial).map(|n| n.value);
+        let restart = initial.filter(|_| initial != self.last_initial);
+        self.last_initial = initial;
+
+        let (now, elapsed) = self.clock.tick();
+        let delayed = self.history.delay(now, input, (dead_time * 1000.0) as u64);
+
+        // Over the elapsed interval the model ramped at the rate of the
+        // previous cycle, held.
+        let level = match (restart, elapsed) {
+            (Some(initial), _) => initial,
+            (None, None) => 0.0,
+            (None, Some(dt)) => self.level + self.rate * dt,
+        };
+        let min = input_as_number(&self.min).map_or(f64::NEG_INFINITY, |n| n.value);
+        let max = input_as_number(&self.max).map_or(f64::INFINITY, |n| n.value);
+        self.level = if min <= max {
+            level.clamp(min, max)
+        } else {
+            level
+        };
+        self.rate = gain * (delayed - load);
+        self.out.set(self.level.into());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::blocks::sim::IntegratingProcess;
+    use crate::testing::TestBench;
+
+    #[test]
+    fn test_integrating_process_ramps() {
+        let mut bench = TestBench::new(IntegratingProcess::new());
+        bench
+            .set("in", 3)
+            .set("load", 1)
+            .set("gain", 0.5)
+            .set("initial", 10)
+            .settle();
+        bench.assert_output("out", 10);
+
+        // (3 - 1) · 0.5 = 1 per second.
+        bench.advance(Duration::from_secs(4)).settle();
+        bench.assert_output("out", 14);
+
+        // Balanced: the level holds.
+        bench.set("in", 1).settle();
+        bench.advance(Duration::from_secs(4)).settle();
+        bench.assert_output("out", 14);
+    }
+
+    #[test]
+    fn test_integrating_process_limits_and_dead_time() {
+        let mut bench = TestBench::new(IntegratingProcess::new());
+        bench
+            .set("in", 0)
+            .set("load", 2)
+            .set("min", 0)
+            .set("max", 20)
+            .set("deadTime", 10)
+            .set("initial", 5)
+            .settle();
+
+        // Draining at 2 per second, until the tank runs dry.
+        bench.advance(Duration::from_secs(1)).settle();
+        bench.assert_output("out", 3);
+        bench.advance(Duration::from_secs(5)).settle();
+        bench.assert_output("out", 0);
+
+        // Filling at 10 − 2 per second, 10 s after the pump started.
+        bench.set("in", 10).settle();
+        bench.advance(Duration::from_secs(10)).settle();
+        bench.assert_output("out", 0);
+        bench.advance(Duration::from_secs(1)).settle();
+        bench.assert_output("out", 8);
+        bench.advance(Duration::from_secs(5)).settle();
+        bench.assert_output("out", 20);
+    }
+}
@@ -0,0 +1,175 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Thermal zone model block.
+
+use std::time::Duration;
+
+use crate::base::output::props::OutputProps;
+use crate::base::{block::Block, input::input_reader::InputReader, output::Output};
+use crate::blocks::utils::{input_as_number, input_as_number_in, input_to_millis_or_default};
+
+use libhaystack::units::units_generated::{CELSIUS, KILOJOULES_PER_DEGREE_KELVIN, WATT};
+use libhaystack::val::Number;
+
+use super::ModelClock;
+use crate::{blocks::InputImpl, blocks::OutputImpl};
+
+/// Thermal zone, as a single lumped capacity losing heat to the outdoors
+/// through its envelope:
+/// `capacity · dT/dt = gains + hvac + ua · (oat − T)`.
+///
+/// - `oat` is the outdoor air temperature. The output, the zone
+///   temperature, has its unit.
+/// - The zone starts at the temperature it settles at. Writing
+///   `initial` restarts it from that temperature instead.
+/// - Temperatures accept any temperature unit, plain numbers are °C.
+/// - `gains` are the internal and solar heat gains, `hvac` the heat the
+///   HVAC system adds (negative when cooling). Both accept any power
+///   unit, plain numbers are W.
+/// - `ua` is the envelope conductance in W/K (default 150), `capacity`
+///   the thermal capacity of the air, furnishings and structure in kJ/K
+///   (default 4000). Together they set the time constant of the zone,
+///   `capacity / ua`, about 7 h by default.
+/// - `interval` is the simulation step, in any time unit (default
+///   200 ms).
+///
+/// Drive `hvac` from a controller through an actuator and a coil or
+/// heater model, such as [`Fopdt`](super::Fopdt), to close the loop.
+#[block]
+#[derive(BlockProps, Debug)]
+#[category = "sim"]
+pub struct ThermalZone {
+    #[input(kind = "Number")]
+    pub oat: InputImpl,
+    #[input(kind = "Number")]
+    pub gains: InputImpl,
+    #[input(kind = "Number")]
+    pub hvac: InputImpl,
+    #[input(kind = "Number")]
+    pub ua: InputImpl,
+    #[input(kind = "Number")]
+    pub capacity: InputImpl,
+    #[input(kind = "Number")]
+    pub initial: InputImpl,
+    #[input(kind = "Number")]
+    pub interval: InputImpl,
+    #[output(kind = "Number")]
+    pub out: OutputImpl,
+    temperature: f64,
+    last_initial: Option<f64>,
+    held_oat: f64,
+    held_heat: f64,
+    clock: ModelClock,
+}
+
+impl Block for ThermalZone {
+    async fn execute(&mut self) {
+        let millis = input_to_millis_or_default(&self.interval.val);
+        self.wait_on_inputs(Duration::from_millis(millis)).await;
+
+        if !self.out.is_connected() {
+            return;
+        }
+
+        let Some(oat) = input_as_number_in(&self.oat, &CELSIUS) else {
+            return;
+        };
+        let heat = input_as_number_in(&self.gains, &WATT).unwrap_or(0.0)
+            + input_as_number_in(&self.hvac, &WATT).unwrap_or(0.0);
+        let ua = input_as_number(&self.ua)
+            .map_or(150.0, |n| n.value)
+            .max(0.0);
+        let capacity = input_as_number_in(&self.capacity, &KILOJOULES_PER_DEGREE_KELVIN)
+            .filter(|kj| *kj > 0.0)
+            .unwrap_or(4000.0)
+            * 1000.0;
+
+        let initial = input_as_number_in(&self.initial, &CELSIUS);
+        let restart = initial.filter(|_| initial != self.last_initial);
+        self.last_initial = initial;
+
+        let (_, elapsed) = self.clock.tick();
+        self.temperature = match (restart, elapsed) {
+            (Some(initial), _) => initial,
+            (None, None) => settled(oat, heat, ua).unwrap_or(oat),
+            // Over the elapsed interval the zone was driven by the outdoor
+            // temperature and heat of the previous cycle, held.
+            (None, Some(dt)) => match settled(self.held_oat, self.held_heat, ua) {
+                Some(target) => target + (self.temperature - target) * (-dt * ua / capacity).exp(),
+                None => self.temperature + self.held_heat * dt / capacity,
+            },
+        };
+        self.held_oat = oat;
+        self.held_heat = heat;
+
+        let out = match input_as_number(&self.oat).and_then(|n| n.unit) {
+            Some(unit) => CELSIUS.convert_to(self.temperature, unit).map_or_else(
+                |_| Number::make(self.temperature),
+                |value| Number::make_with_unit(value, unit),
+            ),
+            None => Number::make(self.temperature),
+        };
+        self.out.set(out.into());
+    }
+}
+
+/// The temperature a zone settles at, [`None`] without an envelope to
+/// lose heat through.
+fn settled(oat: f64, heat: f64, ua: f64) -> Option<f64> {
+    (ua > 0.0).then(|| oat + heat / ua)
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use libhaystack::units::get_unit;
+    use libhaystack::val::Value;
+
+    use crate::blocks::sim::ThermalZone;
+    use crate::testing::TestBench;
+
+    fn out(bench: &TestBench<ThermalZone>) -> f64 {
+        match bench.output("out") {
+            Value::Number(n) => n.value,
+            other => panic!("expected a number, got {other}"),
+        }
+    }
+
+    #[test]
+    fn test_thermal_zone_settles() {
+        let mut bench = TestBench::new(ThermalZone::new());
+        bench
+            .set("oat", 0)
+            .set("gains", 500)
+            .set("ua", 100)
+            .set("capacity", 3600)
+            .set("initial", 20)
+            .settle();
+        bench.assert_output("out", 20);
+
+        // Settles at 0 + 500 / 100 = 5 °C, with a time constant of 10 h.
+        bench.advance(Duration::from_secs(36_000)).settle();
+        assert!((out(&bench) - (5.0 + 15.0 * (-1.0f64).exp())).abs() < 1e-6);
+
+        // 1.5 kW of heating brings it to 20 °C.
+        bench.set("hvac", 1.5 * 1000.0).settle();
+        bench.advance(Duration::from_secs(1_000_000)).settle();
+        assert!((out(&bench) - 20.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_thermal_zone_units() {
+        let degf = get_unit("°F").unwrap();
+        let kw = get_unit("kW").unwrap();
+
+        let mut bench = TestBench::new(ThermalZone::new());
+        bench
+            .set("oat", Value::make_number_unit(32.0, degf))
+            .set("hvac", Value::make_number_unit(1.0, kw))
+            .set("ua", 100)
+            .settle();
+        // Starts where it settles: 10 °C.
+        bench.assert_output("out", Value::make_number_unit(50.0, degf));
+    }
+}
@@ -0,0 +1,373 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Dimensional analysis of the numbers the math blocks compute with.
+//!
+//! Operands are brought to a common unit before they are added,
+//! subtracted or compared, the unit of a product, quotient or power is
+//! derived from the dimensions of the operands, and operands of different
+//! dimensions are rejected rather than combined. A number without a unit
+//! takes the unit of the other operand.
+//!
+//! Absolute temperatures (`°C`, `°F`, `K`) and temperature differences
+//! (`Δ°C`, `Δ°F`, `ΔK`) share a dimension but don't mix freely: a
+//! difference added to or subtracted from a temperature yields a
+//! temperature, the difference of two temperatures is a temperature
+//! difference, and a temperature is never converted to a difference.
+//! Temperatures in different units are not added: their sum depends on
+//! the unit's zero.
+//!
+//! Dimensionless units such as `%` scale the other operand of a product
+//! or quotient: `50% * 10kW` is `5kW`.
+
+use libhaystack::units::{Unit, UnitDimensions, get_unit, match_units};
+use libhaystack::val::Number;
+
+use crate::base::error::ValueError;
+
+/// Returns `a + b`, in the unit of `a`, or of the temperature when one
+/// operand is a temperature difference. Two temperatures are only added
+/// in the same unit.
+pub(crate) fn add(a: Number, b: Number) -> Result<Number, ValueError> {
+    let (Some(ua), Some(ub)) = (a.unit, b.unit) else {
+        return Ok(plain(a.value + b.value, a.unit.or(b.unit)));
+    };
+
+    if is_temperature(ua) && is_temperature(ub) && ua.name() != ub.name() {
+        Err(incompatible(ua, ub))
+    } else if is_temperature(ua) && is_difference(ub) {
+        Ok(with_unit(a.value + difference_in(b.value, ub, ua), ua))
+    } else if is_difference(ua) && is_temperature(ub) {
+        Ok(with_unit(difference_in(a.value, ua, ub) + b.value, ub))
+    } else {
+        let b = convert(b, ua)?;
+        Ok(with_unit(a.value + b.value, ua))
+    }
+}
+
+/// Returns `a - b`, in the unit of `a`. The difference of two
+/// temperatures is a temperature difference.
+pub(crate) fn sub(a: Number, b: Number) -> Result<Number, ValueError> {
+    let (Some(ua), Some(ub)) = (a.unit, b.unit) else {
+        return Ok(plain(a.value - b.value, a.unit.or(b.unit)));
+    };
+
+    if is_temperature(ua) && is_difference(ub) {
+        Ok(with_unit(a.value - difference_in(b.value, ub, ua), ua))
+    } else if is_temperature(ua) && is_temperature(ub) {
+        let b = convert(b, ua)?;
+        Ok(with_unit(a.value - b.value, difference_of(ua)))
+    } else {
+        let b = convert(b, ua)?;
+        Ok(with_unit(a.value - b.value, ua))
+    }
+}
+
+/// Returns `a * b`, in the unit derived from the operands' units.
+pub(crate) fn mul(a: Number, b: Number) -> Result<Number, ValueError> {
+    let (Some(ua), Some(ub)) = (a.unit, b.unit) else {
+        return Ok(plain(a.value * b.value, a.unit.or(b.unit)));
+    };
+
+    match (scalar_scale(ua), scalar_scale(ub)) {
+        (Some(sa), Some(sb)) => return Ok(Number::make(a.value * sa * b.value * sb)),
+        (Some(sa), None) => return Ok(with_unit(a.value * sa * b.value, ub)),
+        (None, Some(sb)) => return Ok(with_unit(a.value * b.value * sb, ua)),
+        (None, None) => {}
+    }
+
+    let (da, db) = dimensions(ua, ub, "*")?;
+    let preferred = [
+        format!("{}_{}", ua.name(), ub.name()),
+        format!("{}_{}", ub.name(), ua.name()),
+    ];
+    let (factor, unit) =
+        derive(da + db, ua.scale * ub.scale, &preferred).ok_or_else(|| underivable(ua, "*", ub))?;
+    Ok(plain(a.value * b.value * factor, unit))
+}
+
+/// Returns `a / b`, in the unit derived from the operands' units.
+pub(crate) fn div(a: Number, b: Number) -> Result<Number, ValueError> {
+    match (a.unit, b.unit) {
+        (_, None) => Ok(plain(a.value / b.value, a.unit)),
+        (Some(ua), Some(ub)) if ua == ub => Ok(Number::make(a.value / b.value)),
+        (None, Some(ub)) => {
+            if let Some(scale) = scalar_scale(ub) {
+                return Ok(Number::make(a.value / (b.value * scale)));
+            }
+            let (_, db) = dimensions(ub, ub, "/")?;
+            let (factor, unit) = derive(UnitDimensions::default() - db, 1.0 / ub.scale, &[])
+                .ok_or_else(|| {
+                    ValueError::UnitConversion(format!("no unit for 1/{}", ub.symbol()))
+                })?;
+            Ok(plain(a.value / b.value * factor, unit))
+        }
+        (Some(ua), Some(ub)) => {
+            if let Some(scale) = scalar_scale(ub) {
+                return Ok(plain(a.value / (b.value * scale), Some(ua)));
+            }
+            if let Some(scale) = scalar_scale(ua) {
+                return div(Number::make(a.value * scale), b);
+            }
+
+            let (da, db) = dimensions(ua, ub, "/")?;
+            let preferred = [
+                format!("{}_per_{}", ua.name(), ub.name()),
+                format!("{}s_per_{}", ua.name(), ub.name()),
+            ];
+            let (factor, unit) = derive(da - db, ua.scale / ub.scale, &preferred)
+                .ok_or_else(|| underivable(ua, "/", ub))?;
+            Ok(plain(a.value / b.value * factor, unit))
+        }
+    }
+}
+
+/// Returns `base` raised to `exponent`, which must not have a unit. The
+/// unit of the base is raised too, so it must be one whose dimensions
+/// stay whole, such as `m²` to the power `0.5`.
+pub(crate) fn pow(base: Number, exponent: Number) -> Result<Number, ValueError> {
+    if let Some(unit) = exponent.unit {
+        return Err(ValueError::UnitConversion(format!(
+            "the exponent can't have a unit, got {unit}"
+        )));
+    }
+    let value = base.value.powf(exponent.value);
+    let Some(unit) = base.unit else {
+        return Ok(Number::make(value));
+    };
+    if exponent.value == 0.0 {
+        return Ok(Number::make(value));
+    }
+    if let Some(scale) = scalar_scale(unit) {
+        return Ok(Number::make((base.value * scale).powf(exponent.value)));
+    }
+
+    let (dims, _) = dimensions(unit, unit, "^")?;
+    let raise = |dim: i8| {
+        let raised = f64::from(dim) * exponent.value;
+        (raised.fract() == 0.0 && raised.abs() <= f64::from(i8::MAX)).then_some(raised as i8)
+    };
+    let raised = (|| {
+        Some(UnitDimensions {
+            kg: raise(dims.kg)?,
+            m: raise(dims.m)?,
+            sec: raise(dims.sec)?,
+            k: raise(dims.k)?,
+            a: raise(dims.a)?,
+            mol: raise(dims.mol)?,
+            cd: raise(dims.cd)?,
+        })
+    })();
+
+    let (factor, unit) = raised
+        .and_then(|dims| derive(dims, unit.scale.powf(exponent.value), &[]))
+        .ok_or_else(|| {
+            ValueError::UnitConversion(format!("no unit for {unit}^{}", exponent.value))
+        })?;
+    Ok(plain(value * factor, unit))
+}
+
+/// Converts `num` to the unit `to`. A number without a unit is taken to
+/// be in `to` already.
+///
+/// # Errors
+///
+/// Fails if the units have different dimensions, or if one is a
+/// temperature and the other a temperature difference.
+pub(crate) fn convert(num: Number, to: &'static Unit) -> Result<Number, ValueError> {
+    match num.unit {
+        None => Ok(with_unit(num.value, to)),
+        Some(unit) if unit == to => Ok(num),
+        Some(unit) if is_temperature(unit) != is_temperature(to) => Err(incompatible(to, unit)),
+        Some(unit) => unit
+            .convert_to(num.value, to)
+            .map(|value| with_unit(value, to))
+            .map_err(|_| incompatible(to, unit)),
+    }
+}
+
+/// A number of `unit`, which may be none.
+fn plain(value: f64, unit: Option<&'static Unit>) -> Number {
+    Number { value, unit }
+}
+
+fn with_unit(value: f64, unit: &'static Unit) -> Number {
+    Number::make_with_unit(value, unit)
+}
+
+/// True for the units of absolute temperature.
+fn is_temperature(unit: &Unit) -> bool {
+    unit.quantity.as_deref() == Some("temperature")
+}
+
+/// True for the units of temperature difference.
+fn is_difference(unit: &Unit) -> bool {
+    unit.quantity.as_deref() == Some("temperature differential")
+}
+
+/// The temperature difference `value` of unit `from`, in the difference
+/// unit matching the temperature unit `to`.
+fn difference_in(value: f64, from: &Unit, to: &Unit) -> f64 {
+    value * from.scale / to.scale
+}
+
+/// The unit of a difference of two temperatures of `unit`.
+fn difference_of(unit: &'static Unit) -> &'static Unit {
+    get_unit(&format!("{}_degrees", unit.name()))
+        .filter(|diff| is_difference(diff))
+        .unwrap_or(unit)
+}
+
+/// The factor a dimensionless unit such as `%` scales its number by.
+fn scalar_scale(unit: &Unit) -> Option<f64> {
+    (unit.dimensions.is_none() && unit.quantity.as_deref() == Some("dimensionless"))
+        .then_some(unit.scale)
+}
+
+/// The dimensions of the operands of a product, quotient or power.
+/// Units without dimensions and units with an offset, such as `°C`,
+/// can't take part in one.
+fn dimensions(
+    a: &Unit,
+    b: &Unit,
+    op: &str,
+) -> Result<(UnitDimensions, UnitDimensions), ValueError> {
+    match (a.dimensions, b.dimensions) {
+        (Some(da), Some(db)) if a.offset == 0.0 && b.offset == 0.0 => Ok((da, db)),
+        _ => Err(underivable(a, op, b)),
+    }
+}
+
+/// The unit of the given dimensions and scale, along with the factor
+/// numbers are multiplied by to be in it.
+///
+/// A dimensionless result has no unit and carries the scale as factor.
+/// Otherwise a unit of that exact scale is preferred, failing that the
+/// coherent SI unit of those dimensions. When several units match, one of
+/// the `preferred` names wins, then the shortest name, as in `kW` rather
+/// than `kVA`.
+fn derive(
+    dims: UnitDimensions,
+    scale: f64,
+    preferred: &[String],
+) -> Option<(f64, Option<&'static Unit>)> {
+    if dims == UnitDimensions::default() {
+        return Some((scale, None));
+    }
+
+    let candidates = |scale: f64| {
+        let mut units: Vec<_> = match_units(dims, scale)
+            .into_iter()
+            .filter(|unit| unit.offset == 0.0 && !is_temperature(unit))
+            .collect();
+        units.sort_by_key(|unit| (unit.name().len(), unit.name()));
+        units.dedup_by(|a, b| a.name() == b.name());
+        units
+    };
+    let pick = |units: Vec<&'static Unit>| {
+        units
+            .iter()
+            .find(|unit| preferred.iter().any(|name| name == unit.name()))
+            .or(units.first())
+            .copied()
+    };
+
+    pick(candidates(scale))
+        .map(|unit| (1.0, Some(unit)))
+        .or_else(|| pick(candidates(1.0)).map(|unit| (scale, Some(unit))))
+}
+
+fn incompatible(a: &Unit, b: &Unit) -> ValueError {
+    ValueError::IncompatibleUnits {
+        lhs: a.symbol().to_string(),
+        rhs: b.symbol().to_string(),
+    }
+}
+
+fn underivable(a: &Unit, op: &str, b: &Unit) -> ValueError {
+    ValueError::UnitConversion(format!("no unit for {a}{op}{b}"))
+}
+
+#[cfg(test)]
+mod test {
+    use libhaystack::units::get_unit;
+    use libhaystack::val::Number;
+
+    use crate::base::error::ValueError;
+
+    use super::{add, convert, div, mul, pow, sub};
+
+    fn num(value: f64, unit: &str) -> Number {
+        Number::make_with_unit(value, get_unit(unit).expect("Known unit"))
+    }
+
+    fn assert_num(actual: Result<Number, ValueError>, value: f64, unit: Option<&str>) {
+        let actual = actual.expect("Computes");
+        assert!(
+            (actual.value - value).abs() < 1e-9,
+            "{} != {value}",
+            actual.value
+        );
+        assert_eq!(actual.unit.map(|unit| unit.symbol()), unit);
+    }
+
+    #[test]
+    fn test_add_sub() {
+        assert_num(add(num(68.0, "°F"), num(10.0, "Δ°C")), 86.0, Some("°F"));
+        assert_num(add(num(10.0, "Δ°C"), num(68.0, "°F")), 86.0, Some("°F"));
+        assert_num(add(num(1.0, "m"), num(50.0, "cm")), 1.5, Some("m"));
+        assert_num(add(num(1.0, "kW"), Number::make(2.0)), 3.0, Some("kW"));
+        assert_num(sub(num(20.0, "°C"), num(50.0, "°F")), 10.0, Some("Δ°C"));
+        assert_num(sub(num(20.0, "°C"), num(5.0, "ΔK")), 15.0, Some("°C"));
+
+        assert_num(add(num(1.0, "°F"), num(2.0, "°F")), 3.0, Some("°F"));
+        assert!(matches!(
+            add(num(1.0, "°F"), num(2.0, "°C")),
+            Err(ValueError::IncompatibleUnits { lhs, rhs }) if lhs == "°F" && rhs == "°C"
+        ));
+        assert!(matches!(
+            add(num(68.0, "°F"), num(1.0, "kPa")),
+            Err(ValueError::IncompatibleUnits { .. })
+        ));
+        assert!(sub(num(5.0, "Δ°C"), num(20.0, "°C")).is_err());
+    }
+
+    #[test]
+    fn test_mul_div() {
+        assert_num(mul(num(2.0, "kW"), num(3.0, "h")), 6.0, Some("kWh"));
+        assert_num(mul(num(3.0, "m"), num(2.0, "m")), 6.0, Some("m²"));
+        assert_num(mul(num(50.0, "%"), num(10.0, "kW")), 5.0, Some("kW"));
+        assert_num(div(num(100.0, "m"), num(10.0, "s")), 10.0, Some("m/s"));
+        assert_num(div(num(6.0, "kWh"), num(3.0, "h")), 2.0, Some("kW"));
+        assert_num(div(num(1.0, "m"), num(50.0, "cm")), 2.0, None);
+        assert_num(div(num(10.0, "kW"), Number::make(2.0)), 5.0, Some("kW"));
+        assert_num(div(Number::make(10.0), num(2.0, "s")), 5.0, Some("Hz"));
+
+        assert!(mul(num(20.0, "°C"), num(2.0, "m")).is_err());
+    }
+
+    #[test]
+    fn test_pow() {
+        assert_num(pow(num(3.0, "m"), Number::make(2.0)), 9.0, Some("m²"));
+        assert_num(pow(num(9.0, "m²"), Number::make(0.5)), 3.0, Some("m"));
+        assert_num(pow(Number::make(2.0), Number::make(3.0)), 8.0, None);
+
+        assert!(pow(num(2.0, "m"), Number::make(0.5)).is_err());
+        assert!(pow(Number::make(2.0), num(2.0, "m")).is_err());
+    }
+
+    #[test]
+    fn test_convert() {
+        assert_num(
+            convert(num(212.0, "°F"), get_unit("°C").unwrap()),
+            100.0,
+            Some("°C"),
+        );
+        assert_num(
+            convert(Number::make(5.0), get_unit("°C").unwrap()),
+            5.0,
+            Some("°C"),
+        );
+        assert!(convert(num(5.0, "Δ°C"), get_unit("°C").unwrap()).is_err());
+        assert!(convert(num(5.0, "kPa"), get_unit("°C").unwrap()).is_err());
+    }
+}
@@ -88,42 +88,18 @@ pub(super) fn input_to_millis_or_default(dur: &Option<Value>) -> u64 {
     }
 }
 
-/// Converts all `numbers` to a common unit.
+/// Converts all `numbers` to a common unit, the first unit among them.
 ///
 /// # Errors
 ///
-/// Returns an error if unit conversion fails.
+/// Returns an error if a number has a unit that is not convertible to
+/// the common one.
 pub(super) fn convert_units(numbers: &[Number]) -> Result<Vec<Number>, ValueError> {
-    if numbers.len() <= 1 {
-        Ok(numbers.to_vec())
-    } else if let Some(unit) = numbers
-        .iter()
-        .find_map(|n| if n.unit.is_some() { n.unit } else { None })
-    {
-        numbers
+    match numbers.iter().find_map(|n| n.unit) {
+        Some(unit) if numbers.len() > 1 => numbers
             .iter()
-            .map(|n| {
-                if let Some(other_unit) = n.unit {
-                    if other_unit != unit {
-                        other_unit
-                            .convert_to(n.value, unit)
-                            .map_err(ValueError::UnitConversion)
-                            .map(|v| Number {
-                                value: v,
-                                unit: Some(unit),
-                            })
-                    } else {
-                        Ok(*n)
-                    }
-                } else {
-                    Ok(Number {
-                        value: n.value,
-                        unit: Some(unit),
-                    })
-                }
-            })
-            .collect::<Result<Vec<Number>, ValueError>>()
-    } else {
-        Ok(numbers.to_vec())
+            .map(|n| super::units::convert(*n, unit))
+            .collect(),
+        _ => Ok(numbers.to_vec()),
     }
 }
@@ -21,8 +21,30 @@ pub use tokio_impl::engine::*;
 /// The per-subsystem errors this crate reports, the [`Error`] aggregate
 /// that wraps them, and the matching `Result` alias.
 pub use base::error::{
-    EngineError, Error, ExternalError, LinkEnd, RegistryError, Result, ValueError,
+    AlarmError, EngineError, Error, ExternalError, HistoryError, LinkEnd, ProgramError,
+    RegistryError, Result, ValueError,
 };
 
+#[cfg(all(feature = "server", not(target_arch = "wasm32")))]
+pub mod server;
+
+#[cfg(all(feature = "modbus", not(target_arch = "wasm32")))]
+pub mod modbus;
+
+#[cfg(all(feature = "bacnet", not(target_arch = "wasm32")))]
+pub mod bacnet;
+
+#[cfg(all(feature = "mqtt", not(target_arch = "wasm32")))]
+pub mod mqtt;
+
+#[cfg(all(feature = "scripting", not(target_arch = "wasm32")))]
+pub mod scripting;
+
+#[cfg(all(feature = "plugins", not(target_arch = "wasm32")))]
+pub mod plugins;
+
+#[cfg(all(any(test, feature = "testing"), not(target_arch = "wasm32")))]
+pub mod testing;
+
 #[cfg(target_arch = "wasm32")]
 pub mod wasm;
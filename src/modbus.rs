@@ -0,0 +1,265 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Modbus TCP client and server.
+//!
+//! Enabled by the `modbus` feature, which also registers the `modbus`
+//! block category:
+//!
+//! - `ModbusReadRegister` and `ModbusReadBit` poll a device at
+//!   `interval` and output a Number or Bool;
+//! - `ModbusWriteRegister` and `ModbusWriteCoil` write their `in` input
+//!   to a device whenever it changes.
+//!
+//! The blocks share one [`ModbusClient`] connection per `host:port`,
+//! and fault while the device cannot be reached or answers with an
+//! exception. Register values are decoded per [`DataFormat`] and
+//! [`WordOrder`], then scaled: `out = raw * scale + offset`.
+//!
+//! [`ModbusServer`] works the other way around, presenting selected
+//! pins of a running engine to a Modbus master.
+
+mod client;
+mod codec;
+mod data;
+mod server;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+pub use client::{DEFAULT_TIMEOUT, ModbusClient};
+pub use data::{DataFormat, WordOrder};
+pub use server::{ModbusServer, RegisterMapping};
+
+/// The four Modbus data tables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Table {
+    /// Read-write bits.
+    Coil,
+    /// Read-only bits.
+    Discrete,
+    /// Read-write registers.
+    Holding,
+    /// Read-only registers.
+    Input,
+}
+
+impl Table {
+    /// Whether the table holds bits rather than registers.
+    pub fn is_bits(self) -> bool {
+        matches!(self, Table::Coil | Table::Discrete)
+    }
+
+    /// Parses a table name, as the blocks take it.
+    pub fn parse(name: &str) -> Option<Self> {
+        serde_json::from_value(name.to_lowercase().into()).ok()
+    }
+}
+
+impl std::fmt::Display for Table {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Table::Coil => "coil",
+            Table::Discrete => "discrete",
+            Table::Holding => "holding",
+            Table::Input => "input",
+        })
+    }
+}
+
+/// Exception codes a server answers with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum Exception {
+    /// The function code is not supported.
+    #[error("illegal function")]
+    IllegalFunction,
+    /// An address in the request does not exist.
+    #[error("illegal data address")]
+    IllegalDataAddress,
+    /// A value or count in the request is not allowed.
+    #[error("illegal data value")]
+    IllegalDataValue,
+    /// The server failed while handling the request.
+    #[error("server device failure")]
+    ServerDeviceFailure,
+    /// Any other code.
+    #[error("exception code {0}")]
+    Other(u8),
+}
+
+impl Exception {
+    fn from_code(code: u8) -> Self {
+        match code {
+            1 => Exception::IllegalFunction,
+            2 => Exception::IllegalDataAddress,
+            3 => Exception::IllegalDataValue,
+            4 => Exception::ServerDeviceFailure,
+            code => Exception::Other(code),
+        }
+    }
+
+    fn code(self) -> u8 {
+        match self {
+            Exception::IllegalFunction => 1,
+            Exception::IllegalDataAddress => 2,
+            Exception::IllegalDataValue => 3,
+            Exception::ServerDeviceFailure => 4,
+            Exception::Other(code) => code,
+        }
+    }
+}
+
+/// Errors reported by the Modbus client and server.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum ModbusError {
+    /// Connecting, reading or writing the socket failed.
+    #[error("Modbus I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    /// The device did not answer in time.
+    #[error("Modbus request timed out")]
+    Timeout,
+    /// The device answered with an exception.
+    #[error("Modbus exception: {0}")]
+    Exception(Exception),
+    /// The device's answer does not match the request.
+    #[error("Invalid Modbus response: {0}")]
+    InvalidResponse(String),
+    /// The request cannot be sent as asked.
+    #[error("Invalid Modbus request: {0}")]
+    InvalidRequest(String),
+    /// A [`RegisterMapping`] is not valid.
+    #[error("Invalid Modbus register mapping: {0}")]
+    InvalidMapping(String),
+}
+
+impl ModbusError {
+    /// Whether the connection is unusable after this error, so the
+    /// next request should reconnect.
+    pub fn is_connection_lost(&self) -> bool {
+        matches!(
+            self,
+            ModbusError::Io(_) | ModbusError::Timeout | ModbusError::InvalidResponse(_)
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use libhaystack::val::Value;
+    use tokio::net::TcpListener;
+    use uuid::Uuid;
+
+    use super::{
+        DataFormat, Exception, ModbusClient, ModbusError, ModbusServer, RegisterMapping, Table,
+    };
+    use crate::base::block::{Block, BlockProps};
+    use crate::base::engine::{Engine, messages::EngineMessage};
+    use crate::blocks::math::Add;
+    use crate::blocks::modbus::ModbusReadRegister;
+    use crate::single_threaded::SingleThreadedEngine;
+
+    fn mapping(table: Table, address: u16, block: Uuid, pin: &str) -> RegisterMapping {
+        serde_json::from_value(serde_json::json!({
+            "table": table,
+            "address": address,
+            "block": block.to_string(),
+            "pin": pin,
+        }))
+        .unwrap()
+    }
+
+    fn reader(port: u16) -> ModbusReadRegister {
+        let mut block = ModbusReadRegister::new();
+        block.host.val = Some("127.0.0.1".into());
+        block.port.val = Some(Value::from(port as i32));
+        block.address.val = Some(10.into());
+        block.scale.val = Some(Value::from(0.1));
+        block.interval.val = Some(1.into());
+        block
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn blocks_read_pins_served_by_an_engine() {
+        // The simulated device: an engine serving the pins of an Add
+        // block as registers and a coil.
+        let mut engine = SingleThreadedEngine::new();
+        let add = Add::new();
+        let add_id = *add.id();
+        engine.schedule(add).unwrap();
+
+        let mut in0 = mapping(Table::Holding, 0, add_id, "in0");
+        in0.format = DataFormat::F32;
+        let mut out = mapping(Table::Holding, 10, add_id, "out");
+        out.scale = 0.1;
+        let server = ModbusServer::new(
+            &mut engine,
+            vec![
+                in0,
+                mapping(Table::Holding, 2, add_id, "in1"),
+                out,
+                mapping(Table::Coil, 0, add_id, "in2"),
+            ],
+        )
+        .unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(server.serve(listener));
+
+        let (reply_sender, _replies) = tokio::sync::mpsc::channel(8);
+        let sender = engine.create_message_channel(Uuid::new_v4(), reply_sender);
+
+        let master = async {
+            let mut client = ModbusClient::connect(("127.0.0.1", port)).await.unwrap();
+            client
+                .write_registers(1, 0, &DataFormat::F32.encode(20.5, Default::default()))
+                .await
+                .unwrap();
+            client.write_registers(1, 2, &[1]).await.unwrap();
+            client.write_registers(1, 10, &[225]).await.unwrap();
+            client.write_coil(1, 0, true).await.unwrap();
+            assert_eq!(
+                client.read_bits(1, Table::Coil, 0, 1).await.unwrap(),
+                [true]
+            );
+            assert!(matches!(
+                client.read_registers(1, Table::Holding, 50, 1).await,
+                Err(ModbusError::Exception(Exception::IllegalDataAddress))
+            ));
+
+            let in0 = client
+                .read_registers(1, Table::Holding, 0, 3)
+                .await
+                .unwrap();
+            assert_eq!(DataFormat::F32.decode(&in0[..2], Default::default()), 20.5);
+            assert_eq!(in0[2], 1);
+
+            let mut block = reader(port);
+            block.execute().await;
+            assert!(!block.state().is_fault(), "{:?}", block.state());
+            assert_eq!(block.out.value, Value::from(22.5));
+
+            sender.send(EngineMessage::Shutdown).await.unwrap();
+        };
+
+        tokio::join!(engine.run(), master);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn blocks_fault_while_the_device_is_unreachable() {
+        let closed = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().port()
+        };
+
+        let mut block = reader(closed);
+        block.execute().await;
+        let state = block.state();
+        assert!(
+            state
+                .fault_reason()
+                .is_some_and(|reason| reason.starts_with("ModbusReadRegister: Modbus I/O error")),
+            "{state:?}"
+        );
+    }
+}
@@ -0,0 +1,179 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Modbus TCP client.
+
+use std::time::Duration;
+
+use tokio::net::{TcpStream, ToSocketAddrs};
+use tokio::time::timeout;
+
+use super::codec::{self, Frame, Request, Response};
+use super::{ModbusError, Table};
+
+/// How long a connect or a request may take by default.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// A connection to a Modbus TCP server.
+///
+/// Requests go out one at a time. The unit id is given per request, so
+/// one connection to a gateway reaches every device behind it.
+///
+/// # Examples
+///
+/// ```no_run
+/// use logic_mesh::modbus::{ModbusClient, Table};
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() -> Result<(), logic_mesh::modbus::ModbusError> {
+/// let mut client = ModbusClient::connect("192.168.1.20:502").await?;
+/// let registers = client.read_registers(1, Table::Holding, 0, 2).await?;
+/// client.write_registers(1, 10, &[42]).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ModbusClient {
+    stream: TcpStream,
+    transaction: u16,
+    timeout: Duration,
+}
+
+impl ModbusClient {
+    /// Connects to a server.
+    pub async fn connect(addr: impl ToSocketAddrs) -> Result<Self, ModbusError> {
+        let stream = timeout(DEFAULT_TIMEOUT, TcpStream::connect(addr))
+            .await
+            .map_err(|_| ModbusError::Timeout)??;
+        stream.set_nodelay(true)?;
+        Ok(Self {
+            stream,
+            transaction: 0,
+            timeout: DEFAULT_TIMEOUT,
+        })
+    }
+
+    /// Sets how long a request may wait for its response.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Reads `count` holding or input registers starting at `address`.
+    pub async fn read_registers(
+        &mut self,
+        unit: u8,
+        table: Table,
+        address: u16,
+        count: u16,
+    ) -> Result<Vec<u16>, ModbusError> {
+        if table.is_bits() || count == 0 || count > codec::MAX_READ_REGISTERS {
+            return Err(ModbusError::InvalidRequest(format!(
+                "cannot read {count} registers from the {table} table"
+            )));
+        }
+        match self
+            .call(
+                unit,
+                Request::Read {
+                    table,
+                    address,
+                    count,
+                },
+            )
+            .await?
+        {
+            Response::Registers(registers) => Ok(registers),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    /// Reads `count` coils or discrete inputs starting at `address`.
+    pub async fn read_bits(
+        &mut self,
+        unit: u8,
+        table: Table,
+        address: u16,
+        count: u16,
+    ) -> Result<Vec<bool>, ModbusError> {
+        if !table.is_bits() || count == 0 || count > codec::MAX_READ_BITS {
+            return Err(ModbusError::InvalidRequest(format!(
+                "cannot read {count} bits from the {table} table"
+            )));
+        }
+        match self
+            .call(
+                unit,
+                Request::Read {
+                    table,
+                    address,
+                    count,
+                },
+            )
+            .await?
+        {
+            Response::Bits(bits) => Ok(bits),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    /// Writes holding registers starting at `address`: one with function
+    /// code 6, several with 16.
+    pub async fn write_registers(
+        &mut self,
+        unit: u8,
+        address: u16,
+        values: &[u16],
+    ) -> Result<(), ModbusError> {
+        let request = match values {
+            [] => return Err(ModbusError::InvalidRequest("nothing to write".into())),
+            [value] => Request::WriteSingleRegister {
+                address,
+                value: *value,
+            },
+            values => Request::WriteMultipleRegisters {
+                address,
+                values: values.to_vec(),
+            },
+        };
+        self.call(unit, request).await.map(|_| ())
+    }
+
+    /// Writes one coil.
+    pub async fn write_coil(
+        &mut self,
+        unit: u8,
+        address: u16,
+        value: bool,
+    ) -> Result<(), ModbusError> {
+        self.call(unit, Request::WriteSingleCoil { address, value })
+            .await
+            .map(|_| ())
+    }
+
+    async fn call(&mut self, unit: u8, request: Request) -> Result<Response, ModbusError> {
+        self.transaction = self.transaction.wrapping_add(1);
+        let frame = Frame {
+            transaction: self.transaction,
+            unit,
+            pdu: request.encode(),
+        };
+
+        let exchange = async {
+            codec::write_frame(&mut self.stream, &frame).await?;
+            loop {
+                let reply = codec::read_frame(&mut self.stream).await?;
+                // A late reply to a request that timed out.
+                if reply.transaction != frame.transaction {
+                    continue;
+                }
+                return Response::decode(&request, &reply.pdu);
+            }
+        };
+        timeout(self.timeout, exchange)
+            .await
+            .map_err(|_| ModbusError::Timeout)?
+    }
+}
+
+fn unexpected(response: Response) -> ModbusError {
+    ModbusError::InvalidResponse(format!("unexpected {response:?}"))
+}
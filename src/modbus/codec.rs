@@ -0,0 +1,390 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Modbus TCP framing and the PDUs of the supported function codes.
+//!
+//! A frame is the 7 byte MBAP header (transaction id, protocol id `0`,
+//! length of what follows, unit id) followed by the PDU: a function
+//! code and its data, all big endian.
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use super::{Exception, ModbusError, Table};
+
+/// Largest PDU allowed by the specification.
+const MAX_PDU: usize = 253;
+
+/// Most registers one request may read.
+pub(super) const MAX_READ_REGISTERS: u16 = 125;
+/// Most bits one request may read.
+pub(super) const MAX_READ_BITS: u16 = 2000;
+/// Most registers one request may write.
+const MAX_WRITE_REGISTERS: u16 = 123;
+/// Most coils one request may write.
+const MAX_WRITE_COILS: u16 = 1968;
+
+/// A request PDU.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Request {
+    /// Reads `count` items of a table, function codes 1 to 4.
+    Read {
+        table: Table,
+        address: u16,
+        count: u16,
+    },
+    /// Function code 5.
+    WriteSingleCoil { address: u16, value: bool },
+    /// Function code 6.
+    WriteSingleRegister { address: u16, value: u16 },
+    /// Function code 15.
+    WriteMultipleCoils { address: u16, values: Vec<bool> },
+    /// Function code 16.
+    WriteMultipleRegisters { address: u16, values: Vec<u16> },
+}
+
+/// A successful response PDU.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Response {
+    /// Coils or discrete inputs, padded to a multiple of 8.
+    Bits(Vec<bool>),
+    /// Holding or input registers.
+    Registers(Vec<u16>),
+    /// A write, echoing the written address and value or count.
+    Written { address: u16, value: u16 },
+}
+
+/// One frame: MBAP header fields and PDU.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Frame {
+    pub(super) transaction: u16,
+    pub(super) unit: u8,
+    pub(super) pdu: Vec<u8>,
+}
+
+impl Table {
+    fn read_function(self) -> u8 {
+        match self {
+            Table::Coil => 0x01,
+            Table::Discrete => 0x02,
+            Table::Holding => 0x03,
+            Table::Input => 0x04,
+        }
+    }
+}
+
+impl Request {
+    /// The request's function code.
+    pub(super) fn function(&self) -> u8 {
+        match self {
+            Request::Read { table, .. } => table.read_function(),
+            Request::WriteSingleCoil { .. } => 0x05,
+            Request::WriteSingleRegister { .. } => 0x06,
+            Request::WriteMultipleCoils { .. } => 0x0F,
+            Request::WriteMultipleRegisters { .. } => 0x10,
+        }
+    }
+
+    pub(super) fn encode(&self) -> Vec<u8> {
+        let mut pdu = vec![self.function()];
+        match self {
+            Request::Read { address, count, .. } => {
+                push_u16(&mut pdu, *address);
+                push_u16(&mut pdu, *count);
+            }
+            Request::WriteSingleCoil { address, value } => {
+                push_u16(&mut pdu, *address);
+                push_u16(&mut pdu, if *value { 0xFF00 } else { 0 });
+            }
+            Request::WriteSingleRegister { address, value } => {
+                push_u16(&mut pdu, *address);
+                push_u16(&mut pdu, *value);
+            }
+            Request::WriteMultipleCoils { address, values } => {
+                push_u16(&mut pdu, *address);
+                push_u16(&mut pdu, values.len() as u16);
+                let bytes = pack_bits(values);
+                pdu.push(bytes.len() as u8);
+                pdu.extend(bytes);
+            }
+            Request::WriteMultipleRegisters { address, values } => {
+                push_u16(&mut pdu, *address);
+                push_u16(&mut pdu, values.len() as u16);
+                pdu.push((values.len() * 2) as u8);
+                values.iter().for_each(|value| push_u16(&mut pdu, *value));
+            }
+        }
+        pdu
+    }
+
+    /// Decodes a request PDU, as a server.
+    pub(super) fn decode(pdu: &[u8]) -> Result<Self, Exception> {
+        let (&function, data) = pdu.split_first().ok_or(Exception::IllegalFunction)?;
+        let word = |index: usize| {
+            data.get(index * 2..index * 2 + 2)
+                .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+                .ok_or(Exception::IllegalDataValue)
+        };
+
+        let table = match function {
+            0x01 => Some(Table::Coil),
+            0x02 => Some(Table::Discrete),
+            0x03 => Some(Table::Holding),
+            0x04 => Some(Table::Input),
+            _ => None,
+        };
+        if let Some(table) = table {
+            let count = word(1)?;
+            let max = if table.is_bits() {
+                MAX_READ_BITS
+            } else {
+                MAX_READ_REGISTERS
+            };
+            if count == 0 || count > max {
+                return Err(Exception::IllegalDataValue);
+            }
+            return Ok(Request::Read {
+                table,
+                address: word(0)?,
+                count,
+            });
+        }
+
+        match function {
+            0x05 => Ok(Request::WriteSingleCoil {
+                address: word(0)?,
+                value: match word(1)? {
+                    0xFF00 => true,
+                    0 => false,
+                    _ => return Err(Exception::IllegalDataValue),
+                },
+            }),
+            0x06 => Ok(Request::WriteSingleRegister {
+                address: word(0)?,
+                value: word(1)?,
+            }),
+            0x0F => {
+                let count = word(1)?;
+                let bytes = data.get(5..).ok_or(Exception::IllegalDataValue)?;
+                if count == 0 || count > MAX_WRITE_COILS || bytes.len() * 8 < count as usize {
+                    return Err(Exception::IllegalDataValue);
+                }
+                let mut values = unpack_bits(bytes);
+                values.truncate(count as usize);
+                Ok(Request::WriteMultipleCoils {
+                    address: word(0)?,
+                    values,
+                })
+            }
+            0x10 => {
+                let count = word(1)?;
+                if count == 0 || count > MAX_WRITE_REGISTERS {
+                    return Err(Exception::IllegalDataValue);
+                }
+                let values = data
+                    .get(5..5 + count as usize * 2)
+                    .ok_or(Exception::IllegalDataValue)?
+                    .chunks(2)
+                    .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+                    .collect();
+                Ok(Request::WriteMultipleRegisters {
+                    address: word(0)?,
+                    values,
+                })
+            }
+            _ => Err(Exception::IllegalFunction),
+        }
+    }
+}
+
+impl Response {
+    /// Encodes the response to `request`, as a server.
+    pub(super) fn encode(&self, request: &Request) -> Vec<u8> {
+        let mut pdu = vec![request.function()];
+        match self {
+            Response::Bits(bits) => {
+                let bytes = pack_bits(bits);
+                pdu.push(bytes.len() as u8);
+                pdu.extend(bytes);
+            }
+            Response::Registers(registers) => {
+                pdu.push((registers.len() * 2) as u8);
+                registers
+                    .iter()
+                    .for_each(|register| push_u16(&mut pdu, *register));
+            }
+            Response::Written { address, value } => {
+                push_u16(&mut pdu, *address);
+                push_u16(&mut pdu, *value);
+            }
+        }
+        pdu
+    }
+
+    /// Decodes the response to `request`, as a client.
+    pub(super) fn decode(request: &Request, pdu: &[u8]) -> Result<Self, ModbusError> {
+        let invalid = |reason: &str| ModbusError::InvalidResponse(reason.to_string());
+        let (&function, data) = pdu.split_first().ok_or_else(|| invalid("empty PDU"))?;
+
+        if function == request.function() | 0x80 {
+            let code = data.first().copied().unwrap_or_default();
+            return Err(ModbusError::Exception(Exception::from_code(code)));
+        }
+        if function != request.function() {
+            return Err(invalid("function code does not match the request"));
+        }
+
+        match request {
+            Request::Read { table, count, .. } => {
+                let (&len, bytes) = data.split_first().ok_or_else(|| invalid("no data"))?;
+                if bytes.len() != len as usize {
+                    return Err(invalid("byte count does not match the data"));
+                }
+                if table.is_bits() {
+                    let mut bits = unpack_bits(bytes);
+                    if bits.len() < *count as usize {
+                        return Err(invalid("too few bits"));
+                    }
+                    bits.truncate(*count as usize);
+                    Ok(Response::Bits(bits))
+                } else {
+                    if bytes.len() != *count as usize * 2 {
+                        return Err(invalid("register count does not match the request"));
+                    }
+                    Ok(Response::Registers(
+                        bytes
+                            .chunks(2)
+                            .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+                            .collect(),
+                    ))
+                }
+            }
+            _ => match data {
+                [a, b, c, d] => Ok(Response::Written {
+                    address: u16::from_be_bytes([*a, *b]),
+                    value: u16::from_be_bytes([*c, *d]),
+                }),
+                _ => Err(invalid("malformed write response")),
+            },
+        }
+    }
+}
+
+/// Encodes an exception response to `function`.
+pub(super) fn encode_exception(function: u8, exception: Exception) -> Vec<u8> {
+    vec![function | 0x80, exception.code()]
+}
+
+/// Reads one frame.
+pub(super) async fn read_frame(stream: &mut (impl AsyncRead + Unpin)) -> std::io::Result<Frame> {
+    let mut header = [0u8; 7];
+    stream.read_exact(&mut header).await?;
+
+    let transaction = u16::from_be_bytes([header[0], header[1]]);
+    let protocol = u16::from_be_bytes([header[2], header[3]]);
+    let len = u16::from_be_bytes([header[4], header[5]]) as usize;
+    if protocol != 0 || !(2..=MAX_PDU + 1).contains(&len) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "Invalid MBAP header",
+        ));
+    }
+
+    let mut pdu = vec![0u8; len - 1];
+    stream.read_exact(&mut pdu).await?;
+    Ok(Frame {
+        transaction,
+        unit: header[6],
+        pdu,
+    })
+}
+
+/// Writes one frame.
+pub(super) async fn write_frame(
+    stream: &mut (impl AsyncWrite + Unpin),
+    frame: &Frame,
+) -> std::io::Result<()> {
+    let mut bytes = Vec::with_capacity(7 + frame.pdu.len());
+    push_u16(&mut bytes, frame.transaction);
+    push_u16(&mut bytes, 0);
+    push_u16(&mut bytes, frame.pdu.len() as u16 + 1);
+    bytes.push(frame.unit);
+    bytes.extend(&frame.pdu);
+    stream.write_all(&bytes).await
+}
+
+fn push_u16(bytes: &mut Vec<u8>, value: u16) {
+    bytes.extend(value.to_be_bytes());
+}
+
+/// Packs bits least significant first, as Modbus orders coils.
+fn pack_bits(bits: &[bool]) -> Vec<u8> {
+    bits.chunks(8)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .enumerate()
+                .fold(0u8, |byte, (index, bit)| byte | (u8::from(*bit) << index))
+        })
+        .collect()
+}
+
+fn unpack_bits(bytes: &[u8]) -> Vec<bool> {
+    bytes
+        .iter()
+        .flat_map(|byte| (0..8).map(move |index| byte & (1 << index) != 0))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Request, Response};
+    use crate::modbus::{Exception, ModbusError, Table};
+
+    #[test]
+    fn requests_round_trip() {
+        let requests = [
+            Request::Read {
+                table: Table::Input,
+                address: 30,
+                count: 2,
+            },
+            Request::WriteSingleCoil {
+                address: 4,
+                value: true,
+            },
+            Request::WriteMultipleCoils {
+                address: 1,
+                values: vec![true, false, true, true, false, false, false, false, true],
+            },
+            Request::WriteMultipleRegisters {
+                address: 100,
+                values: vec![0x4148, 0x0000],
+            },
+        ];
+        for request in requests {
+            assert_eq!(Request::decode(&request.encode()), Ok(request));
+        }
+    }
+
+    #[test]
+    fn decodes_responses_and_exceptions() {
+        let request = Request::Read {
+            table: Table::Coil,
+            address: 0,
+            count: 10,
+        };
+        let bits = vec![
+            true, false, false, true, false, false, false, false, false, true,
+        ];
+        let pdu = Response::Bits(bits.clone()).encode(&request);
+        assert_eq!(pdu, [0x01, 2, 0b0000_1001, 0b0000_0010]);
+        assert_eq!(
+            Response::decode(&request, &pdu).unwrap(),
+            Response::Bits(bits)
+        );
+
+        assert!(matches!(
+            Response::decode(&request, &[0x81, 0x02]),
+            Err(ModbusError::Exception(Exception::IllegalDataAddress))
+        ));
+    }
+}
@@ -0,0 +1,124 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Typed values spread over 16 bit registers.
+
+use serde::{Deserialize, Serialize};
+
+/// How a number is laid out in registers.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DataFormat {
+    /// One register, unsigned.
+    #[default]
+    U16,
+    /// One register, two's complement.
+    I16,
+    /// Two registers, unsigned.
+    U32,
+    /// Two registers, two's complement.
+    I32,
+    /// Two registers, IEEE 754 single precision.
+    F32,
+}
+
+/// Which register of a two register value comes first.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WordOrder {
+    /// High word first, as the specification lays out bytes.
+    #[default]
+    Big,
+    /// Low word first, common on devices with little endian CPUs.
+    Little,
+}
+
+impl DataFormat {
+    /// Number of registers a value takes.
+    pub fn registers(self) -> u16 {
+        match self {
+            DataFormat::U16 | DataFormat::I16 => 1,
+            DataFormat::U32 | DataFormat::I32 | DataFormat::F32 => 2,
+        }
+    }
+
+    /// Decodes the value held in `registers`, which must be
+    /// [`registers`](Self::registers) long.
+    pub fn decode(self, registers: &[u16], order: WordOrder) -> f64 {
+        let word = || {
+            let (high, low) = match order {
+                WordOrder::Big => (registers[0], registers[1]),
+                WordOrder::Little => (registers[1], registers[0]),
+            };
+            (u32::from(high) << 16) | u32::from(low)
+        };
+        match self {
+            DataFormat::U16 => f64::from(registers[0]),
+            DataFormat::I16 => f64::from(registers[0] as i16),
+            DataFormat::U32 => f64::from(word()),
+            DataFormat::I32 => f64::from(word() as i32),
+            DataFormat::F32 => f64::from(f32::from_bits(word())),
+        }
+    }
+
+    /// Encodes `value` into registers, rounding and saturating integers
+    /// to the format's range.
+    pub fn encode(self, value: f64, order: WordOrder) -> Vec<u16> {
+        let split = |word: u32| {
+            let (high, low) = ((word >> 16) as u16, word as u16);
+            match order {
+                WordOrder::Big => vec![high, low],
+                WordOrder::Little => vec![low, high],
+            }
+        };
+        let value = if self == DataFormat::F32 {
+            value
+        } else {
+            value.round()
+        };
+        match self {
+            DataFormat::U16 => vec![value as u16],
+            DataFormat::I16 => vec![value as i16 as u16],
+            DataFormat::U32 => split(value as u32),
+            DataFormat::I32 => split(value as i32 as u32),
+            DataFormat::F32 => split((value as f32).to_bits()),
+        }
+    }
+
+    /// Parses a format name, as the blocks take it.
+    pub fn parse(name: &str) -> Option<Self> {
+        serde_json::from_value(name.to_lowercase().into()).ok()
+    }
+}
+
+impl WordOrder {
+    /// Parses a word order name, as the blocks take it.
+    pub fn parse(name: &str) -> Option<Self> {
+        serde_json::from_value(name.to_lowercase().into()).ok()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DataFormat, WordOrder};
+
+    #[test]
+    fn formats_round_trip_in_both_word_orders() {
+        for order in [WordOrder::Big, WordOrder::Little] {
+            for (format, value) in [
+                (DataFormat::U16, 65535.0),
+                (DataFormat::I16, -2.0),
+                (DataFormat::U32, 70000.0),
+                (DataFormat::I32, -70000.0),
+                (DataFormat::F32, 21.5),
+            ] {
+                let registers = format.encode(value, order);
+                assert_eq!(registers.len(), format.registers() as usize);
+                assert_eq!(format.decode(&registers, order), value);
+            }
+        }
+
+        assert_eq!(DataFormat::F32.encode(1.0, WordOrder::Big), [0x3F80, 0]);
+        assert_eq!(DataFormat::F32.encode(1.0, WordOrder::Little), [0, 0x3F80]);
+        assert_eq!(DataFormat::parse("F32"), Some(DataFormat::F32));
+    }
+}
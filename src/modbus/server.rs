@@ -0,0 +1,400 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Modbus TCP server exposing block pins as registers and bits.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use libhaystack::val::{Number, Value};
+use serde::{Deserialize, Serialize};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{Mutex, mpsc};
+use uuid::Uuid;
+
+use super::codec::{self, Frame, Request, Response};
+use super::{DataFormat, Exception, ModbusError, Table, WordOrder};
+use crate::base::engine::{
+    Engine,
+    messages::{BlockDefinition, EngineMessage},
+};
+use crate::tokio_impl::engine::single_threaded::Messages;
+
+/// One pin exposed at an address of a table.
+///
+/// Registers hold `(value - offset) / scale` in the given format, so a
+/// temperature of `21.5` with `scale: 0.1` reads as `215`. Bits hold
+/// Bool pins, or whether a Number pin is non-zero.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisterMapping {
+    /// The table the pin appears in.
+    pub table: Table,
+    /// The first address the pin takes.
+    pub address: u16,
+    /// UUID of the block.
+    pub block: String,
+    /// Pin name: an output, or else an input, of the block.
+    pub pin: String,
+    /// Register layout, ignored for bits.
+    #[serde(default)]
+    pub format: DataFormat,
+    /// Register order of two register formats.
+    #[serde(default)]
+    pub word_order: WordOrder,
+    /// Engineering units per raw count.
+    #[serde(default = "unit_scale")]
+    pub scale: f64,
+    /// Engineering value of a raw `0`.
+    #[serde(default)]
+    pub offset: f64,
+}
+
+fn unit_scale() -> f64 {
+    1.0
+}
+
+impl RegisterMapping {
+    /// Number of addresses the pin takes.
+    fn len(&self) -> u16 {
+        if self.table.is_bits() {
+            1
+        } else {
+            self.format.registers()
+        }
+    }
+
+    fn end(&self) -> u32 {
+        u32::from(self.address) + u32::from(self.len())
+    }
+
+    fn overlaps(&self, address: u16, count: u16) -> bool {
+        u32::from(self.address) < u32::from(address) + u32::from(count)
+            && self.end() > u32::from(address)
+    }
+}
+
+/// Serves selected pins of an engine over Modbus TCP.
+///
+/// Reads return the pins' current values. Writes to holding registers
+/// and coils write the pin, so a supervisory system can command block
+/// inputs. Addresses no mapping covers answer the illegal data address
+/// exception.
+///
+/// # Examples
+///
+/// ```no_run
+/// use logic_mesh::base::engine::Engine;
+/// use logic_mesh::modbus::{ModbusServer, RegisterMapping};
+/// use logic_mesh::single_threaded::SingleThreadedEngine;
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut engine = SingleThreadedEngine::new();
+/// let mappings: Vec<RegisterMapping> = serde_json::from_str(
+///     r#"[{"table": "holding", "address": 0, "block": "...", "pin": "out",
+///          "format": "f32"}]"#,
+/// )?;
+/// let server = ModbusServer::new(&mut engine, mappings)?;
+///
+/// let listener = tokio::net::TcpListener::bind("0.0.0.0:502").await?;
+/// tokio::spawn(server.serve(listener));
+/// engine.run().await;
+/// # Ok(())
+/// # }
+/// ```
+pub struct ModbusServer {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    id: Uuid,
+    channel: Mutex<(mpsc::Sender<Messages>, mpsc::Receiver<Messages>)>,
+    mappings: Vec<(Uuid, RegisterMapping)>,
+}
+
+impl ModbusServer {
+    /// Attaches a server to `engine`. Call before the engine runs: the
+    /// server's reply channel is registered with it here.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ModbusError::InvalidMapping`] if a block id is not a
+    /// UUID or two mappings share an address.
+    pub fn new<E: Engine<Channel = mpsc::Sender<Messages>>>(
+        engine: &mut E,
+        mappings: Vec<RegisterMapping>,
+    ) -> Result<Self, ModbusError> {
+        let mut parsed: Vec<(Uuid, RegisterMapping)> = Vec::with_capacity(mappings.len());
+        for mapping in mappings {
+            let block = Uuid::parse_str(&mapping.block).map_err(|err| {
+                ModbusError::InvalidMapping(format!("invalid block '{}': {err}", mapping.block))
+            })?;
+            if mapping.end() > 0x1_0000 {
+                return Err(ModbusError::InvalidMapping(format!(
+                    "{} {} does not fit the {} table",
+                    mapping.block, mapping.pin, mapping.table
+                )));
+            }
+            if let Some((_, other)) = parsed.iter().find(|(_, other)| {
+                other.table == mapping.table && other.overlaps(mapping.address, mapping.len())
+            }) {
+                return Err(ModbusError::InvalidMapping(format!(
+                    "{} {} and {} {} share {} address {}",
+                    other.block,
+                    other.pin,
+                    mapping.block,
+                    mapping.pin,
+                    mapping.table,
+                    mapping.address
+                )));
+            }
+            parsed.push((block, mapping));
+        }
+
+        let id = Uuid::new_v4();
+        let (reply_sender, replies) = mpsc::channel(32);
+        let sender = engine.create_message_channel(id, reply_sender);
+
+        Ok(Self {
+            shared: Arc::new(Shared {
+                id,
+                channel: Mutex::new((sender, replies)),
+                mappings: parsed,
+            }),
+        })
+    }
+
+    /// Serves clients on `listener` until the task is dropped.
+    pub async fn serve(self, listener: TcpListener) -> std::io::Result<()> {
+        loop {
+            let (stream, peer) = listener.accept().await?;
+            let shared = self.shared.clone();
+            tokio::spawn(async move {
+                if let Err(err) = shared.session(stream).await {
+                    log::debug!("Modbus client {peer} disconnected: {err}");
+                }
+            });
+        }
+    }
+}
+
+impl Shared {
+    async fn session(&self, mut stream: TcpStream) -> std::io::Result<()> {
+        stream.set_nodelay(true)?;
+        loop {
+            let frame = match codec::read_frame(&mut stream).await {
+                Ok(frame) => frame,
+                Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(()),
+                Err(err) => return Err(err),
+            };
+
+            let pdu = match Request::decode(&frame.pdu) {
+                Ok(request) => match self.handle(&request).await {
+                    Ok(response) => response.encode(&request),
+                    Err(exception) => codec::encode_exception(request.function(), exception),
+                },
+                Err(exception) => {
+                    codec::encode_exception(frame.pdu.first().copied().unwrap_or(0), exception)
+                }
+            };
+            codec::write_frame(&mut stream, &Frame { pdu, ..frame }).await?;
+        }
+    }
+
+    async fn handle(&self, request: &Request) -> Result<Response, Exception> {
+        match request {
+            Request::Read {
+                table,
+                address,
+                count,
+            } => self.read(*table, *address, *count).await,
+            Request::WriteSingleCoil { address, value } => {
+                self.write(Table::Coil, *address, Raw::Bits(&[*value]))
+                    .await?;
+                Ok(Response::Written {
+                    address: *address,
+                    value: if *value { 0xFF00 } else { 0 },
+                })
+            }
+            Request::WriteSingleRegister { address, value } => {
+                self.write(Table::Holding, *address, Raw::Registers(&[*value]))
+                    .await?;
+                Ok(Response::Written {
+                    address: *address,
+                    value: *value,
+                })
+            }
+            Request::WriteMultipleCoils { address, values } => {
+                self.write(Table::Coil, *address, Raw::Bits(values)).await?;
+                Ok(Response::Written {
+                    address: *address,
+                    value: values.len() as u16,
+                })
+            }
+            Request::WriteMultipleRegisters { address, values } => {
+                self.write(Table::Holding, *address, Raw::Registers(values))
+                    .await?;
+                Ok(Response::Written {
+                    address: *address,
+                    value: values.len() as u16,
+                })
+            }
+        }
+    }
+
+    /// The mappings covering a range, or the illegal data address
+    /// exception if an address in it is not covered.
+    fn covering(
+        &self,
+        table: Table,
+        address: u16,
+        count: u16,
+    ) -> Result<Vec<&(Uuid, RegisterMapping)>, Exception> {
+        let mappings: Vec<_> = self
+            .mappings
+            .iter()
+            .filter(|(_, mapping)| mapping.table == table && mapping.overlaps(address, count))
+            .collect();
+        let covered: u32 = mappings
+            .iter()
+            .map(|(_, mapping)| {
+                mapping.end().min(u32::from(address) + u32::from(count))
+                    - u32::from(mapping.address.max(address))
+            })
+            .sum();
+        if covered == u32::from(count) {
+            Ok(mappings)
+        } else {
+            Err(Exception::IllegalDataAddress)
+        }
+    }
+
+    async fn read(&self, table: Table, address: u16, count: u16) -> Result<Response, Exception> {
+        let mappings = self.covering(table, address, count)?;
+
+        let mut blocks = BTreeMap::new();
+        for (block, _) in &mappings {
+            if !blocks.contains_key(block) {
+                blocks.insert(*block, self.inspect(*block).await?);
+            }
+        }
+        let value_of = |block: &Uuid, pin: &str| {
+            let def = &blocks[block];
+            def.outputs
+                .get(pin)
+                .map(|output| &output.val)
+                .or_else(|| def.inputs.get(pin).map(|input| &input.val))
+                .ok_or(Exception::ServerDeviceFailure)
+        };
+
+        if table.is_bits() {
+            let mut bits = vec![false; count as usize];
+            for (block, mapping) in mappings {
+                bits[(mapping.address - address) as usize] = match value_of(block, &mapping.pin)? {
+                    Value::Bool(value) => value.value,
+                    Value::Number(value) => value.value != 0.0,
+                    _ => false,
+                };
+            }
+            return Ok(Response::Bits(bits));
+        }
+
+        let mut registers = vec![0u16; count as usize];
+        for (block, mapping) in mappings {
+            let value = match value_of(block, &mapping.pin)? {
+                Value::Number(value) => value.value,
+                Value::Bool(value) => f64::from(u8::from(value.value)),
+                _ => 0.0,
+            };
+            let raw = (value - mapping.offset) / mapping.scale;
+            for (index, register) in mapping
+                .format
+                .encode(raw, mapping.word_order)
+                .into_iter()
+                .enumerate()
+            {
+                let at = u32::from(mapping.address) + index as u32;
+                if let Some(slot) = at
+                    .checked_sub(u32::from(address))
+                    .and_then(|offset| registers.get_mut(offset as usize))
+                {
+                    *slot = register;
+                }
+            }
+        }
+        Ok(Response::Registers(registers))
+    }
+
+    async fn write(&self, table: Table, address: u16, raw: Raw<'_>) -> Result<(), Exception> {
+        let count = raw.len() as u16;
+        let mappings = self.covering(table, address, count)?;
+        // Half of a two register value cannot be written.
+        if mappings.iter().any(|(_, mapping)| {
+            mapping.address < address || mapping.end() > u32::from(address) + u32::from(count)
+        }) {
+            return Err(Exception::IllegalDataAddress);
+        }
+
+        for (block, mapping) in mappings {
+            let offset = (mapping.address - address) as usize;
+            let value = match raw {
+                Raw::Bits(bits) => Value::make_bool(bits[offset]),
+                Raw::Registers(registers) => {
+                    let registers = &registers[offset..offset + mapping.len() as usize];
+                    let raw = mapping.format.decode(registers, mapping.word_order);
+                    Number::make(raw * mapping.scale + mapping.offset).into()
+                }
+            };
+            self.write_pin(*block, &mapping.pin, value).await?;
+        }
+        Ok(())
+    }
+
+    async fn inspect(&self, block: Uuid) -> Result<BlockDefinition, Exception> {
+        match self
+            .exchange(EngineMessage::InspectBlockReq(self.id, block))
+            .await
+        {
+            Some(EngineMessage::InspectBlockRes(Ok(def))) => Ok(def),
+            _ => Err(Exception::ServerDeviceFailure),
+        }
+    }
+
+    /// Writes an input of the block, or an output if it has no such
+    /// input.
+    async fn write_pin(&self, block: Uuid, pin: &str, value: Value) -> Result<(), Exception> {
+        let input = EngineMessage::WriteBlockInputReq(self.id, block, pin.into(), value.clone());
+        if let Some(EngineMessage::WriteBlockInputRes(Ok(_))) = self.exchange(input).await {
+            return Ok(());
+        }
+        let output = EngineMessage::WriteBlockOutputReq(self.id, block, pin.into(), value);
+        match self.exchange(output).await {
+            Some(EngineMessage::WriteBlockOutputRes(Ok(_))) => Ok(()),
+            _ => Err(Exception::ServerDeviceFailure),
+        }
+    }
+
+    /// Sends a request and waits for its reply, or [`None`] once the
+    /// engine has stopped.
+    async fn exchange(&self, msg: Messages) -> Option<Messages> {
+        let mut channel = self.channel.lock().await;
+        channel.0.send(msg).await.ok()?;
+        channel.1.recv().await
+    }
+}
+
+/// Written values.
+#[derive(Clone, Copy)]
+enum Raw<'a> {
+    Bits(&'a [bool]),
+    Registers(&'a [u16]),
+}
+
+impl Raw<'_> {
+    fn len(&self) -> usize {
+        match self {
+            Raw::Bits(bits) => bits.len(),
+            Raw::Registers(registers) => registers.len(),
+        }
+    }
+}
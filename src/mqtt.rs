@@ -0,0 +1,418 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! MQTT client, engine bridge and payload encodings.
+//!
+//! Enabled by the `mqtt` feature, which also registers the `mqtt` block
+//! category:
+//!
+//! - `MqttPublish` publishes its `in` input to a topic whenever it
+//!   changes;
+//! - `MqttSubscribe` outputs the values published on a topic filter.
+//!
+//! The blocks share one [`MqttClient`] connection per broker
+//! `host:port`, and fault while the broker cannot be reached.
+//!
+//! [`MqttBridge`] works at the engine level instead: it publishes every
+//! pin change seen on the engine's watch channel, and writes the inputs
+//! commanded on `set` topics. See [`MqttBridge`] for the topic layout.
+//!
+//! Values travel as Hayson JSON or as Zinc, per [`Encoding`]. The client
+//! speaks MQTT 3.1.1 with QoS 0 and 1.
+
+mod bridge;
+mod client;
+mod codec;
+mod payload;
+
+#[cfg(test)]
+mod broker;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+pub use bridge::{BridgeConfig, MqttBridge, TopicNaming};
+pub use client::{DEFAULT_PORT, DEFAULT_TIMEOUT, MqttClient, MqttOptions};
+pub use payload::Encoding;
+
+/// Delivery guarantee of a message.
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(try_from = "u8", into = "u8")]
+pub enum QoS {
+    /// Delivered at most once, without acknowledgement.
+    #[default]
+    AtMostOnce,
+    /// Delivered at least once, acknowledged by the receiver.
+    AtLeastOnce,
+}
+
+impl QoS {
+    /// The QoS level, `0` or `1`.
+    pub fn level(self) -> u8 {
+        match self {
+            QoS::AtMostOnce => 0,
+            QoS::AtLeastOnce => 1,
+        }
+    }
+}
+
+impl TryFrom<u8> for QoS {
+    type Error = String;
+
+    fn try_from(level: u8) -> Result<Self, Self::Error> {
+        match level {
+            0 => Ok(QoS::AtMostOnce),
+            1 => Ok(QoS::AtLeastOnce),
+            level => Err(format!("unsupported QoS {level}")),
+        }
+    }
+}
+
+impl From<QoS> for u8 {
+    fn from(qos: QoS) -> Self {
+        qos.level()
+    }
+}
+
+/// A message published to, or received from, a broker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    /// Topic name, without wildcards.
+    pub topic: String,
+    /// Payload bytes.
+    pub payload: Vec<u8>,
+    /// Delivery guarantee.
+    pub qos: QoS,
+    /// Whether the broker keeps the message as the topic's last value,
+    /// sent to every new subscriber. Received messages are flagged when
+    /// delivered from that store rather than published live.
+    pub retain: bool,
+}
+
+impl Message {
+    /// A QoS 0 message, not retained.
+    pub fn new(topic: impl Into<String>, payload: impl Into<Vec<u8>>) -> Self {
+        Self {
+            topic: topic.into(),
+            payload: payload.into(),
+            qos: QoS::AtMostOnce,
+            retain: false,
+        }
+    }
+}
+
+/// Whether `topic` matches the subscription `filter`, where `+` matches
+/// one level and a trailing `#` any number of levels.
+///
+/// # Examples
+///
+/// ```
+/// use logic_mesh::mqtt::topic_matches;
+///
+/// assert!(topic_matches("site/+/temp", "site/ahu1/temp"));
+/// assert!(topic_matches("site/#", "site/ahu1/temp"));
+/// assert!(!topic_matches("site/+", "site/ahu1/temp"));
+/// ```
+pub fn topic_matches(filter: &str, topic: &str) -> bool {
+    // Wildcards do not match topics reserved by the broker.
+    if topic.starts_with('$') && !filter.starts_with('$') {
+        return false;
+    }
+    let mut topic_levels = topic.split('/');
+    for level in filter.split('/') {
+        match (level, topic_levels.next()) {
+            ("#", _) => return true,
+            ("+", Some(_)) => {}
+            (level, Some(topic_level)) if level == topic_level => {}
+            _ => return false,
+        }
+    }
+    topic_levels.next().is_none()
+}
+
+/// Whether `filter` is a valid subscription filter.
+fn is_valid_filter(filter: &str) -> bool {
+    let levels: Vec<&str> = filter.split('/').collect();
+    !filter.is_empty()
+        && levels
+            .iter()
+            .enumerate()
+            .all(|(index, level)| match *level {
+                "#" => index == levels.len() - 1,
+                "+" => true,
+                level => !level.contains(['+', '#']),
+            })
+}
+
+/// Whether `topic` is a valid topic name to publish to.
+fn is_valid_topic(topic: &str) -> bool {
+    !topic.is_empty() && !topic.contains(['+', '#'])
+}
+
+/// Errors reported by the MQTT client and bridge.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum MqttError {
+    /// Connecting, reading or writing the socket failed.
+    #[error("MQTT I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    /// The broker did not answer in time.
+    #[error("MQTT request timed out")]
+    Timeout,
+    /// The broker refused the connection.
+    #[error("MQTT connection refused: {0}")]
+    Refused(&'static str),
+    /// The connection to the broker was closed.
+    #[error("MQTT connection lost")]
+    ConnectionLost,
+    /// The broker sent a packet that is not valid.
+    #[error("Invalid MQTT packet: {0}")]
+    Protocol(String),
+    /// The broker refused a subscription.
+    #[error("MQTT subscription to '{0}' refused")]
+    SubscriptionRefused(String),
+    /// A topic name or filter is not valid.
+    #[error("Invalid MQTT topic '{0}'")]
+    InvalidTopic(String),
+    /// A payload cannot be encoded or decoded.
+    #[error("Invalid MQTT payload: {0}")]
+    Payload(String),
+}
+
+impl MqttError {
+    /// Whether the connection is unusable after this error, so the
+    /// next request should reconnect.
+    pub fn is_connection_lost(&self) -> bool {
+        matches!(
+            self,
+            MqttError::Io(_)
+                | MqttError::Timeout
+                | MqttError::ConnectionLost
+                | MqttError::Protocol(_)
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    use libhaystack::val::{Dict, Value};
+    use tokio::sync::mpsc::UnboundedReceiver;
+
+    use super::broker::Broker;
+    use super::{
+        BridgeConfig, Encoding, Message, MqttBridge, MqttClient, MqttError, MqttOptions, QoS,
+        TopicNaming,
+    };
+    use crate::base::block::{Block, BlockProps};
+    use crate::base::engine::{Engine, messages::EngineMessage};
+    use crate::base::program::{Program, ProgramBlock};
+    use crate::blocks::mqtt::{MqttPublish, MqttSubscribe};
+    use crate::single_threaded::SingleThreadedEngine;
+
+    fn options(port: u16) -> MqttOptions {
+        let mut options = MqttOptions::new("127.0.0.1");
+        options.port = port;
+        options
+    }
+
+    /// The next message on `topic`, skipping the others.
+    async fn next_on(messages: &mut UnboundedReceiver<Message>, topic: &str) -> Message {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                let message = messages.recv().await.expect("the connection is open");
+                if message.topic == topic {
+                    return message;
+                }
+            }
+        })
+        .await
+        .unwrap_or_else(|_| panic!("nothing published on '{topic}'"))
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn clients_exchange_live_and_retained_messages() {
+        let broker = Broker::start().await;
+        let publisher = MqttClient::connect(options(broker.port())).await.unwrap();
+        let subscriber = MqttClient::connect(options(broker.port())).await.unwrap();
+
+        let mut retained = Message::new("site/ahu1/temp", "21.5");
+        retained.qos = QoS::AtLeastOnce;
+        retained.retain = true;
+        publisher.publish(retained).await.unwrap();
+
+        let mut temps = subscriber
+            .subscribe("site/+/temp", QoS::AtLeastOnce)
+            .await
+            .unwrap();
+        let first = next_on(&mut temps, "site/ahu1/temp").await;
+        assert_eq!(first.payload, b"21.5");
+        assert!(first.retain);
+
+        publisher
+            .publish(Message::new("site/ahu2/temp", "19"))
+            .await
+            .unwrap();
+        publisher
+            .publish(Message::new("site/ahu2/humidity", "40"))
+            .await
+            .unwrap();
+        let live = temps.recv().await.unwrap();
+        assert_eq!(live.topic, "site/ahu2/temp");
+        assert!(!live.retain);
+
+        assert!(matches!(
+            subscriber.subscribe("site/#/temp", QoS::AtMostOnce).await,
+            Err(MqttError::InvalidTopic(_))
+        ));
+        assert!(matches!(
+            publisher.publish(Message::new("site/+", "1")).await,
+            Err(MqttError::InvalidTopic(_))
+        ));
+
+        broker.stop();
+        assert_eq!(temps.recv().await, None);
+        assert!(matches!(
+            publisher
+                .publish(Message::new("site/ahu1/temp", "22"))
+                .await,
+            Err(MqttError::ConnectionLost)
+        ));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn bridge_publishes_pins_and_writes_commands() {
+        let broker = Broker::start().await;
+        let mut engine = SingleThreadedEngine::new();
+        let mut program = Program::default();
+        program.blocks.insert(
+            "00000000-0000-0000-0000-0000000000a1".into(),
+            ProgramBlock {
+                name: "Abs".into(),
+                lib: "core".into(),
+                label: Some("zone/temp".into()),
+                ..Default::default()
+            },
+        );
+        program.blocks.insert(
+            "00000000-0000-0000-0000-0000000000a2".into(),
+            ProgramBlock {
+                name: "Not".into(),
+                lib: "core".into(),
+                ..Default::default()
+            },
+        );
+        engine.schedule_program_blocks(&program).unwrap();
+
+        let config = BridgeConfig {
+            prefix: "site/ahu1".into(),
+            naming: TopicNaming::Label,
+            ..Default::default()
+        };
+        let bridge = MqttBridge::new(&mut engine, config).unwrap();
+        let bridged = tokio::spawn(bridge.run(options(broker.port())));
+
+        let (reply_sender, _replies) = tokio::sync::mpsc::channel(8);
+        let sender = engine.create_message_channel(uuid::Uuid::new_v4(), reply_sender);
+
+        let client = async {
+            let client = MqttClient::connect(options(broker.port())).await.unwrap();
+            let mut messages = client
+                .subscribe("site/ahu1/#", QoS::AtMostOnce)
+                .await
+                .unwrap();
+
+            let status = next_on(&mut messages, "site/ahu1/status").await;
+            assert_eq!(status.payload, b"online");
+            // Every block's status, the unlabelled one named by id.
+            let mut statuses = HashMap::new();
+            while statuses.len() < 2 {
+                let message = messages.recv().await.unwrap();
+                if message.topic.ends_with("/$status") {
+                    statuses.insert(message.topic, message.payload);
+                }
+            }
+            let mut running = Dict::new();
+            running.insert("state".into(), "running".into());
+            assert_eq!(
+                Encoding::Json
+                    .decode(&statuses["site/ahu1/zone_temp/$status"])
+                    .unwrap(),
+                Value::from(running)
+            );
+            assert!(
+                statuses.contains_key("site/ahu1/00000000-0000-0000-0000-0000000000a2/$status")
+            );
+
+            client
+                .publish(Message::new("site/ahu1/zone_temp/in/set", "-7"))
+                .await
+                .unwrap();
+            loop {
+                let input = next_on(&mut messages, "site/ahu1/zone_temp/in").await;
+                if Encoding::Json.decode(&input.payload).unwrap() == Value::from(-7) {
+                    break;
+                }
+            }
+
+            sender.send(EngineMessage::Shutdown).await.unwrap();
+            loop {
+                let status = next_on(&mut messages, "site/ahu1/status").await;
+                if status.payload == b"offline" {
+                    break;
+                }
+            }
+        };
+
+        // Dropping the engine closes the watch channel, ending the bridge.
+        let engine = async move { engine.run().await };
+        tokio::join!(engine, client);
+        bridged.await.unwrap();
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn blocks_publish_subscribe_and_fault_without_a_broker() {
+        let broker = Broker::start().await;
+        let port = Value::from(broker.port() as i32);
+
+        let mut publish = MqttPublish::new();
+        publish.host.val = Some("127.0.0.1".into());
+        publish.port.val = Some(port.clone());
+        publish.topic.val = Some("plant/flow".into());
+        publish.retain.val = Some(true.into());
+        publish.input.val = Some(12.5.into());
+        publish.execute().await;
+        assert!(!publish.state().is_fault(), "{:?}", publish.state());
+        assert_eq!(publish.out.value, Value::from(12.5));
+
+        let mut subscribe = MqttSubscribe::new();
+        subscribe.host.val = Some("127.0.0.1".into());
+        subscribe.port.val = Some(port);
+        subscribe.topic.val = Some("plant/#".into());
+        subscribe.execute().await;
+        assert!(!subscribe.state().is_fault(), "{:?}", subscribe.state());
+        assert_eq!(subscribe.out.value, Value::from(12.5));
+
+        broker.stop();
+        subscribe.execute().await;
+        assert!(
+            subscribe
+                .state()
+                .fault_reason()
+                .is_some_and(|reason| reason.contains("connection lost")),
+            "{:?}",
+            subscribe.state()
+        );
+
+        let closed = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().port()
+        };
+        publish.port.val = Some(Value::from(closed as i32));
+        publish.execute().await;
+        assert!(publish.state().is_fault());
+    }
+}
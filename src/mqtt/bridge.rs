@@ -0,0 +1,494 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Engine-level bridge between block pins and MQTT topics.
+
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+use libhaystack::val::{Dict, Value};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use super::{Encoding, Message, MqttClient, MqttError, MqttOptions, QoS, is_valid_topic};
+use crate::base::engine::{
+    Engine,
+    messages::{ChangeSource, EngineMessage, WatchMessage},
+};
+use crate::tokio_impl::engine::single_threaded::Messages;
+
+/// Wait before reconnecting to a broker that dropped the connection.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// What names a block in its topics.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TopicNaming {
+    /// The block's UUID.
+    #[default]
+    Id,
+    /// The block's label, with `/`, `+` and `#` replaced by `_`. Blocks
+    /// without a label, or sharing theirs with another block, fall back
+    /// to their UUID.
+    Label,
+}
+
+/// How the bridge publishes and takes commands.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BridgeConfig {
+    /// Topic prefix, the first levels of every topic.
+    #[serde(default = "default_prefix")]
+    pub prefix: String,
+    /// What names a block in its topics.
+    #[serde(default)]
+    pub naming: TopicNaming,
+    /// Payload encoding of values.
+    #[serde(default)]
+    pub encoding: Encoding,
+    /// Whether values are published retained, so new subscribers get
+    /// the last one at once.
+    #[serde(default = "default_true")]
+    pub retain: bool,
+    /// Delivery guarantee of published values and of commands.
+    #[serde(default)]
+    pub qos: QoS,
+    /// Whether inputs can be written on `set` topics.
+    #[serde(default = "default_true")]
+    pub commands: bool,
+}
+
+fn default_prefix() -> String {
+    "logic-mesh".into()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for BridgeConfig {
+    fn default() -> Self {
+        Self {
+            prefix: default_prefix(),
+            naming: TopicNaming::default(),
+            encoding: Encoding::default(),
+            retain: true,
+            qos: QoS::default(),
+            commands: true,
+        }
+    }
+}
+
+/// Publishes the pins of an engine's blocks to an MQTT broker, and
+/// writes the inputs commanded by other clients.
+///
+/// | Topic                         | Payload                               |
+/// |-------------------------------|---------------------------------------|
+/// | `<prefix>/status`             | `online`, or `offline` as the will    |
+/// | `<prefix>/<block>/<pin>`      | the pin's value                       |
+/// | `<prefix>/<block>/$status`    | Dict of `state` and, on fault, `reason`|
+/// | `<prefix>/<block>/<pin>/set`  | subscribed: a value to write          |
+///
+/// `<block>` is the block's id or label, per [`TopicNaming`], and values
+/// are encoded per [`Encoding`]. Every pin is published once connected,
+/// then on each change seen on the watch channel; `state` is the block
+/// state (`running`, `fault`, `disabled` or `terminated`). Retained
+/// messages on `set` topics are ignored, so a stale command is not
+/// replayed on reconnecting.
+///
+/// When the broker drops the connection the bridge reconnects every 5
+/// seconds, republishing the last value of every pin.
+///
+/// # Examples
+///
+/// ```no_run
+/// use logic_mesh::base::engine::Engine;
+/// use logic_mesh::mqtt::{BridgeConfig, MqttBridge, MqttOptions};
+/// use logic_mesh::single_threaded::SingleThreadedEngine;
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut engine = SingleThreadedEngine::new();
+/// let config: BridgeConfig =
+///     serde_json::from_str(r#"{"prefix": "site/ahu1", "naming": "label"}"#)?;
+/// let bridge = MqttBridge::new(&mut engine, config)?;
+///
+/// tokio::spawn(bridge.run(MqttOptions::new("broker.local")));
+/// engine.run().await;
+/// # Ok(())
+/// # }
+/// ```
+pub struct MqttBridge {
+    config: BridgeConfig,
+    engine: EngineLink,
+    /// Last known pins and status of every block seen.
+    blocks: HashMap<Uuid, BlockTopics>,
+}
+
+/// The bridge's channel to the engine.
+struct EngineLink {
+    id: Uuid,
+    sender: mpsc::Sender<Messages>,
+    replies: mpsc::Receiver<Messages>,
+}
+
+struct BlockTopics {
+    /// The block's topic level.
+    segment: String,
+    pins: BTreeMap<String, Value>,
+    status: Status,
+}
+
+/// A block state label and fault reason.
+#[derive(Debug, Clone, PartialEq)]
+struct Status {
+    state: String,
+    reason: Option<String>,
+}
+
+impl Status {
+    fn value(&self) -> Value {
+        let mut dict = Dict::new();
+        dict.insert("state".into(), self.state.as_str().into());
+        if let Some(reason) = &self.reason {
+            dict.insert("reason".into(), reason.as_str().into());
+        }
+        dict.into()
+    }
+}
+
+impl MqttBridge {
+    /// Attaches a bridge to `engine`. Call before the engine runs: the
+    /// bridge's reply channel is registered with it here.
+    ///
+    /// # Errors
+    /// [`MqttError::InvalidTopic`] if the prefix holds a wildcard.
+    pub fn new<E: Engine<Channel = mpsc::Sender<Messages>>>(
+        engine: &mut E,
+        config: BridgeConfig,
+    ) -> Result<Self, MqttError> {
+        if !is_valid_topic(&config.prefix) {
+            return Err(MqttError::InvalidTopic(config.prefix));
+        }
+
+        let id = Uuid::new_v4();
+        let (reply_sender, replies) = mpsc::channel(32);
+        let sender = engine.create_message_channel(id, reply_sender);
+
+        Ok(Self {
+            config,
+            engine: EngineLink {
+                id,
+                sender,
+                replies,
+            },
+            blocks: HashMap::new(),
+        })
+    }
+
+    /// Bridges the engine to the broker until the engine is dropped,
+    /// reconnecting whenever the connection is lost. The bridge then
+    /// publishes `offline` and disconnects.
+    pub async fn run(mut self, options: MqttOptions) {
+        let (watch_sender, mut watch) = mpsc::unbounded_channel();
+        let subscribe = EngineMessage::WatchBlockSubReq(self.engine.id, watch_sender);
+        if !matches!(
+            self.engine.exchange(subscribe).await,
+            Some(EngineMessage::WatchBlockSubRes(Ok(_)))
+        ) {
+            return;
+        }
+        self.refresh().await;
+
+        loop {
+            match self.session(&options, &mut watch).await {
+                Ok(()) => return,
+                Err(err) => log::warn!("MQTT bridge: {err}, reconnecting"),
+            }
+
+            // Keep up with the engine until it is time to retry.
+            let retry = tokio::time::sleep(RECONNECT_DELAY);
+            tokio::pin!(retry);
+            loop {
+                tokio::select! {
+                    _ = &mut retry => break,
+                    change = watch.recv() => match change {
+                        Some(change) => {
+                            self.update(change).await;
+                        }
+                        None => return,
+                    },
+                }
+            }
+        }
+    }
+
+    /// One connection to the broker, until the engine is dropped or the
+    /// connection fails.
+    async fn session(
+        &mut self,
+        options: &MqttOptions,
+        watch: &mut mpsc::UnboundedReceiver<WatchMessage>,
+    ) -> Result<(), MqttError> {
+        let status_topic = format!("{}/status", self.config.prefix);
+        let mut options = options.clone();
+        options.will = Some(Message {
+            topic: status_topic.clone(),
+            payload: b"offline".to_vec(),
+            qos: self.config.qos,
+            retain: true,
+        });
+        let client = MqttClient::connect(options).await?;
+
+        let mut commands = match self.config.commands {
+            true => {
+                let filter = format!("{}/+/+/set", self.config.prefix);
+                Some(client.subscribe(&filter, self.config.qos).await?)
+            }
+            false => None,
+        };
+
+        let mut online = Message::new(status_topic.clone(), "online");
+        online.qos = self.config.qos;
+        online.retain = true;
+        client.publish(online).await?;
+
+        let everything: Vec<Message> = self
+            .blocks
+            .values()
+            .flat_map(|block| {
+                block
+                    .pins
+                    .iter()
+                    .map(|(pin, value)| (&block.segment, pin.as_str(), value.clone()))
+                    .chain(std::iter::once((
+                        &block.segment,
+                        "$status",
+                        block.status.value(),
+                    )))
+            })
+            .filter_map(|(segment, pin, value)| self.message(segment, pin, &value))
+            .collect();
+        for message in everything {
+            client.publish(message).await?;
+        }
+
+        loop {
+            tokio::select! {
+                change = watch.recv() => match change {
+                    Some(change) => {
+                        for message in self.update(change).await {
+                            client.publish(message).await?;
+                        }
+                    }
+                    None => {
+                        let mut offline = Message::new(status_topic, "offline");
+                        offline.qos = self.config.qos;
+                        offline.retain = true;
+                        let _ = client.publish(offline).await;
+                        client.disconnect().await;
+                        return Ok(());
+                    }
+                },
+                command = async {
+                    match &mut commands {
+                        Some(commands) => commands.recv().await,
+                        None => std::future::pending().await,
+                    }
+                } => match command {
+                    Some(command) => self.command(command).await,
+                    None => return Err(MqttError::ConnectionLost),
+                },
+            }
+        }
+    }
+
+    /// Records the changes of a block, returning the messages that
+    /// publish them.
+    async fn update(&mut self, change: WatchMessage) -> Vec<Message> {
+        if !self.blocks.contains_key(&change.block_id) {
+            self.refresh().await;
+        }
+        let config = &self.config;
+        let block = self
+            .blocks
+            .entry(change.block_id)
+            .or_insert_with(|| BlockTopics {
+                segment: change.block_id.to_string(),
+                pins: BTreeMap::new(),
+                status: Status {
+                    state: String::new(),
+                    reason: None,
+                },
+            });
+
+        let mut messages = Vec::new();
+        for source in change.changes.into_values() {
+            let (ChangeSource::Input(pin, value) | ChangeSource::Output(pin, value)) = source;
+            messages.extend(message(config, &block.segment, &pin, &value));
+            block.pins.insert(pin, value);
+        }
+        let status = Status {
+            state: change.state.label().into(),
+            reason: change.state.fault_reason().map(Into::into),
+        };
+        if status != block.status {
+            messages.extend(message(config, &block.segment, "$status", &status.value()));
+            block.status = status;
+        }
+        messages
+    }
+
+    /// Writes the input commanded by a message on a `set` topic.
+    async fn command(&mut self, command: Message) {
+        if command.retain {
+            return;
+        }
+        let Some((segment, pin)) = command
+            .topic
+            .strip_prefix(&format!("{}/", self.config.prefix))
+            .and_then(|topic| topic.strip_suffix("/set"))
+            .and_then(|topic| topic.split_once('/'))
+        else {
+            return;
+        };
+        let value = match self.config.encoding.decode(&command.payload) {
+            Ok(value) => value,
+            Err(err) => {
+                log::warn!("MQTT bridge: command on '{}': {err}", command.topic);
+                return;
+            }
+        };
+
+        let mut block = self.find(segment);
+        if block.is_none() {
+            self.refresh().await;
+            block = self.find(segment);
+        }
+        let Some(block) = block else {
+            log::warn!("MQTT bridge: no block '{segment}'");
+            return;
+        };
+
+        let write = EngineMessage::WriteBlockInputReq(self.engine.id, block, pin.into(), value);
+        match self.engine.exchange(write).await {
+            Some(EngineMessage::WriteBlockInputRes(Ok(_))) => {}
+            Some(EngineMessage::WriteBlockInputRes(Err(err))) => {
+                log::warn!("MQTT bridge: writing {segment}.{pin}: {err}")
+            }
+            _ => {}
+        }
+    }
+
+    /// The block a topic level names.
+    fn find(&self, segment: &str) -> Option<Uuid> {
+        self.blocks
+            .iter()
+            .find(|(_, block)| block.segment == segment)
+            .map(|(id, _)| *id)
+            .or_else(|| Uuid::parse_str(segment).ok())
+    }
+
+    /// Reads the engine's blocks: their topic levels, and the pins and
+    /// status of blocks not seen before.
+    async fn refresh(&mut self) {
+        let programs = match self
+            .engine
+            .exchange(EngineMessage::ListProgramsReq(self.engine.id))
+            .await
+        {
+            Some(EngineMessage::ListProgramsRes(programs)) => programs,
+            _ => return,
+        };
+
+        let mut labels: Vec<(Uuid, Option<String>)> = Vec::new();
+        for summary in programs {
+            let request = EngineMessage::GetProgramReq(self.engine.id, summary.id);
+            if let Some(EngineMessage::GetProgramRes(Ok(program))) =
+                self.engine.exchange(request).await
+            {
+                labels.extend(
+                    program
+                        .blocks
+                        .into_iter()
+                        .filter_map(|(id, block)| Some((Uuid::parse_str(&id).ok()?, block.label))),
+                );
+            }
+        }
+
+        for (id, label) in &labels {
+            let segment = match (self.config.naming, label) {
+                (TopicNaming::Label, Some(label))
+                    if !label.is_empty()
+                        && labels
+                            .iter()
+                            .filter(|(_, other)| other.as_ref() == Some(label))
+                            .count()
+                            == 1 =>
+                {
+                    label.replace(['/', '+', '#'], "_")
+                }
+                _ => id.to_string(),
+            };
+
+            if let Some(block) = self.blocks.get_mut(id) {
+                block.segment = segment;
+                continue;
+            }
+            let request = EngineMessage::InspectBlockReq(self.engine.id, *id);
+            let Some(EngineMessage::InspectBlockRes(Ok(def))) = self.engine.exchange(request).await
+            else {
+                continue;
+            };
+            let pins = def
+                .inputs
+                .into_iter()
+                .map(|(pin, input)| (pin, input.val))
+                .chain(
+                    def.outputs
+                        .into_iter()
+                        .map(|(pin, output)| (pin, output.val)),
+                )
+                .collect();
+            self.blocks.insert(
+                *id,
+                BlockTopics {
+                    segment,
+                    pins,
+                    status: Status {
+                        state: def.state,
+                        reason: def.fault_reason,
+                    },
+                },
+            );
+        }
+    }
+
+    fn message(&self, segment: &str, pin: &str, value: &Value) -> Option<Message> {
+        message(&self.config, segment, pin, value)
+    }
+}
+
+/// The message publishing `value` on a pin's topic.
+fn message(config: &BridgeConfig, segment: &str, pin: &str, value: &Value) -> Option<Message> {
+    let topic = format!("{}/{segment}/{pin}", config.prefix);
+    match config.encoding.encode(value) {
+        Ok(payload) => Some(Message {
+            topic,
+            payload,
+            qos: config.qos,
+            retain: config.retain,
+        }),
+        Err(err) => {
+            log::debug!("MQTT bridge: cannot encode '{topic}': {err}");
+            None
+        }
+    }
+}
+
+impl EngineLink {
+    async fn exchange(&mut self, msg: Messages) -> Option<Messages> {
+        self.sender.send(msg).await.ok()?;
+        self.replies.recv().await
+    }
+}
@@ -0,0 +1,197 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! A minimal in-process broker for the tests: QoS 0 and 1, retained
+//! messages and wills, without sessions or authentication.
+
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio::task::{JoinHandle, JoinSet};
+
+use super::codec::Packet;
+use super::{Message, QoS, topic_matches};
+
+/// A broker listening on a loopback port until stopped.
+pub(super) struct Broker {
+    addr: SocketAddr,
+    task: JoinHandle<()>,
+}
+
+#[derive(Default)]
+struct State {
+    next_session: usize,
+    sessions: HashMap<usize, Session>,
+    retained: BTreeMap<String, Message>,
+}
+
+struct Session {
+    sender: mpsc::UnboundedSender<Message>,
+    filters: Vec<(String, QoS)>,
+}
+
+impl Broker {
+    pub(super) async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let state = Arc::new(Mutex::new(State::default()));
+        let task = tokio::spawn(async move {
+            // Sessions are dropped with the broker's task.
+            let mut sessions = JoinSet::new();
+            while let Ok((stream, _)) = listener.accept().await {
+                sessions.spawn(session(stream, state.clone()));
+            }
+        });
+        Self { addr, task }
+    }
+
+    pub(super) fn port(&self) -> u16 {
+        self.addr.port()
+    }
+
+    /// Closes the listener and every connection.
+    pub(super) fn stop(self) {
+        self.task.abort();
+    }
+}
+
+async fn session(mut stream: TcpStream, state: Arc<Mutex<State>>) {
+    let (sender, mut outgoing) = mpsc::unbounded_channel();
+    let id = {
+        let mut state = state.lock().unwrap();
+        state.next_session += 1;
+        let id = state.next_session;
+        state.sessions.insert(
+            id,
+            Session {
+                sender,
+                filters: Vec::new(),
+            },
+        );
+        id
+    };
+
+    let mut will = None;
+    let mut packet_id = 0u16;
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    'session: loop {
+        tokio::select! {
+            read = stream.read(&mut chunk) => match read {
+                Ok(0) | Err(_) => break,
+                Ok(len) => buf.extend_from_slice(&chunk[..len]),
+            },
+            Some(message) = outgoing.recv() => {
+                let packet_id = match message.qos {
+                    QoS::AtMostOnce => None,
+                    QoS::AtLeastOnce => {
+                        packet_id = packet_id.wrapping_add(1).max(1);
+                        Some(packet_id)
+                    }
+                };
+                let packet = Packet::Publish { message, packet_id, dup: false };
+                if stream.write_all(&packet.encode()).await.is_err() {
+                    break;
+                }
+            }
+        }
+
+        while let Ok(Some((packet, len))) = Packet::decode(&buf) {
+            buf.drain(..len);
+            let reply = match packet {
+                Packet::Connect(connect) => {
+                    will = connect.will;
+                    Some(Packet::ConnAck {
+                        session_present: false,
+                        code: 0,
+                    })
+                }
+                Packet::Publish {
+                    message, packet_id, ..
+                } => {
+                    publish(&state, message);
+                    packet_id.map(Packet::PubAck)
+                }
+                Packet::Subscribe { packet_id, filters } => {
+                    let mut state = state.lock().unwrap();
+                    let retained: Vec<Message> = state
+                        .retained
+                        .values()
+                        .filter(|message| {
+                            filters
+                                .iter()
+                                .any(|(filter, _)| topic_matches(filter, &message.topic))
+                        })
+                        .cloned()
+                        .collect();
+                    let session = state.sessions.get_mut(&id).unwrap();
+                    session.filters.extend(filters.iter().cloned());
+                    // Queued behind the acknowledgement, written below.
+                    for message in retained {
+                        let _ = session.sender.send(message);
+                    }
+                    Some(Packet::SubAck {
+                        packet_id,
+                        codes: filters.iter().map(|(_, qos)| qos.level()).collect(),
+                    })
+                }
+                Packet::Unsubscribe { packet_id, filters } => {
+                    let mut state = state.lock().unwrap();
+                    let session = state.sessions.get_mut(&id).unwrap();
+                    session
+                        .filters
+                        .retain(|(filter, _)| !filters.contains(filter));
+                    Some(Packet::UnsubAck(packet_id))
+                }
+                Packet::PingReq => Some(Packet::PingResp),
+                Packet::Disconnect => {
+                    will = None;
+                    break 'session;
+                }
+                _ => None,
+            };
+            if let Some(reply) = reply
+                && stream.write_all(&reply.encode()).await.is_err()
+            {
+                break 'session;
+            }
+        }
+    }
+
+    state.lock().unwrap().sessions.remove(&id);
+    if let Some(will) = will {
+        publish(&state, will);
+    }
+}
+
+/// Stores a retained message and routes it to the subscribers, at the
+/// lower of the published and subscribed QoS.
+fn publish(state: &Mutex<State>, message: Message) {
+    let mut state = state.lock().unwrap();
+    if message.retain {
+        match message.payload.is_empty() {
+            true => state.retained.remove(&message.topic),
+            false => state
+                .retained
+                .insert(message.topic.clone(), message.clone()),
+        };
+    }
+    for session in state.sessions.values() {
+        let qos = session
+            .filters
+            .iter()
+            .filter(|(filter, _)| topic_matches(filter, &message.topic))
+            .map(|(_, qos)| *qos)
+            .max();
+        if let Some(qos) = qos {
+            let _ = session.sender.send(Message {
+                qos: qos.min(message.qos),
+                retain: false,
+                ..message.clone()
+            });
+        }
+    }
+}
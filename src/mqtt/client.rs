@@ -0,0 +1,395 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! MQTT client connection.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::Instant;
+
+use super::codec::{Connect, Packet, refusal};
+use super::{Message, MqttError, QoS, is_valid_filter, is_valid_topic, topic_matches};
+
+/// Standard MQTT port.
+pub const DEFAULT_PORT: u16 = 1883;
+
+/// How long connecting, and each acknowledged request, may take.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Where and how to connect to a broker.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MqttOptions {
+    /// Broker host name or address.
+    pub host: String,
+    /// Broker port.
+    #[serde(default = "default_port")]
+    pub port: u16,
+    /// Client identifier, unique on the broker. Generated when empty.
+    #[serde(default)]
+    pub client_id: String,
+    /// User name, if the broker asks for one.
+    #[serde(default)]
+    pub username: Option<String>,
+    /// Password, if the broker asks for one.
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Seconds between pings while the connection is otherwise idle.
+    #[serde(default = "default_keep_alive")]
+    pub keep_alive: u16,
+    /// Message the broker publishes if the connection drops without a
+    /// disconnect.
+    #[serde(skip)]
+    pub will: Option<Message>,
+}
+
+fn default_port() -> u16 {
+    DEFAULT_PORT
+}
+
+fn default_keep_alive() -> u16 {
+    30
+}
+
+impl MqttOptions {
+    /// Options for a broker at `host` on the standard port.
+    pub fn new(host: impl Into<String>) -> Self {
+        Self {
+            host: host.into(),
+            port: DEFAULT_PORT,
+            client_id: String::new(),
+            username: None,
+            password: None,
+            keep_alive: default_keep_alive(),
+            will: None,
+        }
+    }
+}
+
+/// A connection to an MQTT broker.
+///
+/// The connection is run by a background task; this handle is cheap to
+/// clone, and the connection closes once every handle is dropped or
+/// [`disconnect`](Self::disconnect) is called.
+///
+/// # Examples
+///
+/// ```no_run
+/// use logic_mesh::mqtt::{Message, MqttClient, MqttOptions, QoS};
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() -> Result<(), logic_mesh::mqtt::MqttError> {
+/// let client = MqttClient::connect(MqttOptions::new("broker.local")).await?;
+/// let mut temps = client.subscribe("site/+/temp", QoS::AtMostOnce).await?;
+/// client.publish(Message::new("site/ahu1/temp", "21.5")).await?;
+/// while let Some(message) = temps.recv().await {
+///     println!("{}: {:?}", message.topic, message.payload);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct MqttClient {
+    requests: mpsc::Sender<Request>,
+}
+
+type Reply = oneshot::Sender<Result<(), MqttError>>;
+
+#[derive(Debug)]
+enum Request {
+    Publish(Message, Reply),
+    Subscribe(String, QoS, mpsc::UnboundedSender<Message>, Reply),
+    Disconnect,
+}
+
+impl MqttClient {
+    /// Connects to the broker, waiting for it to accept the connection.
+    ///
+    /// # Errors
+    /// [`MqttError::Refused`] if the broker refuses the connection,
+    /// [`MqttError::Timeout`] if it does not answer in time.
+    pub async fn connect(options: MqttOptions) -> Result<Self, MqttError> {
+        let client_id = match options.client_id.is_empty() {
+            true => format!(
+                "logic-mesh-{}",
+                &uuid::Uuid::new_v4().simple().to_string()[..8]
+            ),
+            false => options.client_id.clone(),
+        };
+        let connect = Packet::Connect(Connect {
+            client_id,
+            keep_alive: options.keep_alive,
+            username: options.username.clone(),
+            password: options.password.clone(),
+            will: options.will.clone(),
+        });
+
+        let handshake = async {
+            let mut stream = TcpStream::connect((options.host.as_str(), options.port)).await?;
+            stream.set_nodelay(true)?;
+            stream.write_all(&connect.encode()).await?;
+            let mut buf = Vec::new();
+            loop {
+                if let Some((packet, len)) = Packet::decode(&buf)? {
+                    buf.drain(..len);
+                    return match packet {
+                        Packet::ConnAck { code, .. } => match refusal(code) {
+                            Some(reason) => Err(MqttError::Refused(reason)),
+                            None => Ok((stream, buf)),
+                        },
+                        other => Err(MqttError::Protocol(format!(
+                            "expected CONNACK, got {other:?}"
+                        ))),
+                    };
+                }
+                if stream.read_buf(&mut buf).await? == 0 {
+                    return Err(MqttError::ConnectionLost);
+                }
+            }
+        };
+        let (stream, buf) = tokio::time::timeout(DEFAULT_TIMEOUT, handshake)
+            .await
+            .map_err(|_| MqttError::Timeout)??;
+
+        let (reader, writer) = stream.into_split();
+        let (requests, receiver) = mpsc::channel(32);
+        let connection = Connection {
+            writer,
+            keep_alive: Duration::from_secs(options.keep_alive.into()),
+            last_sent: Instant::now(),
+            ping_sent: None,
+            next_packet_id: 0,
+            pending: HashMap::new(),
+            routes: Vec::new(),
+        };
+        tokio::spawn(connection.run(reader, buf, receiver));
+        Ok(Self { requests })
+    }
+
+    /// Publishes `message`, waiting for the broker's acknowledgement at
+    /// QoS 1.
+    pub async fn publish(&self, message: Message) -> Result<(), MqttError> {
+        if !is_valid_topic(&message.topic) {
+            return Err(MqttError::InvalidTopic(message.topic));
+        }
+        self.call(|reply| Request::Publish(message, reply)).await
+    }
+
+    /// Subscribes to the topics matching `filter`, receiving their
+    /// messages until the connection closes.
+    pub async fn subscribe(
+        &self,
+        filter: &str,
+        qos: QoS,
+    ) -> Result<mpsc::UnboundedReceiver<Message>, MqttError> {
+        if !is_valid_filter(filter) {
+            return Err(MqttError::InvalidTopic(filter.into()));
+        }
+        let (sender, receiver) = mpsc::unbounded_channel();
+        self.call(|reply| Request::Subscribe(filter.into(), qos, sender, reply))
+            .await?;
+        Ok(receiver)
+    }
+
+    /// Closes the connection cleanly, so the broker does not publish the
+    /// will.
+    pub async fn disconnect(&self) {
+        let _ = self.requests.send(Request::Disconnect).await;
+    }
+
+    /// Whether the connection is still open.
+    pub fn is_connected(&self) -> bool {
+        !self.requests.is_closed()
+    }
+
+    async fn call(&self, request: impl FnOnce(Reply) -> Request) -> Result<(), MqttError> {
+        let (reply, response) = oneshot::channel();
+        self.requests
+            .send(request(reply))
+            .await
+            .map_err(|_| MqttError::ConnectionLost)?;
+        match tokio::time::timeout(DEFAULT_TIMEOUT, response).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err(MqttError::ConnectionLost),
+            Err(_) => Err(MqttError::Timeout),
+        }
+    }
+}
+
+/// The connection's state, owned by its task.
+struct Connection {
+    writer: OwnedWriteHalf,
+    keep_alive: Duration,
+    last_sent: Instant,
+    /// When a ping went unanswered so far.
+    ping_sent: Option<Instant>,
+    next_packet_id: u16,
+    /// Requests waiting for the broker's acknowledgement, by packet id.
+    pending: HashMap<u16, Pending>,
+    /// Subscribers, by filter.
+    routes: Vec<(String, mpsc::UnboundedSender<Message>)>,
+}
+
+enum Pending {
+    Publish(Reply),
+    Subscribe(String, Reply),
+}
+
+impl Connection {
+    /// Runs the connection until it breaks or is closed. Dropping the
+    /// connection fails the pending requests and ends the subscriptions.
+    async fn run(
+        mut self,
+        mut reader: OwnedReadHalf,
+        mut buf: Vec<u8>,
+        mut requests: mpsc::Receiver<Request>,
+    ) {
+        let mut chunk = [0u8; 4096];
+        loop {
+            if let Err(err) = self.incoming(&mut buf).await {
+                log::debug!("MQTT connection closed: {err}");
+                return;
+            }
+
+            let deadline = match self.ping_sent {
+                Some(sent) => sent + self.keep_alive,
+                None => self.last_sent + self.keep_alive,
+            };
+            tokio::select! {
+                request = requests.recv() => match request {
+                    Some(Request::Disconnect) | None => {
+                        let _ = self.send(Packet::Disconnect).await;
+                        return;
+                    }
+                    Some(request) => {
+                        if let Err(err) = self.request(request).await {
+                            log::debug!("MQTT connection closed: {err}");
+                            return;
+                        }
+                    }
+                },
+                read = reader.read(&mut chunk) => match read {
+                    Ok(0) | Err(_) => return,
+                    Ok(len) => buf.extend_from_slice(&chunk[..len]),
+                },
+                _ = tokio::time::sleep_until(deadline), if !self.keep_alive.is_zero() => {
+                    if self.ping_sent.is_some() {
+                        log::debug!("MQTT broker stopped answering pings");
+                        return;
+                    }
+                    if self.send(Packet::PingReq).await.is_err() {
+                        return;
+                    }
+                    self.ping_sent = Some(Instant::now());
+                }
+            }
+        }
+    }
+
+    async fn request(&mut self, request: Request) -> Result<(), MqttError> {
+        match request {
+            Request::Publish(message, reply) => {
+                let packet_id = match message.qos {
+                    QoS::AtMostOnce => None,
+                    QoS::AtLeastOnce => Some(self.packet_id()),
+                };
+                self.send(Packet::Publish {
+                    message,
+                    packet_id,
+                    dup: false,
+                })
+                .await?;
+                match packet_id {
+                    Some(packet_id) => {
+                        self.pending.insert(packet_id, Pending::Publish(reply));
+                    }
+                    None => {
+                        let _ = reply.send(Ok(()));
+                    }
+                }
+            }
+            Request::Subscribe(filter, qos, sender, reply) => {
+                let packet_id = self.packet_id();
+                self.send(Packet::Subscribe {
+                    packet_id,
+                    filters: vec![(filter.clone(), qos)],
+                })
+                .await?;
+                // Routed from now on: retained messages follow the
+                // acknowledgement at once.
+                self.routes.push((filter.clone(), sender));
+                self.pending
+                    .insert(packet_id, Pending::Subscribe(filter, reply));
+            }
+            Request::Disconnect => {}
+        }
+        Ok(())
+    }
+
+    /// Handles the complete packets in `buf`.
+    async fn incoming(&mut self, buf: &mut Vec<u8>) -> Result<(), MqttError> {
+        while let Some((packet, len)) = Packet::decode(buf)? {
+            buf.drain(..len);
+            match packet {
+                Packet::Publish {
+                    message, packet_id, ..
+                } => {
+                    self.routes.retain(|(filter, sender)| {
+                        !topic_matches(filter, &message.topic)
+                            || sender.send(message.clone()).is_ok()
+                    });
+                    if let Some(packet_id) = packet_id {
+                        self.send(Packet::PubAck(packet_id)).await?;
+                    }
+                }
+                Packet::PubAck(packet_id) => {
+                    if let Some(Pending::Publish(reply)) = self.pending.remove(&packet_id) {
+                        let _ = reply.send(Ok(()));
+                    }
+                }
+                Packet::SubAck { packet_id, codes } => {
+                    if let Some(Pending::Subscribe(filter, reply)) = self.pending.remove(&packet_id)
+                    {
+                        let result = match codes.first() {
+                            Some(code) if *code < 0x80 => Ok(()),
+                            _ => {
+                                if let Some(index) =
+                                    self.routes.iter().rposition(|(route, _)| *route == filter)
+                                {
+                                    self.routes.remove(index);
+                                }
+                                Err(MqttError::SubscriptionRefused(filter))
+                            }
+                        };
+                        let _ = reply.send(result);
+                    }
+                }
+                Packet::PingResp => self.ping_sent = None,
+                Packet::UnsubAck(_) => {}
+                other => {
+                    return Err(MqttError::Protocol(format!(
+                        "unexpected packet from broker: {other:?}"
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn send(&mut self, packet: Packet) -> Result<(), MqttError> {
+        self.writer.write_all(&packet.encode()).await?;
+        self.last_sent = Instant::now();
+        Ok(())
+    }
+
+    /// The next packet id, never `0`.
+    fn packet_id(&mut self) -> u16 {
+        self.next_packet_id = self.next_packet_id.wrapping_add(1).max(1);
+        self.next_packet_id
+    }
+}
@@ -0,0 +1,407 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! MQTT 3.1.1 control packets.
+//!
+//! A packet is a fixed header, the packet type and flags in one byte then
+//! the remaining length as a variable length integer, followed by the
+//! packet's variable header and payload. Strings are UTF-8 prefixed by
+//! their big endian length.
+
+use super::{Message, MqttError, QoS};
+
+/// Largest packet accepted, well under the protocol's 256 MiB.
+const MAX_PACKET: usize = 1024 * 1024;
+
+/// Protocol level of MQTT 3.1.1.
+const PROTOCOL_LEVEL: u8 = 4;
+
+/// A CONNECT packet's fields.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Connect {
+    pub(super) client_id: String,
+    pub(super) keep_alive: u16,
+    pub(super) username: Option<String>,
+    pub(super) password: Option<String>,
+    pub(super) will: Option<Message>,
+}
+
+/// A control packet.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Packet {
+    Connect(Connect),
+    ConnAck {
+        session_present: bool,
+        code: u8,
+    },
+    /// A message, with its packet id when QoS 1.
+    Publish {
+        message: Message,
+        packet_id: Option<u16>,
+        dup: bool,
+    },
+    PubAck(u16),
+    Subscribe {
+        packet_id: u16,
+        filters: Vec<(String, QoS)>,
+    },
+    /// The granted QoS per filter, or `0x80` for a refused one.
+    SubAck {
+        packet_id: u16,
+        codes: Vec<u8>,
+    },
+    Unsubscribe {
+        packet_id: u16,
+        filters: Vec<String>,
+    },
+    UnsubAck(u16),
+    PingReq,
+    PingResp,
+    Disconnect,
+}
+
+/// The reason a CONNACK return code refuses a connection.
+pub(super) fn refusal(code: u8) -> Option<&'static str> {
+    match code {
+        0 => None,
+        1 => Some("unacceptable protocol version"),
+        2 => Some("client identifier rejected"),
+        3 => Some("server unavailable"),
+        4 => Some("bad user name or password"),
+        5 => Some("not authorized"),
+        _ => Some("unknown return code"),
+    }
+}
+
+impl Packet {
+    /// The packet's bytes.
+    pub(super) fn encode(&self) -> Vec<u8> {
+        let mut body = Vec::new();
+        let header = match self {
+            Packet::Connect(connect) => {
+                put_str(&mut body, "MQTT");
+                body.push(PROTOCOL_LEVEL);
+                let mut flags = 0x02; // clean session
+                if let Some(will) = &connect.will {
+                    flags |= 0x04 | (will.qos.level() << 3);
+                    if will.retain {
+                        flags |= 0x20;
+                    }
+                }
+                if connect.password.is_some() {
+                    flags |= 0x40;
+                }
+                if connect.username.is_some() {
+                    flags |= 0x80;
+                }
+                body.push(flags);
+                body.extend(connect.keep_alive.to_be_bytes());
+                put_str(&mut body, &connect.client_id);
+                if let Some(will) = &connect.will {
+                    put_str(&mut body, &will.topic);
+                    put_bytes(&mut body, &will.payload);
+                }
+                if let Some(username) = &connect.username {
+                    put_str(&mut body, username);
+                }
+                if let Some(password) = &connect.password {
+                    put_str(&mut body, password);
+                }
+                0x10
+            }
+            Packet::ConnAck {
+                session_present,
+                code,
+            } => {
+                body.extend([u8::from(*session_present), *code]);
+                0x20
+            }
+            Packet::Publish {
+                message,
+                packet_id,
+                dup,
+            } => {
+                put_str(&mut body, &message.topic);
+                if let Some(packet_id) = packet_id {
+                    body.extend(packet_id.to_be_bytes());
+                }
+                body.extend(&message.payload);
+                0x30 | (u8::from(*dup) << 3) | (message.qos.level() << 1) | u8::from(message.retain)
+            }
+            Packet::PubAck(packet_id) => {
+                body.extend(packet_id.to_be_bytes());
+                0x40
+            }
+            Packet::Subscribe { packet_id, filters } => {
+                body.extend(packet_id.to_be_bytes());
+                for (filter, qos) in filters {
+                    put_str(&mut body, filter);
+                    body.push(qos.level());
+                }
+                0x82
+            }
+            Packet::SubAck { packet_id, codes } => {
+                body.extend(packet_id.to_be_bytes());
+                body.extend(codes);
+                0x90
+            }
+            Packet::Unsubscribe { packet_id, filters } => {
+                body.extend(packet_id.to_be_bytes());
+                for filter in filters {
+                    put_str(&mut body, filter);
+                }
+                0xA2
+            }
+            Packet::UnsubAck(packet_id) => {
+                body.extend(packet_id.to_be_bytes());
+                0xB0
+            }
+            Packet::PingReq => 0xC0,
+            Packet::PingResp => 0xD0,
+            Packet::Disconnect => 0xE0,
+        };
+
+        let mut packet = Vec::with_capacity(body.len() + 5);
+        packet.push(header);
+        let mut len = body.len();
+        loop {
+            let byte = (len % 128) as u8;
+            len /= 128;
+            if len == 0 {
+                packet.push(byte);
+                break;
+            }
+            packet.push(byte | 0x80);
+        }
+        packet.extend(body);
+        packet
+    }
+
+    /// Decodes the packet at the start of `buf`, with its length in
+    /// bytes, or [`None`] until `buf` holds all of it.
+    pub(super) fn decode(buf: &[u8]) -> Result<Option<(Self, usize)>, MqttError> {
+        let Some(&header) = buf.first() else {
+            return Ok(None);
+        };
+        let mut len = 0usize;
+        let mut offset = 1;
+        loop {
+            let Some(&byte) = buf.get(offset) else {
+                return Ok(None);
+            };
+            len |= usize::from(byte & 0x7F) << (7 * (offset - 1));
+            offset += 1;
+            if byte & 0x80 == 0 {
+                break;
+            }
+            if offset > 4 {
+                return Err(invalid("remaining length is too long"));
+            }
+        }
+        if len > MAX_PACKET {
+            return Err(invalid("packet is too large"));
+        }
+        let Some(body) = buf.get(offset..offset + len) else {
+            return Ok(None);
+        };
+
+        let flags = header & 0x0F;
+        let mut body = Reader(body);
+        let packet = match header >> 4 {
+            1 => {
+                if body.str()? != "MQTT" || body.u8()? != PROTOCOL_LEVEL {
+                    return Err(invalid("unsupported protocol"));
+                }
+                let flags = body.u8()?;
+                let keep_alive = body.u16()?;
+                let client_id = body.str()?;
+                let will = match flags & 0x04 {
+                    0 => None,
+                    _ => Some(Message {
+                        topic: body.str()?,
+                        payload: body.bytes()?,
+                        qos: qos((flags >> 3) & 0x03)?,
+                        retain: flags & 0x20 != 0,
+                    }),
+                };
+                let username = (flags & 0x80 != 0).then(|| body.str()).transpose()?;
+                let password = (flags & 0x40 != 0).then(|| body.str()).transpose()?;
+                Packet::Connect(Connect {
+                    client_id,
+                    keep_alive,
+                    username,
+                    password,
+                    will,
+                })
+            }
+            2 => Packet::ConnAck {
+                session_present: body.u8()? & 0x01 != 0,
+                code: body.u8()?,
+            },
+            3 => {
+                let qos = qos((flags >> 1) & 0x03)?;
+                let topic = body.str()?;
+                let packet_id = match qos {
+                    QoS::AtMostOnce => None,
+                    QoS::AtLeastOnce => Some(body.u16()?),
+                };
+                Packet::Publish {
+                    message: Message {
+                        topic,
+                        payload: body.rest().to_vec(),
+                        qos,
+                        retain: flags & 0x01 != 0,
+                    },
+                    packet_id,
+                    dup: flags & 0x08 != 0,
+                }
+            }
+            4 => Packet::PubAck(body.u16()?),
+            8 => {
+                let packet_id = body.u16()?;
+                let mut filters = Vec::new();
+                while !body.0.is_empty() {
+                    filters.push((body.str()?, qos(body.u8()? & 0x03)?));
+                }
+                Packet::Subscribe { packet_id, filters }
+            }
+            9 => Packet::SubAck {
+                packet_id: body.u16()?,
+                codes: body.rest().to_vec(),
+            },
+            10 => {
+                let packet_id = body.u16()?;
+                let mut filters = Vec::new();
+                while !body.0.is_empty() {
+                    filters.push(body.str()?);
+                }
+                Packet::Unsubscribe { packet_id, filters }
+            }
+            11 => Packet::UnsubAck(body.u16()?),
+            12 => Packet::PingReq,
+            13 => Packet::PingResp,
+            14 => Packet::Disconnect,
+            kind => return Err(invalid(&format!("unsupported packet type {kind}"))),
+        };
+        Ok(Some((packet, offset + len)))
+    }
+}
+
+fn qos(level: u8) -> Result<QoS, MqttError> {
+    QoS::try_from(level).map_err(MqttError::Protocol)
+}
+
+fn invalid(reason: &str) -> MqttError {
+    MqttError::Protocol(reason.into())
+}
+
+fn put_str(buf: &mut Vec<u8>, value: &str) {
+    put_bytes(buf, value.as_bytes());
+}
+
+fn put_bytes(buf: &mut Vec<u8>, value: &[u8]) {
+    buf.extend((value.len() as u16).to_be_bytes());
+    buf.extend(value);
+}
+
+/// Reads the fields of a packet body.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], MqttError> {
+        if self.0.len() < len {
+            return Err(invalid("packet is truncated"));
+        }
+        let (head, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, MqttError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, MqttError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn bytes(&mut self) -> Result<Vec<u8>, MqttError> {
+        let len = self.u16()?;
+        Ok(self.take(usize::from(len))?.to_vec())
+    }
+
+    fn str(&mut self) -> Result<String, MqttError> {
+        String::from_utf8(self.bytes()?).map_err(|_| invalid("string is not UTF-8"))
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        std::mem::take(&mut self.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Connect, Packet};
+    use crate::mqtt::{Message, QoS};
+
+    #[test]
+    fn packets_round_trip() {
+        let packets = [
+            Packet::Connect(Connect {
+                client_id: "mesh".into(),
+                keep_alive: 30,
+                username: Some("user".into()),
+                password: Some("secret".into()),
+                will: Some(Message {
+                    topic: "mesh/status".into(),
+                    payload: b"offline".to_vec(),
+                    qos: QoS::AtLeastOnce,
+                    retain: true,
+                }),
+            }),
+            Packet::ConnAck {
+                session_present: false,
+                code: 5,
+            },
+            Packet::Publish {
+                message: Message {
+                    topic: "mesh/ahu/out".into(),
+                    // Long enough for a two byte remaining length.
+                    payload: vec![b'7'; 300],
+                    qos: QoS::AtLeastOnce,
+                    retain: true,
+                },
+                packet_id: Some(7),
+                dup: false,
+            },
+            Packet::Publish {
+                message: Message::new("mesh/ahu/out", "21.5"),
+                packet_id: None,
+                dup: false,
+            },
+            Packet::PubAck(7),
+            Packet::Subscribe {
+                packet_id: 8,
+                filters: vec![("mesh/+/+/set".into(), QoS::AtLeastOnce)],
+            },
+            Packet::SubAck {
+                packet_id: 8,
+                codes: vec![1, 0x80],
+            },
+            Packet::Unsubscribe {
+                packet_id: 9,
+                filters: vec!["mesh/#".into()],
+            },
+            Packet::UnsubAck(9),
+            Packet::PingReq,
+            Packet::PingResp,
+            Packet::Disconnect,
+        ];
+
+        for packet in packets {
+            let bytes = packet.encode();
+            assert_eq!(Packet::decode(&bytes[..bytes.len() - 1]).unwrap(), None);
+            assert_eq!(Packet::decode(&bytes).unwrap(), Some((packet, bytes.len())));
+        }
+    }
+}
@@ -0,0 +1,104 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Payload encodings of pin values.
+
+use libhaystack::encoding::zinc;
+use libhaystack::val::Value;
+use serde::{Deserialize, Serialize};
+
+use super::MqttError;
+
+/// How values are written in message payloads.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Encoding {
+    /// Hayson JSON: plain JSON numbers, booleans and strings, with
+    /// objects for the other kinds and for numbers with a unit.
+    #[default]
+    Json,
+    /// A Zinc scalar, e.g. `21.5°C`, `T` or `"text"`.
+    Zinc,
+}
+
+impl Encoding {
+    /// Parses an encoding name, as the blocks take it.
+    pub fn parse(name: &str) -> Option<Self> {
+        serde_json::from_value(name.to_lowercase().into()).ok()
+    }
+
+    /// The payload carrying `value`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libhaystack::val::Value;
+    /// use logic_mesh::mqtt::Encoding;
+    ///
+    /// assert_eq!(Encoding::Json.encode(&Value::from(21.5)).unwrap(), b"21.5");
+    /// assert_eq!(Encoding::Zinc.encode(&Value::from(true)).unwrap(), b"T");
+    /// ```
+    pub fn encode(self, value: &Value) -> Result<Vec<u8>, MqttError> {
+        match self {
+            Encoding::Json => {
+                serde_json::to_vec(value).map_err(|err| MqttError::Payload(err.to_string()))
+            }
+            Encoding::Zinc => zinc::encode::to_zinc_string(value)
+                .map(String::into_bytes)
+                .map_err(|err| MqttError::Payload(err.to_string())),
+        }
+    }
+
+    /// The value carried by `payload`. An empty payload is Null.
+    pub fn decode(self, payload: &[u8]) -> Result<Value, MqttError> {
+        if payload.is_empty() {
+            return Ok(Value::Null);
+        }
+        let text = std::str::from_utf8(payload)
+            .map_err(|_| MqttError::Payload("payload is not UTF-8".into()))?;
+        match self {
+            Encoding::Json => {
+                serde_json::from_str(text).map_err(|err| MqttError::Payload(err.to_string()))
+            }
+            Encoding::Zinc => zinc::decode::from_str(text.trim())
+                .map_err(|err| MqttError::Payload(err.to_string())),
+        }
+    }
+}
+
+impl std::fmt::Display for Encoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Encoding::Json => "json",
+            Encoding::Zinc => "zinc",
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use libhaystack::units::get_unit;
+    use libhaystack::val::{Number, Value};
+
+    use super::Encoding;
+
+    #[test]
+    fn values_round_trip_in_both_encodings() {
+        let values = [
+            Value::from(21.5),
+            Value::from(Number::make_with_unit(21.5, get_unit("°C").unwrap())),
+            Value::from(false),
+            Value::from("occupied"),
+            Value::Null,
+        ];
+        for encoding in [Encoding::Json, Encoding::Zinc] {
+            for value in &values {
+                let payload = encoding.encode(value).unwrap();
+                assert_eq!(&encoding.decode(&payload).unwrap(), value, "{encoding}");
+            }
+        }
+        assert_eq!(Encoding::Zinc.decode(b"21.5\xc2\xb0C").unwrap(), values[1]);
+        assert_eq!(Encoding::Json.decode(b"").unwrap(), Value::Null);
+        assert!(Encoding::Json.decode(b"{").is_err());
+        assert_eq!(Encoding::parse("ZINC"), Some(Encoding::Zinc));
+    }
+}
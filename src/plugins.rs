@@ -0,0 +1,259 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Native blocks loaded from sandboxed WebAssembly components.
+//!
+//! Enabled by the `plugins` feature, [`register_plugin`] loads a component
+//! targeting the `block-library` world of `schema/block-plugin.wit` and
+//! registers the blocks it describes as external blocks of a library,
+//! through [`register_block_desc`]. Programs and engine messages then
+//! reference them by name like any other block, on both engines.
+//!
+//! Every block instance runs in its own instance of the component, so a
+//! block keeps its state in the guest's memory between runs. Components
+//! get no imports: they only see the pin values the engine passes them,
+//! and each run is bounded by the [`PluginLimits`] the plugin was loaded
+//! with. A run that traps, errors or exceeds its limits faults the block;
+//! after a trap, the next run starts over on a fresh instance. A block
+//! runs when its inputs change, or every cycle if its description says so.
+//!
+//! Numbers keep their unit across the boundary, as its symbol; values of
+//! kinds the interface has no case for, such as refs or dates, reach the
+//! guest as their Zinc string.
+//!
+//! [`register_block_desc`]: crate::blocks::registry::register_block_desc
+
+pub(crate) mod block;
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, LazyLock, Mutex};
+
+use libhaystack::units::get_unit;
+use libhaystack::val::{Number, Value, kind::HaystackKind};
+use wasmtime::component::{Component, Linker};
+use wasmtime::{Config, Engine, Store, StoreLimits, StoreLimitsBuilder, Trap};
+
+use crate::base::block::desc::{BlockImplementation, BlockRunCondition};
+use crate::base::block::{BlockDesc, BlockPin};
+use crate::base::error::{ExternalError, Result};
+use crate::blocks::registry::{get_block, register_block_desc};
+
+#[cfg(feature = "multi-threaded")]
+pub(crate) use block::schedule_plugin_block_send;
+pub use block::{PluginBlock, PluginExecutor};
+pub(crate) use block::{eval_plugin_block, schedule_plugin_block};
+
+mod bindings {
+    wasmtime::component::bindgen!({
+        path: "schema/block-plugin.wit",
+        world: "block-library",
+    });
+}
+
+use bindings::BlockLibrary;
+
+/// Bounds on the instances of a plugin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PluginLimits {
+    /// Most fuel a single call into the component may consume, roughly
+    /// one unit per WebAssembly instruction.
+    pub max_fuel: u64,
+    /// Largest size a linear memory of an instance may grow to, in bytes.
+    pub max_memory: usize,
+}
+
+impl Default for PluginLimits {
+    fn default() -> Self {
+        Self {
+            max_fuel: 10_000_000,
+            max_memory: 16 * 1024 * 1024,
+        }
+    }
+}
+
+/// A loaded component and the limits its instances run within.
+pub(crate) struct Plugin {
+    component: Component,
+    limits: PluginLimits,
+}
+
+/// The engine shared by all plugins, metering fuel.
+static ENGINE: LazyLock<Engine> = LazyLock::new(|| {
+    let mut config = Config::new();
+    config.consume_fuel(true);
+    Engine::new(&config).expect("Plugin engine configuration is valid")
+});
+
+/// A block's library and name.
+type BlockKey = (String, String);
+
+/// The loaded plugins, by the blocks they provide. Several plugins may
+/// provide blocks of one library.
+static PLUGINS: LazyLock<Mutex<HashMap<BlockKey, Arc<Plugin>>>> = LazyLock::new(Default::default);
+
+/// Loads the WebAssembly `component` and registers its blocks in
+/// `library`, as external blocks. Returns the registered descriptions.
+///
+/// # Errors
+///
+/// Fails if the component does not compile, imports anything, does not
+/// target the `block-library` world or describes an invalid block, if it
+/// describes two blocks with the same name, or if one of its blocks is
+/// already registered in the library. A plugin that fails to load
+/// registers none of its blocks.
+pub fn register_plugin(
+    library: &str,
+    component: &[u8],
+    limits: PluginLimits,
+) -> Result<Vec<BlockDesc>> {
+    let load_err = |detail: String| ExternalError::PluginLoad {
+        library: library.to_string(),
+        detail,
+    };
+
+    let component = Component::new(&ENGINE, component).map_err(|err| load_err(err.to_string()))?;
+    let plugin = Plugin { component, limits };
+    let (mut store, instance) = plugin
+        .instantiate()
+        .map_err(|err| load_err(err.to_string()))?;
+    let descs = instance
+        .call_blocks(&mut store)
+        .map_err(|err| load_err(plugin.describe_error(err)))?
+        .into_iter()
+        .map(|desc| to_block_desc(library, desc))
+        .collect::<Result<Vec<_>, String>>()
+        .map_err(load_err)?;
+
+    let mut names = HashSet::new();
+    if let Some(desc) = descs.iter().find(|desc| !names.insert(&desc.name)) {
+        return Err(load_err(format!("block '{}' is described twice", desc.name)).into());
+    }
+
+    let mut plugins = PLUGINS.lock().expect("Plugin registry is locked");
+    if let Some(desc) = descs
+        .iter()
+        .find(|desc| get_block(&desc.name, Some(library)).is_some())
+    {
+        return Err(load_err(format!("block '{}' is already registered", desc.name)).into());
+    }
+    let plugin = Arc::new(plugin);
+    for desc in &descs {
+        register_block_desc(desc)?;
+        plugins.insert((library.to_string(), desc.name.clone()), plugin.clone());
+    }
+    Ok(descs)
+}
+
+/// The plugin providing the block of `desc`, if any.
+pub(crate) fn resolve_plugin(desc: &BlockDesc) -> Option<Arc<Plugin>> {
+    PLUGINS
+        .lock()
+        .expect("Plugin registry is locked")
+        .get(&(desc.library.clone(), desc.name.clone()))
+        .cloned()
+}
+
+impl Plugin {
+    /// Creates a new, isolated instance of the component.
+    fn instantiate(&self) -> wasmtime::Result<(Store<StoreLimits>, BlockLibrary)> {
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(self.limits.max_memory)
+            .build();
+        let mut store = Store::new(&ENGINE, limits);
+        store.limiter(|limits| limits);
+        store.set_fuel(self.limits.max_fuel)?;
+
+        let instance =
+            BlockLibrary::instantiate(&mut store, &self.component, &Linker::new(&ENGINE))?;
+        Ok((store, instance))
+    }
+
+    /// Describes why a call into the component failed.
+    fn describe_error(&self, err: wasmtime::Error) -> String {
+        match err.downcast_ref::<Trap>() {
+            Some(Trap::OutOfFuel) => format!("ran out of its {} fuel", self.limits.max_fuel),
+            Some(trap) => format!("trapped: {trap}"),
+            None => err.to_string(),
+        }
+    }
+}
+
+/// Converts a block description of the guest.
+fn to_block_desc(library: &str, desc: bindings::BlockDesc) -> Result<BlockDesc, String> {
+    let bindings::BlockDesc {
+        name,
+        dis,
+        category,
+        ver,
+        doc,
+        inputs,
+        outputs,
+        run_condition,
+    } = desc;
+
+    let pins = |pins: Vec<bindings::Pin>| {
+        pins.into_iter()
+            .map(|pin| {
+                let kind = HaystackKind::try_from(pin.kind.as_str()).map_err(|_| {
+                    format!(
+                        "pin '{}' of block '{name}' has the unknown kind '{}'",
+                        pin.name, pin.kind
+                    )
+                })?;
+                Ok(BlockPin {
+                    name: pin.name,
+                    kind,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()
+    };
+    let inputs = pins(inputs)?;
+    let outputs = pins(outputs)?;
+
+    Ok(BlockDesc {
+        name,
+        library: library.to_string(),
+        dis,
+        category,
+        ver,
+        inputs,
+        outputs,
+        doc,
+        implementation: BlockImplementation::External,
+        run_condition: match run_condition {
+            bindings::RunCondition::Change => None,
+            bindings::RunCondition::Always => Some(BlockRunCondition::Always),
+        },
+    })
+}
+
+/// Converts a pin value into a guest value.
+fn to_guest(value: Option<&Value>) -> bindings::Value {
+    match value {
+        None | Some(Value::Null) => bindings::Value::Null,
+        Some(Value::Bool(val)) => bindings::Value::Boolean(val.value),
+        Some(Value::Number(num)) => bindings::Value::Number(bindings::Number {
+            value: num.value,
+            unit: num.unit.map(|unit| unit.symbol().to_string()),
+        }),
+        Some(Value::Str(val)) => bindings::Value::Str(val.value.clone()),
+        Some(other) => bindings::Value::Str(other.to_string()),
+    }
+}
+
+/// Converts a guest value into a pin value.
+fn from_guest(value: bindings::Value) -> Result<Value, String> {
+    Ok(match value {
+        bindings::Value::Null => Value::Null,
+        bindings::Value::Boolean(val) => val.into(),
+        bindings::Value::Number(bindings::Number { value, unit: None }) => value.into(),
+        bindings::Value::Number(bindings::Number {
+            value,
+            unit: Some(symbol),
+        }) => {
+            let unit =
+                get_unit(&symbol).ok_or_else(|| format!("returned the unknown unit '{symbol}'"))?;
+            Number::make_with_unit(value, unit).into()
+        }
+        bindings::Value::Str(val) => val.as_str().into(),
+    })
+}
@@ -0,0 +1,434 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! The block that runs in an instance of a plugin.
+
+use std::sync::Arc;
+
+use libhaystack::val::Value;
+use uuid::Uuid;
+use wasmtime::{Store, StoreLimits};
+
+use crate::base::block::{BlockDesc, BlockProps};
+use crate::base::engine::Engine;
+use crate::base::error::{ExternalError, Result};
+use crate::base::input::InputProps;
+use crate::base::output::Output;
+use crate::blocks::dyn_block::{DynBlock, DynExecutor};
+use crate::blocks::registry::eval_block_impl;
+use crate::blocks::{InputImpl, OutputImpl};
+
+use super::{BlockLibrary, Plugin, from_guest, resolve_plugin, to_guest};
+
+/// A block loaded from a WebAssembly plugin.
+///
+/// Each instance runs in its own instance of the plugin's component. See
+/// the [module documentation](super) for how it is sandboxed.
+pub type PluginBlock = DynBlock<PluginExecutor>;
+
+/// Runs a [`PluginBlock`] in its instance of the plugin's component.
+pub struct PluginExecutor {
+    plugin: Arc<Plugin>,
+    /// The component instance, dropped after it traps.
+    instance: Option<(Store<StoreLimits>, BlockLibrary)>,
+}
+
+impl PluginBlock {
+    /// Creates an instance of the plugin block registered as `desc`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if no plugin provides `desc`, or if the plugin's component
+    /// cannot be instantiated.
+    pub fn new(desc: &BlockDesc, block_id: Option<Uuid>) -> Result<Self> {
+        let plugin = resolve_plugin(desc).ok_or_else(|| ExternalError::NotRegistered {
+            library: desc.library.clone(),
+            name: desc.name.clone(),
+        })?;
+        let instance = plugin
+            .instantiate()
+            .map_err(|err| ExternalError::PluginLoad {
+                library: desc.library.clone(),
+                detail: err.to_string(),
+            })?;
+
+        Ok(Self::with_executor(
+            desc.clone(),
+            block_id,
+            PluginExecutor {
+                plugin,
+                instance: Some(instance),
+            },
+        ))
+    }
+}
+
+impl PluginExecutor {
+    /// Runs the block in its component instance, instantiating the
+    /// component again if the previous run trapped.
+    fn run(
+        &mut self,
+        desc: &BlockDesc,
+        inputs: &[InputImpl],
+        outputs: &mut [OutputImpl],
+    ) -> Result<(), String> {
+        let inputs: Vec<_> = inputs
+            .iter()
+            .map(|input| to_guest(input.get_value()))
+            .collect();
+
+        let (store, instance) = match &mut self.instance {
+            Some(instance) => instance,
+            instance => instance.insert(
+                self.plugin
+                    .instantiate()
+                    .map_err(|err| format!("could not be instantiated: {err}"))?,
+            ),
+        };
+        store
+            .set_fuel(self.plugin.limits.max_fuel)
+            .map_err(|err| err.to_string())?;
+
+        let values = match instance.call_execute(&mut *store, &desc.name, &inputs) {
+            Ok(result) => result.map_err(|err| format!("failed: {err}"))?,
+            Err(err) => {
+                self.instance = None;
+                return Err(self.plugin.describe_error(err));
+            }
+        };
+        for (output, value) in outputs.iter_mut().zip(values) {
+            output.set(from_guest(value)?);
+        }
+        Ok(())
+    }
+}
+
+impl DynExecutor for PluginExecutor {
+    async fn execute(
+        &mut self,
+        desc: &BlockDesc,
+        inputs: &[InputImpl],
+        outputs: &mut [OutputImpl],
+    ) -> Result<(), String> {
+        self.run(desc, inputs, outputs)
+            .map_err(|err| format!("Plugin {err}"))
+    }
+}
+
+pub(crate) fn schedule_plugin_block(
+    engine: &mut impl Engine<
+        Reader = <InputImpl as InputProps>::Reader,
+        Writer = <InputImpl as InputProps>::Writer,
+    >,
+    desc: &BlockDesc,
+    block_id: Option<Uuid>,
+) -> Result<Uuid> {
+    let block = PluginBlock::new(desc, block_id)?;
+    let id = *block.id();
+    engine.schedule(block)?;
+    Ok(id)
+}
+
+#[cfg(feature = "multi-threaded")]
+pub(crate) fn schedule_plugin_block_send(
+    engine: &mut crate::tokio_impl::engine::multi_threaded::MultiThreadedEngine,
+    desc: &BlockDesc,
+    block_id: Option<Uuid>,
+) -> Result<Uuid> {
+    let block = PluginBlock::new(desc, block_id)?;
+    let id = *block.id();
+    engine.schedule_send(block);
+    Ok(id)
+}
+
+pub(crate) async fn eval_plugin_block(desc: &BlockDesc, inputs: Vec<Value>) -> Result<Vec<Value>> {
+    let mut block = PluginBlock::new(desc, None)?;
+    eval_block_impl(&mut block, inputs).await
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use libhaystack::units::get_unit;
+    use libhaystack::val::{Number, Value};
+
+    use crate::base::block::desc::BlockRunCondition;
+    use crate::base::block::test_utils::write_block_inputs;
+    use crate::base::block::{Block, BlockProps, BlockState};
+    use crate::base::error::{Error, ExternalError};
+    use crate::blocks::registry::get_block;
+    use crate::plugins::{PluginLimits, register_plugin};
+
+    use super::{PluginBlock, eval_plugin_block};
+
+    /// Static data of the test component, laid out as the canonical ABI
+    /// lays out the values it points to.
+    struct Data {
+        base: u32,
+        bytes: Vec<u8>,
+    }
+
+    impl Data {
+        fn addr(&self) -> u32 {
+            self.base + self.bytes.len() as u32
+        }
+
+        fn string(&mut self, text: &str) -> [u32; 2] {
+            let ptr = self.addr();
+            self.bytes.extend_from_slice(text.as_bytes());
+            [ptr, text.len() as u32]
+        }
+
+        fn words(&mut self, words: &[u32]) -> u32 {
+            while !self.bytes.len().is_multiple_of(4) {
+                self.bytes.push(0);
+            }
+            let ptr = self.addr();
+            for word in words {
+                self.bytes.extend_from_slice(&word.to_le_bytes());
+            }
+            ptr
+        }
+
+        fn pins(&mut self, pins: &[(&str, &str)]) -> [u32; 2] {
+            let pins: Vec<_> = pins
+                .iter()
+                .flat_map(|(name, kind)| [self.string(name), self.string(kind)].concat())
+                .collect();
+            [self.words(&pins), pins.len() as u32 / 4]
+        }
+
+        fn wat(&self) -> String {
+            let bytes: String = self
+                .bytes
+                .iter()
+                .map(|byte| format!("\\{byte:02x}"))
+                .collect();
+            format!("(data (i32.const {}) \"{bytes}\")", self.base)
+        }
+    }
+
+    /// A component providing `Double`, which runs every cycle, doubles its
+    /// positive input keeping the unit and counts its runs, and `Spin`,
+    /// which never returns.
+    pub(crate) fn component() -> Vec<u8> {
+        component_named("Double", "Spin")
+    }
+
+    /// The [`component`] with its blocks named `double` and `spin`. The
+    /// guest tells them apart by the length of the name, which for `spin`
+    /// is four bytes.
+    fn component_named(double: &str, spin: &str) -> Vec<u8> {
+        let mut data = Data {
+            base: 1024,
+            bytes: Vec::new(),
+        };
+        let mut descs = Vec::new();
+        for (name, doc, outputs, run_condition) in [
+            (
+                double,
+                "Doubles its input and counts its runs.",
+                &[("out", "number"), ("runs", "number")][..],
+                1,
+            ),
+            (spin, "Never returns.", &[("out", "number")][..], 0),
+        ] {
+            let fields = [
+                data.string(name),
+                data.string(name),
+                data.string("misc"),
+                data.string("1.0.0"),
+                data.string(doc),
+                data.pins(&[("in", "number")]),
+                data.pins(outputs),
+            ];
+            descs.extend(fields.concat());
+            descs.push(run_condition);
+        }
+        let descs = data.words(&descs);
+        let blocks = data.words(&[descs, 2]);
+        let [err_ptr, err_len] = data.string("expects a positive number");
+
+        let component = format!(
+            r#"
+            (component
+              (core module $m
+                (memory (export "memory") 1)
+                (global $heap (mut i32) (i32.const 4096))
+                (global $runs (mut i32) (i32.const 0))
+                (func (export "cabi_realloc") (param i32 i32 i32 i32) (result i32)
+                  (local $ptr i32)
+                  (local.set $ptr
+                    (i32.and
+                      (i32.add (global.get $heap) (i32.sub (local.get 2) (i32.const 1)))
+                      (i32.sub (i32.const 0) (local.get 2))))
+                  (global.set $heap (i32.add (local.get $ptr) (local.get 3)))
+                  (local.get $ptr))
+                (func (export "blocks") (result i32) (i32.const {blocks}))
+                (func (export "execute") (param $name i32) (param $name_len i32)
+                                         (param $in i32) (param $in_len i32) (result i32)
+                  (if (i32.eq (local.get $name_len) (i32.const 4))
+                    (then (loop $spin (br $spin))))
+                  (if (i32.or
+                        (i32.ne (i32.load8_u (local.get $in)) (i32.const 2))
+                        (f64.lt (f64.load (i32.add (local.get $in) (i32.const 8))) (f64.const 0)))
+                    (then
+                      (i32.store8 (i32.const 2112) (i32.const 1))
+                      (i32.store (i32.const 2116) (i32.const {err_ptr}))
+                      (i32.store (i32.const 2120) (i32.const {err_len}))
+                      (return (i32.const 2112))))
+                  (global.set $runs (i32.add (global.get $runs) (i32.const 1)))
+                  (i32.store8 (i32.const 2048) (i32.const 2))
+                  (f64.store (i32.const 2056)
+                    (f64.mul (f64.load (i32.add (local.get $in) (i32.const 8))) (f64.const 2)))
+                  (i32.store8 (i32.const 2064) (i32.load8_u (i32.add (local.get $in) (i32.const 16))))
+                  (i32.store (i32.const 2068) (i32.load (i32.add (local.get $in) (i32.const 20))))
+                  (i32.store (i32.const 2072) (i32.load (i32.add (local.get $in) (i32.const 24))))
+                  (i32.store8 (i32.const 2080) (i32.const 2))
+                  (f64.store (i32.const 2088) (f64.convert_i32_u (global.get $runs)))
+                  (i32.store8 (i32.const 2096) (i32.const 0))
+                  (i32.store8 (i32.const 2112) (i32.const 0))
+                  (i32.store (i32.const 2116) (i32.const 2048))
+                  (i32.store (i32.const 2120) (i32.const 2))
+                  (i32.const 2112))
+                {data}
+              )
+              (core instance $i (instantiate $m))
+              (type $number' (record (field "value" float64) (field "unit" (option string))))
+              (export $number "number" (type $number'))
+              (type $value' (variant (case "null") (case "boolean" bool)
+                                     (case "number" $number) (case "str" string)))
+              (export $value "value" (type $value'))
+              (type $pin' (record (field "name" string) (field "kind" string)))
+              (export $pin "pin" (type $pin'))
+              (type $run' (enum "change" "always"))
+              (export $run "run-condition" (type $run'))
+              (type $desc' (record (field "name" string) (field "dis" string)
+                                   (field "category" string) (field "ver" string)
+                                   (field "doc" string) (field "inputs" (list $pin))
+                                   (field "outputs" (list $pin))
+                                   (field "run-condition" $run)))
+              (export $desc "block-desc" (type $desc'))
+              (func (export "blocks") (result (list $desc))
+                (canon lift (core func $i "blocks") (memory $i "memory")
+                  (realloc (func $i "cabi_realloc"))))
+              (func (export "execute") (param "block" string) (param "inputs" (list $value))
+                (result (result (list $value) (error string)))
+                (canon lift (core func $i "execute") (memory $i "memory")
+                  (realloc (func $i "cabi_realloc"))))
+            )
+            "#,
+            data = data.wat(),
+        );
+        wat::parse_str(component).expect("Test component is valid")
+    }
+
+    fn celsius(value: f64) -> Value {
+        Number::make_with_unit(value, get_unit("°C").unwrap()).into()
+    }
+
+    #[tokio::test]
+    async fn test_plugin_blocks_keep_state_per_instance() {
+        let descs = register_plugin("plugin_test", &component(), PluginLimits::default()).unwrap();
+        assert_eq!(
+            descs
+                .iter()
+                .map(|desc| desc.name.as_str())
+                .collect::<Vec<_>>(),
+            ["Double", "Spin"]
+        );
+        let desc = &get_block("Double", Some("plugin_test")).unwrap().desc;
+        assert_eq!(desc.outputs[1].name, "runs");
+        assert_eq!(desc.run_condition, Some(BlockRunCondition::Always));
+        let spin = &get_block("Spin", Some("plugin_test")).unwrap().desc;
+        assert_eq!(spin.run_condition, None);
+
+        let mut block = PluginBlock::new(desc, None).unwrap();
+        for value in [1.5, 2.0] {
+            write_block_inputs([(block.get_input_mut("in").unwrap(), celsius(value))]).await;
+            block.execute().await;
+        }
+        assert_eq!(block.outputs()[0].value(), &celsius(4.0));
+        assert_eq!(block.outputs()[1].value(), &Value::from(2));
+        assert_eq!(block.state(), BlockState::Running);
+
+        block.execute().await;
+        assert_eq!(block.outputs()[1].value(), &Value::from(3));
+
+        assert_eq!(
+            eval_plugin_block(desc, vec![Value::from(5)]).await.unwrap(),
+            vec![Value::from(10), Value::from(1)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_plugin_failures_fault_the_block() {
+        let limits = PluginLimits {
+            max_fuel: 100_000,
+            ..Default::default()
+        };
+        register_plugin("plugin_fault_test", &component(), limits).unwrap();
+
+        let desc = &get_block("Double", Some("plugin_fault_test")).unwrap().desc;
+        let mut block = PluginBlock::new(desc, None).unwrap();
+        write_block_inputs([(block.get_input_mut("in").unwrap(), -2)]).await;
+        block.execute().await;
+        assert_eq!(
+            block.state().fault_reason(),
+            Some("Plugin failed: expects a positive number")
+        );
+
+        let desc = &get_block("Spin", Some("plugin_fault_test")).unwrap().desc;
+        let mut block = PluginBlock::new(desc, None).unwrap();
+        for value in [1, 2] {
+            write_block_inputs([(block.get_input_mut("in").unwrap(), value)]).await;
+            block.execute().await;
+            assert_eq!(
+                block.state().fault_reason(),
+                Some("Plugin ran out of its 100000 fuel")
+            );
+        }
+
+        let err = register_plugin("plugin_fault_test", &component(), limits)
+            .expect_err("blocks are already registered");
+        assert_eq!(
+            err.to_string(),
+            "Plugin 'plugin_fault_test' could not be loaded: block 'Double' is already registered"
+        );
+        let err =
+            register_plugin("plugin_invalid_test", b"\0asm", limits).expect_err("not a component");
+        assert!(matches!(
+            err,
+            Error::External(ExternalError::PluginLoad { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_plugins_share_a_library() {
+        register_plugin("plugin_shared_test", &component(), PluginLimits::default()).unwrap();
+        register_plugin(
+            "plugin_shared_test",
+            &component_named("Twice", "Loop"),
+            PluginLimits::default(),
+        )
+        .unwrap();
+
+        for name in ["Double", "Twice"] {
+            let desc = &get_block(name, Some("plugin_shared_test")).unwrap().desc;
+            assert_eq!(
+                eval_plugin_block(desc, vec![Value::from(3)]).await.unwrap(),
+                vec![Value::from(6), Value::from(1)]
+            );
+        }
+
+        let err = register_plugin(
+            "plugin_twice_test",
+            &component_named("Spin", "Spin"),
+            PluginLimits::default(),
+        )
+        .expect_err("blocks are described twice");
+        assert_eq!(
+            err.to_string(),
+            "Plugin 'plugin_twice_test' could not be loaded: block 'Spin' is described twice"
+        );
+        assert!(get_block("Spin", Some("plugin_twice_test")).is_none());
+    }
+}
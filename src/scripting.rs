@@ -0,0 +1,268 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Native blocks scripted in [Rhai](https://rhai.rs).
+//!
+//! Enabled by the `scripting` feature, this is the native counterpart of
+//! the JavaScript blocks of the wasm build: [`register_script_block`]
+//! registers a block description through
+//! [`register_block_desc`] along with the script that executes it, so
+//! programs and engine messages reference the block by name like any
+//! other. Both engines schedule script blocks.
+//!
+//! On every run, the script sees two object maps:
+//!
+//! - `inputs`, the input values by pin name, read as `inputs.a` or, for
+//!   names Rhai reserves such as `in`, as `inputs["in"]`;
+//! - `state`, which the block instance keeps from one run to the next.
+//!
+//! It evaluates to the outputs: an object map of values by output name,
+//! an array in output order, a single value for the first output, or
+//! `()` to leave them as they are. Numbers reach the script as floats,
+//! without their unit; values of kinds a script has no type for, such as
+//! refs or dates, reach it as their Zinc string.
+//!
+//! Runs are bounded by the [`ScriptLimits`] the block was registered
+//! with. A script that fails, or exceeds its limits, faults the block
+//! until a later run succeeds.
+//!
+//! ```
+//! use logic_mesh::{
+//!     HaystackKind,
+//!     base::block::{BlockDesc, BlockPin},
+//!     blocks::registry::get_block,
+//!     scripting::{ScriptLimits, register_script_block},
+//! };
+//!
+//! let desc = BlockDesc {
+//!     name: "Totalizer".into(),
+//!     library: "scripts".into(),
+//!     category: "misc".into(),
+//!     inputs: vec![BlockPin { name: "in".into(), kind: HaystackKind::Number }],
+//!     outputs: vec![BlockPin { name: "total".into(), kind: HaystackKind::Number }],
+//!     ..Default::default()
+//! };
+//! let script = r#"
+//!     state.total = (state.total ?? 0.0) + inputs["in"];
+//!     #{ total: state.total }
+//! "#;
+//!
+//! register_script_block(&desc, script, ScriptLimits::default())?;
+//! assert!(get_block("Totalizer", Some("scripts")).is_some());
+//! # Ok::<(), logic_mesh::Error>(())
+//! ```
+
+mod block;
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use libhaystack::val::{Dict, Value};
+use rhai::{AST, Array, Dynamic, Engine, Map};
+
+use crate::base::block::BlockDesc;
+use crate::base::block::desc::BlockImplementation;
+use crate::base::error::{ExternalError, Result};
+use crate::blocks::registry::register_block_desc;
+
+#[cfg(feature = "multi-threaded")]
+pub(crate) use block::schedule_script_block_send;
+pub use block::{ScriptBlock, ScriptExecutor};
+pub(crate) use block::{eval_script_block, schedule_script_block};
+
+/// Bounds on a single run of a block's script.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScriptLimits {
+    /// Most operations a run may take.
+    pub max_operations: u64,
+    /// Longest a run may take.
+    pub max_duration: Duration,
+    /// Longest string a script may build, in bytes.
+    pub max_string_size: usize,
+    /// Largest array a script may build.
+    pub max_array_size: usize,
+    /// Largest object map a script may build, `state` included.
+    pub max_map_size: usize,
+    /// Deepest function call nesting.
+    pub max_call_levels: usize,
+}
+
+impl Default for ScriptLimits {
+    fn default() -> Self {
+        Self {
+            max_operations: 1_000_000,
+            max_duration: Duration::from_millis(100),
+            max_string_size: 64 * 1024,
+            max_array_size: 10_000,
+            max_map_size: 10_000,
+            max_call_levels: 32,
+        }
+    }
+}
+
+/// A compiled script and the Rhai engine that runs it within its limits.
+#[derive(Debug)]
+pub(crate) struct Script {
+    engine: Engine,
+    ast: AST,
+    limits: ScriptLimits,
+}
+
+thread_local! {
+    /// When the script running on this thread has to stop. Runs are
+    /// synchronous, so they never share a thread.
+    static DEADLINE: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+type ScriptRegistry = Mutex<HashMap<String, HashMap<String, Arc<Script>>>>;
+
+/// The scripts of the registered blocks, by library and block name.
+static SCRIPTS: LazyLock<ScriptRegistry> = LazyLock::new(Default::default);
+
+/// Registers a block executed by `script`, referenced afterwards by the
+/// name and library of `desc`. The description is registered as an
+/// external block.
+///
+/// # Errors
+///
+/// Fails if the script does not compile, or if a block with the same name
+/// is already registered in the library.
+pub fn register_script_block(desc: &BlockDesc, script: &str, limits: ScriptLimits) -> Result<()> {
+    let script = Script::compile(&desc.name, script, limits)?;
+
+    let desc = BlockDesc {
+        implementation: BlockImplementation::External,
+        ..desc.clone()
+    };
+    register_block_desc(&desc)?;
+
+    SCRIPTS
+        .lock()
+        .expect("Script registry is locked")
+        .entry(desc.library.clone())
+        .or_default()
+        .insert(desc.name.clone(), Arc::new(script));
+    Ok(())
+}
+
+/// The script registered for `desc`.
+fn resolve_script(desc: &BlockDesc) -> Result<Arc<Script>, ExternalError> {
+    SCRIPTS
+        .lock()
+        .expect("Script registry is locked")
+        .get(&desc.library)
+        .and_then(|scripts| scripts.get(&desc.name))
+        .cloned()
+        .ok_or_else(|| ExternalError::NotRegistered {
+            library: desc.library.clone(),
+            name: desc.name.clone(),
+        })
+}
+
+impl Script {
+    fn compile(name: &str, source: &str, limits: ScriptLimits) -> Result<Self, ExternalError> {
+        let mut engine = Engine::new();
+        engine
+            .set_max_operations(limits.max_operations)
+            .set_max_string_size(limits.max_string_size)
+            .set_max_array_size(limits.max_array_size)
+            .set_max_map_size(limits.max_map_size)
+            .set_max_call_levels(limits.max_call_levels)
+            .on_progress(|_| {
+                DEADLINE
+                    .get()
+                    .is_some_and(|deadline| Instant::now() > deadline)
+                    .then_some(Dynamic::UNIT)
+            });
+
+        let ast = engine
+            .compile(source)
+            .map_err(|err| ExternalError::ScriptCompile {
+                name: name.to_string(),
+                detail: err.to_string(),
+            })?;
+
+        Ok(Self {
+            engine,
+            ast,
+            limits,
+        })
+    }
+
+    /// Runs the script over `inputs`, with the instance's `state`, and
+    /// returns what it evaluated to.
+    fn run(&self, inputs: Map, state: &mut Map) -> Result<Dynamic, String> {
+        let mut scope = rhai::Scope::new();
+        scope.push("inputs", inputs);
+        scope.push("state", std::mem::take(state));
+
+        DEADLINE.set(Some(Instant::now() + self.limits.max_duration));
+        let result = self
+            .engine
+            .eval_ast_with_scope::<Dynamic>(&mut scope, &self.ast);
+        DEADLINE.set(None);
+
+        if let Some(kept) = scope.remove::<Map>("state") {
+            *state = kept;
+        }
+
+        result.map_err(|err| match *err {
+            rhai::EvalAltResult::ErrorTerminated(..) => format!(
+                "ran longer than {} ms",
+                self.limits.max_duration.as_millis()
+            ),
+            err => err.to_string(),
+        })
+    }
+}
+
+/// Converts a pin value into a script value.
+fn to_dynamic(value: &Value) -> Dynamic {
+    match value {
+        Value::Null => Dynamic::UNIT,
+        Value::Bool(val) => val.value.into(),
+        Value::Number(num) => num.value.into(),
+        Value::Str(val) => val.value.clone().into(),
+        Value::List(list) => list.iter().map(to_dynamic).collect::<Array>().into(),
+        Value::Dict(dict) => dict
+            .iter()
+            .map(|(key, value)| (key.as_str().into(), to_dynamic(value)))
+            .collect::<Map>()
+            .into(),
+        other => other.to_string().into(),
+    }
+}
+
+/// Converts a script value into a pin value.
+fn from_dynamic(value: Dynamic) -> Result<Value, String> {
+    if value.is_unit() {
+        Ok(Value::Null)
+    } else if let Ok(val) = value.as_bool() {
+        Ok(val.into())
+    } else if let Ok(val) = value.as_int() {
+        Ok((val as f64).into())
+    } else if let Ok(val) = value.as_float() {
+        Ok(val.into())
+    } else if value.is_string() {
+        Ok(value.into_string()?.as_str().into())
+    } else if value.is_array() {
+        let list = value
+            .into_array()?
+            .into_iter()
+            .map(from_dynamic)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(list.into())
+    } else if let Some(map) = value.clone().try_cast::<Map>() {
+        let dict = map
+            .into_iter()
+            .map(|(key, value)| Ok((key.to_string(), from_dynamic(value)?)))
+            .collect::<Result<Dict, String>>()?;
+        Ok(dict.into())
+    } else {
+        Err(format!(
+            "returned a {}, which is not a pin value",
+            value.type_name()
+        ))
+    }
+}
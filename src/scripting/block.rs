@@ -0,0 +1,220 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! The block that runs a registered script.
+
+use std::sync::Arc;
+
+use libhaystack::val::Value;
+use rhai::{Dynamic, Map};
+use uuid::Uuid;
+
+use crate::base::block::{BlockDesc, BlockProps};
+use crate::base::engine::Engine;
+use crate::base::error::Result;
+use crate::base::input::InputProps;
+use crate::base::output::{Output, OutputProps};
+use crate::blocks::dyn_block::{DynBlock, DynExecutor};
+use crate::blocks::registry::eval_block_impl;
+use crate::blocks::{InputImpl, OutputImpl};
+
+use super::{Script, from_dynamic, resolve_script, to_dynamic};
+
+/// A block executed by a registered Rhai script.
+///
+/// Each instance keeps its own script `state` between runs. See the
+/// [module documentation](super) for what the script sees and returns.
+pub type ScriptBlock = DynBlock<ScriptExecutor>;
+
+/// Runs a [`ScriptBlock`]: its script, and the state the script keeps.
+pub struct ScriptExecutor {
+    script: Arc<Script>,
+    state: Map,
+}
+
+impl ScriptBlock {
+    /// Creates an instance of the script block registered as `desc`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if no script is registered for `desc`.
+    pub fn new(desc: &BlockDesc, block_id: Option<Uuid>) -> Result<Self> {
+        let script = resolve_script(desc)?;
+        Ok(Self::with_executor(
+            desc.clone(),
+            block_id,
+            ScriptExecutor {
+                script,
+                state: Map::new(),
+            },
+        ))
+    }
+}
+
+impl DynExecutor for ScriptExecutor {
+    async fn execute(
+        &mut self,
+        _desc: &BlockDesc,
+        inputs: &[InputImpl],
+        outputs: &mut [OutputImpl],
+    ) -> Result<(), String> {
+        let inputs = inputs
+            .iter()
+            .map(|input| {
+                let value = input.get_value().map_or(Dynamic::UNIT, to_dynamic);
+                (input.name().into(), value)
+            })
+            .collect();
+
+        self.script
+            .run(inputs, &mut self.state)
+            .and_then(|result| set_outputs(outputs, result))
+            .map_err(|err| format!("Script {err}"))
+    }
+}
+
+/// Sets the outputs from what the script evaluated to.
+fn set_outputs(outputs: &mut [OutputImpl], result: Dynamic) -> Result<(), String> {
+    if result.is_unit() {
+        return Ok(());
+    }
+
+    if let Some(map) = result.clone().try_cast::<Map>() {
+        for (name, value) in map {
+            let output = outputs
+                .iter_mut()
+                .find(|output| output.name() == name.as_str())
+                .ok_or_else(|| format!("returned the unknown output '{name}'"))?;
+            output.set(from_dynamic(value)?);
+        }
+    } else if result.is_array() {
+        let values = result.into_array()?;
+        for (output, value) in outputs.iter_mut().zip(values) {
+            output.set(from_dynamic(value)?);
+        }
+    } else if let Some(output) = outputs.first_mut() {
+        output.set(from_dynamic(result)?);
+    }
+    Ok(())
+}
+
+pub(crate) fn schedule_script_block(
+    engine: &mut impl Engine<
+        Reader = <InputImpl as InputProps>::Reader,
+        Writer = <InputImpl as InputProps>::Writer,
+    >,
+    desc: &BlockDesc,
+    block_id: Option<Uuid>,
+) -> Result<Uuid> {
+    let block = ScriptBlock::new(desc, block_id)?;
+    let id = *block.id();
+    engine.schedule(block)?;
+    Ok(id)
+}
+
+#[cfg(feature = "multi-threaded")]
+pub(crate) fn schedule_script_block_send(
+    engine: &mut crate::tokio_impl::engine::multi_threaded::MultiThreadedEngine,
+    desc: &BlockDesc,
+    block_id: Option<Uuid>,
+) -> Result<Uuid> {
+    let block = ScriptBlock::new(desc, block_id)?;
+    let id = *block.id();
+    engine.schedule_send(block);
+    Ok(id)
+}
+
+pub(crate) async fn eval_script_block(desc: &BlockDesc, inputs: Vec<Value>) -> Result<Vec<Value>> {
+    let mut block = ScriptBlock::new(desc, None)?;
+    eval_block_impl(&mut block, inputs).await
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use libhaystack::val::Value;
+
+    use crate::base::block::test_utils::write_block_inputs;
+    use crate::base::block::{Block, BlockDesc, BlockProps};
+    use crate::base::error::{Error, ExternalError};
+    use crate::base::input::input_reader::InputReader;
+    use crate::blocks::dyn_block::test::number_desc;
+    use crate::scripting::{ScriptLimits, register_script_block};
+
+    use super::{ScriptBlock, eval_script_block};
+
+    fn script_desc(name: &str, inputs: &[&str], outputs: &[&str]) -> BlockDesc {
+        number_desc("script_test", name, inputs, outputs)
+    }
+
+    #[tokio::test]
+    async fn test_script_block_keeps_state() {
+        let desc = script_desc("Accumulate", &["in", "gain"], &["sum", "runs"]);
+        let script = r#"
+            state.sum = (state.sum ?? 0.0) + inputs["in"] * (inputs.gain ?? 1.0);
+            state.runs = (state.runs ?? 0) + 1;
+            #{ sum: state.sum, runs: state.runs }
+        "#;
+        register_script_block(&desc, script, ScriptLimits::default()).unwrap();
+
+        let mut block = ScriptBlock::new(&desc, None).unwrap();
+        write_block_inputs([(block.get_input_mut("gain").unwrap(), 2)]).await;
+        block.read_inputs().await;
+
+        for value in [1, 2] {
+            write_block_inputs([(block.get_input_mut("in").unwrap(), value)]).await;
+            block.execute().await;
+        }
+        assert_eq!(block.outputs()[0].value(), &Value::from(6));
+        assert_eq!(block.outputs()[1].value(), &Value::from(2));
+        assert_eq!(block.state(), crate::base::block::BlockState::Running);
+
+        assert_eq!(
+            eval_script_block(&desc, vec![Value::from(5)])
+                .await
+                .unwrap(),
+            vec![Value::from(5), Value::from(1)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_script_errors_and_limits_fault_the_block() {
+        let limits = ScriptLimits {
+            max_duration: Duration::from_millis(20),
+            ..Default::default()
+        };
+        let desc = script_desc("Spin", &["in"], &["out"]);
+        let script = r#"if inputs["in"] > 0.0 { loop {} } else { inputs["in"] / "zero" }"#;
+        register_script_block(&desc, script, limits).unwrap();
+
+        let mut block = ScriptBlock::new(&desc, None).unwrap();
+        write_block_inputs([(block.get_input_mut("in").unwrap(), 1)]).await;
+        block.execute().await;
+        assert_eq!(
+            block.state().fault_reason(),
+            Some("Script ran longer than 20 ms")
+        );
+
+        write_block_inputs([(block.get_input_mut("in").unwrap(), 0)]).await;
+        block.execute().await;
+        assert!(
+            block
+                .state()
+                .fault_reason()
+                .is_some_and(|reason| reason.contains("Function not found"))
+        );
+
+        let err = register_script_block(&script_desc("Broken", &[], &["out"]), "1 +", limits)
+            .expect_err("script should not compile");
+        assert!(matches!(
+            err,
+            Error::External(ExternalError::ScriptCompile { .. })
+        ));
+        let err = ScriptBlock::new(&script_desc("Unknown", &[], &["out"]), None)
+            .expect_err("no script is registered");
+        assert_eq!(
+            err.to_string(),
+            "Nothing registered to execute the external block 'script_test::Unknown'"
+        );
+    }
+}
@@ -0,0 +1,610 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! WebSocket and REST server for a native engine.
+//!
+//! Enabled by the `server` feature. [`EngineServer`] attaches to an
+//! engine before it runs and serves its message protocol as JSON, so the
+//! web editor can drive an engine running on a controller the same way
+//! it drives the in-browser wasm build.
+//!
+//! # WebSocket
+//!
+//! `GET /api/ws` upgrades to a WebSocket. Each text frame is a
+//! [`Request`] with an optional `id` that is echoed back:
+//!
+//! ```json
+//! {"id": 1, "type": "addBlock", "name": "Add", "lib": "core"}
+//! {"id": 1, "result": "0b0d2f4c-..."}
+//! {"id": 2, "type": "removeBlock", "block": "nope"}
+//! {"id": 2, "error": "Invalid uuid 'nope': ..."}
+//! ```
+//!
+//! Watch notifications stream to every connected socket as
+//! `{"watch": {...}}`, shaped like the web package's `BlockNotification`,
+//! and, on a server given the engine's alarm table with
+//! [`EngineServer::with_alarms`], alarm transitions as
+//! `{"alarm": {...}}`, shaped like an
+//! [`AlarmEvent`](crate::base::alarm::AlarmEvent).
+//!
+//! # REST
+//!
+//! | Method and path                          | Request                  |
+//! |------------------------------------------|--------------------------|
+//! | `POST /api`                              | any [`Request`]          |
+//! | `GET /api/catalog`                       | `listBlocks`             |
+//! | `POST /api/blocks`                       | `addBlock`               |
+//! | `GET /api/blocks/{id}`                   | `inspectBlock`           |
+//! | `DELETE /api/blocks/{id}`                | `removeBlock`            |
+//! | `PUT /api/blocks/{id}/inputs/{pin}`      | `writeBlockInput`        |
+//! | `PUT /api/blocks/{id}/outputs/{pin}`     | `writeBlockOutput`       |
+//! | `GET /api/blocks/{id}/history/{pin}`     | `queryHistory`           |
+//! | `PUT /api/blocks/{id}/history/{pin}`     | `trendPin`               |
+//! | `DELETE /api/blocks/{id}/history/{pin}`  | `trendPin`, stopping it  |
+//! | `GET /api/alarms`                        | `listAlarms`             |
+//! | `POST /api/alarms/{id}/acknowledge`      | `acknowledgeAlarm`       |
+//! | `PUT /api/alarms/{id}/shelve`            | `shelveAlarm`            |
+//! | `DELETE /api/alarms/{id}/shelve`         | `unshelveAlarm`          |
+//! | `POST /api/links`                        | `createLink`             |
+//! | `DELETE /api/links/{id}`                 | `removeLink`             |
+//! | `POST /api/eval`                         | `evalBlock`              |
+//! | `GET /api/program?programId=`            | `getProgram`             |
+//! | `PUT /api/program`                       | `loadProgram`            |
+//! | `GET /api/programs`                      | `listPrograms`           |
+//! | `PUT /api/programs/{id}`                 | `loadNamedProgram`       |
+//! | `DELETE /api/programs/{id}`              | `unloadProgram`          |
+//! | `POST /api/programs/{id}/{action}`       | `pause`/`resume`/`reset` |
+//! | `POST /api/engine/{action}`              | `pause`/`resume`/`reset`/`stop` |
+//!
+//! Bodies and results are JSON; pin values are Haystack JSON. A rejected
+//! request answers `400` with `{"error": "..."}`, and `503` once the
+//! engine has stopped.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use logic_mesh::base::engine::Engine;
+//! use logic_mesh::server::EngineServer;
+//! use logic_mesh::single_threaded::SingleThreadedEngine;
+//!
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() -> std::io::Result<()> {
+//! let mut engine = SingleThreadedEngine::new();
+//! let server = EngineServer::new(&mut engine);
+//!
+//! let listener = tokio::net::TcpListener::bind("127.0.0.1:8080").await?;
+//! tokio::spawn(server.serve(listener));
+//! engine.run().await;
+//! # Ok(())
+//! # }
+//! ```
+
+mod haystack;
+mod protocol;
+mod rest;
+mod socket;
+
+use std::sync::Arc;
+
+use axum::Router;
+use tokio::net::TcpListener;
+use tokio::sync::{Mutex, OnceCell, broadcast, mpsc};
+use uuid::Uuid;
+
+use crate::base::alarm::{AlarmEvent, AlarmTable};
+use crate::base::engine::{Engine, messages::EngineMessage};
+use crate::base::error::AlarmError;
+use crate::tokio_impl::engine::single_threaded::Messages;
+
+use protocol::{Call, reply_json};
+pub use protocol::{Request, WatchChange, WatchNotification};
+
+/// Watch notifications buffered per socket before a slow one lags.
+const WATCH_BUFFER: usize = 1024;
+
+/// Serves an engine's message protocol over WebSocket and REST.
+pub struct EngineServer {
+    connection: Connection,
+}
+
+impl EngineServer {
+    /// Attaches a server to `engine`. Call before the engine runs: the
+    /// server's reply channel is registered with it here.
+    pub fn new<E: Engine<Channel = mpsc::Sender<Messages>>>(engine: &mut E) -> Self {
+        let id = Uuid::new_v4();
+        let (reply_sender, replies) = mpsc::channel(32);
+        let sender = engine.create_message_channel(id, reply_sender);
+
+        Self {
+            connection: Connection(Arc::new(Shared {
+                id,
+                channel: Mutex::new((sender, replies)),
+                watches: broadcast::channel(WATCH_BUFFER).0,
+                watching: OnceCell::new(),
+                alarm_table: std::sync::OnceLock::new(),
+                alarms: broadcast::channel(WATCH_BUFFER).0,
+                alarming: OnceCell::new(),
+                haystack: haystack::Tables::new(),
+            })),
+        }
+    }
+
+    /// Streams the transitions of `alarms`, the table given to the
+    /// engine with `set_alarms`, to WebSocket clients. Only the first
+    /// table given is used.
+    pub fn with_alarms(self, alarms: AlarmTable) -> Self {
+        let _ = self.connection.0.alarm_table.set(alarms);
+        self
+    }
+
+    /// The server's routes, for mounting into a larger application.
+    pub fn router(&self) -> Router {
+        rest::routes()
+            .merge(haystack::routes())
+            .with_state(self.connection.clone())
+    }
+
+    /// Serves requests on `listener` until the task is dropped.
+    pub async fn serve(self, listener: TcpListener) -> std::io::Result<()> {
+        axum::serve(listener, self.router()).await
+    }
+}
+
+/// Why a request got no result.
+#[derive(Debug)]
+pub(crate) enum CallError {
+    /// The request was malformed or the engine refused it.
+    Rejected(String),
+    /// The engine is no longer running.
+    Stopped,
+}
+
+impl std::fmt::Display for CallError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CallError::Rejected(error) => f.write_str(error),
+            CallError::Stopped => f.write_str("Engine is not running"),
+        }
+    }
+}
+
+/// The server's single connection to the engine, shared by every client.
+///
+/// The engine answers on one reply channel per sender, so requests are
+/// sent one at a time and each waits for its reply before the next goes
+/// out.
+#[derive(Clone)]
+pub(crate) struct Connection(Arc<Shared>);
+
+struct Shared {
+    id: Uuid,
+    channel: Mutex<(mpsc::Sender<Messages>, mpsc::Receiver<Messages>)>,
+    watches: broadcast::Sender<Arc<WatchNotification>>,
+    watching: OnceCell<()>,
+    alarm_table: std::sync::OnceLock<AlarmTable>,
+    alarms: broadcast::Sender<Arc<AlarmEvent>>,
+    alarming: OnceCell<()>,
+    haystack: haystack::Tables,
+}
+
+impl Connection {
+    pub(crate) async fn call(&self, request: Request) -> Result<serde_json::Value, CallError> {
+        match request.into_call(self.0.id).map_err(CallError::Rejected)? {
+            Call::Local(result) => Ok(result),
+            Call::Notify(msg) => {
+                let channel = self.0.channel.lock().await;
+                channel.0.send(msg).await.map_err(|_| CallError::Stopped)?;
+                Ok(serde_json::Value::Null)
+            }
+            Call::Reply(msg) => reply_json(self.exchange(msg).await?).map_err(CallError::Rejected),
+        }
+    }
+
+    /// Subscribes to watch notifications, subscribing the server itself
+    /// with the engine on first use.
+    pub(crate) async fn watch(
+        &self,
+    ) -> Result<broadcast::Receiver<Arc<WatchNotification>>, CallError> {
+        self.0
+            .watching
+            .get_or_try_init(|| async {
+                let (sender, mut receiver) = mpsc::unbounded_channel();
+                match self
+                    .exchange(EngineMessage::WatchBlockSubReq(self.0.id, sender))
+                    .await?
+                {
+                    EngineMessage::WatchBlockSubRes(Ok(_)) => {}
+                    other => return Err(CallError::Rejected(format!("{other:?}"))),
+                }
+
+                let watches = self.0.watches.clone();
+                tokio::spawn(async move {
+                    while let Some(msg) = receiver.recv().await {
+                        // No receivers just means no socket is open.
+                        let _ = watches.send(Arc::new(msg.into()));
+                    }
+                });
+                Ok(())
+            })
+            .await?;
+
+        Ok(self.0.watches.subscribe())
+    }
+
+    /// Subscribes to alarm transitions, subscribing the server itself
+    /// with the alarm table on first use. Fails if the server was given
+    /// no alarm table.
+    pub(crate) async fn alarms(&self) -> Result<broadcast::Receiver<Arc<AlarmEvent>>, CallError> {
+        let Some(table) = self.0.alarm_table.get() else {
+            return Err(CallError::Rejected(AlarmError::NotEnabled.to_string()));
+        };
+        self.0
+            .alarming
+            .get_or_init(|| async {
+                let alarms = self.0.alarms.clone();
+                table.subscribe(self.0.id, move |event| {
+                    // No receivers just means no socket is open.
+                    let _ = alarms.send(Arc::new(event.clone()));
+                    true
+                });
+            })
+            .await;
+
+        Ok(self.0.alarms.subscribe())
+    }
+
+    /// Sends a request and waits for its reply. Runs on its own task so a
+    /// client hanging up mid-request cannot leave the reply behind for
+    /// the next caller.
+    async fn exchange(&self, msg: Messages) -> Result<Messages, CallError> {
+        let shared = self.0.clone();
+        let exchange = tokio::spawn(async move {
+            let mut channel = shared.channel.lock().await;
+            channel.0.send(msg).await.map_err(|_| CallError::Stopped)?;
+            channel.1.recv().await.ok_or(CallError::Stopped)
+        });
+        exchange.await.unwrap_or(Err(CallError::Stopped))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures::{SinkExt, StreamExt};
+    use serde_json::{Value as Json, json};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async, tungstenite::Message};
+
+    use super::EngineServer;
+    use crate::base::alarm::AlarmTable;
+    use crate::base::engine::Engine;
+    use crate::base::history::{History, MemoryStore};
+    use crate::single_threaded::SingleThreadedEngine;
+
+    /// Sends one HTTP/1.1 request and returns the status and JSON body.
+    async fn http(addr: &str, method: &str, path: &str, body: Option<Json>) -> (u16, Json) {
+        let body = body.map(|body| body.to_string()).unwrap_or_default();
+        let (status, body) = http_text(addr, method, path, "application/json", &body).await;
+        (status, serde_json::from_str(&body).unwrap_or(Json::Null))
+    }
+
+    /// Sends one HTTP/1.1 request and returns the status and body text.
+    async fn http_text(
+        addr: &str,
+        method: &str,
+        path: &str,
+        content_type: &str,
+        body: &str,
+    ) -> (u16, String) {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let request = format!(
+            "{method} {path} HTTP/1.1\r\nHost: {addr}\r\nConnection: close\r\n\
+             Content-Type: {content_type}\r\nAccept: {content_type}\r\n\
+             Content-Length: {}\r\n\r\n{body}",
+            body.len()
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        let status = response[9..12].parse().unwrap();
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        (status, body.to_string())
+    }
+
+    async fn next_frame(socket: &mut WebSocketStream<MaybeTlsStream<TcpStream>>) -> Json {
+        loop {
+            if let Some(Ok(Message::Text(text))) = socket.next().await {
+                return serde_json::from_str(&text).unwrap();
+            }
+        }
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn serves_rest_and_websocket_on_loopback() {
+        let mut engine = SingleThreadedEngine::new();
+        engine.set_history(History::new(MemoryStore::new(10)));
+        let alarms = AlarmTable::new();
+        engine.set_alarms(alarms.clone());
+        let server = EngineServer::new(&mut engine).with_alarms(alarms);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(server.serve(listener));
+
+        let client = async {
+            let (status, catalog) = http(&addr, "GET", "/api/catalog", None).await;
+            assert_eq!(status, 200);
+            assert!(
+                catalog
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .any(|block| block["name"] == "Add")
+            );
+
+            let (mut socket, _) = connect_async(format!("ws://{addr}/api/ws")).await.unwrap();
+
+            let (status, id) = http(
+                &addr,
+                "POST",
+                "/api/blocks",
+                Some(json!({ "name": "Add", "lib": "core" })),
+            )
+            .await;
+            assert_eq!(status, 200);
+            let id = id.as_str().unwrap().to_string();
+
+            let (status, _) = http(
+                &addr,
+                "PUT",
+                &format!("/api/blocks/{id}/history/in0"),
+                Some(json!("onChange")),
+            )
+            .await;
+            assert_eq!(status, 200);
+
+            let (status, _) = http(
+                &addr,
+                "PUT",
+                &format!("/api/blocks/{id}/inputs/in0"),
+                Some(json!(40)),
+            )
+            .await;
+            assert_eq!(status, 200);
+
+            // Notifications for the block stream to the socket.
+            loop {
+                let frame = next_frame(&mut socket).await;
+                if frame["watch"]["id"] == id.as_str() {
+                    break;
+                }
+            }
+
+            socket
+                .send(Message::Text(
+                    json!({ "id": 7, "type": "inspectBlock", "block": id })
+                        .to_string()
+                        .into(),
+                ))
+                .await
+                .unwrap();
+            let reply = loop {
+                let frame = next_frame(&mut socket).await;
+                if frame.get("watch").is_none() {
+                    break frame;
+                }
+            };
+            assert_eq!(reply["id"], 7);
+            assert_eq!(reply["result"]["name"], "Add");
+            assert_eq!(reply["result"]["inputs"]["in0"]["val"], 40);
+
+            socket
+                .send(Message::Text(
+                    json!({
+                        "id": 8, "type": "queryHistory", "block": id, "pin": "in0",
+                        "rollup": "last",
+                    })
+                    .to_string()
+                    .into(),
+                ))
+                .await
+                .unwrap();
+            let reply = loop {
+                let frame = next_frame(&mut socket).await;
+                if frame.get("watch").is_none() {
+                    break frame;
+                }
+            };
+            assert_eq!(reply["id"], 8);
+            assert_eq!(reply["result"][0]["value"], 40);
+            assert_eq!(reply["result"][0]["status"], "Ok");
+
+            // The trend was seeded with the value before the write.
+            let (status, samples) =
+                http(&addr, "GET", &format!("/api/blocks/{id}/history/in0"), None).await;
+            assert_eq!(status, 200);
+            assert_eq!(samples.as_array().unwrap().len(), 2);
+            let (status, _) = http(
+                &addr,
+                "GET",
+                &format!("/api/blocks/{id}/history/in0?interval=0&rollup=avg"),
+                None,
+            )
+            .await;
+            assert_eq!(status, 400);
+
+            // Alarm transitions stream to the socket too.
+            let (_, alarm) = http(
+                &addr,
+                "POST",
+                "/api/blocks",
+                Some(json!({ "name": "BoolAlarm", "lib": "core" })),
+            )
+            .await;
+            let alarm = alarm.as_str().unwrap().to_string();
+            let (status, _) = http(
+                &addr,
+                "PUT",
+                &format!("/api/blocks/{alarm}/inputs/in"),
+                Some(json!(true)),
+            )
+            .await;
+            assert_eq!(status, 200);
+            let event = loop {
+                if let Some(event) = next_frame(&mut socket).await.get("alarm") {
+                    break event.clone();
+                }
+            };
+            assert_eq!(event["transition"], "raised");
+            assert_eq!(event["source"], alarm.as_str());
+
+            let (status, _) = http(
+                &addr,
+                "POST",
+                &format!("/api/alarms/{alarm}/acknowledge"),
+                None,
+            )
+            .await;
+            assert_eq!(status, 200);
+            let (status, alarms) = http(&addr, "GET", "/api/alarms", None).await;
+            assert_eq!(status, 200);
+            assert_eq!(alarms[0]["state"], "acknowledged");
+            let (status, _) = http(
+                &addr,
+                "PUT",
+                &format!("/api/alarms/{id}/shelve"),
+                Some(json!(60_000)),
+            )
+            .await;
+            assert_eq!(status, 400);
+
+            let (status, error) = http(&addr, "DELETE", "/api/blocks/nope", None).await;
+            assert_eq!(status, 400);
+            assert!(error["error"].as_str().unwrap().contains("nope"));
+
+            let (status, _) = http(&addr, "POST", "/api/engine/stop", None).await;
+            assert_eq!(status, 200);
+        };
+
+        tokio::join!(engine.run(), client);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn serves_haystack_points_on_loopback() {
+        let mut engine = SingleThreadedEngine::new();
+        let server = EngineServer::new(&mut engine);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(server.serve(listener));
+
+        let zinc = |op: &'static str, body: String| {
+            let addr = addr.clone();
+            async move {
+                let path = format!("/haystack/{op}");
+                let (status, body) = http_text(&addr, "POST", &path, "text/zinc", &body).await;
+                assert_eq!(status, 200);
+                body
+            }
+        };
+
+        let client = async {
+            let (_, id) = http(
+                &addr,
+                "POST",
+                "/api/blocks",
+                Some(json!({ "name": "Add", "lib": "core" })),
+            )
+            .await;
+            let id = id.as_str().unwrap().to_string();
+
+            let (status, about) = http(&addr, "GET", "/haystack/about", None).await;
+            assert_eq!(status, 200);
+            assert_eq!(about["rows"][0]["productName"], "logic-mesh");
+
+            let (_, points) = http(
+                &addr,
+                "GET",
+                "/haystack/read?filter=point%20and%20writable",
+                None,
+            )
+            .await;
+            let points = points["rows"].as_array().unwrap();
+            assert!(points.iter().any(|point| point["pin"] == "in0"));
+            assert!(points.iter().all(|point| point["logicInput"].is_object()));
+
+            // Level 8 wins over level 16 until released.
+            let write = format!("ver:\"3.0\"\nid,level,val,who\n@{id}.in0,16,1,\"ui\"\n");
+            assert!(!zinc("pointWrite", write).await.contains("err"));
+            let write = format!("ver:\"3.0\"\nid,level,val,who\n@{id}.in0,8,40,\"op\"\n");
+            assert!(!zinc("pointWrite", write).await.contains("err"));
+
+            let (_, read) = http(&addr, "GET", &format!("/haystack/read?id=@{id}.in0"), None).await;
+            assert_eq!(read["rows"][0]["curVal"], 40);
+            assert_eq!(read["rows"][0]["curStatus"], "ok");
+            assert_eq!(read["rows"][0]["equipRef"]["val"], id.as_str());
+
+            let (_, array) = http(
+                &addr,
+                "GET",
+                &format!("/haystack/pointWrite?id=@{id}.in0"),
+                None,
+            )
+            .await;
+            assert_eq!(array["rows"][7]["who"], "op");
+            assert_eq!(array["rows"][15]["val"], 1);
+
+            let sub = format!("ver:\"3.0\" watchDis:\"test\"\nid\n@{id}.in0\n");
+            let sub = zinc("watchSub", sub).await;
+            let watch_id = sub
+                .split("watchId:\"")
+                .nth(1)
+                .and_then(|rest| rest.split('"').next())
+                .unwrap()
+                .to_string();
+            assert!(sub.contains("40"));
+
+            // Releasing level 8 falls back to level 16, which the watch
+            // reports on the next poll.
+            let release = format!("ver:\"3.0\"\nid,level,val\n@{id}.in0,8,N\n");
+            zinc("pointWrite", release).await;
+            let poll = format!("ver:\"3.0\" watchId:\"{watch_id}\"\nempty\n");
+            let poll = zinc("watchPoll", poll).await;
+            assert!(poll.contains("curVal"), "{poll}");
+
+            let poll = format!("ver:\"3.0\" watchId:\"{watch_id}\"\nempty\n");
+            let poll = zinc("watchPoll", poll).await;
+            assert!(!poll.contains("curVal"), "{poll}");
+
+            // Once every level is released, the value the pin was last
+            // given is its relinquish default.
+            let release = format!("ver:\"3.0\"\nid,level,val\n@{id}.in0,16,N\n");
+            assert!(!zinc("pointWrite", release).await.contains("err"));
+            let (status, _) = http(
+                &addr,
+                "PUT",
+                &format!("/api/blocks/{id}/inputs/in0"),
+                Some(json!(7)),
+            )
+            .await;
+            assert_eq!(status, 200);
+            let (_, array) = http(
+                &addr,
+                "GET",
+                &format!("/haystack/pointWrite?id=@{id}.in0"),
+                None,
+            )
+            .await;
+            assert_eq!(array["rows"][16]["val"], 7);
+            assert_eq!(array["rows"][16]["who"], "default");
+
+            let (_, nav) = http(&addr, "GET", "/haystack/nav", None).await;
+            assert_eq!(nav["rows"][0]["navId"], "default");
+
+            let (_, error) = http(&addr, "GET", "/haystack/bogus", None).await;
+            assert!(error["meta"]["err"].is_object());
+
+            http(&addr, "POST", "/api/engine/stop", None).await;
+        };
+
+        tokio::join!(engine.run(), client);
+    }
+}
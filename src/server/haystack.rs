@@ -0,0 +1,493 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Haystack HTTP API presenting block pins as points.
+//!
+//! Served under `/haystack/{op}`, by `GET` with the request as query
+//! parameters or by `POST` with a request grid. Grids are Zinc, or
+//! Hayson when the request's `Accept` or `Content-Type` is
+//! `application/json`. Failed requests answer an error grid.
+//!
+//! | Op            | Request                                            |
+//! |---------------|----------------------------------------------------|
+//! | `about`       |                                                    |
+//! | `ops`         |                                                    |
+//! | `formats`     |                                                    |
+//! | `read`        | `filter` and optional `limit`, or rows of `id`     |
+//! | `nav`         | optional `navId`: a program id, then a block id    |
+//! | `watchSub`    | meta `watchDis` or `watchId`, `lease`; rows of `id`|
+//! | `watchUnsub`  | meta `watchId`, `close`; rows of `id`              |
+//! | `watchPoll`   | meta `watchId`, `refresh`                          |
+//! | `pointWrite`  | `id`, `level`, `val`, `who`; or just `id` to read  |
+//!
+//! Block and point records are described in [`points`]. Writes go
+//! through a 17 level priority array per point, so a client commanding
+//! at level 8 overrides one at level 16 until it releases its level by
+//! writing `val: N`. While no level is set, the point keeps the value it
+//! was last given, which becomes its relinquish default. A write the
+//! engine rejects leaves the array as it was.
+
+mod points;
+mod priority;
+mod watch;
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::{
+    Router,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use libhaystack::{
+    encoding::zinc,
+    filter::{Filter, Filtered},
+    units::get_unit,
+    val::{DateTime, Dict, Grid, HaystackDict, Number, Str, Uri, Value},
+};
+
+use crate::base::engine::messages::EngineMessage;
+use crate::base::program::grid::grid_to_zinc;
+
+use super::{CallError, Connection};
+use points::{Snapshot, inspect, split_id};
+use priority::{Command, LEVELS, PriorityArray, to_grid};
+use watch::Watches;
+
+/// Haystack state kept by the server between requests.
+pub(crate) struct Tables {
+    watches: Mutex<Watches>,
+    /// Each point's array, locked for the whole of a `pointWrite` so
+    /// writes reach the engine in the order they change the array.
+    priorities: Mutex<BTreeMap<String, Arc<tokio::sync::Mutex<PriorityArray>>>>,
+    booted: DateTime,
+}
+
+impl Tables {
+    pub(crate) fn new() -> Self {
+        Self {
+            watches: Mutex::default(),
+            priorities: Mutex::default(),
+            booted: DateTime::utc_now(),
+        }
+    }
+
+    /// The priority array of the point `id`.
+    fn priorities(&self, id: &str) -> Arc<tokio::sync::Mutex<PriorityArray>> {
+        self.priorities
+            .lock()
+            .unwrap()
+            .entry(id.to_string())
+            .or_insert_with(|| {
+                let array = PriorityArray::new(Command::relinquish(Value::Null));
+                Arc::new(tokio::sync::Mutex::new(array))
+            })
+            .clone()
+    }
+}
+
+pub(super) fn routes() -> Router<Connection> {
+    Router::new().route("/haystack/{op}", get(op).post(op))
+}
+
+/// The supported ops, with a summary each.
+const OPS: [(&str, &str); 9] = [
+    ("about", "Summary information for the server"),
+    ("ops", "Operations supported by the server"),
+    ("formats", "Grid formats supported by the server"),
+    ("read", "Read records by id or filter"),
+    ("nav", "Navigate programs, blocks and points"),
+    ("watchSub", "Subscribe to records"),
+    ("watchUnsub", "Unsubscribe from records"),
+    ("watchPoll", "Poll a watch for changes"),
+    ("pointWrite", "Read or write a point's priority array"),
+];
+
+const JSON: &str = "application/json";
+const ZINC: &str = "text/zinc";
+
+async fn op(
+    State(connection): State<Connection>,
+    Path(op): Path<String>,
+    Query(params): Query<BTreeMap<String, String>>,
+    headers: HeaderMap,
+    body: String,
+) -> Response {
+    let is_json = |name| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.contains(JSON))
+    };
+
+    let request = if body.trim().is_empty() {
+        Ok(query_grid(params))
+    } else {
+        decode(&body, is_json(header::CONTENT_TYPE))
+    };
+    let response = match request {
+        Ok(request) => dispatch(&connection, &op, &request).await,
+        Err(err) => Err(err),
+    };
+    let grid = response.unwrap_or_else(|err| Grid::make_err(&err));
+
+    encode(
+        grid,
+        is_json(header::ACCEPT) || is_json(header::CONTENT_TYPE),
+    )
+}
+
+async fn dispatch(connection: &Connection, op: &str, request: &Grid) -> Result<Grid, String> {
+    let tables = &connection.0.haystack;
+    match op {
+        "about" => Ok(about(tables)),
+        "ops" => Ok(grid(
+            OPS.iter()
+                .map(|(name, summary)| {
+                    let mut row = Dict::new();
+                    row.insert("name".into(), (*name).into());
+                    row.insert("summary".into(), (*summary).into());
+                    row
+                })
+                .collect(),
+        )),
+        "formats" => Ok(grid(
+            [ZINC, JSON]
+                .into_iter()
+                .map(|mime| {
+                    let mut row = Dict::new();
+                    row.insert("mime".into(), mime.into());
+                    row.insert("receive".into(), Value::Marker);
+                    row.insert("send".into(), Value::Marker);
+                    row
+                })
+                .collect(),
+        )),
+        "read" => read(connection, request).await,
+        "nav" => nav(connection, request).await,
+        "watchSub" => watch_sub(connection, request).await,
+        "watchUnsub" => watch_unsub(tables, request),
+        "watchPoll" => watch_poll(connection, request).await,
+        "pointWrite" => point_write(connection, request).await,
+        _ => Err(format!("Unknown op '{op}'")),
+    }
+}
+
+fn about(tables: &Tables) -> Grid {
+    let version = env!("CARGO_PKG_VERSION");
+    let mut row = Dict::new();
+    row.insert("haystackVersion".into(), "3.0".into());
+    row.insert("tz".into(), "UTC".into());
+    row.insert("serverName".into(), "logic-mesh".into());
+    row.insert("serverTime".into(), DateTime::utc_now().into());
+    row.insert("serverBootTime".into(), tables.booted.into());
+    row.insert("productName".into(), "logic-mesh".into());
+    row.insert(
+        "productUri".into(),
+        Uri::make(env!("CARGO_PKG_REPOSITORY")).into(),
+    );
+    row.insert("productVersion".into(), version.into());
+    row.insert("moduleName".into(), env!("CARGO_PKG_NAME").into());
+    row.insert("moduleVersion".into(), version.into());
+    grid(vec![row])
+}
+
+async fn read(connection: &Connection, request: &Grid) -> Result<Grid, String> {
+    let first = request.rows.first().ok_or("Request has no rows")?;
+    let snapshot = Snapshot::take(connection).await.map_err(stringify)?;
+
+    if let Some(Str { value: filter }) = first.get_str("filter") {
+        let filter =
+            Filter::try_from(filter.as_str()).map_err(|err| format!("Invalid filter: {err}"))?;
+        let limit = first
+            .get_num("limit")
+            .map_or(usize::MAX, |limit| limit.value.max(0.0) as usize);
+        let rows = snapshot
+            .records()
+            .into_iter()
+            .filter(|record| record.filter(&filter))
+            .take(limit)
+            .collect();
+        return Ok(grid(rows));
+    }
+
+    let rows = request
+        .rows
+        .iter()
+        .map(|row| {
+            row.get_ref("id")
+                .and_then(|id| snapshot.record(&id.value))
+                .unwrap_or_default()
+        })
+        .collect();
+    Ok(grid(rows))
+}
+
+async fn nav(connection: &Connection, request: &Grid) -> Result<Grid, String> {
+    let nav_id = request.rows.first().and_then(|row| match row.get("navId") {
+        Some(Value::Str(id)) => Some(id.value.clone()),
+        Some(Value::Ref(id)) => Some(id.value.clone()),
+        _ => None,
+    });
+    let snapshot = Snapshot::take(connection).await.map_err(stringify)?;
+
+    let Some(nav_id) = nav_id else {
+        let rows = snapshot
+            .programs
+            .iter()
+            .map(|program| {
+                let mut row = Dict::new();
+                row.insert("navId".into(), program.id.as_str().into());
+                row.insert(
+                    "dis".into(),
+                    program.name.as_deref().unwrap_or(&program.id).into(),
+                );
+                row.insert("logicProgram".into(), Value::Marker);
+                row.insert("programId".into(), program.id.as_str().into());
+                row
+            })
+            .collect();
+        return Ok(grid(rows));
+    };
+
+    if snapshot.programs.iter().any(|program| program.id == nav_id) {
+        let rows = snapshot
+            .blocks
+            .iter()
+            .filter(|block| block.program == nav_id)
+            .map(|block| {
+                let mut row = block.record();
+                row.insert("navId".into(), block.id.to_string().as_str().into());
+                row
+            })
+            .collect();
+        return Ok(grid(rows));
+    }
+
+    match split_id(&nav_id) {
+        Some((id, None)) => {
+            let block = snapshot
+                .blocks
+                .iter()
+                .find(|block| block.id == id)
+                .ok_or_else(|| format!("Unknown navId '{nav_id}'"))?;
+            Ok(grid(block.points().collect()))
+        }
+        _ => Err(format!("Unknown navId '{nav_id}'")),
+    }
+}
+
+async fn watch_sub(connection: &Connection, request: &Grid) -> Result<Grid, String> {
+    let meta = request.meta.clone().unwrap_or_default();
+    let lease = meta.get_num("lease").map_or(watch::DEFAULT_LEASE, lease);
+    let ids: Vec<String> = request
+        .rows
+        .iter()
+        .filter_map(|row| row.get_ref("id").map(|id| id.value.clone()))
+        .collect();
+    let snapshot = Snapshot::take(connection).await.map_err(stringify)?;
+    let records: Vec<_> = ids.iter().map(|id| snapshot.record(id)).collect();
+
+    let mut watches = connection.0.haystack.watches.lock().unwrap();
+    watches.expire(Instant::now());
+    let watch_id = match meta.get_str("watchId") {
+        Some(Str { value }) => value.clone(),
+        None => {
+            let dis = meta
+                .get_str("watchDis")
+                .map_or("Haystack watch".to_string(), |dis| dis.value.clone());
+            watches.open(dis, lease)
+        }
+    };
+    let watch = watches
+        .get_mut(&watch_id)
+        .ok_or_else(|| format!("Unknown watch '{watch_id}'"))?;
+    watch.add(ids.into_iter().zip(records.iter().cloned()));
+
+    let mut meta = Dict::new();
+    meta.insert("watchId".into(), watch_id.as_str().into());
+    meta.insert("watchDis".into(), watch.dis.as_str().into());
+    meta.insert("lease".into(), seconds(watch.lease).into());
+    let rows = records.into_iter().map(Option::unwrap_or_default).collect();
+    Ok(grid_with_meta(rows, meta))
+}
+
+fn watch_unsub(tables: &Tables, request: &Grid) -> Result<Grid, String> {
+    let meta = request.meta.clone().unwrap_or_default();
+    let watch_id = meta.get_str("watchId").ok_or("Missing watchId")?;
+
+    let mut watches = tables.watches.lock().unwrap();
+    if meta.has_marker("close") {
+        watches.close(&watch_id.value);
+    } else if let Some(watch) = watches.get_mut(&watch_id.value) {
+        watch.remove(
+            request
+                .rows
+                .iter()
+                .filter_map(|row| row.get_ref("id").map(|id| id.value.as_str())),
+        );
+    }
+    Ok(Grid::make_empty())
+}
+
+async fn watch_poll(connection: &Connection, request: &Grid) -> Result<Grid, String> {
+    let meta = request.meta.clone().unwrap_or_default();
+    let watch_id = meta.get_str("watchId").ok_or("Missing watchId")?;
+    let unknown = || format!("Unknown watch '{}'", watch_id.value);
+
+    {
+        let mut watches = connection.0.haystack.watches.lock().unwrap();
+        watches.expire(Instant::now());
+        watches.get_mut(&watch_id.value).ok_or_else(unknown)?;
+    }
+    let snapshot = Snapshot::take(connection).await.map_err(stringify)?;
+
+    let mut watches = connection.0.haystack.watches.lock().unwrap();
+    let watch = watches.get_mut(&watch_id.value).ok_or_else(unknown)?;
+    let rows = watch.poll(meta.has_marker("refresh"), |id| snapshot.record(id));
+
+    let mut meta = Dict::new();
+    meta.insert("watchId".into(), watch_id.value.as_str().into());
+    Ok(grid_with_meta(rows, meta))
+}
+
+async fn point_write(connection: &Connection, request: &Grid) -> Result<Grid, String> {
+    let row = request.rows.first().ok_or("Request has no rows")?;
+    let id = row.get_ref("id").ok_or("Missing id")?.value.clone();
+    let Some((block, Some(pin))) = split_id(&id) else {
+        return Err(format!("'{id}' is not a point"));
+    };
+
+    let priorities = connection.0.haystack.priorities(&id);
+    let mut array = priorities.lock().await;
+
+    let def = inspect(connection, block).await.map_err(stringify)?;
+    let input = def
+        .inputs
+        .get(pin)
+        .filter(|input| !input.is_connected)
+        .ok_or_else(|| format!("Point '{id}' is not writable"))?;
+    if !array.is_commanded() {
+        array.relinquish_default = Command::relinquish(input.val.clone());
+    }
+
+    let Some(level) = row.get_num("level") else {
+        return Ok(to_grid(&array));
+    };
+    if level.value.fract() != 0.0 || !(1.0..=LEVELS as f64).contains(&level.value) {
+        return Err(format!("Invalid level, expected 1 to {LEVELS}"));
+    }
+    let level = level.value as usize;
+    let val = row.get("val").filter(|val| !val.is_null()).cloned();
+    let who = row
+        .get_str("who")
+        .map_or("haystack".to_string(), |who| who.value.clone());
+
+    let command = val.map(|val| Command { val, who });
+    priority::command(
+        &mut array,
+        level,
+        command,
+        async |effective| match connection
+            .exchange(EngineMessage::WriteBlockInputReq(
+                connection.0.id,
+                block,
+                pin.to_string(),
+                effective,
+            ))
+            .await
+            .map_err(stringify)?
+        {
+            EngineMessage::WriteBlockInputRes(res) => res.map(drop),
+            other => Err(format!("Invalid response: {other:?}")),
+        },
+    )
+    .await
+    .map(|()| Grid::make_empty())
+}
+
+/// Converts a `lease` number to a duration, in seconds unless it has a
+/// time unit.
+fn lease(lease: &Number) -> Duration {
+    let secs = match lease.unit.map(|unit| unit.symbol()) {
+        Some("ms") => lease.value / 1000.0,
+        Some("min") => lease.value * 60.0,
+        Some("h" | "hr") => lease.value * 3600.0,
+        _ => lease.value,
+    };
+    Duration::from_secs_f64(secs.max(1.0))
+}
+
+fn seconds(duration: Duration) -> Number {
+    match get_unit("s") {
+        Some(unit) => Number::make_with_unit(duration.as_secs_f64(), unit),
+        None => Number::make(duration.as_secs_f64()),
+    }
+}
+
+fn stringify(err: CallError) -> String {
+    err.to_string()
+}
+
+/// A grid of `rows`, with the `empty` column when there are none.
+fn grid(rows: Vec<Dict>) -> Grid {
+    grid_with_meta(rows, Dict::new())
+}
+
+fn grid_with_meta(rows: Vec<Dict>, meta: Dict) -> Grid {
+    let mut grid = Grid::make_from_dicts_with_meta(rows, meta);
+    if grid.columns.is_empty() {
+        grid.columns = Grid::make_empty().columns;
+    }
+    grid
+}
+
+/// A request grid of one row from `GET` query parameters. Values are
+/// decoded as Zinc scalars, falling back to strings; `filter` is always
+/// a string.
+fn query_grid(params: BTreeMap<String, String>) -> Grid {
+    let row = params
+        .into_iter()
+        .map(|(name, param)| {
+            let value = match zinc::decode::from_str(&param) {
+                Ok(Value::Str(value)) => Value::Str(value),
+                Ok(value) if name != "filter" && !matches!(value, Value::Grid(_)) => value,
+                _ => param.as_str().into(),
+            };
+            (name, value)
+        })
+        .collect();
+    grid(vec![row])
+}
+
+fn decode(body: &str, is_json: bool) -> Result<Grid, String> {
+    if is_json {
+        return serde_json::from_str(body).map_err(|err| format!("Invalid request grid: {err}"));
+    }
+    match zinc::decode::from_str(body) {
+        Ok(Value::Grid(grid)) => Ok(grid),
+        Ok(_) => Err("Request is not a grid".to_string()),
+        Err(err) => Err(format!("Invalid request grid: {err}")),
+    }
+}
+
+fn encode(grid: Grid, as_json: bool) -> Response {
+    let body = if as_json {
+        serde_json::to_string(&grid).map_err(|err| err.to_string())
+    } else {
+        grid_to_zinc(grid).map_err(|err| err.to_string())
+    };
+    match body {
+        Ok(body) => {
+            let mime = if as_json { JSON } else { ZINC };
+            (
+                [(header::CONTENT_TYPE, format!("{mime}; charset=utf-8"))],
+                body,
+            )
+                .into_response()
+        }
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err).into_response(),
+    }
+}
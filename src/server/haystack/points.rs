@@ -0,0 +1,220 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Haystack records for the programs, blocks and pins of an engine.
+//!
+//! Each block is an `equip` with id `@<block uuid>`, and each of its pins
+//! a `point` with id `@<block uuid>.<pin>`:
+//!
+//! | Tag           | On      | Value                                         |
+//! |---------------|---------|-----------------------------------------------|
+//! | `logicBlock`  | equip   | marker                                        |
+//! | `blockName`   | equip   | block type, e.g. `"Add"`                      |
+//! | `blockLib`    | equip   | block library, e.g. `"core"`                  |
+//! | `programId`   | both    | id of the program the block belongs to        |
+//! | `equipRef`    | point   | the block                                     |
+//! | `pin`         | point   | pin name                                      |
+//! | `logicInput`  | point   | marker on input pins                          |
+//! | `logicOutput` | point   | marker on output pins                         |
+//! | `writable`    | point   | marker on inputs that are not linked          |
+//! | `kind`        | point   | `Number`, `Bool` or `Str` for scalar pins     |
+//! | `curVal`      | point   | current value, with `unit` for numbers        |
+//! | `curStatus`   | point   | `ok`, `fault`, `disabled` or `down`           |
+//! | `curErr`      | point   | the block's fault reason                      |
+//!
+//! `dis` is the block label, or its type name, followed by the pin name.
+
+use libhaystack::val::{Dict, Ref, Str, Value};
+use uuid::Uuid;
+
+use crate::base::engine::messages::{BlockDefinition, EngineMessage, ProgramSummary};
+
+use super::super::{CallError, Connection};
+
+/// One block as read from the engine.
+pub(super) struct BlockView {
+    pub(super) program: String,
+    pub(super) id: Uuid,
+    pub(super) label: Option<String>,
+    pub(super) def: BlockDefinition,
+}
+
+/// The engine's programs and blocks at one point in time.
+pub(super) struct Snapshot {
+    pub(super) programs: Vec<ProgramSummary>,
+    pub(super) blocks: Vec<BlockView>,
+}
+
+impl Snapshot {
+    /// Reads every program and block from the engine.
+    pub(super) async fn take(connection: &Connection) -> Result<Self, CallError> {
+        let sender = connection.0.id;
+        let programs = match connection
+            .exchange(EngineMessage::ListProgramsReq(sender))
+            .await?
+        {
+            EngineMessage::ListProgramsRes(programs) => programs,
+            other => return Err(unexpected(&other)),
+        };
+
+        let mut blocks = Vec::new();
+        for summary in &programs {
+            let program = match connection
+                .exchange(EngineMessage::GetProgramReq(sender, summary.id.clone()))
+                .await?
+            {
+                EngineMessage::GetProgramRes(res) => res.map_err(CallError::Rejected)?,
+                other => return Err(unexpected(&other)),
+            };
+
+            for (id, block) in program.blocks {
+                let Ok(id) = Uuid::parse_str(&id) else {
+                    continue;
+                };
+                blocks.push(BlockView {
+                    program: summary.id.clone(),
+                    id,
+                    label: block.label,
+                    def: inspect(connection, id).await?,
+                });
+            }
+        }
+
+        Ok(Self { programs, blocks })
+    }
+
+    /// Every block and point record.
+    pub(super) fn records(&self) -> Vec<Dict> {
+        self.blocks
+            .iter()
+            .flat_map(|block| std::iter::once(block.record()).chain(block.points()))
+            .collect()
+    }
+
+    /// The block or point record with the given id.
+    pub(super) fn record(&self, id: &str) -> Option<Dict> {
+        let (block, pin) = split_id(id)?;
+        let block = self.blocks.iter().find(|view| view.id == block)?;
+        match pin {
+            None => Some(block.record()),
+            Some(pin) => block.points().find(|point| pin_of(point) == Some(pin)),
+        }
+    }
+}
+
+impl BlockView {
+    fn dis(&self) -> &str {
+        self.label.as_deref().unwrap_or(&self.def.name)
+    }
+
+    fn block_ref(&self) -> Ref {
+        Ref::make(&self.id.to_string(), Some(self.dis()))
+    }
+
+    /// The block's `equip` record.
+    pub(super) fn record(&self) -> Dict {
+        let mut dict = Dict::new();
+        dict.insert("id".into(), self.block_ref().into());
+        dict.insert("dis".into(), self.dis().into());
+        dict.insert("equip".into(), Value::Marker);
+        dict.insert("logicBlock".into(), Value::Marker);
+        dict.insert("blockName".into(), self.def.name.as_str().into());
+        dict.insert("blockLib".into(), self.def.library.as_str().into());
+        dict.insert("programId".into(), self.program.as_str().into());
+        dict
+    }
+
+    /// The `point` records of the block's inputs, then its outputs.
+    pub(super) fn points(&self) -> impl Iterator<Item = Dict> + '_ {
+        let inputs = self.def.inputs.iter().map(|(pin, data)| {
+            let mut point = self.point(pin, &data.kind, &data.val);
+            point.insert("logicInput".into(), Value::Marker);
+            if !data.is_connected {
+                point.insert("writable".into(), Value::Marker);
+            }
+            point
+        });
+        let outputs = self.def.outputs.iter().map(|(pin, data)| {
+            let mut point = self.point(pin, &data.kind, &data.val);
+            point.insert("logicOutput".into(), Value::Marker);
+            point
+        });
+        inputs.chain(outputs)
+    }
+
+    fn point(&self, pin: &str, kind: &str, val: &Value) -> Dict {
+        let dis = format!("{} {pin}", self.dis());
+        let id = Ref::make(&format!("{}.{pin}", self.id), Some(&dis));
+
+        let mut dict = Dict::new();
+        dict.insert("id".into(), id.into());
+        dict.insert("dis".into(), dis.as_str().into());
+        dict.insert("point".into(), Value::Marker);
+        dict.insert("equipRef".into(), self.block_ref().into());
+        dict.insert("programId".into(), self.program.as_str().into());
+        dict.insert("pin".into(), pin.into());
+
+        let kind = match kind {
+            "number" => Some("Number"),
+            "bool" => Some("Bool"),
+            "str" => Some("Str"),
+            _ => None,
+        };
+        if let Some(kind) = kind {
+            dict.insert("kind".into(), kind.into());
+        }
+
+        if !val.is_null() {
+            if let Value::Number(number) = val
+                && let Some(unit) = number.unit
+            {
+                dict.insert("unit".into(), unit.symbol().into());
+            }
+            dict.insert("curVal".into(), val.clone());
+        }
+
+        let status = match self.def.state.as_str() {
+            "running" => "ok",
+            "fault" => "fault",
+            "disabled" => "disabled",
+            _ => "down",
+        };
+        dict.insert("curStatus".into(), status.into());
+        if let Some(reason) = &self.def.fault_reason {
+            dict.insert("curErr".into(), reason.as_str().into());
+        }
+        dict
+    }
+}
+
+/// Splits a record id into its block and, for points, pin.
+pub(super) fn split_id(id: &str) -> Option<(Uuid, Option<&str>)> {
+    match id.split_once('.') {
+        Some((block, pin)) => Some((Uuid::parse_str(block).ok()?, Some(pin))),
+        None => Some((Uuid::parse_str(id).ok()?, None)),
+    }
+}
+
+fn pin_of(point: &Dict) -> Option<&str> {
+    match point.get("pin") {
+        Some(Value::Str(Str { value })) => Some(value),
+        _ => None,
+    }
+}
+
+/// Reads one block from the engine.
+pub(super) async fn inspect(
+    connection: &Connection,
+    block: Uuid,
+) -> Result<BlockDefinition, CallError> {
+    match connection
+        .exchange(EngineMessage::InspectBlockReq(connection.0.id, block))
+        .await?
+    {
+        EngineMessage::InspectBlockRes(res) => res.map_err(CallError::Rejected),
+        other => Err(unexpected(&other)),
+    }
+}
+
+fn unexpected<T: std::fmt::Debug>(reply: &T) -> CallError {
+    CallError::Rejected(format!("Invalid response: {reply:?}"))
+}
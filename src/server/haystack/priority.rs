@@ -0,0 +1,109 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Priority arrays for `pointWrite`.
+
+use libhaystack::val::{Dict, Grid, Number, Value};
+
+use crate::base::priority::Priorities;
+
+/// Number of priority levels; 1 is the highest.
+pub(super) const LEVELS: usize = 17;
+
+/// A value commanded at one level, and who commanded it.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Command {
+    pub(super) val: Value,
+    pub(super) who: String,
+}
+
+impl Command {
+    /// The relinquish default: the pin's own value while no level is set.
+    pub(super) fn relinquish(val: Value) -> Self {
+        Self {
+            val,
+            who: "default".to_string(),
+        }
+    }
+}
+
+/// The commanded values of one writable point.
+pub(super) type PriorityArray = Priorities<Command, LEVELS>;
+
+/// Commands `level`, or releases it when [`None`], and hands the point's
+/// new value to `write`. The level is restored if the write fails.
+pub(super) async fn command(
+    array: &mut PriorityArray,
+    level: usize,
+    command: Option<Command>,
+    write: impl AsyncFnOnce(Value) -> Result<(), String>,
+) -> Result<(), String> {
+    let previous = array.write(level, command);
+    let written = write(array.effective().val.clone()).await;
+    if written.is_err() {
+        array.write(level, previous);
+    }
+    written
+}
+
+/// The array as a `pointWrite` read response.
+pub(super) fn to_grid(array: &PriorityArray) -> Grid {
+    let rows = array
+        .levels()
+        .enumerate()
+        .map(|(index, level)| {
+            let mut row = Dict::new();
+            row.insert("level".into(), Number::make(index as f64 + 1.0).into());
+            row.insert("levelDis".into(), format!("{}", index + 1).as_str().into());
+            let level = match level {
+                None if index + 1 == LEVELS => Some(&array.relinquish_default),
+                level => level,
+            };
+            if let Some(Command { val, who }) = level
+                && !val.is_null()
+            {
+                row.insert("val".into(), val.clone());
+                row.insert("who".into(), who.as_str().into());
+            }
+            row
+        })
+        .collect();
+    Grid::make_from_dicts(rows)
+}
+
+#[cfg(test)]
+mod test {
+    use libhaystack::val::Value;
+
+    use super::{Command, PriorityArray, command};
+
+    fn op(val: i64) -> Option<Command> {
+        Some(Command {
+            val: Value::make_int(val),
+            who: "op".to_string(),
+        })
+    }
+
+    #[tokio::test]
+    async fn rejected_writes_are_undone() {
+        let mut array = PriorityArray::new(Command::relinquish(Value::make_int(0)));
+        command(&mut array, 8, op(8), async |val| {
+            assert_eq!(val, Value::make_int(8));
+            Ok(())
+        })
+        .await
+        .unwrap();
+
+        let rejected = command(&mut array, 1, op(1), async |val| {
+            assert_eq!(val, Value::make_int(1));
+            Err("rejected".to_string())
+        })
+        .await;
+        assert_eq!(rejected, Err("rejected".to_string()));
+        assert_eq!(array.level(1), None);
+        assert_eq!(array.effective().val, Value::make_int(8));
+
+        let rejected = command(&mut array, 8, None, async |_| Err("rejected".to_string())).await;
+        assert!(rejected.is_err());
+        assert_eq!(array.level(8), op(8).as_ref());
+    }
+}
@@ -0,0 +1,97 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Haystack watches: sets of record ids a client polls for changes.
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use libhaystack::val::Dict;
+use uuid::Uuid;
+
+/// Lease given to watches that do not ask for one.
+pub(super) const DEFAULT_LEASE: Duration = Duration::from_secs(60);
+
+/// The open watches of a server.
+#[derive(Default)]
+pub(crate) struct Watches {
+    watches: BTreeMap<String, Watch>,
+}
+
+/// One watch.
+pub(super) struct Watch {
+    pub(super) dis: String,
+    pub(super) lease: Duration,
+    /// Record ids, each with the record as last sent to the client.
+    ids: BTreeMap<String, Option<Dict>>,
+    touched: Instant,
+}
+
+impl Watches {
+    /// Closes the watches whose lease ran out.
+    pub(super) fn expire(&mut self, now: Instant) {
+        self.watches
+            .retain(|_, watch| now.duration_since(watch.touched) < watch.lease);
+    }
+
+    /// Opens a watch and returns its id.
+    pub(super) fn open(&mut self, dis: String, lease: Duration) -> String {
+        let id = Uuid::new_v4().to_string();
+        self.watches.insert(
+            id.clone(),
+            Watch {
+                dis,
+                lease,
+                ids: BTreeMap::new(),
+                touched: Instant::now(),
+            },
+        );
+        id
+    }
+
+    /// The watch with the given id, renewing its lease.
+    pub(super) fn get_mut(&mut self, id: &str) -> Option<&mut Watch> {
+        let watch = self.watches.get_mut(id)?;
+        watch.touched = Instant::now();
+        Some(watch)
+    }
+
+    /// Closes a watch.
+    pub(super) fn close(&mut self, id: &str) {
+        self.watches.remove(id);
+    }
+}
+
+impl Watch {
+    /// Adds ids to the watch. Their records count as sent: the client
+    /// gets them in the `watchSub` response.
+    pub(super) fn add(&mut self, records: impl IntoIterator<Item = (String, Option<Dict>)>) {
+        self.ids.extend(records);
+    }
+
+    /// Removes ids from the watch.
+    pub(super) fn remove<'a>(&mut self, ids: impl IntoIterator<Item = &'a str>) {
+        for id in ids {
+            self.ids.remove(id);
+        }
+    }
+
+    /// Records that changed since the last poll, or every record when
+    /// `refresh` is set. `current` looks a record up by id.
+    pub(super) fn poll(
+        &mut self,
+        refresh: bool,
+        current: impl Fn(&str) -> Option<Dict>,
+    ) -> Vec<Dict> {
+        let mut changed = Vec::new();
+        for (id, sent) in &mut self.ids {
+            let record = current(id);
+            if refresh || *sent != record {
+                if let Some(record) = &record {
+                    changed.push(record.clone());
+                }
+                *sent = record;
+            }
+        }
+        changed
+    }
+}
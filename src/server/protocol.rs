@@ -0,0 +1,379 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! JSON form of the engine message protocol.
+//!
+//! Each [`Request`] names one [`EngineMessage`] request by the method name
+//! the wasm `EngineCommand` gives it (`addBlock`, `writeBlockInput`, ...)
+//! and carries its arguments as fields. The reply is the JSON form of the
+//! matching response, so a client written against the in-browser engine
+//! can talk to a native one unchanged.
+
+use std::collections::BTreeMap;
+
+use libhaystack::val::Value;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::base::{
+    alarm::AlarmAction,
+    block::{BlockDesc, BlockPin},
+    engine::messages::{ChangeSource, EngineMessage, ProgramAction, WatchMessage},
+    history::{HistoryQuery, Point, Recording},
+    program::{Program, data::LinkData},
+};
+use crate::blocks::registry::list_registered_blocks;
+use crate::tokio_impl::engine::single_threaded::Messages;
+
+/// A request to the engine.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum Request {
+    /// Lists the blocks in the registry.
+    ListBlocks,
+    /// Adds a block instance, see [`EngineMessage::AddBlockReq`].
+    AddBlock {
+        /// Block type name.
+        name: String,
+        /// UUID to give the block, or a fresh one.
+        #[serde(default)]
+        block: Option<String>,
+        /// Block library, or any library.
+        #[serde(default)]
+        lib: Option<String>,
+    },
+    /// Removes a block instance and its links.
+    RemoveBlock {
+        /// The block UUID.
+        block: String,
+    },
+    /// Links two pins.
+    CreateLink(LinkData),
+    /// Removes a link.
+    RemoveLink {
+        /// The link UUID.
+        link: String,
+    },
+    /// Writes a value to a block input.
+    WriteBlockInput {
+        /// The block UUID.
+        block: String,
+        /// The input pin name.
+        pin: String,
+        /// The value to write.
+        value: Value,
+    },
+    /// Writes a value to a block output.
+    WriteBlockOutput {
+        /// The block UUID.
+        block: String,
+        /// The output pin name.
+        pin: String,
+        /// The value to write.
+        value: Value,
+    },
+    /// Returns the current state of a block.
+    InspectBlock {
+        /// The block UUID.
+        block: String,
+    },
+    /// Evaluates a block once on the given inputs.
+    EvalBlock {
+        /// Block type name.
+        name: String,
+        /// Input values in pin order.
+        #[serde(default)]
+        inputs: Vec<Value>,
+        /// Block library, or any library.
+        #[serde(default)]
+        lib: Option<String>,
+    },
+    /// Loads a program into the default program.
+    LoadProgram {
+        /// The program to load.
+        program: Program,
+    },
+    /// Saves the running program: the named one, or every block.
+    GetProgram {
+        /// Program id, or [`None`] for the whole engine.
+        #[serde(default)]
+        program_id: Option<String>,
+    },
+    /// Loads a program under a new id.
+    LoadNamedProgram {
+        /// The new program id.
+        program_id: String,
+        /// The program to load.
+        program: Program,
+    },
+    /// Pauses a program.
+    PauseProgram {
+        /// The program id.
+        program_id: String,
+    },
+    /// Resumes a paused program.
+    ResumeProgram {
+        /// The program id.
+        program_id: String,
+    },
+    /// Removes every block of a program.
+    ResetProgram {
+        /// The program id.
+        program_id: String,
+    },
+    /// Removes a program and its blocks.
+    UnloadProgram {
+        /// The program id.
+        program_id: String,
+    },
+    /// Lists the loaded programs.
+    ListPrograms,
+    /// Starts or stops recording a pin into the engine's history.
+    TrendPin {
+        /// The block UUID.
+        block: String,
+        /// The pin name.
+        pin: String,
+        /// How to record the pin, or [`None`] to stop.
+        #[serde(default)]
+        recording: Option<Recording>,
+    },
+    /// Reads the engine's history.
+    QueryHistory(HistoryQuery),
+    /// Lists the alarms in the engine's alarm table.
+    ListAlarms,
+    /// Acknowledges the alarm of an alarm block.
+    AcknowledgeAlarm {
+        /// The alarm block UUID.
+        block: String,
+    },
+    /// Stops an alarm block from announcing its alarms for a while.
+    ShelveAlarm {
+        /// The alarm block UUID.
+        block: String,
+        /// How long to shelve it, in milliseconds.
+        duration: u64,
+    },
+    /// Lifts the shelving of an alarm block.
+    UnshelveAlarm {
+        /// The alarm block UUID.
+        block: String,
+    },
+    /// Pauses block execution.
+    PauseExecution,
+    /// Resumes block execution.
+    ResumeExecution,
+    /// Removes every block, link and program.
+    ResetEngine,
+    /// Stops the engine.
+    StopEngine,
+}
+
+/// How a [`Request`] is answered.
+pub(super) enum Call {
+    /// Answered by the server without the engine.
+    Local(serde_json::Value),
+    /// Sent to the engine, which replies.
+    Reply(Messages),
+    /// Sent to the engine, which does not reply.
+    Notify(Messages),
+}
+
+impl Request {
+    /// Turns the request into engine messages sent as `sender`.
+    pub(super) fn into_call(self, sender: Uuid) -> Result<Call, String> {
+        let program =
+            |id: String, action| Call::Reply(EngineMessage::ProgramReq(sender, id, action));
+        let alarm = |block: String, action| -> Result<Call, String> {
+            Ok(Call::Reply(EngineMessage::AlarmReq(
+                sender,
+                parse_uuid(&block)?,
+                action,
+            )))
+        };
+
+        Ok(match self {
+            Request::ListBlocks => {
+                let mut blocks = list_registered_blocks();
+                blocks.sort_by_key(BlockDesc::qname);
+                let blocks: Vec<_> = blocks.iter().map(catalog_entry).collect();
+                Call::Local(blocks.into())
+            }
+            Request::AddBlock { name, block, lib } => {
+                Call::Reply(EngineMessage::AddBlockReq(sender, name, block, lib))
+            }
+            Request::RemoveBlock { block } => {
+                Call::Reply(EngineMessage::RemoveBlockReq(sender, parse_uuid(&block)?))
+            }
+            Request::CreateLink(link) => Call::Reply(EngineMessage::ConnectBlocksReq(sender, link)),
+            Request::RemoveLink { link } => {
+                Call::Reply(EngineMessage::RemoveLinkReq(sender, parse_uuid(&link)?))
+            }
+            Request::WriteBlockInput { block, pin, value } => Call::Reply(
+                EngineMessage::WriteBlockInputReq(sender, parse_uuid(&block)?, pin, value),
+            ),
+            Request::WriteBlockOutput { block, pin, value } => Call::Reply(
+                EngineMessage::WriteBlockOutputReq(sender, parse_uuid(&block)?, pin, value),
+            ),
+            Request::InspectBlock { block } => {
+                Call::Reply(EngineMessage::InspectBlockReq(sender, parse_uuid(&block)?))
+            }
+            Request::EvalBlock { name, inputs, lib } => {
+                Call::Reply(EngineMessage::EvaluateBlockReq(sender, name, inputs, lib))
+            }
+            Request::LoadProgram { program } => {
+                Call::Reply(EngineMessage::LoadProgramReq(sender, program))
+            }
+            Request::GetProgram { program_id } => Call::Reply(match program_id {
+                Some(program_id) => EngineMessage::GetProgramReq(sender, program_id),
+                None => EngineMessage::GetCurrentProgramReq(sender),
+            }),
+            Request::LoadNamedProgram {
+                program_id,
+                program: loaded,
+            } => program(program_id, ProgramAction::Load(loaded)),
+            Request::PauseProgram { program_id } => program(program_id, ProgramAction::Pause),
+            Request::ResumeProgram { program_id } => program(program_id, ProgramAction::Resume),
+            Request::ResetProgram { program_id } => program(program_id, ProgramAction::Reset),
+            Request::UnloadProgram { program_id } => program(program_id, ProgramAction::Unload),
+            Request::ListPrograms => Call::Reply(EngineMessage::ListProgramsReq(sender)),
+            Request::TrendPin {
+                block,
+                pin,
+                recording,
+            } => Call::Reply(EngineMessage::TrendReq(
+                sender,
+                Point::new(parse_uuid(&block)?, pin),
+                recording,
+            )),
+            Request::QueryHistory(query) => Call::Reply(EngineMessage::HistoryReq(sender, query)),
+            Request::ListAlarms => Call::Reply(EngineMessage::ListAlarmsReq(sender)),
+            Request::AcknowledgeAlarm { block } => alarm(block, AlarmAction::Acknowledge)?,
+            Request::ShelveAlarm { block, duration } => {
+                alarm(block, AlarmAction::Shelve(duration))?
+            }
+            Request::UnshelveAlarm { block } => alarm(block, AlarmAction::Unshelve)?,
+            Request::PauseExecution => Call::Notify(EngineMessage::Pause),
+            Request::ResumeExecution => Call::Notify(EngineMessage::Resume),
+            Request::ResetEngine => Call::Notify(EngineMessage::Reset),
+            Request::StopEngine => Call::Notify(EngineMessage::Shutdown),
+        })
+    }
+}
+
+/// The JSON result of an engine response.
+pub(super) fn reply_json(reply: Messages) -> Result<serde_json::Value, String> {
+    fn to_json<T: Serialize>(value: T) -> Result<serde_json::Value, String> {
+        serde_json::to_value(value).map_err(|err| err.to_string())
+    }
+
+    match reply {
+        EngineMessage::AddBlockRes(res) | EngineMessage::RemoveBlockRes(res) => {
+            res.map(|id| id.to_string().into())
+        }
+        EngineMessage::ConnectBlocksRes(res) => to_json(res?),
+        EngineMessage::RemoveLinkRes(res) => res.map(Into::into),
+        EngineMessage::WriteBlockInputRes(res) => to_json(res?),
+        EngineMessage::WriteBlockOutputRes(res) => to_json(res?),
+        EngineMessage::InspectBlockRes(res) => to_json(res?),
+        EngineMessage::EvaluateBlockRes(res) => to_json(res?),
+        EngineMessage::LoadProgramRes(res)
+        | EngineMessage::ProgramRes(res)
+        | EngineMessage::TrendRes(res)
+        | EngineMessage::AlarmRes(res) => res.map(|_| serde_json::Value::Null),
+        EngineMessage::GetCurrentProgramRes(res) | EngineMessage::GetProgramRes(res) => {
+            to_json(res?)
+        }
+        EngineMessage::ListProgramsRes(programs) => to_json(programs),
+        EngineMessage::HistoryRes(res) => to_json(res?),
+        EngineMessage::ListAlarmsRes(res) => to_json(res?),
+        other => Err(format!("Invalid response: {other:?}")),
+    }
+}
+
+/// A block change notification, in the shape of the web package's
+/// `BlockNotification`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchNotification {
+    /// Block UUID.
+    pub id: String,
+    /// Changed pins.
+    pub changes: Vec<WatchChange>,
+    /// Block state label (`running | fault | disabled | terminated`).
+    pub state: String,
+    /// Fault reason when `state == "fault"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fault_reason: Option<String>,
+}
+
+/// One changed pin of a [`WatchNotification`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WatchChange {
+    /// Pin name.
+    pub name: String,
+    /// `"input"` or `"output"`.
+    pub source: String,
+    /// New pin value.
+    pub value: Value,
+}
+
+impl From<WatchMessage> for WatchNotification {
+    fn from(msg: WatchMessage) -> Self {
+        let changes = msg
+            .changes
+            .into_iter()
+            .map(|(name, change)| {
+                let (source, value) = match change {
+                    ChangeSource::Input(_, value) => ("input", value),
+                    ChangeSource::Output(_, value) => ("output", value),
+                };
+                WatchChange {
+                    name,
+                    source: source.to_string(),
+                    value,
+                }
+            })
+            .collect();
+
+        Self {
+            id: msg.block_id.to_string(),
+            changes,
+            state: msg.state.label().to_string(),
+            fault_reason: msg.state.fault_reason().map(str::to_string),
+        }
+    }
+}
+
+/// A registry entry in the shape of the web package's `BlockDesc`.
+fn catalog_entry(desc: &BlockDesc) -> serde_json::Value {
+    let pins = |pins: &[BlockPin]| {
+        pins.iter()
+            .map(|pin| json!({ "name": pin.name, "kind": pin.kind.to_string() }))
+            .collect::<Vec<_>>()
+    };
+
+    let mut entry = BTreeMap::from([
+        ("name", json!(desc.name)),
+        ("dis", json!(desc.dis)),
+        ("lib", json!(desc.library)),
+        ("ver", json!(desc.ver)),
+        ("category", json!(desc.category)),
+        ("doc", json!(desc.doc)),
+        ("implementation", json!(desc.implementation.to_string())),
+        ("inputs", json!(pins(&desc.inputs))),
+        ("outputs", json!(pins(&desc.outputs))),
+    ]);
+    if let Some(condition) = &desc.run_condition {
+        entry.insert("runCondition", json!(condition.to_string()));
+    }
+    json!(entry)
+}
+
+pub(super) fn parse_uuid(id: &str) -> Result<Uuid, String> {
+    Uuid::parse_str(id).map_err(|err| format!("Invalid uuid '{id}': {err}"))
+}
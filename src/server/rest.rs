@@ -0,0 +1,322 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! REST routes. Each handler builds a [`Request`] from the path and body
+//! and answers with its JSON result.
+
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
+};
+use libhaystack::val::Value;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::base::history::{HistoryQuery, Point, Recording, Rollup};
+use crate::base::program::{Program, data::LinkData};
+
+use super::protocol::parse_uuid;
+use super::{CallError, Connection, Request, socket};
+
+pub(super) fn routes() -> Router<Connection> {
+    Router::new()
+        .route("/api", post(any))
+        .route("/api/ws", get(socket::upgrade))
+        .route("/api/catalog", get(catalog))
+        .route("/api/blocks", post(add_block))
+        .route("/api/blocks/{id}", get(inspect_block).delete(remove_block))
+        .route("/api/blocks/{id}/inputs/{pin}", put(write_input))
+        .route("/api/blocks/{id}/outputs/{pin}", put(write_output))
+        .route(
+            "/api/blocks/{id}/history/{pin}",
+            get(query_history).put(trend_pin).delete(untrend_pin),
+        )
+        .route("/api/alarms", get(list_alarms))
+        .route("/api/alarms/{id}/acknowledge", post(acknowledge_alarm))
+        .route(
+            "/api/alarms/{id}/shelve",
+            put(shelve_alarm).delete(unshelve_alarm),
+        )
+        .route("/api/links", post(create_link))
+        .route("/api/links/{id}", axum::routing::delete(remove_link))
+        .route("/api/eval", post(eval_block))
+        .route("/api/program", get(get_program).put(load_program))
+        .route("/api/programs", get(list_programs))
+        .route(
+            "/api/programs/{id}",
+            put(load_named_program).delete(unload_program),
+        )
+        .route("/api/programs/{id}/{action}", post(program_action))
+        .route("/api/engine/{action}", post(engine_action))
+}
+
+impl IntoResponse for CallError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            CallError::Rejected(_) => StatusCode::BAD_REQUEST,
+            CallError::Stopped => StatusCode::SERVICE_UNAVAILABLE,
+        };
+        (status, Json(json!({ "error": self.to_string() }))).into_response()
+    }
+}
+
+async fn call(
+    connection: &Connection,
+    request: Request,
+) -> Result<Json<serde_json::Value>, CallError> {
+    connection.call(request).await.map(Json)
+}
+
+async fn any(
+    State(connection): State<Connection>,
+    Json(request): Json<Request>,
+) -> Result<Json<serde_json::Value>, CallError> {
+    call(&connection, request).await
+}
+
+async fn catalog(
+    State(connection): State<Connection>,
+) -> Result<Json<serde_json::Value>, CallError> {
+    call(&connection, Request::ListBlocks).await
+}
+
+#[derive(Deserialize)]
+struct NewBlock {
+    name: String,
+    #[serde(default)]
+    block: Option<String>,
+    #[serde(default)]
+    lib: Option<String>,
+}
+
+async fn add_block(
+    State(connection): State<Connection>,
+    Json(NewBlock { name, block, lib }): Json<NewBlock>,
+) -> Result<Json<serde_json::Value>, CallError> {
+    call(&connection, Request::AddBlock { name, block, lib }).await
+}
+
+async fn inspect_block(
+    State(connection): State<Connection>,
+    Path(block): Path<String>,
+) -> Result<Json<serde_json::Value>, CallError> {
+    call(&connection, Request::InspectBlock { block }).await
+}
+
+async fn remove_block(
+    State(connection): State<Connection>,
+    Path(block): Path<String>,
+) -> Result<Json<serde_json::Value>, CallError> {
+    call(&connection, Request::RemoveBlock { block }).await
+}
+
+async fn write_input(
+    State(connection): State<Connection>,
+    Path((block, pin)): Path<(String, String)>,
+    Json(value): Json<Value>,
+) -> Result<Json<serde_json::Value>, CallError> {
+    call(&connection, Request::WriteBlockInput { block, pin, value }).await
+}
+
+async fn write_output(
+    State(connection): State<Connection>,
+    Path((block, pin)): Path<(String, String)>,
+    Json(value): Json<Value>,
+) -> Result<Json<serde_json::Value>, CallError> {
+    call(&connection, Request::WriteBlockOutput { block, pin, value }).await
+}
+
+async fn create_link(
+    State(connection): State<Connection>,
+    Json(link): Json<LinkData>,
+) -> Result<Json<serde_json::Value>, CallError> {
+    call(&connection, Request::CreateLink(link)).await
+}
+
+async fn remove_link(
+    State(connection): State<Connection>,
+    Path(link): Path<String>,
+) -> Result<Json<serde_json::Value>, CallError> {
+    call(&connection, Request::RemoveLink { link }).await
+}
+
+#[derive(Deserialize)]
+struct Eval {
+    name: String,
+    #[serde(default)]
+    inputs: Vec<Value>,
+    #[serde(default)]
+    lib: Option<String>,
+}
+
+async fn eval_block(
+    State(connection): State<Connection>,
+    Json(Eval { name, inputs, lib }): Json<Eval>,
+) -> Result<Json<serde_json::Value>, CallError> {
+    call(&connection, Request::EvalBlock { name, inputs, lib }).await
+}
+
+#[derive(Deserialize)]
+struct HistoryParams {
+    start: Option<u64>,
+    end: Option<u64>,
+    rollup: Option<Rollup>,
+    interval: Option<u64>,
+}
+
+async fn query_history(
+    State(connection): State<Connection>,
+    Path((block, pin)): Path<(String, String)>,
+    Query(params): Query<HistoryParams>,
+) -> Result<Json<serde_json::Value>, CallError> {
+    let block = parse_uuid(&block).map_err(CallError::Rejected)?;
+    let query = HistoryQuery {
+        point: Point::new(block, pin),
+        start: params.start,
+        end: params.end,
+        rollup: params.rollup,
+        interval: params.interval,
+    };
+    call(&connection, Request::QueryHistory(query)).await
+}
+
+async fn trend_pin(
+    State(connection): State<Connection>,
+    Path((block, pin)): Path<(String, String)>,
+    Json(recording): Json<Recording>,
+) -> Result<Json<serde_json::Value>, CallError> {
+    let recording = Some(recording);
+    call(
+        &connection,
+        Request::TrendPin {
+            block,
+            pin,
+            recording,
+        },
+    )
+    .await
+}
+
+async fn untrend_pin(
+    State(connection): State<Connection>,
+    Path((block, pin)): Path<(String, String)>,
+) -> Result<Json<serde_json::Value>, CallError> {
+    call(
+        &connection,
+        Request::TrendPin {
+            block,
+            pin,
+            recording: None,
+        },
+    )
+    .await
+}
+
+async fn list_alarms(
+    State(connection): State<Connection>,
+) -> Result<Json<serde_json::Value>, CallError> {
+    call(&connection, Request::ListAlarms).await
+}
+
+async fn acknowledge_alarm(
+    State(connection): State<Connection>,
+    Path(block): Path<String>,
+) -> Result<Json<serde_json::Value>, CallError> {
+    call(&connection, Request::AcknowledgeAlarm { block }).await
+}
+
+async fn shelve_alarm(
+    State(connection): State<Connection>,
+    Path(block): Path<String>,
+    Json(duration): Json<u64>,
+) -> Result<Json<serde_json::Value>, CallError> {
+    call(&connection, Request::ShelveAlarm { block, duration }).await
+}
+
+async fn unshelve_alarm(
+    State(connection): State<Connection>,
+    Path(block): Path<String>,
+) -> Result<Json<serde_json::Value>, CallError> {
+    call(&connection, Request::UnshelveAlarm { block }).await
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProgramQuery {
+    program_id: Option<String>,
+}
+
+async fn get_program(
+    State(connection): State<Connection>,
+    Query(ProgramQuery { program_id }): Query<ProgramQuery>,
+) -> Result<Json<serde_json::Value>, CallError> {
+    call(&connection, Request::GetProgram { program_id }).await
+}
+
+async fn load_program(
+    State(connection): State<Connection>,
+    Json(program): Json<Program>,
+) -> Result<Json<serde_json::Value>, CallError> {
+    call(&connection, Request::LoadProgram { program }).await
+}
+
+async fn list_programs(
+    State(connection): State<Connection>,
+) -> Result<Json<serde_json::Value>, CallError> {
+    call(&connection, Request::ListPrograms).await
+}
+
+async fn load_named_program(
+    State(connection): State<Connection>,
+    Path(program_id): Path<String>,
+    Json(program): Json<Program>,
+) -> Result<Json<serde_json::Value>, CallError> {
+    call(
+        &connection,
+        Request::LoadNamedProgram {
+            program_id,
+            program,
+        },
+    )
+    .await
+}
+
+async fn unload_program(
+    State(connection): State<Connection>,
+    Path(program_id): Path<String>,
+) -> Result<Json<serde_json::Value>, CallError> {
+    call(&connection, Request::UnloadProgram { program_id }).await
+}
+
+async fn program_action(
+    State(connection): State<Connection>,
+    Path((program_id, action)): Path<(String, String)>,
+) -> Result<Json<serde_json::Value>, CallError> {
+    let request = match action.as_str() {
+        "pause" => Request::PauseProgram { program_id },
+        "resume" => Request::ResumeProgram { program_id },
+        "reset" => Request::ResetProgram { program_id },
+        _ => return Err(unknown_action(&action)),
+    };
+    call(&connection, request).await
+}
+
+async fn engine_action(
+    State(connection): State<Connection>,
+    Path(action): Path<String>,
+) -> Result<Json<serde_json::Value>, CallError> {
+    let request = match action.as_str() {
+        "pause" => Request::PauseExecution,
+        "resume" => Request::ResumeExecution,
+        "reset" => Request::ResetEngine,
+        "stop" => Request::StopEngine,
+        _ => return Err(unknown_action(&action)),
+    };
+    call(&connection, request).await
+}
+
+fn unknown_action(action: &str) -> CallError {
+    CallError::Rejected(format!("Unknown action '{action}'"))
+}
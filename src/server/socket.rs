@@ -0,0 +1,107 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! WebSocket sessions: requests in, replies, watch notifications and
+//! alarm transitions out.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{
+        State,
+        ws::{Message, WebSocket, WebSocketUpgrade},
+    },
+    response::Response,
+};
+use serde_json::json;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::base::alarm::AlarmEvent;
+
+use super::{CallError, Connection, Request};
+
+pub(super) async fn upgrade(
+    ws: WebSocketUpgrade,
+    State(connection): State<Connection>,
+) -> Response {
+    ws.on_upgrade(move |socket| session(socket, connection))
+}
+
+async fn session(mut socket: WebSocket, connection: Connection) {
+    let mut watches = match connection.watch().await {
+        Ok(watches) => watches,
+        Err(err) => {
+            let _ = send(&mut socket, json!({ "error": err.to_string() })).await;
+            return;
+        }
+    };
+    // An engine without an alarm table streams none.
+    let mut alarms = connection.alarms().await.ok();
+
+    loop {
+        let frame = tokio::select! {
+            frame = socket.recv() => match frame {
+                Some(Ok(Message::Text(text))) => reply(&connection, &text).await,
+                Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+                Some(Ok(_)) => continue,
+            },
+            note = watches.recv() => match note {
+                Ok(note) => json!({ "watch": *note }),
+                Err(RecvError::Lagged(missed)) => {
+                    log::warn!("WebSocket client fell behind, {missed} notifications dropped");
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            },
+            event = next_alarm(&mut alarms) => match event {
+                Ok(event) => json!({ "alarm": *event }),
+                Err(RecvError::Lagged(missed)) => {
+                    log::warn!("WebSocket client fell behind, {missed} alarm events dropped");
+                    continue;
+                }
+                Err(RecvError::Closed) => {
+                    alarms = None;
+                    continue;
+                }
+            },
+        };
+
+        if send(&mut socket, frame).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// The next alarm transition, or never without an alarm stream.
+async fn next_alarm(
+    alarms: &mut Option<broadcast::Receiver<Arc<AlarmEvent>>>,
+) -> Result<Arc<AlarmEvent>, RecvError> {
+    match alarms {
+        Some(alarms) => alarms.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Answers one request frame, echoing its `id`.
+async fn reply(connection: &Connection, text: &str) -> serde_json::Value {
+    let mut frame: serde_json::Value = match serde_json::from_str(text) {
+        Ok(frame) => frame,
+        Err(err) => return json!({ "error": format!("Invalid request: {err}") }),
+    };
+    let id = frame
+        .as_object_mut()
+        .and_then(|frame| frame.remove("id"))
+        .unwrap_or_default();
+
+    let result = match serde_json::from_value::<Request>(frame) {
+        Ok(request) => connection.call(request).await,
+        Err(err) => Err(CallError::Rejected(format!("Invalid request: {err}"))),
+    };
+    match result {
+        Ok(result) => json!({ "id": id, "result": result }),
+        Err(err) => json!({ "id": id, "error": err.to_string() }),
+    }
+}
+
+async fn send(socket: &mut WebSocket, frame: serde_json::Value) -> Result<(), axum::Error> {
+    socket.send(Message::Text(frame.to_string().into())).await
+}
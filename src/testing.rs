@@ -0,0 +1,100 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Test bench for driving a block or a program with scripted inputs.
+//!
+//! Enabled by the `testing` feature, for the authors of blocks to test
+//! them the way the engine runs them, without an engine:
+//!
+//! - [`TestBench`] owns a block, writes its inputs as if they came over
+//!   links, steps `execute()` until the block settles, and checks its
+//!   outputs, their status and the [`BlockState`](crate::base::block::BlockState).
+//!   The block sees a virtual clock that only moves on
+//!   [`TestBench::advance`], so timers and rate blocks are tested without
+//!   waiting.
+//! - [`Scenario`] is a table of such steps, one row per point in time:
+//!
+//! ```
+//! use logic_mesh::blocks::timers::OnDelay;
+//! use logic_mesh::testing::{Scenario, TestBench};
+//!
+//! # fn main() -> Result<(), logic_mesh::testing::ScenarioError> {
+//! let scenario = Scenario::parse(
+//!     r#"
+//!     | at   | in    | delay | out   |
+//!     |------|-------|-------|-------|
+//!     | 0s   | true  | 1s    | false |
+//!     | 0.5s |       |       | false |
+//!     | 1s   |       |       | true  |
+//!     | 2s   | false |       | false |
+//!     "#,
+//! )?;
+//!
+//! TestBench::new(OnDelay::new()).run(&scenario);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! See [`Scenario`] for the columns and cell syntax.
+//!
+//! [`ProgramScenario`] does the same for a whole [`Program`](crate::base::program::Program):
+//! it loads the program into an engine, writes inputs along a timeline,
+//! and reports the pins that did not hold the expected values or ranges
+//! at the given times. The engine runs on Tokio's paused clock, so
+//! minutes of control sequence play in moments. The `scenarios`
+//! directory of the repository holds the ones run before each release.
+
+mod bench;
+mod program;
+mod scenario;
+
+use thiserror::Error;
+
+pub use bench::TestBench;
+pub use program::{Expectation, Mismatch, ProgramScenario, ScenarioReport};
+pub use scenario::Scenario;
+
+/// Errors reported when parsing a [`Scenario`], or when loading or
+/// running a [`ProgramScenario`].
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum ScenarioError {
+    /// The scenario has no header row.
+    #[error("Scenario has no header row")]
+    MissingHeader,
+    /// A row has a different number of cells than the header.
+    #[error("Line {line}: expected {expected} cells, found {actual}")]
+    CellCount {
+        /// The 1-based line of the row.
+        line: usize,
+        /// The number of header cells.
+        expected: usize,
+        /// The number of cells in the row.
+        actual: usize,
+    },
+    /// A cell could not be parsed.
+    #[error("Line {line}, column '{column}': {message}")]
+    InvalidCell {
+        /// The 1-based line of the row.
+        line: usize,
+        /// The header of the cell's column.
+        column: String,
+        /// Why the cell is invalid.
+        message: String,
+    },
+    /// A scenario or program file could not be read or parsed.
+    #[error("Cannot read '{path}': {message}")]
+    File {
+        /// The path of the file.
+        path: String,
+        /// Why it could not be read.
+        message: String,
+    },
+    /// A scenario document is invalid.
+    #[error("Invalid scenario: {0}")]
+    Invalid(String),
+    /// A scenario names a block or pin the program does not have.
+    #[error("No pin '{0}' in the program")]
+    UnknownPin(String),
+    /// The engine rejected the program or a request.
+    #[error("Engine error: {0}")]
+    Engine(String),
+}
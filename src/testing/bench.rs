@@ -0,0 +1,496 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! The block test bench.
+
+use std::future::Future;
+use std::pin::pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::task::{Context, Wake, Waker};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use libhaystack::val::{Value, kind::HaystackKind};
+use uuid::Uuid;
+
+use super::scenario::{Column, Scenario, matches};
+use crate::base::Status;
+use crate::base::block::{Block, BlockState};
+use crate::base::input::{Input, InputProps};
+use crate::blocks::{InputImpl, ReaderImpl, WriterImpl};
+use crate::tokio_impl::sleep::{VIRTUAL_CLOCK, VirtualClock};
+
+/// How many cycles [`TestBench::settle`] runs before giving up.
+const MAX_CYCLES: usize = 100;
+
+/// How many times a cycle may wake itself up before it counts as parked,
+/// such as a block that polls for inputs none of which is connected.
+const MAX_WAKEUPS: usize = 1000;
+
+/// Drives a block the way an engine does, from a test.
+///
+/// The bench owns the block and steps its `execute()` itself: inputs are
+/// written as if they arrived over links, each output is linked to a
+/// probe that sees its values and status the way a downstream block
+/// would, and a cycle ends when `execute()` returns or parks waiting
+/// for inputs. Between cycles the bench clears and propagates faults as
+/// the engine does.
+///
+/// The block runs on a virtual clock, which starts at the wall-clock
+/// time the bench is created and only moves on [`TestBench::advance`].
+/// While a cycle runs, sleeps return as soon as the block yields, so a
+/// block waiting on a timeout runs its next cycle right away.
+///
+/// The assertion methods panic with the pin, the expected and the actual
+/// values. See [`Scenario`] for tables of steps.
+///
+/// # Examples
+///
+/// ```
+/// use logic_mesh::base::block::BlockState;
+/// use logic_mesh::blocks::math::Add;
+/// use logic_mesh::testing::TestBench;
+///
+/// let mut bench = TestBench::new(Add::new());
+/// bench.set("in0", 1).set("in1", 2).settle();
+/// bench.assert_output("out", 3).assert_state(&BlockState::Running);
+/// ```
+pub struct TestBench<B> {
+    block: B,
+    probes: Vec<InputImpl>,
+    start_ms: u64,
+    now_ms: u64,
+    last_state: BlockState,
+}
+
+impl<B: Block<Reader = ReaderImpl, Writer = WriterImpl>> TestBench<B> {
+    /// Creates a bench driving `block`, with its outputs linked to probes.
+    pub fn new(mut block: B) -> Self {
+        let probe_id = Uuid::new_v4();
+        let names: Vec<String> = block
+            .outputs()
+            .iter()
+            .map(|output| output.name().to_string())
+            .collect();
+        let probes = names
+            .iter()
+            .map(|name| {
+                let mut probe = InputImpl::new(name, HaystackKind::Null, probe_id);
+                block
+                    .connect_output(name, &mut probe)
+                    .expect("a fresh output links to its probe");
+                probe
+            })
+            .collect();
+
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |now| now.as_millis() as u64);
+        Self {
+            block,
+            probes,
+            start_ms: now_ms,
+            now_ms,
+            last_state: BlockState::Running,
+        }
+    }
+
+    /// Starts the virtual clock at `time` instead of the current time,
+    /// for blocks that depend on the time of day or the date.
+    pub fn starting_at(mut self, time: SystemTime) -> Self {
+        let millis = time
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_millis() as u64);
+        self.start_ms = millis;
+        self.now_ms = millis;
+        self
+    }
+
+    /// The block under test.
+    pub fn block(&self) -> &B {
+        &self.block
+    }
+
+    /// The block under test, for setting its fields directly.
+    pub fn block_mut(&mut self) -> &mut B {
+        &mut self.block
+    }
+
+    /// Releases the block under test.
+    pub fn into_inner(self) -> B {
+        self.block
+    }
+
+    /// Writes `value` to the input named `pin`, with an [`Status::Ok`]
+    /// status. The block reads it on its next cycle.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the block has no such input.
+    #[track_caller]
+    pub fn set(&mut self, pin: &str, value: impl Into<Value>) -> &mut Self {
+        self.set_with_status(pin, value, Status::Ok)
+    }
+
+    /// Writes `value` with `status` to the input named `pin`, as sent by
+    /// an upstream block in that status.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the block has no such input.
+    #[track_caller]
+    pub fn set_with_status(
+        &mut self,
+        pin: &str,
+        value: impl Into<Value>,
+        status: Status,
+    ) -> &mut Self {
+        let name = self.block.name().to_string();
+        let Some(input) = self.block.get_input_mut(pin) else {
+            panic!("{name} has no input named '{pin}'");
+        };
+        if !input.is_connected() {
+            input.increment_conn();
+        }
+        input.writer().send_replace((value.into(), status));
+        self
+    }
+
+    /// Moves the virtual clock forward by `by`.
+    pub fn advance(&mut self, by: Duration) -> &mut Self {
+        self.now_ms += by.as_millis() as u64;
+        self
+    }
+
+    /// The time the virtual clock moved since the bench was created.
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_millis(self.now_ms - self.start_ms)
+    }
+
+    /// Runs one cycle of the block. Returns `true` if `execute()`
+    /// returned, `false` if it parked waiting for inputs.
+    pub fn step(&mut self) -> bool {
+        // Like the engine, clear a fault before the cycle; the block
+        // faults again if the cause persists. A cycle that parks has not
+        // run yet, so it keeps the fault.
+        let state = self.block.state();
+        if state.is_fault() {
+            self.block.set_state(BlockState::Running);
+        }
+
+        let previous = VIRTUAL_CLOCK.replace(Some(VirtualClock::Stopped(self.now_ms)));
+        let completed = poll_until_parked(self.block.execute());
+        VIRTUAL_CLOCK.set(previous);
+        if !completed {
+            self.block.set_state(state);
+            return false;
+        }
+
+        let state = self.block.state();
+        if state.is_fault() {
+            for output in self.block.outputs_mut() {
+                output.emit_status(Status::Fault);
+            }
+        } else if self.last_state.is_fault() {
+            for output in self.block.outputs_mut() {
+                output.emit_status(Status::Ok);
+            }
+        }
+        self.last_state = state;
+
+        for probe in &mut self.probes {
+            if let Some((value, status)) = probe.try_take() {
+                probe.set_value(value, status);
+            }
+        }
+        true
+    }
+
+    /// Runs cycles until the block parks waiting for inputs, or a cycle
+    /// leaves its outputs, their status and its state unchanged. Returns
+    /// the number of cycles that ran to completion.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the block does not settle within 100 cycles, like a
+    /// free-running oscillator; drive those with [`TestBench::step`].
+    #[track_caller]
+    pub fn settle(&mut self) -> usize {
+        for cycle in 1..=MAX_CYCLES {
+            let before = self.snapshot();
+            if !self.step() {
+                return cycle - 1;
+            }
+            if self.snapshot() == before {
+                return cycle;
+            }
+        }
+        panic!(
+            "{} did not settle within {MAX_CYCLES} cycles",
+            self.block.name()
+        );
+    }
+
+    /// The value of the output named `pin`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the block has no such output.
+    #[track_caller]
+    pub fn output(&self, pin: &str) -> &Value {
+        match self.block.get_output(pin) {
+            Some(output) => output.value(),
+            None => panic!("{} has no output named '{pin}'", self.block.name()),
+        }
+    }
+
+    /// The status the output named `pin` was last sent downstream with.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the block has no such output.
+    #[track_caller]
+    pub fn status(&self, pin: &str) -> Status {
+        match self.probes.iter().find(|probe| probe.name() == pin) {
+            Some(probe) => probe.status(),
+            None => panic!("{} has no output named '{pin}'", self.block.name()),
+        }
+    }
+
+    /// The state of the block.
+    pub fn state(&self) -> BlockState {
+        self.block.state()
+    }
+
+    /// Asserts the value of the output named `pin`. Numbers are compared
+    /// with a relative tolerance of `1e-9`, and must have the same unit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the values differ, or if the block has no such output.
+    #[track_caller]
+    pub fn assert_output(&self, pin: &str, expected: impl Into<Value>) -> &Self {
+        let expected = expected.into();
+        let actual = self.output(pin);
+        assert!(
+            matches(&expected, actual),
+            "{}.{pin}: expected {expected}, got {actual}",
+            self.block.name()
+        );
+        self
+    }
+
+    /// Asserts the status of the output named `pin`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the status differs, or if the block has no such output.
+    #[track_caller]
+    pub fn assert_status(&self, pin: &str, expected: Status) -> &Self {
+        let actual = self.status(pin);
+        assert_eq!(
+            actual,
+            expected,
+            "{}.{pin}: expected status {expected:?}, got {actual:?}",
+            self.block.name()
+        );
+        self
+    }
+
+    /// Asserts the state of the block.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the state differs.
+    #[track_caller]
+    pub fn assert_state(&self, expected: &BlockState) -> &Self {
+        let actual = self.block.state();
+        assert_eq!(
+            &actual,
+            expected,
+            "{}: expected state {expected:?}, got {actual:?}",
+            self.block.name()
+        );
+        self
+    }
+
+    /// Plays `scenario` from the current time: for each row, advances
+    /// the clock to its time, writes its inputs, settles the block and
+    /// checks its expectations.
+    ///
+    /// # Panics
+    ///
+    /// Panics on the first expectation that fails, with the line of its
+    /// row, or if a column names neither an input nor an output.
+    #[track_caller]
+    pub fn run(&mut self, scenario: &Scenario) -> &mut Self {
+        let name = self.block.name().to_string();
+        for column in scenario.columns() {
+            let pin = match column {
+                Column::Pin(pin) => pin,
+                Column::Status(pin) => pin,
+                Column::At | Column::State => continue,
+            };
+            if self.block.get_input(pin).is_none() && self.block.get_output(pin).is_none() {
+                panic!("{name} has no pin named '{pin}'");
+            }
+        }
+
+        let start = self.elapsed();
+        for row in scenario.rows() {
+            if let Some(at) = row.at {
+                let target = start + at;
+                if target > self.elapsed() {
+                    self.advance(target - self.elapsed());
+                }
+            }
+
+            let inputs: Vec<_> = row
+                .pins
+                .iter()
+                .filter(|(pin, _)| self.block.get_input(pin).is_some())
+                .collect();
+            for (pin, value) in inputs {
+                self.set(pin, value.clone());
+            }
+            self.settle();
+
+            let line = row.line;
+            for (pin, expected) in &row.pins {
+                if self.block.get_output(pin).is_some() {
+                    let actual = self.output(pin);
+                    assert!(
+                        matches(expected, actual),
+                        "line {line}: {name}.{pin}: expected {expected}, got {actual}"
+                    );
+                }
+            }
+            for (pin, expected) in &row.statuses {
+                let actual = self.status(pin);
+                assert_eq!(
+                    actual, *expected,
+                    "line {line}: {name}.{pin}: expected status {expected:?}, got {actual:?}"
+                );
+            }
+            if let Some(expected) = &row.state {
+                let actual = self.block.state();
+                assert!(
+                    expected.matches(&actual),
+                    "line {line}: {name}: expected state {expected}, got {actual:?}"
+                );
+            }
+        }
+        self
+    }
+
+    fn snapshot(&self) -> (Vec<Value>, Vec<Status>, BlockState) {
+        (
+            self.block
+                .outputs()
+                .iter()
+                .map(|output| output.value().clone())
+                .collect(),
+            self.probes.iter().map(|probe| probe.status()).collect(),
+            self.block.state(),
+        )
+    }
+}
+
+/// Wakes up the cycle being polled by [`poll_until_parked`].
+struct WakeFlag(AtomicBool);
+
+impl Wake for WakeFlag {
+    fn wake(self: Arc<Self>) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Polls `fut` for as long as it wakes itself up. Returns `true` if it
+/// completed, `false` if it parked, waiting on something that only
+/// another task could do, such as writing an input.
+fn poll_until_parked(fut: impl Future<Output = ()>) -> bool {
+    let flag = Arc::new(WakeFlag(AtomicBool::new(false)));
+    let waker = Waker::from(flag.clone());
+    let mut cx = Context::from_waker(&waker);
+    let mut fut = pin!(fut);
+
+    for _ in 0..MAX_WAKEUPS {
+        if fut.as_mut().poll(&mut cx).is_ready() {
+            return true;
+        }
+        if !flag.0.swap(false, Ordering::SeqCst) {
+            return false;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use libhaystack::units::get_unit;
+    use libhaystack::val::Number;
+
+    use super::TestBench;
+    use crate::base::Status;
+    use crate::base::block::BlockState;
+    use crate::blocks::math::Add;
+    use crate::blocks::timers::OnDelay;
+
+    #[test]
+    fn test_bench_settles_block() {
+        let mut bench = TestBench::new(Add::new());
+        bench.set("in0", 1).set("in1", 2);
+        assert_eq!(bench.settle(), 1);
+        bench
+            .assert_output("out", 3)
+            .assert_status("out", Status::Ok);
+
+        // Nothing new to read: the block parks.
+        assert!(!bench.step());
+    }
+
+    #[test]
+    fn test_bench_propagates_faults() {
+        let mut bench = TestBench::new(Add::new());
+        let num = |value, unit| Number::make_with_unit(value, get_unit(unit).unwrap());
+
+        bench.set("in0", num(1.0, "°F")).set("in1", num(1.0, "kPa"));
+        bench.settle();
+        bench
+            .assert_state(&BlockState::fault("Add: Incompatible units °F and kPa"))
+            .assert_status("out", Status::Fault);
+
+        bench.set("in1", num(1.0, "Δ°F")).settle();
+        bench
+            .assert_state(&BlockState::Running)
+            .assert_status("out", Status::Ok)
+            .assert_output("out", num(2.0, "°F"));
+    }
+
+    #[test]
+    fn test_bench_advances_time() {
+        let mut bench = TestBench::new(OnDelay::new());
+        bench.set("in", true).set("delay", 1000).settle();
+        bench.assert_output("out", false);
+
+        bench.advance(Duration::from_millis(999)).settle();
+        bench.assert_output("out", false);
+
+        bench.advance(Duration::from_millis(1)).settle();
+        bench.assert_output("out", true);
+        assert_eq!(bench.elapsed(), Duration::from_secs(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Add.out: expected 4, got 3")]
+    fn test_bench_assert_output() {
+        let mut bench = TestBench::new(Add::new());
+        bench.set("in0", 1).set("in1", 2).settle();
+        bench.assert_output("out", 4);
+    }
+}
@@ -0,0 +1,889 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Program scenarios, played on an engine.
+
+use std::collections::BTreeMap;
+use std::collections::btree_map::Entry;
+use std::fmt::{self, Display};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use libhaystack::val::{Number, Value};
+use serde::Deserialize;
+use tokio::sync::mpsc::{self, Receiver, Sender};
+use tokio::time::Instant;
+use uuid::Uuid;
+
+use super::ScenarioError;
+use super::scenario::{matches, parse_time};
+use crate::base::engine::Engine;
+use crate::base::engine::messages::{BlockDefinition, EngineMessage};
+use crate::base::program::Program;
+use crate::blocks::units;
+use crate::single_threaded::{Messages, SingleThreadedEngine};
+use crate::tokio_impl::sleep::{VIRTUAL_CLOCK, VirtualClock};
+
+/// How long after a step's writes its expectations are checked, for the
+/// writes to propagate through the program.
+const SETTLE: Duration = Duration::from_millis(1);
+
+/// A [`Program`] and a timeline of pin writes and of the values to
+/// expect of its pins, played on a [`SingleThreadedEngine`] by
+/// [`ProgramScenario::run`].
+///
+/// Scenarios are JSON documents:
+///
+/// ```json
+/// {
+///   "name": "Anti-short-cycle compressor",
+///   "program": "anti-short-cycle.program.json",
+///   "timeline": [
+///     { "at": 0, "write": { "Cooling call.out": true } },
+///     { "at": "2.9s", "expect": { "3 s warmup.out": false } },
+///     { "at": "3.5s", "expect": { "3 s warmup.out": true } },
+///     { "at": "1min", "expect": { "SAT loop.out": { "approx": 65, "tolerance": 0.5 } } },
+///     { "at": "2min", "expect": { "SAT loop.out": { "min": 55, "max": 65 } } }
+///   ]
+/// }
+/// ```
+///
+/// - `program` is the path of a program file, relative to the scenario
+///   file, or the program itself.
+/// - `at` is the time of the step since the program was loaded, as a
+///   number of milliseconds or a Zinc number with a duration unit. Steps
+///   are in time order.
+/// - `write` and `expect` name pins as `<block>.<pin>`, where the block
+///   is its label, which must be unique in the program, or its id. Values
+///   are Haystack JSON.
+/// - Pins are written at the time of the step: the block's output by
+///   that name if it has one, standing in for whatever would feed the
+///   program, and its input otherwise. Most blocks wait for a linked
+///   input to change before they run, so programs are best fed through
+///   the output of a source block.
+/// - Expectations are checked once the writes have propagated, against
+///   the block's outputs first and then its inputs. An expectation is a
+///   value, checked as in [`Scenario`](super::Scenario), `{ "approx":
+///   <number>, "tolerance": <number> }`, or a range `{ "min": <number>,
+///   "max": <number> }` with either bound optional. Numbers are converted
+///   to the unit of the expectation first.
+///
+/// The engine runs on Tokio's paused clock, which the blocks see as
+/// their clock too, so a scenario spanning hours runs in moments. Tests
+/// already on a Tokio runtime use [`run_async`](ProgramScenario::run_async).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgramScenario {
+    name: String,
+    description: Option<String>,
+    program: Program,
+    steps: Vec<Step>,
+    start: Option<SystemTime>,
+}
+
+/// The value a scenario expects of a pin.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expectation {
+    /// This value; numbers within a relative 1e-9, element-wise in lists.
+    Exact(Value),
+    /// A number within `tolerance` of `value`, in the unit of `value`.
+    Approx {
+        /// The expected number.
+        value: Number,
+        /// The largest difference allowed.
+        tolerance: f64,
+    },
+    /// A number within the bounds, inclusive.
+    Range {
+        /// The lower bound, if any.
+        min: Option<Number>,
+        /// The upper bound, if any.
+        max: Option<Number>,
+    },
+}
+
+/// The outcome of [`ProgramScenario::run`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScenarioReport {
+    /// The name of the scenario.
+    pub name: String,
+    /// The number of expectations checked.
+    pub checks: usize,
+    /// The expectations that were not met, in timeline order.
+    pub mismatches: Vec<Mismatch>,
+}
+
+/// An expectation that was not met.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mismatch {
+    /// The time of the step.
+    pub at: Duration,
+    /// The 1-based index of the step in the timeline.
+    pub step: usize,
+    /// The pin, as named in the scenario.
+    pub pin: String,
+    /// What was expected of it.
+    pub expected: Expectation,
+    /// Its value.
+    pub actual: Value,
+    /// The block's type, state and pins at the time.
+    pub context: String,
+}
+
+/// One timeline entry.
+#[derive(Debug, Clone, PartialEq)]
+struct Step {
+    at: Duration,
+    writes: Vec<(PinRef, Value)>,
+    expects: Vec<(PinRef, Expectation)>,
+}
+
+/// A `<block>.<pin>` reference, resolved against the program.
+#[derive(Debug, Clone, PartialEq)]
+struct PinRef {
+    name: String,
+    block: Uuid,
+    pin: String,
+}
+
+impl Display for PinRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+/// The scenario document.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ScenarioDoc {
+    name: String,
+    #[serde(default)]
+    description: Option<String>,
+    program: ProgramDoc,
+    #[serde(default)]
+    timeline: Vec<StepDoc>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ProgramDoc {
+    Path(PathBuf),
+    Inline(Box<Program>),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StepDoc {
+    at: TimeDoc,
+    #[serde(default)]
+    write: BTreeMap<String, Value>,
+    #[serde(default)]
+    expect: BTreeMap<String, ExpectationDoc>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TimeDoc {
+    Millis(f64),
+    Zinc(String),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ExpectationDoc {
+    Approx(ApproxDoc),
+    Range(RangeDoc),
+    Exact(Value),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ApproxDoc {
+    approx: Value,
+    tolerance: f64,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RangeDoc {
+    #[serde(default)]
+    min: Option<Value>,
+    #[serde(default)]
+    max: Option<Value>,
+}
+
+impl ProgramScenario {
+    /// Reads a scenario file, and the program file it names.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a file cannot be read or parsed, or if the timeline is
+    /// invalid, such as naming a block that is not in the program.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ScenarioError> {
+        let path = path.as_ref();
+        let json = read(path)?;
+        Self::from_json(&json, path.parent().unwrap_or(Path::new("")))
+    }
+
+    /// Parses a scenario document, reading a program file it names
+    /// relative to `base_dir`.
+    ///
+    /// # Errors
+    ///
+    /// As for [`load`](Self::load).
+    pub fn from_json(json: &str, base_dir: impl AsRef<Path>) -> Result<Self, ScenarioError> {
+        let doc: ScenarioDoc =
+            serde_json::from_str(json).map_err(|err| ScenarioError::Invalid(err.to_string()))?;
+
+        let program = match doc.program {
+            ProgramDoc::Inline(program) => *program,
+            ProgramDoc::Path(path) => {
+                let path = base_dir.as_ref().join(path);
+                serde_json::from_str(&read(&path)?).map_err(|err| ScenarioError::File {
+                    path: path.display().to_string(),
+                    message: err.to_string(),
+                })?
+            }
+        };
+
+        let mut steps = Vec::with_capacity(doc.timeline.len());
+        let mut last_at = Duration::ZERO;
+        for (index, step) in doc.timeline.into_iter().enumerate() {
+            let invalid = |message: String| {
+                ScenarioError::Invalid(format!("Timeline step {}: {message}", index + 1))
+            };
+
+            let at = match step.at {
+                TimeDoc::Millis(millis) if millis >= 0.0 => {
+                    Duration::try_from_secs_f64(millis / 1000.0)
+                        .map_err(|err| invalid(format!("'{millis}': {err}")))?
+                }
+                TimeDoc::Millis(millis) => return Err(invalid(format!("'{millis}' is negative"))),
+                TimeDoc::Zinc(text) => parse_time(&text).map_err(invalid)?,
+            };
+            if at < last_at {
+                return Err(invalid(format!(
+                    "time goes back from {}ms",
+                    last_at.as_millis()
+                )));
+            }
+            last_at = at;
+
+            let writes = step
+                .write
+                .into_iter()
+                .map(|(name, value)| Ok((resolve(&program, name)?, value)))
+                .collect::<Result<_, ScenarioError>>()?;
+            let expects = step
+                .expect
+                .into_iter()
+                .map(|(name, expectation)| {
+                    let expectation = expectation.into_expectation().map_err(&invalid)?;
+                    Ok((resolve(&program, name)?, expectation))
+                })
+                .collect::<Result<_, ScenarioError>>()?;
+
+            steps.push(Step {
+                at,
+                writes,
+                expects,
+            });
+        }
+
+        Ok(Self {
+            name: doc.name,
+            description: doc.description,
+            program,
+            steps,
+            start: None,
+        })
+    }
+
+    /// Starts the blocks' clock at `time` instead of the current time,
+    /// for programs that depend on the time of day or the date.
+    pub fn starting_at(mut self, time: SystemTime) -> Self {
+        self.start = Some(time);
+        self
+    }
+
+    /// The name of the scenario.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The description of the scenario, if any.
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// The program the scenario runs.
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    /// The number of timeline steps.
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Whether the timeline is empty.
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Loads the program into a new engine and plays the timeline,
+    /// checking every expectation, on a runtime of its own.
+    ///
+    /// Unmet expectations are reported, not returned as errors.
+    ///
+    /// # Errors
+    ///
+    /// Fails if called on a Tokio runtime, which cannot block on another
+    /// one, if the engine rejects the program or a write, or if an
+    /// expectation names a pin its block does not have.
+    pub fn run(&self) -> Result<ScenarioReport, ScenarioError> {
+        if tokio::runtime::Handle::try_current().is_ok() {
+            return Err(ScenarioError::Engine(
+                "cannot block on a Tokio runtime, use run_async instead".to_string(),
+            ));
+        }
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .start_paused(true)
+            .build()
+            .map_err(|err| ScenarioError::Engine(err.to_string()))?;
+        runtime.block_on(self.run_async())
+    }
+
+    /// As [`run`](Self::run), on the current runtime, such as the one of
+    /// a `#[tokio::test(start_paused = true)]`.
+    ///
+    /// The runtime must be a current-thread one, as the blocks' clock is
+    /// per thread. Its clock should be paused, or the timeline plays in
+    /// real time.
+    ///
+    /// # Errors
+    ///
+    /// As for [`run`](Self::run), and fails on a multi-threaded runtime.
+    pub async fn run_async(&self) -> Result<ScenarioReport, ScenarioError> {
+        if tokio::runtime::Handle::current().runtime_flavor()
+            != tokio::runtime::RuntimeFlavor::CurrentThread
+        {
+            return Err(ScenarioError::Engine(
+                "scenarios run on a current-thread runtime".to_string(),
+            ));
+        }
+
+        let epoch_ms = self
+            .start
+            .unwrap_or_else(SystemTime::now)
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_millis() as u64);
+        let previous = VIRTUAL_CLOCK.replace(Some(VirtualClock::Tokio {
+            epoch_ms,
+            start: Instant::now(),
+        }));
+
+        let mut engine = SingleThreadedEngine::new();
+        let id = Uuid::new_v4();
+        let (reply_sender, replies) = mpsc::channel(32);
+        let sender = engine.create_message_channel(id, reply_sender);
+        let session = Session {
+            id,
+            sender,
+            replies,
+        };
+        let session = tokio::spawn(session.run(self.clone()));
+
+        engine.run().await;
+        let result = session.await;
+        VIRTUAL_CLOCK.set(previous);
+
+        result.map_err(|err| ScenarioError::Engine(err.to_string()))?
+    }
+}
+
+impl ExpectationDoc {
+    fn into_expectation(self) -> Result<Expectation, String> {
+        match self {
+            ExpectationDoc::Exact(value) => Ok(Expectation::Exact(value)),
+            ExpectationDoc::Approx(ApproxDoc { approx, tolerance }) => {
+                if tolerance.is_sign_negative() {
+                    return Err(format!("tolerance {tolerance} is negative"));
+                }
+                Ok(Expectation::Approx {
+                    value: number(approx)?,
+                    tolerance,
+                })
+            }
+            ExpectationDoc::Range(RangeDoc {
+                min: None,
+                max: None,
+            }) => Err("a range needs a min, a max or both".to_string()),
+            ExpectationDoc::Range(RangeDoc { min, max }) => Ok(Expectation::Range {
+                min: min.map(number).transpose()?,
+                max: max.map(number).transpose()?,
+            }),
+        }
+    }
+}
+
+impl Expectation {
+    /// Whether `actual` meets the expectation.
+    pub fn matches(&self, actual: &Value) -> bool {
+        match self {
+            Expectation::Exact(expected) => matches(expected, actual),
+            Expectation::Approx { value, tolerance } => in_unit_of(actual, value)
+                .is_some_and(|actual| (actual - value.value).abs() <= *tolerance),
+            Expectation::Range { min, max } => {
+                let above = |bound: &Number| {
+                    in_unit_of(actual, bound).is_some_and(|actual| actual >= bound.value)
+                };
+                let below = |bound: &Number| {
+                    in_unit_of(actual, bound).is_some_and(|actual| actual <= bound.value)
+                };
+                matches!(actual, Value::Number(_))
+                    && min.as_ref().is_none_or(above)
+                    && max.as_ref().is_none_or(below)
+            }
+        }
+    }
+}
+
+impl Display for Expectation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Numbers print as Zinc, with their unit.
+        let zinc = |num: &Number| Value::Number(*num);
+        match self {
+            Expectation::Exact(value) => write!(f, "{value}"),
+            Expectation::Approx { value, tolerance } => {
+                write!(f, "{} ± {tolerance}", zinc(value))
+            }
+            Expectation::Range {
+                min: Some(min),
+                max: Some(max),
+            } => write!(f, "between {} and {}", zinc(min), zinc(max)),
+            Expectation::Range { min: Some(min), .. } => write!(f, "at least {}", zinc(min)),
+            Expectation::Range { max: Some(max), .. } => write!(f, "at most {}", zinc(max)),
+            Expectation::Range { .. } => f.write_str("any number"),
+        }
+    }
+}
+
+impl ScenarioReport {
+    /// Whether every expectation was met.
+    pub fn is_ok(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+
+    /// Panics with the report unless every expectation was met.
+    #[track_caller]
+    pub fn assert_ok(&self) {
+        assert!(self.is_ok(), "{self}");
+    }
+}
+
+impl Display for ScenarioReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_ok() {
+            return write!(
+                f,
+                "Scenario '{}': all {} checks passed",
+                self.name, self.checks
+            );
+        }
+        write!(
+            f,
+            "Scenario '{}': {} of {} checks failed",
+            self.name,
+            self.mismatches.len(),
+            self.checks
+        )?;
+        for mismatch in &self.mismatches {
+            write!(f, "\n{mismatch}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "  at {:?} (step {}): {} expected {}, got {}\n    {}",
+            self.at, self.step, self.pin, self.expected, self.actual, self.context
+        )
+    }
+}
+
+/// The scenario's connection to the engine playing it.
+struct Session {
+    id: Uuid,
+    sender: Sender<Messages>,
+    replies: Receiver<Messages>,
+}
+
+impl Session {
+    async fn run(mut self, scenario: ProgramScenario) -> Result<ScenarioReport, ScenarioError> {
+        let report = self.play(&scenario).await;
+        let _ = self.sender.send(EngineMessage::Shutdown).await;
+        report
+    }
+
+    async fn play(&mut self, scenario: &ProgramScenario) -> Result<ScenarioReport, ScenarioError> {
+        match self
+            .request(EngineMessage::LoadProgramReq(
+                self.id,
+                scenario.program.clone(),
+            ))
+            .await?
+        {
+            EngineMessage::LoadProgramRes(result) => result.map_err(ScenarioError::Engine)?,
+            other => return Err(unexpected(&other)),
+        }
+
+        let start = Instant::now();
+        let mut report = ScenarioReport {
+            name: scenario.name.clone(),
+            checks: 0,
+            mismatches: Vec::new(),
+        };
+        for (index, step) in scenario.steps.iter().enumerate() {
+            tokio::time::sleep_until(start + step.at).await;
+
+            for (pin, value) in &step.writes {
+                self.write(pin, value.clone()).await?;
+            }
+            if step.expects.is_empty() {
+                continue;
+            }
+            tokio::time::sleep(SETTLE).await;
+
+            let mut blocks = BTreeMap::new();
+            for (pin, expected) in &step.expects {
+                let block = match blocks.entry(pin.block) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => entry.insert(self.inspect(pin).await?),
+                };
+                let actual = block
+                    .outputs
+                    .get(&pin.pin)
+                    .map(|output| &output.val)
+                    .or_else(|| block.inputs.get(&pin.pin).map(|input| &input.val))
+                    .ok_or_else(|| ScenarioError::UnknownPin(pin.name.clone()))?;
+
+                report.checks += 1;
+                if !expected.matches(actual) {
+                    report.mismatches.push(Mismatch {
+                        at: step.at,
+                        step: index + 1,
+                        pin: pin.name.clone(),
+                        expected: expected.clone(),
+                        actual: actual.clone(),
+                        context: describe(block),
+                    });
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// Writes the block's output by the pin's name if it has one, as a
+    /// block upstream of it would, and its input otherwise.
+    async fn write(&mut self, pin: &PinRef, value: Value) -> Result<(), ScenarioError> {
+        let block = self.inspect(pin).await?;
+        let (id, name) = (self.id, pin.pin.clone());
+        let result = if block.outputs.contains_key(&pin.pin) {
+            match self
+                .request(EngineMessage::WriteBlockOutputReq(
+                    id, pin.block, name, value,
+                ))
+                .await?
+            {
+                EngineMessage::WriteBlockOutputRes(result) => result.map(drop),
+                other => return Err(unexpected(&other)),
+            }
+        } else if block.inputs.contains_key(&pin.pin) {
+            match self
+                .request(EngineMessage::WriteBlockInputReq(
+                    id, pin.block, name, value,
+                ))
+                .await?
+            {
+                EngineMessage::WriteBlockInputRes(result) => result.map(drop),
+                other => return Err(unexpected(&other)),
+            }
+        } else {
+            return Err(ScenarioError::UnknownPin(pin.name.clone()));
+        };
+        result.map_err(|err| ScenarioError::Engine(format!("{pin}: {err}")))
+    }
+
+    async fn inspect(&mut self, pin: &PinRef) -> Result<BlockDefinition, ScenarioError> {
+        match self
+            .request(EngineMessage::InspectBlockReq(self.id, pin.block))
+            .await?
+        {
+            EngineMessage::InspectBlockRes(result) => {
+                result.map_err(|err| ScenarioError::Engine(format!("{pin}: {err}")))
+            }
+            other => Err(unexpected(&other)),
+        }
+    }
+
+    async fn request(&mut self, msg: Messages) -> Result<Messages, ScenarioError> {
+        let stopped = || ScenarioError::Engine("engine stopped".to_string());
+        self.sender.send(msg).await.map_err(|_| stopped())?;
+        self.replies.recv().await.ok_or_else(stopped)
+    }
+}
+
+/// Resolves a `<block>.<pin>` reference, the block by label or id.
+fn resolve(program: &Program, name: String) -> Result<PinRef, ScenarioError> {
+    let unknown = || ScenarioError::UnknownPin(name.clone());
+    let (block, pin) = name.rsplit_once('.').ok_or_else(unknown)?;
+
+    let mut labelled = program
+        .blocks
+        .iter()
+        .filter(|(_, data)| data.label.as_deref() == Some(block));
+    let id = match (labelled.next(), labelled.next()) {
+        (Some((id, _)), None) => id.as_str(),
+        (Some(_), Some(_)) => {
+            return Err(ScenarioError::Invalid(format!(
+                "'{name}': more than one block is labelled '{block}'"
+            )));
+        }
+        (None, _) if program.blocks.contains_key(block) => block,
+        (None, _) => return Err(unknown()),
+    };
+
+    Ok(PinRef {
+        block: Uuid::parse_str(id).map_err(|_| unknown())?,
+        pin: pin.to_string(),
+        name,
+    })
+}
+
+/// The number an approx or range expectation holds.
+fn number(value: Value) -> Result<Number, String> {
+    match value {
+        Value::Number(num) => Ok(num),
+        other => Err(format!("{other} is not a number")),
+    }
+}
+
+/// The value of `actual` in the unit of `expected`, if it is a number of
+/// a compatible unit.
+fn in_unit_of(actual: &Value, expected: &Number) -> Option<f64> {
+    let Value::Number(actual) = actual else {
+        return None;
+    };
+    match expected.unit {
+        None => Some(actual.value),
+        Some(unit) => units::convert(*actual, unit).ok().map(|num| num.value),
+    }
+}
+
+/// A block's type, state and pin values, for a mismatch report.
+fn describe(block: &BlockDefinition) -> String {
+    let state = match &block.fault_reason {
+        Some(reason) => format!("{} \"{reason}\"", block.state),
+        None => block.state.clone(),
+    };
+    let pins: Vec<String> = block
+        .inputs
+        .iter()
+        .map(|(name, input)| format!("{name} = {}", input.val))
+        .chain(
+            block
+                .outputs
+                .iter()
+                .map(|(name, output)| format!("{name} = {}", output.val)),
+        )
+        .collect();
+    format!("{} {} {state}; {}", block.name, block.id, pins.join(", "))
+}
+
+fn read(path: &Path) -> Result<String, ScenarioError> {
+    std::fs::read_to_string(path).map_err(|err| ScenarioError::File {
+        path: path.display().to_string(),
+        message: err.to_string(),
+    })
+}
+
+fn unexpected(msg: &Messages) -> ScenarioError {
+    ScenarioError::Engine(format!("unexpected engine reply: {msg:?}"))
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+    use std::time::Duration;
+
+    use libhaystack::units::get_unit;
+    use libhaystack::val::{Number, Value};
+
+    use super::{Expectation, ProgramScenario};
+    use crate::testing::ScenarioError;
+
+    const PROGRAM: &str = r#"{
+        "version": 1,
+        "blocks": {
+            "00000000-0000-4000-8000-000000000001": {
+                "name": "OnDelay",
+                "lib": "core",
+                "label": "warmup",
+                "inputs": { "delay": { "value": 1000 } }
+            },
+            "00000000-0000-4000-8000-000000000002": {
+                "name": "Not",
+                "lib": "core"
+            }
+        },
+        "links": {
+            "00000000-0000-4000-8000-000000000003": {
+                "sourceBlockUuid": "00000000-0000-4000-8000-000000000001",
+                "sourceBlockPinName": "out",
+                "targetBlockUuid": "00000000-0000-4000-8000-000000000002",
+                "targetBlockPinName": "in"
+            }
+        }
+    }"#;
+
+    fn scenario(timeline: &str) -> Result<ProgramScenario, ScenarioError> {
+        let json = format!(r#"{{ "name": "test", "program": {PROGRAM}, "timeline": {timeline} }}"#);
+        ProgramScenario::from_json(&json, "")
+    }
+
+    #[test]
+    fn test_expectations() {
+        let degf = get_unit("°F").unwrap();
+        let degc = Value::make_number_unit(20.0, get_unit("°C").unwrap());
+
+        assert!(Expectation::Exact(true.into()).matches(&true.into()));
+        assert!(!Expectation::Exact(1.into()).matches(&true.into()));
+
+        let approx = Expectation::Approx {
+            value: Number::make_with_unit(68.0, degf),
+            tolerance: 0.5,
+        };
+        assert!(approx.matches(&degc));
+        assert!(!approx.matches(&Value::make_number_unit(21.0, get_unit("°C").unwrap())));
+        assert!(!approx.matches(&Value::make_number_unit(68.0, get_unit("kPa").unwrap())));
+        assert_eq!(approx.to_string(), "68°F ± 0.5");
+
+        let range = Expectation::Range {
+            min: Some(Number::make(10.0)),
+            max: None,
+        };
+        assert!(range.matches(&10.into()));
+        assert!(!range.matches(&9.into()));
+        assert!(!range.matches(&"10".into()));
+        assert_eq!(range.to_string(), "at least 10");
+    }
+
+    #[test]
+    fn test_run_program_scenario() {
+        let scenario = scenario(
+            r#"[
+                { "at": 0, "write": { "warmup.in": true } },
+                { "at": "0.9s", "expect": { "warmup.out": false } },
+                { "at": "1.5s", "expect": {
+                    "warmup.out": true,
+                    "00000000-0000-4000-8000-000000000002.out": false,
+                    "warmup.delay": { "min": 500, "max": 1000 }
+                } }
+            ]"#,
+        )
+        .unwrap();
+
+        let report = scenario.run().unwrap();
+        assert_eq!(report.checks, 4);
+        assert!(report.is_ok(), "{report}");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_run_on_a_tokio_runtime() {
+        let scenario = scenario(
+            r#"[
+                { "at": 0, "write": { "warmup.in": true } },
+                { "at": "1.5s", "expect": { "warmup.out": true } }
+            ]"#,
+        )
+        .unwrap();
+
+        assert!(matches!(scenario.run(), Err(ScenarioError::Engine(_))));
+        let report = scenario.run_async().await.unwrap();
+        assert_eq!(report.checks, 1);
+        assert!(report.is_ok(), "{report}");
+    }
+
+    #[test]
+    fn test_report_mismatches() {
+        let scenario = scenario(
+            r#"[
+                { "at": 0, "write": { "warmup.in": true } },
+                { "at": 500, "expect": { "warmup.out": true } }
+            ]"#,
+        )
+        .unwrap();
+
+        let report = scenario.run().unwrap();
+        assert_eq!(report.checks, 1);
+        assert_eq!(report.mismatches.len(), 1);
+        assert_eq!(report.mismatches[0].at, Duration::from_millis(500));
+        assert_eq!(report.mismatches[0].actual, false.into());
+
+        let text = report.to_string();
+        assert!(text.starts_with("Scenario 'test': 1 of 1 checks failed"));
+        assert!(text.contains("at 500ms (step 2): warmup.out expected true, got false"));
+        assert!(text.contains("OnDelay 00000000-0000-4000-8000-000000000001 running;"));
+    }
+
+    #[test]
+    fn test_invalid_scenarios() {
+        assert_eq!(
+            scenario(r#"[{ "at": 0, "write": { "boost.in": true } }]"#),
+            Err(ScenarioError::UnknownPin("boost.in".into()))
+        );
+        assert!(matches!(
+            scenario(r#"[{ "at": "2s" }, { "at": "1s" }]"#),
+            Err(ScenarioError::Invalid(_))
+        ));
+        assert!(matches!(
+            scenario(r#"[{ "at": 1e30 }]"#),
+            Err(ScenarioError::Invalid(_))
+        ));
+        assert!(matches!(
+            scenario(r#"[{ "at": 0, "expect": { "warmup.out": {} } }]"#),
+            Err(ScenarioError::Invalid(_))
+        ));
+        assert!(matches!(
+            ProgramScenario::load("no/such.scenario.json"),
+            Err(ScenarioError::File { .. })
+        ));
+        assert!(matches!(
+            scenario(r#"[{ "at": 0, "expect": { "warmup.boost": true } }]"#)
+                .unwrap()
+                .run(),
+            Err(ScenarioError::UnknownPin(_))
+        ));
+    }
+
+    #[test]
+    fn test_bundled_scenarios() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenarios");
+        let mut count = 0;
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if !path.to_string_lossy().ends_with(".scenario.json") {
+                continue;
+            }
+            let scenario = ProgramScenario::load(&path)
+                .unwrap_or_else(|err| panic!("{}: {err}", path.display()));
+            scenario.run().unwrap().assert_ok();
+            count += 1;
+        }
+        assert!(count >= 2);
+    }
+}
@@ -0,0 +1,388 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Table-driven block scenarios.
+
+use std::fmt::{self, Display};
+use std::time::Duration;
+
+use libhaystack::encoding::zinc;
+use libhaystack::units::get_unit;
+use libhaystack::val::{Bool, Value};
+
+use super::ScenarioError;
+use crate::base::Status;
+use crate::base::block::BlockState;
+
+/// A table of inputs to write to a block and of the outputs, statuses
+/// and state to expect of it, one row per point in time, played by
+/// [`TestBench::run`](super::TestBench::run).
+///
+/// The table is written in the Markdown style: cells are separated by
+/// `|`, the first row is the header, and rows made of dashes, blank
+/// lines and lines starting with `#` are skipped. The header names the
+/// columns:
+///
+/// - `at`, the time of the row since the start of the scenario, as a
+///   Zinc number with a duration unit (`500ms`, `2s`, `1min`) or a plain
+///   number of milliseconds. Rows without one run at the time of the row
+///   before.
+/// - An input pin, such as `in`, written before the row is settled.
+/// - An output pin, such as `out`, checked after the row is settled.
+/// - `<output>.status`, such as `out.status`: the status the output was
+///   sent with, `ok`, `fault` or `stale`.
+/// - `state`, the block state: `running`, `fault`, `disabled`, or a
+///   quoted fault reason such as `"Add: Incompatible units °F and kPa"`.
+///
+/// Pin cells hold Zinc scalars, such as `true`, `21.5°C`, `"text"` or
+/// `[1, 2]`; `true` and `false` are accepted along with Zinc's `T` and
+/// `F`. An empty cell writes or checks nothing. Cells cannot contain a
+/// `|`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scenario {
+    columns: Vec<Column>,
+    rows: Vec<Row>,
+}
+
+/// What a scenario column holds.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Column {
+    /// The time of the row.
+    At,
+    /// A pin to write, or to check.
+    Pin(String),
+    /// The status of an output.
+    Status(String),
+    /// The block state.
+    State,
+}
+
+/// A parsed scenario row.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Row {
+    /// The 1-based line of the row in the scenario text.
+    pub line: usize,
+    /// The time of the row since the start of the scenario.
+    pub at: Option<Duration>,
+    /// The pin cells, by pin name.
+    pub pins: Vec<(String, Value)>,
+    /// The expected output statuses, by output name.
+    pub statuses: Vec<(String, Status)>,
+    /// The expected block state.
+    pub state: Option<ExpectedState>,
+}
+
+/// The block state a row expects.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum ExpectedState {
+    /// A state with this [`BlockState::label`].
+    Label(String),
+    /// A fault with this reason.
+    Fault(String),
+}
+
+impl ExpectedState {
+    /// Whether `state` is the one expected.
+    pub fn matches(&self, state: &BlockState) -> bool {
+        match self {
+            ExpectedState::Label(label) => state.label() == label,
+            ExpectedState::Fault(reason) => state.fault_reason() == Some(reason),
+        }
+    }
+}
+
+impl Display for ExpectedState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExpectedState::Label(label) => f.write_str(label),
+            ExpectedState::Fault(reason) => write!(f, "fault \"{reason}\""),
+        }
+    }
+}
+
+impl Scenario {
+    /// Parses a scenario table.
+    ///
+    /// # Errors
+    ///
+    /// Fails if there is no header, if a row does not have as many cells
+    /// as the header, or if a cell does not parse, such as times that go
+    /// backwards.
+    pub fn parse(text: &str) -> Result<Self, ScenarioError> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .map(|(line, text)| (line, cells(text)))
+            .filter(|(_, cells)| !is_separator(cells));
+
+        let (_, header) = lines.next().ok_or(ScenarioError::MissingHeader)?;
+        let columns: Vec<Column> = header
+            .iter()
+            .map(|name| match *name {
+                "at" => Column::At,
+                "state" => Column::State,
+                name => match name.strip_suffix(".status") {
+                    Some(pin) => Column::Status(pin.to_string()),
+                    None => Column::Pin(name.to_string()),
+                },
+            })
+            .collect();
+
+        let mut rows = Vec::new();
+        let mut last_at = Duration::ZERO;
+        for (line, cells) in lines {
+            if cells.len() != columns.len() {
+                return Err(ScenarioError::CellCount {
+                    line,
+                    expected: columns.len(),
+                    actual: cells.len(),
+                });
+            }
+
+            let mut row = Row {
+                line,
+                at: None,
+                pins: Vec::new(),
+                statuses: Vec::new(),
+                state: None,
+            };
+            for ((column, name), cell) in columns.iter().zip(&header).zip(cells) {
+                if cell.is_empty() {
+                    continue;
+                }
+                let invalid = |message: String| ScenarioError::InvalidCell {
+                    line,
+                    column: name.to_string(),
+                    message,
+                };
+                match column {
+                    Column::At => {
+                        let at = parse_time(cell).map_err(invalid)?;
+                        if at < last_at {
+                            return Err(invalid(format!(
+                                "time goes back from {}ms",
+                                last_at.as_millis()
+                            )));
+                        }
+                        last_at = at;
+                        row.at = Some(at);
+                    }
+                    Column::Pin(pin) => {
+                        row.pins
+                            .push((pin.clone(), parse_value(cell).map_err(invalid)?));
+                    }
+                    Column::Status(pin) => {
+                        row.statuses
+                            .push((pin.clone(), parse_status(cell).map_err(invalid)?));
+                    }
+                    Column::State => row.state = Some(parse_state(cell).map_err(invalid)?),
+                }
+            }
+            rows.push(row);
+        }
+
+        Ok(Self { columns, rows })
+    }
+
+    /// The number of rows, without the header.
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Whether the scenario has no rows.
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    pub(super) fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    pub(super) fn rows(&self) -> &[Row] {
+        &self.rows
+    }
+}
+
+/// Whether `actual` is the `expected` value, comparing numbers with a
+/// relative tolerance, element-wise in lists.
+pub(super) fn matches(expected: &Value, actual: &Value) -> bool {
+    match (expected, actual) {
+        (Value::Number(expected), Value::Number(actual)) => {
+            let tolerance = 1e-9 * expected.value.abs().max(1.0);
+            expected.unit == actual.unit && (expected.value - actual.value).abs() <= tolerance
+        }
+        (Value::List(expected), Value::List(actual)) => {
+            expected.len() == actual.len()
+                && expected
+                    .iter()
+                    .zip(actual.iter())
+                    .all(|(expected, actual)| matches(expected, actual))
+        }
+        (expected, actual) => expected == actual,
+    }
+}
+
+/// The trimmed cells of a table line.
+fn cells(line: &str) -> Vec<&str> {
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = line.strip_suffix('|').unwrap_or(line);
+    line.split('|').map(str::trim).collect()
+}
+
+/// Whether the cells are a `|---|---|` separator.
+fn is_separator(cells: &[&str]) -> bool {
+    cells
+        .iter()
+        .all(|cell| !cell.is_empty() && cell.chars().all(|c| matches!(c, '-' | ':')))
+}
+
+pub(super) fn parse_time(cell: &str) -> Result<Duration, String> {
+    let Ok(Value::Number(num)) = zinc::decode::from_str(cell) else {
+        return Err(format!("'{cell}' is not a number"));
+    };
+    let millis = match num.unit {
+        None => num.value,
+        Some(unit) => {
+            let ms = get_unit("ms").expect("ms is a unit");
+            unit.convert_to(num.value, ms)
+                .map_err(|_| format!("'{cell}' is not a duration"))?
+        }
+    };
+    if millis < 0.0 {
+        return Err(format!("'{cell}' is negative"));
+    }
+    Ok(Duration::from_secs_f64(millis / 1000.0))
+}
+
+fn parse_value(cell: &str) -> Result<Value, String> {
+    match cell {
+        "true" => Ok(Bool::from(true).into()),
+        "false" => Ok(Bool::from(false).into()),
+        cell => zinc::decode::from_str(cell).map_err(|err| format!("'{cell}': {err}")),
+    }
+}
+
+fn parse_status(cell: &str) -> Result<Status, String> {
+    match cell.to_ascii_lowercase().as_str() {
+        "ok" => Ok(Status::Ok),
+        "fault" => Ok(Status::Fault),
+        "stale" => Ok(Status::Stale),
+        _ => Err(format!("'{cell}' is not ok, fault or stale")),
+    }
+}
+
+fn parse_state(cell: &str) -> Result<ExpectedState, String> {
+    match cell {
+        "running" | "fault" | "disabled" | "terminated" => {
+            Ok(ExpectedState::Label(cell.to_string()))
+        }
+        cell => match zinc::decode::from_str(cell) {
+            Ok(Value::Str(reason)) => Ok(ExpectedState::Fault(reason.value)),
+            _ => Err(format!(
+                "'{cell}' is not running, fault, disabled or a quoted fault reason"
+            )),
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use libhaystack::val::Value;
+
+    use super::{ExpectedState, Scenario};
+    use crate::base::Status;
+    use crate::blocks::math::Add;
+    use crate::blocks::timers::OnDelay;
+    use crate::testing::{ScenarioError, TestBench};
+
+    #[test]
+    fn test_parse_scenario() {
+        let scenario = Scenario::parse(
+            r#"
+            | at  | in0  | out       | out.status | state           |
+            |-----|------|-----------|------------|-----------------|
+            # Comments and blank lines are skipped.
+
+            | 0   | 1°C  | [1, 2]    | ok         | running         |
+            | 1s  | true |           | fault      | "Add: overflow" |
+            "#,
+        )
+        .unwrap();
+        assert_eq!(scenario.len(), 2);
+
+        let rows = scenario.rows();
+        assert_eq!(rows[0].line, 6);
+        assert_eq!(rows[0].at, Some(Duration::ZERO));
+        assert_eq!(
+            rows[0].pins[1],
+            (
+                "out".to_string(),
+                Value::make_list(vec![1.into(), 2.into()])
+            )
+        );
+        assert_eq!(rows[1].at, Some(Duration::from_secs(1)));
+        assert_eq!(rows[1].pins, [("in0".to_string(), true.into())]);
+        assert_eq!(rows[1].statuses, [("out".to_string(), Status::Fault)]);
+        assert_eq!(
+            rows[1].state,
+            Some(ExpectedState::Fault("Add: overflow".into()))
+        );
+    }
+
+    #[test]
+    fn test_parse_scenario_errors() {
+        assert_eq!(Scenario::parse("\n"), Err(ScenarioError::MissingHeader));
+        assert_eq!(
+            Scenario::parse("| in | out |\n| 1 |"),
+            Err(ScenarioError::CellCount {
+                line: 2,
+                expected: 2,
+                actual: 1
+            })
+        );
+        assert!(matches!(
+            Scenario::parse("| at | in |\n| 2s | 1 |\n| 1s | 2 |"),
+            Err(ScenarioError::InvalidCell { line: 3, .. })
+        ));
+        assert!(matches!(
+            Scenario::parse("| at | in |\n| 2kPa | 1 |"),
+            Err(ScenarioError::InvalidCell { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_run_scenario() {
+        let scenario = Scenario::parse(
+            r#"
+            | at    | in    | delay | out   | out.status |
+            |-------|-------|-------|-------|------------|
+            | 0     | true  | 1min  | false | ok         |
+            | 59.9s |       |       | false |            |
+            | 1min  |       |       | true  |            |
+            | 2min  | false |       | false | ok         |
+            "#,
+        )
+        .unwrap();
+
+        let mut bench = TestBench::new(OnDelay::new());
+        bench.run(&scenario);
+        assert_eq!(bench.elapsed(), Duration::from_secs(120));
+    }
+
+    #[test]
+    #[should_panic(expected = "line 3: Add.out: expected 4, got 3")]
+    fn test_run_scenario_failure() {
+        let scenario = Scenario::parse(
+            "
+            | in0 | in1 | out | state   |
+            | 1   | 2   | 4   | running |
+            ",
+        )
+        .unwrap();
+
+        TestBench::new(Add::new()).run(&scenario);
+    }
+}
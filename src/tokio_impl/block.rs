@@ -7,7 +7,7 @@ use libhaystack::val::kind::HaystackKind;
 
 use super::sleep::sleep_millis;
 use crate::base::Status;
-use crate::base::block::{Block, BlockState, convert_value_kind};
+use crate::base::block::{Block, BlockState, convert_list_kind, convert_value_kind};
 use crate::base::input::{InputProps, input_reader::InputReader};
 use crate::blocks::InputImpl;
 use crate::blocks::utils::get_sleep_dur;
@@ -92,7 +92,8 @@ impl<B: Block> InputReader for B {
 ///   wakes up, drain again (the wait might have woken several at once).
 /// - Returns [`None`] only when the block has no connected inputs at all.
 ///
-/// Type-mismatched inputs are converted via [`convert_value_kind`]; if the
+/// Type-mismatched inputs are converted via [`convert_value_kind`], or
+/// [`convert_list_kind`] for lists on inputs that accept them; if the
 /// conversion fails the block transitions to [`BlockState::Fault`].
 pub(crate) async fn read_block_inputs<B: Block>(block: &mut B) -> Option<usize> {
     // Phase 1: drain anything already ready, no awaiting.
@@ -148,7 +149,12 @@ fn drain_ready_inputs<B: Block>(block: &mut B) -> Option<usize> {
             let expected = *input.kind();
             let actual = HaystackKind::from(&value);
             let converted = if expected != HaystackKind::Null && expected != actual {
-                match convert_value_kind(value, expected, actual) {
+                let converted = if actual == HaystackKind::List && input.accepts_lists() {
+                    convert_list_kind(value, expected)
+                } else {
+                    convert_value_kind(value, expected, actual)
+                };
+                match converted {
                     Ok(v) => v,
                     Err(err) => {
                         log::error!("Error converting value: {}", err);
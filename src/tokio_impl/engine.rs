@@ -1,10 +1,6 @@
 // Copyright (c) 2022-2023, Radu Racariu.
 
 use crate::base::error::Result;
-// External blocks only exist on `wasm32`, where the host supplies their
-// executor; every other target rejects them with this error.
-#[cfg(not(target_arch = "wasm32"))]
-use crate::base::error::ExternalError;
 use libhaystack::val::Value;
 use uuid::Uuid;
 
@@ -15,8 +11,13 @@ use crate::{
 
 use self::single_threaded::SingleThreadedEngine;
 
+mod alarm;
 mod block_mailbox;
+#[cfg(not(target_arch = "wasm32"))]
+mod external;
+mod history;
 mod message_dispatch;
+mod programs;
 pub mod single_threaded;
 
 #[cfg(feature = "multi-threaded")]
@@ -36,7 +37,7 @@ pub(super) fn schedule_block_on_engine(
         }
         #[cfg(not(target_arch = "wasm32"))]
         {
-            Err(ExternalError::Unsupported.into())
+            external::schedule_external_block(engine, block, block_id)
         }
     } else if let Some(uuid) = block_id {
         schedule_block_with_uuid(&block.name, Some(&block.library), uuid, engine)
@@ -55,7 +56,7 @@ pub(super) fn schedule_block_on_engine_mt(
     use crate::blocks::registry::{schedule_block_send, schedule_block_send_with_uuid};
 
     if block.implementation == BlockImplementation::External {
-        Err(ExternalError::UnsupportedMultiThreaded.into())
+        external::schedule_external_block_send(engine, block, block_id)
     } else if let Some(uuid) = block_id {
         schedule_block_send_with_uuid(&block.name, Some(&block.library), uuid, engine)
     } else {
@@ -72,7 +73,7 @@ pub(super) async fn eval_block(block: &BlockDesc, inputs: Vec<Value>) -> Result<
         }
         #[cfg(not(target_arch = "wasm32"))]
         {
-            Err(ExternalError::Unsupported.into())
+            external::eval_external_block(block, inputs).await
         }
     } else {
         eval_static_block(&block.name, Some(&block.library), inputs).await
@@ -0,0 +1,65 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Engine side of the [`AlarmTable`]: the task feeding it the engine's
+//! change notifications and lifting expired shelving, shared by both
+//! engines.
+
+use tokio::sync::mpsc::UnboundedReceiver;
+use uuid::Uuid;
+
+use crate::base::alarm::{ALARM_CATEGORY, ALARM_INPUTS, AlarmTable};
+use crate::base::block::BlockDesc;
+use crate::base::engine::messages::WatchMessage;
+use crate::base::program::Program;
+use crate::tokio_impl::sleep::{current_time_millis, sleep_millis};
+
+/// Records the notifications of `feed` and lifts the shelving that
+/// expires, until the engine drops the feed's sender.
+pub(super) async fn track_alarms(alarms: AlarmTable, mut feed: UnboundedReceiver<WatchMessage>) {
+    loop {
+        let next = alarms.unshelve_due(current_time_millis());
+        let due = async {
+            match next {
+                Some(due) => sleep_millis(due.saturating_sub(current_time_millis())).await,
+                None => std::future::pending().await,
+            }
+        };
+
+        tokio::select! {
+            msg = feed.recv() => match msg {
+                Some(msg) => alarms.record(&msg, current_time_millis()),
+                None => break,
+            },
+            _ = due => {}
+        }
+    }
+}
+
+/// Registers the block `id` as a source of `alarms` if it is an alarm
+/// block.
+pub(super) fn watch_source(alarms: Option<&AlarmTable>, id: Uuid, desc: &BlockDesc) {
+    if let Some(alarms) = alarms
+        && desc.category == ALARM_CATEGORY
+    {
+        alarms.watch(id, &desc.name);
+    }
+}
+
+/// Seeds the alarm sources among the blocks of `program` with the
+/// priority and message constants it holds for them, so an alarm the
+/// block raises before reporting those inputs already carries them.
+pub(super) fn seed_program_sources(alarms: Option<&AlarmTable>, program: &Program) {
+    let Some(alarms) = alarms else {
+        return;
+    };
+    for (id, block) in &program.blocks {
+        let Ok(id) = Uuid::parse_str(id) else {
+            continue;
+        };
+        for pin in ALARM_INPUTS {
+            if let Some(input) = block.inputs.get(pin) {
+                alarms.configure(&id, pin, &input.value);
+            }
+        }
+    }
+}
@@ -119,6 +119,10 @@ pub(super) enum BlockMailboxCmd {
     GetBlockData {
         reply: oneshot::Sender<(BlockData, Vec<LinkData>)>,
     },
+    /// Pause or resume the block. A paused block is
+    /// [`Disabled`](BlockState::Disabled): it keeps answering mailbox
+    /// commands but does not execute.
+    SetPaused { paused: bool },
     /// Force the block into the Terminated state and exit the actor task.
     Terminate,
 }
@@ -314,6 +318,14 @@ where
             let _ = reply.send(snapshot_block_data(block));
         }
 
+        BlockMailboxCmd::SetPaused { paused } => {
+            if paused {
+                block.set_state(BlockState::Disabled);
+            } else if block.state() == BlockState::Disabled {
+                block.set_state(BlockState::Running);
+            }
+        }
+
         BlockMailboxCmd::Terminate => {
             block.set_state(BlockState::Terminated);
             return true;
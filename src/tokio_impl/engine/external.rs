@@ -0,0 +1,97 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Executors of external blocks on native builds.
+//!
+//! A block loaded from a WebAssembly plugin (`plugins` feature) runs in
+//! its plugin; any other external block runs the script registered for it
+//! (`scripting` feature). Builds without either reject external blocks.
+
+use libhaystack::val::Value;
+use uuid::Uuid;
+
+use crate::base::block::BlockDesc;
+#[cfg(not(feature = "scripting"))]
+use crate::base::error::ExternalError;
+use crate::base::error::Result;
+
+use super::single_threaded::SingleThreadedEngine;
+
+pub(super) fn schedule_external_block(
+    engine: &mut SingleThreadedEngine,
+    desc: &BlockDesc,
+    block_id: Option<Uuid>,
+) -> Result<Uuid> {
+    #[cfg(feature = "plugins")]
+    if crate::plugins::resolve_plugin(desc).is_some() {
+        return crate::plugins::schedule_plugin_block(engine, desc, block_id);
+    }
+    #[cfg(feature = "scripting")]
+    {
+        crate::scripting::schedule_script_block(engine, desc, block_id)
+    }
+    #[cfg(not(feature = "scripting"))]
+    {
+        let _ = (engine, block_id);
+        Err(unavailable(desc, ExternalError::Unsupported))
+    }
+}
+
+#[cfg(feature = "multi-threaded")]
+pub(super) fn schedule_external_block_send(
+    engine: &mut super::multi_threaded::MultiThreadedEngine,
+    desc: &BlockDesc,
+    block_id: Option<Uuid>,
+) -> Result<Uuid> {
+    #[cfg(feature = "plugins")]
+    if crate::plugins::resolve_plugin(desc).is_some() {
+        return crate::plugins::schedule_plugin_block_send(engine, desc, block_id);
+    }
+    #[cfg(feature = "scripting")]
+    {
+        crate::scripting::schedule_script_block_send(engine, desc, block_id)
+    }
+    #[cfg(not(feature = "scripting"))]
+    {
+        let _ = (engine, block_id);
+        Err(unavailable(desc, ExternalError::UnsupportedMultiThreaded))
+    }
+}
+
+pub(super) async fn eval_external_block(
+    desc: &BlockDesc,
+    inputs: Vec<Value>,
+) -> Result<Vec<Value>> {
+    #[cfg(feature = "plugins")]
+    if crate::plugins::resolve_plugin(desc).is_some() {
+        return crate::plugins::eval_plugin_block(desc, inputs).await;
+    }
+    #[cfg(feature = "scripting")]
+    {
+        crate::scripting::eval_script_block(desc, inputs).await
+    }
+    #[cfg(not(feature = "scripting"))]
+    {
+        let _ = inputs;
+        Err(unavailable(desc, ExternalError::Unsupported))
+    }
+}
+
+/// The error for an external block nothing executes: `unsupported` when
+/// this build has no executors at all.
+#[cfg(not(feature = "scripting"))]
+fn unavailable(desc: &BlockDesc, unsupported: ExternalError) -> crate::base::error::Error {
+    #[cfg(feature = "plugins")]
+    {
+        let _ = unsupported;
+        ExternalError::NotRegistered {
+            library: desc.library.clone(),
+            name: desc.name.clone(),
+        }
+        .into()
+    }
+    #[cfg(not(feature = "plugins"))]
+    {
+        let _ = desc;
+        unsupported.into()
+    }
+}
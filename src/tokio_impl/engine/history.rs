@@ -0,0 +1,94 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Engine side of the trend [`History`]: the task recording the
+//! engine's change notifications into it, shared by both engines.
+
+use tokio::sync::mpsc::UnboundedReceiver;
+
+use crate::base::Status;
+use crate::base::engine::messages::{BlockDefinition, WatchMessage};
+use crate::base::error::HistoryError;
+use crate::base::history::{History, HistoryQuery, Point, Recording, Sample};
+use crate::tokio_impl::sleep::{current_time_millis, sleep_millis};
+
+/// Records the notifications of `feed` and samples the interval trends
+/// when due, until the engine drops the feed's sender.
+pub(super) async fn record_history(history: History, mut feed: UnboundedReceiver<WatchMessage>) {
+    loop {
+        let sampler = history.clone();
+        let next = with_store(move || sampler.sample_due(current_time_millis())).await;
+        let due = async {
+            match next {
+                Some(due) => sleep_millis(due.saturating_sub(current_time_millis())).await,
+                None => std::future::pending().await,
+            }
+        };
+
+        tokio::select! {
+            msg = feed.recv() => match msg {
+                Some(msg) => {
+                    let recorder = history.clone();
+                    let now = current_time_millis();
+                    with_store(move || recorder.record(&msg, now)).await;
+                }
+                None => break,
+            },
+            _ = due => {}
+        }
+    }
+}
+
+/// Runs `task`, which reads or writes the history store, on the blocking
+/// thread pool, so a store on disk does not stall the engine's task.
+#[cfg(not(target_arch = "wasm32"))]
+pub(super) async fn with_store<T: Send + 'static>(task: impl FnOnce() -> T + Send + 'static) -> T {
+    tokio::task::spawn_blocking(task)
+        .await
+        .unwrap_or_else(|err| std::panic::resume_unwind(err.into_panic()))
+}
+
+/// Runs `task` in place: the browser has no blocking pool, and its store
+/// is in memory.
+#[cfg(target_arch = "wasm32")]
+pub(super) async fn with_store<T>(task: impl FnOnce() -> T) -> T {
+    task()
+}
+
+/// Trends `point` of `block`, seeding the trend with the pin's current
+/// value.
+pub(super) async fn trend_pin(
+    history: &History,
+    block: &BlockDefinition,
+    point: Point,
+    recording: Recording,
+) -> Result<(), HistoryError> {
+    let value = block
+        .inputs
+        .get(&point.pin)
+        .map(|input| &input.val)
+        .or_else(|| block.outputs.get(&point.pin).map(|output| &output.val))
+        .ok_or_else(|| HistoryError::PinNotFound {
+            block: point.block,
+            pin: point.pin.clone(),
+        })?
+        .clone();
+    let status = match block.fault_reason {
+        Some(_) => Status::Fault,
+        None => Status::Ok,
+    };
+
+    history.trend(point.clone(), recording);
+    let history = history.clone();
+    let now = current_time_millis();
+    with_store(move || history.observe(&point, value, status, now)).await;
+    Ok(())
+}
+
+/// Reads the samples `query` asks for from `history`.
+pub(super) async fn query_history(
+    history: &History,
+    query: HistoryQuery,
+) -> Result<Vec<Sample>, HistoryError> {
+    let history = history.clone();
+    with_store(move || history.query(&query)).await
+}
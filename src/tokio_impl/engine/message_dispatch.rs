@@ -145,6 +145,17 @@ pub(super) async fn dispatch_message(engine: &mut SingleThreadedEngine, msg: Mes
             );
         }
 
+        EngineMessage::GetProgramReq(sender_uuid, program_id) => {
+            log::debug!("GetProgramReq: {program_id}");
+
+            let program = engine
+                .save_named_program(&program_id)
+                .await
+                .map_err(|err| err.to_string());
+
+            reply_to_sender(engine, sender_uuid, EngineMessage::GetProgramRes(program));
+        }
+
         EngineMessage::LoadProgramReq(sender_uuid, program) => {
             log::debug!(
                 "LoadProgramReq: {} blocks, {} links",
@@ -160,6 +171,25 @@ pub(super) async fn dispatch_message(engine: &mut SingleThreadedEngine, msg: Mes
             reply_to_sender(engine, sender_uuid, EngineMessage::LoadProgramRes(res));
         }
 
+        EngineMessage::ProgramReq(sender_uuid, program_id, action) => {
+            log::debug!("ProgramReq: {}", program_id);
+
+            let res = engine
+                .program_action(&program_id, action)
+                .await
+                .map_err(|err| err.to_string());
+            reply_to_sender(engine, sender_uuid, EngineMessage::ProgramRes(res));
+        }
+
+        EngineMessage::ListProgramsReq(sender_uuid) => {
+            let programs = engine.programs();
+            reply_to_sender(
+                engine,
+                sender_uuid,
+                EngineMessage::ListProgramsRes(programs),
+            );
+        }
+
         EngineMessage::ConnectBlocksReq(sender_uuid, link_data) => {
             log::debug!("ConnectBlocksReq: {:?}", link_data);
 
@@ -180,6 +210,34 @@ pub(super) async fn dispatch_message(engine: &mut SingleThreadedEngine, msg: Mes
             reply_to_sender(engine, sender_uuid, EngineMessage::RemoveLinkRes(res));
         }
 
+        EngineMessage::TrendReq(sender_uuid, point, recording) => {
+            let res = engine
+                .trend_pin(point, recording)
+                .await
+                .map_err(|err| err.to_string());
+            reply_to_sender(engine, sender_uuid, EngineMessage::TrendRes(res));
+        }
+
+        EngineMessage::HistoryReq(sender_uuid, query) => {
+            let res = engine
+                .query_history(query)
+                .await
+                .map_err(|err| err.to_string());
+            reply_to_sender(engine, sender_uuid, EngineMessage::HistoryRes(res));
+        }
+
+        EngineMessage::ListAlarmsReq(sender_uuid) => {
+            let res = engine.list_alarms().map_err(|err| err.to_string());
+            reply_to_sender(engine, sender_uuid, EngineMessage::ListAlarmsRes(res));
+        }
+
+        EngineMessage::AlarmReq(sender_uuid, source, action) => {
+            let res = engine
+                .alarm_action(&source, action)
+                .map_err(|err| err.to_string());
+            reply_to_sender(engine, sender_uuid, EngineMessage::AlarmRes(res));
+        }
+
         _ => unreachable!("Invalid message"),
     }
 }
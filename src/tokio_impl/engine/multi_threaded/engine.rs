@@ -25,7 +25,9 @@
 use std::collections::BTreeMap;
 use std::sync::Arc;
 
-use crate::base::error::{EngineError, LinkEnd, RegistryError, Result, parse_block_uuid};
+use crate::base::error::{
+    AlarmError, EngineError, HistoryError, LinkEnd, RegistryError, Result, parse_block_uuid,
+};
 use libhaystack::val::Value;
 use tokio::sync::{
     RwLock,
@@ -34,16 +36,21 @@ use tokio::sync::{
 };
 use uuid::Uuid;
 
+use super::super::alarm::{seed_program_sources, track_alarms, watch_source};
 use super::super::block_mailbox::{
     BLOCK_MAILBOX_CAP, BlockMailboxCmd, mailbox_request, mailbox_send,
 };
+use super::super::history::{query_history, record_history, trend_pin};
+use super::super::programs::ProgramTable;
 use super::actor::{WatchersHandle, block_actor_task};
 use crate::base::{
+    alarm::{ALARM_CATEGORY, ALARM_INPUTS, Alarm, AlarmAction, AlarmTable},
     block::{Block, BlockDesc},
-    engine::{
-        Engine,
-        messages::{BlockDefinition, EngineMessage, WatchMessage},
+    engine::messages::{
+        BlockDefinition, DEFAULT_PROGRAM, EngineMessage, ProgramAction, ProgramSummary,
+        WatchMessage,
     },
+    history::{History, HistoryQuery, Point, Recording, Sample},
     program::{
         Program,
         data::{LinkData, PinValue, Position, ProgramBlock},
@@ -51,11 +58,12 @@ use crate::base::{
 };
 use crate::blocks::registry::{CORE_LIB, get_block};
 use crate::tokio_impl::engine::schedule_block_on_engine_mt;
+use crate::tokio_impl::sleep::current_time_millis;
 use crate::tokio_impl::{MtBlock, ReaderImpl, WriterImpl};
 
 /// Concrete engine-message type.
 ///
-/// The watch-event sender is unbounded — see `wasm/engine_command.rs`
+/// The watch- and alarm-event senders are unbounded — see `wasm/engine_command.rs`
 /// `create_watch` for the rationale.
 pub type Messages = EngineMessage<UnboundedSender<WatchMessage>>;
 
@@ -78,6 +86,8 @@ pub struct BlockHandle {
     label: Option<String>,
     /// See [`BlockHandle::position`](crate::single_threaded::BlockHandle::position).
     position: Option<Position>,
+    /// See [`BlockHandle::program`](crate::single_threaded::BlockHandle::program).
+    program: String,
 }
 
 impl BlockHandle {
@@ -105,6 +115,10 @@ impl BlockHandle {
     pub fn position(&self) -> Option<Position> {
         self.position
     }
+    /// Returns the id of the program the block belongs to.
+    pub fn program(&self) -> &str {
+        &self.program
+    }
 }
 
 /// Multi-threaded execution environment for blocks.
@@ -139,6 +153,11 @@ pub struct MultiThreadedEngine {
     receiver: Receiver<Messages>,
     pub(in super::super) reply_senders: BTreeMap<Uuid, Sender<Messages>>,
     pub(in super::super) watchers: WatchersHandle,
+    programs: ProgramTable,
+    history: Option<History>,
+    recording_history: bool,
+    alarms: Option<AlarmTable>,
+    tracking_alarms: bool,
 }
 
 impl Default for MultiThreadedEngine {
@@ -164,24 +183,7 @@ impl crate::base::engine::Engine for MultiThreadedEngine {
     }
 
     fn schedule_program_blocks(&mut self, program: &Program) -> Result<()> {
-        for (uuid_str, pb) in &program.blocks {
-            let id = parse_block_uuid(uuid_str)?;
-            let block_def = get_block(&pb.name, Some(pb.lib.as_str())).ok_or_else(|| {
-                RegistryError::BlockNotFound {
-                    library: pb.lib.clone(),
-                    name: pb.name.clone(),
-                }
-            })?;
-            schedule_block_on_engine_mt(&block_def.desc, Some(id), self)?;
-            if let Some(handle) = self.handles.get_mut(&id) {
-                handle.label = pb.label.clone();
-                handle.position = pb.positions;
-            }
-        }
-        for link in program.links.values() {
-            self.connect_blocks_sync(link)?;
-        }
-        Ok(())
+        self.schedule_program_blocks_into(DEFAULT_PROGRAM, program)
     }
 
     async fn run(&mut self) {
@@ -194,6 +196,25 @@ impl crate::base::engine::Engine for MultiThreadedEngine {
             let _ = self.connect_blocks(&link).await;
         }
 
+        if let Some(history) = &self.history
+            && !self.recording_history
+        {
+            let (sender, feed) = mpsc::unbounded_channel();
+            self.watchers.write().await.insert(Uuid::new_v4(), sender);
+            tokio::spawn(record_history(history.clone(), feed));
+            self.recording_history = true;
+        }
+
+        if let Some(alarms) = &self.alarms
+            && !self.tracking_alarms
+        {
+            let (sender, feed) = mpsc::unbounded_channel();
+            self.watchers.write().await.insert(Uuid::new_v4(), sender);
+            tokio::spawn(track_alarms(alarms.clone(), feed));
+            self.seed_alarm_sources(alarms).await;
+            self.tracking_alarms = true;
+        }
+
         let mut is_paused = false;
         loop {
             let engine_msg = self.receiver.recv().await;
@@ -217,7 +238,11 @@ impl crate::base::engine::Engine for MultiThreadedEngine {
                         if let Some(handle) = self.handles.remove(&id) {
                             let _ = handle.mailbox.send(BlockMailboxCmd::Terminate).await;
                         }
+                        if let Some(alarms) = &self.alarms {
+                            alarms.forget(&id, current_time_millis());
+                        }
                     }
+                    self.programs = ProgramTable::default();
                     continue;
                 } else if matches!(message, EngineMessage::Pause) {
                     is_paused = true;
@@ -258,7 +283,39 @@ impl MultiThreadedEngine {
             receiver,
             reply_senders: BTreeMap::new(),
             watchers: Arc::new(RwLock::new(BTreeMap::new())),
+            programs: ProgramTable::default(),
+            history: None,
+            recording_history: false,
+            alarms: None,
+            tracking_alarms: false,
+        }
+    }
+
+    /// Records the pins trended in `history` once the engine runs, and
+    /// answers history requests from it. Call before
+    /// [`run`](crate::base::engine::Engine::run).
+    pub fn set_history(&mut self, history: History) {
+        self.history = Some(history);
+    }
+
+    /// The engine's trend history, if it has one.
+    pub fn history(&self) -> Option<&History> {
+        self.history.as_ref()
+    }
+
+    /// Tracks the alarms of the engine's alarm blocks in `alarms` once the
+    /// engine runs, and answers alarm requests from it. Call before
+    /// [`run`](crate::base::engine::Engine::run).
+    pub fn set_alarms(&mut self, alarms: AlarmTable) {
+        for handle in self.handles.values() {
+            watch_source(Some(&alarms), handle.id, &handle.desc);
         }
+        self.alarms = Some(alarms);
+    }
+
+    /// The engine's alarm table, if it has one.
+    pub fn alarms(&self) -> Option<&AlarmTable> {
+        self.alarms.as_ref()
     }
 
     /// Schedules a block on the engine. The block must be [`Send`] `+ 'static`
@@ -279,6 +336,7 @@ impl MultiThreadedEngine {
         // See `Engine::schedule` on SingleThreadedEngine for why we clone.
         let desc: BlockDesc = block.desc().clone();
         let (mailbox_tx, mailbox_rx) = mpsc::channel::<BlockMailboxCmd>(BLOCK_MAILBOX_CAP);
+        watch_source(self.alarms.as_ref(), id, &desc);
 
         let handle = BlockHandle {
             id,
@@ -288,6 +346,7 @@ impl MultiThreadedEngine {
             mailbox: mailbox_tx,
             label: None,
             position: None,
+            program: DEFAULT_PROGRAM.to_string(),
         };
         self.handles.insert(id, handle);
 
@@ -336,7 +395,38 @@ impl MultiThreadedEngine {
                 name: block_name.clone(),
             }
         })?;
-        schedule_block_on_engine_mt(&block_def.desc, block_id, self)
+        let id = schedule_block_on_engine_mt(&block_def.desc, block_id, self)?;
+        if self.programs.is_paused(DEFAULT_PROGRAM) {
+            // The mailbox of a block scheduled just now has room to spare.
+            let _ = self
+                .mailbox_or_err(&id)?
+                .try_send(BlockMailboxCmd::SetPaused { paused: true });
+        }
+        Ok(id)
+    }
+
+    /// Schedules the blocks of `program` as members of the program `id`
+    /// and queues its links.
+    fn schedule_program_blocks_into(&mut self, id: &str, program: &Program) -> Result<()> {
+        for (uuid_str, pb) in &program.blocks {
+            let block_id = parse_block_uuid(uuid_str)?;
+            let block_def = get_block(&pb.name, Some(pb.lib.as_str())).ok_or_else(|| {
+                RegistryError::BlockNotFound {
+                    library: pb.lib.clone(),
+                    name: pb.name.clone(),
+                }
+            })?;
+            schedule_block_on_engine_mt(&block_def.desc, Some(block_id), self)?;
+            if let Some(handle) = self.handles.get_mut(&block_id) {
+                handle.label = pb.label.clone();
+                handle.position = pb.positions;
+                handle.program = id.to_string();
+            }
+        }
+        for link in program.links.values() {
+            self.connect_blocks_sync(link)?;
+        }
+        Ok(())
     }
 
     /// Sync configuration-time link validation. Real wiring is deferred to
@@ -526,6 +616,21 @@ impl MultiThreadedEngine {
         })
     }
 
+    /// Seeds `alarms` with the priority and message inputs the alarm
+    /// blocks already hold, which they report only once they change.
+    async fn seed_alarm_sources(&self, alarms: &AlarmTable) {
+        for handle in self.handles.values() {
+            if handle.desc.category != ALARM_CATEGORY {
+                continue;
+            }
+            for pin in ALARM_INPUTS {
+                if let Some(value) = self.read_source_value(&handle.id, pin, false).await {
+                    alarms.configure(&handle.id, pin, &value);
+                }
+            }
+        }
+    }
+
     async fn read_source_value(
         &self,
         source_id: &Uuid,
@@ -615,6 +720,9 @@ impl MultiThreadedEngine {
         // 3. Terminate the block.
         let _ = target_mb.send(BlockMailboxCmd::Terminate).await;
         self.handles.remove(block_id);
+        if let Some(alarms) = &self.alarms {
+            alarms.forget(block_id, current_time_millis());
+        }
 
         Ok(*block_id)
     }
@@ -623,10 +731,34 @@ impl MultiThreadedEngine {
     /// metadata and current pin values, plus every link. This is the
     /// canonical save format — round-trips through [`load_program`](Self::load_program).
     pub async fn save_program(&self) -> Result<Program> {
+        self.snapshot(None).await
+    }
+
+    /// Snapshots one program: its blocks, and the links leaving them,
+    /// including links into other programs. Round-trips through
+    /// [`load_named_program`](Self::load_named_program) once the
+    /// programs those links enter are loaded.
+    pub async fn save_named_program(&self, id: &str) -> Result<Program> {
+        let slot = self.programs.get(id)?;
+        let name = slot.name.clone();
+        let description = slot.description.clone();
+
+        Ok(Program {
+            name,
+            description,
+            ..self.snapshot(Some(id)).await?
+        })
+    }
+
+    async fn snapshot(&self, program: Option<&str>) -> Result<Program> {
         let mut blocks = std::collections::BTreeMap::new();
         let mut links = std::collections::BTreeMap::new();
 
-        for (id, handle) in &self.handles {
+        let handles = self
+            .handles
+            .iter()
+            .filter(|(_, handle)| program.is_none_or(|program| handle.program == program));
+        for (id, handle) in handles {
             let definition = mailbox_request(&handle.mailbox, *id, |reply| {
                 BlockMailboxCmd::Inspect { reply }
             })
@@ -690,6 +822,7 @@ impl MultiThreadedEngine {
             description: None,
             blocks,
             links,
+            params: BTreeMap::new(),
         })
     }
 
@@ -698,29 +831,159 @@ impl MultiThreadedEngine {
     /// metadata. Must be called from within the engine `run()` context
     /// (the actor tasks need to be live to handle the mailbox commands).
     pub async fn load_program(&mut self, program: Program) -> Result<()> {
-        self.schedule_program_blocks(&program)?;
+        self.load_program_into(DEFAULT_PROGRAM, program).await
+    }
+
+    /// Loads a [`Program`] side by side with the ones already running,
+    /// as the program `id`. Its links may enter blocks of programs
+    /// loaded earlier. A program that fails to load is removed again with
+    /// the blocks scheduled for it, so loading can be retried.
+    pub async fn load_named_program(&mut self, id: &str, program: Program) -> Result<()> {
+        self.programs.insert(id, &program)?;
+        let pending_links = self.pending_links.len();
+        if let Err(err) = self.load_program_into(id, program).await {
+            self.pending_links.truncate(pending_links);
+            let _ = self.unload_program(id).await;
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    async fn load_program_into(&mut self, id: &str, program: Program) -> Result<()> {
+        self.schedule_program_blocks_into(id, &program)?;
 
         let pending_links = std::mem::take(&mut self.pending_links);
         for link in pending_links {
             self.connect_blocks(&link).await?;
         }
 
+        seed_program_sources(self.alarms.as_ref(), &program);
+
         for (uuid_str, pb) in &program.blocks {
-            let id = parse_block_uuid(uuid_str)?;
+            let block_id = parse_block_uuid(uuid_str)?;
             for (name, pin) in &pb.inputs {
                 if hasinitialvalue_mt(&pin.value) {
-                    let _ = self.write_input(&id, name.clone(), pin.value.clone()).await;
+                    let _ = self
+                        .write_input(&block_id, name.clone(), pin.value.clone())
+                        .await;
                 }
             }
             for (name, pin) in &pb.outputs {
                 if hasinitialvalue_mt(&pin.value) {
                     let _ = self
-                        .write_output(&id, name.clone(), pin.value.clone())
+                        .write_output(&block_id, name.clone(), pin.value.clone())
                         .await;
                 }
             }
         }
 
+        // Loading into a paused program leaves the new blocks paused too.
+        if self.programs.is_paused(id) {
+            let ids = program
+                .blocks
+                .keys()
+                .map(|uuid| parse_block_uuid(uuid))
+                .collect::<Result<Vec<_>, _>>()?;
+            self.set_blocks_paused(&ids, true).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Pauses or resumes every block of the program `id`.
+    pub async fn set_program_paused(&mut self, id: &str, paused: bool) -> Result<()> {
+        self.programs.get_mut(id)?.paused = paused;
+        let ids = self.program_block_ids(id);
+        self.set_blocks_paused(&ids, paused).await
+    }
+
+    /// Removes every block of the program `id`, keeping the program.
+    pub async fn reset_program(&mut self, id: &str) -> Result<()> {
+        self.programs.get_mut(id)?.paused = false;
+        for block_id in self.program_block_ids(id) {
+            self.remove_block(&block_id).await?;
+        }
+        Ok(())
+    }
+
+    /// Removes every block of the program `id` and the program itself.
+    pub async fn unload_program(&mut self, id: &str) -> Result<()> {
+        if id == DEFAULT_PROGRAM {
+            return Err(EngineError::DefaultProgramUnload.into());
+        }
+        self.reset_program(id).await?;
+        self.programs.remove(id)?;
+        Ok(())
+    }
+
+    /// Applies a [`ProgramAction`] to the program `id`.
+    pub async fn program_action(&mut self, id: &str, action: ProgramAction) -> Result<()> {
+        match action {
+            ProgramAction::Load(program) => self.load_named_program(id, program).await,
+            ProgramAction::Pause => self.set_program_paused(id, true).await,
+            ProgramAction::Resume => self.set_program_paused(id, false).await,
+            ProgramAction::Reset => self.reset_program(id).await,
+            ProgramAction::Unload => self.unload_program(id).await,
+        }
+    }
+
+    /// Starts, changes or with [`None`] stops recording `point` into the
+    /// engine's history.
+    pub(crate) async fn trend_pin(&self, point: Point, recording: Option<Recording>) -> Result<()> {
+        let history = self.history.as_ref().ok_or(HistoryError::NotEnabled)?;
+        match recording {
+            Some(recording) => {
+                let block = self.inspect_block(&point.block).await?;
+                Ok(trend_pin(history, &block, point, recording).await?)
+            }
+            None => {
+                history.untrend(&point);
+                Ok(())
+            }
+        }
+    }
+
+    /// Reads the engine's history.
+    pub(crate) async fn query_history(&self, query: HistoryQuery) -> Result<Vec<Sample>> {
+        let history = self.history.as_ref().ok_or(HistoryError::NotEnabled)?;
+        Ok(query_history(history, query).await?)
+    }
+
+    /// The alarms in the engine's alarm table.
+    pub(crate) fn list_alarms(&self) -> Result<Vec<Alarm>> {
+        let alarms = self.alarms.as_ref().ok_or(AlarmError::NotEnabled)?;
+        Ok(alarms.alarms())
+    }
+
+    /// Applies an operator request to the alarm source `source`.
+    pub(crate) fn alarm_action(&self, source: &Uuid, action: AlarmAction) -> Result<()> {
+        let alarms = self.alarms.as_ref().ok_or(AlarmError::NotEnabled)?;
+        Ok(alarms.apply(source, action, current_time_millis())?)
+    }
+
+    /// Summaries of the loaded programs.
+    pub fn programs(&self) -> Vec<ProgramSummary> {
+        self.programs
+            .summaries(self.handles.values().map(|handle| handle.program.as_str()))
+    }
+
+    fn program_block_ids(&self, id: &str) -> Vec<Uuid> {
+        self.handles
+            .values()
+            .filter(|handle| handle.program == id)
+            .map(|handle| handle.id)
+            .collect()
+    }
+
+    async fn set_blocks_paused(&self, ids: &[Uuid], paused: bool) -> Result<()> {
+        for id in ids {
+            mailbox_send(
+                self.mailbox_or_err(id)?,
+                *id,
+                BlockMailboxCmd::SetPaused { paused },
+            )
+            .await?;
+        }
         Ok(())
     }
 
@@ -861,6 +1124,14 @@ impl MultiThreadedEngine {
                 self.reply_to_sender(sender_uuid, EngineMessage::GetCurrentProgramRes(res));
             }
 
+            EngineMessage::GetProgramReq(sender_uuid, program_id) => {
+                let res = self
+                    .save_named_program(&program_id)
+                    .await
+                    .map_err(|err| err.to_string());
+                self.reply_to_sender(sender_uuid, EngineMessage::GetProgramRes(res));
+            }
+
             EngineMessage::LoadProgramReq(sender_uuid, program) => {
                 let res = self
                     .load_program(program)
@@ -869,6 +1140,19 @@ impl MultiThreadedEngine {
                 self.reply_to_sender(sender_uuid, EngineMessage::LoadProgramRes(res));
             }
 
+            EngineMessage::ProgramReq(sender_uuid, program_id, action) => {
+                let res = self
+                    .program_action(&program_id, action)
+                    .await
+                    .map_err(|err| err.to_string());
+                self.reply_to_sender(sender_uuid, EngineMessage::ProgramRes(res));
+            }
+
+            EngineMessage::ListProgramsReq(sender_uuid) => {
+                let programs = self.programs();
+                self.reply_to_sender(sender_uuid, EngineMessage::ListProgramsRes(programs));
+            }
+
             EngineMessage::ConnectBlocksReq(sender_uuid, link_data) => {
                 let res = self
                     .connect_blocks(&link_data)
@@ -885,6 +1169,34 @@ impl MultiThreadedEngine {
                 self.reply_to_sender(sender_uuid, EngineMessage::RemoveLinkRes(res));
             }
 
+            EngineMessage::TrendReq(sender_uuid, point, recording) => {
+                let res = self
+                    .trend_pin(point, recording)
+                    .await
+                    .map_err(|err| err.to_string());
+                self.reply_to_sender(sender_uuid, EngineMessage::TrendRes(res));
+            }
+
+            EngineMessage::HistoryReq(sender_uuid, query) => {
+                let res = self
+                    .query_history(query)
+                    .await
+                    .map_err(|err| err.to_string());
+                self.reply_to_sender(sender_uuid, EngineMessage::HistoryRes(res));
+            }
+
+            EngineMessage::ListAlarmsReq(sender_uuid) => {
+                let res = self.list_alarms().map_err(|err| err.to_string());
+                self.reply_to_sender(sender_uuid, EngineMessage::ListAlarmsRes(res));
+            }
+
+            EngineMessage::AlarmReq(sender_uuid, source, action) => {
+                let res = self
+                    .alarm_action(&source, action)
+                    .map_err(|err| err.to_string());
+                self.reply_to_sender(sender_uuid, EngineMessage::AlarmRes(res));
+            }
+
             _ => unreachable!("Invalid message"),
         }
     }
@@ -0,0 +1,107 @@
+// Copyright (c) 2022-2026, Radu Racariu.
+
+//! Table of the programs loaded in an engine.
+//!
+//! Shared by the [`SingleThreadedEngine`](super::single_threaded::SingleThreadedEngine)
+//! and the multi-threaded engine. Each block handle records the id of the
+//! program it belongs to; this table holds what is known about the
+//! program itself — its name, description and whether it is paused — so
+//! an emptied program keeps existing until it is unloaded.
+
+use std::collections::BTreeMap;
+
+use crate::base::{
+    engine::messages::{DEFAULT_PROGRAM, ProgramSummary},
+    error::EngineError,
+    program::Program,
+};
+
+/// What the engine knows about one loaded program.
+#[derive(Debug, Default)]
+pub(super) struct ProgramSlot {
+    pub(super) name: Option<String>,
+    pub(super) description: Option<String>,
+    pub(super) paused: bool,
+}
+
+/// Loaded programs keyed by id. Always holds the [`DEFAULT_PROGRAM`].
+#[derive(Debug)]
+pub(super) struct ProgramTable {
+    slots: BTreeMap<String, ProgramSlot>,
+}
+
+impl Default for ProgramTable {
+    fn default() -> Self {
+        Self {
+            slots: BTreeMap::from([(DEFAULT_PROGRAM.to_string(), ProgramSlot::default())]),
+        }
+    }
+}
+
+impl ProgramTable {
+    pub(super) fn get(&self, id: &str) -> Result<&ProgramSlot, EngineError> {
+        self.slots
+            .get(id)
+            .ok_or_else(|| EngineError::ProgramNotFound { id: id.to_string() })
+    }
+
+    pub(super) fn get_mut(&mut self, id: &str) -> Result<&mut ProgramSlot, EngineError> {
+        self.slots
+            .get_mut(id)
+            .ok_or_else(|| EngineError::ProgramNotFound { id: id.to_string() })
+    }
+
+    pub(super) fn is_paused(&self, id: &str) -> bool {
+        self.slots.get(id).is_some_and(|slot| slot.paused)
+    }
+
+    /// Registers a new program, taking its name and description.
+    pub(super) fn insert(&mut self, id: &str, program: &Program) -> Result<(), EngineError> {
+        if self.slots.contains_key(id) {
+            return Err(EngineError::ProgramExists { id: id.to_string() });
+        }
+        self.slots.insert(
+            id.to_string(),
+            ProgramSlot {
+                name: program.name.clone(),
+                description: program.description.clone(),
+                paused: false,
+            },
+        );
+        Ok(())
+    }
+
+    /// Forgets a program. Its blocks must already be gone.
+    pub(super) fn remove(&mut self, id: &str) -> Result<(), EngineError> {
+        if id == DEFAULT_PROGRAM {
+            return Err(EngineError::DefaultProgramUnload);
+        }
+        self.slots
+            .remove(id)
+            .map(|_| ())
+            .ok_or_else(|| EngineError::ProgramNotFound { id: id.to_string() })
+    }
+
+    /// Summaries of every program, counting blocks from the program id
+    /// of each scheduled block.
+    pub(super) fn summaries<'a>(
+        &self,
+        block_programs: impl Iterator<Item = &'a str>,
+    ) -> Vec<ProgramSummary> {
+        let mut counts = BTreeMap::<&str, usize>::new();
+        for id in block_programs {
+            *counts.entry(id).or_default() += 1;
+        }
+
+        self.slots
+            .iter()
+            .map(|(id, slot)| ProgramSummary {
+                id: id.clone(),
+                name: slot.name.clone(),
+                description: slot.description.clone(),
+                blocks: counts.get(id.as_str()).copied().unwrap_or_default(),
+                paused: slot.paused,
+            })
+            .collect()
+    }
+}
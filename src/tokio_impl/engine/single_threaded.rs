@@ -57,8 +57,8 @@ mod tests {
     use crate::blocks::{math::Add, misc::SineWave};
     use base::block::{BlockConnect, BlockProps};
     use base::engine::messages::EngineMessage::{
-        GetCurrentProgramReq, GetCurrentProgramRes, InspectBlockReq, InspectBlockRes,
-        LoadProgramReq, LoadProgramRes, Shutdown,
+        GetCurrentProgramReq, GetCurrentProgramRes, GetProgramReq, GetProgramRes, InspectBlockReq,
+        InspectBlockRes, LoadProgramReq, LoadProgramRes, Shutdown,
     };
 
     use super::SingleThreadedEngine;
@@ -234,6 +234,7 @@ mod tests {
             description: None,
             blocks,
             links,
+            ..Default::default()
         };
 
         let mut eng = SingleThreadedEngine::new();
@@ -298,4 +299,516 @@ mod tests {
 
         eng.run().await;
     }
+
+    /// Programs loaded side by side keep their own lifecycle, and a link
+    /// between them is dropped with the program it enters or leaves.
+    #[tokio::test(flavor = "current_thread")]
+    async fn named_programs_are_isolated() {
+        use super::Messages;
+        use base::engine::messages::{
+            DEFAULT_PROGRAM, EngineMessage, ProgramAction, ProgramSummary,
+        };
+
+        let a1 = Uuid::new_v4();
+        let a2 = Uuid::new_v4();
+        let single_block = |id: Uuid, name: &str| {
+            let mut program = Program {
+                name: Some(name.to_string()),
+                ..Default::default()
+            };
+            program.blocks.insert(
+                id.to_string(),
+                ProgramBlock {
+                    name: "Add".to_string(),
+                    lib: "core".to_string(),
+                    ..Default::default()
+                },
+            );
+            program
+        };
+        let ahu1 = single_block(a1, "AHU 1");
+        let mut ahu2 = single_block(a2, "AHU 2");
+        // Cross-program link: AHU 1's output feeds AHU 2.
+        let cross = Uuid::new_v4().to_string();
+        ahu2.links.insert(
+            cross.clone(),
+            LinkData {
+                id: Some(cross),
+                source_block_uuid: a1.to_string(),
+                target_block_uuid: a2.to_string(),
+                source_block_pin_name: "out".to_string(),
+                target_block_pin_name: "in0".to_string(),
+            },
+        );
+
+        fn program_res(message: Messages) -> Result<(), String> {
+            match message {
+                EngineMessage::ProgramRes(res) => res,
+                other => panic!("Expected ProgramRes, got {other:?}"),
+            }
+        }
+
+        let mut eng = SingleThreadedEngine::new();
+        let (sender, mut receiver) = mpsc::channel(32);
+        let channel_id = Uuid::new_v4();
+        let engine_sender = eng.create_message_channel(channel_id, sender.clone());
+
+        thread::spawn(move || {
+            let rt = Runtime::new().expect("RT");
+            let handle = rt.spawn(async move {
+                let mut request = async |message| {
+                    engine_sender.send(message).await.expect("send");
+                    receiver.recv().await.expect("reply")
+                };
+
+                program_res(
+                    request(EngineMessage::ProgramReq(
+                        channel_id,
+                        "ahu-1".to_string(),
+                        ProgramAction::Load(ahu1.clone()),
+                    ))
+                    .await,
+                )
+                .expect("load ahu-1");
+                program_res(
+                    request(EngineMessage::ProgramReq(
+                        channel_id,
+                        "ahu-2".to_string(),
+                        ProgramAction::Load(ahu2),
+                    ))
+                    .await,
+                )
+                .expect("load ahu-2");
+                assert!(
+                    program_res(
+                        request(EngineMessage::ProgramReq(
+                            channel_id,
+                            "ahu-1".to_string(),
+                            ProgramAction::Load(ahu1)
+                        ))
+                        .await
+                    )
+                    .is_err(),
+                    "program ids are unique"
+                );
+                assert!(
+                    program_res(
+                        request(EngineMessage::ProgramReq(
+                            channel_id,
+                            DEFAULT_PROGRAM.to_string(),
+                            ProgramAction::Unload
+                        ))
+                        .await
+                    )
+                    .is_err()
+                );
+
+                program_res(
+                    request(EngineMessage::ProgramReq(
+                        channel_id,
+                        "ahu-1".to_string(),
+                        ProgramAction::Pause,
+                    ))
+                    .await,
+                )
+                .expect("pause");
+                match request(InspectBlockReq(channel_id, a1)).await {
+                    InspectBlockRes(Ok(block)) => assert_eq!(block.state, "disabled"),
+                    other => panic!("Expected InspectBlockRes, got {other:?}"),
+                }
+                match request(InspectBlockReq(channel_id, a2)).await {
+                    InspectBlockRes(Ok(block)) => assert_eq!(block.state, "running"),
+                    other => panic!("Expected InspectBlockRes, got {other:?}"),
+                }
+
+                match request(EngineMessage::ListProgramsReq(channel_id)).await {
+                    EngineMessage::ListProgramsRes(programs) => {
+                        let summary = |id: &str| {
+                            programs
+                                .iter()
+                                .find(|program| program.id == id)
+                                .cloned()
+                                .expect("listed")
+                        };
+                        assert_eq!(programs.len(), 3);
+                        assert_eq!(summary(DEFAULT_PROGRAM).blocks, 0);
+                        assert_eq!(
+                            summary("ahu-1"),
+                            ProgramSummary {
+                                id: "ahu-1".to_string(),
+                                name: Some("AHU 1".to_string()),
+                                description: None,
+                                blocks: 1,
+                                paused: true,
+                            }
+                        );
+                        assert!(!summary("ahu-2").paused);
+                    }
+                    other => panic!("Expected ListProgramsRes, got {other:?}"),
+                }
+
+                // The link leaves AHU 1, so it is saved with AHU 1.
+                match request(GetProgramReq(channel_id, "ahu-1".into())).await {
+                    GetProgramRes(Ok(saved)) => {
+                        assert_eq!(saved.name.as_deref(), Some("AHU 1"));
+                        assert_eq!(saved.blocks.len(), 1);
+                        assert!(saved.blocks.contains_key(&a1.to_string()));
+                        assert_eq!(saved.links.len(), 1);
+                    }
+                    other => panic!("Expected GetProgramRes, got {other:?}"),
+                }
+
+                program_res(
+                    request(EngineMessage::ProgramReq(
+                        channel_id,
+                        "ahu-1".to_string(),
+                        ProgramAction::Unload,
+                    ))
+                    .await,
+                )
+                .expect("unload");
+                match request(GetCurrentProgramReq(channel_id)).await {
+                    GetCurrentProgramRes(Ok(saved)) => {
+                        assert_eq!(saved.blocks.len(), 1);
+                        assert!(saved.links.is_empty());
+                        let in0 = &saved.blocks[&a2.to_string()].inputs["in0"];
+                        assert!(!in0.is_connected, "cross-program link is gone");
+                    }
+                    other => panic!("Expected GetCurrentProgramRes, got {other:?}"),
+                }
+                assert!(
+                    program_res(
+                        request(EngineMessage::ProgramReq(
+                            channel_id,
+                            "ahu-1".to_string(),
+                            ProgramAction::Pause
+                        ))
+                        .await
+                    )
+                    .is_err(),
+                    "unloaded programs are forgotten"
+                );
+
+                let _ = engine_sender.send(Shutdown).await;
+            });
+            rt.block_on(handle)
+        });
+
+        eng.run().await;
+    }
+
+    /// A program that fails to load leaves neither its slot nor its
+    /// blocks behind, so it loads once fixed.
+    #[tokio::test(flavor = "current_thread")]
+    async fn failed_program_loads_can_be_retried() {
+        use base::engine::messages::{DEFAULT_PROGRAM, EngineMessage, ProgramAction};
+
+        let (a1, a2) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut program = Program::default();
+        for id in [a1, a2] {
+            program.blocks.insert(
+                id.to_string(),
+                ProgramBlock {
+                    name: "Add".to_string(),
+                    lib: "core".to_string(),
+                    ..Default::default()
+                },
+            );
+        }
+        let link = |target_pin: &str| LinkData {
+            id: None,
+            source_block_uuid: a1.to_string(),
+            target_block_uuid: a2.to_string(),
+            source_block_pin_name: "out".to_string(),
+            target_block_pin_name: target_pin.to_string(),
+        };
+        let mut broken = program.clone();
+        broken
+            .links
+            .insert(Uuid::new_v4().to_string(), link("nope"));
+        program
+            .links
+            .insert(Uuid::new_v4().to_string(), link("in0"));
+
+        let mut eng = SingleThreadedEngine::new();
+        let (sender, mut receiver) = mpsc::channel(32);
+        let channel_id = Uuid::new_v4();
+        let engine_sender = eng.create_message_channel(channel_id, sender);
+
+        thread::spawn(move || {
+            let rt = Runtime::new().expect("RT");
+            let handle = rt.spawn(async move {
+                let mut request = async |message| {
+                    engine_sender.send(message).await.expect("send");
+                    receiver.recv().await.expect("reply")
+                };
+                let mut load = async |program| {
+                    let load = ProgramAction::Load(program);
+                    match request(EngineMessage::ProgramReq(channel_id, "ahu-1".into(), load)).await
+                    {
+                        EngineMessage::ProgramRes(res) => res,
+                        other => panic!("Expected ProgramRes, got {other:?}"),
+                    }
+                };
+
+                assert!(load(broken).await.is_err(), "the link's pin is missing");
+                load(program).await.expect("the fixed program loads");
+
+                match request(EngineMessage::ListProgramsReq(channel_id)).await {
+                    EngineMessage::ListProgramsRes(programs) => {
+                        let blocks = |id: &str| {
+                            programs
+                                .iter()
+                                .find(|program| program.id == id)
+                                .map(|program| program.blocks)
+                        };
+                        assert_eq!(programs.len(), 2);
+                        assert_eq!(blocks(DEFAULT_PROGRAM), Some(0));
+                        assert_eq!(blocks("ahu-1"), Some(2));
+                    }
+                    other => panic!("Expected ListProgramsRes, got {other:?}"),
+                }
+
+                let _ = engine_sender.send(Shutdown).await;
+            });
+            rt.block_on(handle)
+        });
+
+        eng.run().await;
+    }
+
+    /// A trended pin is seeded with its current value, recorded on
+    /// change, and read back through the engine messages.
+    #[tokio::test(flavor = "current_thread")]
+    async fn trended_pins_are_recorded_and_queried() {
+        use base::engine::messages::EngineMessage::{
+            HistoryReq, HistoryRes, TrendReq, TrendRes, WriteBlockInputReq,
+        };
+        use base::history::{History, HistoryQuery, MemoryStore, Point, Recording};
+
+        let add = Add::new();
+        let add_uuid = *add.id();
+        let mut eng = SingleThreadedEngine::new();
+        eng.schedule(add).unwrap();
+        eng.set_history(History::new(MemoryStore::new(100)));
+
+        let (sender, mut receiver) = mpsc::channel(32);
+        let channel_id = Uuid::new_v4();
+        let engine_sender = eng.create_message_channel(channel_id, sender.clone());
+
+        thread::spawn(move || {
+            let rt = Runtime::new().expect("RT");
+            let handle = rt.spawn(async move {
+                let mut request = async |message| {
+                    engine_sender.send(message).await.expect("send");
+                    receiver.recv().await.expect("reply")
+                };
+                let in0 = Point::new(add_uuid, "in0");
+
+                match request(TrendReq(
+                    channel_id,
+                    Point::new(add_uuid, "nope"),
+                    Some(Recording::OnChange),
+                ))
+                .await
+                {
+                    TrendRes(Err(err)) => assert!(err.contains("'nope'"), "{err}"),
+                    other => panic!("Expected an error, got {other:?}"),
+                }
+                match request(TrendReq(channel_id, in0.clone(), Some(Recording::OnChange))).await {
+                    TrendRes(res) => res.expect("trended"),
+                    other => panic!("Expected TrendRes, got {other:?}"),
+                }
+
+                for value in [1, 2] {
+                    request(WriteBlockInputReq(
+                        channel_id,
+                        add_uuid,
+                        "in0".to_string(),
+                        value.into(),
+                    ))
+                    .await;
+                    sleep(Duration::from_millis(100)).await;
+                }
+
+                match request(HistoryReq(channel_id, HistoryQuery::new(in0))).await {
+                    HistoryRes(Ok(samples)) => {
+                        assert_eq!(samples.len(), 3, "{samples:?}");
+                        let last = samples.last().unwrap();
+                        assert_eq!(last.value, 2.into());
+                        assert!(last.status.is_ok());
+                        assert!(samples.windows(2).all(|pair| pair[0].ts <= pair[1].ts));
+                    }
+                    other => panic!("Expected HistoryRes, got {other:?}"),
+                }
+
+                let _ = engine_sender.send(Shutdown).await;
+            });
+            rt.block_on(handle)
+        });
+
+        eng.run().await;
+
+        let mut plain = SingleThreadedEngine::new();
+        let query = HistoryQuery::new(Point::new(add_uuid, "out"));
+        assert!(plain.query_history(query.clone()).await.is_err());
+        assert!(plain.history().is_none());
+        plain.set_history(History::new(MemoryStore::new(1)));
+        assert!(plain.query_history(query).await.unwrap().is_empty());
+    }
+
+    /// Alarm blocks raise alarms in the engine's table, with the priority
+    /// they held when scheduled, streamed to the table's subscribers and acknowledged through the engine messages.
+    #[tokio::test(flavor = "current_thread")]
+    async fn alarm_blocks_raise_alarms() {
+        use crate::blocks::alarm::BoolAlarm;
+        use base::alarm::{AlarmAction, AlarmState, AlarmTable, AlarmTransition};
+        use base::engine::messages::EngineMessage::{
+            AlarmReq, AlarmRes, ListAlarmsReq, ListAlarmsRes, WriteBlockInputReq,
+        };
+        use base::input::Input;
+
+        let mut alarm = BoolAlarm::new();
+        alarm.priority.set_value(5.into(), base::Status::Ok);
+        let alarm_uuid = *alarm.id();
+        let mut eng = SingleThreadedEngine::new();
+        eng.schedule(alarm).unwrap();
+        eng.schedule(Add::new()).unwrap();
+        let table = AlarmTable::new();
+        eng.set_alarms(table.clone());
+
+        let (sender, mut receiver) = mpsc::channel(32);
+        let channel_id = Uuid::new_v4();
+        let engine_sender = eng.create_message_channel(channel_id, sender.clone());
+
+        let (events, mut stream) = mpsc::unbounded_channel();
+        table.subscribe(channel_id, move |event| events.send(event.clone()).is_ok());
+
+        thread::spawn(move || {
+            let rt = Runtime::new().expect("RT");
+            let handle = rt.spawn(async move {
+                let mut request = async |message| {
+                    engine_sender.send(message).await.expect("send");
+                    receiver.recv().await.expect("reply")
+                };
+
+                request(WriteBlockInputReq(
+                    channel_id,
+                    alarm_uuid,
+                    "in".to_string(),
+                    true.into(),
+                ))
+                .await;
+
+                let event = stream.recv().await.expect("event");
+                assert_eq!(event.transition, AlarmTransition::Raised);
+                assert_eq!(event.source, alarm_uuid);
+                assert_eq!(event.alarm.map(|alarm| alarm.priority), Some(5));
+
+                match request(AlarmReq(channel_id, alarm_uuid, AlarmAction::Acknowledge)).await {
+                    AlarmRes(res) => res.expect("acknowledged"),
+                    other => panic!("Expected AlarmRes, got {other:?}"),
+                }
+                let event = stream.recv().await.expect("event");
+                assert_eq!(event.transition, AlarmTransition::Acknowledged);
+
+                match request(ListAlarmsReq(channel_id)).await {
+                    ListAlarmsRes(Ok(alarms)) => {
+                        assert_eq!(alarms.len(), 1);
+                        assert_eq!(alarms[0].kind, "BoolAlarm");
+                        assert_eq!(alarms[0].state, AlarmState::Acknowledged);
+                    }
+                    other => panic!("Expected ListAlarmsRes, got {other:?}"),
+                }
+
+                match request(AlarmReq(channel_id, Uuid::new_v4(), AlarmAction::Unshelve)).await {
+                    AlarmRes(Err(err)) => assert!(err.contains("not an alarm source"), "{err}"),
+                    other => panic!("Expected an error, got {other:?}"),
+                }
+
+                let _ = engine_sender.send(Shutdown).await;
+            });
+            rt.block_on(handle)
+        });
+
+        eng.run().await;
+
+        let plain = SingleThreadedEngine::new();
+        assert!(plain.list_alarms().is_err());
+        assert!(plain.alarms().is_none());
+    }
+
+    /// The `priority` and `message` constants of a loaded alarm block
+    /// reach the alarm it raises.
+    #[tokio::test(flavor = "current_thread")]
+    async fn loaded_alarm_constants_reach_the_table() {
+        use base::alarm::{AlarmTable, AlarmTransition};
+
+        let alarm_uuid = Uuid::new_v4();
+        let inputs = [
+            ("in", true.into()),
+            ("priority", 7.into()),
+            ("message", "Supply fan tripped".into()),
+        ]
+        .into_iter()
+        .map(|(pin, value)| {
+            let value = PinValue {
+                value,
+                is_connected: false,
+            };
+            (pin.to_string(), value)
+        })
+        .collect();
+        let program = Program {
+            blocks: [(
+                alarm_uuid.to_string(),
+                ProgramBlock {
+                    name: "BoolAlarm".to_string(),
+                    lib: "core".to_string(),
+                    label: None,
+                    positions: None,
+                    inputs,
+                    outputs: Default::default(),
+                },
+            )]
+            .into(),
+            ..Default::default()
+        };
+
+        let mut eng = SingleThreadedEngine::new();
+        let table = AlarmTable::new();
+        eng.set_alarms(table.clone());
+
+        let (sender, mut receiver) = mpsc::channel(32);
+        let channel_id = Uuid::new_v4();
+        let engine_sender = eng.create_message_channel(channel_id, sender);
+
+        let (events, mut stream) = mpsc::unbounded_channel();
+        table.subscribe(channel_id, move |event| events.send(event.clone()).is_ok());
+
+        thread::spawn(move || {
+            let rt = Runtime::new().expect("RT");
+            let handle = rt.spawn(async move {
+                engine_sender
+                    .send(LoadProgramReq(channel_id, program))
+                    .await
+                    .expect("send");
+                match receiver.recv().await {
+                    Some(LoadProgramRes(Ok(()))) => {}
+                    other => panic!("Expected LoadProgramRes(Ok), got {other:?}"),
+                }
+
+                let event = stream.recv().await.expect("event");
+                assert_eq!(event.transition, AlarmTransition::Raised);
+                let alarm = event.alarm.expect("raised alarm");
+                assert_eq!(alarm.priority, 7);
+                assert_eq!(alarm.message, "Supply fan tripped");
+
+                let _ = engine_sender.send(Shutdown).await;
+            });
+            rt.block_on(handle)
+        });
+
+        eng.run().await;
+    }
 }
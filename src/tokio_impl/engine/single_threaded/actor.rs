@@ -118,6 +118,15 @@ async fn run_one_step<B>(block: &mut B, mailbox: &mut mpsc::Receiver<BlockMailbo
 where
     B: EngineBlock + 'static,
 {
+    // A paused block only serves its mailbox. With the mailbox closed
+    // nothing can resume it, so the task exits.
+    if block.state() == BlockState::Disabled {
+        return match mailbox.recv().await {
+            Some(cmd) => handle_cmd(cmd, block).await,
+            None => true,
+        };
+    }
+
     let mut cmd_to_handle: Option<BlockMailboxCmd> = None;
     {
         let execute_fut = block.execute();
@@ -5,7 +5,9 @@
 
 use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
 
-use crate::base::error::{EngineError, LinkEnd, RegistryError, Result, parse_block_uuid};
+use crate::base::error::{
+    AlarmError, EngineError, HistoryError, LinkEnd, RegistryError, Result, parse_block_uuid,
+};
 use libhaystack::val::Value;
 use tokio::{
     sync::{
@@ -16,16 +18,24 @@ use tokio::{
 };
 use uuid::Uuid;
 
+use super::super::alarm::{seed_program_sources, track_alarms, watch_source};
 use super::super::block_mailbox::{
     BLOCK_MAILBOX_CAP, BlockMailboxCmd, mailbox_request, mailbox_send,
 };
+use super::super::history::{query_history, record_history, trend_pin};
+use super::super::programs::ProgramTable;
 use super::actor::block_actor_task;
 use crate::base::{
+    alarm::{ALARM_CATEGORY, ALARM_INPUTS, Alarm, AlarmAction, AlarmTable},
     block::{Block, BlockDesc},
     engine::{
         Engine,
-        messages::{BlockDefinition, EngineMessage, WatchMessage},
+        messages::{
+            BlockDefinition, DEFAULT_PROGRAM, EngineMessage, ProgramAction, ProgramSummary,
+            WatchMessage,
+        },
     },
+    history::{History, HistoryQuery, Point, Recording, Sample},
     program::{
         Program,
         data::{LinkData, PinValue, Position, ProgramBlock},
@@ -34,11 +44,12 @@ use crate::base::{
 use crate::blocks::registry::{CORE_LIB, get_block};
 use crate::tokio_impl::engine::message_dispatch::dispatch_message;
 use crate::tokio_impl::engine::schedule_block_on_engine;
+use crate::tokio_impl::sleep::current_time_millis;
 use crate::tokio_impl::{ReaderImpl, WriterImpl};
 
 /// Concrete engine-message type.
 ///
-/// The watch-event sender is unbounded — see `wasm/engine_command.rs`
+/// The watch- and alarm-event senders are unbounded — see `wasm/engine_command.rs`
 /// `create_watch` for the rationale (fault notifications must not drop
 /// under burst load).
 pub type Messages = EngineMessage<UnboundedSender<WatchMessage>>;
@@ -72,6 +83,8 @@ pub struct BlockHandle {
     /// UI position. Same role as `label`: pure passthrough so headless
     /// save/load preserves the layout.
     position: Option<Position>,
+    /// Id of the program the block belongs to.
+    program: String,
 }
 
 impl BlockHandle {
@@ -99,6 +112,10 @@ impl BlockHandle {
     pub fn position(&self) -> Option<Position> {
         self.position
     }
+    /// Returns the id of the program the block belongs to.
+    pub fn program(&self) -> &str {
+        &self.program
+    }
 }
 
 /// Single-threaded execution environment for blocks.
@@ -148,6 +165,17 @@ pub struct SingleThreadedEngine {
     /// Watchers for change-of-value notifications. Same visibility note as
     /// `reply_senders`.
     pub(in super::super) watchers: Rc<RefCell<BTreeMap<Uuid, UnboundedSender<WatchMessage>>>>,
+    /// Programs loaded side by side. Blocks record their program id on
+    /// their handle.
+    programs: ProgramTable,
+    /// Trend history, recorded from the watch notifications once running.
+    history: Option<History>,
+    /// Whether `run()` started recording the history.
+    recording_history: bool,
+    /// Alarm table, fed from the watch notifications once running.
+    alarms: Option<AlarmTable>,
+    /// Whether `run()` started tracking the alarms.
+    tracking_alarms: bool,
 }
 
 impl Default for SingleThreadedEngine {
@@ -173,6 +201,7 @@ impl Engine for SingleThreadedEngine {
         // block instance; borrowing it across the move is UB.
         let desc: BlockDesc = block.desc().clone();
         let (mailbox_tx, mailbox_rx) = mpsc::channel::<BlockMailboxCmd>(BLOCK_MAILBOX_CAP);
+        watch_source(self.alarms.as_ref(), id, &desc);
 
         let handle = BlockHandle {
             id,
@@ -182,6 +211,7 @@ impl Engine for SingleThreadedEngine {
             mailbox: mailbox_tx,
             label: None,
             position: None,
+            program: DEFAULT_PROGRAM.to_string(),
         };
         self.handles.insert(id, handle);
 
@@ -192,26 +222,7 @@ impl Engine for SingleThreadedEngine {
     }
 
     fn schedule_program_blocks(&mut self, program: &Program) -> Result<()> {
-        for (uuid_str, pb) in &program.blocks {
-            let id = parse_block_uuid(uuid_str)?;
-            let block_def =
-                get_block(&pb.name, Some(&pb.lib)).ok_or_else(|| RegistryError::BlockNotFound {
-                    library: pb.lib.clone(),
-                    name: pb.name.clone(),
-                })?;
-            schedule_block_on_engine(&block_def.desc, Some(id), self)?;
-            // Record UI metadata on the handle so it round-trips on save.
-            if let Some(handle) = self.handles.get_mut(&id) {
-                handle.label = pb.label.clone();
-                handle.position = pb.positions;
-            }
-        }
-        // Wiring is async (mailbox round-trips); validate sync and queue
-        // for processing at `run()` start.
-        for link in program.links.values() {
-            self.connect_blocks_sync(link)?;
-        }
-        Ok(())
+        self.schedule_program_blocks_into(DEFAULT_PROGRAM, program)
     }
 
     async fn run(&mut self) {
@@ -225,6 +236,25 @@ impl Engine for SingleThreadedEngine {
             let _ = local.run_until(self.connect_blocks(&link)).await;
         }
 
+        if let Some(history) = &self.history
+            && !self.recording_history
+        {
+            let (sender, feed) = mpsc::unbounded_channel();
+            self.watchers.borrow_mut().insert(Uuid::new_v4(), sender);
+            local.spawn_local(record_history(history.clone(), feed));
+            self.recording_history = true;
+        }
+
+        if let Some(alarms) = &self.alarms
+            && !self.tracking_alarms
+        {
+            let (sender, feed) = mpsc::unbounded_channel();
+            self.watchers.borrow_mut().insert(Uuid::new_v4(), sender);
+            local.spawn_local(track_alarms(alarms.clone(), feed));
+            local.run_until(self.seed_alarm_sources(alarms)).await;
+            self.tracking_alarms = true;
+        }
+
         let mut is_paused = false;
         loop {
             let mut engine_msg = None;
@@ -247,7 +277,11 @@ impl Engine for SingleThreadedEngine {
                         if let Some(handle) = self.handles.remove(&id) {
                             let _ = handle.mailbox.send(BlockMailboxCmd::Terminate).await;
                         }
+                        if let Some(alarms) = &self.alarms {
+                            alarms.forget(&id, current_time_millis());
+                        }
                     }
+                    self.programs = ProgramTable::default();
                     continue;
                 } else if matches!(message, EngineMessage::Pause) {
                     is_paused = true;
@@ -284,7 +318,38 @@ impl SingleThreadedEngine {
             receiver,
             reply_senders: BTreeMap::new(),
             watchers: Rc::default(),
+            programs: ProgramTable::default(),
+            history: None,
+            recording_history: false,
+            alarms: None,
+            tracking_alarms: false,
+        }
+    }
+
+    /// Records the pins trended in `history` once the engine runs, and
+    /// answers history requests from it. Call before [`run`](Engine::run).
+    pub fn set_history(&mut self, history: History) {
+        self.history = Some(history);
+    }
+
+    /// The engine's trend history, if it has one.
+    pub fn history(&self) -> Option<&History> {
+        self.history.as_ref()
+    }
+
+    /// Tracks the alarms of the engine's alarm blocks in `alarms` once the
+    /// engine runs, and answers alarm requests from it. Call before
+    /// [`run`](Engine::run).
+    pub fn set_alarms(&mut self, alarms: AlarmTable) {
+        for handle in self.handles.values() {
+            watch_source(Some(&alarms), handle.id, &handle.desc);
         }
+        self.alarms = Some(alarms);
+    }
+
+    /// The engine's alarm table, if it has one.
+    pub fn alarms(&self) -> Option<&AlarmTable> {
+        self.alarms.as_ref()
     }
 
     /// Returns sync metadata handles for every scheduled block. Use the
@@ -317,6 +382,32 @@ impl SingleThreadedEngine {
 
     // --- Sync engine ops (configuration phase) -----------------------------
 
+    /// Schedules the blocks of `program` as members of the program `id`
+    /// and queues its links.
+    fn schedule_program_blocks_into(&mut self, id: &str, program: &Program) -> Result<()> {
+        for (uuid_str, pb) in &program.blocks {
+            let block_id = parse_block_uuid(uuid_str)?;
+            let block_def =
+                get_block(&pb.name, Some(&pb.lib)).ok_or_else(|| RegistryError::BlockNotFound {
+                    library: pb.lib.clone(),
+                    name: pb.name.clone(),
+                })?;
+            schedule_block_on_engine(&block_def.desc, Some(block_id), self)?;
+            // Record UI metadata on the handle so it round-trips on save.
+            if let Some(handle) = self.handles.get_mut(&block_id) {
+                handle.label = pb.label.clone();
+                handle.position = pb.positions;
+                handle.program = id.to_string();
+            }
+        }
+        // Wiring is async (mailbox round-trips); validate sync and queue
+        // for processing at `run()` start.
+        for link in program.links.values() {
+            self.connect_blocks_sync(link)?;
+        }
+        Ok(())
+    }
+
     pub(super) fn connect_blocks_sync(&mut self, link_data: &LinkData) -> Result<LinkData> {
         // Wiring requires mailbox round-trips between the source and target
         // actor tasks; that's deferred to `run()` start. Here we validate
@@ -391,7 +482,11 @@ impl SingleThreadedEngine {
             })?;
         // schedule_block_on_engine spawns the actor task immediately (via
         // `schedule()`); it returns the assigned block id.
-        schedule_block_on_engine(&block_def.desc, block_id, self)
+        let id = schedule_block_on_engine(&block_def.desc, block_id, self)?;
+        if self.programs.is_paused(DEFAULT_PROGRAM) {
+            self.set_blocks_paused(&[id], true).await?;
+        }
+        Ok(id)
     }
 
     pub(crate) async fn inspect_block(&self, id: &Uuid) -> Result<BlockDefinition, EngineError> {
@@ -526,6 +621,21 @@ impl SingleThreadedEngine {
         })
     }
 
+    /// Seeds `alarms` with the priority and message inputs the alarm
+    /// blocks already hold, which they report only once they change.
+    async fn seed_alarm_sources(&self, alarms: &AlarmTable) {
+        for handle in self.handles.values() {
+            if handle.desc.category != ALARM_CATEGORY {
+                continue;
+            }
+            for pin in ALARM_INPUTS {
+                if let Some(value) = self.read_source_value(&handle.id, pin, false).await {
+                    alarms.configure(&handle.id, pin, &value);
+                }
+            }
+        }
+    }
+
     async fn read_source_value(
         &self,
         source_id: &Uuid,
@@ -621,6 +731,9 @@ impl SingleThreadedEngine {
         // 3. Terminate the block.
         let _ = target_mb.send(BlockMailboxCmd::Terminate).await;
         self.handles.remove(block_id);
+        if let Some(alarms) = &self.alarms {
+            alarms.forget(block_id, current_time_millis());
+        }
 
         Ok(*block_id)
     }
@@ -629,10 +742,34 @@ impl SingleThreadedEngine {
     /// metadata + current pin values, plus every link. This is the
     /// canonical save format — round-trips through `load_program`.
     pub(crate) async fn save_program(&self) -> Result<Program> {
+        self.snapshot(None).await
+    }
+
+    /// Snapshot one program: its blocks, and the links leaving them,
+    /// including links into other programs. Round-trips through
+    /// `load_named_program` once the programs those links enter are
+    /// loaded.
+    pub(crate) async fn save_named_program(&self, id: &str) -> Result<Program> {
+        let slot = self.programs.get(id)?;
+        let name = slot.name.clone();
+        let description = slot.description.clone();
+
+        Ok(Program {
+            name,
+            description,
+            ..self.snapshot(Some(id)).await?
+        })
+    }
+
+    async fn snapshot(&self, program: Option<&str>) -> Result<Program> {
         let mut blocks = std::collections::BTreeMap::new();
         let mut links = std::collections::BTreeMap::new();
 
-        for (id, handle) in &self.handles {
+        let handles = self
+            .handles
+            .iter()
+            .filter(|(_, handle)| program.is_none_or(|program| handle.program == program));
+        for (id, handle) in handles {
             // Pull dynamic state (pin values + per-input connectedness)
             // via `Inspect`.
             let definition = mailbox_request(&handle.mailbox, *id, |reply| {
@@ -699,6 +836,7 @@ impl SingleThreadedEngine {
             description: None,
             blocks,
             links,
+            params: BTreeMap::new(),
         })
     }
 
@@ -712,10 +850,29 @@ impl SingleThreadedEngine {
     /// cmds). When invoked through the engine message channel
     /// ([`LoadProgramReq`](EngineMessage::LoadProgramReq)), this is automatic.
     pub(crate) async fn load_program(&mut self, program: Program) -> Result<()> {
+        self.load_program_into(DEFAULT_PROGRAM, program).await
+    }
+
+    /// Load a [`Program`] side by side with the ones already running, as
+    /// the program `id`. Its links may enter blocks of programs loaded
+    /// earlier. A program that fails to load is removed again with the
+    /// blocks scheduled for it, so loading can be retried.
+    pub(crate) async fn load_named_program(&mut self, id: &str, program: Program) -> Result<()> {
+        self.programs.insert(id, &program)?;
+        let pending_links = self.pending_links.len();
+        if let Err(err) = self.load_program_into(id, program).await {
+            self.pending_links.truncate(pending_links);
+            let _ = self.unload_program(id).await;
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    async fn load_program_into(&mut self, id: &str, program: Program) -> Result<()> {
         // Sync: schedule blocks + queue links. After this the per-block
         // actor tasks have been spawned and the link wiring is queued
         // for processing by `connect_blocks` calls below.
-        self.schedule_program_blocks(&program)?;
+        self.schedule_program_blocks_into(id, &program)?;
 
         // Wire each queued link asynchronously (mailbox round-trips
         // between source and target blocks).
@@ -724,23 +881,134 @@ impl SingleThreadedEngine {
             self.connect_blocks(&link).await?;
         }
 
+        seed_program_sources(self.alarms.as_ref(), &program);
+
         // Push each block's saved input/output values.
         for (uuid_str, pb) in &program.blocks {
-            let id = parse_block_uuid(uuid_str)?;
+            let block_id = parse_block_uuid(uuid_str)?;
             for (name, pin) in &pb.inputs {
                 if hasinitialvalue(&pin.value) {
-                    let _ = self.write_input(&id, name.clone(), pin.value.clone()).await;
+                    let _ = self
+                        .write_input(&block_id, name.clone(), pin.value.clone())
+                        .await;
                 }
             }
             for (name, pin) in &pb.outputs {
                 if hasinitialvalue(&pin.value) {
                     let _ = self
-                        .write_output(&id, name.clone(), pin.value.clone())
+                        .write_output(&block_id, name.clone(), pin.value.clone())
                         .await;
                 }
             }
         }
 
+        // Loading into a paused program leaves the new blocks paused too.
+        if self.programs.is_paused(id) {
+            let ids = program
+                .blocks
+                .keys()
+                .map(|uuid| parse_block_uuid(uuid))
+                .collect::<Result<Vec<_>, _>>()?;
+            self.set_blocks_paused(&ids, true).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Pause or resume every block of the program `id`.
+    pub(crate) async fn set_program_paused(&mut self, id: &str, paused: bool) -> Result<()> {
+        self.programs.get_mut(id)?.paused = paused;
+        let ids = self.program_block_ids(id);
+        self.set_blocks_paused(&ids, paused).await
+    }
+
+    /// Remove every block of the program `id`, keeping the program.
+    pub(crate) async fn reset_program(&mut self, id: &str) -> Result<()> {
+        self.programs.get_mut(id)?.paused = false;
+        for block_id in self.program_block_ids(id) {
+            self.remove_block(&block_id).await?;
+        }
+        Ok(())
+    }
+
+    /// Remove every block of the program `id` and the program itself.
+    pub(crate) async fn unload_program(&mut self, id: &str) -> Result<()> {
+        if id == DEFAULT_PROGRAM {
+            return Err(EngineError::DefaultProgramUnload.into());
+        }
+        self.reset_program(id).await?;
+        self.programs.remove(id)?;
+        Ok(())
+    }
+
+    /// Applies a [`ProgramAction`] to the program `id`.
+    pub(crate) async fn program_action(&mut self, id: &str, action: ProgramAction) -> Result<()> {
+        match action {
+            ProgramAction::Load(program) => self.load_named_program(id, program).await,
+            ProgramAction::Pause => self.set_program_paused(id, true).await,
+            ProgramAction::Resume => self.set_program_paused(id, false).await,
+            ProgramAction::Reset => self.reset_program(id).await,
+            ProgramAction::Unload => self.unload_program(id).await,
+        }
+    }
+
+    /// Starts, changes or with [`None`] stops recording `point` into the
+    /// engine's history.
+    pub(crate) async fn trend_pin(&self, point: Point, recording: Option<Recording>) -> Result<()> {
+        let history = self.history.as_ref().ok_or(HistoryError::NotEnabled)?;
+        match recording {
+            Some(recording) => {
+                let block = self.inspect_block(&point.block).await?;
+                Ok(trend_pin(history, &block, point, recording).await?)
+            }
+            None => {
+                history.untrend(&point);
+                Ok(())
+            }
+        }
+    }
+
+    /// Reads the engine's history.
+    pub(crate) async fn query_history(&self, query: HistoryQuery) -> Result<Vec<Sample>> {
+        let history = self.history.as_ref().ok_or(HistoryError::NotEnabled)?;
+        Ok(query_history(history, query).await?)
+    }
+
+    /// The alarms in the engine's alarm table.
+    pub(crate) fn list_alarms(&self) -> Result<Vec<Alarm>> {
+        let alarms = self.alarms.as_ref().ok_or(AlarmError::NotEnabled)?;
+        Ok(alarms.alarms())
+    }
+
+    /// Applies an operator request to the alarm source `source`.
+    pub(crate) fn alarm_action(&self, source: &Uuid, action: AlarmAction) -> Result<()> {
+        let alarms = self.alarms.as_ref().ok_or(AlarmError::NotEnabled)?;
+        Ok(alarms.apply(source, action, current_time_millis())?)
+    }
+
+    /// Summaries of the loaded programs.
+    pub fn programs(&self) -> Vec<ProgramSummary> {
+        self.programs
+            .summaries(self.handles.values().map(|handle| handle.program.as_str()))
+    }
+
+    fn program_block_ids(&self, id: &str) -> Vec<Uuid> {
+        self.handles
+            .values()
+            .filter(|handle| handle.program == id)
+            .map(|handle| handle.id)
+            .collect()
+    }
+
+    async fn set_blocks_paused(&self, ids: &[Uuid], paused: bool) -> Result<()> {
+        for id in ids {
+            mailbox_send(
+                self.mailbox_or_err(id)?,
+                *id,
+                BlockMailboxCmd::SetPaused { paused },
+            )
+            .await?;
+        }
         Ok(())
     }
 
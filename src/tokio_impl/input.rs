@@ -30,6 +30,7 @@ impl InputImpl {
         Self {
             name: name.to_string(),
             kind,
+            accepts_lists: false,
 
             block_id,
             connection_count: 0,
@@ -42,6 +43,12 @@ impl InputImpl {
             links: Default::default(),
         }
     }
+
+    /// Sets whether the input also takes lists of its kind.
+    pub fn accepting_lists(mut self, accepts_lists: bool) -> Self {
+        self.accepts_lists = accepts_lists;
+        self
+    }
 }
 
 impl Input for InputImpl {
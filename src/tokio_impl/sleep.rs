@@ -5,11 +5,51 @@ pub(crate) use crate::wasm::sleep::current_time_millis;
 #[cfg(target_arch = "wasm32")]
 pub(super) use crate::wasm::sleep::sleep_millis;
 
+/// A clock the blocks on this thread see instead of the wall clock, set
+/// by the [`testing`](crate::testing) harness.
+#[cfg(all(any(test, feature = "testing"), not(target_arch = "wasm32")))]
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum VirtualClock {
+    /// Stopped at this time, in milliseconds since the Unix epoch. Sleeps
+    /// return as soon as the sleeping block yields.
+    Stopped(u64),
+    /// Follows the paused Tokio clock, which read `start` at `epoch_ms`
+    /// milliseconds since the Unix epoch.
+    Tokio {
+        epoch_ms: u64,
+        start: tokio::time::Instant,
+    },
+}
+
+#[cfg(all(any(test, feature = "testing"), not(target_arch = "wasm32")))]
+thread_local! {
+    pub(crate) static VIRTUAL_CLOCK: std::cell::Cell<Option<VirtualClock>> = const {
+        std::cell::Cell::new(None)
+    };
+}
+
 /// Sleeps for the given number of milliseconds (non-WASM version).
 #[cfg(not(target_arch = "wasm32"))]
 pub(super) async fn sleep_millis(millis: u64) {
     use tokio::time::{Duration, sleep};
 
+    #[cfg(any(test, feature = "testing"))]
+    if let Some(VirtualClock::Stopped(_)) = VIRTUAL_CLOCK.get() {
+        // Yield once, so a block waiting on its inputs or a timeout still
+        // takes the inputs that are ready.
+        let mut yielded = false;
+        return std::future::poll_fn(|cx| {
+            if yielded {
+                std::task::Poll::Ready(())
+            } else {
+                yielded = true;
+                cx.waker().wake_by_ref();
+                std::task::Poll::Pending
+            }
+        })
+        .await;
+    }
+
     sleep(Duration::from_millis(millis)).await;
 }
 
@@ -18,6 +58,15 @@ pub(super) async fn sleep_millis(millis: u64) {
 pub(crate) fn current_time_millis() -> u64 {
     use std::time::{SystemTime, UNIX_EPOCH};
 
+    #[cfg(any(test, feature = "testing"))]
+    match VIRTUAL_CLOCK.get() {
+        Some(VirtualClock::Stopped(now)) => return now,
+        Some(VirtualClock::Tokio { epoch_ms, start }) => {
+            return epoch_ms + start.elapsed().as_millis() as u64;
+        }
+        None => {}
+    }
+
     let now = SystemTime::now();
     match now.duration_since(UNIX_EPOCH) {
         Ok(duration) => duration.as_millis() as u64,
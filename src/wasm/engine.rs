@@ -15,7 +15,9 @@ use uuid::Uuid;
 use wasm_bindgen::JsValue;
 use wasm_bindgen::prelude::wasm_bindgen;
 
+use crate::base::alarm::AlarmTable;
 use crate::base::engine::Engine;
+use crate::base::history::{History, MemoryStore};
 
 /// Controls the execution of blocks.
 ///
@@ -112,6 +114,25 @@ impl BlocksEngine {
         Ok(name)
     }
 
+    /// Keeps a trend history in memory, up to `capacity` samples per pin,
+    /// so charts can show values recorded before they were opened. Pins
+    /// are trended with the command object's `trendPin`. Call before
+    /// `run`.
+    #[wasm_bindgen(js_name = "enableHistory")]
+    pub fn enable_history(&mut self, capacity: usize) {
+        self.engine
+            .set_history(History::new(MemoryStore::new(capacity)));
+    }
+
+    /// Tracks the alarms raised by alarm blocks in an alarm table, so
+    /// they can be listed, acknowledged and shelved with the command
+    /// object. Call before `run` and before creating the command objects
+    /// that watch alarms.
+    #[wasm_bindgen(js_name = "enableAlarms")]
+    pub fn enable_alarms(&mut self) {
+        self.engine.set_alarms(AlarmTable::new());
+    }
+
     /// Returns a new [`EngineCommand`] handle for sending commands.
     #[wasm_bindgen(js_name = "engineCommand")]
     pub fn engine_command(&mut self) -> EngineCommand {
@@ -120,7 +141,7 @@ impl BlocksEngine {
         let uuid = Uuid::new_v4();
         let engine_sender = self.engine.create_message_channel(uuid, sender);
 
-        EngineCommand::new(uuid, engine_sender, receiver)
+        EngineCommand::new(uuid, engine_sender, receiver, self.engine.alarms().cloned())
     }
 
     /// Runs the engine asynchronously.
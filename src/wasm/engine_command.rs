@@ -4,6 +4,9 @@
 
 use std::str::FromStr;
 
+use crate::base::alarm::{AlarmAction, AlarmTable};
+use crate::base::error::AlarmError;
+use crate::base::history::{HistoryQuery, Point, Recording};
 use crate::base::program::Program;
 use crate::base::program::data::LinkData;
 use crate::wasm::types::JsWatchNotification;
@@ -13,7 +16,7 @@ use uuid::Uuid;
 use wasm_bindgen::JsValue;
 use wasm_bindgen::prelude::wasm_bindgen;
 
-use crate::base::engine::messages::EngineMessage;
+use crate::base::engine::messages::{EngineMessage, ProgramAction};
 use crate::single_threaded::Messages;
 
 /// Commands a running instance of a Block Engine.
@@ -22,16 +25,23 @@ pub struct EngineCommand {
     uuid: Uuid,
     sender: Sender<Messages>,
     receiver: Receiver<Messages>,
+    alarms: Option<AlarmTable>,
 }
 
 #[wasm_bindgen]
 impl EngineCommand {
     /// Creates a new engine command handle.
-    pub(super) fn new(uuid: Uuid, sender: Sender<Messages>, receiver: Receiver<Messages>) -> Self {
+    pub(super) fn new(
+        uuid: Uuid,
+        sender: Sender<Messages>,
+        receiver: Receiver<Messages>,
+        alarms: Option<AlarmTable>,
+    ) -> Self {
         Self {
             uuid,
             sender,
             receiver,
+            alarms,
         }
     }
 
@@ -236,16 +246,20 @@ impl EngineCommand {
     /// Returns the current running engine program in the canonical save
     /// format ([`Program`] serialized as JSON). Round-trips through
     /// `loadProgram` without re-assembly.
+    ///
+    /// With a `program_id`, returns only that program, which round-trips
+    /// through `loadNamedProgram`.
     #[wasm_bindgen(js_name = "getProgram")]
-    pub async fn get_program(&mut self) -> Result<JsValue, String> {
-        match self
-            .sender
-            .send(EngineMessage::GetCurrentProgramReq(self.uuid))
-            .await
-        {
+    pub async fn get_program(&mut self, program_id: Option<String>) -> Result<JsValue, String> {
+        let request = match program_id {
+            Some(program_id) => EngineMessage::GetProgramReq(self.uuid, program_id),
+            None => EngineMessage::GetCurrentProgramReq(self.uuid),
+        };
+        match self.sender.send(request).await {
             Ok(_) => match self.receiver.recv().await {
                 Some(res) => match res {
-                    EngineMessage::GetCurrentProgramRes(data) => data
+                    EngineMessage::GetCurrentProgramRes(data)
+                    | EngineMessage::GetProgramRes(data) => data
                         .map(|ok| serde_wasm_bindgen::to_value(&ok))?
                         .map_err(|err| err.to_string()),
                     _ => Err("Invalid response".to_string()),
@@ -256,6 +270,63 @@ impl EngineCommand {
         }
     }
 
+    /// Loads a program side by side with the ones already running, under
+    /// the given id.
+    #[wasm_bindgen(js_name = "loadNamedProgram")]
+    pub async fn load_named_program(
+        &mut self,
+        program_id: String,
+        program: JsValue,
+    ) -> Result<(), String> {
+        let program: Program = serde_wasm_bindgen::from_value(program)
+            .map_err(|err| format!("Invalid program payload: {err}"))?;
+        self.program_req(program_id, ProgramAction::Load(program))
+            .await
+    }
+
+    /// Pauses the blocks of one program.
+    #[wasm_bindgen(js_name = "pauseProgram")]
+    pub async fn pause_program(&mut self, program_id: String) -> Result<(), String> {
+        self.program_req(program_id, ProgramAction::Pause).await
+    }
+
+    /// Resumes the blocks of one program.
+    #[wasm_bindgen(js_name = "resumeProgram")]
+    pub async fn resume_program(&mut self, program_id: String) -> Result<(), String> {
+        self.program_req(program_id, ProgramAction::Resume).await
+    }
+
+    /// Removes the blocks of one program, keeping the program loaded.
+    #[wasm_bindgen(js_name = "resetProgram")]
+    pub async fn reset_program(&mut self, program_id: String) -> Result<(), String> {
+        self.program_req(program_id, ProgramAction::Reset).await
+    }
+
+    /// Removes one program and its blocks.
+    #[wasm_bindgen(js_name = "unloadProgram")]
+    pub async fn unload_program(&mut self, program_id: String) -> Result<(), String> {
+        self.program_req(program_id, ProgramAction::Unload).await
+    }
+
+    /// Lists the programs loaded in the engine.
+    #[wasm_bindgen(js_name = "listPrograms")]
+    pub async fn list_programs(&mut self) -> Result<JsValue, String> {
+        match self
+            .sender
+            .send(EngineMessage::ListProgramsReq(self.uuid))
+            .await
+        {
+            Ok(_) => match self.receiver.recv().await {
+                Some(EngineMessage::ListProgramsRes(programs)) => {
+                    serde_wasm_bindgen::to_value(&programs).map_err(|err| err.to_string())
+                }
+                Some(_) => Err("Invalid response".to_string()),
+                None => Err("Failed to receive message".to_string()),
+            },
+            Err(_) => Err("Failed to send message".to_string()),
+        }
+    }
+
     /// Inspects the current state of a block.
     #[wasm_bindgen(js_name = "inspectBlock")]
     pub async fn inspect_block(&mut self, block_uuid: String) -> Result<JsValue, String> {
@@ -317,6 +388,131 @@ impl EngineCommand {
         }
     }
 
+    /// Starts recording a block pin into the engine's history, or stops
+    /// it when `recording` is omitted. `recording` is `"onChange"` or
+    /// `{"interval": <millis>}`. The engine needs a history, see
+    /// `enableHistory`.
+    #[wasm_bindgen(js_name = "trendPin")]
+    pub async fn trend_pin(
+        &mut self,
+        block_uuid: String,
+        pin: String,
+        recording: JsValue,
+    ) -> Result<(), String> {
+        let recording: Option<Recording> = serde_wasm_bindgen::from_value(recording)
+            .map_err(|err| format!("Invalid recording: {err}"))?;
+        let point = Point::new(Uuid::from_str(&block_uuid).unwrap_or_default(), pin);
+        match self
+            .sender
+            .send(EngineMessage::TrendReq(self.uuid, point, recording))
+            .await
+        {
+            Ok(_) => match self.receiver.recv().await {
+                Some(EngineMessage::TrendRes(res)) => res,
+                Some(_) => Err("Invalid response".to_string()),
+                None => Err("Failed to receive message".to_string()),
+            },
+            Err(_) => Err("Failed to send message".to_string()),
+        }
+    }
+
+    /// Reads the engine's history of a pin, returning its samples as
+    /// `{ts, value, status}` objects.
+    ///
+    /// `query` names the `block` and `pin`, and optionally the `start` and
+    /// `end` of the range in milliseconds since the Unix epoch, and a
+    /// `rollup` (`min`, `max`, `avg` or `last`) over an `interval` in
+    /// milliseconds. Includes what was recorded before the caller
+    /// connected, so a chart can start with the recent past.
+    #[wasm_bindgen(js_name = "queryHistory")]
+    pub async fn query_history(&mut self, query: JsValue) -> Result<JsValue, String> {
+        let query: HistoryQuery = serde_wasm_bindgen::from_value(query)
+            .map_err(|err| format!("Invalid history query: {err}"))?;
+        match self
+            .sender
+            .send(EngineMessage::HistoryReq(self.uuid, query))
+            .await
+        {
+            Ok(_) => match self.receiver.recv().await {
+                Some(EngineMessage::HistoryRes(res)) => res
+                    .map(|samples| serde_wasm_bindgen::to_value(&samples))?
+                    .map_err(|err| err.to_string()),
+                Some(_) => Err("Invalid response".to_string()),
+                None => Err("Failed to receive message".to_string()),
+            },
+            Err(_) => Err("Failed to send message".to_string()),
+        }
+    }
+
+    /// Lists the alarms in the engine's alarm table, most urgent first.
+    /// The engine needs an alarm table, see `enableAlarms`.
+    #[wasm_bindgen(js_name = "listAlarms")]
+    pub async fn list_alarms(&mut self) -> Result<JsValue, String> {
+        match self
+            .sender
+            .send(EngineMessage::ListAlarmsReq(self.uuid))
+            .await
+        {
+            Ok(_) => match self.receiver.recv().await {
+                Some(EngineMessage::ListAlarmsRes(res)) => res
+                    .map(|alarms| serde_wasm_bindgen::to_value(&alarms))?
+                    .map_err(|err| err.to_string()),
+                Some(_) => Err("Invalid response".to_string()),
+                None => Err("Failed to receive message".to_string()),
+            },
+            Err(_) => Err("Failed to send message".to_string()),
+        }
+    }
+
+    /// Acknowledges the alarm of an alarm block.
+    #[wasm_bindgen(js_name = "acknowledgeAlarm")]
+    pub async fn acknowledge_alarm(&mut self, block_uuid: String) -> Result<(), String> {
+        self.alarm_req(block_uuid, AlarmAction::Acknowledge).await
+    }
+
+    /// Stops an alarm block from announcing its alarms for `duration`
+    /// milliseconds.
+    #[wasm_bindgen(js_name = "shelveAlarm")]
+    pub async fn shelve_alarm(&mut self, block_uuid: String, duration: u64) -> Result<(), String> {
+        self.alarm_req(block_uuid, AlarmAction::Shelve(duration))
+            .await
+    }
+
+    /// Lifts the shelving of an alarm block.
+    #[wasm_bindgen(js_name = "unshelveAlarm")]
+    pub async fn unshelve_alarm(&mut self, block_uuid: String) -> Result<(), String> {
+        self.alarm_req(block_uuid, AlarmAction::Unshelve).await
+    }
+
+    /// Calls `callback` with every alarm transition: raised, acknowledged,
+    /// cleared, shelved, unshelved and removed. Like `createWatch`, this
+    /// never returns. The engine needs an alarm table, see `enableAlarms`,
+    /// enabled before this command object was created.
+    #[wasm_bindgen(js_name = "createAlarmWatch")]
+    pub async fn create_alarm_watch(&mut self, callback: &js_sys::Function) -> Result<(), String> {
+        let alarms = self
+            .alarms
+            .as_ref()
+            .ok_or_else(|| AlarmError::NotEnabled.to_string())?;
+        let (sender, mut receiver) = unbounded_channel();
+        alarms.subscribe(self.uuid, move |event| sender.send(event.clone()).is_ok());
+
+        loop {
+            if let Some(event) = receiver.recv().await {
+                match serde_wasm_bindgen::to_value(&event)
+                    .map_err(|err| format!("Failed to serialize alarm event: {err:?}"))
+                    .and_then(|js_res| {
+                        callback
+                            .call1(&JsValue::NULL, &js_res)
+                            .map_err(|err| format!("Failed to call alarm callback: {err:?}"))
+                    }) {
+                    Ok(_) => (),
+                    Err(err) => log::debug!(target: "create_alarm_watch", "{err}"),
+                }
+            }
+        }
+    }
+
     /// Creates a watch on block changes.
     ///
     /// The engine→UI watch channel is **unbounded**. The producer rate is
@@ -390,3 +586,40 @@ impl EngineCommand {
             .map_err(|err| err.to_string())
     }
 }
+
+impl EngineCommand {
+    async fn alarm_req(&mut self, block_uuid: String, action: AlarmAction) -> Result<(), String> {
+        let block_uuid = Uuid::from_str(&block_uuid).map_err(|err| err.to_string())?;
+        match self
+            .sender
+            .send(EngineMessage::AlarmReq(self.uuid, block_uuid, action))
+            .await
+        {
+            Ok(_) => match self.receiver.recv().await {
+                Some(EngineMessage::AlarmRes(res)) => res,
+                Some(_) => Err("Invalid response".to_string()),
+                None => Err("Failed to receive message".to_string()),
+            },
+            Err(_) => Err("Failed to send message".to_string()),
+        }
+    }
+
+    async fn program_req(
+        &mut self,
+        program_id: String,
+        action: ProgramAction,
+    ) -> Result<(), String> {
+        match self
+            .sender
+            .send(EngineMessage::ProgramReq(self.uuid, program_id, action))
+            .await
+        {
+            Ok(_) => match self.receiver.recv().await {
+                Some(EngineMessage::ProgramRes(res)) => res,
+                Some(_) => Err("Invalid response".to_string()),
+                None => Err("Failed to receive message".to_string()),
+            },
+            Err(_) => Err("Failed to send message".to_string()),
+        }
+    }
+}
@@ -0,0 +1,331 @@
+import type {
+  BlockDesc,
+  BlockNotification,
+  LinkData,
+  Program,
+} from './index';
+
+/**
+ * Summary of a program loaded in an engine.
+ */
+export interface ProgramSummary {
+  id: string;
+  name?: string;
+  description?: string;
+  blocks: number;
+  paused: boolean;
+}
+
+/**
+ * How a pin is recorded into the engine's history.
+ */
+export type Recording = 'onChange' | { interval: number };
+
+/**
+ * A read of one pin's history. Times are milliseconds since the Unix
+ * epoch; `end` is exclusive.
+ */
+export interface HistoryQuery {
+  block: string;
+  pin: string;
+  start?: number;
+  end?: number;
+  rollup?: 'min' | 'max' | 'avg' | 'last';
+  interval?: number;
+}
+
+/**
+ * A recorded pin value.
+ */
+export interface HistorySample {
+  ts: number;
+  value: unknown;
+  status: 'Ok' | 'Fault' | 'Stale';
+}
+
+/**
+ * An alarm in the engine's alarm table. Times are milliseconds since the
+ * Unix epoch.
+ */
+export interface Alarm {
+  source: string;
+  kind: string;
+  message?: string;
+  priority: number;
+  state: 'active' | 'acknowledged' | 'cleared';
+  raisedAt: number;
+  ackedAt?: number;
+  clearedAt?: number;
+  shelvedUntil?: number;
+}
+
+/**
+ * A change of an alarm, with the alarm as it is after the change.
+ */
+export interface AlarmEvent {
+  ts: number;
+  source: string;
+  transition:
+    | 'raised'
+    | 'acknowledged'
+    | 'cleared'
+    | 'shelved'
+    | 'unshelved'
+    | 'removed';
+  alarm?: Alarm;
+}
+
+type Pending = {
+  resolve: (result: unknown) => void;
+  reject: (error: string) => void;
+};
+
+/**
+ * Drives a native engine served by the Rust `server` feature (for example
+ * `logic-mesh run program.json --listen 0.0.0.0:8080`) over a WebSocket.
+ *
+ * The methods mirror `EngineCommand`, so an editor written against the
+ * in-browser engine can attach to one running on a controller.
+ */
+export class RemoteEngine {
+  private nextId = 1;
+  private readonly pending = new Map<number, Pending>();
+  private readonly watchers = new Set<
+    (notification: BlockNotification) => void
+  >();
+  private readonly alarmWatchers = new Set<(event: AlarmEvent) => void>();
+
+  private constructor(private readonly socket: WebSocket) {
+    socket.addEventListener('message', (event) => this.onFrame(event.data));
+    socket.addEventListener('close', () => {
+      for (const { reject } of this.pending.values()) {
+        reject('Connection closed');
+      }
+      this.pending.clear();
+    });
+  }
+
+  /**
+   * Connects to a server. `url` is the server root, e.g.
+   * `ws://controller:8080`.
+   */
+  static connect(url: string): Promise<RemoteEngine> {
+    const socket = new WebSocket(`${url.replace(/\/$/, '')}/api/ws`);
+    return new Promise((resolve, reject) => {
+      socket.addEventListener('open', () => resolve(new RemoteEngine(socket)));
+      socket.addEventListener('error', () =>
+        reject(new Error(`Cannot connect to ${url}`))
+      );
+    });
+  }
+
+  /** Closes the connection. */
+  close(): void {
+    this.socket.close();
+  }
+
+  listBlocks(): Promise<BlockDesc[]> {
+    return this.call({ type: 'listBlocks' });
+  }
+
+  addBlock(blockName: string, blockUuid?: string, lib?: string) {
+    return this.call<string>({
+      type: 'addBlock',
+      name: blockName,
+      block: blockUuid,
+      lib,
+    });
+  }
+
+  removeBlock(blockUuid: string) {
+    return this.call<string>({ type: 'removeBlock', block: blockUuid });
+  }
+
+  createLink(
+    sourceBlockUuid: string,
+    targetBlockUuid: string,
+    sourceBlockPinName: string,
+    targetBlockPinName: string
+  ) {
+    return this.call<LinkData>({
+      type: 'createLink',
+      sourceBlockUuid,
+      targetBlockUuid,
+      sourceBlockPinName,
+      targetBlockPinName,
+    });
+  }
+
+  removeLink(linkUuid: string) {
+    return this.call<boolean>({ type: 'removeLink', link: linkUuid });
+  }
+
+  writeBlockOutput(blockUuid: string, outputName: string, value: unknown) {
+    return this.call({
+      type: 'writeBlockOutput',
+      block: blockUuid,
+      pin: outputName,
+      value,
+    });
+  }
+
+  writeBlockInput(blockUuid: string, inputName: string, value: unknown) {
+    return this.call({
+      type: 'writeBlockInput',
+      block: blockUuid,
+      pin: inputName,
+      value,
+    });
+  }
+
+  loadProgram(program: Program) {
+    return this.call<void>({ type: 'loadProgram', program });
+  }
+
+  getProgram(programId?: string) {
+    return this.call<Program>({ type: 'getProgram', programId });
+  }
+
+  loadNamedProgram(programId: string, program: Program) {
+    return this.call<void>({ type: 'loadNamedProgram', programId, program });
+  }
+
+  pauseProgram(programId: string) {
+    return this.call<void>({ type: 'pauseProgram', programId });
+  }
+
+  resumeProgram(programId: string) {
+    return this.call<void>({ type: 'resumeProgram', programId });
+  }
+
+  resetProgram(programId: string) {
+    return this.call<void>({ type: 'resetProgram', programId });
+  }
+
+  unloadProgram(programId: string) {
+    return this.call<void>({ type: 'unloadProgram', programId });
+  }
+
+  listPrograms() {
+    return this.call<ProgramSummary[]>({ type: 'listPrograms' });
+  }
+
+  inspectBlock(blockUuid: string) {
+    return this.call({ type: 'inspectBlock', block: blockUuid });
+  }
+
+  trendPin(blockUuid: string, pin: string, recording?: Recording) {
+    return this.call<void>({
+      type: 'trendPin',
+      block: blockUuid,
+      pin,
+      recording,
+    });
+  }
+
+  queryHistory(query: HistoryQuery) {
+    return this.call<HistorySample[]>({ type: 'queryHistory', ...query });
+  }
+
+  listAlarms() {
+    return this.call<Alarm[]>({ type: 'listAlarms' });
+  }
+
+  acknowledgeAlarm(blockUuid: string) {
+    return this.call<void>({ type: 'acknowledgeAlarm', block: blockUuid });
+  }
+
+  shelveAlarm(blockUuid: string, duration: number) {
+    return this.call<void>({
+      type: 'shelveAlarm',
+      block: blockUuid,
+      duration,
+    });
+  }
+
+  unshelveAlarm(blockUuid: string) {
+    return this.call<void>({ type: 'unshelveAlarm', block: blockUuid });
+  }
+
+  evalBlock(blockName: string, inputs: unknown[], lib?: string) {
+    return this.call<unknown[]>({
+      type: 'evalBlock',
+      name: blockName,
+      inputs,
+      lib,
+    });
+  }
+
+  /**
+   * Calls `callback` for every block notification until the connection
+   * closes. Unlike the wasm `createWatch`, this returns immediately.
+   */
+  createWatch(callback: (notification: BlockNotification) => void): void {
+    this.watchers.add(callback);
+  }
+
+  /**
+   * Calls `callback` for every alarm transition until the connection
+   * closes. Unlike the wasm `createAlarmWatch`, this returns immediately.
+   */
+  createAlarmWatch(callback: (event: AlarmEvent) => void): void {
+    this.alarmWatchers.add(callback);
+  }
+
+  pauseExecution() {
+    return this.call<void>({ type: 'pauseExecution' });
+  }
+
+  resumeExecution() {
+    return this.call<void>({ type: 'resumeExecution' });
+  }
+
+  resetEngine() {
+    return this.call<void>({ type: 'resetEngine' });
+  }
+
+  stopEngine() {
+    return this.call<void>({ type: 'stopEngine' });
+  }
+
+  private call<T = unknown>(request: Record<string, unknown>): Promise<T> {
+    const id = this.nextId++;
+    return new Promise<T>((resolve, reject) => {
+      this.pending.set(id, {
+        resolve: resolve as (result: unknown) => void,
+        reject,
+      });
+      this.socket.send(JSON.stringify({ ...request, id }));
+    });
+  }
+
+  private onFrame(data: unknown): void {
+    if (typeof data !== 'string') {
+      return;
+    }
+    const frame = JSON.parse(data);
+    if (frame.watch) {
+      for (const watcher of this.watchers) {
+        watcher(frame.watch as BlockNotification);
+      }
+      return;
+    }
+    if (frame.alarm) {
+      for (const watcher of this.alarmWatchers) {
+        watcher(frame.alarm as AlarmEvent);
+      }
+      return;
+    }
+
+    const pending = this.pending.get(frame.id);
+    if (!pending) {
+      return;
+    }
+    this.pending.delete(frame.id);
+    if ('error' in frame) {
+      pending.reject(frame.error);
+    } else {
+      pending.resolve(frame.result);
+    }
+  }
+}
@@ -0,0 +1,1483 @@
+// Generated by `cargo run --example export_catalog`; do not edit.
+
+/**
+ * The Haystack JSON (Hayson) encoding of the values of each kind.
+ */
+export interface KindValues {
+  null: unknown;
+  remove: { _kind: 'remove' };
+  marker: { _kind: 'marker' };
+  na: { _kind: 'na' };
+  bool: boolean;
+  number: number | { _kind: 'number'; val: number; unit?: string };
+  str: string;
+  uri: { _kind: 'uri'; val: string };
+  ref: { _kind: 'ref'; val: string; dis?: string };
+  symbol: { _kind: 'symbol'; val: string };
+  date: { _kind: 'date'; val: string };
+  time: { _kind: 'time'; val: string };
+  dateTime: { _kind: 'dateTime'; val: string; tz?: string };
+  coord: { _kind: 'coord'; lat: number; lng: number };
+  xstr: { _kind: 'xstr'; type: string; val: string };
+  list: unknown[];
+  dict: { [name: string]: unknown };
+  grid: {
+    _kind: 'grid';
+    meta?: { [name: string]: unknown };
+    cols: { name: string; meta?: { [name: string]: unknown } }[];
+    rows: { [name: string]: unknown }[];
+  };
+}
+
+/**
+ * The kind of the block pin.
+ */
+export type Kind = keyof KindValues;
+
+/**
+ * The JSON value of a pin of kind `K`.
+ */
+export type KindValue<K extends Kind> = KindValues[K];
+
+/**
+ * A block pin.
+ *
+ * Pins are the inputs and outputs of a block.
+ */
+export interface BlockPin {
+  /**
+   * The pin name
+   */
+  name: string;
+
+  /**
+   * The pin kind
+   * Must be a valid haystack type kind.
+   * See https://project-haystack.org/doc/docHaystack/Kinds
+   */
+  kind: Kind;
+
+  /**
+   * The pin value
+   * Value is a Haystack value encoded as JSON.
+   */
+  value?: unknown;
+
+  /**
+   * True if the pin is connected to another pin.
+   */
+  isConnected?: boolean;
+}
+
+/**
+ * Describe a block that is available in block library.
+ */
+export interface BlockDesc {
+  /**
+   * The block name
+   */
+  name: string;
+
+  /**
+   * The block display name
+   */
+  dis: string;
+
+  /**
+   * The block library name
+   */
+  lib: string;
+
+  /**
+   * The block library version
+   */
+  ver: string;
+
+  /**
+   * The block category
+   */
+  category: string;
+
+  /**
+   * The block documentation
+   */
+  doc: string;
+
+  /**
+   * The block implementation
+   */
+  implementation: 'native' | 'external';
+
+  /**
+   * The block inputs
+   */
+  inputs: BlockPin[];
+
+  /**
+   * The block outputs
+   */
+  outputs: BlockPin[];
+
+  /**
+   * The block run condition.
+   *
+   * If not set, the block will be executed when any of its inputs change.
+   * Otherwise, the block will execute regularly according to the run condition.
+   *
+   * Default: 'change'
+   */
+  runCondition?: 'change' | 'always';
+}
+
+/**
+ * A link between an output pin and an input pin of a program.
+ */
+export interface LinkData {
+  /**
+   * The link id
+   */
+  id?: string;
+
+  /**
+   * The link source block pin name
+   */
+  sourceBlockPinName: string;
+
+  /**
+   * The link source block uuid
+   */
+  sourceBlockUuid: string;
+
+  /**
+   * The link target block pin name
+   */
+  targetBlockPinName: string;
+
+  /**
+   * The link target block uuid
+   */
+  targetBlockUuid: string;
+}
+
+/**
+ * The value of a pin saved in a program.
+ */
+export interface PinValue {
+  value?: unknown;
+  isConnected?: boolean;
+}
+
+/**
+ * Describes a program that would be loaded in the engine.
+ *
+ * The program is a set of blocks and links between them.
+ * The program and the blocks have meta data that would be used in the editor
+ * for example to position the blocks.
+ */
+export interface Program {
+  /**
+   * Program file format version. Written by the engine on save; files
+   * without it are treated as the unversioned 1.0 format and upgraded on
+   * load.
+   */
+  version?: number;
+
+  /**
+   * The program name
+   */
+  name?: string;
+
+  /**
+   * Optional program description
+   */
+  description?: string;
+
+  /**
+   * Blocks used in the program
+   */
+  blocks: {
+    [blockUuid: string]: {
+      name: string;
+      lib: string;
+
+      /** User-supplied display label shown alongside the block-type name. */
+      label?: string;
+
+      positions?: {
+        x: number;
+        y: number;
+      };
+
+      inputs?: {
+        [pinName: string]: PinValue;
+      };
+
+      outputs?: {
+        [pinName: string]: PinValue;
+      };
+    };
+  };
+
+  /**
+   * Links between blocks
+   */
+  links: {
+    [linkUuid: string]: LinkData;
+  };
+
+  /**
+   * Parameters declared by a template program, referenced as `${name}`
+   * from input values, labels, the name and the description.
+   */
+  params?: {
+    [paramName: string]: { description?: string; default?: unknown };
+  };
+}
+
+/**
+ * The input and output values of the registered blocks, by qualified
+ * block name.
+ */
+export interface BlockDefinitions {
+  /** Outputs the absolute value of the input. */
+  'core::Abs': {
+    inputs: {
+      in: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Valve or damper driven by an actuator, with its travel time and theplay (backlash) of its linkage.`in` is the commanded position and `out` the actual one, both in %open (0–100):- The position moves toward the command at `100 / stroke` % persecond. `stroke` is the full-stroke time, in any time unit, plainnumbers are seconds (default 90 s, 0 for an instant move).- `hysteresis` is the play of the linkage, in % (default 0). On areversal, the command has to move through it before the positionfollows, so the position lags the command by half of it.- `interval` is the simulation step, in any time unit (default200 ms).The actuator starts at its first command. */
+  'core::Actuator': {
+    inputs: {
+      in: KindValue<'number'>;
+      stroke: KindValue<'number'>;
+      hysteresis: KindValue<'number'>;
+      interval: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Performs an addition of multiple numbers from the 16 inputsthis block has.The addition would take into account the units of those input's values,converting them to the unit of the first one; a temperature difference(`Δ°C`) can be added to a temperature (`°F`), but temperatures areonly added in the same unit. If the units are not convertible, theblock would be in an error state.A list input, as built by the `List` block, is added element-wise tothe other inputs, a single number being added to every element, andthe output is a list. A list on its own is summed up. */
+  'core::Add': {
+    inputs: {
+      in0: KindValue<'number'>;
+      in1: KindValue<'number'>;
+      in2: KindValue<'number'>;
+      in3: KindValue<'number'>;
+      in4: KindValue<'number'>;
+      in5: KindValue<'number'>;
+      in6: KindValue<'number'>;
+      in7: KindValue<'number'>;
+      in8: KindValue<'number'>;
+      in9: KindValue<'number'>;
+      in10: KindValue<'number'>;
+      in11: KindValue<'number'>;
+      in12: KindValue<'number'>;
+      in13: KindValue<'number'>;
+      in14: KindValue<'number'>;
+      in15: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Outputs the logical And value of the inputs. */
+  'core::And': {
+    inputs: {
+      in1: KindValue<'bool'>;
+      in2: KindValue<'bool'>;
+    };
+    outputs: {
+      out: KindValue<'bool'>;
+    };
+  };
+
+  /** Outputs the ArcCos value of the input. */
+  'core::ArcCos': {
+    inputs: {
+      in: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Outputs the ArcSin value of the input. */
+  'core::ArcSin': {
+    inputs: {
+      in: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Outputs the Arc Tangent value of the input. */
+  'core::ArcTan': {
+    inputs: {
+      in: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Calculates an average of multiple numbers from the 16 inputsthis block has.The operation would take into account the units of those input's values,if the units are not convertible, the block would be in an error state.A list input is evaluated element-wise with the other inputs, giving alist. A list on its own gives the average of its elements. */
+  'core::Average': {
+    inputs: {
+      in0: KindValue<'number'>;
+      in1: KindValue<'number'>;
+      in2: KindValue<'number'>;
+      in3: KindValue<'number'>;
+      in4: KindValue<'number'>;
+      in5: KindValue<'number'>;
+      in6: KindValue<'number'>;
+      in7: KindValue<'number'>;
+      in8: KindValue<'number'>;
+      in9: KindValue<'number'>;
+      in10: KindValue<'number'>;
+      in11: KindValue<'number'>;
+      in12: KindValue<'number'>;
+      in13: KindValue<'number'>;
+      in14: KindValue<'number'>;
+      in15: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Outputs bitwise AND operation. */
+  'core::BitwiseAnd': {
+    inputs: {
+      in1: KindValue<'number'>;
+      in2: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Outputs bitwise NOT operation. */
+  'core::BitwiseNot': {
+    inputs: {
+      in: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Outputs bitwise OR operation. */
+  'core::BitwiseOr': {
+    inputs: {
+      in1: KindValue<'number'>;
+      in2: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Outputs bitwise XOR operation. */
+  'core::BitwiseXor': {
+    inputs: {
+      in1: KindValue<'number'>;
+      in2: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Goes into alarm while `in` is true, such as a pump's trip contact.Feed it through a `Not` block to alarm on false. `delayOn` and`delayOff` are in milliseconds. */
+  'core::BoolAlarm': {
+    inputs: {
+      in: KindValue<'bool'>;
+      delayOn: KindValue<'number'>;
+      delayOff: KindValue<'number'>;
+      priority: KindValue<'number'>;
+      message: KindValue<'str'>;
+    };
+    outputs: {
+      out: KindValue<'bool'>;
+    };
+  };
+
+  /** Date list match. The output is true when today (in the configuredtime zone) appears in the comma-separated `dates` input.`dates` accepts `"YYYY-MM-DD"` entries separated by commas, spaces,or semicolons (mix freely). `tzOffset` accepts any time unit(e.g. `-5h`, `60min`); a bare number is interpreted as minutes.Compose with [`Schedule`](super::schedule::Schedule) (e.g. `schedule AND NOT calendar`) toimplement holiday overrides. */
+  'core::Calendar': {
+    inputs: {
+      dates: KindValue<'str'>;
+      tzOffset: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'bool'>;
+    };
+  };
+
+  /** Change-of-value (COV) gate. The output mirrors `in`, but only updateswhen the input has changed by at least `delta` from the last valuethat was emitted. Useful for throttling chatty sensors and matchingBACnet COV semantics. */
+  'core::ChangeOfValue': {
+    inputs: {
+      in: KindValue<'number'>;
+      delta: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Clamp the input to the inclusive range `[min..max]`. If `min > max`the bounds are swapped so the block is still well-defined.`min` and `max` are read in the same unit as `in`; bounds with acompatible unit are converted, bounds without a unit are takenas-is. The output carries the unit of `in`. */
+  'core::Clamp': {
+    inputs: {
+      in: KindValue<'number'>;
+      min: KindValue<'number'>;
+      max: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Outputs the concatenated value of all the input strings. */
+  'core::Concat': {
+    inputs: {
+      in0: KindValue<'str'>;
+      in1: KindValue<'str'>;
+      in2: KindValue<'str'>;
+      in3: KindValue<'str'>;
+      in4: KindValue<'str'>;
+      in5: KindValue<'str'>;
+      in6: KindValue<'str'>;
+      in7: KindValue<'str'>;
+      in8: KindValue<'str'>;
+      in9: KindValue<'str'>;
+      in10: KindValue<'str'>;
+      in11: KindValue<'str'>;
+      in12: KindValue<'str'>;
+      in13: KindValue<'str'>;
+      in14: KindValue<'str'>;
+      in15: KindValue<'str'>;
+    };
+    outputs: {
+      out: KindValue<'str'>;
+    };
+  };
+
+  /** Outputs the cosine value of the input. */
+  'core::Cos': {
+    inputs: {
+      in: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Counts rising edges on `in`. Useful for tracking equipment startcounts for maintenance and wear tracking. A rising edge on `reset`zeros the counter. */
+  'core::CycleCount': {
+    inputs: {
+      in: KindValue<'bool'>;
+      reset: KindValue<'bool'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Deadband (Schmitt-trigger) block. Output turns ON when `in` rises at orabove `high`, and turns OFF when `in` falls at or below `low`. Betweenthe two thresholds the output holds its previous state, preventingshort-cycling when a sensor hovers around a setpoint. */
+  'core::Deadband': {
+    inputs: {
+      in: KindValue<'number'>;
+      high: KindValue<'number'>;
+      low: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'bool'>;
+    };
+  };
+
+  /** Discrete derivative `d(in)/dt`, output in units of `in` per second.Sampled at `interval` milliseconds. The first sample seeds theprevious value so the first output is zero. */
+  'core::Derivative': {
+    inputs: {
+      in: KindValue<'number'>;
+      interval: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Goes into alarm when `in` strays from `setpoint` by more than `limit`,either way, and returns to normal once it is back within`limit - deadband`. The `limit` and `deadband` are in the units of`in`; `delayOn` and `delayOff` are in milliseconds. */
+  'core::DeviationAlarm': {
+    inputs: {
+      in: KindValue<'number'>;
+      setpoint: KindValue<'number'>;
+      limit: KindValue<'number'>;
+      deadband: KindValue<'number'>;
+      delayOn: KindValue<'number'>;
+      delayOff: KindValue<'number'>;
+      priority: KindValue<'number'>;
+      message: KindValue<'str'>;
+    };
+    outputs: {
+      out: KindValue<'bool'>;
+    };
+  };
+
+  /** Dew-point temperature from dry-bulb temperature and relative humidity.Uses the Magnus formula with Alduchov-Eskridge coefficients(a = 17.625, b = 243.04 °C), accurate to about 0.4 °C over thebuilding range.`t` accepts any temperature unit (°C, °F, K) and is converted to °Cinternally. The output is always tagged °C. */
+  'core::Dewpoint': {
+    inputs: {
+      t: KindValue<'number'>;
+      rh: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Outputs a dictionary of elements constructed from the input keys and values. */
+  'core::Dict': {
+    inputs: {
+      keys: KindValue<'list'>;
+      values: KindValue<'list'>;
+    };
+    outputs: {
+      out: KindValue<'dict'>;
+    };
+  };
+
+  /** Performs an division of 2 numbers.The unit of the quotient is derived from the units of the inputs, suchas `m/s` for `m` over `s`; inputs of the same dimension give a plainratio. If no unit matches the quotient's dimensions, the block would bein an error state. */
+  'core::Div': {
+    inputs: {
+      a: KindValue<'number'>;
+      b: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Air-side economizer decision with hysteresis.Compares an outdoor measurement (`oa`) against a return measurement(`ra`). Use temperatures for a dry-bulb economizer or enthalpies foran enthalpy economizer — the block doesn't care, as long as `oa`,`ra`, `highLimit`, and `deadband` share a unit.Output `enable` is a [Schmitt](super::Deadband)-style decision:- **enable** when `oa < ra − deadband` AND `oa < highLimit`- **disable** when `oa > ra` OR `oa > highLimit`(the high-limit trip skips the deadband for safety)- **hold** previous state otherwise`highLimit` defaults to +∞ (no limit). `deadband` defaults to 0. */
+  'core::Economizer': {
+    inputs: {
+      oa: KindValue<'number'>;
+      ra: KindValue<'number'>;
+      highLimit: KindValue<'number'>;
+      deadband: KindValue<'number'>;
+    };
+    outputs: {
+      enable: KindValue<'bool'>;
+    };
+  };
+
+  /** Exponential moving average (first-order low-pass) filter.`out[n] = alpha * in + (1 - alpha) * out[n-1]``alpha` is clamped to `[0..1]`. A larger alpha tracks the input moreclosely; a smaller alpha smooths more heavily. The block samples at`interval` milliseconds; on the first sample the output is seededwith the current input.A list input is filtered element-wise and gives a list; the filteris seeded again whenever the list changes its length. */
+  'core::Ema': {
+    inputs: {
+      in: KindValue<'number'>;
+      alpha: KindValue<'number'>;
+      interval: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Moist-air specific enthalpy in kJ per kg of dry air. The dry-bulbtemperature accepts any temperature unit (°C, °F, K) and is convertedto °C internally; pressure accepts Pa or kPa and is converted to Pa(default 101 325, sea-level standard). Relative humidity is in %.Saturation pressure uses the Tetens approximation; this is accurateto better than 0.1% over the typical building range and is what mostBAS controllers use for economizer enthalpy comparisons. */
+  'core::Enthalpy': {
+    inputs: {
+      t: KindValue<'number'>;
+      rh: KindValue<'number'>;
+      p: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Outputs true if value of the inputs are equal.If either input is a list, the inputs are compared element-wise andthe output is a list of booleans. */
+  'core::Equal': {
+    inputs: {
+      in1: KindValue<'null'>;
+      in2: KindValue<'null'>;
+    };
+    outputs: {
+      out: KindValue<'bool'>;
+    };
+  };
+
+  /** Outputs true if the input value is even. */
+  'core::Even': {
+    inputs: {
+      in: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'bool'>;
+    };
+  };
+
+  /** Outputs the exponential value of the input. */
+  'core::Exp': {
+    inputs: {
+      in: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Evaluates the `formula` over its 16 inputs, `in0` to `in15`, forexample `(in0 - in1) * 0.5 + max(in2, 10)`.Formulas combine arithmetic (`+ - * / % ^`), comparisons, booleanlogic (`&&`/`and`, `||`/`or`, `!`/`not`), `if(cond, then, else)` andmath functions, over unit-aware numbers: `in0 > 20°C` converts aninput in `°F` before comparing. The output is a number or a boolean.A formula that does not parse, including one nesting parentheses,calls or unary operators more than 64 deep, or values it cannotcombine, fault the block; it waits while an input the formula needshas no value. */
+  'core::Expr': {
+    inputs: {
+      formula: KindValue<'str'>;
+      in0: KindValue<'number'>;
+      in1: KindValue<'number'>;
+      in2: KindValue<'number'>;
+      in3: KindValue<'number'>;
+      in4: KindValue<'number'>;
+      in5: KindValue<'number'>;
+      in6: KindValue<'number'>;
+      in7: KindValue<'number'>;
+      in8: KindValue<'number'>;
+      in9: KindValue<'number'>;
+      in10: KindValue<'number'>;
+      in11: KindValue<'number'>;
+      in12: KindValue<'number'>;
+      in13: KindValue<'number'>;
+      in14: KindValue<'number'>;
+      in15: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'null'>;
+    };
+  };
+
+  /** Fan or pump on a variable-speed drive, following the affinity lawsfrom its design point: flow scales with the speed, pressure (head)with its square and shaft power with its cube.- `speed` is in % of the design speed; plain numbers are %.- `designFlow`, `designPressure` and `designPower` describe thedesign point, in any unit, which the matching output keeps. Eachdefaults to 100, for outputs in % of design.The laws hold along a system curve through the origin; systems witha static head or a duct static setpoint drift from them at lowspeeds. */
+  'core::FanPump': {
+    inputs: {
+      speed: KindValue<'number'>;
+      designFlow: KindValue<'number'>;
+      designPressure: KindValue<'number'>;
+      designPower: KindValue<'number'>;
+    };
+    outputs: {
+      flow: KindValue<'number'>;
+      pressure: KindValue<'number'>;
+      power: KindValue<'number'>;
+    };
+  };
+
+  /** Flip-Flop block. Set input prioritizes over Reset when both are true. */
+  'core::FlipFlop': {
+    inputs: {
+      set: KindValue<'bool'>;
+      reset: KindValue<'bool'>;
+    };
+    outputs: {
+      out: KindValue<'bool'>;
+    };
+  };
+
+  /** First-order-plus-dead-time (FOPDT) process, the usual model of aself-regulating plant: a coil's leaving-air temperature, a ductpressure, a zone that settles for a given heating output.The output settles at `bias + gain · in`, following changes of `in`after a `deadTime` and with a time constant `tau`:`tau · dy/dt = bias + gain · in(t − deadTime) − y`.- `gain` defaults to 1, `bias` to 0.- `tau` and `deadTime` accept any time unit, plain numbers areseconds; they default to 60 s and 0.- `interval` is the simulation step, in any time unit (default200 ms).The model starts at rest, at the output its first `in` settles to. */
+  'core::Fopdt': {
+    inputs: {
+      in: KindValue<'number'>;
+      gain: KindValue<'number'>;
+      tau: KindValue<'number'>;
+      deadTime: KindValue<'number'>;
+      bias: KindValue<'number'>;
+      interval: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Gets the element specified at key from the input and outputs theelement's value. */
+  'core::GetElement': {
+    inputs: {
+      input: KindValue<'null'>;
+      key: KindValue<'null'>;
+    };
+    outputs: {
+      out: KindValue<'null'>;
+    };
+  };
+
+  /** Outputs true if value of the in1 is greater.If either input is a list, the inputs are compared element-wise andthe output is a list of booleans. */
+  'core::GreaterThan': {
+    inputs: {
+      in1: KindValue<'null'>;
+      in2: KindValue<'null'>;
+    };
+    outputs: {
+      out: KindValue<'bool'>;
+    };
+  };
+
+  /** Outputs true if value of the in1 is greater or equals.If either input is a list, the inputs are compared element-wise andthe output is a list of booleans. */
+  'core::GreaterThanEq': {
+    inputs: {
+      in1: KindValue<'null'>;
+      in2: KindValue<'null'>;
+    };
+    outputs: {
+      out: KindValue<'bool'>;
+    };
+  };
+
+  /** Outputs true if the input is not null. */
+  'core::HasValue': {
+    inputs: {
+      in: KindValue<'null'>;
+    };
+    outputs: {
+      out: KindValue<'bool'>;
+    };
+  };
+
+  /** Goes into alarm when `in` rises above `limit`, and returns to normalonce it falls below `limit - deadband`. The `deadband` is in the unitsof `in`; `delayOn` and `delayOff` are in milliseconds. */
+  'core::HighLimitAlarm': {
+    inputs: {
+      in: KindValue<'number'>;
+      limit: KindValue<'number'>;
+      deadband: KindValue<'number'>;
+      delayOn: KindValue<'number'>;
+      delayOff: KindValue<'number'>;
+      priority: KindValue<'number'>;
+      message: KindValue<'str'>;
+    };
+    outputs: {
+      out: KindValue<'bool'>;
+    };
+  };
+
+  /** Integrating process, a plant with no self-regulation: a tank levelfed by a pump against a draw, a pressure vessel, a conveyor buffer.The output ramps at `gain · (in − load)` per second, `in` arrivingafter a `deadTime`: `dy/dt = gain · (in(t − deadTime) − load)`. Itonly stops moving when `in` balances `load`.- `gain` defaults to 1, `load` to 0.- `deadTime` accepts any time unit, plain numbers are seconds; itdefaults to 0.- The output starts at 0, or restarts at `initial` whenever it iswritten. It is kept within `min` and `max` when they are set, likea tank that runs dry or overflows.- `interval` is the simulation step, in any time unit (default200 ms). */
+  'core::IntegratingProcess': {
+    inputs: {
+      in: KindValue<'number'>;
+      gain: KindValue<'number'>;
+      load: KindValue<'number'>;
+      deadTime: KindValue<'number'>;
+      initial: KindValue<'number'>;
+      min: KindValue<'number'>;
+      max: KindValue<'number'>;
+      interval: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Time integrator (totalizer). Accumulates `in * dt`, where `in` is inunits-per-second and `dt` is the elapsed wall-clock interval inseconds. A rising edge on `reset` clears the accumulator. Useful fortotalizing energy (kWh from kW), volume (gallons from gpm/60), etc. */
+  'core::Integrator': {
+    inputs: {
+      in: KindValue<'number'>;
+      reset: KindValue<'bool'>;
+      interval: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Outputs the keys of a dictionary. */
+  'core::Keys': {
+    inputs: {
+      input: KindValue<'dict'>;
+    };
+    outputs: {
+      out: KindValue<'list'>;
+    };
+  };
+
+  /** Outputs the input value if the condition is true. */
+  'core::Latch': {
+    inputs: {
+      in: KindValue<'null'>;
+      condition: KindValue<'bool'>;
+    };
+    outputs: {
+      out: KindValue<'null'>;
+    };
+  };
+
+  /** Two-device lead-lag controller. `demand` is the number of devicesthat should run (0, 1, or 2). The "lead" device is whichever onecurrently holds the lead role; the other is "lag". A rising edge on`rotate` swaps the roles. When `enable` is false both outputs areoff.Pair with [`Sequencer`](super::Sequencer) to drive `demand` from acontinuous load signal, or with [`Schedule`](crate::blocks::time::Schedule)+ a weekly cron to rotate at a fixed time. */
+  'core::LeadLag': {
+    inputs: {
+      enable: KindValue<'bool'>;
+      rotate: KindValue<'bool'>;
+      demand: KindValue<'number'>;
+    };
+    outputs: {
+      a: KindValue<'bool'>;
+      b: KindValue<'bool'>;
+    };
+  };
+
+  /** Outputs the number of elements in the the collection. */
+  'core::Length': {
+    inputs: {
+      input: KindValue<'null'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Outputs true if value of the in1 is less.If either input is a list, the inputs are compared element-wise andthe output is a list of booleans. */
+  'core::LessThan': {
+    inputs: {
+      in1: KindValue<'null'>;
+      in2: KindValue<'null'>;
+    };
+    outputs: {
+      out: KindValue<'bool'>;
+    };
+  };
+
+  /** Outputs true if value of the in1 is less or equal.If either input is a list, the inputs are compared element-wise andthe output is a list of booleans. */
+  'core::LessThanEq': {
+    inputs: {
+      in1: KindValue<'null'>;
+      in2: KindValue<'null'>;
+    };
+    outputs: {
+      out: KindValue<'bool'>;
+    };
+  };
+
+  /** Outputs a list of elements constructed from the inputs. */
+  'core::List': {
+    inputs: {
+      in0: KindValue<'null'>;
+      in1: KindValue<'null'>;
+      in2: KindValue<'null'>;
+      in3: KindValue<'null'>;
+      in4: KindValue<'null'>;
+      in5: KindValue<'null'>;
+      in6: KindValue<'null'>;
+      in7: KindValue<'null'>;
+      in8: KindValue<'null'>;
+      in9: KindValue<'null'>;
+      in10: KindValue<'null'>;
+      in11: KindValue<'null'>;
+      in12: KindValue<'null'>;
+      in13: KindValue<'null'>;
+      in14: KindValue<'null'>;
+      in15: KindValue<'null'>;
+    };
+    outputs: {
+      out: KindValue<'list'>;
+    };
+  };
+
+  /** Outputs the log10 value of the input. */
+  'core::Log10': {
+    inputs: {
+      in: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Outputs the natural logarithm value of the input. */
+  'core::Logn': {
+    inputs: {
+      in: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Goes into alarm when `in` falls below `limit`, and returns to normalonce it rises above `limit + deadband`. The `deadband` is in the unitsof `in`; `delayOn` and `delayOff` are in milliseconds. */
+  'core::LowLimitAlarm': {
+    inputs: {
+      in: KindValue<'number'>;
+      limit: KindValue<'number'>;
+      deadband: KindValue<'number'>;
+      delayOn: KindValue<'number'>;
+      delayOff: KindValue<'number'>;
+      priority: KindValue<'number'>;
+      message: KindValue<'str'>;
+    };
+    outputs: {
+      out: KindValue<'bool'>;
+    };
+  };
+
+  /** Outputs the Maximum value of the inputs.If an input is a list, the maximum is taken element-wise, giving alist. A list on its own gives its maximum element. */
+  'core::Max': {
+    inputs: {
+      a: KindValue<'number'>;
+      b: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Calculates the median of multiple numbers from the 16 inputsthis block has.The operation would take into account the units of those input's values,if the units are not convertible, the block would be in an error state.A list input is evaluated element-wise with the other inputs, giving alist. A list on its own gives the median of its elements. */
+  'core::Median': {
+    inputs: {
+      in0: KindValue<'number'>;
+      in1: KindValue<'number'>;
+      in2: KindValue<'number'>;
+      in3: KindValue<'number'>;
+      in4: KindValue<'number'>;
+      in5: KindValue<'number'>;
+      in6: KindValue<'number'>;
+      in7: KindValue<'number'>;
+      in8: KindValue<'number'>;
+      in9: KindValue<'number'>;
+      in10: KindValue<'number'>;
+      in11: KindValue<'number'>;
+      in12: KindValue<'number'>;
+      in13: KindValue<'number'>;
+      in14: KindValue<'number'>;
+      in15: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Outputs the Minimum value of the inputs.If an input is a list, the minimum is taken element-wise, giving alist. A list on its own gives its minimum element. */
+  'core::Min': {
+    inputs: {
+      a: KindValue<'number'>;
+      b: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Outputs the modulus value of the inputs. */
+  'core::Mod': {
+    inputs: {
+      a: KindValue<'number'>;
+      b: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Simple moving average over the last N samples. The block samples at`interval` milliseconds and reports the unweighted mean of the mostrecent `window` samples (clamped to at least 1).A list input is averaged element-wise and gives a list; the windowstarts over whenever the list changes its length. */
+  'core::MovingAverage': {
+    inputs: {
+      in: KindValue<'number'>;
+      window: KindValue<'number'>;
+      interval: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Performs a multiplication of multiple numbers from the 16 inputsthis block has.The unit of the product is derived from the units of the inputs, suchas `kWh` for `kW` times `h`. If no unit matches the product'sdimensions, the block would be in an error state.A list input is multiplied element-wise with the other inputs, and theoutput is a list. A list on its own is multiplied out. */
+  'core::Mul': {
+    inputs: {
+      in0: KindValue<'number'>;
+      in1: KindValue<'number'>;
+      in2: KindValue<'number'>;
+      in3: KindValue<'number'>;
+      in4: KindValue<'number'>;
+      in5: KindValue<'number'>;
+      in6: KindValue<'number'>;
+      in7: KindValue<'number'>;
+      in8: KindValue<'number'>;
+      in9: KindValue<'number'>;
+      in10: KindValue<'number'>;
+      in11: KindValue<'number'>;
+      in12: KindValue<'number'>;
+      in13: KindValue<'number'>;
+      in14: KindValue<'number'>;
+      in15: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Outputs the unary minus value of the input. */
+  'core::Neg': {
+    inputs: {
+      in: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Outputs the negated value of the input. */
+  'core::Not': {
+    inputs: {
+      in: KindValue<'bool'>;
+    };
+    outputs: {
+      out: KindValue<'bool'>;
+    };
+  };
+
+  /** Outputs true if value of the inputs are not equal.If either input is a list, the inputs are compared element-wise andthe output is a list of booleans. */
+  'core::NotEqual': {
+    inputs: {
+      in1: KindValue<'null'>;
+      in2: KindValue<'null'>;
+    };
+    outputs: {
+      out: KindValue<'bool'>;
+    };
+  };
+
+  /** Outputs the current wall clock time in millis at the desired resolution. */
+  'core::Now': {
+    inputs: {
+      resolution: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Outputs true if the input value is odd. */
+  'core::Odd': {
+    inputs: {
+      in: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'bool'>;
+    };
+  };
+
+  /** Off-delay timer. Output goes true immediately when `in` becomes true,and stays true for at least `delay` (milliseconds) after `in` goesfalse. Any new true on `in` during the delay window cancels thepending drop. */
+  'core::OffDelay': {
+    inputs: {
+      in: KindValue<'bool'>;
+      delay: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'bool'>;
+    };
+  };
+
+  /** On-delay timer. Output goes true only after `in` has been continuouslytrue for at least `delay` (milliseconds). When `in` goes false theoutput drops immediately and the elapsed time is reset. */
+  'core::OnDelay': {
+    inputs: {
+      in: KindValue<'bool'>;
+      delay: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'bool'>;
+    };
+  };
+
+  /** One-shot pulse. On a rising edge of `in`, the output goes true for`width` milliseconds and then back to false. Subsequent rising edgeswhile the pulse is still active are ignored (non-retriggerable). */
+  'core::OneShot': {
+    inputs: {
+      in: KindValue<'bool'>;
+      width: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'bool'>;
+    };
+  };
+
+  /** Outputs the logical Or value of the inputs. */
+  'core::Or': {
+    inputs: {
+      in1: KindValue<'bool'>;
+      in2: KindValue<'bool'>;
+    };
+    outputs: {
+      out: KindValue<'bool'>;
+    };
+  };
+
+  /** Outputs the parsed boolean value of the input. */
+  'core::ParseBool': {
+    inputs: {
+      in: KindValue<'str'>;
+    };
+    outputs: {
+      out: KindValue<'bool'>;
+    };
+  };
+
+  /** Outputs the parsed numeric value of the input. */
+  'core::ParseNumber': {
+    inputs: {
+      in: KindValue<'str'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Discrete PID controller.`error = sp − pv`, output = `P + I + D`, clamped to `[min..max]`.- **P**: `Kp · error`- **I** (trapezoidal, `dt` in seconds): `I += Ki · dt/2 · (error + error_prev)`,clamped to `[min..max]` for simple anti-windup so a saturated actuatordoes not wind the integral past the achievable output.- **D** (filtered derivative on measurement): acting on the PV (not theerror) prevents derivative kick on setpoint changes.`D = −(bias·Kd·(pv − pv_prev) + (bias − dt)·D_prev) / (bias + dt)``bias` is the derivative-filter time constant in seconds (default 0.1 s,i.e. ~100 ms filter). Keep `bias ≥ dt` for stable filter behavior.`interval` accepts any time unit (`ms`, `s`, `min`, `h`); defaults to 200 ms.If `input` is not connected, the controller's previous output is used asthe process variable — convenient for demos that should converge to SPwithout an explicit plant model. For a plant with lag and dead time,close the loop through the `sim` blocks such as `Fopdt` or `ThermalZone`.To tune the gains, put a `PidAutotune` between `out` and the actuatorand link its `kp`, `ki` and `kd` to this block's. */
+  'core::Pid': {
+    inputs: {
+      input: KindValue<'number'>;
+      sp: KindValue<'number'>;
+      kp: KindValue<'number'>;
+      ki: KindValue<'number'>;
+      kd: KindValue<'number'>;
+      interval: KindValue<'number'>;
+      min: KindValue<'number'>;
+      max: KindValue<'number'>;
+      bias: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Relay autotuner for a [`Pid`](super::Pid) loop.Sits between the controller and its actuator: `pid` takes thecontroller's output and `out` drives the actuator, passing `pid`through until a rising edge of `start` begins an Åström–Hägglundrelay experiment. The relay then swings `out` by `step` either sideof the output at the start, switching each time `input` crosses `sp`by more than `hysteresis`, until the loop oscillates steadily.From the amplitude `a` and period of the last `cycles` oscillations(default 3, after a first one that is discarded) it identifies theultimate gain `ku = 4·step / (π·√(a² − ε²))` and period `pu`, `ε`being how far from `sp` the relay switched, and computes the gains by`rule`:- `zieglerNichols` (default): PI `0.45·ku`, `Ti = pu/1.2`; PID`0.6·ku`, `Ti = pu/2`, `Td = pu/8`.- `tyreusLuyben`: PI `ku/3.2`, `Ti = 2.2·pu`; PID `ku/2.2`,`Ti = 2.2·pu`, `Td = pu/6.3`. Slower, with less overshoot.- `simc`: fits a first-order-plus-dead-time model to `ku`, `pu` andthe delay from each relay switch to the following peak, and appliesSkogestad's PI rule with the closed-loop time constant equal to thedead time. Always PI.The PI rules are used unless `derivative` is true. Once done, the`kp`, `ki = kp/Ti` (per second) and `kd` outputs hold the tuned gains,to be linked to the controller's inputs, and `out` goes back to `pid`.The filtered derivative of a [`Pid`](super::Pid) running every `dt`seconds amounts to a derivative time of `kd·dt/2`, so for thecontroller to act with `Td`, `kd = 2·kp·Td/dt`, with `dt` this block's`interval`: run both blocks at the same interval.`status` is `idle`, `tuning`, `done` or `aborted`. The experimentaborts, leaving the gains alone, when `start` goes false, when`input` or `sp` go missing, when `input` strays from `sp` by more than`maxDeviation` (in the units of `input`, unlimited by default), orwhen no steady oscillation is found within `timeout` (any time unit,plain numbers are seconds, default 1 h). `reason` says why.The relay output stays within `min` and `max` (default 0 and 100);`step` defaults to 10. Set `hysteresis` above the noise on `input`.`interval` accepts any time unit (`ms`, `s`, `min`, `h`); defaults to200 ms, and should be short next to the loop's dead time. */
+  'core::PidAutotune': {
+    inputs: {
+      input: KindValue<'number'>;
+      sp: KindValue<'number'>;
+      pid: KindValue<'number'>;
+      start: KindValue<'bool'>;
+      rule: KindValue<'str'>;
+      derivative: KindValue<'bool'>;
+      step: KindValue<'number'>;
+      hysteresis: KindValue<'number'>;
+      min: KindValue<'number'>;
+      max: KindValue<'number'>;
+      cycles: KindValue<'number'>;
+      timeout: KindValue<'number'>;
+      maxDeviation: KindValue<'number'>;
+      interval: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+      kp: KindValue<'number'>;
+      ki: KindValue<'number'>;
+      kd: KindValue<'number'>;
+      ku: KindValue<'number'>;
+      pu: KindValue<'number'>;
+      status: KindValue<'str'>;
+      reason: KindValue<'str'>;
+    };
+  };
+
+  /** Outputs the power root value of the input.The unit of the base is raised too, so `m` squared is `m²`; the exponentmust not have a unit. */
+  'core::Pow': {
+    inputs: {
+      base: KindValue<'number'>;
+      exponent: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Outputs the result of a priority array based on the input values. */
+  'core::PriorityArray': {
+    inputs: {
+      ManualLifeSafety: KindValue<'number'>;
+      AutoLifeSafety: KindValue<'number'>;
+      priority3: KindValue<'number'>;
+      priority4: KindValue<'number'>;
+      CriticalEquipmentControl: KindValue<'number'>;
+      MinOnOf: KindValue<'number'>;
+      priority7: KindValue<'number'>;
+      ManualOperator: KindValue<'number'>;
+      priority9: KindValue<'number'>;
+      priority10: KindValue<'number'>;
+      priority11: KindValue<'number'>;
+      priority12: KindValue<'number'>;
+      priority13: KindValue<'number'>;
+      priority14: KindValue<'number'>;
+      priority15: KindValue<'number'>;
+      priority16: KindValue<'number'>;
+      default: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Generates a random number at the specified frequency.min and max control the range of the generated random number.The defaults are 0 and 100. */
+  'core::Random': {
+    inputs: {
+      freq: KindValue<'number'>;
+      min: KindValue<'number'>;
+      max: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Rate Limit block. The output follows the input but is prevented from changingfaster than the specified maximum rate per second.The `rising` input sets the max rate of increase per second,and the `falling` input sets the max rate of decrease per second. */
+  'core::RateLimit': {
+    inputs: {
+      in: KindValue<'number'>;
+      rising: KindValue<'number'>;
+      falling: KindValue<'number'>;
+      interval: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Outputs a new string based on input string, the needle and the replace value. */
+  'core::Replace': {
+    inputs: {
+      in: KindValue<'str'>;
+      find: KindValue<'str'>;
+      replace: KindValue<'str'>;
+    };
+    outputs: {
+      out: KindValue<'str'>;
+    };
+  };
+
+  /** Linear reset (range scale). Maps `in` from `[inMin..inMax]` onto`[outMin..outMax]` and clamps the result to that output range.Inverted output ranges (`outMax < outMin`) are supported, which isthe common BAS reset pattern (e.g. SAT falls as OAT rises).`inMin` / `inMax` are matched to the unit of `in`; `outMin` /`outMax` define the output range and dictate the output's unit. */
+  'core::Reset': {
+    inputs: {
+      in: KindValue<'number'>;
+      inMin: KindValue<'number'>;
+      inMax: KindValue<'number'>;
+      outMin: KindValue<'number'>;
+      outMax: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Runtime accumulator. Integrates the time `in` is true and exposes thetotal as hours on the `hours` output. A rising edge on `reset` zerosthe accumulator. Useful for tracking equipment runtime formaintenance scheduling. */
+  'core::Runtime': {
+    inputs: {
+      in: KindValue<'bool'>;
+      reset: KindValue<'bool'>;
+    };
+    outputs: {
+      hours: KindValue<'number'>;
+    };
+  };
+
+  /** Sample-and-hold. On a rising edge of `trigger`, the current value of`in` is captured and emitted on `out`. The output then holds thatvalue until the next rising edge. Until the first trigger, the outputis null. */
+  'core::SampleHold': {
+    inputs: {
+      in: KindValue<'null'>;
+      trigger: KindValue<'bool'>;
+    };
+    outputs: {
+      out: KindValue<'null'>;
+    };
+  };
+
+  /** Weekly time-of-day schedule. Output is true when the local clock fallsinside the `[start..end)` window on one of the selected `days`.- `start` / `end`: time of day in `"HH:MM"` (24h). When `start > end`the window is treated as crossing midnight (e.g. 22:00 → 06:00).- `days`: a string of day-letters using the BACnet/scheduling convention`M T W R F S U` (R = Thursday, U = Sunday). For example weekdays =`"MTWRF"`, weekends = `"SU"`, every day = `"MTWRFSU"`.- `tzOffset`: time to add to UTC to reach the local zone. Accepts anytime unit (e.g. `-5h` for EST, `60min` for CET, `300s`). A barenumber is interpreted as minutes for backwards compatibility.Defaults to 0.Output is the boolean `occupied`. The block re-evaluates once per second. */
+  'core::Schedule': {
+    inputs: {
+      start: KindValue<'str'>;
+      end: KindValue<'str'>;
+      days: KindValue<'str'>;
+      tzOffset: KindValue<'number'>;
+    };
+    outputs: {
+      occupied: KindValue<'bool'>;
+    };
+  };
+
+  /** Equipment stager. Maps a `demand` in `[0..1]` to an integer stagecount in `[0..stages]`. Stage-up transitions wait `upDelay` ms withthe higher target; stage-down transitions wait `downDelay` ms withthe lower target. Use the output to gate boilers, chillers, orcompressors and avoid short-cycling. */
+  'core::Sequencer': {
+    inputs: {
+      demand: KindValue<'number'>;
+      stages: KindValue<'number'>;
+      upDelay: KindValue<'number'>;
+      downDelay: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Outputs the sine value of the input. */
+  'core::Sin': {
+    inputs: {
+      in: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Block that generates a sine wave based onthe frequency and the amplitude inputs. */
+  'core::SineWave': {
+    inputs: {
+      freq: KindValue<'number'>;
+      amplitude: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Outputs the square root value of the input. */
+  'core::Sqrt': {
+    inputs: {
+      in: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Goes into alarm while the value on `in` does not have the[`Ok`](Status::Ok) status, such as when the block feeding it is infault. The block reports the fault instead of faulting itself.`delayOn` and `delayOff` are in milliseconds. */
+  'core::StatusAlarm': {
+    inputs: {
+      in: KindValue<'null'>;
+      delayOn: KindValue<'number'>;
+      delayOff: KindValue<'number'>;
+      priority: KindValue<'number'>;
+      message: KindValue<'str'>;
+    };
+    outputs: {
+      out: KindValue<'bool'>;
+    };
+  };
+
+  /** Outputs the length of the input string. */
+  'core::StrLen': {
+    inputs: {
+      in: KindValue<'str'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Performs an subtraction of 2 numbers.The operation would take into account the units of those input's values,converting `b` to the unit of `a`; the difference of two temperatures isa temperature difference (`Δ°C`). If the units are not convertible, theblock would be in an error state. */
+  'core::Sub': {
+    inputs: {
+      a: KindValue<'number'>;
+      b: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Sunrise / sunset times for a given location, plus a `isDay` indicator.Inputs:- `lat` — latitude in degrees, north positive (-90..90)- `lon` — longitude in degrees, east positive (-180..180)- `tzOffset` — minutes from UTC; accepts any time unit (`-5h`, `60min`).Outputs (minutes since local midnight, 0..1440):- `sunrise` — local time of sunrise- `sunset` — local time of sunset- `isDay` — true when the local clock is between sunrise and sunset.At polar latitudes where the sun never rises or never sets, `isDay`reflects the polar state (true on polar day, false on polar night)and `sunrise` / `sunset` retain their previous values.Uses the Wikipedia "Sunrise equation" — a simplified NOAA formulationaccurate to within a few minutes for typical latitudes.## TimingThe first execute emits as soon as the inputs are available (nostartup wait). Subsequent executes align to the next wall-clockminute boundary, so multiple [`Sun`] blocks (or repeated programloads of the same program) stay phase-aligned. Editing `lat`,`lon`, or `tzOffset` wakes the block immediately for a freshcompute — `wait_on_inputs` returns early on any input change.Trade-off vs. the previous 1-second polling: `isDay` transitionscan now lag up to ~60 s behind the actual sunrise/sunset wall-clock time. The underlying sunrise equation is itself onlyaccurate to ±a few minutes, so this is well within the model'sown error bars and fine for lighting / scheduling use cases. */
+  'core::Sun': {
+    inputs: {
+      lat: KindValue<'number'>;
+      lon: KindValue<'number'>;
+      tzOffset: KindValue<'number'>;
+    };
+    outputs: {
+      sunrise: KindValue<'number'>;
+      sunset: KindValue<'number'>;
+      isDay: KindValue<'bool'>;
+    };
+  };
+
+  /** Outputs the Tangent value of the input. */
+  'core::Tan': {
+    inputs: {
+      in: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Thermal zone, as a single lumped capacity losing heat to the outdoorsthrough its envelope:`capacity · dT/dt = gains + hvac + ua · (oat − T)`.- `oat` is the outdoor air temperature. The output, the zonetemperature, has its unit.- The zone starts at the temperature it settles at. Writing`initial` restarts it from that temperature instead.- Temperatures accept any temperature unit, plain numbers are °C.- `gains` are the internal and solar heat gains, `hvac` the heat theHVAC system adds (negative when cooling). Both accept any powerunit, plain numbers are W.- `ua` is the envelope conductance in W/K (default 150), `capacity`the thermal capacity of the air, furnishings and structure in kJ/K(default 4000). Together they set the time constant of the zone,`capacity / ua`, about 7 h by default.- `interval` is the simulation step, in any time unit (default200 ms).Drive `hvac` from a controller through an actuator and a coil orheater model, such as [`Fopdt`](super::Fopdt), to close the loop. */
+  'core::ThermalZone': {
+    inputs: {
+      oat: KindValue<'number'>;
+      gains: KindValue<'number'>;
+      hvac: KindValue<'number'>;
+      ua: KindValue<'number'>;
+      capacity: KindValue<'number'>;
+      initial: KindValue<'number'>;
+      interval: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Detects a change on the Bool input and outputs a True pulse for one cycle.Operation modes: "RisingEdge", "FallingEdge", "RisingOrFallingEdge", "Off".Defaults to "RisingEdge" if not specified. */
+  'core::Trigger': {
+    inputs: {
+      in: KindValue<'bool'>;
+      operation: KindValue<'str'>;
+    };
+    outputs: {
+      out: KindValue<'bool'>;
+    };
+  };
+
+  /** ASHRAE Guideline 36 trim-and-respond setpoint reset.Once per `period` ms the setpoint is adjusted:- if `requests <= ignore` → `sp += trim`- else → `sp += respond * (requests - ignore)`, capped per cycle by`maxChange` (when `maxChange > 0`)Use signed values: e.g. for "increase SP to respond" (duct staticpressure) set `trim = -0.05`, `respond = +0.05`. The output is heldinside `[min..max]`. */
+  'core::TrimRespond': {
+    inputs: {
+      requests: KindValue<'number'>;
+      period: KindValue<'number'>;
+      ignore: KindValue<'number'>;
+      trim: KindValue<'number'>;
+      respond: KindValue<'number'>;
+      maxChange: KindValue<'number'>;
+      min: KindValue<'number'>;
+      max: KindValue<'number'>;
+      initial: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Outputs the values of a dictionary. */
+  'core::Values': {
+    inputs: {
+      input: KindValue<'dict'>;
+    };
+    outputs: {
+      out: KindValue<'list'>;
+    };
+  };
+
+  /** Wet-bulb temperature from dry-bulb temperature and relative humidity.Uses Stull's empirical correlation (Stull 2011). No iteration needed.Accurate to ~0.4 °C across the validity range:`T ∈ [-20, 50] °C`, `RH ∈ [5, 99] %`, `P ≈ 101.325 kPa` (sea level).Outside that envelope the value is still finite but degrades; theblock does not error.`t` accepts any temperature unit (°C, °F, K) and is converted to °Cinternally; the output is tagged °C. */
+  'core::WetBulb': {
+    inputs: {
+      t: KindValue<'number'>;
+      rh: KindValue<'number'>;
+    };
+    outputs: {
+      out: KindValue<'number'>;
+    };
+  };
+
+  /** Outputs the logical Xor value of the inputs. */
+  'core::Xor': {
+    inputs: {
+      in1: KindValue<'bool'>;
+      in2: KindValue<'bool'>;
+    };
+    outputs: {
+      out: KindValue<'bool'>;
+    };
+  };
+}
+
+/**
+ * The input and output values of the block named `Name`.
+ */
+export type BlockDefinition<
+  Name extends keyof BlockDefinitions = keyof BlockDefinitions,
+> = BlockDefinitions[Name];